@@ -48,6 +48,9 @@ func main() {
 	rest.SetAnomalyStore(anomalyStore)
 	slog.Info("Anomaly store initialized")
 
+	rest.SetPolicyStore(storage.NewInMemoryPolicyStore())
+	slog.Info("Policy store initialized")
+
 	// Create agent store that bridges gRPC agents to REST stores
 	agentStore := storage.NewInMemoryAgentStore(clusterStore, anomalyStore)
 	slog.Info("Agent store initialized - clusters appear when resource agents register via gRPC")
@@ -36,6 +36,12 @@ type Anomaly struct {
 	DetectedAt time.Time       `json:"detectedAt"`
 	ResolvedAt *time.Time      `json:"resolvedAt,omitempty"`
 	Status     string          `json:"status"` // active, resolved, acknowledged
+	Message    string          `json:"message,omitempty"`
+
+	// Type-specific details reported by the agent
+	LeakRateBytesPerSecond *float64   `json:"leakRateBytesPerSecond,omitempty"`
+	SpikeMagnitude         *float64   `json:"spikeMagnitude,omitempty"` // z-score of the spike
+	ProjectedOOMAt         *time.Time `json:"projectedOomAt,omitempty"`
 }
 
 // AnomalyDetail provides detailed information about an anomaly
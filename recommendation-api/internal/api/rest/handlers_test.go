@@ -101,6 +101,61 @@ func TestListNamespaceRecommendationsHandler(t *testing.T) {
 	}
 }
 
+func TestRecommendationPagination(t *testing.T) {
+	router := gin.New()
+	all := []Recommendation{{ID: "a"}, {ID: "b"}, {ID: "c"}}
+	router.GET("/page", func(c *gin.Context) {
+		respondRecommendationPage(c, func(limit, offset int) ([]Recommendation, int, error) {
+			end := len(all)
+			if limit > 0 && offset+limit < end {
+				end = offset + limit
+			}
+			return all[offset:end], len(all), nil
+		})
+	})
+
+	var ids []string
+	path := "/page?limit=2"
+	for pages := 0; path != ""; pages++ {
+		if pages > len(all) {
+			t.Fatal("Pagination did not terminate")
+		}
+		req, _ := http.NewRequest("GET", path, nil)
+		w := httptest.NewRecorder()
+		router.ServeHTTP(w, req)
+
+		if w.Code != http.StatusOK {
+			t.Fatalf("Expected status %d, got %d", http.StatusOK, w.Code)
+		}
+		var response RecommendationList
+		if err := json.Unmarshal(w.Body.Bytes(), &response); err != nil {
+			t.Fatalf("Failed to unmarshal response: %v", err)
+		}
+		if response.Total != len(all) {
+			t.Errorf("Expected total %d, got %d", len(all), response.Total)
+		}
+		for _, r := range response.Recommendations {
+			ids = append(ids, r.ID)
+		}
+
+		path = ""
+		if response.NextPageToken != "" {
+			path = "/page?limit=2&page_token=" + response.NextPageToken
+		}
+	}
+
+	if len(ids) != 3 || ids[0] != "a" || ids[2] != "c" {
+		t.Errorf("Expected all recommendations in order, got %v", ids)
+	}
+
+	req, _ := http.NewRequest("GET", "/page?page_token=not-a-token", nil)
+	w := httptest.NewRecorder()
+	router.ServeHTTP(w, req)
+	if w.Code != http.StatusBadRequest {
+		t.Errorf("Expected status %d for invalid token, got %d", http.StatusBadRequest, w.Code)
+	}
+}
+
 func TestGetRecommendationHandler(t *testing.T) {
 	router := setupRouter()
 
@@ -337,3 +392,51 @@ func TestGetPredictionHistoryHandler(t *testing.T) {
 		t.Error("Expected predictions to be non-empty")
 	}
 }
+
+func TestEvaluatePolicies(t *testing.T) {
+	minCPU := uint32(100)
+	maxMemory := uint64(512 * 1024 * 1024)
+	rec := &Recommendation{
+		ID:                   "rec-1",
+		Namespace:            "payments",
+		Deployment:           "api",
+		CpuRequestMillicores: 50,
+		MemoryRequestBytes:   1024 * 1024 * 1024,
+		Status:               "pending",
+	}
+	policies := []Policy{
+		{Name: "bounds", MinCPUMillicores: &minCPU, MaxMemoryBytes: &maxMemory},
+		{Name: "prod", Namespaces: []string{"payments"}, RequireApproval: true},
+		{Name: "other", Namespaces: []string{"batch"}, ExcludedNamespaces: []string{"batch"}},
+	}
+
+	result := EvaluatePolicies(rec, policies)
+
+	if result.Allowed {
+		t.Error("Expected recommendation to be disallowed")
+	}
+	if result.PoliciesChecked != 2 {
+		t.Errorf("Expected 2 policies checked, got %d", result.PoliciesChecked)
+	}
+	rules := make([]string, 0, len(result.Violations))
+	for _, v := range result.Violations {
+		rules = append(rules, v.Rule)
+	}
+	expected := []string{"min_cpu", "max_memory", "approval_required"}
+	if len(rules) != len(expected) {
+		t.Fatalf("Expected violations %v, got %v", expected, rules)
+	}
+	for i := range expected {
+		if rules[i] != expected[i] {
+			t.Errorf("Expected violations %v, got %v", expected, rules)
+			break
+		}
+	}
+
+	rec.Status = "approved"
+	rec.CpuRequestMillicores = 200
+	rec.MemoryRequestBytes = 256 * 1024 * 1024
+	if result := EvaluatePolicies(rec, policies); !result.Allowed {
+		t.Errorf("Expected recommendation to be allowed, got %v", result.Violations)
+	}
+}
@@ -0,0 +1,218 @@
+// Package rest provides REST API handlers
+package rest
+
+import (
+	"fmt"
+	"net/http"
+	"slices"
+	"time"
+
+	"github.com/gin-gonic/gin"
+)
+
+// Policy is a guardrail that recommendations must satisfy before being applied
+type Policy struct {
+	Name string `json:"name"`
+	// Namespaces the policy applies to; empty means all namespaces
+	Namespaces []string `json:"namespaces,omitempty"`
+	// Bounds on the recommended requests
+	MinCPUMillicores *uint32 `json:"min_cpu_millicores,omitempty"`
+	MaxCPUMillicores *uint32 `json:"max_cpu_millicores,omitempty"`
+	MinMemoryBytes   *uint64 `json:"min_memory_bytes,omitempty"`
+	MaxMemoryBytes   *uint64 `json:"max_memory_bytes,omitempty"`
+	// Namespaces whose recommendations must never be applied
+	ExcludedNamespaces []string `json:"excluded_namespaces,omitempty"`
+	// Recommendations must be approved before they are applied
+	RequireApproval bool      `json:"require_approval"`
+	CreatedAt       time.Time `json:"created_at"`
+	UpdatedAt       time.Time `json:"updated_at"`
+}
+
+// PolicyViolation is one rule of a policy that a recommendation breaks
+type PolicyViolation struct {
+	Policy  string `json:"policy"`
+	Rule    string `json:"rule"` // min_cpu, max_cpu, min_memory, max_memory, excluded_namespace, approval_required
+	Message string `json:"message"`
+}
+
+// PolicyCheckResult lists the policy violations of a recommendation
+type PolicyCheckResult struct {
+	RecommendationID string            `json:"recommendation_id"`
+	Namespace        string            `json:"namespace"`
+	Deployment       string            `json:"deployment"`
+	Allowed          bool              `json:"allowed"`
+	PoliciesChecked  int               `json:"policies_checked"`
+	Violations       []PolicyViolation `json:"violations"`
+}
+
+// AppliesTo reports whether the policy covers recommendations in namespace
+func (p *Policy) AppliesTo(namespace string) bool {
+	return len(p.Namespaces) == 0 || slices.Contains(p.Namespaces, namespace)
+}
+
+// EvaluatePolicies checks a recommendation against every applicable policy
+func EvaluatePolicies(rec *Recommendation, policies []Policy) PolicyCheckResult {
+	result := PolicyCheckResult{
+		RecommendationID: rec.ID,
+		Namespace:        rec.Namespace,
+		Deployment:       rec.Deployment,
+		Violations:       []PolicyViolation{},
+	}
+
+	for _, p := range policies {
+		if !p.AppliesTo(rec.Namespace) {
+			continue
+		}
+		result.PoliciesChecked++
+
+		violate := func(rule, format string, args ...any) {
+			result.Violations = append(result.Violations, PolicyViolation{
+				Policy:  p.Name,
+				Rule:    rule,
+				Message: fmt.Sprintf(format, args...),
+			})
+		}
+		if slices.Contains(p.ExcludedNamespaces, rec.Namespace) {
+			violate("excluded_namespace", "namespace %s is excluded from recommendations", rec.Namespace)
+		}
+		if p.MinCPUMillicores != nil && rec.CpuRequestMillicores < *p.MinCPUMillicores {
+			violate("min_cpu", "CPU request %dm is below the minimum of %dm", rec.CpuRequestMillicores, *p.MinCPUMillicores)
+		}
+		if p.MaxCPUMillicores != nil && rec.CpuRequestMillicores > *p.MaxCPUMillicores {
+			violate("max_cpu", "CPU request %dm exceeds the maximum of %dm", rec.CpuRequestMillicores, *p.MaxCPUMillicores)
+		}
+		if p.MinMemoryBytes != nil && rec.MemoryRequestBytes < *p.MinMemoryBytes {
+			violate("min_memory", "memory request %d bytes is below the minimum of %d bytes", rec.MemoryRequestBytes, *p.MinMemoryBytes)
+		}
+		if p.MaxMemoryBytes != nil && rec.MemoryRequestBytes > *p.MaxMemoryBytes {
+			violate("max_memory", "memory request %d bytes exceeds the maximum of %d bytes", rec.MemoryRequestBytes, *p.MaxMemoryBytes)
+		}
+		if p.RequireApproval && rec.Status == "pending" {
+			violate("approval_required", "recommendation must be approved before it is applied")
+		}
+	}
+
+	result.Allowed = len(result.Violations) == 0
+	return result
+}
+
+// listPoliciesHandler returns all policies
+func listPoliciesHandler(c *gin.Context) {
+	policies, err := getPolicyStore().ListPolicies(c.Request.Context())
+	if err != nil {
+		c.JSON(http.StatusInternalServerError, ErrorResponse{
+			Error: "Failed to list policies",
+			Code:  "INTERNAL_ERROR",
+		})
+		return
+	}
+
+	c.JSON(http.StatusOK, policies)
+}
+
+// getPolicyHandler returns a single policy
+func getPolicyHandler(c *gin.Context) {
+	policy, err := getPolicyStore().GetPolicy(c.Request.Context(), c.Param("name"))
+	if err != nil {
+		c.JSON(http.StatusInternalServerError, ErrorResponse{
+			Error: "Failed to get policy",
+			Code:  "INTERNAL_ERROR",
+		})
+		return
+	}
+	if policy == nil {
+		c.JSON(http.StatusNotFound, ErrorResponse{
+			Error: "Policy not found",
+			Code:  "NOT_FOUND",
+		})
+		return
+	}
+
+	c.JSON(http.StatusOK, policy)
+}
+
+// setPolicyHandler creates or replaces a policy
+func setPolicyHandler(c *gin.Context) {
+	var policy Policy
+	if err := c.ShouldBindJSON(&policy); err != nil {
+		c.JSON(http.StatusBadRequest, ErrorResponse{
+			Error: "Invalid request body",
+			Code:  "INVALID_REQUEST",
+		})
+		return
+	}
+	policy.Name = c.Param("name")
+
+	if (policy.MinCPUMillicores != nil && policy.MaxCPUMillicores != nil && *policy.MinCPUMillicores > *policy.MaxCPUMillicores) ||
+		(policy.MinMemoryBytes != nil && policy.MaxMemoryBytes != nil && *policy.MinMemoryBytes > *policy.MaxMemoryBytes) {
+		c.JSON(http.StatusBadRequest, ErrorResponse{
+			Error: "Minimum must not exceed maximum",
+			Code:  "INVALID_REQUEST",
+		})
+		return
+	}
+
+	if err := getPolicyStore().SetPolicy(c.Request.Context(), &policy); err != nil {
+		c.JSON(http.StatusInternalServerError, ErrorResponse{
+			Error: "Failed to save policy",
+			Code:  "INTERNAL_ERROR",
+		})
+		return
+	}
+
+	c.JSON(http.StatusOK, policy)
+}
+
+// deletePolicyHandler removes a policy
+func deletePolicyHandler(c *gin.Context) {
+	found, err := getPolicyStore().DeletePolicy(c.Request.Context(), c.Param("name"))
+	if err != nil {
+		c.JSON(http.StatusInternalServerError, ErrorResponse{
+			Error: "Failed to delete policy",
+			Code:  "INTERNAL_ERROR",
+		})
+		return
+	}
+	if !found {
+		c.JSON(http.StatusNotFound, ErrorResponse{
+			Error: "Policy not found",
+			Code:  "NOT_FOUND",
+		})
+		return
+	}
+
+	c.Status(http.StatusNoContent)
+}
+
+// checkRecommendationPoliciesHandler evaluates a recommendation against all policies
+func checkRecommendationPoliciesHandler(c *gin.Context) {
+	ctx := c.Request.Context()
+
+	if store == nil {
+		c.JSON(http.StatusServiceUnavailable, ErrorResponse{
+			Error: "Recommendation store not configured",
+			Code:  "UNAVAILABLE",
+		})
+		return
+	}
+
+	rec, err := store.GetRecommendationByID(ctx, c.Param("id"))
+	if err != nil || rec == nil {
+		c.JSON(http.StatusNotFound, ErrorResponse{
+			Error: "Recommendation not found",
+			Code:  "NOT_FOUND",
+		})
+		return
+	}
+
+	policies, err := getPolicyStore().ListPolicies(ctx)
+	if err != nil {
+		c.JSON(http.StatusInternalServerError, ErrorResponse{
+			Error: "Failed to list policies",
+			Code:  "INTERNAL_ERROR",
+		})
+		return
+	}
+
+	c.JSON(http.StatusOK, EvaluatePolicies(rec, policies))
+}
@@ -2,7 +2,10 @@
 package rest
 
 import (
+	"encoding/base64"
+	"errors"
 	"net/http"
+	"strconv"
 	"time"
 
 	"github.com/gin-gonic/gin"
@@ -10,46 +13,53 @@ import (
 
 // listRecommendationsHandler returns all recommendations
 func listRecommendationsHandler(c *gin.Context) {
-	ctx := c.Request.Context()
-
-	if store == nil {
-		// Return mock data when store is not configured
-		c.JSON(http.StatusOK, RecommendationList{
-			Recommendations: []Recommendation{},
-			Total:           0,
-		})
-		return
-	}
-
-	recommendations, err := store.ListRecommendations(ctx, "")
-	if err != nil {
-		c.JSON(http.StatusInternalServerError, ErrorResponse{
-			Error: "Failed to list recommendations",
-			Code:  "INTERNAL_ERROR",
-		})
-		return
-	}
-
-	c.JSON(http.StatusOK, RecommendationList{
-		Recommendations: recommendations,
-		Total:           len(recommendations),
+	respondRecommendationPage(c, func(limit, offset int) ([]Recommendation, int, error) {
+		return listRecommendations(c, "", limit, offset)
 	})
 }
 
 // listNamespaceRecommendationsHandler returns recommendations for a namespace
 func listNamespaceRecommendationsHandler(c *gin.Context) {
-	ctx := c.Request.Context()
 	namespace := c.Param("namespace")
+	respondRecommendationPage(c, func(limit, offset int) ([]Recommendation, int, error) {
+		return listRecommendations(c, namespace, limit, offset)
+	})
+}
 
+// listRecommendations reads a page from the store, empty when no store is
+// configured
+func listRecommendations(c *gin.Context, namespace string, limit, offset int) ([]Recommendation, int, error) {
 	if store == nil {
-		c.JSON(http.StatusOK, RecommendationList{
-			Recommendations: []Recommendation{},
-			Total:           0,
+		return []Recommendation{}, 0, nil
+	}
+	return store.ListRecommendations(c.Request.Context(), namespace, limit, offset)
+}
+
+// respondRecommendationPage writes one page of recommendations, read with
+// fetch from the requested limit and offset
+//
+// Without a limit query parameter all recommendations are returned. Otherwise
+// next_page_token is set while more remain; pass it back as page_token.
+func respondRecommendationPage(c *gin.Context, fetch func(limit, offset int) ([]Recommendation, int, error)) {
+	var params PageParams
+	if err := c.ShouldBindQuery(&params); err != nil {
+		c.JSON(http.StatusBadRequest, ErrorResponse{
+			Error: "Invalid pagination parameters",
+			Code:  "BAD_REQUEST",
 		})
 		return
 	}
 
-	recommendations, err := store.ListRecommendations(ctx, namespace)
+	offset, err := decodePageToken(params.PageToken)
+	if err != nil {
+		c.JSON(http.StatusBadRequest, ErrorResponse{
+			Error: "Invalid page token",
+			Code:  "BAD_REQUEST",
+		})
+		return
+	}
+
+	recommendations, total, err := fetch(params.Limit, offset)
 	if err != nil {
 		c.JSON(http.StatusInternalServerError, ErrorResponse{
 			Error: "Failed to list recommendations",
@@ -57,11 +67,44 @@ func listNamespaceRecommendationsHandler(c *gin.Context) {
 		})
 		return
 	}
+	if offset > total {
+		c.JSON(http.StatusBadRequest, ErrorResponse{
+			Error: "Invalid page token",
+			Code:  "BAD_REQUEST",
+		})
+		return
+	}
 
-	c.JSON(http.StatusOK, RecommendationList{
-		Recommendations: recommendations,
-		Total:           len(recommendations),
-	})
+	list := RecommendationList{Recommendations: recommendations, Total: total}
+	if params.Limit > 0 && offset+len(recommendations) < total {
+		list.NextPageToken = encodePageToken(offset + len(recommendations))
+	}
+
+	c.JSON(http.StatusOK, list)
+}
+
+// encodePageToken makes an opaque token for the page starting at offset
+func encodePageToken(offset int) string {
+	return base64.RawURLEncoding.EncodeToString([]byte(strconv.Itoa(offset)))
+}
+
+// decodePageToken returns the offset encoded in token, 0 for an empty token
+func decodePageToken(token string) (int, error) {
+	if token == "" {
+		return 0, nil
+	}
+	raw, err := base64.RawURLEncoding.DecodeString(token)
+	if err != nil {
+		return 0, err
+	}
+	offset, err := strconv.Atoi(string(raw))
+	if err != nil {
+		return 0, err
+	}
+	if offset < 0 {
+		return 0, errors.New("negative page offset")
+	}
+	return offset, nil
 }
 
 // getRecommendationHandler returns a specific recommendation
@@ -40,6 +40,7 @@ func RegisterRoutes(r *gin.Engine) {
 			recActions.POST("/:id/dry-run", dryRunRecommendationHandler)
 			recActions.GET("/:id/approval-history", getApprovalHistoryHandler)
 			recActions.GET("/:id/outcome", getRecommendationOutcomeHandler)
+			recActions.GET("/:id/policy-check", checkRecommendationPoliciesHandler)
 		}
 
 		// Costs
@@ -61,6 +62,15 @@ func RegisterRoutes(r *gin.Engine) {
 			clusters.DELETE("/:id", deleteClusterHandler)
 		}
 
+		// Guardrail policies
+		policies := v1.Group("/policies")
+		{
+			policies.GET("", listPoliciesHandler)
+			policies.GET("/:name", getPolicyHandler)
+			policies.PUT("/:name", setPolicyHandler)
+			policies.DELETE("/:name", deletePolicyHandler)
+		}
+
 		// Anomalies
 		anomalies := v1.Group("/anomalies")
 		{
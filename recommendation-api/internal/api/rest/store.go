@@ -15,7 +15,9 @@ type Store interface {
 
 // RecommendationStore handles recommendation data
 type RecommendationStore interface {
-	ListRecommendations(ctx context.Context, namespace string) ([]Recommendation, error)
+	// ListRecommendations returns up to limit recommendations from offset,
+	// all of them for a limit of 0, and the number matching namespace
+	ListRecommendations(ctx context.Context, namespace string, limit, offset int) ([]Recommendation, int, error)
 	GetRecommendation(ctx context.Context, namespace, name string) (*Recommendation, error)
 	GetRecommendationByID(ctx context.Context, id string) (*Recommendation, error)
 	ApplyRecommendation(ctx context.Context, id string, dryRun bool) (*ApplyRecommendationResponse, error)
@@ -81,6 +83,15 @@ type AnomalyStore interface {
 	GetAnomalyDetail(ctx context.Context, anomalyID string) (*AnomalyDetail, error)
 }
 
+// PolicyStore handles guardrail policies
+type PolicyStore interface {
+	ListPolicies(ctx context.Context) ([]Policy, error)
+	GetPolicy(ctx context.Context, name string) (*Policy, error)
+	SetPolicy(ctx context.Context, policy *Policy) error
+	// DeletePolicy reports whether the policy existed
+	DeletePolicy(ctx context.Context, name string) (bool, error)
+}
+
 // AuthStore handles authentication
 type AuthStore interface {
 	Authenticate(ctx context.Context, email, password string) (*User, string, error)
@@ -101,6 +112,9 @@ var clusterStore ClusterStore
 // anomalyStore is the global anomaly store instance
 var anomalyStore AnomalyStore
 
+// policyStore is the global policy store instance
+var policyStore PolicyStore
+
 // authStore is the global auth store instance
 var authStore AuthStore
 
@@ -153,3 +167,13 @@ func SetAuthStore(s AuthStore) {
 func getAuthStore() AuthStore {
 	return authStore
 }
+
+// SetPolicyStore sets the global policy store instance
+func SetPolicyStore(s PolicyStore) {
+	policyStore = s
+}
+
+// getPolicyStore returns the global policy store instance
+func getPolicyStore() PolicyStore {
+	return policyStore
+}
@@ -33,7 +33,15 @@ type ResourceSpec struct {
 // RecommendationList is a list of recommendations
 type RecommendationList struct {
 	Recommendations []Recommendation `json:"recommendations"`
-	Total           int              `json:"total"`
+	// Total counts all matching recommendations, not just this page
+	Total         int    `json:"total"`
+	NextPageToken string `json:"next_page_token,omitempty"`
+}
+
+// PageParams are the optional pagination query parameters of list endpoints
+type PageParams struct {
+	Limit     int    `form:"limit" binding:"omitempty,min=1,max=1000"`
+	PageToken string `form:"page_token"`
 }
 
 // ApplyRecommendationRequest is the request body for applying a recommendation
@@ -175,10 +175,23 @@ func (s *InMemoryAgentStore) StoreAnomalies(ctx context.Context, anomalies []*pr
 				Container:  a.ContainerId,
 				DetectedAt: a.DetectedAt.AsTime(),
 				Status:     "active",
+				Message:    a.Message,
 			},
 			Metrics:                []rest.AnomalyMetric{},
 			RelatedRecommendations: []string{},
 		}
+		if leak := a.GetMemoryLeak(); leak != nil {
+			rate := leak.GetSlopeBytesPerSecond()
+			detail.LeakRateBytesPerSecond = &rate
+			if leak.GetProjectedOomTime() != nil {
+				oomAt := leak.GetProjectedOomTime().AsTime()
+				detail.ProjectedOOMAt = &oomAt
+			}
+		}
+		if spike := a.GetCpuSpike(); spike != nil {
+			magnitude := spike.GetZScore()
+			detail.SpikeMagnitude = &magnitude
+		}
 
 		s.anomalyStore.RecordAnomaly(ctx, detail)
 		
@@ -4,6 +4,8 @@ package storage
 import (
 	"context"
 	"errors"
+	"fmt"
+	"sort"
 	"sync"
 	"time"
 
@@ -28,6 +30,15 @@ func (s *InMemoryAnomalyStore) ListAnomalies(ctx context.Context, filters rest.A
 	s.mu.RLock()
 	defer s.mu.RUnlock()
 
+	var since time.Time
+	if filters.StartDate != "" {
+		parsed, err := time.Parse(time.RFC3339, filters.StartDate)
+		if err != nil {
+			return nil, fmt.Errorf("invalid startDate: %w", err)
+		}
+		since = parsed
+	}
+
 	anomalies := make([]rest.Anomaly, 0)
 	for _, a := range s.anomalies {
 		// Apply filters
@@ -40,8 +51,16 @@ func (s *InMemoryAnomalyStore) ListAnomalies(ctx context.Context, filters rest.A
 		if filters.Namespace != "" && a.Namespace != filters.Namespace {
 			continue
 		}
+		if !since.IsZero() && a.DetectedAt.Before(since) {
+			continue
+		}
 		anomalies = append(anomalies, a.Anomaly)
 	}
+
+	// Most recent first
+	sort.Slice(anomalies, func(i, j int) bool {
+		return anomalies[i].DetectedAt.After(anomalies[j].DetectedAt)
+	})
 	return anomalies, nil
 }
 
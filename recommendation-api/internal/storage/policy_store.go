@@ -0,0 +1,81 @@
+// Package storage provides data persistence implementations
+package storage
+
+import (
+	"context"
+	"sort"
+	"sync"
+	"time"
+
+	"github.com/container-resource-predictor/recommendation-api/internal/api/rest"
+)
+
+// InMemoryPolicyStore provides an in-memory guardrail policy store
+type InMemoryPolicyStore struct {
+	policies map[string]*rest.Policy
+	mu       sync.RWMutex
+}
+
+// NewInMemoryPolicyStore creates a new in-memory policy store
+func NewInMemoryPolicyStore() *InMemoryPolicyStore {
+	return &InMemoryPolicyStore{
+		policies: make(map[string]*rest.Policy),
+	}
+}
+
+// ListPolicies returns all policies sorted by name
+func (s *InMemoryPolicyStore) ListPolicies(ctx context.Context) ([]rest.Policy, error) {
+	s.mu.RLock()
+	defer s.mu.RUnlock()
+
+	policies := make([]rest.Policy, 0, len(s.policies))
+	for _, p := range s.policies {
+		policies = append(policies, *p)
+	}
+	sort.Slice(policies, func(i, j int) bool {
+		return policies[i].Name < policies[j].Name
+	})
+	return policies, nil
+}
+
+// GetPolicy returns a policy by name, or nil if it does not exist
+func (s *InMemoryPolicyStore) GetPolicy(ctx context.Context, name string) (*rest.Policy, error) {
+	s.mu.RLock()
+	defer s.mu.RUnlock()
+
+	p, exists := s.policies[name]
+	if !exists {
+		return nil, nil
+	}
+	policy := *p
+	return &policy, nil
+}
+
+// SetPolicy creates or replaces a policy, keeping its original creation time
+func (s *InMemoryPolicyStore) SetPolicy(ctx context.Context, policy *rest.Policy) error {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	now := time.Now()
+	policy.CreatedAt = now
+	if existing, exists := s.policies[policy.Name]; exists {
+		policy.CreatedAt = existing.CreatedAt
+	}
+	policy.UpdatedAt = now
+
+	stored := *policy
+	s.policies[policy.Name] = &stored
+	return nil
+}
+
+// DeletePolicy removes a policy and reports whether it existed
+func (s *InMemoryPolicyStore) DeletePolicy(ctx context.Context, name string) (bool, error) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	if _, exists := s.policies[name]; !exists {
+		return false, nil
+	}
+	delete(s.policies, name)
+	return true, nil
+}
@@ -20,8 +20,22 @@ func NewRepository(db *DB) *Repository {
 	return &Repository{db: db}
 }
 
-// ListRecommendations returns recommendations, optionally filtered by namespace
-func (r *Repository) ListRecommendations(ctx context.Context, namespace string) ([]rest.Recommendation, error) {
+// ListRecommendations returns a page of recommendations, newest first and
+// optionally filtered by namespace, with the number of matching
+// recommendations. A limit of 0 returns every recommendation from offset.
+func (r *Repository) ListRecommendations(ctx context.Context, namespace string, limit, offset int) ([]rest.Recommendation, int, error) {
+	var total int
+	countQuery := `SELECT COUNT(*) FROM recommendations WHERE ($1 = '' OR namespace = $1)`
+	if err := r.db.QueryRowContext(ctx, countQuery, namespace).Scan(&total); err != nil {
+		return nil, 0, fmt.Errorf("failed to count recommendations: %w", err)
+	}
+
+	// LIMIT NULL returns all rows
+	var limitArg interface{}
+	if limit > 0 {
+		limitArg = limit
+	}
+
 	query := `
 		SELECT id, namespace, deployment, cpu_request_millicores, cpu_limit_millicores,
 		       memory_request_bytes, memory_limit_bytes, confidence, model_version,
@@ -30,13 +44,13 @@ func (r *Repository) ListRecommendations(ctx context.Context, namespace string)
 		       current_memory_request_bytes, current_memory_limit_bytes
 		FROM recommendations
 		WHERE ($1 = '' OR namespace = $1)
-		ORDER BY created_at DESC
-		LIMIT 100
+		ORDER BY created_at DESC, id DESC
+		LIMIT $2 OFFSET $3
 	`
 
-	rows, err := r.db.QueryContext(ctx, query, namespace)
+	rows, err := r.db.QueryContext(ctx, query, namespace, limitArg, offset)
 	if err != nil {
-		return nil, fmt.Errorf("failed to query recommendations: %w", err)
+		return nil, 0, fmt.Errorf("failed to query recommendations: %w", err)
 	}
 	defer rows.Close()
 
@@ -56,7 +70,7 @@ func (r *Repository) ListRecommendations(ctx context.Context, namespace string)
 			&currentCpuReq, &currentCpuLim, &currentMemReq, &currentMemLim,
 		)
 		if err != nil {
-			return nil, fmt.Errorf("failed to scan recommendation: %w", err)
+			return nil, 0, fmt.Errorf("failed to scan recommendation: %w", err)
 		}
 
 		if appliedAt.Valid {
@@ -82,7 +96,10 @@ func (r *Repository) ListRecommendations(ctx context.Context, namespace string)
 		recommendations = append(recommendations, rec)
 	}
 
-	return recommendations, rows.Err()
+	if err := rows.Err(); err != nil {
+		return nil, 0, err
+	}
+	return recommendations, total, nil
 }
 
 // GetRecommendation returns a specific recommendation
@@ -0,0 +1,44 @@
+//! Batch-building path of the metrics stream
+//!
+//! Converts a batch of 1000 synthetic container samples to a `MetricsBatch`,
+//! and additionally encodes it. Both should stay well under a millisecond.
+//!
+//! Run with `cargo bench -p agent-lib --bench batch`.
+
+use agent_lib::sync::{proto_batch, PendingData};
+use agent_lib::synthetic;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use prost::Message;
+
+const CONTAINERS: usize = 100;
+const SAMPLES: usize = 1000;
+
+fn pending() -> PendingData {
+    PendingData {
+        metrics: synthetic::fleet(CONTAINERS, SAMPLES / CONTAINERS, 1),
+        ..Default::default()
+    }
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let data = pending();
+
+    c.bench_function("proto_batch/1000", |b| {
+        b.iter_batched(
+            || data.clone(),
+            |data| proto_batch("agent-1", "node-1", data),
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("proto_batch_encode/1000", |b| {
+        b.iter_batched(
+            || data.clone(),
+            |data| proto_batch("agent-1", "node-1", data).encode_to_vec(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_batch);
+criterion_main!(benches);
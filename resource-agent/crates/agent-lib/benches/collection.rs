@@ -0,0 +1,45 @@
+//! Cgroup parsing on the collection path
+//!
+//! Parses the `cpu.stat` and `memory.stat` files of a synthetic container,
+//! as the collectors do for every container on every tick, and extracts a
+//! container ID from a systemd cgroup path. Each should stay within a few
+//! microseconds.
+//!
+//! Run with `cargo bench -p agent-lib --bench collection`.
+
+use agent_lib::collector::{CgroupV1Collector, CgroupV2Collector};
+use agent_lib::synthetic::{self, SyntheticStream, WorkloadPattern};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn bench_collection(c: &mut Criterion) {
+    let sample = SyntheticStream::new(WorkloadPattern::DiurnalWeb, 1)
+        .nth(1000)
+        .unwrap();
+    let cpu_stat_v2 = synthetic::cpu_stat_v2(&sample, 123_456_789);
+    let memory_stat_v2 = synthetic::memory_stat_v2(&sample);
+    let cpu_stat_v1 = synthetic::cpu_stat_v1(&sample);
+    let memory_stat_v1 = synthetic::memory_stat_v1(&sample);
+    let cgroup_path = format!(
+        "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod3f2a.slice/cri-containerd-{:064x}.scope",
+        1
+    );
+
+    c.bench_function("cgroup_v2/cpu_stat", |b| {
+        b.iter(|| CgroupV2Collector::parse_cpu_stat(black_box(&cpu_stat_v2)))
+    });
+    c.bench_function("cgroup_v2/memory_stat", |b| {
+        b.iter(|| CgroupV2Collector::parse_memory_stat(black_box(&memory_stat_v2)))
+    });
+    c.bench_function("cgroup_v1/cpu_stat", |b| {
+        b.iter(|| CgroupV1Collector::parse_cpu_stat(black_box(&cpu_stat_v1)))
+    });
+    c.bench_function("cgroup_v1/memory_stat", |b| {
+        b.iter(|| CgroupV1Collector::parse_memory_stat(black_box(&memory_stat_v1)))
+    });
+    c.bench_function("extract_container_id", |b| {
+        b.iter(|| CgroupV2Collector::extract_container_id(black_box(&cgroup_path)))
+    });
+}
+
+criterion_group!(benches, bench_collection);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+//! Feature extraction for a container
+//!
+//! Extracts features from a day of samples of a synthetic web service, as
+//! the scheduler does for every container at each prediction, over the
+//! default one-hour window and over the whole day.
+//!
+//! Run with `cargo bench -p agent-lib --bench features`.
+
+use agent_lib::predictor::{FeatureExtractor, PredictionConfig, SampleSeries, MAX_SERIES_SAMPLES};
+use agent_lib::synthetic::{SyntheticStream, WorkloadPattern};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bench_features(c: &mut Criterion) {
+    let mut series = SampleSeries::new(MAX_SERIES_SAMPLES);
+    for sample in SyntheticStream::new(WorkloadPattern::DiurnalWeb, 1).take(MAX_SERIES_SAMPLES) {
+        series.push(&sample);
+    }
+    let columns = series.columns();
+
+    let window = PredictionConfig::default().feature_window_size;
+    let extractor = FeatureExtractor::new(window);
+    c.bench_function("extract_view/window", |b| {
+        b.iter(|| extractor.extract_view(columns.view()))
+    });
+
+    let extractor = FeatureExtractor::new(MAX_SERIES_SAMPLES);
+    c.bench_function("extract_view/day", |b| {
+        b.iter(|| extractor.extract_view(columns.view()))
+    });
+
+    // Copying the columns out of the ring buffer precedes every extraction
+    c.bench_function("series_columns/day", |b| b.iter(|| series.columns()));
+}
+
+criterion_group!(benches, bench_features);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+//! Inference for a container
+//!
+//! Predicts a profile from the features of each synthetic workload pattern
+//! with the bundled ONNX model, and with the fallback heuristics used when
+//! no model is loaded. Model inference should stay well under the
+//! scheduler's inference timeout.
+//!
+//! Run with `cargo bench -p agent-lib --bench inference`.
+
+use agent_lib::models::FeatureVector;
+use agent_lib::predictor::{FallbackPredictor, FeatureExtractor, OnnxPredictor, Predictor};
+use agent_lib::synthetic::{SyntheticStream, WorkloadPattern};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Model shipped with the agent, relative to this crate
+const MODEL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../models/predictor.onnx");
+
+fn features() -> Vec<FeatureVector> {
+    let extractor = FeatureExtractor::new(360);
+    WorkloadPattern::ALL
+        .iter()
+        .map(|&pattern| {
+            let samples: Vec<_> = SyntheticStream::new(pattern, 1).take(360).collect();
+            extractor.extract(&samples).unwrap()
+        })
+        .collect()
+}
+
+fn bench_inference(c: &mut Criterion) {
+    let features = features();
+
+    match std::fs::read(MODEL_PATH) {
+        Ok(model) => {
+            let predictor = OnnxPredictor::new(&model).unwrap();
+            c.bench_function("onnx_predict/patterns", |b| {
+                b.iter(|| {
+                    for f in &features {
+                        black_box(predictor.predict(f).unwrap());
+                    }
+                })
+            });
+        }
+        Err(e) => eprintln!("Skipping ONNX inference, {}: {}", MODEL_PATH, e),
+    }
+
+    c.bench_function("fallback_predict/patterns", |b| {
+        b.iter(|| {
+            for f in &features {
+                black_box(FallbackPredictor::predict(f));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_inference);
+criterion_main!(benches);
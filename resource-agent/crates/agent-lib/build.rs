@@ -32,7 +32,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     /*
     let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
     tonic_build::configure()
-        .build_server(false)
+        .build_server(true)
         .build_client(true)
         .out_dir(&out_dir)
         .compile(
@@ -0,0 +1,18 @@
+//! Arbitrary cgroup v1 file contents through the v1 parsers
+//!
+//! Run with `cargo +nightly fuzz run cgroup_v1` from `crates/agent-lib`.
+
+#![no_main]
+
+use agent_lib::collector::CgroupV1Collector;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let content = String::from_utf8_lossy(data);
+    let _ = CgroupV1Collector::parse_cpu_stat(&content);
+    let _ = CgroupV1Collector::parse_memory_stat(&content);
+    let _ = CgroupV1Collector::parse_proc_cgroup(&content);
+    if let Some(id) = CgroupV1Collector::extract_container_id(&content) {
+        assert!(!id.is_empty());
+    }
+});
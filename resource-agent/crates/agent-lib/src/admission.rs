@@ -0,0 +1,509 @@
+//! Mutating admission webhook that applies recommendations to new pods
+//!
+//! Deployments opt in by setting the [`INJECT_ANNOTATION`] annotation to
+//! `"true"` on their pod template. When such a pod is created, the requests
+//! and limits of its deployment's ResourceRecommendation are patched into
+//! the target container, so a recommendation takes effect as pods are
+//! replaced instead of through a manual apply and rollout.
+//!
+//! This module only turns an `AdmissionReview` into a response. Serving it
+//! over TLS, registering the MutatingWebhookConfiguration and keeping the
+//! [`RecommendationIndex`] in sync with the cluster are up to the caller.
+//! Pods are never rejected: when anything is missing the pod is admitted
+//! unchanged.
+
+use crate::k8s::{RecommendationPhase, RecommendationWindow, ResourceRecommendation, TargetKind};
+use dashmap::DashMap;
+use json_patch::{AddOperation, Patch, PatchOperation};
+use k8s_openapi::api::core::v1::{Container, Pod, ResourceRequirements};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use kube::core::admission::{AdmissionRequest, AdmissionResponse, AdmissionReview, Operation};
+use kube::core::DynamicObject;
+use kube::ResourceExt;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Pod template annotation opting a deployment in to injection
+pub const INJECT_ANNOTATION: &str = "predictor.io/inject-resources";
+
+/// Annotation recording the recommendation injected into a pod
+pub const INJECTED_ANNOTATION: &str = "predictor.io/injected-recommendation";
+
+/// Label set by the Deployment controller on the pods of a ReplicaSet
+const POD_TEMPLATE_HASH_LABEL: &str = "pod-template-hash";
+
+/// Recommendations that may be injected, by namespace and deployment
+#[derive(Default)]
+pub struct RecommendationIndex {
+    by_target: DashMap<(String, String), BTreeMap<String, Arc<ResourceRecommendation>>>,
+    /// Confidence below which recommendations are not applied unapproved
+    min_auto_apply_confidence: f64,
+}
+
+impl RecommendationIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only apply recommendations that don't need approval when their
+    /// confidence is at least `confidence`
+    ///
+    /// Meant for calibrated confidence, where it bounds the chance of usage
+    /// exceeding the injected limits. Approved recommendations are applied
+    /// whatever their confidence.
+    pub fn with_min_auto_apply_confidence(mut self, confidence: f64) -> Self {
+        self.min_auto_apply_confidence = confidence;
+        self
+    }
+
+    /// Add or replace a recommendation, e.g. from a watch event
+    ///
+    /// Recommendations that target other kinds than deployments are ignored.
+    pub fn upsert(&self, recommendation: ResourceRecommendation) {
+        if recommendation.spec.target_ref.kind != TargetKind::Deployment {
+            return;
+        }
+        let Some(namespace) = recommendation.namespace() else {
+            return;
+        };
+        let name = recommendation.name_any();
+        // A recommendation may move to another target, drop the old entry
+        self.remove(&namespace, &name);
+        let key = (namespace, recommendation.spec.target_ref.name.clone());
+        self.by_target
+            .entry(key)
+            .or_default()
+            .insert(name, Arc::new(recommendation));
+    }
+
+    /// Remove the recommendation with the given object name
+    pub fn remove(&self, namespace: &str, name: &str) {
+        self.by_target.retain(|(ns, _), recommendations| {
+            if ns == namespace {
+                recommendations.remove(name);
+            }
+            !recommendations.is_empty()
+        });
+    }
+
+    /// Recommendation to inject into pods of a deployment
+    ///
+    /// Only recommendations that were approved, or don't need approval, are
+    /// considered. The all-day window is preferred over the others, then the
+    /// most recently generated one.
+    pub fn get(&self, namespace: &str, deployment: &str) -> Option<Arc<ResourceRecommendation>> {
+        let recommendations = self
+            .by_target
+            .get(&(namespace.to_string(), deployment.to_string()))?;
+        let best = recommendations
+            .values()
+            .filter(|r| injectable(r, self.min_auto_apply_confidence))
+            .max_by_key(|r| {
+                (
+                    r.spec.recommendation.time_window == RecommendationWindow::All,
+                    r.spec.recommendation.generated_at,
+                )
+            })
+            .cloned();
+        best
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_target.iter().map(|entry| entry.value().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_target.is_empty()
+    }
+}
+
+/// Whether a recommendation may be applied without further approval
+fn injectable(recommendation: &ResourceRecommendation, min_auto_apply_confidence: f64) -> bool {
+    let phase = recommendation
+        .status
+        .as_ref()
+        .map(|status| status.phase)
+        .unwrap_or_default();
+    match phase {
+        RecommendationPhase::Approved | RecommendationPhase::Applied => true,
+        RecommendationPhase::Pending => {
+            let confidence = recommendation.spec.recommendation.confidence.unwrap_or(0.0);
+            !recommendation.spec.requires_approval && confidence >= min_auto_apply_confidence
+        }
+        RecommendationPhase::RolledBack
+        | RecommendationPhase::Failed
+        | RecommendationPhase::Rejected => false,
+    }
+}
+
+/// Mutating webhook handler injecting recommended resources into pods
+pub struct ResourceInjector {
+    index: Arc<RecommendationIndex>,
+}
+
+impl ResourceInjector {
+    pub fn new(index: Arc<RecommendationIndex>) -> Self {
+        Self { index }
+    }
+
+    /// Answer an `AdmissionReview` request with a review holding the response
+    pub fn review(&self, review: AdmissionReview<Pod>) -> AdmissionReview<DynamicObject> {
+        let request: AdmissionRequest<Pod> = match review.try_into() {
+            Ok(request) => request,
+            Err(e) => {
+                warn!(error = %e, "Invalid admission review");
+                return AdmissionResponse::invalid(e.to_string()).into_review();
+            }
+        };
+        self.mutate(&request).into_review()
+    }
+
+    /// Admit a pod, patching in its deployment's recommendation when opted in
+    pub fn mutate(&self, request: &AdmissionRequest<Pod>) -> AdmissionResponse {
+        let response = AdmissionResponse::from(request);
+        if request.operation != Operation::Create {
+            return response;
+        }
+        let Some(pod) = &request.object else {
+            return response;
+        };
+        if !opted_in(pod) {
+            return response;
+        }
+        let Some(namespace) = request.namespace.clone().or_else(|| pod.namespace()) else {
+            return response;
+        };
+        let Some(deployment) = owning_deployment(pod) else {
+            debug!(namespace = %namespace, "Opted-in pod has no owning deployment");
+            return response;
+        };
+        let Some(recommendation) = self.index.get(&namespace, &deployment) else {
+            debug!(
+                namespace = %namespace,
+                deployment = %deployment,
+                "No recommendation to inject"
+            );
+            return response;
+        };
+        let Some(patch) = resource_patch(pod, &deployment, &recommendation) else {
+            return response;
+        };
+
+        match response.with_patch(patch) {
+            Ok(response) => {
+                info!(
+                    namespace = %namespace,
+                    deployment = %deployment,
+                    recommendation = %recommendation.name_any(),
+                    "Injected recommended resources into pod"
+                );
+                response
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize resource patch");
+                AdmissionResponse::from(request)
+            }
+        }
+    }
+}
+
+fn opted_in(pod: &Pod) -> bool {
+    pod.annotations()
+        .get(INJECT_ANNOTATION)
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Deployment owning a pod through its ReplicaSet
+///
+/// ReplicaSets created by a Deployment are named `<deployment>-<pod template
+/// hash>`, and their pods carry that hash as a label.
+fn owning_deployment(pod: &Pod) -> Option<String> {
+    let owner = pod
+        .owner_references()
+        .iter()
+        .find(|owner| owner.kind == "ReplicaSet" && owner.controller == Some(true))?;
+    let hash = pod.labels().get(POD_TEMPLATE_HASH_LABEL)?;
+    owner
+        .name
+        .strip_suffix(hash.as_str())?
+        .strip_suffix('-')
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+/// Index of the container a recommendation applies to
+///
+/// The container named in the recommendation, else the one named after the
+/// deployment, else the only container.
+fn target_container(
+    containers: &[Container],
+    deployment: &str,
+    recommendation: &ResourceRecommendation,
+) -> Option<usize> {
+    if let Some(name) = &recommendation.spec.target_ref.container_name {
+        return containers.iter().position(|c| &c.name == name);
+    }
+    containers
+        .iter()
+        .position(|c| c.name == deployment)
+        .or_else(|| (containers.len() == 1).then_some(0))
+}
+
+/// JSON patch setting the recommended resources and recording the injection
+fn resource_patch(
+    pod: &Pod,
+    deployment: &str,
+    recommendation: &ResourceRecommendation,
+) -> Option<Patch> {
+    let containers = &pod.spec.as_ref()?.containers;
+    let index = target_container(containers, deployment, recommendation)?;
+    let rec = &recommendation.spec.recommendation;
+
+    let mut resources = containers[index].resources.clone().unwrap_or_default();
+    let requests = set_quantities(
+        &mut resources.requests,
+        [("cpu", &rec.cpu_request), ("memory", &rec.memory_request)],
+    );
+    let limits = set_quantities(
+        &mut resources.limits,
+        [("cpu", &rec.cpu_limit), ("memory", &rec.memory_limit)],
+    );
+    let changed = requests || limits;
+    if !changed {
+        return None;
+    }
+
+    let mut operations = vec![add(
+        &format!("/spec/containers/{}/resources", index),
+        serde_json::to_value::<ResourceRequirements>(resources).ok()?,
+    )];
+    let name = recommendation.name_any();
+    if pod.metadata.annotations.is_some() {
+        operations.push(add(
+            &format!(
+                "/metadata/annotations/{}",
+                escape_pointer(INJECTED_ANNOTATION)
+            ),
+            serde_json::Value::String(name),
+        ));
+    } else {
+        operations.push(add(
+            "/metadata/annotations",
+            serde_json::json!({ INJECTED_ANNOTATION: name }),
+        ));
+    }
+    Some(Patch(operations))
+}
+
+/// Set the given quantities, returning whether any was set
+fn set_quantities(
+    map: &mut Option<BTreeMap<String, Quantity>>,
+    quantities: [(&str, &Option<String>); 2],
+) -> bool {
+    let mut changed = false;
+    for (name, value) in quantities {
+        if let Some(value) = value {
+            map.get_or_insert_with(BTreeMap::new)
+                .insert(name.to_string(), Quantity(value.clone()));
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn add(path: &str, value: serde_json::Value) -> PatchOperation {
+    PatchOperation::Add(AddOperation {
+        path: path.to_string(),
+        value,
+    })
+}
+
+/// Escape a JSON pointer reference token (RFC 6901)
+fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k8s::{
+        Recommendation, ResourceRecommendationSpec, ResourceRecommendationStatus, RiskLevel,
+        TargetRef,
+    };
+
+    fn recommendation(name: &str, phase: RecommendationPhase) -> ResourceRecommendation {
+        let mut recommendation = ResourceRecommendation::new(
+            name,
+            ResourceRecommendationSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: TargetKind::Deployment,
+                    name: "api".to_string(),
+                    container_name: None,
+                },
+                recommendation: Recommendation {
+                    cpu_request: Some("250m".to_string()),
+                    cpu_limit: Some("1".to_string()),
+                    memory_request: Some("256Mi".to_string()),
+                    memory_limit: Some("512Mi".to_string()),
+                    ..Default::default()
+                },
+                cost_impact: None,
+                auto_apply: false,
+                requires_approval: true,
+                risk_level: RiskLevel::Low,
+            },
+        );
+        recommendation.metadata.namespace = Some("payments".to_string());
+        recommendation.status = Some(ResourceRecommendationStatus {
+            phase,
+            ..Default::default()
+        });
+        recommendation
+    }
+
+    fn pod(annotations: serde_json::Value) -> Pod {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "generateName": "api-5d8f7c9b4-",
+                "namespace": "payments",
+                "labels": {"app": "api", "pod-template-hash": "5d8f7c9b4"},
+                "annotations": annotations,
+                "ownerReferences": [{
+                    "apiVersion": "apps/v1",
+                    "kind": "ReplicaSet",
+                    "name": "api-5d8f7c9b4",
+                    "uid": "1",
+                    "controller": true
+                }]
+            },
+            "spec": {
+                "containers": [
+                    {"name": "istio-proxy", "image": "proxy"},
+                    {"name": "api", "image": "api", "resources": {"requests": {"cpu": "1"}}}
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    fn request(pod: Pod) -> AdmissionRequest<Pod> {
+        let review: AdmissionReview<Pod> = serde_json::from_value(serde_json::json!({
+            "apiVersion": "admission.k8s.io/v1",
+            "kind": "AdmissionReview",
+            "request": {
+                "uid": "705ab4f5-6393-11e8-b7cc-42010a800002",
+                "kind": {"group": "", "version": "v1", "kind": "Pod"},
+                "resource": {"group": "", "version": "v1", "resource": "pods"},
+                "namespace": "payments",
+                "operation": "CREATE",
+                "userInfo": {},
+                "object": pod
+            }
+        }))
+        .unwrap();
+        review.try_into().unwrap()
+    }
+
+    fn injector(recommendations: Vec<ResourceRecommendation>) -> ResourceInjector {
+        let index = Arc::new(RecommendationIndex::new());
+        for recommendation in recommendations {
+            index.upsert(recommendation);
+        }
+        ResourceInjector::new(index)
+    }
+
+    #[test]
+    fn test_injects_approved_recommendation() {
+        let injector = injector(vec![recommendation(
+            "api-all",
+            RecommendationPhase::Approved,
+        )]);
+        let response = injector.mutate(&request(pod(
+            serde_json::json!({ INJECT_ANNOTATION: "true" }),
+        )));
+
+        assert!(response.allowed);
+        let patch: Vec<serde_json::Value> =
+            serde_json::from_slice(response.patch.as_deref().unwrap()).unwrap();
+        assert_eq!(patch[0]["path"], "/spec/containers/1/resources");
+        assert_eq!(patch[0]["value"]["requests"]["cpu"], "250m");
+        assert_eq!(patch[0]["value"]["limits"]["memory"], "512Mi");
+        assert_eq!(
+            patch[1]["path"],
+            "/metadata/annotations/predictor.io~1injected-recommendation"
+        );
+        assert_eq!(patch[1]["value"], "api-all");
+    }
+
+    #[test]
+    fn test_skips_pods_not_opted_in() {
+        let injector = injector(vec![recommendation(
+            "api-all",
+            RecommendationPhase::Approved,
+        )]);
+        let response = injector.mutate(&request(pod(serde_json::json!({}))));
+        assert!(response.allowed);
+        assert!(response.patch.is_none());
+    }
+
+    #[test]
+    fn test_skips_unapproved_recommendation() {
+        let injector = injector(vec![
+            recommendation("api-all", RecommendationPhase::Pending),
+            recommendation("api-peak", RecommendationPhase::Rejected),
+        ]);
+        let response = injector.mutate(&request(pod(
+            serde_json::json!({ INJECT_ANNOTATION: "true" }),
+        )));
+        assert!(response.allowed);
+        assert!(response.patch.is_none());
+    }
+
+    #[test]
+    fn test_index_prefers_all_day_window() {
+        let mut peak = recommendation("api-peak", RecommendationPhase::Approved);
+        peak.spec.recommendation.time_window = RecommendationWindow::Peak;
+        let index = RecommendationIndex::new();
+        index.upsert(peak);
+        index.upsert(recommendation("api-all", RecommendationPhase::Applied));
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get("payments", "api").unwrap().name_any(), "api-all");
+
+        index.remove("payments", "api-all");
+        assert_eq!(index.get("payments", "api").unwrap().name_any(), "api-peak");
+        index.remove("payments", "api-peak");
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_auto_apply_gated_on_confidence() {
+        let auto = |name: &str, confidence: f64| {
+            let mut recommendation = recommendation(name, RecommendationPhase::Pending);
+            recommendation.spec.requires_approval = false;
+            recommendation.spec.recommendation.confidence = Some(confidence);
+            recommendation
+        };
+        let index = RecommendationIndex::new().with_min_auto_apply_confidence(0.9);
+        index.upsert(auto("api-all", 0.8));
+        assert!(index.get("payments", "api").is_none());
+
+        index.upsert(auto("api-all", 0.95));
+        assert!(index.get("payments", "api").is_some());
+
+        // Approval overrides the gate
+        let mut approved = recommendation("api-all", RecommendationPhase::Approved);
+        approved.spec.recommendation.confidence = Some(0.5);
+        index.upsert(approved);
+        assert!(index.get("payments", "api").is_some());
+    }
+
+    #[test]
+    fn test_owning_deployment() {
+        let pod = pod(serde_json::json!({}));
+        assert_eq!(owning_deployment(&pod).as_deref(), Some("api"));
+    }
+}
@@ -4,16 +4,32 @@
 //! - Creating Kubernetes events on affected pods
 //! - Formatting alerts for Alertmanager webhook
 //! - Deduplication of alerts within a configurable window
+//! - Dropping alerts for sidecars excluded by policy
+//! - Persisting dedup state so restarts don't re-fire active alerts
+//!
+//! The alert types are always available; `Alerter` requires the `alerting`
+//! feature.
+#![cfg_attr(not(feature = "alerting"), allow(unused_imports))]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::RwLock;
-use std::time::{Duration, Instant};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
 
-use super::{LeakAnomaly, SpikeAnomaly, SpikeSeverity};
+use super::{CorrelatedAnomaly, CorrelationScope, LeakAnomaly, SpikeAnomaly, SpikeSeverity};
+use crate::maintenance::MaintenanceStatus;
+use crate::models::{ContainerRole, WELL_KNOWN_SIDECARS};
 
 /// Default deduplication window (15 minutes)
+#[cfg(feature = "alerting")]
 const DEFAULT_DEDUP_WINDOW_SECS: u64 = 15 * 60;
 
 /// Alert severity levels
@@ -137,9 +153,49 @@ pub struct AlertContext {
     pub namespace: String,
     pub node_name: String,
     pub deployment: Option<String>,
+    /// Container name within the pod, when known
+    pub container_name: Option<String>,
+    pub role: ContainerRole,
+    /// Pod labels, for matching alert routes
+    pub pod_labels: HashMap<String, String>,
+}
+
+/// Which sidecar containers are kept out of anomaly alerts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SidecarAlertPolicy {
+    /// Alert on sidecars like on any other container
+    #[default]
+    Include,
+    /// Never alert on sidecars
+    ExcludeAll,
+    /// Skip sidecars with one of these container names
+    Exclude(HashSet<String>),
+}
+
+impl SidecarAlertPolicy {
+    /// Skip the sidecars in [`WELL_KNOWN_SIDECARS`]
+    pub fn well_known() -> Self {
+        Self::Exclude(WELL_KNOWN_SIDECARS.iter().map(|s| s.to_string()).collect())
+    }
+
+    /// Whether alerts for a container are dropped
+    pub fn excludes(&self, ctx: &AlertContext) -> bool {
+        if ctx.role != ContainerRole::Sidecar {
+            return false;
+        }
+        match self {
+            Self::Include => false,
+            Self::ExcludeAll => true,
+            Self::Exclude(names) => ctx
+                .container_name
+                .as_ref()
+                .is_some_and(|name| names.contains(name)),
+        }
+    }
 }
 
 /// Key for deduplication
+#[cfg(feature = "alerting")]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct DedupKey {
     alert_type: AlertType,
@@ -147,18 +203,51 @@ struct DedupKey {
     pod_name: String,
 }
 
+/// Emission times for an alert
+#[cfg(feature = "alerting")]
+#[derive(Debug, Clone, Copy)]
+struct AlertState {
+    first_fired_at: SystemTime,
+    last_fired_at: SystemTime,
+}
+
+/// An alert that fired within the dedup window
+///
+/// Also used as the on-disk format for the dedup cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveAlert {
+    pub alert_type: AlertType,
+    pub namespace: String,
+    /// Pod name (or "Kind/name" for correlated alerts)
+    pub pod_name: String,
+    /// First emission time (Unix milliseconds)
+    pub first_fired_at_ms: u64,
+    /// Most recent emission time (Unix milliseconds)
+    pub last_fired_at_ms: u64,
+}
+
 /// Alert emitter with deduplication
+#[cfg(feature = "alerting")]
 pub struct Alerter {
     /// Deduplication window
     dedup_window: Duration,
-    /// Recent alerts for deduplication (key -> last emission time)
-    recent_alerts: RwLock<HashMap<DedupKey, Instant>>,
+    /// Recent alerts for deduplication (key -> emission times)
+    recent_alerts: RwLock<HashMap<DedupKey, AlertState>>,
     /// Node name for event source
     node_name: String,
     /// Component name for event source
     component_name: String,
+    /// Path for persisting the dedup cache (optional)
+    persistence_path: Option<PathBuf>,
+    /// Dirty flag for persistence
+    dirty: AtomicBool,
+    /// Sidecars that never raise alerts
+    sidecar_policy: SidecarAlertPolicy,
+    /// Node maintenance status; alerts pause while it is active
+    maintenance: Option<watch::Receiver<MaintenanceStatus>>,
 }
 
+#[cfg(feature = "alerting")]
 impl Alerter {
     /// Create a new alerter with default 15-minute deduplication window
     pub fn new(node_name: String) -> Self {
@@ -167,6 +256,10 @@ impl Alerter {
             recent_alerts: RwLock::new(HashMap::new()),
             node_name,
             component_name: "resource-agent".to_string(),
+            persistence_path: None,
+            dirty: AtomicBool::new(false),
+            sidecar_policy: SidecarAlertPolicy::default(),
+            maintenance: None,
         }
     }
 
@@ -176,22 +269,86 @@ impl Alerter {
         self
     }
 
-    /// Check if an alert should be suppressed due to deduplication
+    /// Keep sidecars matching `policy` out of alerts
+    pub fn with_sidecar_policy(mut self, policy: SidecarAlertPolicy) -> Self {
+        self.sidecar_policy = policy;
+        self
+    }
+
+    /// Pause alerts while the node is in maintenance
+    pub fn with_maintenance(mut self, maintenance: watch::Receiver<MaintenanceStatus>) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Persist the dedup cache to a file, loading any existing state
+    ///
+    /// Should be called after `with_dedup_window` so that expired entries
+    /// are dropped on load.
+    pub fn with_persistence(mut self, persistence_path: PathBuf) -> Self {
+        self.persistence_path = Some(persistence_path.clone());
+
+        if persistence_path.exists() {
+            if let Err(e) = self.load_from_disk(&persistence_path) {
+                warn!(error = %e, "Failed to load persisted alert state, starting fresh");
+            }
+        }
+
+        self
+    }
+
+    /// Check if an alert should be suppressed due to deduplication,
+    /// node maintenance or because the container is an excluded sidecar
     pub fn should_suppress(&self, alert_type: &AlertType, ctx: &AlertContext) -> bool {
+        if self.in_maintenance() {
+            debug!(
+                pod = %ctx.pod_name,
+                alert_type = %alert_type,
+                "Suppressing alert during node maintenance"
+            );
+            return true;
+        }
+        if self.sidecar_policy.excludes(ctx) {
+            debug!(
+                pod = %ctx.pod_name,
+                container = ?ctx.container_name,
+                "Suppressing alert for excluded sidecar"
+            );
+            return true;
+        }
+
         let key = DedupKey {
             alert_type: alert_type.clone(),
             namespace: ctx.namespace.clone(),
             pod_name: ctx.pod_name.clone(),
         };
 
+        self.is_suppressed(&key)
+    }
+
+    /// Whether the node is in maintenance
+    fn in_maintenance(&self) -> bool {
+        self.maintenance
+            .as_ref()
+            .is_some_and(|status| status.borrow().active())
+    }
+
+    /// Check if a dedup key was emitted within the dedup window
+    fn is_suppressed(&self, key: &DedupKey) -> bool {
         let alerts = self.recent_alerts.read().unwrap();
-        if let Some(last_time) = alerts.get(&key) {
-            last_time.elapsed() < self.dedup_window
+        if let Some(state) = alerts.get(key) {
+            self.within_window(state.last_fired_at)
         } else {
             false
         }
     }
 
+    /// Check if a wall-clock time is within the dedup window
+    fn within_window(&self, time: SystemTime) -> bool {
+        // A clock step backwards counts as "just now"
+        time.elapsed().unwrap_or_default() < self.dedup_window
+    }
+
     /// Record that an alert was emitted
     pub fn record_alert(&self, alert_type: &AlertType, ctx: &AlertContext) {
         let key = DedupKey {
@@ -200,11 +357,42 @@ impl Alerter {
             pod_name: ctx.pod_name.clone(),
         };
 
+        self.record_key(key);
+    }
+
+    /// Record emission time for a dedup key
+    fn record_key(&self, key: DedupKey) {
+        let now = SystemTime::now();
         let mut alerts = self.recent_alerts.write().unwrap();
-        alerts.insert(key, Instant::now());
 
         // Clean up old entries
-        alerts.retain(|_, time| time.elapsed() < self.dedup_window);
+        alerts.retain(|_, state| self.within_window(state.last_fired_at));
+
+        alerts
+            .entry(key)
+            .and_modify(|state| state.last_fired_at = now)
+            .or_insert(AlertState {
+                first_fired_at: now,
+                last_fired_at: now,
+            });
+
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Get alerts that fired within the dedup window
+    pub fn active_alerts(&self) -> Vec<ActiveAlert> {
+        let alerts = self.recent_alerts.read().unwrap();
+        alerts
+            .iter()
+            .filter(|(_, state)| self.within_window(state.last_fired_at))
+            .map(|(key, state)| ActiveAlert {
+                alert_type: key.alert_type.clone(),
+                namespace: key.namespace.clone(),
+                pod_name: key.pod_name.clone(),
+                first_fired_at_ms: to_unix_millis(state.first_fired_at),
+                last_fired_at_ms: to_unix_millis(state.last_fired_at),
+            })
+            .collect()
     }
 
     /// Create a Kubernetes event for a memory leak anomaly
@@ -335,6 +523,9 @@ impl Alerter {
         if let Some(ref deployment) = ctx.deployment {
             labels.insert("deployment".to_string(), deployment.clone());
         }
+        if let Some(ref container) = ctx.container_name {
+            labels.insert("container".to_string(), container.clone());
+        }
 
         let mut annotations = HashMap::new();
         annotations.insert(
@@ -396,6 +587,9 @@ impl Alerter {
         if let Some(ref deployment) = ctx.deployment {
             labels.insert("deployment".to_string(), deployment.clone());
         }
+        if let Some(ref container) = ctx.container_name {
+            labels.insert("container".to_string(), container.clone());
+        }
 
         let mut annotations = HashMap::new();
         annotations.insert(
@@ -435,6 +629,133 @@ impl Alerter {
         }
     }
 
+    /// Create a Kubernetes event for a correlated anomaly
+    ///
+    /// The event is attached to the Deployment (or Node) rather than to
+    /// each affected pod.
+    pub fn create_correlated_event(
+        &self,
+        anomaly: &CorrelatedAnomaly,
+        timestamp: &str,
+    ) -> Option<KubernetesEvent> {
+        if self.in_maintenance() {
+            return None;
+        }
+        let (api_version, kind, name, namespace) = match &anomaly.scope {
+            CorrelationScope::Deployment { namespace, name } => {
+                ("apps/v1", "Deployment", name.clone(), namespace.clone())
+            }
+            CorrelationScope::Node { name } => ("v1", "Node", name.clone(), "default".to_string()),
+        };
+
+        let key = DedupKey {
+            alert_type: anomaly.anomaly_type.clone(),
+            namespace: namespace.clone(),
+            pod_name: format!("{}/{}", kind, name),
+        };
+        if self.is_suppressed(&key) {
+            return None;
+        }
+
+        let message = format!(
+            "{} detected on {} pods of {} {}: {}",
+            anomaly.anomaly_type,
+            anomaly.affected_pods.len(),
+            kind,
+            name,
+            anomaly.affected_pods.join(", ")
+        );
+
+        let event = KubernetesEvent {
+            api_version: "v1".to_string(),
+            kind: "Event".to_string(),
+            metadata: EventMetadata {
+                name: format!("{}.{}", name, uuid_v4_simple()),
+                namespace: namespace.clone(),
+            },
+            involved_object: ObjectReference {
+                api_version: api_version.to_string(),
+                kind: kind.to_string(),
+                name,
+                namespace,
+                uid: None,
+            },
+            reason: format!("Correlated{}", anomaly.anomaly_type),
+            message,
+            event_type: "Warning".to_string(),
+            first_timestamp: timestamp.to_string(),
+            last_timestamp: timestamp.to_string(),
+            count: anomaly.affected_pods.len() as u32,
+            source: EventSource {
+                component: self.component_name.clone(),
+                host: Some(self.node_name.clone()),
+            },
+        };
+
+        self.record_key(key);
+        Some(event)
+    }
+
+    /// Create an Alertmanager alert for a correlated anomaly
+    pub fn create_correlated_alertmanager_alert(
+        &self,
+        anomaly: &CorrelatedAnomaly,
+        timestamp: &str,
+    ) -> AlertmanagerAlert {
+        let alertname = match anomaly.anomaly_type {
+            AlertType::MemoryLeak => "CorrelatedMemoryLeak",
+            AlertType::CpuSpike => "CorrelatedCPUSpike",
+            AlertType::OomRisk => "CorrelatedOOMRisk",
+        };
+
+        let mut labels = HashMap::new();
+        labels.insert("alertname".to_string(), alertname.to_string());
+        labels.insert("severity".to_string(), anomaly.severity.to_string());
+        labels.insert("node".to_string(), self.node_name.clone());
+
+        let scope_description = match &anomaly.scope {
+            CorrelationScope::Deployment { namespace, name } => {
+                labels.insert("scope".to_string(), "deployment".to_string());
+                labels.insert("namespace".to_string(), namespace.clone());
+                labels.insert("deployment".to_string(), name.clone());
+                format!("deployment {}/{}", namespace, name)
+            }
+            CorrelationScope::Node { name } => {
+                labels.insert("scope".to_string(), "node".to_string());
+                format!("node {}", name)
+            }
+        };
+
+        let mut annotations = HashMap::new();
+        annotations.insert(
+            "summary".to_string(),
+            format!(
+                "{} on {} pods of {}",
+                anomaly.anomaly_type,
+                anomaly.affected_pods.len(),
+                scope_description
+            ),
+        );
+        annotations.insert("affected_pods".to_string(), anomaly.affected_pods.join(","));
+        annotations.insert(
+            "first_detected_at".to_string(),
+            anomaly.first_detected_at.to_string(),
+        );
+        annotations.insert(
+            "last_detected_at".to_string(),
+            anomaly.last_detected_at.to_string(),
+        );
+
+        AlertmanagerAlert {
+            status: "firing".to_string(),
+            labels,
+            annotations,
+            starts_at: timestamp.to_string(),
+            ends_at: None,
+            generator_url: None,
+        }
+    }
+
     /// Create an Alertmanager payload from multiple alerts
     pub fn create_alertmanager_payload(alerts: Vec<AlertmanagerAlert>) -> AlertmanagerPayload {
         AlertmanagerPayload { alerts }
@@ -443,20 +764,117 @@ impl Alerter {
     /// Clear expired deduplication entries
     pub fn cleanup_dedup_cache(&self) {
         let mut alerts = self.recent_alerts.write().unwrap();
-        alerts.retain(|_, time| time.elapsed() < self.dedup_window);
+        let before = alerts.len();
+        alerts.retain(|_, state| self.within_window(state.last_fired_at));
+        if alerts.len() != before {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Flush the dedup cache to disk if persistence is enabled
+    pub fn flush(&self) -> Result<()> {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if let Some(ref path) = self.persistence_path {
+            self.save_to_disk(path)?;
+            self.dirty.store(false, Ordering::Relaxed);
+            debug!(path = %path.display(), "Alert state flushed to disk");
+        }
+
+        Ok(())
+    }
+
+    /// Save active alerts to disk
+    fn save_to_disk(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+
+        let json =
+            serde_json::to_vec(&self.active_alerts()).context("Failed to serialize alert state")?;
+
+        // Write atomically using temp file
+        let temp_path = path.with_extension("tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)
+            .with_context(|| format!("Failed to create temp file {:?}", temp_path))?;
+
+        file.write_all(&json)
+            .context("Failed to write alert state")?;
+        file.sync_all().context("Failed to sync alert state file")?;
+
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
+
+        Ok(())
+    }
+
+    /// Load active alerts from disk, dropping entries outside the window
+    fn load_from_disk(&self, path: &Path) -> Result<()> {
+        let mut file = File::open(path)
+            .with_context(|| format!("Failed to open alert state file {:?}", path))?;
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .context("Failed to read alert state file")?;
+
+        let loaded: Vec<ActiveAlert> =
+            serde_json::from_slice(&data).context("Failed to deserialize alert state")?;
+
+        let mut alerts = self.recent_alerts.write().unwrap();
+        for alert in loaded {
+            let state = AlertState {
+                first_fired_at: from_unix_millis(alert.first_fired_at_ms),
+                last_fired_at: from_unix_millis(alert.last_fired_at_ms),
+            };
+            if !self.within_window(state.last_fired_at) {
+                continue;
+            }
+            alerts.insert(
+                DedupKey {
+                    alert_type: alert.alert_type,
+                    namespace: alert.namespace,
+                    pod_name: alert.pod_name,
+                },
+                state,
+            );
+        }
+
+        info!(path = %path.display(), active = alerts.len(), "Loaded alert state from disk");
+        Ok(())
     }
 }
 
+/// Convert a wall-clock time to Unix milliseconds
+#[cfg(feature = "alerting")]
+fn to_unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Convert Unix milliseconds to a wall-clock time
+#[cfg(feature = "alerting")]
+fn from_unix_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
+
 /// Generate a simple UUID-like string for event naming
+#[cfg(feature = "alerting")]
 fn uuid_v4_simple() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default();
     format!("{:x}{:x}", now.as_secs(), now.subsec_nanos())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alerting"))]
 mod tests {
     use super::*;
     use std::thread::sleep;
@@ -469,6 +887,9 @@ mod tests {
             namespace: "default".to_string(),
             node_name: "node-1".to_string(),
             deployment: Some("test-deployment".to_string()),
+            container_name: Some("app".to_string()),
+            role: ContainerRole::Main,
+            pod_labels: HashMap::new(),
         }
     }
 
@@ -484,6 +905,7 @@ mod tests {
             confidence: 0.9,
             current_memory_bytes: 100_000_000,
             samples_analyzed: 60,
+            windows_secs: Vec::new(),
         };
 
         // First alert should succeed
@@ -512,6 +934,7 @@ mod tests {
             confidence: 0.85,
             current_memory_bytes: 500_000_000,
             samples_analyzed: 60,
+            windows_secs: Vec::new(),
         };
 
         let event = alerter
@@ -560,6 +983,7 @@ mod tests {
             confidence: 0.9,
             current_memory_bytes: 100_000_000,
             samples_analyzed: 60,
+            windows_secs: Vec::new(),
         };
 
         let spike = SpikeAnomaly {
@@ -577,4 +1001,123 @@ mod tests {
         assert!(event1.is_some());
         assert!(event2.is_some());
     }
+
+    #[test]
+    fn test_correlated_alert_and_event() {
+        let alerter = Alerter::new("node-1".to_string());
+        let anomaly = CorrelatedAnomaly {
+            scope: CorrelationScope::Deployment {
+                namespace: "default".to_string(),
+                name: "web".to_string(),
+            },
+            anomaly_type: AlertType::CpuSpike,
+            severity: AlertSeverity::Warning,
+            affected_pods: vec!["default/web-0".to_string(), "default/web-1".to_string()],
+            first_detected_at: 1000,
+            last_detected_at: 1005,
+        };
+
+        let alert = alerter.create_correlated_alertmanager_alert(&anomaly, "2024-01-01T00:00:00Z");
+        assert_eq!(alert.labels.get("alertname").unwrap(), "CorrelatedCPUSpike");
+        assert_eq!(alert.labels.get("deployment").unwrap(), "web");
+        assert_eq!(
+            alert.annotations.get("affected_pods").unwrap(),
+            "default/web-0,default/web-1"
+        );
+
+        let event = alerter
+            .create_correlated_event(&anomaly, "2024-01-01T00:00:00Z")
+            .unwrap();
+        assert_eq!(event.involved_object.kind, "Deployment");
+        assert_eq!(event.count, 2);
+
+        // Same deployment-level event is deduplicated
+        assert!(alerter
+            .create_correlated_event(&anomaly, "2024-01-01T00:00:01Z")
+            .is_none());
+    }
+
+    #[test]
+    fn test_sidecar_policy() {
+        let leak = LeakAnomaly {
+            slope_bytes_per_sec: 10000.0,
+            projected_oom_time: 0,
+            confidence: 0.9,
+            current_memory_bytes: 100_000_000,
+            samples_analyzed: 60,
+            windows_secs: Vec::new(),
+        };
+        let sidecar = AlertContext {
+            container_name: Some("istio-proxy".to_string()),
+            role: ContainerRole::Sidecar,
+            ..test_context()
+        };
+
+        // Sidecars alert by default
+        let alerter = Alerter::new("node-1".to_string());
+        assert!(alerter
+            .create_leak_event(&leak, &sidecar, "2024-01-01T00:00:00Z")
+            .is_some());
+
+        let alerter = Alerter::new("node-1".to_string())
+            .with_sidecar_policy(SidecarAlertPolicy::well_known());
+        assert!(alerter.should_suppress(&AlertType::MemoryLeak, &sidecar));
+        assert!(!alerter.should_suppress(&AlertType::MemoryLeak, &test_context()));
+
+        // Only containers detected as sidecars are matched by name
+        let custom = AlertContext {
+            container_name: Some("log-shipper".to_string()),
+            ..sidecar.clone()
+        };
+        assert!(!alerter.should_suppress(&AlertType::MemoryLeak, &custom));
+        let main_named_envoy = AlertContext {
+            container_name: Some("envoy".to_string()),
+            ..test_context()
+        };
+        assert!(!alerter.should_suppress(&AlertType::MemoryLeak, &main_named_envoy));
+
+        let alerter =
+            Alerter::new("node-1".to_string()).with_sidecar_policy(SidecarAlertPolicy::ExcludeAll);
+        assert!(alerter.should_suppress(&AlertType::CpuSpike, &custom));
+    }
+
+    #[test]
+    fn test_suppressed_during_maintenance() {
+        let mode = crate::maintenance::MaintenanceMode::new();
+        let alerter = Alerter::new("node-1".to_string()).with_maintenance(mode.subscribe());
+        let ctx = test_context();
+        assert!(!alerter.should_suppress(&AlertType::OomRisk, &ctx));
+
+        mode.set_cordoned(true);
+        assert!(alerter.should_suppress(&AlertType::OomRisk, &ctx));
+
+        mode.set_cordoned(false);
+        assert!(!alerter.should_suppress(&AlertType::OomRisk, &ctx));
+    }
+
+    #[test]
+    fn test_dedup_state_survives_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("alerts.json");
+        let ctx = test_context();
+
+        {
+            let alerter = Alerter::new("node-1".to_string()).with_persistence(path.clone());
+            alerter.record_alert(&AlertType::MemoryLeak, &ctx);
+            assert_eq!(alerter.active_alerts().len(), 1);
+            alerter.flush().unwrap();
+        }
+
+        let alerter = Alerter::new("node-1".to_string()).with_persistence(path.clone());
+        assert!(alerter.should_suppress(&AlertType::MemoryLeak, &ctx));
+        assert!(!alerter.should_suppress(&AlertType::CpuSpike, &ctx));
+
+        // Entries outside the window are dropped on load
+        sleep(Duration::from_millis(20));
+        let alerter = Alerter::new("node-1".to_string())
+            .with_dedup_window(Duration::from_millis(10))
+            .with_persistence(path);
+        assert!(alerter.active_alerts().is_empty());
+        assert!(!alerter.should_suppress(&AlertType::MemoryLeak, &ctx));
+    }
 }
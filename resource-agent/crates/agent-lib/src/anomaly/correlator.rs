@@ -0,0 +1,343 @@
+//! Anomaly correlation
+//!
+//! Groups anomalies that fire at roughly the same time across containers of
+//! the same deployment (or across the whole node) into a single aggregated
+//! event, so that 10 pods spiking at once produce one alert instead of ten.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{AlertSeverity, AlertType, AnomalyRecord};
+
+/// Default correlation window (1 minute)
+const DEFAULT_WINDOW_SECS: u64 = 60;
+
+/// Default number of distinct pods in a deployment before aggregating
+const DEFAULT_MIN_DEPLOYMENT_PODS: usize = 3;
+
+/// Default number of distinct pods on a node before aggregating
+const DEFAULT_MIN_NODE_PODS: usize = 5;
+
+/// Configuration for anomaly correlation
+#[derive(Debug, Clone)]
+pub struct CorrelationConfig {
+    /// Anomalies detected within this window are considered simultaneous
+    pub window: Duration,
+    /// Minimum distinct pods of one deployment to emit a deployment-level event
+    pub min_deployment_pods: usize,
+    /// Minimum distinct pods across the node to emit a node-level event
+    pub min_node_pods: usize,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(DEFAULT_WINDOW_SECS),
+            min_deployment_pods: DEFAULT_MIN_DEPLOYMENT_PODS,
+            min_node_pods: DEFAULT_MIN_NODE_PODS,
+        }
+    }
+}
+
+/// Scope of a correlated anomaly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CorrelationScope {
+    /// Several pods of the same deployment
+    Deployment { namespace: String, name: String },
+    /// Many pods across the node
+    Node { name: String },
+}
+
+/// An aggregated anomaly affecting several pods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelatedAnomaly {
+    pub scope: CorrelationScope,
+    pub anomaly_type: AlertType,
+    /// Highest severity among the grouped anomalies
+    pub severity: AlertSeverity,
+    /// Affected pods as "namespace/pod", sorted
+    pub affected_pods: Vec<String>,
+    /// Earliest detection time in the group (Unix seconds)
+    pub first_detected_at: i64,
+    /// Latest detection time in the group (Unix seconds)
+    pub last_detected_at: i64,
+}
+
+impl CorrelatedAnomaly {
+    fn from_records(scope: CorrelationScope, records: &[AnomalyRecord]) -> Self {
+        let pods: BTreeSet<String> = records
+            .iter()
+            .map(|r| format!("{}/{}", r.namespace, r.pod_name))
+            .collect();
+
+        let severity = if records
+            .iter()
+            .any(|r| r.severity == AlertSeverity::Critical)
+        {
+            AlertSeverity::Critical
+        } else {
+            AlertSeverity::Warning
+        };
+
+        Self {
+            scope,
+            anomaly_type: records[0].anomaly_type.clone(),
+            severity,
+            affected_pods: pods.into_iter().collect(),
+            first_detected_at: records.iter().map(|r| r.detected_at).min().unwrap_or(0),
+            last_detected_at: records.iter().map(|r| r.detected_at).max().unwrap_or(0),
+        }
+    }
+}
+
+/// Result of a correlation pass
+#[derive(Debug, Clone, Default)]
+pub struct CorrelationOutcome {
+    /// Aggregated events replacing their individual anomalies
+    pub correlated: Vec<CorrelatedAnomaly>,
+    /// Anomalies that did not correlate and should be alerted on individually
+    pub individual: Vec<AnomalyRecord>,
+}
+
+impl CorrelationOutcome {
+    /// Check if the pass produced nothing
+    pub fn is_empty(&self) -> bool {
+        self.correlated.is_empty() && self.individual.is_empty()
+    }
+}
+
+/// Groups simultaneous anomalies into deployment- or node-level events
+pub struct AnomalyCorrelator {
+    config: CorrelationConfig,
+    node_name: String,
+    /// Anomalies waiting for their correlation window to close
+    pending: Vec<AnomalyRecord>,
+}
+
+impl AnomalyCorrelator {
+    /// Create a new correlator with default configuration
+    pub fn new(node_name: impl Into<String>) -> Self {
+        Self::with_config(node_name, CorrelationConfig::default())
+    }
+
+    /// Create a new correlator with custom configuration
+    pub fn with_config(node_name: impl Into<String>, config: CorrelationConfig) -> Self {
+        Self {
+            config,
+            node_name: node_name.into(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Queue an anomaly for correlation
+    pub fn add(&mut self, record: AnomalyRecord) {
+        self.pending.push(record);
+    }
+
+    /// Number of anomalies waiting for their window to close
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Correlate every window that has closed by `now` (Unix seconds)
+    pub fn correlate(&mut self, now: i64) -> CorrelationOutcome {
+        let window = self.config.window.as_secs() as i64;
+        let mut outcome = CorrelationOutcome::default();
+
+        self.pending.sort_by_key(|r| r.detected_at);
+
+        while let Some(window_start) = self.pending.first().map(|r| r.detected_at) {
+            if window_start + window > now {
+                break;
+            }
+
+            let split = self
+                .pending
+                .iter()
+                .position(|r| r.detected_at >= window_start + window)
+                .unwrap_or(self.pending.len());
+            let batch: Vec<AnomalyRecord> = self.pending.drain(..split).collect();
+
+            self.correlate_batch(batch, &mut outcome);
+        }
+
+        outcome
+    }
+
+    /// Correlate every pending anomaly regardless of window (e.g. on shutdown)
+    pub fn flush(&mut self) -> CorrelationOutcome {
+        let latest = self.pending.iter().map(|r| r.detected_at).max();
+        match latest {
+            Some(ts) => self.correlate(ts + self.config.window.as_secs() as i64),
+            None => CorrelationOutcome::default(),
+        }
+    }
+
+    /// Correlate a single window worth of anomalies
+    fn correlate_batch(&self, batch: Vec<AnomalyRecord>, outcome: &mut CorrelationOutcome) {
+        // Group by (type, namespace, deployment)
+        let mut by_deployment: HashMap<(AlertType, String, String), Vec<AnomalyRecord>> =
+            HashMap::new();
+        let mut leftovers = Vec::new();
+
+        for record in batch {
+            match record.deployment.clone() {
+                Some(deployment) => by_deployment
+                    .entry((
+                        record.anomaly_type.clone(),
+                        record.namespace.clone(),
+                        deployment,
+                    ))
+                    .or_default()
+                    .push(record),
+                None => leftovers.push(record),
+            }
+        }
+
+        for ((_, namespace, deployment), records) in by_deployment {
+            if distinct_pods(&records) >= self.config.min_deployment_pods {
+                outcome.correlated.push(CorrelatedAnomaly::from_records(
+                    CorrelationScope::Deployment {
+                        namespace,
+                        name: deployment,
+                    },
+                    &records,
+                ));
+            } else {
+                leftovers.extend(records);
+            }
+        }
+
+        // Whatever did not group per deployment may still be a node-wide event
+        let mut by_type: HashMap<AlertType, Vec<AnomalyRecord>> = HashMap::new();
+        for record in leftovers {
+            by_type
+                .entry(record.anomaly_type.clone())
+                .or_default()
+                .push(record);
+        }
+
+        for (_, records) in by_type {
+            if distinct_pods(&records) >= self.config.min_node_pods {
+                outcome.correlated.push(CorrelatedAnomaly::from_records(
+                    CorrelationScope::Node {
+                        name: self.node_name.clone(),
+                    },
+                    &records,
+                ));
+            } else {
+                outcome.individual.extend(records);
+            }
+        }
+    }
+}
+
+/// Count distinct pods in a group of anomalies
+fn distinct_pods(records: &[AnomalyRecord]) -> usize {
+    records
+        .iter()
+        .map(|r| (&r.namespace, &r.pod_name))
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(pod: &str, deployment: Option<&str>, detected_at: i64) -> AnomalyRecord {
+        AnomalyRecord {
+            container_id: format!("{}-container", pod),
+            pod_name: pod.to_string(),
+            namespace: "default".to_string(),
+            deployment: deployment.map(|d| d.to_string()),
+            anomaly_type: AlertType::CpuSpike,
+            severity: AlertSeverity::Warning,
+            message: "CPU spike detected".to_string(),
+            detected_at,
+        }
+    }
+
+    #[test]
+    fn test_deployment_level_aggregation() {
+        let mut correlator = AnomalyCorrelator::new("node-1");
+        for i in 0..10 {
+            correlator.add(record(&format!("web-{}", i), Some("web"), 1000 + i));
+        }
+
+        let outcome = correlator.correlate(1100);
+        assert_eq!(outcome.correlated.len(), 1);
+        assert!(outcome.individual.is_empty());
+
+        let event = &outcome.correlated[0];
+        assert_eq!(event.affected_pods.len(), 10);
+        assert_eq!(
+            event.scope,
+            CorrelationScope::Deployment {
+                namespace: "default".to_string(),
+                name: "web".to_string()
+            }
+        );
+        assert_eq!(event.first_detected_at, 1000);
+        assert_eq!(event.last_detected_at, 1009);
+    }
+
+    #[test]
+    fn test_below_threshold_stays_individual() {
+        let mut correlator = AnomalyCorrelator::new("node-1");
+        correlator.add(record("web-0", Some("web"), 1000));
+        correlator.add(record("web-1", Some("web"), 1001));
+
+        let outcome = correlator.correlate(1100);
+        assert!(outcome.correlated.is_empty());
+        assert_eq!(outcome.individual.len(), 2);
+    }
+
+    #[test]
+    fn test_node_level_aggregation() {
+        let mut correlator = AnomalyCorrelator::new("node-1");
+        for i in 0..6 {
+            let deployment = format!("app-{}", i);
+            correlator.add(record(&format!("pod-{}", i), Some(&deployment), 1000));
+        }
+
+        let outcome = correlator.correlate(1100);
+        assert_eq!(outcome.correlated.len(), 1);
+        assert_eq!(
+            outcome.correlated[0].scope,
+            CorrelationScope::Node {
+                name: "node-1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_window_is_held_back() {
+        let mut correlator = AnomalyCorrelator::new("node-1");
+        correlator.add(record("web-0", Some("web"), 1000));
+
+        assert!(correlator.correlate(1030).is_empty());
+        assert_eq!(correlator.pending_count(), 1);
+
+        assert_eq!(correlator.correlate(1060).individual.len(), 1);
+        assert_eq!(correlator.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_critical_severity_wins() {
+        let mut correlator = AnomalyCorrelator::new("node-1");
+        for i in 0..3 {
+            let mut r = record(&format!("web-{}", i), Some("web"), 1000);
+            if i == 1 {
+                r.severity = AlertSeverity::Critical;
+            }
+            correlator.add(r);
+        }
+
+        let outcome = correlator.flush();
+        assert_eq!(outcome.correlated[0].severity, AlertSeverity::Critical);
+    }
+}
@@ -2,7 +2,15 @@
 //!
 //! Detects memory leaks by calculating linear regression slope on memory samples
 //! and identifying monotonically increasing patterns over a configurable window.
+//! Samples are aligned to a fixed time grid first, see `Resampler`.
+//!
+//! A deploy-time memory ramp looks like a leak over a short window, so the
+//! pipeline looks for leaks over several windows, from half an hour to a
+//! day, and reports one only when at least two of them agree.
 
+use crate::predictor::Resampler;
+use serde::Serialize;
+use std::borrow::Cow;
 use std::time::Duration;
 
 /// Minimum samples required for leak detection
@@ -11,6 +19,19 @@ const MIN_SAMPLES_FOR_DETECTION: usize = 10;
 /// Monotonicity threshold - percentage of samples that must be increasing
 const MONOTONICITY_THRESHOLD: f64 = 0.95;
 
+/// Default windows leaks are looked for over
+pub const DEFAULT_LEAK_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(6 * 60 * 60),
+    Duration::from_secs(24 * 60 * 60),
+];
+
+/// Windows that must agree on a leak before it is reported
+pub const MIN_AGREEING_WINDOWS: usize = 2;
+
+/// Share of a window the samples must span for it to count
+const MIN_WINDOW_COVERAGE: f64 = 0.9;
+
 /// Detects memory leaks via linear regression on memory samples
 pub struct LeakDetector {
     /// Time window for analysis (default: 1 hour)
@@ -19,6 +40,8 @@ pub struct LeakDetector {
     pub slope_threshold: f64,
     /// Memory limit for OOM projection (optional)
     pub memory_limit: Option<u64>,
+    /// Aligns samples to the collection grid before analysis
+    pub resampler: Resampler,
 }
 
 impl LeakDetector {
@@ -28,6 +51,7 @@ impl LeakDetector {
             window_size,
             slope_threshold,
             memory_limit: None,
+            resampler: Resampler::default(),
         }
     }
 
@@ -37,10 +61,20 @@ impl LeakDetector {
         self
     }
 
+    /// Align samples with `resampler` before analysis
+    pub fn with_resampler(mut self, resampler: Resampler) -> Self {
+        self.resampler = resampler;
+        self
+    }
+
     /// Detect memory leak from samples
     ///
     /// # Arguments
-    /// * `samples` - Slice of (timestamp_secs, memory_bytes) tuples, sorted by timestamp
+    /// * `samples` - Slice of (timestamp_secs, memory_bytes) tuples, oldest first.
+    ///   Samples out of order are sorted, and of samples sharing a timestamp the
+    ///   last one is kept, so wall-clock steps do not skew the slope. Samples
+    ///   are then aligned to the resampler's grid, so that periods sampled
+    ///   more often do not weigh more.
     ///
     /// # Returns
     /// * `Some(LeakAnomaly)` if a leak is detected
@@ -50,8 +84,10 @@ impl LeakDetector {
             return None;
         }
 
+        let (samples, _) = self.resampler.resample_pairs(&ordered(samples));
+
         // Filter samples within window
-        let window_samples = self.filter_window(samples);
+        let window_samples = self.filter_window(&samples);
         if window_samples.len() < MIN_SAMPLES_FOR_DETECTION {
             return None;
         }
@@ -83,6 +119,7 @@ impl LeakDetector {
             confidence,
             current_memory_bytes: window_samples.last().map(|(_, m)| *m).unwrap_or(0),
             samples_analyzed: window_samples.len(),
+            windows_secs: Vec::new(),
         })
     }
 
@@ -208,18 +245,99 @@ impl LeakDetector {
     }
 }
 
+/// Samples sorted by timestamp, keeping the last of samples sharing one
+fn ordered(samples: &[(i64, u64)]) -> Cow<'_, [(i64, u64)]> {
+    if samples.windows(2).all(|w| w[0].0 < w[1].0) {
+        return Cow::Borrowed(samples);
+    }
+    let mut sorted: Vec<(usize, (i64, u64))> = samples.iter().copied().enumerate().collect();
+    sorted.sort_by_key(|&(i, (ts, _))| (ts, std::cmp::Reverse(i)));
+    sorted.dedup_by_key(|(_, (ts, _))| *ts);
+    Cow::Owned(sorted.into_iter().map(|(_, sample)| sample).collect())
+}
+
 impl Default for LeakDetector {
     fn default() -> Self {
         Self {
             window_size: Duration::from_secs(3600), // 1 hour
             slope_threshold: 1024.0,                // 1 KB/sec minimum
             memory_limit: None,
+            resampler: Resampler::default(),
+        }
+    }
+}
+
+/// Detects memory leaks that several windows agree on
+///
+/// Only windows the samples span count. A ramp after a deploy is found over
+/// the short window alone, and once the long windows are covered it has
+/// flattened out of the short one, so it is never reported.
+pub struct MultiWindowLeakDetector {
+    /// Windows leaks are looked for over
+    pub windows: Vec<Duration>,
+    /// Windows that must agree, at most all of them
+    pub min_agreeing: usize,
+    /// Minimum slope (bytes/sec) to consider a leak
+    pub slope_threshold: f64,
+    /// Memory limit for OOM projection (optional)
+    pub memory_limit: Option<u64>,
+}
+
+impl MultiWindowLeakDetector {
+    pub fn new(windows: Vec<Duration>, slope_threshold: f64) -> Self {
+        Self {
+            windows,
+            min_agreeing: MIN_AGREEING_WINDOWS,
+            slope_threshold,
+            memory_limit: None,
+        }
+    }
+
+    /// Set memory limit for OOM time projection
+    pub fn with_memory_limit(mut self, limit: u64) -> Self {
+        self.memory_limit = Some(limit);
+        self
+    }
+
+    /// Detect a memory leak from `(timestamp_secs, memory_bytes)` samples
+    ///
+    /// The leak reported is the one found over the longest agreeing window,
+    /// whose slope projects the OOM time, with the agreeing windows listed.
+    pub fn detect(&self, samples: &[(i64, u64)]) -> Option<LeakAnomaly> {
+        let samples = ordered(samples);
+        let (&(oldest, _), &(newest, _)) = (samples.first()?, samples.last()?);
+        let span = (newest - oldest) as f64;
+
+        let mut windows = self.windows.clone();
+        windows.sort();
+        let mut agreeing = Vec::new();
+        let mut longest = None;
+        for window in windows {
+            if span < window.as_secs_f64() * MIN_WINDOW_COVERAGE {
+                continue;
+            }
+            let start = samples.partition_point(|&(ts, _)| ts < newest - window.as_secs() as i64);
+            let mut detector = LeakDetector::new(window, self.slope_threshold);
+            detector.memory_limit = self.memory_limit;
+            if let Some(leak) = detector.detect(&samples[start..]) {
+                agreeing.push(window.as_secs());
+                longest = Some(leak);
+            }
+        }
+
+        let needed = self.min_agreeing.clamp(1, self.windows.len().max(1));
+        if agreeing.len() < needed {
+            return None;
         }
+        longest.map(|leak| LeakAnomaly {
+            windows_secs: agreeing,
+            ..leak
+        })
     }
 }
 
 /// Memory leak anomaly details
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LeakAnomaly {
     /// Rate of memory increase in bytes per second
     pub slope_bytes_per_sec: f64,
@@ -231,6 +349,10 @@ pub struct LeakAnomaly {
     pub current_memory_bytes: u64,
     /// Number of samples used in analysis
     pub samples_analyzed: usize,
+    /// Windows that agreed on the leak, in seconds, when several were
+    /// checked
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub windows_secs: Vec<u64>,
 }
 
 impl LeakAnomaly {
@@ -314,4 +436,82 @@ mod tests {
 
         assert!(detector.detect(&samples).is_none());
     }
+
+    #[test]
+    fn test_leak_with_clock_step() {
+        let detector = LeakDetector::new(Duration::from_secs(3600), 1000.0);
+        let mut samples: Vec<(i64, u64)> = (0..60)
+            .map(|i| (i * 60, 100_000_000 + (i as u64 * 600_000)))
+            .collect();
+        let expected = detector.detect(&samples).unwrap();
+
+        // The wall clock stepped back: the last ten samples repeat timestamps
+        // already seen, arriving after the ones they duplicate
+        let stepped: Vec<(i64, u64)> = samples[40..50].to_vec();
+        samples.truncate(50);
+        samples.extend(stepped);
+
+        let anomaly = detector.detect(&samples).unwrap();
+        assert_eq!(anomaly.samples_analyzed, 50);
+        assert!((anomaly.slope_bytes_per_sec - expected.slope_bytes_per_sec).abs() < 1.0);
+    }
+    #[test]
+    fn test_irregular_spacing_unbiased() {
+        let detector = LeakDetector::new(Duration::from_secs(3600), 1000.0);
+        let unfilled = LeakDetector::new(Duration::from_secs(3600), 1000.0)
+            .with_resampler(Resampler::new(Duration::from_secs(10), Duration::ZERO));
+        // Leaking for 40 minutes, then flat for 20
+        let memory = |t: i64| (t, 100_000_000 + 5_000 * t.min(2400) as u64);
+        let regular: Vec<(i64, u64)> = (0..3600).step_by(10).map(memory).collect();
+        let expected = detector.detect(&regular).unwrap().slope_bytes_per_sec;
+
+        // Collected every 30s in degraded mode while leaking, so the flat
+        // stretch has most of the samples
+        let irregular: Vec<(i64, u64)> = (0..2400)
+            .step_by(30)
+            .chain((2400..3600).step_by(10))
+            .map(memory)
+            .collect();
+        let anomaly = detector.detect(&irregular).unwrap();
+        assert_eq!(anomaly.samples_analyzed, 360);
+        assert!((anomaly.slope_bytes_per_sec - expected).abs() < expected * 0.01);
+
+        let biased = unfilled.detect(&irregular).unwrap().slope_bytes_per_sec;
+        assert!(expected - biased > expected * 0.05);
+    }
+
+    #[test]
+    fn test_windows_must_agree() {
+        let detector = MultiWindowLeakDetector::new(DEFAULT_LEAK_WINDOWS.to_vec(), 1000.0)
+            .with_memory_limit(1 << 30);
+        let growing = |minutes: i64| -> Vec<(i64, u64)> {
+            (0..minutes)
+                .map(|i| (i * 60, 100_000_000 + i as u64 * 600_000))
+                .collect()
+        };
+
+        // Forty minutes of growth after a deploy: only the short window is
+        // covered, so a ramp is not told apart from a leak yet
+        let ramp = growing(40);
+        assert!(LeakDetector::new(DEFAULT_LEAK_WINDOWS[0], 1000.0)
+            .detect(&ramp)
+            .is_some());
+        assert!(detector.detect(&ramp).is_none());
+
+        // Still growing seven hours later: both covered windows agree
+        let leak = detector.detect(&growing(7 * 60)).unwrap();
+        assert_eq!(leak.windows_secs, vec![30 * 60, 6 * 60 * 60]);
+        assert_eq!(leak.samples_analyzed, 6 * 60 + 1);
+        assert!(leak.projected_oom_time > 0);
+    }
+
+    #[test]
+    fn test_deploy_ramp_not_reported() {
+        let detector = MultiWindowLeakDetector::new(DEFAULT_LEAK_WINDOWS.to_vec(), 1000.0);
+        // Ramping up for 20 minutes after a deploy, then flat for 7 hours
+        let samples: Vec<(i64, u64)> = (0..7 * 60 + 20)
+            .map(|i| (i * 60, 100_000_000 + i.min(20) as u64 * 10_000_000))
+            .collect();
+        assert!(detector.detect(&samples).is_none());
+    }
 }
@@ -3,15 +3,46 @@
 //! This module provides detection for:
 //! - Memory leaks (monotonically increasing memory over time)
 //! - CPU spikes (values exceeding standard deviation thresholds)
-//! - Alert emission to Kubernetes and Alertmanager
+//! - CPU throttling and network spikes, when enabled
+//! - A pipeline composing these detectors from configuration
+//! - Correlation of simultaneous anomalies into deployment/node events
+//! - Alert emission to Kubernetes and Alertmanager (`alerting` feature)
+//! - Webhook delivery of alerts for standalone hosts (`alerting` feature)
+//! - Routing of alerts to per-team destinations by namespace and pod labels
+//! - Local anomaly history for the agent API
+//! - Anomalies per container shared with the prediction scheduler
 
 mod alerter;
+mod correlator;
 mod leak_detector;
+mod pipeline;
+mod routing;
 mod spike_detector;
+mod state;
+mod store;
+#[cfg(feature = "alerting")]
+mod webhook;
 
+#[cfg(feature = "alerting")]
+pub use alerter::Alerter;
 pub use alerter::{
-    AlertContext, AlertSeverity, AlertType, Alerter, AlertmanagerAlert, AlertmanagerPayload,
-    EventMetadata, EventSource, KubernetesEvent, ObjectReference,
+    ActiveAlert, AlertContext, AlertSeverity, AlertType, AlertmanagerAlert, AlertmanagerPayload,
+    EventMetadata, EventSource, KubernetesEvent, ObjectReference, SidecarAlertPolicy,
 };
-pub use leak_detector::{LeakAnomaly, LeakDetector};
+pub use correlator::{
+    AnomalyCorrelator, CorrelatedAnomaly, CorrelationConfig, CorrelationOutcome, CorrelationScope,
+};
+pub use leak_detector::{
+    LeakAnomaly, LeakDetector, MultiWindowLeakDetector, DEFAULT_LEAK_WINDOWS, MIN_AGREEING_WINDOWS,
+};
+pub use pipeline::{
+    AnomalyPipeline, DetectedAnomaly, DetectorConfig, DetectorKind, PipelineConfig, ThrottleAnomaly,
+};
+#[cfg(feature = "alerting")]
+pub use routing::AlertRouter;
+pub use routing::{AlertDestination, AlertRoute, SlackDestination};
 pub use spike_detector::{RollingStats, SpikeAnomaly, SpikeDetector, SpikeSeverity};
+pub use state::AnomalyState;
+pub use store::{AnomalyRecord, AnomalyStore, AnomalyStoreConfig};
+#[cfg(feature = "alerting")]
+pub use webhook::WebhookSink;
@@ -0,0 +1,581 @@
+//! Configurable anomaly detection pipeline
+//!
+//! Composes the enabled detectors from configuration, with thresholds that
+//! can be overridden per namespace, and keeps the per-container history they
+//! need. An anomaly is reported once when it starts rather than on every
+//! sample while it lasts.
+
+use super::{
+    AnomalyState, LeakAnomaly, LeakDetector, MultiWindowLeakDetector, RollingStats, SpikeAnomaly,
+    SpikeDetector, DEFAULT_LEAK_WINDOWS,
+};
+use crate::models::ContainerMetrics;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+/// Detectors the pipeline can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectorKind {
+    /// Steadily growing working set (threshold in bytes/sec)
+    MemoryLeak,
+    /// CPU usage above its rolling statistics (threshold in standard deviations)
+    CpuSpike,
+    /// Sustained CFS throttling (threshold in throttled periods/sec)
+    CpuThrottling,
+    /// Network traffic above its rolling statistics (threshold in standard
+    /// deviations)
+    NetworkSpike,
+}
+
+impl DetectorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DetectorKind::MemoryLeak => "memory_leak",
+            DetectorKind::CpuSpike => "cpu_spike",
+            DetectorKind::CpuThrottling => "cpu_throttling",
+            DetectorKind::NetworkSpike => "network_spike",
+        }
+    }
+
+    fn default_threshold(self) -> f64 {
+        match self {
+            DetectorKind::MemoryLeak => LeakDetector::default().slope_threshold,
+            DetectorKind::CpuSpike | DetectorKind::NetworkSpike => {
+                SpikeDetector::default().std_dev_threshold
+            }
+            // Half of the 100ms CFS periods in a second
+            DetectorKind::CpuThrottling => 5.0,
+        }
+    }
+
+    fn default_window(self) -> Duration {
+        match self {
+            DetectorKind::MemoryLeak => LeakDetector::default().window_size,
+            _ => SpikeDetector::default().window_size,
+        }
+    }
+
+    /// Detectors that run unless disabled
+    fn enabled_by_default(self) -> bool {
+        matches!(self, DetectorKind::MemoryLeak | DetectorKind::CpuSpike)
+    }
+}
+
+/// Configuration of one detector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectorConfig {
+    pub kind: DetectorKind,
+    pub enabled: bool,
+    /// Threshold in the unit of the detector's kind
+    pub threshold: f64,
+    /// History the detector looks at (unused by throttling)
+    pub window_secs: u64,
+    /// Windows a memory leak is looked for over, of which at least two
+    /// must agree; `window_secs` alone when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows_secs: Vec<u64>,
+    /// Thresholds replacing `threshold` in particular namespaces
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub namespace_thresholds: BTreeMap<String, f64>,
+}
+
+impl DetectorConfig {
+    /// Detector with its kind's defaults
+    pub fn new(kind: DetectorKind) -> Self {
+        Self {
+            kind,
+            enabled: kind.enabled_by_default(),
+            threshold: kind.default_threshold(),
+            window_secs: kind.default_window().as_secs(),
+            windows_secs: match kind {
+                DetectorKind::MemoryLeak => DEFAULT_LEAK_WINDOWS.map(|w| w.as_secs()).to_vec(),
+                _ => Vec::new(),
+            },
+            namespace_thresholds: BTreeMap::new(),
+        }
+    }
+
+    /// Threshold applied to containers in `namespace`
+    pub fn threshold_for(&self, namespace: &str) -> f64 {
+        self.namespace_thresholds
+            .get(namespace)
+            .copied()
+            .unwrap_or(self.threshold)
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+
+    /// Windows the detector looks at, `window` alone unless several are set
+    pub fn windows(&self) -> Vec<Duration> {
+        if self.windows_secs.is_empty() {
+            return vec![self.window()];
+        }
+        self.windows_secs
+            .iter()
+            .map(|&secs| Duration::from_secs(secs))
+            .collect()
+    }
+
+    /// History the detector needs, its longest window
+    fn retention(&self) -> Duration {
+        self.windows().into_iter().max().unwrap_or_default()
+    }
+}
+
+/// Detectors making up a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    pub detectors: Vec<DetectorConfig>,
+}
+
+impl Default for PipelineConfig {
+    /// Every detector with its defaults; leak and spike detection enabled
+    fn default() -> Self {
+        Self {
+            detectors: [
+                DetectorKind::MemoryLeak,
+                DetectorKind::CpuSpike,
+                DetectorKind::CpuThrottling,
+                DetectorKind::NetworkSpike,
+            ]
+            .into_iter()
+            .map(DetectorConfig::new)
+            .collect(),
+        }
+    }
+}
+
+impl PipelineConfig {
+    pub fn detector(&self, kind: DetectorKind) -> Option<&DetectorConfig> {
+        self.detectors.iter().find(|d| d.kind == kind)
+    }
+
+    /// Configuration of a detector, added with its defaults if missing
+    pub fn detector_mut(&mut self, kind: DetectorKind) -> &mut DetectorConfig {
+        let index = match self.detectors.iter().position(|d| d.kind == kind) {
+            Some(index) => index,
+            None => {
+                self.detectors.push(DetectorConfig::new(kind));
+                self.detectors.len() - 1
+            }
+        };
+        &mut self.detectors[index]
+    }
+
+    pub fn set_threshold(&mut self, kind: DetectorKind, threshold: f64) {
+        self.detector_mut(kind).threshold = threshold;
+    }
+
+    /// Kinds of the enabled detectors
+    pub fn enabled(&self) -> Vec<DetectorKind> {
+        self.detectors
+            .iter()
+            .filter(|d| d.enabled)
+            .map(|d| d.kind)
+            .collect()
+    }
+
+    fn enabled_detector(&self, kind: DetectorKind) -> Option<&DetectorConfig> {
+        self.detector(kind).filter(|d| d.enabled)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for detector in &self.detectors {
+            let name = detector.kind.as_str();
+            if !seen.insert(detector.kind) {
+                anyhow::bail!("Detector {} is configured more than once", name);
+            }
+            if detector.window_secs == 0 || detector.windows_secs.contains(&0) {
+                anyhow::bail!("Detector {} window must be positive", name);
+            }
+            // A zero leak slope reports any growth, other thresholds must be positive
+            let thresholds = std::iter::once(detector.threshold)
+                .chain(detector.namespace_thresholds.values().copied());
+            for threshold in thresholds {
+                let valid = match detector.kind {
+                    DetectorKind::MemoryLeak => threshold >= 0.0,
+                    _ => threshold > 0.0,
+                };
+                if !valid || !threshold.is_finite() {
+                    anyhow::bail!("Detector {} has invalid threshold {}", name, threshold);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// CPU throttling anomaly details
+#[derive(Debug, Clone, Serialize)]
+pub struct ThrottleAnomaly {
+    /// Rate of throttled CFS periods since the previous sample
+    pub throttled_periods_per_sec: f64,
+    /// Threshold that was exceeded
+    pub threshold: f64,
+}
+
+/// Anomaly found by a pipeline detector
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "detector", rename_all = "snake_case")]
+pub enum DetectedAnomaly {
+    MemoryLeak(LeakAnomaly),
+    CpuSpike(SpikeAnomaly),
+    CpuThrottling(ThrottleAnomaly),
+    /// Spike in network bytes/sec, received and sent combined
+    NetworkSpike(SpikeAnomaly),
+}
+
+impl DetectedAnomaly {
+    pub fn kind(&self) -> DetectorKind {
+        match self {
+            DetectedAnomaly::MemoryLeak(_) => DetectorKind::MemoryLeak,
+            DetectedAnomaly::CpuSpike(_) => DetectorKind::CpuSpike,
+            DetectedAnomaly::CpuThrottling(_) => DetectorKind::CpuThrottling,
+            DetectedAnomaly::NetworkSpike(_) => DetectorKind::NetworkSpike,
+        }
+    }
+}
+
+/// Detection history of a single container
+struct ContainerState {
+    namespace: String,
+    /// Working set samples within the longest leak window
+    memory: VecDeque<(i64, u64)>,
+    cpu: RollingStats,
+    network: RollingStats,
+    /// Timestamp, throttled periods and network bytes of the last sample
+    previous: Option<(i64, u64, u64)>,
+    /// Timestamp of the newest sample when trends were last checked
+    trends_checked_at: Option<i64>,
+    /// Detectors whose anomaly is ongoing, so it is reported once
+    active: HashSet<DetectorKind>,
+}
+
+impl ContainerState {
+    fn new(namespace: &str, config: &PipelineConfig) -> Self {
+        let window = |kind| {
+            config
+                .detector(kind)
+                .map(DetectorConfig::window)
+                .unwrap_or_else(|| kind.default_window())
+        };
+        Self {
+            namespace: namespace.to_string(),
+            memory: VecDeque::new(),
+            cpu: RollingStats::new(window(DetectorKind::CpuSpike)),
+            network: RollingStats::new(window(DetectorKind::NetworkSpike)),
+            previous: None,
+            trends_checked_at: None,
+            active: HashSet::new(),
+        }
+    }
+
+    /// Pass on an anomaly only when it starts
+    fn report(
+        &mut self,
+        kind: DetectorKind,
+        anomaly: Option<DetectedAnomaly>,
+    ) -> Option<DetectedAnomaly> {
+        match anomaly {
+            Some(anomaly) => self.active.insert(kind).then_some(anomaly),
+            None => {
+                self.active.remove(&kind);
+                None
+            }
+        }
+    }
+}
+
+/// Per-second rate of a cumulative counter, None after a reset
+fn rate(current: u64, previous: u64, elapsed_secs: i64) -> Option<f64> {
+    Some(current.checked_sub(previous)? as f64 / elapsed_secs as f64)
+}
+
+/// Runs the configured detectors over container samples
+pub struct AnomalyPipeline {
+    config: PipelineConfig,
+    containers: HashMap<String, ContainerState>,
+    /// Where active anomalies are published, if anywhere
+    state: Option<AnomalyState>,
+}
+
+impl AnomalyPipeline {
+    pub fn new(config: PipelineConfig) -> Self {
+        Self {
+            config,
+            containers: HashMap::new(),
+            state: None,
+        }
+    }
+
+    /// Publish the anomalies active for each container to `state`
+    pub fn with_state(mut self, state: AnomalyState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    pub fn config(&self) -> &PipelineConfig {
+        &self.config
+    }
+
+    /// Run the per-sample detectors, returning anomalies that started
+    ///
+    /// Samples of a container are expected in timestamp order. Spikes are
+    /// measured against the history before the sample.
+    pub fn observe(&mut self, metrics: &ContainerMetrics) -> Vec<DetectedAnomaly> {
+        let found = self.detect(metrics);
+        self.publish(&metrics.container_id, metrics.timestamp);
+        found
+    }
+
+    fn detect(&mut self, metrics: &ContainerMetrics) -> Vec<DetectedAnomaly> {
+        let config = &self.config;
+        let state = self
+            .containers
+            .entry(metrics.container_id.clone())
+            .or_insert_with(|| ContainerState::new(&metrics.namespace, config));
+        let namespace = metrics.namespace.as_str();
+        let timestamp = metrics.timestamp;
+        let mut found = Vec::new();
+
+        if let Some(detector) = config.enabled_detector(DetectorKind::CpuSpike) {
+            let cpu = metrics.cpu_usage_cores as f64;
+            let spike = SpikeDetector::new(detector.threshold_for(namespace))
+                .detect(cpu, &state.cpu)
+                .map(DetectedAnomaly::CpuSpike);
+            found.extend(state.report(DetectorKind::CpuSpike, spike));
+            state.cpu.add_sample(timestamp, cpu);
+        }
+
+        if let Some(detector) = config.enabled_detector(DetectorKind::MemoryLeak) {
+            state
+                .memory
+                .push_back((timestamp, metrics.memory_working_set_bytes));
+            let window_start = timestamp - detector.retention().as_secs() as i64;
+            while state
+                .memory
+                .front()
+                .is_some_and(|(ts, _)| *ts < window_start)
+            {
+                state.memory.pop_front();
+            }
+        }
+
+        let network = metrics
+            .network_rx_bytes
+            .saturating_add(metrics.network_tx_bytes);
+        let previous = state
+            .previous
+            .replace((timestamp, metrics.cpu_throttled_periods, network));
+        let Some((previous_ts, previous_throttled, previous_network)) = previous else {
+            return found;
+        };
+        let elapsed = timestamp - previous_ts;
+        if elapsed <= 0 {
+            return found;
+        }
+
+        if let Some(detector) = config.enabled_detector(DetectorKind::CpuThrottling) {
+            if let Some(throttled) =
+                rate(metrics.cpu_throttled_periods, previous_throttled, elapsed)
+            {
+                let threshold = detector.threshold_for(namespace);
+                let anomaly = (throttled >= threshold).then_some(DetectedAnomaly::CpuThrottling(
+                    ThrottleAnomaly {
+                        throttled_periods_per_sec: throttled,
+                        threshold,
+                    },
+                ));
+                found.extend(state.report(DetectorKind::CpuThrottling, anomaly));
+            }
+        }
+
+        if let Some(detector) = config.enabled_detector(DetectorKind::NetworkSpike) {
+            if let Some(bytes_per_sec) = rate(network, previous_network, elapsed) {
+                let spike = SpikeDetector::new(detector.threshold_for(namespace))
+                    .detect(bytes_per_sec, &state.network)
+                    .map(DetectedAnomaly::NetworkSpike);
+                found.extend(state.report(DetectorKind::NetworkSpike, spike));
+                state.network.add_sample(timestamp, bytes_per_sec);
+            }
+        }
+
+        found
+    }
+
+    /// Run the trend detectors over a container's history
+    ///
+    /// These look at the whole window, so they are meant to run
+    /// periodically rather than on every sample. The window is kept here
+    /// rather than read from `MetricsBuffer::query`, as the offline buffer
+    /// only holds samples until they are synced.
+    pub fn check_trends(&mut self, container_id: &str) -> Vec<DetectedAnomaly> {
+        let Some(state) = self.containers.get_mut(container_id) else {
+            return Vec::new();
+        };
+        let Some(detector) = self.config.enabled_detector(DetectorKind::MemoryLeak) else {
+            return Vec::new();
+        };
+
+        let leak = MultiWindowLeakDetector::new(
+            detector.windows(),
+            detector.threshold_for(&state.namespace),
+        )
+        .detect(state.memory.make_contiguous())
+        .map(DetectedAnomaly::MemoryLeak);
+        let found = state
+            .report(DetectorKind::MemoryLeak, leak)
+            .into_iter()
+            .collect();
+        let newest = state.memory.back().map(|&(timestamp, _)| timestamp);
+        state.trends_checked_at = newest;
+        if let Some(timestamp) = newest {
+            self.publish(container_id, timestamp);
+        }
+        found
+    }
+
+    /// Whether a container's trends were last checked at least `interval`
+    /// before `timestamp`, or never
+    pub fn trends_due(&self, container_id: &str, timestamp: i64, interval: Duration) -> bool {
+        self.containers.get(container_id).is_some_and(|state| {
+            state.trends_checked_at.map_or(true, |checked| {
+                timestamp - checked >= interval.as_secs() as i64
+            })
+        })
+    }
+
+    /// Forget a container's history
+    pub fn remove_container(&mut self, container_id: &str) {
+        self.containers.remove(container_id);
+        if let Some(shared) = &self.state {
+            shared.remove_container(container_id);
+        }
+    }
+
+    /// Publish a container's active anomalies as of `timestamp`
+    fn publish(&self, container_id: &str, timestamp: i64) {
+        if let (Some(shared), Some(state)) = (&self.state, self.containers.get(container_id)) {
+            shared.update(container_id, &state.active, timestamp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(namespace: &str, timestamp: i64, throttled: u64) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: format!("{}-app", namespace),
+            pod_name: "app".to_string(),
+            namespace: namespace.to_string(),
+            owner: None,
+            timestamp,
+            cpu_usage_cores: 0.5,
+            cpu_throttled_periods: throttled,
+            memory_usage_bytes: 0,
+            memory_working_set_bytes: 0,
+            memory_cache_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_detectors_follow_config() {
+        // Throttled 10 periods/sec for a minute
+        let run = |pipeline: &mut AnomalyPipeline, namespace: &str| -> Vec<DetectorKind> {
+            (0..6)
+                .flat_map(|i| pipeline.observe(&sample(namespace, i * 10, i as u64 * 100)))
+                .map(|a| a.kind())
+                .collect()
+        };
+
+        let mut pipeline = AnomalyPipeline::new(PipelineConfig::default());
+        assert!(run(&mut pipeline, "prod").is_empty());
+
+        let mut config = PipelineConfig::default();
+        let throttling = config.detector_mut(DetectorKind::CpuThrottling);
+        throttling.enabled = true;
+        throttling
+            .namespace_thresholds
+            .insert("batch".to_string(), 50.0);
+        let mut pipeline = AnomalyPipeline::new(config);
+        // Reported once while it lasts, and not where the threshold is higher
+        assert_eq!(run(&mut pipeline, "prod"), [DetectorKind::CpuThrottling]);
+        assert!(run(&mut pipeline, "batch").is_empty());
+    }
+
+    #[test]
+    fn test_active_anomalies_published() {
+        let mut config = PipelineConfig::default();
+        config.detector_mut(DetectorKind::CpuThrottling).enabled = true;
+        let state = AnomalyState::new();
+        let mut pipeline = AnomalyPipeline::new(config).with_state(state.clone());
+
+        for i in 0..6 {
+            pipeline.observe(&sample("prod", i * 10, i as u64 * 100));
+        }
+        assert_eq!(state.recent("prod-app", 50), [DetectorKind::CpuThrottling]);
+
+        // Ends once throttling stops, remembered for when it was active
+        pipeline.observe(&sample("prod", 60, 500));
+        assert_eq!(state.recent("prod-app", 50), [DetectorKind::CpuThrottling]);
+        assert!(state.recent("prod-app", 51).is_empty());
+
+        pipeline.remove_container("prod-app");
+        assert!(state.recent("prod-app", 0).is_empty());
+    }
+
+    #[test]
+    fn test_leak_reported_once_windows_agree() {
+        let mut pipeline = AnomalyPipeline::new(PipelineConfig::default());
+        let mut reported = Vec::new();
+        // Growing 10KB/s, sampled every minute for seven hours
+        for i in 0..7 * 60 {
+            let metrics = ContainerMetrics {
+                memory_working_set_bytes: 100_000_000 + i as u64 * 600_000,
+                ..sample("prod", i * 60, 0)
+            };
+            pipeline.observe(&metrics);
+            if pipeline.trends_due("prod-app", metrics.timestamp, Duration::from_secs(600)) {
+                let found = pipeline.check_trends("prod-app");
+                reported.extend(found.iter().map(|_| metrics.timestamp));
+            }
+        }
+        assert!(!pipeline.trends_due("prod-app", (7 * 60 - 1) * 60, Duration::from_secs(600)));
+        assert!(!pipeline.trends_due("other", 0, Duration::ZERO));
+
+        // Not before the six-hour window is covered, then only once
+        assert_eq!(reported.len(), 1);
+        assert!(reported[0] as f64 >= 6.0 * 3600.0 * 0.9);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = PipelineConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.enabled(),
+            [DetectorKind::MemoryLeak, DetectorKind::CpuSpike]
+        );
+
+        config.set_threshold(DetectorKind::MemoryLeak, 0.0);
+        assert!(config.validate().is_ok());
+        config.set_threshold(DetectorKind::CpuSpike, 0.0);
+        assert!(config.validate().is_err());
+
+        let mut config = PipelineConfig::default();
+        config
+            .detectors
+            .push(DetectorConfig::new(DetectorKind::CpuSpike));
+        assert!(config.validate().is_err());
+    }
+}
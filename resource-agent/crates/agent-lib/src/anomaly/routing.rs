@@ -0,0 +1,341 @@
+//! Routing of alerts to per-team destinations
+//!
+//! Routes match alerts by namespace and pod labels. A matching route can add
+//! labels, so a shared Alertmanager can pick the team's receiver, and can
+//! deliver to its own webhooks or Slack channel. Routes are evaluated in order
+//! and the first match wins unless it asks to continue. Alerts no route sends
+//! anywhere go to the fallback webhooks. Delivery requires the `alerting`
+//! feature.
+
+use super::AlertContext;
+#[cfg(feature = "alerting")]
+use super::{webhook::DEFAULT_WEBHOOK_TIMEOUT, AlertmanagerAlert};
+#[cfg(feature = "alerting")]
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "alerting")]
+use std::sync::RwLock;
+#[cfg(feature = "alerting")]
+use tracing::{debug, warn};
+
+/// Slack incoming webhook receiving routed alerts
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SlackDestination {
+    pub webhook_url: String,
+    /// Channel overriding the webhook's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+}
+
+/// Where a batch of routed alerts is delivered
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AlertDestination {
+    /// Alertmanager-compatible webhook
+    Webhook(String),
+    Slack(SlackDestination),
+}
+
+/// Alerts for matching workloads and where they go
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRoute {
+    /// Namespaces matched (any when empty)
+    pub namespaces: Vec<String>,
+    /// Pod labels that must all be present with these values
+    pub match_labels: BTreeMap<String, String>,
+    /// Labels added to matching alerts, e.g. `team` for Alertmanager routing
+    pub labels: BTreeMap<String, String>,
+    /// Alertmanager-compatible webhooks receiving matching alerts
+    pub webhook_urls: Vec<String>,
+    pub slack: Option<SlackDestination>,
+    /// Keep evaluating later routes after this one matches
+    pub continue_matching: bool,
+}
+
+impl AlertRoute {
+    /// Whether alerts for a container match this route
+    pub fn matches(&self, ctx: &AlertContext) -> bool {
+        (self.namespaces.is_empty() || self.namespaces.contains(&ctx.namespace))
+            && self
+                .match_labels
+                .iter()
+                .all(|(key, value)| ctx.pod_labels.get(key) == Some(value))
+    }
+
+    #[cfg(feature = "alerting")]
+    fn destinations(&self) -> impl Iterator<Item = AlertDestination> + '_ {
+        self.webhook_urls
+            .iter()
+            .cloned()
+            .map(AlertDestination::Webhook)
+            .chain(self.slack.clone().map(AlertDestination::Slack))
+    }
+}
+
+/// Delivers alerts to the destinations of their matching routes
+#[cfg(feature = "alerting")]
+pub struct AlertRouter {
+    routes: RwLock<Vec<AlertRoute>>,
+    fallback_urls: Vec<String>,
+    http: reqwest::Client,
+}
+
+#[cfg(feature = "alerting")]
+impl AlertRouter {
+    /// Create a router sending unrouted alerts to `fallback_urls`
+    pub fn new(fallback_urls: Vec<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(DEFAULT_WEBHOOK_TIMEOUT)
+            .build()
+            .context("Failed to build alert routing HTTP client")?;
+        Ok(Self {
+            routes: RwLock::new(Vec::new()),
+            fallback_urls,
+            http,
+        })
+    }
+
+    pub fn with_routes(self, routes: Vec<AlertRoute>) -> Self {
+        self.set_routes(routes);
+        self
+    }
+
+    /// Replace the routes, e.g. after a control plane config update
+    pub fn set_routes(&self, routes: Vec<AlertRoute>) {
+        *self.routes.write().unwrap_or_else(|e| e.into_inner()) = routes;
+    }
+
+    /// Label each alert and group the alerts by destination
+    ///
+    /// Labels added by routes never replace the alert's own labels.
+    /// Destinations are returned in the order they were first routed to.
+    pub fn plan(
+        &self,
+        alerts: &[(AlertContext, AlertmanagerAlert)],
+    ) -> Vec<(AlertDestination, Vec<AlertmanagerAlert>)> {
+        let routes = self.routes.read().unwrap_or_else(|e| e.into_inner());
+        let mut batches: Vec<(AlertDestination, Vec<AlertmanagerAlert>)> = Vec::new();
+
+        for (ctx, alert) in alerts {
+            let mut alert = alert.clone();
+            let mut destinations = Vec::new();
+            for route in routes.iter().filter(|route| route.matches(ctx)) {
+                for (key, value) in &route.labels {
+                    alert
+                        .labels
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+                for destination in route.destinations() {
+                    if !destinations.contains(&destination) {
+                        destinations.push(destination);
+                    }
+                }
+                if !route.continue_matching {
+                    break;
+                }
+            }
+            if destinations.is_empty() {
+                destinations.extend(
+                    self.fallback_urls
+                        .iter()
+                        .cloned()
+                        .map(AlertDestination::Webhook),
+                );
+            }
+
+            for destination in destinations {
+                match batches.iter_mut().find(|(d, _)| *d == destination) {
+                    Some((_, batch)) => batch.push(alert.clone()),
+                    None => batches.push((destination, vec![alert.clone()])),
+                }
+            }
+        }
+        batches
+    }
+
+    /// Route alerts and deliver them
+    ///
+    /// Every destination is attempted even if an earlier one fails; the error
+    /// reports how many deliveries failed.
+    pub async fn send(&self, alerts: &[(AlertContext, AlertmanagerAlert)]) -> Result<()> {
+        let batches = self.plan(alerts);
+        let mut failed = 0;
+        for (destination, batch) in &batches {
+            let request = match destination {
+                AlertDestination::Webhook(url) => self.http.post(url).json(batch),
+                AlertDestination::Slack(slack) => self
+                    .http
+                    .post(&slack.webhook_url)
+                    .json(&slack_message(slack, batch)),
+            };
+            let result = request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            match result {
+                Ok(_) => debug!(
+                    destination = ?destination,
+                    alerts = batch.len(),
+                    "Routed alerts delivered"
+                ),
+                Err(e) => {
+                    failed += 1;
+                    warn!(
+                        destination = ?destination,
+                        error = %e,
+                        "Failed to deliver routed alerts"
+                    );
+                }
+            }
+        }
+
+        if failed > 0 {
+            anyhow::bail!(
+                "Alert delivery failed for {} of {} destinations",
+                failed,
+                batches.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Slack incoming webhook message listing the alerts
+#[cfg(feature = "alerting")]
+fn slack_message(slack: &SlackDestination, alerts: &[AlertmanagerAlert]) -> serde_json::Value {
+    let text = alerts
+        .iter()
+        .map(|alert| {
+            let label = |key: &str| alert.labels.get(key).map(String::as_str).unwrap_or("");
+            format!(
+                "[{}] *{}* {}",
+                label("severity"),
+                label("alertname"),
+                alert
+                    .annotations
+                    .get("summary")
+                    .map(String::as_str)
+                    .unwrap_or("")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut message = serde_json::json!({ "text": text });
+    if let Some(channel) = &slack.channel {
+        message["channel"] = serde_json::Value::String(channel.clone());
+    }
+    message
+}
+
+#[cfg(all(test, feature = "alerting"))]
+mod tests {
+    use super::*;
+    use crate::models::ContainerRole;
+    use std::collections::HashMap;
+
+    fn alert(namespace: &str, labels: &[(&str, &str)]) -> (AlertContext, AlertmanagerAlert) {
+        let ctx = AlertContext {
+            container_id: "abc123".to_string(),
+            pod_name: "pod".to_string(),
+            pod_uid: None,
+            namespace: namespace.to_string(),
+            node_name: "node-1".to_string(),
+            deployment: None,
+            container_name: None,
+            role: ContainerRole::Main,
+            pod_labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let alert = AlertmanagerAlert {
+            status: "firing".to_string(),
+            labels: HashMap::from([("namespace".to_string(), namespace.to_string())]),
+            annotations: HashMap::new(),
+            starts_at: "2024-01-01T00:00:00Z".to_string(),
+            ends_at: None,
+            generator_url: None,
+        };
+        (ctx, alert)
+    }
+
+    #[test]
+    fn test_routes_by_namespace_and_labels() {
+        let payments = AlertRoute {
+            namespaces: vec!["payments".to_string()],
+            labels: BTreeMap::from([("team".to_string(), "payments".to_string())]),
+            slack: Some(SlackDestination {
+                webhook_url: "https://hooks.slack.test/payments".to_string(),
+                channel: Some("#payments-alerts".to_string()),
+            }),
+            ..Default::default()
+        };
+        // Label-only route: the shared Alertmanager picks the receiver
+        let search = AlertRoute {
+            match_labels: BTreeMap::from([("team".to_string(), "search".to_string())]),
+            labels: BTreeMap::from([("team".to_string(), "search".to_string())]),
+            ..Default::default()
+        };
+        let router = AlertRouter::new(vec!["http://alertmanager".to_string()])
+            .unwrap()
+            .with_routes(vec![payments, search]);
+
+        let plan = router.plan(&[
+            alert("payments", &[]),
+            alert("web", &[("team", "search")]),
+            alert("web", &[("team", "ads")]),
+        ]);
+        assert_eq!(plan.len(), 2);
+
+        let (destination, batch) = &plan[0];
+        assert!(
+            matches!(destination, AlertDestination::Slack(s) if s.channel.as_deref() == Some("#payments-alerts"))
+        );
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].labels["team"], "payments");
+
+        let (destination, batch) = &plan[1];
+        assert_eq!(
+            *destination,
+            AlertDestination::Webhook("http://alertmanager".to_string())
+        );
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].labels["team"], "search");
+        assert!(!batch[1].labels.contains_key("team"));
+    }
+
+    #[test]
+    fn test_continue_matching() {
+        let team = AlertRoute {
+            namespaces: vec!["payments".to_string()],
+            webhook_urls: vec!["http://team".to_string()],
+            continue_matching: true,
+            ..Default::default()
+        };
+        let audit = AlertRoute {
+            webhook_urls: vec!["http://audit".to_string()],
+            ..Default::default()
+        };
+        let router = AlertRouter::new(vec![])
+            .unwrap()
+            .with_routes(vec![team.clone(), audit.clone()]);
+        assert_eq!(router.plan(&[alert("payments", &[])]).len(), 2);
+
+        router.set_routes(vec![
+            AlertRoute {
+                continue_matching: false,
+                ..team
+            },
+            audit,
+        ]);
+        let plan = router.plan(&[alert("payments", &[])]);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(
+            plan[0].0,
+            AlertDestination::Webhook("http://team".to_string())
+        );
+    }
+}
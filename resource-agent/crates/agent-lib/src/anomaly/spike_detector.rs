@@ -3,6 +3,7 @@
 //! Detects CPU spikes by maintaining rolling 24-hour statistics and
 //! identifying values exceeding a configurable standard deviation threshold.
 
+use serde::Serialize;
 use std::collections::VecDeque;
 use std::time::Duration;
 
@@ -198,7 +199,7 @@ impl Default for RollingStats {
 }
 
 /// CPU spike anomaly details
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SpikeAnomaly {
     /// Current CPU usage that triggered the spike
     pub current_usage: f64,
@@ -0,0 +1,98 @@
+//! Anomalies per container, shared with prediction
+//!
+//! The pipeline records which of its detectors are active for a container
+//! and when each was last active, so the prediction scheduler can tell when
+//! a recommendation would be derived from anomalous samples.
+
+use super::DetectorKind;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// Activity of one detector for a container
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+    active: bool,
+    /// Timestamp of the last sample the anomaly was active for
+    last_active: i64,
+}
+
+/// Registry of the anomalies active or recently active per container
+///
+/// Cloning shares the registry.
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyState {
+    containers: Arc<RwLock<HashMap<String, HashMap<DetectorKind, Activity>>>>,
+}
+
+impl AnomalyState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the detectors active for a container as of `timestamp`
+    pub fn update(&self, container_id: &str, active: &HashSet<DetectorKind>, timestamp: i64) {
+        let mut containers = self.containers.write().unwrap();
+        if active.is_empty() && !containers.contains_key(container_id) {
+            return;
+        }
+        let detectors = containers.entry(container_id.to_string()).or_default();
+        for (kind, activity) in detectors.iter_mut() {
+            activity.active = active.contains(kind);
+        }
+        for &kind in active {
+            detectors.insert(
+                kind,
+                Activity {
+                    active: true,
+                    last_active: timestamp,
+                },
+            );
+        }
+    }
+
+    /// Detectors active for a container, or last active at or after `since`
+    pub fn recent(&self, container_id: &str, since: i64) -> Vec<DetectorKind> {
+        let containers = self.containers.read().unwrap();
+        let mut kinds: Vec<DetectorKind> = containers
+            .get(container_id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, activity)| activity.active || activity.last_active >= since)
+            .map(|(&kind, _)| kind)
+            .collect();
+        kinds.sort_by_key(|kind| kind.as_str());
+        kinds
+    }
+
+    pub fn remove_container(&self, container_id: &str) {
+        self.containers.write().unwrap().remove(container_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_anomalies() {
+        let state = AnomalyState::new();
+        let spike = HashSet::from([DetectorKind::CpuSpike]);
+        state.update("a", &HashSet::new(), 0);
+        assert!(state.recent("a", 0).is_empty());
+
+        state.update("a", &spike, 100);
+        state.update("a", &HashSet::new(), 110);
+        // No longer active, but within the window
+        assert_eq!(state.recent("a", 50), vec![DetectorKind::CpuSpike]);
+        assert!(state.recent("a", 101).is_empty());
+
+        let leak = HashSet::from([DetectorKind::MemoryLeak]);
+        state.update("a", &leak, 120);
+        // Still active however long ago it started
+        assert_eq!(state.recent("a", 1000), vec![DetectorKind::MemoryLeak]);
+        assert!(state.recent("b", 0).is_empty());
+
+        state.remove_container("a");
+        assert!(state.recent("a", 0).is_empty());
+    }
+}
@@ -0,0 +1,415 @@
+//! Local anomaly history
+//!
+//! Keeps a bounded history of recent anomalies per container so they can be
+//! inspected through the agent API without querying the Recommendation API.
+//! Like the metrics buffer, the store can optionally be persisted to disk.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use super::{AlertSeverity, AlertType, DetectedAnomaly, SpikeSeverity};
+use crate::intern::{self, intern};
+use crate::live::LiveFeed;
+use crate::models::{ContainerMetrics, OwnerRef};
+
+/// Default number of anomalies kept per container
+const DEFAULT_MAX_PER_CONTAINER: usize = 50;
+
+/// A single recorded anomaly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyRecord {
+    pub container_id: String,
+    pub pod_name: String,
+    pub namespace: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    pub anomaly_type: AlertType,
+    pub severity: AlertSeverity,
+    pub message: String,
+    /// Detection time (Unix seconds)
+    pub detected_at: i64,
+}
+
+impl AnomalyRecord {
+    /// Record of an anomaly the pipeline found in `metrics`
+    ///
+    /// Throttling and network spikes have no alert type and aren't recorded.
+    pub fn detected(metrics: &ContainerMetrics, anomaly: &DetectedAnomaly) -> Option<Self> {
+        let (anomaly_type, severity, message) = match anomaly {
+            DetectedAnomaly::MemoryLeak(leak) => (
+                AlertType::MemoryLeak,
+                AlertSeverity::Warning,
+                format!(
+                    "Memory leak detected: {:.2} MB/hour increase. Confidence: {:.0}%",
+                    leak.leak_rate_mb_per_hour(),
+                    leak.confidence * 100.0
+                ),
+            ),
+            DetectedAnomaly::CpuSpike(spike) => (
+                AlertType::CpuSpike,
+                match spike.severity() {
+                    SpikeSeverity::Critical => AlertSeverity::Critical,
+                    SpikeSeverity::High | SpikeSeverity::Warning => AlertSeverity::Warning,
+                },
+                format!(
+                    "CPU spike detected: {:.2} cores (expected {:.2}, z-score: {:.1})",
+                    spike.current_usage, spike.expected_usage, spike.z_score
+                ),
+            ),
+            DetectedAnomaly::CpuThrottling(_) | DetectedAnomaly::NetworkSpike(_) => return None,
+        };
+        Some(Self {
+            container_id: metrics.container_id.clone(),
+            pod_name: metrics.pod_name.clone(),
+            namespace: metrics.namespace.clone(),
+            deployment: metrics
+                .owner
+                .as_ref()
+                .and_then(OwnerRef::deployment_name)
+                .map(String::from),
+            anomaly_type,
+            severity,
+            message,
+            detected_at: metrics.timestamp,
+        })
+    }
+}
+
+/// Configuration for the anomaly store
+#[derive(Debug, Clone)]
+pub struct AnomalyStoreConfig {
+    /// Maximum number of anomalies kept per container
+    pub max_per_container: usize,
+    /// Path for persistent storage (optional)
+    pub persistence_path: Option<PathBuf>,
+}
+
+impl Default for AnomalyStoreConfig {
+    fn default() -> Self {
+        Self {
+            max_per_container: DEFAULT_MAX_PER_CONTAINER,
+            persistence_path: None,
+        }
+    }
+}
+
+/// Bounded per-container anomaly history
+pub struct AnomalyStore {
+    config: AnomalyStoreConfig,
+    /// Interned container_id -> anomalies, oldest first
+    records: RwLock<HashMap<Arc<str>, VecDeque<AnomalyRecord>>>,
+    /// Dirty flag for persistence
+    dirty: AtomicBool,
+    /// Feed that recorded anomalies are published to
+    live: Option<LiveFeed>,
+}
+
+impl AnomalyStore {
+    /// Create a new in-memory anomaly store
+    pub fn new(config: AnomalyStoreConfig) -> Self {
+        Self {
+            config,
+            records: RwLock::new(HashMap::new()),
+            dirty: AtomicBool::new(false),
+            live: None,
+        }
+    }
+
+    /// Publish recorded anomalies on `feed`
+    pub fn with_live_feed(mut self, feed: LiveFeed) -> Self {
+        self.live = Some(feed);
+        self
+    }
+
+    /// Create a store backed by a file, loading any existing history
+    pub fn with_persistence(persistence_path: PathBuf) -> Result<Self> {
+        let store = Self::new(AnomalyStoreConfig {
+            persistence_path: Some(persistence_path.clone()),
+            ..Default::default()
+        });
+
+        if persistence_path.exists() {
+            if let Err(e) = store.load_from_disk() {
+                warn!(error = %e, "Failed to load persisted anomalies, starting fresh");
+            }
+        }
+
+        Ok(store)
+    }
+
+    /// Record a new anomaly, evicting the oldest one for the container if full
+    pub fn record(&self, record: AnomalyRecord) {
+        if let Some(feed) = &self.live {
+            feed.publish_anomaly(&record);
+        }
+        let mut records = self.records.write().unwrap();
+        let key = match records.get_key_value(record.container_id.as_str()) {
+            Some((key, _)) => key.clone(),
+            None => intern(&record.container_id),
+        };
+        let history = records.entry(key).or_default();
+
+        while history.len() >= self.config.max_per_container.max(1) {
+            history.pop_front();
+        }
+        history.push_back(record);
+
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Get the anomaly history for a container, newest first
+    pub fn for_container(&self, container_id: &str) -> Vec<AnomalyRecord> {
+        let records = self.records.read().unwrap();
+        records
+            .get(container_id)
+            .map(|h| h.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get all recorded anomalies across containers, newest first
+    pub fn all(&self) -> Vec<AnomalyRecord> {
+        let records = self.records.read().unwrap();
+        let mut all: Vec<AnomalyRecord> = records.values().flatten().cloned().collect();
+        all.sort_by(|a, b| b.detected_at.cmp(&a.detected_at));
+        all
+    }
+
+    /// Get recent anomalies, newest first, optionally filtered by namespace
+    pub fn recent(&self, namespace: Option<&str>, limit: usize) -> Vec<AnomalyRecord> {
+        self.all()
+            .into_iter()
+            .filter(|r| namespace.map(|ns| r.namespace == ns).unwrap_or(true))
+            .take(limit)
+            .collect()
+    }
+
+    /// Drop the history for a container (e.g. when it stops)
+    pub fn remove_container(&self, container_id: &str) {
+        let mut records = self.records.write().unwrap();
+        if records.remove(container_id).is_some() {
+            self.dirty.store(true, Ordering::Relaxed);
+            intern::interner().purge();
+        }
+    }
+
+    /// Total number of anomalies stored
+    pub fn len(&self) -> usize {
+        self.records.read().unwrap().values().map(|h| h.len()).sum()
+    }
+
+    /// Check if the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Flush the store to disk if persistence is enabled
+    pub fn flush(&self) -> Result<()> {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if let Some(ref path) = self.config.persistence_path {
+            self.save_to_disk(path)?;
+            self.dirty.store(false, Ordering::Relaxed);
+            debug!(path = %path.display(), entries = self.len(), "Anomaly store flushed to disk");
+        }
+
+        Ok(())
+    }
+
+    /// Save the store to disk
+    fn save_to_disk(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+
+        let json = {
+            let records = self.records.read().unwrap();
+            let all: Vec<&AnomalyRecord> = records.values().flatten().collect();
+            serde_json::to_vec(&all).context("Failed to serialize anomalies")?
+        };
+
+        // Write atomically using temp file
+        let temp_path = path.with_extension("tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)
+            .with_context(|| format!("Failed to create temp file {:?}", temp_path))?;
+
+        file.write_all(&json)
+            .context("Failed to write anomaly data")?;
+        file.sync_all().context("Failed to sync anomaly file")?;
+
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
+
+        Ok(())
+    }
+
+    /// Load the store from disk
+    fn load_from_disk(&self) -> Result<()> {
+        let path = self
+            .config
+            .persistence_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No persistence path configured"))?;
+
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open anomaly file {:?}", path))?;
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .context("Failed to read anomaly file")?;
+
+        let mut loaded: Vec<AnomalyRecord> =
+            serde_json::from_slice(&data).context("Failed to deserialize anomaly data")?;
+        loaded.sort_by_key(|r| r.detected_at);
+
+        for record in loaded {
+            self.record(record);
+        }
+        self.dirty.store(false, Ordering::Relaxed);
+
+        info!(path = %path.display(), entries = self.len(), "Loaded anomaly history from disk");
+        Ok(())
+    }
+}
+
+impl Default for AnomalyStore {
+    fn default() -> Self {
+        Self::new(AnomalyStoreConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anomaly::{SpikeAnomaly, ThrottleAnomaly};
+    use tempfile::TempDir;
+
+    fn test_record(container_id: &str, detected_at: i64) -> AnomalyRecord {
+        AnomalyRecord {
+            container_id: container_id.to_string(),
+            pod_name: "test-pod".to_string(),
+            namespace: "default".to_string(),
+            deployment: Some("test-deployment".to_string()),
+            anomaly_type: AlertType::CpuSpike,
+            severity: AlertSeverity::Warning,
+            message: "CPU spike detected".to_string(),
+            detected_at,
+        }
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let store = AnomalyStore::default();
+        store.record(test_record("c1", 100));
+        store.record(test_record("c1", 200));
+        store.record(test_record("c2", 150));
+
+        assert_eq!(store.len(), 3);
+
+        let c1 = store.for_container("c1");
+        assert_eq!(c1.len(), 2);
+        assert_eq!(c1[0].detected_at, 200); // newest first
+
+        let all = store.all();
+        assert_eq!(all[0].detected_at, 200);
+        assert_eq!(all[2].detected_at, 100);
+    }
+
+    #[test]
+    fn test_detected_anomaly_record() {
+        let metrics = ContainerMetrics {
+            container_id: "c1".to_string(),
+            pod_name: "web-1".to_string(),
+            namespace: "default".to_string(),
+            owner: Some(OwnerRef::deployment("web")),
+            timestamp: 100,
+            cpu_usage_cores: 4.0,
+            cpu_throttled_periods: 0,
+            memory_usage_bytes: 0,
+            memory_working_set_bytes: 0,
+            memory_cache_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        };
+        let spike = SpikeAnomaly {
+            current_usage: 4.0,
+            expected_usage: 0.5,
+            z_score: 6.0,
+            std_dev: 0.5,
+            threshold: 3.0,
+        };
+
+        let record = AnomalyRecord::detected(&metrics, &DetectedAnomaly::CpuSpike(spike)).unwrap();
+        assert_eq!(record.anomaly_type, AlertType::CpuSpike);
+        assert_eq!(record.severity, AlertSeverity::Critical);
+        assert_eq!(record.deployment.as_deref(), Some("web"));
+        assert_eq!(record.detected_at, 100);
+
+        let throttling = DetectedAnomaly::CpuThrottling(ThrottleAnomaly {
+            throttled_periods_per_sec: 20.0,
+            threshold: 10.0,
+        });
+        assert!(AnomalyRecord::detected(&metrics, &throttling).is_none());
+    }
+
+    #[test]
+    fn test_bounded_per_container() {
+        let store = AnomalyStore::new(AnomalyStoreConfig {
+            max_per_container: 3,
+            persistence_path: None,
+        });
+
+        for i in 0..10 {
+            store.record(test_record("c1", i));
+        }
+
+        let history = store.for_container("c1");
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].detected_at, 9);
+        assert_eq!(history[2].detected_at, 7);
+    }
+
+    #[test]
+    fn test_recent_filters_namespace() {
+        let store = AnomalyStore::default();
+        store.record(test_record("c1", 100));
+        let mut other = test_record("c2", 200);
+        other.namespace = "kube-system".to_string();
+        store.record(other);
+
+        assert_eq!(store.recent(Some("default"), 10).len(), 1);
+        assert_eq!(store.recent(None, 10).len(), 2);
+        assert_eq!(store.recent(None, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("anomalies.json");
+
+        {
+            let store = AnomalyStore::with_persistence(path.clone()).unwrap();
+            store.record(test_record("c1", 100));
+            store.record(test_record("c2", 200));
+            store.flush().unwrap();
+        }
+
+        let store = AnomalyStore::with_persistence(path).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.for_container("c2")[0].detected_at, 200);
+    }
+}
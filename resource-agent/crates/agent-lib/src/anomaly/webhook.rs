@@ -0,0 +1,73 @@
+//! Delivery of alerts to Alertmanager-compatible webhooks
+//!
+//! Without Kubernetes there are no pod events to attach anomalies to, so
+//! standalone agents deliver alerts to webhooks only.
+
+use super::AlertmanagerPayload;
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default timeout for a single webhook request
+pub(super) const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts alert payloads to a set of webhook URLs
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    urls: Vec<String>,
+    http: reqwest::Client,
+}
+
+impl WebhookSink {
+    /// Create a sink posting to `urls`
+    pub fn new(urls: Vec<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(DEFAULT_WEBHOOK_TIMEOUT)
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+        Ok(Self { urls, http })
+    }
+
+    /// Whether any webhook is configured
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+
+    /// Post a payload's alerts to every webhook
+    ///
+    /// Alerts are sent as a JSON array, the format of Alertmanager's
+    /// `/api/v2/alerts` endpoint. Each webhook is attempted even if an
+    /// earlier one fails; the error reports how many deliveries failed.
+    pub async fn send(&self, payload: &AlertmanagerPayload) -> Result<()> {
+        if payload.alerts.is_empty() {
+            return Ok(());
+        }
+
+        let mut failed = 0;
+        for url in &self.urls {
+            let result = self
+                .http
+                .post(url)
+                .json(&payload.alerts)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            match result {
+                Ok(_) => debug!(url = %url, alerts = payload.alerts.len(), "Alerts delivered"),
+                Err(e) => {
+                    failed += 1;
+                    warn!(url = %url, error = %e, "Failed to deliver alerts to webhook");
+                }
+            }
+        }
+
+        if failed > 0 {
+            anyhow::bail!(
+                "Alert delivery failed for {} of {} webhooks",
+                failed,
+                self.urls.len()
+            );
+        }
+        Ok(())
+    }
+}
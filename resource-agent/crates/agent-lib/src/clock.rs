@@ -0,0 +1,214 @@
+//! Skew-resistant sample timestamps
+//!
+//! Samples carry wall-clock time so that they line up across nodes, but an
+//! NTP step moves the wall clock by seconds or hours at once. Read directly,
+//! a backward step leaves samples duplicated and out of order, and a step
+//! either way skews buffer retention by its size.
+//!
+//! `HybridClock` advances with the monotonic clock between readings and
+//! follows the wall clock without ever going backwards: it jumps forward
+//! with the wall clock, and catches up with a wall clock that stepped back
+//! by running at half speed. Differences larger than `max_skew` are reported
+//! as skew events.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Default difference between the clocks reported as skew
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(2);
+
+/// Wall-clock time read through a `HybridClock`
+///
+/// Ordered by wall-clock time, which never decreases between readings of
+/// the same clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HybridTimestamp {
+    unix_millis: i64,
+    monotonic: Instant,
+}
+
+impl HybridTimestamp {
+    /// Unix time in seconds
+    pub fn unix_secs(&self) -> i64 {
+        self.unix_millis.div_euclid(1000)
+    }
+
+    /// Unix time in milliseconds
+    pub fn unix_millis(&self) -> i64 {
+        self.unix_millis
+    }
+
+    pub fn system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.unix_millis.max(0) as u64)
+    }
+
+    /// Time since the reading, unaffected by wall-clock steps
+    pub fn elapsed(&self) -> Duration {
+        self.monotonic.elapsed()
+    }
+}
+
+#[derive(Debug)]
+struct ClockState {
+    last: Option<HybridTimestamp>,
+    /// Whether the last reading was skewed, so a step counts once
+    skewed: bool,
+    skew_events: u64,
+    last_skew_millis: i64,
+}
+
+/// Monotonic clock that follows the wall clock
+#[derive(Debug)]
+pub struct HybridClock {
+    max_skew: Duration,
+    state: Mutex<ClockState>,
+}
+
+impl HybridClock {
+    pub fn new(max_skew: Duration) -> Self {
+        Self {
+            max_skew,
+            state: Mutex::new(ClockState {
+                last: None,
+                skewed: false,
+                skew_events: 0,
+                last_skew_millis: 0,
+            }),
+        }
+    }
+
+    pub fn now(&self) -> HybridTimestamp {
+        self.advance(SystemTime::now(), Instant::now())
+    }
+
+    /// Reading for the given wall-clock and monotonic time
+    fn advance(&self, wall: SystemTime, monotonic: Instant) -> HybridTimestamp {
+        let wall_millis = match wall.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_millis() as i64,
+            Err(e) => -(e.duration().as_millis() as i64),
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let Some(last) = state.last else {
+            let first = HybridTimestamp {
+                unix_millis: wall_millis,
+                monotonic,
+            };
+            state.last = Some(first);
+            return first;
+        };
+
+        let elapsed = monotonic
+            .saturating_duration_since(last.monotonic)
+            .as_millis() as i64;
+        let expected = last.unix_millis + elapsed;
+        let skew = wall_millis - expected;
+
+        let skewed = skew.unsigned_abs() > self.max_skew.as_millis() as u64;
+        if skewed && !state.skewed {
+            state.skew_events += 1;
+            warn!(
+                skew_ms = skew,
+                "Wall clock stepped, smoothing sample timestamps"
+            );
+        }
+        if skewed {
+            state.last_skew_millis = skew;
+        }
+        state.skewed = skewed;
+
+        // Ahead: follow the wall clock. Behind: slow down to half speed
+        let unix_millis = if skew >= 0 {
+            wall_millis
+        } else {
+            expected - (elapsed / 2).min(-skew)
+        };
+        let reading = HybridTimestamp {
+            unix_millis,
+            monotonic,
+        };
+        state.last = Some(reading);
+        reading
+    }
+
+    /// Number of times the wall clock was found skewed
+    pub fn skew_events(&self) -> u64 {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .skew_events
+    }
+
+    /// Difference between the wall clock and the hybrid clock at the last
+    /// skew event, in milliseconds (negative when the wall clock was behind)
+    pub fn last_skew_millis(&self) -> i64 {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_skew_millis
+    }
+}
+
+impl Default for HybridClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CLOCK_SKEW)
+    }
+}
+
+/// Process-wide clock used to stamp samples
+pub fn clock() -> &'static HybridClock {
+    static CLOCK: OnceLock<HybridClock> = OnceLock::new();
+    CLOCK.get_or_init(HybridClock::default)
+}
+
+/// Current time of the process-wide clock
+pub fn now() -> HybridTimestamp {
+    clock().now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_follows_wall_clock() {
+        let clock = HybridClock::default();
+        let start = Instant::now();
+
+        assert_eq!(clock.advance(wall(1000), start).unix_secs(), 1000);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(clock.advance(wall(1010), later).unix_secs(), 1010);
+        assert_eq!(clock.skew_events(), 0);
+
+        // A forward step is followed at once
+        let later = later + Duration::from_secs(10);
+        assert_eq!(clock.advance(wall(4620), later).unix_secs(), 4620);
+        assert_eq!(clock.skew_events(), 1);
+    }
+
+    #[test]
+    fn test_backward_step_never_goes_back() {
+        let clock = HybridClock::default();
+        let start = Instant::now();
+        clock.advance(wall(10_000), start);
+
+        // Wall clock steps back an hour: timestamps keep increasing, at half
+        // speed, until the wall clock catches up
+        let mut previous = 10_000;
+        for i in 1..=100u64 {
+            let reading = clock.advance(
+                wall(10_000 + i * 10 - 3600),
+                start + Duration::from_secs(i * 10),
+            );
+            assert_eq!(reading.unix_secs(), previous + 5);
+            previous = reading.unix_secs();
+        }
+        assert_eq!(clock.skew_events(), 1);
+        assert_eq!(clock.last_skew_millis(), -3_105_000);
+    }
+}
@@ -5,9 +5,9 @@
 //! - cpu controller for throttling stats
 //! - memory controller for memory usage
 
-use super::MetricsCollector;
-use crate::models::{ContainerInfo, ContainerMetrics};
-use anyhow::{Context, Result};
+use super::{CollectorError, MetricsCollector};
+use crate::models::{ContainerInfo, ContainerMetrics, ContainerRole, OwnerRef};
+use anyhow::Result;
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -48,16 +48,16 @@ impl CgroupV1Collector {
     }
 
     /// Read CPU usage from cpuacct.usage (nanoseconds)
-    async fn read_cpu_usage(&self, cgroup_path: &Path) -> Result<u64> {
+    async fn read_cpu_usage(&self, cgroup_path: &Path) -> Result<u64, CollectorError> {
         let usage_file = cgroup_path.join("cpuacct.usage");
         let content = fs::read_to_string(&usage_file)
             .await
-            .with_context(|| format!("Failed to read {}", usage_file.display()))?;
+            .map_err(CollectorError::io(&usage_file))?;
 
         content
             .trim()
             .parse()
-            .with_context(|| "Failed to parse cpuacct.usage")
+            .map_err(CollectorError::parse(usage_file))
     }
 
     /// Read CPU throttling stats from cpu.stat
@@ -80,16 +80,16 @@ impl CgroupV1Collector {
     }
 
     /// Read memory usage from memory.usage_in_bytes
-    async fn read_memory_usage(&self, cgroup_path: &Path) -> Result<u64> {
+    async fn read_memory_usage(&self, cgroup_path: &Path) -> Result<u64, CollectorError> {
         let usage_file = cgroup_path.join("memory.usage_in_bytes");
         let content = fs::read_to_string(&usage_file)
             .await
-            .with_context(|| format!("Failed to read {}", usage_file.display()))?;
+            .map_err(CollectorError::io(&usage_file))?;
 
         content
             .trim()
             .parse()
-            .with_context(|| "Failed to parse memory.usage_in_bytes")
+            .map_err(CollectorError::parse(usage_file))
     }
 
     /// Parse memory.stat file contents
@@ -148,12 +148,21 @@ impl CgroupV1Collector {
 
     /// Parse /proc/{pid}/cgroup to get cgroup paths for a process (v1 format)
     /// Returns a map of controller -> path
-    pub async fn get_cgroup_paths_for_pid(&self, pid: u32) -> Result<HashMap<String, String>> {
+    pub async fn get_cgroup_paths_for_pid(
+        &self,
+        pid: u32,
+    ) -> Result<HashMap<String, String>, CollectorError> {
         let cgroup_file = self.proc_path.join(format!("{}/cgroup", pid));
         let content = fs::read_to_string(&cgroup_file)
             .await
-            .with_context(|| format!("Failed to read cgroup for pid {}", pid))?;
+            .map_err(CollectorError::io(&cgroup_file))?;
+
+        Ok(Self::parse_proc_cgroup(&content))
+    }
 
+    /// Parse /proc/{pid}/cgroup file contents (v1 format)
+    /// Returns a map of controller -> path
+    pub fn parse_proc_cgroup(content: &str) -> HashMap<String, String> {
         let mut paths = HashMap::new();
 
         // cgroup v1 format: "hierarchy-ID:controller-list:cgroup-path"
@@ -170,13 +179,13 @@ impl CgroupV1Collector {
                 }
 
                 // Handle comma-separated controllers (e.g., "cpu,cpuacct")
-                for controller in controllers.split(',') {
+                for controller in controllers.split(',').filter(|c| !c.is_empty()) {
                     paths.insert(controller.to_string(), path.to_string());
                 }
             }
         }
 
-        Ok(paths)
+        paths
     }
 
     /// Build full cgroup filesystem path for a specific controller
@@ -194,8 +203,8 @@ impl CgroupV1Collector {
         memory_path: &Path,
         container_id: &str,
         metadata: &ContainerMetadata,
-    ) -> Result<ContainerMetrics> {
-        let timestamp = chrono::Utc::now().timestamp();
+    ) -> Result<ContainerMetrics, CollectorError> {
+        let timestamp = crate::clock::now().unix_secs();
 
         // Read CPU usage (nanoseconds -> cores)
         let cpu_usage_ns = self.read_cpu_usage(cpuacct_path).await.unwrap_or(0);
@@ -236,7 +245,7 @@ impl CgroupV1Collector {
             container_id: container_id.to_string(),
             pod_name: metadata.pod_name.clone(),
             namespace: metadata.namespace.clone(),
-            deployment: metadata.deployment.clone(),
+            owner: metadata.owner.clone(),
             timestamp,
             cpu_usage_cores,
             cpu_throttled_periods,
@@ -254,14 +263,14 @@ impl CgroupV1Collector {
 pub struct ContainerMetadata {
     pub pod_name: String,
     pub namespace: String,
-    pub deployment: Option<String>,
+    pub owner: Option<OwnerRef>,
     #[allow(dead_code)]
     pub node_name: String,
 }
 
 #[async_trait]
 impl MetricsCollector for CgroupV1Collector {
-    async fn collect(&self, container_id: &str) -> Result<ContainerMetrics> {
+    async fn collect(&self, container_id: &str) -> Result<ContainerMetrics, CollectorError> {
         // Build paths for each controller
         let cpuacct_path = self.cgroup_root.join("cpuacct").join(container_id);
         let cpu_path = self.cgroup_root.join("cpu").join(container_id);
@@ -269,7 +278,9 @@ impl MetricsCollector for CgroupV1Collector {
 
         // Verify at least one path exists
         if !cpuacct_path.exists() && !memory_path.exists() {
-            anyhow::bail!("Cgroup paths not found for container {}", container_id);
+            return Err(CollectorError::ContainerNotFound {
+                container_id: container_id.to_string(),
+            });
         }
 
         let metadata = ContainerMetadata::default();
@@ -283,7 +294,7 @@ impl MetricsCollector for CgroupV1Collector {
         .await
     }
 
-    async fn list_containers(&self) -> Result<Vec<ContainerInfo>> {
+    async fn list_containers(&self) -> Result<Vec<ContainerInfo>, CollectorError> {
         // This will be fully implemented in Task 2.3 (container discovery)
         // For now, provide a basic implementation that scans kubepods
         let mut containers = Vec::new();
@@ -331,9 +342,11 @@ impl CgroupV1Collector {
                             container_id: container_id.clone(),
                             pod_name: String::new(), // Will be populated by discovery
                             namespace: String::new(),
-                            deployment: None,
+                            owner: None,
                             node_name: String::new(),
                             cgroup_path: entry_path.to_string_lossy().to_string(),
+                            container_name: None,
+                            role: ContainerRole::Main,
                         });
                     }
                 }
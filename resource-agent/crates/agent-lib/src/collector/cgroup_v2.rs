@@ -5,9 +5,9 @@
 //! - memory.current for current memory usage
 //! - memory.stat for detailed memory statistics
 
-use super::MetricsCollector;
-use crate::models::{ContainerInfo, ContainerMetrics};
-use anyhow::{Context, Result};
+use super::{CollectorError, MetricsCollector};
+use crate::models::{ContainerInfo, ContainerMetrics, ContainerRole, OwnerRef};
+use anyhow::Result;
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -44,7 +44,7 @@ impl CgroupV2Collector {
 
     /// Parse cpu.stat file contents
     /// Returns (usage_usec, throttled_periods)
-    pub fn parse_cpu_stat(content: &str) -> Result<(u64, u64)> {
+    pub fn parse_cpu_stat(content: &str) -> Result<(u64, u64), CollectorError> {
         let mut usage_usec = 0u64;
         let mut throttled_periods = 0u64;
 
@@ -84,16 +84,20 @@ impl CgroupV2Collector {
     }
 
     /// Read a single value from a cgroup file
-    async fn read_cgroup_value(&self, cgroup_path: &Path, filename: &str) -> Result<u64> {
+    async fn read_cgroup_value(
+        &self,
+        cgroup_path: &Path,
+        filename: &str,
+    ) -> Result<u64, CollectorError> {
         let file_path = cgroup_path.join(filename);
         let content = fs::read_to_string(&file_path)
             .await
-            .with_context(|| format!("Failed to read {}", file_path.display()))?;
+            .map_err(CollectorError::io(&file_path))?;
 
         content
             .trim()
             .parse()
-            .with_context(|| format!("Failed to parse {} value", filename))
+            .map_err(CollectorError::parse(file_path))
     }
 
     /// Extract container ID from cgroup path
@@ -130,21 +134,29 @@ impl CgroupV2Collector {
     }
 
     /// Parse /proc/{pid}/cgroup to get cgroup path for a process
-    pub async fn get_cgroup_path_for_pid(&self, pid: u32) -> Result<String> {
+    pub async fn get_cgroup_path_for_pid(&self, pid: u32) -> Result<String, CollectorError> {
         let cgroup_file = self.proc_path.join(format!("{}/cgroup", pid));
         let content = fs::read_to_string(&cgroup_file)
             .await
-            .with_context(|| format!("Failed to read cgroup for pid {}", pid))?;
+            .map_err(CollectorError::io(&cgroup_file))?;
+
+        Self::parse_proc_cgroup(&content).ok_or_else(|| CollectorError::Parse {
+            path: cgroup_file,
+            message: "no cgroup v2 path".to_string(),
+        })
+    }
 
+    /// Parse /proc/{pid}/cgroup file contents for the unified hierarchy path
+    pub fn parse_proc_cgroup(content: &str) -> Option<String> {
         // cgroup v2 format: "0::/path/to/cgroup"
         for line in content.lines() {
             let parts: Vec<&str> = line.splitn(3, ':').collect();
             if parts.len() == 3 && parts[0] == "0" {
-                return Ok(parts[2].to_string());
+                return Some(parts[2].to_string());
             }
         }
 
-        anyhow::bail!("No cgroup v2 path found for pid {}", pid)
+        None
     }
 
     /// Build full cgroup filesystem path from relative cgroup path
@@ -158,8 +170,8 @@ impl CgroupV2Collector {
         cgroup_path: &Path,
         container_id: &str,
         metadata: &ContainerMetadata,
-    ) -> Result<ContainerMetrics> {
-        let timestamp = chrono::Utc::now().timestamp();
+    ) -> Result<ContainerMetrics, CollectorError> {
+        let timestamp = crate::clock::now().unix_secs();
 
         // Read cpu.stat
         let cpu_stat_content = fs::read_to_string(cgroup_path.join("cpu.stat"))
@@ -194,7 +206,7 @@ impl CgroupV2Collector {
             container_id: container_id.to_string(),
             pod_name: metadata.pod_name.clone(),
             namespace: metadata.namespace.clone(),
-            deployment: metadata.deployment.clone(),
+            owner: metadata.owner.clone(),
             timestamp,
             cpu_usage_cores,
             cpu_throttled_periods,
@@ -212,20 +224,22 @@ impl CgroupV2Collector {
 pub struct ContainerMetadata {
     pub pod_name: String,
     pub namespace: String,
-    pub deployment: Option<String>,
+    pub owner: Option<OwnerRef>,
     #[allow(dead_code)]
     pub node_name: String,
 }
 
 #[async_trait]
 impl MetricsCollector for CgroupV2Collector {
-    async fn collect(&self, container_id: &str) -> Result<ContainerMetrics> {
+    async fn collect(&self, container_id: &str) -> Result<ContainerMetrics, CollectorError> {
         // Find the cgroup path for this container
         // In production, this would be cached from container discovery
         let cgroup_path = self.cgroup_root.join(container_id);
 
         if !cgroup_path.exists() {
-            anyhow::bail!("Cgroup path not found for container {}", container_id);
+            return Err(CollectorError::ContainerNotFound {
+                container_id: container_id.to_string(),
+            });
         }
 
         let metadata = ContainerMetadata::default();
@@ -233,7 +247,7 @@ impl MetricsCollector for CgroupV2Collector {
             .await
     }
 
-    async fn list_containers(&self) -> Result<Vec<ContainerInfo>> {
+    async fn list_containers(&self) -> Result<Vec<ContainerInfo>, CollectorError> {
         // This will be fully implemented in Task 2.3 (container discovery)
         // For now, provide a basic implementation that scans kubepods
         let mut containers = Vec::new();
@@ -278,9 +292,11 @@ impl CgroupV2Collector {
                             container_id: container_id.clone(),
                             pod_name: String::new(), // Will be populated by discovery
                             namespace: String::new(),
-                            deployment: None,
+                            owner: None,
                             node_name: String::new(),
                             cgroup_path: entry_path.to_string_lossy().to_string(),
+                            container_name: None,
+                            role: ContainerRole::Main,
                         });
                     }
                 }
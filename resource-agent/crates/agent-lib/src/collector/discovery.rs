@@ -4,13 +4,26 @@
 //! on cgroup directories and maintains an active container registry.
 
 use super::MetricsCollector;
-use crate::models::ContainerInfo;
-use anyhow::{Context, Result};
+use crate::intern::{self, intern};
+use crate::models::{
+    ContainerInfo, ContainerKey, ContainerRole, DisruptionBudget, HpaTarget, OwnerRef, WorkloadKind,
+};
+#[cfg(any(feature = "cgroup-watch", feature = "kube-api"))]
+use anyhow::Context;
+use anyhow::Result;
 use dashmap::DashMap;
+#[cfg(feature = "cgroup-watch")]
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::path::Path;
+#[cfg(any(feature = "cgroup-watch", feature = "kube-api"))]
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(feature = "cgroup-watch")]
 use tokio::sync::mpsc;
-use tracing::{debug, info, warn};
+#[cfg(any(feature = "cgroup-watch", feature = "kube-api"))]
+use tracing::warn;
+use tracing::{debug, info};
 
 /// Container lifecycle events
 #[derive(Debug, Clone)]
@@ -21,10 +34,16 @@ pub enum ContainerEvent {
     Stopped(String), // container_id
 }
 
+/// Label Argo Rollouts sets on the pods of its ReplicaSets
+const ROLLOUTS_POD_TEMPLATE_HASH_LABEL: &str = "rollouts-pod-template-hash";
+
+/// Label the Deployment controller sets on the pods of its ReplicaSets
+const POD_TEMPLATE_HASH_LABEL: &str = "pod-template-hash";
+
 /// Registry of active containers on the node
 pub struct ContainerRegistry {
-    /// Map of container_id -> ContainerInfo
-    containers: DashMap<String, ContainerInfo>,
+    /// Map of interned container_id -> ContainerInfo
+    containers: DashMap<Arc<str>, ContainerInfo>,
     /// Node name for this agent
     node_name: String,
 }
@@ -42,13 +61,15 @@ impl ContainerRegistry {
     pub fn register(&self, mut info: ContainerInfo) {
         info.node_name = self.node_name.clone();
         debug!(container_id = %info.container_id, "Registering container");
-        self.containers.insert(info.container_id.clone(), info);
+        self.containers.insert(intern(&info.container_id), info);
     }
 
     /// Unregister a container
     pub fn unregister(&self, container_id: &str) -> Option<ContainerInfo> {
         debug!(container_id = %container_id, "Unregistering container");
-        self.containers.remove(container_id).map(|(_, v)| v)
+        let removed = self.containers.remove(container_id).map(|(_, v)| v);
+        intern::interner().purge();
+        removed
     }
 
     /// Get container info by ID
@@ -56,6 +77,11 @@ impl ContainerRegistry {
         self.containers.get(container_id).map(|r| r.clone())
     }
 
+    /// Interned identity of a registered container
+    pub fn key(&self, container_id: &str) -> Option<ContainerKey> {
+        self.containers.get(container_id).map(|r| r.value().key())
+    }
+
     /// List all registered containers
     pub fn list(&self) -> Vec<ContainerInfo> {
         self.containers.iter().map(|r| r.value().clone()).collect()
@@ -77,7 +103,7 @@ impl ContainerRegistry {
         container_id: &str,
         pod_name: Option<String>,
         namespace: Option<String>,
-        deployment: Option<String>,
+        owner: Option<OwnerRef>,
     ) {
         if let Some(mut entry) = self.containers.get_mut(container_id) {
             if let Some(name) = pod_name {
@@ -86,14 +112,23 @@ impl ContainerRegistry {
             if let Some(ns) = namespace {
                 entry.namespace = ns;
             }
-            if deployment.is_some() {
-                entry.deployment = deployment;
+            if owner.is_some() {
+                entry.owner = owner;
             }
         }
     }
+
+    /// Record a container's name and role within its pod
+    pub fn update_role(&self, container_id: &str, container_name: String, role: ContainerRole) {
+        if let Some(mut entry) = self.containers.get_mut(container_id) {
+            entry.container_name = Some(container_name);
+            entry.role = role;
+        }
+    }
 }
 
 /// Watches cgroup directories for container lifecycle events
+#[cfg(feature = "cgroup-watch")]
 pub struct ContainerWatcher {
     /// Root path for cgroup filesystem
     cgroup_root: PathBuf,
@@ -103,6 +138,7 @@ pub struct ContainerWatcher {
     event_tx: mpsc::Sender<ContainerEvent>,
 }
 
+#[cfg(feature = "cgroup-watch")]
 impl ContainerWatcher {
     /// Create a new container watcher
     pub fn new(
@@ -257,9 +293,11 @@ impl ContainerWatcher {
             container_id,
             pod_name: String::new(),
             namespace: String::new(),
-            deployment: None,
+            owner: None,
             node_name: String::new(),
             cgroup_path: path_str.to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
         })
     }
 
@@ -284,21 +322,24 @@ impl ContainerWatcher {
 
 /// Handle to a running watcher
 /// Stops watching when dropped
+#[cfg(feature = "cgroup-watch")]
 pub struct WatcherHandle {
     _watcher: RecommendedWatcher,
     _task: tokio::task::JoinHandle<()>,
 }
 
 /// Kubernetes metadata fetcher
-/// Queries the Kubernetes API for pod/deployment labels
+/// Queries the Kubernetes API for pod owners, labels, autoscalers and
+/// disruption budgets
+#[cfg(feature = "kube-api")]
 pub struct K8sMetadataFetcher {
     /// Kubernetes API endpoint (typically from in-cluster config)
-    #[allow(dead_code)]
     api_endpoint: String,
     /// Service account token path
     token_path: PathBuf,
 }
 
+#[cfg(feature = "kube-api")]
 impl K8sMetadataFetcher {
     /// Create a new metadata fetcher with in-cluster configuration
     pub fn in_cluster() -> Self {
@@ -323,16 +364,16 @@ impl K8sMetadataFetcher {
     }
 
     /// Fetch metadata for a container
-    /// Returns (pod_name, namespace, deployment)
+    /// Returns (pod_name, namespace, owner)
     pub async fn fetch_metadata(
         &self,
         container_id: &str,
-    ) -> Result<(String, String, Option<String>)> {
+    ) -> Result<(String, String, Option<OwnerRef>)> {
         // In a full implementation, this would:
         // 1. Read the service account token
         // 2. Query the Kubernetes API for pods on this node
         // 3. Match container ID to pod
-        // 4. Extract deployment from owner references
+        // 4. Extract the owning workload with `pod_workload`
 
         // For now, return placeholder - full implementation requires HTTP client
         warn!(
@@ -347,6 +388,222 @@ impl K8sMetadataFetcher {
     pub fn is_in_cluster(&self) -> bool {
         self.token_path.exists()
     }
+
+    /// Fetch the HPA scaling a deployment on CPU utilization, if any
+    pub async fn fetch_hpa(&self, namespace: &str, deployment: &str) -> Result<Option<HpaTarget>> {
+        let path = format!(
+            "/apis/autoscaling/v2/namespaces/{}/horizontalpodautoscalers",
+            namespace
+        );
+        let list = self
+            .get(&path)
+            .await
+            .with_context(|| format!("Failed to list HPAs in {}", namespace))?;
+        Ok(hpa_for_deployment(&list, deployment))
+    }
+
+    /// Fetch the PodDisruptionBudgets selecting pods with `labels`
+    pub async fn fetch_disruption_budgets(
+        &self,
+        namespace: &str,
+        labels: &HashMap<String, String>,
+    ) -> Result<Vec<DisruptionBudget>> {
+        let path = format!(
+            "/apis/policy/v1/namespaces/{}/poddisruptionbudgets",
+            namespace
+        );
+        let list = self
+            .get(&path)
+            .await
+            .with_context(|| format!("Failed to list PodDisruptionBudgets in {}", namespace))?;
+        Ok(disruption_budgets_for_pod(&list, labels))
+    }
+
+    /// GET an API path with the service account's credentials
+    async fn get(&self, path: &str) -> Result<serde_json::Value> {
+        let token = tokio::fs::read_to_string(&self.token_path)
+            .await
+            .context("Failed to read service account token")?;
+        let mut builder = reqwest::Client::builder();
+        let ca_path = self.token_path.with_file_name("ca.crt");
+        if let Ok(pem) = tokio::fs::read(&ca_path).await {
+            let cert = reqwest::Certificate::from_pem(&pem)
+                .with_context(|| format!("Invalid CA certificate {}", ca_path.display()))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        let client = builder
+            .build()
+            .context("Failed to build Kubernetes client")?;
+
+        client
+            .get(format!("{}{}", self.api_endpoint, path))
+            .bearer_auth(token.trim())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)?
+            .json()
+            .await
+            .context("Invalid API response")
+    }
+}
+
+/// Find the CPU utilization HPA targeting a deployment in an
+/// `autoscaling/v2` HorizontalPodAutoscalerList
+#[cfg_attr(not(feature = "kube-api"), allow(dead_code))]
+fn hpa_for_deployment(list: &serde_json::Value, deployment: &str) -> Option<HpaTarget> {
+    list["items"].as_array()?.iter().find_map(|hpa| {
+        let spec = &hpa["spec"];
+        let target = &spec["scaleTargetRef"];
+        if target["kind"] != "Deployment" || target["name"] != deployment {
+            return None;
+        }
+        let utilization = spec["metrics"].as_array()?.iter().find_map(|metric| {
+            let resource = &metric["resource"];
+            (metric["type"] == "Resource"
+                && resource["name"] == "cpu"
+                && resource["target"]["type"] == "Utilization")
+                .then(|| resource["target"]["averageUtilization"].as_u64())
+                .flatten()
+        })?;
+        let replicas = |value: &serde_json::Value, default: u64| {
+            value.as_u64().unwrap_or(default).min(u32::MAX as u64) as u32
+        };
+        Some(HpaTarget {
+            name: hpa["metadata"]["name"].as_str()?.to_string(),
+            min_replicas: replicas(&spec["minReplicas"], 1),
+            max_replicas: replicas(&spec["maxReplicas"], 0),
+            current_replicas: replicas(&hpa["status"]["currentReplicas"], 0),
+            target_cpu_utilization_percent: utilization.min(u32::MAX as u64) as u32,
+        })
+    })
+}
+
+/// Whether a label selector (`matchLabels` and `matchExpressions`) selects
+/// `labels`
+///
+/// An empty selector selects every pod, like in a `policy/v1` budget.
+pub fn selector_matches(selector: &serde_json::Value, labels: &HashMap<String, String>) -> bool {
+    let match_labels = selector["matchLabels"].as_object().into_iter().flatten();
+    let labels_match = match_labels
+        .into_iter()
+        .all(|(key, value)| labels.get(key).map(String::as_str) == value.as_str());
+    let expressions = selector["matchExpressions"]
+        .as_array()
+        .into_iter()
+        .flatten();
+    labels_match
+        && expressions.into_iter().all(|expression| {
+            let Some(key) = expression["key"].as_str() else {
+                return false;
+            };
+            let value = labels.get(key);
+            let in_values = || {
+                expression["values"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .any(|v| value.map(String::as_str) == v.as_str())
+            };
+            match expression["operator"].as_str() {
+                Some("In") => in_values(),
+                Some("NotIn") => !in_values(),
+                Some("Exists") => value.is_some(),
+                Some("DoesNotExist") => value.is_none(),
+                _ => false,
+            }
+        })
+}
+
+/// Budgets selecting pods with `labels` in a `policy/v1`
+/// PodDisruptionBudgetList
+pub fn disruption_budgets_for_pod(
+    list: &serde_json::Value,
+    labels: &HashMap<String, String>,
+) -> Vec<DisruptionBudget> {
+    let count = |value: &serde_json::Value| value.as_u64().unwrap_or(0).min(u32::MAX as u64) as u32;
+    list["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|pdb| selector_matches(&pdb["spec"]["selector"], labels))
+        .filter_map(|pdb| {
+            let status = &pdb["status"];
+            Some(DisruptionBudget {
+                name: pdb["metadata"]["name"].as_str()?.to_string(),
+                disruptions_allowed: count(&status["disruptionsAllowed"]),
+                current_healthy: count(&status["currentHealthy"]),
+                desired_healthy: count(&status["desiredHealthy"]),
+            })
+        })
+        .collect()
+}
+
+/// Names and roles of a pod's containers, keyed by runtime container ID
+///
+/// Roles come from the pod spec, IDs from the container statuses; containers
+/// that have not started yet have no ID and are left out.
+pub fn pod_container_roles(pod: &serde_json::Value) -> HashMap<String, (String, ContainerRole)> {
+    let spec = &pod["spec"];
+    let mut roles = HashMap::new();
+    for container in spec["containers"].as_array().into_iter().flatten() {
+        if let Some(name) = container["name"].as_str() {
+            roles.insert(name, ContainerRole::from_name(name));
+        }
+    }
+    for container in spec["initContainers"].as_array().into_iter().flatten() {
+        if let Some(name) = container["name"].as_str() {
+            let role = ContainerRole::from_init_container(container["restartPolicy"].as_str());
+            roles.insert(name, role);
+        }
+    }
+
+    let status = &pod["status"];
+    ["containerStatuses", "initContainerStatuses"]
+        .iter()
+        .flat_map(|field| status[*field].as_array().into_iter().flatten())
+        .filter_map(|container| {
+            let name = container["name"].as_str()?;
+            // "containerd://<id>", "docker://<id>" or "cri-o://<id>"
+            let id = container["containerID"].as_str()?.rsplit("://").next()?;
+            let role = roles.get(name).copied().unwrap_or_default();
+            Some((id.to_string(), (name.to_string(), role)))
+        })
+        .collect()
+}
+
+/// Workload owning a pod, from its controller owner reference
+///
+/// Deployments and Argo Rollouts name their ReplicaSets `<owner>-<pod
+/// template hash>` and label the pods with that hash, as
+/// `pod-template-hash` and `rollouts-pod-template-hash` respectively. A
+/// ReplicaSet whose name and labels don't agree on a hash is the workload.
+pub fn pod_workload(pod: &serde_json::Value) -> Option<OwnerRef> {
+    let metadata = &pod["metadata"];
+    let owner = metadata["ownerReferences"]
+        .as_array()?
+        .iter()
+        .filter(|owner| owner["controller"] == true)
+        .find_map(|owner| {
+            OwnerRef::from_controller(owner["kind"].as_str()?, owner["name"].as_str()?)
+        })?;
+    if owner.kind != WorkloadKind::ReplicaSet {
+        return Some(owner);
+    }
+    let managed = [
+        (ROLLOUTS_POD_TEMPLATE_HASH_LABEL, WorkloadKind::Rollout),
+        (POD_TEMPLATE_HASH_LABEL, WorkloadKind::Deployment),
+    ]
+    .into_iter()
+    .find_map(|(label, kind)| {
+        let hash = metadata["labels"][label].as_str()?;
+        let name = owner
+            .name
+            .strip_suffix(hash)?
+            .strip_suffix('-')
+            .filter(|name| !name.is_empty())?;
+        Some(OwnerRef::new(kind, name))
+    });
+    Some(managed.unwrap_or(owner))
 }
 
 /// Perform initial container discovery by scanning cgroup filesystem
@@ -380,9 +637,11 @@ mod tests {
             container_id: "abc123".to_string(),
             pod_name: "test-pod".to_string(),
             namespace: "default".to_string(),
-            deployment: Some("test-deploy".to_string()),
+            owner: Some(OwnerRef::deployment("test-deploy")),
             node_name: String::new(),
             cgroup_path: "/test/path".to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
         };
 
         registry.register(info.clone());
@@ -404,9 +663,11 @@ mod tests {
             container_id: "abc123".to_string(),
             pod_name: String::new(),
             namespace: String::new(),
-            deployment: None,
+            owner: None,
             node_name: String::new(),
             cgroup_path: "/test/path".to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
         };
 
         registry.register(info);
@@ -415,19 +676,232 @@ mod tests {
             "abc123",
             Some("updated-pod".to_string()),
             Some("kube-system".to_string()),
-            Some("my-deployment".to_string()),
+            Some(OwnerRef::new(WorkloadKind::StatefulSet, "my-statefulset")),
         );
 
         let retrieved = registry.get("abc123").unwrap();
         assert_eq!(retrieved.pod_name, "updated-pod");
         assert_eq!(retrieved.namespace, "kube-system");
-        assert_eq!(retrieved.deployment, Some("my-deployment".to_string()));
+        assert_eq!(
+            retrieved.owner,
+            Some(OwnerRef::new(WorkloadKind::StatefulSet, "my-statefulset"))
+        );
     }
 
+    #[cfg(feature = "kube-api")]
     #[test]
     fn test_k8s_metadata_fetcher_in_cluster_detection() {
         let fetcher = K8sMetadataFetcher::in_cluster();
         // In test environment, we're not in a cluster
         assert!(!fetcher.is_in_cluster());
     }
+
+    #[test]
+    fn test_hpa_for_deployment() {
+        let list = serde_json::json!({
+            "apiVersion": "autoscaling/v2",
+            "kind": "HorizontalPodAutoscalerList",
+            "items": [
+                {
+                    "metadata": {"name": "worker"},
+                    "spec": {
+                        "scaleTargetRef": {"apiVersion": "apps/v1", "kind": "Deployment", "name": "worker"},
+                        "maxReplicas": 5,
+                        "metrics": [{"type": "Resource", "resource": {"name": "cpu", "target": {"type": "Utilization", "averageUtilization": 60}}}]
+                    }
+                },
+                {
+                    "metadata": {"name": "api-hpa"},
+                    "spec": {
+                        "scaleTargetRef": {"apiVersion": "apps/v1", "kind": "Deployment", "name": "api"},
+                        "minReplicas": 2,
+                        "maxReplicas": 10,
+                        "metrics": [
+                            {"type": "Resource", "resource": {"name": "memory", "target": {"type": "Utilization", "averageUtilization": 80}}},
+                            {"type": "Resource", "resource": {"name": "cpu", "target": {"type": "Utilization", "averageUtilization": 70}}}
+                        ]
+                    },
+                    "status": {"currentReplicas": 4}
+                }
+            ]
+        });
+
+        let hpa = hpa_for_deployment(&list, "api").unwrap();
+        assert_eq!(
+            hpa,
+            HpaTarget {
+                name: "api-hpa".to_string(),
+                min_replicas: 2,
+                max_replicas: 10,
+                current_replicas: 4,
+                target_cpu_utilization_percent: 70,
+            }
+        );
+        assert_eq!(hpa_for_deployment(&list, "worker").unwrap().min_replicas, 1);
+        assert!(hpa_for_deployment(&list, "web").is_none());
+    }
+
+    #[test]
+    fn test_disruption_budgets_for_pod() {
+        let list = serde_json::json!({
+            "apiVersion": "policy/v1",
+            "kind": "PodDisruptionBudgetList",
+            "items": [
+                {
+                    "metadata": {"name": "api-pdb"},
+                    "spec": {"minAvailable": 3, "selector": {"matchLabels": {"app": "api"}}},
+                    "status": {"disruptionsAllowed": 0, "currentHealthy": 3, "desiredHealthy": 3}
+                },
+                {
+                    "metadata": {"name": "tier-pdb"},
+                    "spec": {
+                        "maxUnavailable": 1,
+                        "selector": {"matchExpressions": [
+                            {"key": "tier", "operator": "In", "values": ["frontend", "edge"]},
+                            {"key": "canary", "operator": "DoesNotExist"}
+                        ]}
+                    },
+                    "status": {"disruptionsAllowed": 1, "currentHealthy": 8, "desiredHealthy": 7}
+                }
+            ]
+        });
+        let labels = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        let budgets =
+            disruption_budgets_for_pod(&list, &labels(&[("app", "api"), ("tier", "edge")]));
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets[0].name, "api-pdb");
+        assert_eq!(budgets[0].disruptions_allowed, 0);
+        assert_eq!(budgets[1].desired_healthy, 7);
+
+        let canary = labels(&[("tier", "frontend"), ("canary", "true")]);
+        assert!(disruption_budgets_for_pod(&list, &canary).is_empty());
+        assert!(selector_matches(&serde_json::json!({}), &canary));
+    }
+
+    #[test]
+    fn test_pod_container_roles() {
+        let pod = serde_json::json!({
+            "spec": {
+                "initContainers": [
+                    {"name": "migrate"},
+                    {"name": "log-shipper", "restartPolicy": "Always"}
+                ],
+                "containers": [{"name": "app"}, {"name": "istio-proxy"}]
+            },
+            "status": {
+                "initContainerStatuses": [
+                    {"name": "migrate", "containerID": "containerd://aaa"},
+                    {"name": "log-shipper", "containerID": "containerd://bbb"}
+                ],
+                "containerStatuses": [
+                    {"name": "app", "containerID": "containerd://ccc"},
+                    {"name": "istio-proxy", "containerID": "containerd://ddd"}
+                ]
+            }
+        });
+
+        let roles = pod_container_roles(&pod);
+        assert_eq!(roles.len(), 4);
+        assert_eq!(roles["aaa"], ("migrate".to_string(), ContainerRole::Init));
+        assert_eq!(roles["bbb"].1, ContainerRole::Sidecar);
+        assert_eq!(roles["ccc"].1, ContainerRole::Main);
+        assert_eq!(roles["ddd"].1, ContainerRole::Sidecar);
+
+        let registry = ContainerRegistry::new("test-node");
+        registry.register(ContainerInfo {
+            container_id: "aaa".to_string(),
+            pod_name: String::new(),
+            namespace: String::new(),
+            owner: None,
+            node_name: String::new(),
+            cgroup_path: String::new(),
+            container_name: None,
+            role: ContainerRole::Main,
+        });
+        let (name, role) = roles["aaa"].clone();
+        registry.update_role("aaa", name, role);
+        assert_eq!(registry.get("aaa").unwrap().role, ContainerRole::Init);
+    }
+
+    #[test]
+    fn test_pod_workload() {
+        let pod = |kind: &str, name: &str| {
+            serde_json::json!({
+                "metadata": {
+                    "ownerReferences": [{"kind": kind, "name": name, "controller": true}]
+                }
+            })
+        };
+        let labeled = |kind: &str, name: &str, labels: serde_json::Value| {
+            let mut pod = pod(kind, name);
+            pod["metadata"]["labels"] = labels;
+            pod
+        };
+
+        assert_eq!(
+            pod_workload(&labeled(
+                "ReplicaSet",
+                "api-5d8f7c9b4",
+                serde_json::json!({"pod-template-hash": "5d8f7c9b4"})
+            )),
+            Some(OwnerRef::deployment("api"))
+        );
+        assert_eq!(
+            pod_workload(&pod("StatefulSet", "db")),
+            Some(OwnerRef::new(WorkloadKind::StatefulSet, "db"))
+        );
+        assert_eq!(
+            pod_workload(&pod("DaemonSet", "node-exporter")),
+            Some(OwnerRef::new(WorkloadKind::DaemonSet, "node-exporter"))
+        );
+        assert_eq!(
+            pod_workload(&pod("Job", "migrate-schema")),
+            Some(OwnerRef::new(WorkloadKind::Job, "migrate-schema"))
+        );
+        assert_eq!(
+            pod_workload(&pod("Job", "nightly-report-28467360")),
+            Some(OwnerRef::new(WorkloadKind::CronJob, "nightly-report"))
+        );
+        assert_eq!(pod_workload(&pod("Node", "node-1")), None);
+        assert_eq!(pod_workload(&serde_json::json!({"metadata": {}})), None);
+
+        let rollout = labeled(
+            "ReplicaSet",
+            "checkout-6f7d9c8b5",
+            serde_json::json!({"rollouts-pod-template-hash": "6f7d9c8b5"}),
+        );
+        assert_eq!(
+            pod_workload(&rollout),
+            Some(OwnerRef::new(WorkloadKind::Rollout, "checkout"))
+        );
+    }
+
+    #[test]
+    fn test_pod_workload_bare_replica_set() {
+        let pod = |labels: serde_json::Value| {
+            serde_json::json!({
+                "metadata": {
+                    "labels": labels,
+                    "ownerReferences": [
+                        {"kind": "ReplicaSet", "name": "worker-pool", "controller": true}
+                    ]
+                }
+            })
+        };
+        let replica_set = Some(OwnerRef::new(WorkloadKind::ReplicaSet, "worker-pool"));
+
+        // Created directly, without a template hash
+        assert_eq!(pod_workload(&pod(serde_json::json!({}))), replica_set);
+        // A hash label the ReplicaSet name doesn't end with
+        assert_eq!(
+            pod_workload(&pod(serde_json::json!({"pod-template-hash": "5d8f7c9b4"}))),
+            replica_set
+        );
+    }
 }
@@ -0,0 +1,50 @@
+//! Errors raised while collecting container metrics
+
+use std::io;
+use std::path::PathBuf;
+
+/// Failure to collect a container's metrics
+#[derive(Debug, thiserror::Error)]
+pub enum CollectorError {
+    /// The container's cgroup is gone, it has exited
+    #[error("Cgroup not found for container {container_id}")]
+    ContainerNotFound { container_id: String },
+    /// A cgroup or proc file could not be read
+    #[error("Failed to read {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A cgroup or proc file has unexpected contents
+    #[error("Failed to parse {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+}
+
+impl CollectorError {
+    /// Whether collecting again may succeed
+    ///
+    /// Missing files mean the container has exited; only transient IO
+    /// errors are worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CollectorError::ContainerNotFound { .. } | CollectorError::Parse { .. } => false,
+            CollectorError::Io { source, .. } => crate::error::is_retryable_io(source),
+        }
+    }
+
+    /// Wrap an error reading `path`
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| CollectorError::Io { path, source }
+    }
+
+    /// Wrap an error parsing the contents of `path`
+    pub(crate) fn parse<E: std::fmt::Display>(path: impl Into<PathBuf>) -> impl FnOnce(E) -> Self {
+        let path = path.into();
+        move |e| CollectorError::Parse {
+            path,
+            message: e.to_string(),
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! Event-driven container discovery
+//!
+//! Watching cgroup directories races with the runtime: under load, a
+//! container that exits quickly can come and go between notifications.
+//! `EventDiscovery` subscribes to the runtime's own event stream instead,
+//! the containerd events API or Docker's `/events`, and keeps the cgroup
+//! watcher running as a fallback for when the runtime can't be reached.
+//! Events of both backends are merged through an `EventDeduplicator`, so
+//! each start and stop is reported once. Without the `cgroup-watch` feature
+//! only runtime events are followed.
+
+use super::discovery::ContainerEvent;
+#[cfg(feature = "cgroup-watch")]
+use super::discovery::{ContainerWatcher, WatcherHandle};
+use super::runtime::{self, RuntimeContainer, RuntimeDiscovery, RuntimeKind, StandaloneConfig};
+use anyhow::{Context, Result};
+#[cfg(feature = "grpc")]
+use prost::Message;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Delay before the first reconnect to the runtime
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between reconnects to the runtime
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Stopped containers remembered to drop repeated stops
+const STOPPED_HISTORY: usize = 4096;
+
+#[cfg(feature = "grpc")]
+/// containerd event topics subscribed to
+const CONTAINERD_TOPICS: [&str; 3] = ["/tasks/start", "/tasks/exit", "/containers/delete"];
+
+#[cfg(feature = "grpc")]
+/// `containerd.services.events.v1.SubscribeRequest`
+#[derive(Clone, PartialEq, prost::Message)]
+struct SubscribeRequest {
+    #[prost(string, repeated, tag = "1")]
+    filters: Vec<String>,
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.services.events.v1.Envelope`
+#[derive(Clone, PartialEq, prost::Message)]
+struct Envelope {
+    #[prost(message, optional, tag = "1")]
+    timestamp: Option<prost_types::Timestamp>,
+    #[prost(string, tag = "2")]
+    namespace: String,
+    #[prost(string, tag = "3")]
+    topic: String,
+    #[prost(message, optional, tag = "4")]
+    event: Option<prost_types::Any>,
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.events.TaskStart`
+#[derive(Clone, PartialEq, prost::Message)]
+struct TaskStart {
+    #[prost(string, tag = "1")]
+    container_id: String,
+    #[prost(uint32, tag = "2")]
+    pid: u32,
+}
+
+#[cfg(feature = "grpc")]
+/// Subset of `containerd.events.TaskExit`
+#[derive(Clone, PartialEq, prost::Message)]
+struct TaskExit {
+    #[prost(string, tag = "1")]
+    container_id: String,
+    /// Process ID within the task, the container ID for its init process
+    #[prost(string, tag = "2")]
+    id: String,
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.events.ContainerDelete`
+#[derive(Clone, PartialEq, prost::Message)]
+struct ContainerDelete {
+    #[prost(string, tag = "1")]
+    id: String,
+}
+
+/// Entry of the Docker `GET /events` stream
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerEvent {
+    #[serde(rename = "Type", default)]
+    kind: String,
+    #[serde(default)]
+    action: String,
+    #[serde(default)]
+    actor: DockerActor,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerActor {
+    #[serde(rename = "ID", default)]
+    id: String,
+    #[serde(default)]
+    attributes: HashMap<String, String>,
+}
+
+/// Lifecycle change reported by the runtime
+#[derive(Debug, Clone, PartialEq)]
+enum RuntimeEvent {
+    /// Container started, with its labels when the event carries them
+    Started {
+        id: String,
+        container: Option<RuntimeContainer>,
+    },
+    Stopped(String),
+}
+
+#[cfg(feature = "grpc")]
+/// Decode a containerd event envelope
+fn decode_envelope(envelope: Envelope) -> Option<RuntimeEvent> {
+    let event = envelope.event?;
+    let type_name = event.type_url.rsplit('/').next().unwrap_or_default();
+    let value = event.value.as_slice();
+    match type_name {
+        "containerd.events.TaskStart" => {
+            let start = TaskStart::decode(value).ok()?;
+            Some(RuntimeEvent::Started {
+                id: start.container_id,
+                container: None,
+            })
+        }
+        "containerd.events.TaskExit" => {
+            // Exec'd processes exit too, only the init process ends the task
+            let exit = TaskExit::decode(value).ok()?;
+            (exit.id == exit.container_id).then_some(RuntimeEvent::Stopped(exit.container_id))
+        }
+        "containerd.events.ContainerDelete" => {
+            let delete = ContainerDelete::decode(value).ok()?;
+            Some(RuntimeEvent::Stopped(delete.id))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a line of the Docker event stream
+fn parse_docker_event(line: &str) -> Option<RuntimeEvent> {
+    let event: DockerEvent = serde_json::from_str(line).ok()?;
+    if event.kind != "container" {
+        return None;
+    }
+    let id = event.actor.id;
+    match event.action.as_str() {
+        "start" => {
+            // Attributes are the container's labels plus a few of Docker's own
+            let mut labels = event.actor.attributes;
+            let name = labels
+                .remove("name")
+                .unwrap_or_else(|| runtime::short_id(&id));
+            labels.remove("image");
+            Some(RuntimeEvent::Started {
+                container: Some(RuntimeContainer {
+                    id: id.clone(),
+                    name,
+                    labels,
+                }),
+                id,
+            })
+        }
+        "die" | "destroy" => Some(RuntimeEvent::Stopped(id)),
+        _ => None,
+    }
+}
+
+/// Drops container events another backend already reported
+#[derive(Debug, Default)]
+pub struct EventDeduplicator {
+    /// Started containers, and whether their start carried runtime metadata
+    started: HashMap<String, bool>,
+    stopped: HashSet<String>,
+    /// Stopped containers, oldest first, to bound `stopped`
+    stopped_order: VecDeque<String>,
+}
+
+impl EventDeduplicator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `event` should be passed on
+    ///
+    /// A container's first start is, and so is a later one that brings the
+    /// runtime metadata the first lacked, as when the cgroup watcher saw the
+    /// container before the runtime reported it. Only the first stop is.
+    pub fn accept(&mut self, event: &ContainerEvent) -> bool {
+        match event {
+            ContainerEvent::Started(info) => {
+                let has_metadata = !info.namespace.is_empty();
+                if let Some(&known) = self.started.get(&info.container_id) {
+                    if known || !has_metadata {
+                        return false;
+                    }
+                }
+                // A restarted container may stop again
+                if self.stopped.remove(&info.container_id) {
+                    self.stopped_order.retain(|id| *id != info.container_id);
+                }
+                self.started.insert(info.container_id.clone(), has_metadata);
+                true
+            }
+            ContainerEvent::Stopped(id) => {
+                self.started.remove(id);
+                if !self.stopped.insert(id.clone()) {
+                    return false;
+                }
+                self.stopped_order.push_back(id.clone());
+                if self.stopped_order.len() > STOPPED_HISTORY {
+                    if let Some(oldest) = self.stopped_order.pop_front() {
+                        self.stopped.remove(&oldest);
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+/// Discovers containers from runtime events, watching cgroups as a fallback
+#[cfg_attr(not(feature = "cgroup-watch"), allow(dead_code))]
+pub struct EventDiscovery {
+    discovery: RuntimeDiscovery,
+    cgroup_root: PathBuf,
+    is_v2: bool,
+}
+
+impl EventDiscovery {
+    /// Subscribe to the runtime, socket and containerd namespace of `config`
+    pub fn new(config: StandaloneConfig, cgroup_root: impl Into<PathBuf>, is_v2: bool) -> Self {
+        let cgroup_root = cgroup_root.into();
+        Self {
+            discovery: RuntimeDiscovery::new(config, cgroup_root.clone()),
+            cgroup_root,
+            is_v2,
+        }
+    }
+
+    /// Start both backends, sending deduplicated events to `event_tx`
+    ///
+    /// The runtime is reconnected to with backoff whenever its event stream
+    /// fails, while the cgroup watcher keeps reporting containers.
+    pub async fn start(self, event_tx: mpsc::Sender<ContainerEvent>) -> EventDiscoveryHandle {
+        let (raw_tx, mut raw_rx) = mpsc::channel(256);
+
+        #[cfg(feature = "cgroup-watch")]
+        let watcher = ContainerWatcher::new(self.cgroup_root.clone(), self.is_v2, raw_tx.clone());
+        #[cfg(feature = "cgroup-watch")]
+        let watcher = match watcher.start().await {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                warn!(error = %format!("{:#}", e), "Cgroup watching unavailable, relying on runtime events");
+                None
+            }
+        };
+
+        let subscription = tokio::spawn(subscribe(self.discovery, raw_tx));
+        let forward = tokio::spawn(async move {
+            let mut dedup = EventDeduplicator::new();
+            while let Some(event) = raw_rx.recv().await {
+                if dedup.accept(&event) && event_tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        EventDiscoveryHandle {
+            #[cfg(feature = "cgroup-watch")]
+            _watcher: watcher,
+            tasks: vec![subscription, forward],
+        }
+    }
+}
+
+/// Handle to running event discovery
+/// Stops discovery when dropped
+pub struct EventDiscoveryHandle {
+    #[cfg(feature = "cgroup-watch")]
+    _watcher: Option<WatcherHandle>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for EventDiscoveryHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Follow the runtime's events, reconnecting with backoff
+async fn subscribe(discovery: RuntimeDiscovery, tx: mpsc::Sender<ContainerEvent>) {
+    let runtime = discovery.config().runtime;
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        let connected_at = Instant::now();
+        let result = match runtime {
+            RuntimeKind::Docker => subscribe_docker(&discovery, &tx).await,
+            RuntimeKind::Containerd => subscribe_containerd(&discovery, &tx).await,
+        };
+        if tx.is_closed() {
+            break;
+        }
+        match result {
+            Ok(()) => debug!(runtime = ?runtime, "Runtime event stream ended"),
+            Err(e) => warn!(
+                runtime = ?runtime,
+                error = %format!("{:#}", e),
+                "Runtime event subscription failed, relying on cgroup watching"
+            ),
+        }
+
+        // A subscription that held for a while starts the backoff over
+        if connected_at.elapsed() > MAX_RECONNECT_DELAY {
+            delay = MIN_RECONNECT_DELAY;
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Pass an event on, returning whether the receiver is still there
+async fn send(tx: &mpsc::Sender<ContainerEvent>, event: Option<ContainerEvent>) -> bool {
+    match event {
+        Some(event) => tx.send(event).await.is_ok(),
+        None => true,
+    }
+}
+
+#[cfg(feature = "grpc")]
+/// Stream containerd task events until the stream ends
+async fn subscribe_containerd(
+    discovery: &RuntimeDiscovery,
+    tx: &mpsc::Sender<ContainerEvent>,
+) -> Result<()> {
+    let config = discovery.config();
+    let mut client = runtime::containerd_client(&config.socket_path).await?;
+    let mut lookup = client.clone();
+
+    let filters = CONTAINERD_TOPICS
+        .iter()
+        .map(|topic| {
+            format!(
+                r#"namespace=="{}",topic=="{}""#,
+                config.containerd_namespace, topic
+            )
+        })
+        .collect();
+    let path = tonic::codegen::http::uri::PathAndQuery::from_static(
+        "/containerd.services.events.v1.Events/Subscribe",
+    );
+    let mut stream = client
+        .server_streaming(
+            tonic::Request::new(SubscribeRequest { filters }),
+            path,
+            tonic::codec::ProstCodec::<SubscribeRequest, Envelope>::default(),
+        )
+        .await
+        .context("Failed to subscribe to containerd events")?
+        .into_inner();
+    info!(socket = %config.socket_path.display(), "Subscribed to containerd events");
+
+    while let Some(envelope) = stream
+        .message()
+        .await
+        .context("containerd event stream failed")?
+    {
+        let event = match decode_envelope(envelope) {
+            Some(RuntimeEvent::Started { id, .. }) => {
+                // Task events carry no labels, look the container up
+                let namespace = &config.containerd_namespace;
+                match runtime::get_containerd(&mut lookup, namespace, &id).await {
+                    // Containers whose cgroup is already gone are dropped
+                    Ok(container) => container
+                        .and_then(|c| discovery.resolve(c))
+                        .map(ContainerEvent::Started),
+                    Err(e) => {
+                        debug!(container_id = %id, error = %format!("{:#}", e), "Failed to look up started container");
+                        None
+                    }
+                }
+            }
+            Some(RuntimeEvent::Stopped(id)) => Some(ContainerEvent::Stopped(id)),
+            None => None,
+        };
+        if !send(tx, event).await {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Without gRPC support only the cgroup watcher reports containerd containers
+#[cfg(not(feature = "grpc"))]
+async fn subscribe_containerd(
+    _discovery: &RuntimeDiscovery,
+    _tx: &mpsc::Sender<ContainerEvent>,
+) -> Result<()> {
+    anyhow::bail!("containerd events require the `grpc` feature")
+}
+
+/// Stream Docker container events until the stream ends
+async fn subscribe_docker(
+    discovery: &RuntimeDiscovery,
+    tx: &mpsc::Sender<ContainerEvent>,
+) -> Result<()> {
+    let config = discovery.config();
+    let filters = r#"{"type":["container"],"event":["start","die","destroy"]}"#;
+    let path = format!(
+        "/events?filters={}",
+        url::form_urlencoded::byte_serialize(filters.as_bytes()).collect::<String>()
+    );
+    let stream = runtime::docker_request(&config.socket_path, &path).await?;
+    let mut reader = BufReader::new(stream);
+
+    // The daemon keeps the response open, read the head on its own
+    let mut head = Vec::new();
+    loop {
+        let read = reader
+            .read_until(b'\n', &mut head)
+            .await
+            .context("Failed to read Docker API response")?;
+        if read == 0 {
+            anyhow::bail!("Docker closed the event stream");
+        }
+        if head.ends_with(b"\r\n\r\n") {
+            break;
+        }
+    }
+    runtime::http_body(&head)?;
+    info!(socket = %config.socket_path.display(), "Subscribed to Docker events");
+
+    let mut lines = reader.lines();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .context("Docker event stream failed")?
+    {
+        let event = match parse_docker_event(&line) {
+            Some(RuntimeEvent::Started { container, .. }) => container
+                .and_then(|c| discovery.resolve(c))
+                .map(ContainerEvent::Started),
+            Some(RuntimeEvent::Stopped(id)) => Some(ContainerEvent::Stopped(id)),
+            None => None,
+        };
+        if !send(tx, event).await {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContainerInfo, ContainerRole};
+
+    #[cfg(feature = "grpc")]
+    fn envelope(type_url: &str, value: Vec<u8>) -> Envelope {
+        Envelope {
+            timestamp: None,
+            namespace: "k8s.io".to_string(),
+            topic: String::new(),
+            event: Some(prost_types::Any {
+                type_url: type_url.to_string(),
+                value,
+            }),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "grpc")]
+    fn test_decode_containerd_events() {
+        let start = TaskStart {
+            container_id: "abc".to_string(),
+            pid: 42,
+        };
+        assert_eq!(
+            decode_envelope(envelope(
+                "containerd.events.TaskStart",
+                start.encode_to_vec()
+            )),
+            Some(RuntimeEvent::Started {
+                id: "abc".to_string(),
+                container: None,
+            })
+        );
+
+        // Only the exit of the init process stops the container
+        let exit = |id: &str| TaskExit {
+            container_id: "abc".to_string(),
+            id: id.to_string(),
+        };
+        let type_url = "types.containerd.io/containerd.events.TaskExit";
+        assert_eq!(
+            decode_envelope(envelope(type_url, exit("abc").encode_to_vec())),
+            Some(RuntimeEvent::Stopped("abc".to_string()))
+        );
+        assert_eq!(
+            decode_envelope(envelope(type_url, exit("exec-1").encode_to_vec())),
+            None
+        );
+        assert_eq!(
+            decode_envelope(envelope("containerd.events.ImageCreate", vec![])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_docker_event() {
+        let line = r#"{"status":"start","id":"abc","Type":"container","Action":"start",
+            "Actor":{"ID":"abc","Attributes":{"image":"nginx","name":"web-1","com.docker.compose.service":"web"}},
+            "time":1700000000}"#;
+        let Some(RuntimeEvent::Started {
+            container: Some(container),
+            ..
+        }) = parse_docker_event(line)
+        else {
+            panic!("not a start");
+        };
+        assert_eq!(container.name, "web-1");
+        assert_eq!(container.labels.len(), 1);
+
+        let line = r#"{"Type":"container","Action":"die","Actor":{"ID":"abc","Attributes":{}}}"#;
+        assert_eq!(
+            parse_docker_event(line),
+            Some(RuntimeEvent::Stopped("abc".to_string()))
+        );
+        let line = r#"{"Type":"network","Action":"connect","Actor":{"ID":"n1"}}"#;
+        assert_eq!(parse_docker_event(line), None);
+    }
+
+    fn info(namespace: &str) -> ContainerInfo {
+        ContainerInfo {
+            container_id: "abc".to_string(),
+            pod_name: String::new(),
+            namespace: namespace.to_string(),
+            owner: None,
+            node_name: String::new(),
+            cgroup_path: String::new(),
+            container_name: None,
+            role: ContainerRole::Main,
+        }
+    }
+
+    #[test]
+    fn test_deduplicates_backends() {
+        let mut dedup = EventDeduplicator::new();
+
+        // The cgroup watcher sees the container first, the runtime adds labels
+        assert!(dedup.accept(&ContainerEvent::Started(info(""))));
+        assert!(dedup.accept(&ContainerEvent::Started(info("shop"))));
+        assert!(!dedup.accept(&ContainerEvent::Started(info(""))));
+        assert!(!dedup.accept(&ContainerEvent::Started(info("shop"))));
+
+        let stopped = ContainerEvent::Stopped("abc".to_string());
+        assert!(dedup.accept(&stopped));
+        assert!(!dedup.accept(&stopped));
+
+        // Restarted under the same ID
+        assert!(dedup.accept(&ContainerEvent::Started(info("shop"))));
+        assert!(dedup.accept(&stopped));
+    }
+}
@@ -3,12 +3,16 @@
 //! Implements the main collection loop that periodically gathers metrics
 //! from all active containers with configurable intervals and jitter.
 
-use super::{ContainerRegistry, MetricsCollector};
+use super::{CollectorError, ContainerRegistry, MetricsCollector};
+use crate::health::ComponentReporter;
+use crate::live::LiveFeed;
 use crate::models::ContainerMetrics;
+use crate::self_limit::DegradationLevel;
+use crate::sync::{next_update, RuntimeConfig};
 use anyhow::Result;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio::time::{interval, Instant};
 use tracing::{debug, info, warn};
 
@@ -51,6 +55,16 @@ pub struct CollectionLoop {
     metrics_tx: mpsc::Sender<ContainerMetrics>,
     /// Whether running in degraded mode
     degraded_mode: bool,
+    /// Config pushed by the control plane
+    runtime: Option<watch::Receiver<RuntimeConfig>>,
+    /// Locally configured interval, restored when the override is cleared
+    local_interval: Duration,
+    /// Health reporting for the collector component
+    health: Option<ComponentReporter>,
+    /// Degradation level set by the self limiter
+    degradation: Option<watch::Receiver<DegradationLevel>>,
+    /// Feed that collected samples are published to
+    live: Option<LiveFeed>,
 }
 
 impl CollectionLoop {
@@ -63,16 +77,45 @@ impl CollectionLoop {
         let (metrics_tx, metrics_rx) = mpsc::channel(config.buffer_size);
 
         let loop_instance = Self {
+            local_interval: config.interval,
             collector,
             registry,
             config,
             metrics_tx,
             degraded_mode: false,
+            runtime: None,
+            health: None,
+            degradation: None,
+            live: None,
         };
 
         (loop_instance, metrics_rx)
     }
 
+    /// Apply control plane interval and namespace filter updates
+    pub fn with_runtime_config(mut self, runtime: watch::Receiver<RuntimeConfig>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Report collection health through `reporter`
+    pub fn with_health(mut self, reporter: ComponentReporter) -> Self {
+        self.health = Some(reporter);
+        self
+    }
+
+    /// Slow down and strip metadata as the self limiter sheds work
+    pub fn with_degradation(mut self, degradation: watch::Receiver<DegradationLevel>) -> Self {
+        self.degradation = Some(degradation);
+        self
+    }
+
+    /// Publish collected samples on `feed`
+    pub fn with_live_feed(mut self, feed: LiveFeed) -> Self {
+        self.live = Some(feed);
+        self
+    }
+
     /// Start the collection loop
     /// Returns a handle that can be used to stop the loop
     pub async fn run(mut self, mut shutdown: tokio::sync::broadcast::Receiver<()>) {
@@ -109,10 +152,23 @@ impl CollectionLoop {
 
                     // Check if we need to adjust collection interval
                     self.check_resource_pressure(elapsed);
+                    self.report_health(&results);
 
                     // Update ticker if interval changed
                     ticker = interval(self.current_interval());
                 }
+                update = next_update(&mut self.runtime) => {
+                    let updated = update.collection_interval.unwrap_or(self.local_interval);
+                    if updated != self.config.interval {
+                        info!(interval_secs = updated.as_secs(), "Collection interval updated");
+                        self.config.interval = updated;
+                        ticker = interval(self.current_interval());
+                    }
+                }
+                level = next_update(&mut self.degradation) => {
+                    info!(level = level.as_str(), "Collection degradation level changed");
+                    ticker = interval(self.current_interval());
+                }
                 _ = shutdown.recv() => {
                     info!("Shutting down metrics collection loop");
                     break;
@@ -128,22 +184,59 @@ impl CollectionLoop {
         } else {
             self.config.interval
         };
+        let base = base * self.degradation_level().interval_multiplier();
 
         // Add jitter to prevent thundering herd
         let jitter_ms = rand_jitter(self.config.jitter.as_millis() as u64);
         base + Duration::from_millis(jitter_ms)
     }
 
+    /// Current self limiter degradation level
+    fn degradation_level(&self) -> DegradationLevel {
+        self.degradation
+            .as_ref()
+            .map(|rx| *rx.borrow())
+            .unwrap_or_default()
+    }
+
     /// Collect metrics from all registered containers
+    #[tracing::instrument(
+        name = "collection_cycle",
+        skip_all,
+        fields(containers = tracing::field::Empty, errors = tracing::field::Empty)
+    )]
     async fn collect_all(&self) -> CollectionResults {
         let containers = self.registry.list();
         let mut results = CollectionResults::default();
+        let with_metadata = self.degradation_level().metadata_enabled();
 
+        let runtime = self.runtime.as_ref().map(|rx| rx.borrow().clone());
         for container in containers {
+            // Init containers run to completion, their usage says nothing
+            // about what the pod needs once it is running
+            if !container.role.is_predicted() {
+                continue;
+            }
+            if let Some(runtime) = &runtime {
+                if !runtime.namespace_allowed(&container.namespace) {
+                    continue;
+                }
+            }
+
             match self.collect_container(&container.container_id).await {
-                Ok(metrics) => {
+                Ok(mut metrics) => {
                     results.success_count += 1;
 
+                    // Namespace is kept, downstream filtering depends on it
+                    if !with_metadata {
+                        metrics.pod_name = String::new();
+                        metrics.owner = None;
+                    }
+
+                    if let Some(feed) = &self.live {
+                        feed.publish_metrics(&metrics);
+                    }
+
                     // Send metrics to channel
                     if let Err(e) = self.metrics_tx.send(metrics).await {
                         warn!(error = %e, "Failed to send metrics to channel");
@@ -154,20 +247,51 @@ impl CollectionLoop {
                     debug!(
                         container_id = %container.container_id,
                         error = %e,
+                        retryable = e.is_retryable(),
                         "Failed to collect metrics"
                     );
                 }
             }
         }
 
+        tracing::Span::current()
+            .record("containers", results.success_count)
+            .record("errors", results.error_count);
         results
     }
 
     /// Collect metrics for a single container
-    async fn collect_container(&self, container_id: &str) -> Result<ContainerMetrics> {
+    async fn collect_container(
+        &self,
+        container_id: &str,
+    ) -> Result<ContainerMetrics, CollectorError> {
         self.collector.collect(container_id).await
     }
 
+    /// Report the outcome of a collection cycle
+    ///
+    /// A cycle only counts as failed when no container could be collected,
+    /// individual containers disappearing mid-cycle is normal churn.
+    fn report_health(&self, results: &CollectionResults) {
+        let Some(health) = &self.health else {
+            return;
+        };
+
+        if results.error_count > 0 && results.success_count == 0 {
+            health.failure(format!(
+                "Failed to collect metrics from all {} containers",
+                results.error_count
+            ));
+        } else {
+            health.success();
+        }
+
+        health.set_condition(
+            self.degraded_mode
+                .then(|| "Collection slowed down due to resource pressure".to_string()),
+        );
+    }
+
     /// Check resource pressure and adjust collection mode
     fn check_resource_pressure(&mut self, collection_duration: Duration) {
         // Simple heuristic: if collection takes too long, we might be under pressure
@@ -215,6 +339,9 @@ pub struct CollectionLoopBuilder {
     collector: Option<Arc<dyn MetricsCollector>>,
     registry: Option<Arc<ContainerRegistry>>,
     config: CollectionConfig,
+    runtime: Option<watch::Receiver<RuntimeConfig>>,
+    health: Option<ComponentReporter>,
+    live: Option<LiveFeed>,
 }
 
 impl CollectionLoopBuilder {
@@ -224,6 +351,9 @@ impl CollectionLoopBuilder {
             collector: None,
             registry: None,
             config: CollectionConfig::default(),
+            runtime: None,
+            health: None,
+            live: None,
         }
     }
 
@@ -269,6 +399,24 @@ impl CollectionLoopBuilder {
         self
     }
 
+    /// Apply config pushed by the control plane
+    pub fn runtime_config(mut self, runtime: watch::Receiver<RuntimeConfig>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Report collection health through `reporter`
+    pub fn health(mut self, reporter: ComponentReporter) -> Self {
+        self.health = Some(reporter);
+        self
+    }
+
+    /// Publish collected samples on `feed`
+    pub fn live_feed(mut self, feed: LiveFeed) -> Self {
+        self.live = Some(feed);
+        self
+    }
+
     /// Build the collection loop
     pub fn build(self) -> Result<(CollectionLoop, mpsc::Receiver<ContainerMetrics>)> {
         let collector = self
@@ -278,7 +426,21 @@ impl CollectionLoopBuilder {
             .registry
             .ok_or_else(|| anyhow::anyhow!("Registry is required"))?;
 
-        Ok(CollectionLoop::new(collector, registry, self.config))
+        let (collection_loop, metrics_rx) = CollectionLoop::new(collector, registry, self.config);
+        let collection_loop = match self.runtime {
+            Some(runtime) => collection_loop.with_runtime_config(runtime),
+            None => collection_loop,
+        };
+        let collection_loop = match self.health {
+            Some(reporter) => collection_loop.with_health(reporter),
+            None => collection_loop,
+        };
+        let collection_loop = match self.live {
+            Some(feed) => collection_loop.with_live_feed(feed),
+            None => collection_loop,
+        };
+
+        Ok((collection_loop, metrics_rx))
     }
 }
 
@@ -291,7 +453,7 @@ impl Default for CollectionLoopBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::ContainerInfo;
+    use crate::models::{ContainerInfo, ContainerRole};
     use async_trait::async_trait;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
@@ -310,14 +472,14 @@ mod tests {
 
     #[async_trait]
     impl MetricsCollector for MockCollector {
-        async fn collect(&self, container_id: &str) -> Result<ContainerMetrics> {
+        async fn collect(&self, container_id: &str) -> Result<ContainerMetrics, CollectorError> {
             self.call_count.fetch_add(1, Ordering::SeqCst);
 
             Ok(ContainerMetrics {
                 container_id: container_id.to_string(),
                 pod_name: "test-pod".to_string(),
                 namespace: "default".to_string(),
-                deployment: None,
+                owner: None,
                 timestamp: chrono::Utc::now().timestamp(),
                 cpu_usage_cores: 0.5,
                 cpu_throttled_periods: 0,
@@ -329,7 +491,7 @@ mod tests {
             })
         }
 
-        async fn list_containers(&self) -> Result<Vec<ContainerInfo>> {
+        async fn list_containers(&self) -> Result<Vec<ContainerInfo>, CollectorError> {
             Ok(vec![])
         }
     }
@@ -398,18 +560,22 @@ mod tests {
             container_id: "container1".to_string(),
             pod_name: "pod1".to_string(),
             namespace: "default".to_string(),
-            deployment: None,
+            owner: None,
             node_name: String::new(),
             cgroup_path: "/test/path1".to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
         });
 
         registry.register(ContainerInfo {
             container_id: "container2".to_string(),
             pod_name: "pod2".to_string(),
             namespace: "default".to_string(),
-            deployment: None,
+            owner: None,
             node_name: String::new(),
             cgroup_path: "/test/path2".to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
         });
 
         let (collection_loop, mut rx) =
@@ -427,4 +593,76 @@ mod tests {
         assert!(metrics1.container_id == "container1" || metrics1.container_id == "container2");
         assert!(metrics2.container_id == "container1" || metrics2.container_id == "container2");
     }
+
+    #[tokio::test]
+    async fn test_collect_all_skips_init_containers() {
+        let collector = Arc::new(MockCollector::new());
+        let registry = Arc::new(ContainerRegistry::new("test-node"));
+
+        for (id, role) in [
+            ("app", ContainerRole::Main),
+            ("migrate", ContainerRole::Init),
+        ] {
+            registry.register(ContainerInfo {
+                container_id: id.to_string(),
+                pod_name: "pod1".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                node_name: String::new(),
+                cgroup_path: String::new(),
+                container_name: Some(id.to_string()),
+                role,
+            });
+        }
+
+        let (collection_loop, mut rx) =
+            CollectionLoop::new(collector, registry, CollectionConfig::default());
+
+        let results = collection_loop.collect_all().await;
+
+        assert_eq!(results.success_count, 1);
+        assert_eq!(rx.try_recv().unwrap().container_id, "app");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_health() {
+        use crate::health::{ComponentStatus, HealthPolicy, HealthRegistry};
+
+        let registry = HealthRegistry::new();
+        let reporter = registry
+            .reporter(
+                "collector",
+                HealthPolicy {
+                    degraded_after_failures: 1,
+                    ..HealthPolicy::default()
+                },
+            )
+            .await;
+        let (mut collection_loop, _rx) = CollectionLoop::new(
+            Arc::new(MockCollector::new()),
+            Arc::new(ContainerRegistry::new("test-node")),
+            CollectionConfig::default(),
+        );
+        collection_loop = collection_loop.with_health(reporter.clone());
+
+        // Partial failures are container churn, total failure is not
+        collection_loop.report_health(&CollectionResults {
+            success_count: 3,
+            error_count: 1,
+        });
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Healthy);
+
+        collection_loop.report_health(&CollectionResults {
+            success_count: 0,
+            error_count: 4,
+        });
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Degraded);
+
+        collection_loop.degraded_mode = true;
+        collection_loop.report_health(&CollectionResults::default());
+        let health = reporter.evaluate();
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert!(health.message.unwrap().contains("resource pressure"));
+    }
 }
@@ -2,23 +2,46 @@
 //!
 //! This module provides collectors for reading container resource metrics
 //! from cgroup filesystems. It supports both cgroup v2 (unified hierarchy)
-//! and cgroup v1 (legacy hierarchy) with automatic detection.
+//! and cgroup v1 (legacy hierarchy) with automatic detection. Outside
+//! Kubernetes, containers are discovered through the Docker or containerd API.
+//! Container starts and stops are followed through runtime events, with
+//! cgroup directory watching as a fallback, and the registry is periodically
+//! reconciled against the cgroup filesystem to correct missed events.
+//! Node-wide capacity, usage and pressure are collected alongside.
 
 mod cgroup_v1;
 mod cgroup_v2;
 mod discovery;
+mod error;
+mod events;
 mod r#loop;
+mod node;
+mod reconcile;
+mod runtime;
 
 #[cfg(test)]
 mod tests;
 
 pub use cgroup_v1::{detect_cgroup_version, CgroupV1Collector, CgroupVersion};
 pub use cgroup_v2::CgroupV2Collector;
+#[cfg(feature = "kube-api")]
+pub use discovery::K8sMetadataFetcher;
 pub use discovery::{
-    discover_existing_containers, ContainerEvent, ContainerRegistry, ContainerWatcher,
-    K8sMetadataFetcher, WatcherHandle,
+    discover_existing_containers, disruption_budgets_for_pod, pod_container_roles, pod_workload,
+    selector_matches, ContainerEvent, ContainerRegistry,
 };
+#[cfg(feature = "cgroup-watch")]
+pub use discovery::{ContainerWatcher, WatcherHandle};
+pub use error::CollectorError;
+pub use events::{EventDeduplicator, EventDiscovery, EventDiscoveryHandle};
+pub use node::{parse_cpu_max, parse_meminfo, parse_pressure, NodeCollector};
 pub use r#loop::{CollectionConfig, CollectionLoop, CollectionLoopBuilder};
+pub use reconcile::{ReconcileStats, Reconciler, DEFAULT_RECONCILE_INTERVAL};
+pub use runtime::{
+    RuntimeDiscovery, RuntimeKind, StandaloneConfig, DEFAULT_CONTAINERD_SOCKET,
+    DEFAULT_DOCKER_SOCKET, DEFAULT_NAMESPACE_LABEL, DEFAULT_STANDALONE_NAMESPACE,
+    DEFAULT_WORKLOAD_LABEL,
+};
 
 use crate::models::{ContainerInfo, ContainerMetrics};
 use anyhow::Result;
@@ -31,10 +54,10 @@ pub use async_trait::async_trait;
 #[async_trait]
 pub trait MetricsCollector: Send + Sync {
     /// Collect metrics for a specific container
-    async fn collect(&self, container_id: &str) -> Result<ContainerMetrics>;
+    async fn collect(&self, container_id: &str) -> Result<ContainerMetrics, CollectorError>;
 
     /// List all active containers on the node
-    async fn list_containers(&self) -> Result<Vec<ContainerInfo>>;
+    async fn list_containers(&self) -> Result<Vec<ContainerInfo>, CollectorError>;
 }
 
 /// Create the appropriate collector based on detected cgroup version
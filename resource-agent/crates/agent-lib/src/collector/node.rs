@@ -0,0 +1,198 @@
+//! Node-level capacity, usage and pressure collection
+//!
+//! Reads node-wide figures that give container predictions context:
+//! - allocatable CPU and memory from the limits kubelet sets on the
+//!   kubepods cgroup, falling back to the node's capacity
+//! - total CPU usage from the root cgroup and memory usage from /proc/meminfo
+//! - pressure stall information (PSI) from the root cgroup or /proc/pressure
+
+use crate::models::NodeMetrics;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::fs;
+
+/// Cgroups kubelet places pods under, for the systemd and cgroupfs drivers
+const KUBEPODS_CGROUPS: [&str; 2] = ["kubepods.slice", "kubepods"];
+
+/// Collector for node-wide metrics
+pub struct NodeCollector {
+    cgroup_root: PathBuf,
+    proc_path: PathBuf,
+    /// Previous cumulative CPU usage in microseconds, to compute a rate
+    last_cpu: Mutex<Option<(Instant, u64)>>,
+}
+
+impl NodeCollector {
+    pub fn new(cgroup_root: impl Into<PathBuf>) -> Self {
+        Self::with_proc_path(cgroup_root, "/proc")
+    }
+
+    /// Create collector with custom proc path (for testing)
+    pub fn with_proc_path(cgroup_root: impl Into<PathBuf>, proc_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cgroup_root: cgroup_root.into(),
+            proc_path: proc_path.into(),
+            last_cpu: Mutex::new(None),
+        }
+    }
+
+    /// Collect a node sample
+    ///
+    /// CPU usage is a rate between consecutive calls and is zero on the
+    /// first one. Missing PSI files (kernels without PSI) read as zero.
+    pub async fn collect(&self) -> Result<NodeMetrics> {
+        let meminfo = fs::read_to_string(self.proc_path.join("meminfo"))
+            .await
+            .context("Failed to read /proc/meminfo")?;
+        let (memory_total, memory_available) = parse_meminfo(&meminfo);
+        let cpu_capacity = self.cpu_capacity().await?;
+        let (cpu_allocatable, memory_allocatable) = self.kubepods_limits().await;
+
+        let cpu_usage_cores = match self.cpu_usage_usec().await {
+            Some(usage) => self.cpu_rate(usage),
+            None => 0.0,
+        };
+
+        Ok(NodeMetrics {
+            timestamp: crate::clock::now().unix_secs(),
+            cpu_allocatable_cores: cpu_allocatable
+                .filter(|cores| *cores < cpu_capacity)
+                .unwrap_or(cpu_capacity),
+            memory_allocatable_bytes: memory_allocatable
+                .filter(|bytes| *bytes < memory_total)
+                .unwrap_or(memory_total),
+            cpu_usage_cores,
+            memory_usage_bytes: memory_total.saturating_sub(memory_available),
+            cpu_pressure: self.pressure("cpu").await,
+            memory_pressure: self.pressure("memory").await,
+            io_pressure: self.pressure("io").await,
+        })
+    }
+
+    /// Number of CPUs, from the `cpuN` lines of /proc/stat
+    async fn cpu_capacity(&self) -> Result<f32> {
+        let stat = fs::read_to_string(self.proc_path.join("stat"))
+            .await
+            .context("Failed to read /proc/stat")?;
+        let cpus = stat
+            .lines()
+            .filter(|line| {
+                line.strip_prefix("cpu")
+                    .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .count();
+        Ok(cpus.max(1) as f32)
+    }
+
+    /// CPU and memory limits of the kubepods cgroup, when set
+    async fn kubepods_limits(&self) -> (Option<f32>, Option<u64>) {
+        for name in KUBEPODS_CGROUPS {
+            let path = self.cgroup_root.join(name);
+            if let Ok(cpu_max) = fs::read_to_string(path.join("cpu.max")).await {
+                let memory_max = fs::read_to_string(path.join("memory.max"))
+                    .await
+                    .ok()
+                    .and_then(|content| content.trim().parse().ok());
+                return (parse_cpu_max(&cpu_max), memory_max);
+            }
+        }
+        (None, None)
+    }
+
+    /// Cumulative CPU usage of the node in microseconds
+    async fn cpu_usage_usec(&self) -> Option<u64> {
+        if let Ok(stat) = fs::read_to_string(self.cgroup_root.join("cpu.stat")).await {
+            return stat.lines().find_map(|line| {
+                line.strip_prefix("usage_usec ")
+                    .and_then(|value| value.trim().parse().ok())
+            });
+        }
+        // cgroup v1 reports nanoseconds
+        let usage = fs::read_to_string(self.cgroup_root.join("cpuacct/cpuacct.usage"))
+            .await
+            .ok()?;
+        usage.trim().parse::<u64>().ok().map(|ns| ns / 1000)
+    }
+
+    /// Cores used since the previous sample
+    fn cpu_rate(&self, usage_usec: u64) -> f32 {
+        let now = Instant::now();
+        let mut last = self.last_cpu.lock().unwrap_or_else(|e| e.into_inner());
+        let rate = match *last {
+            Some((at, previous)) if usage_usec >= previous => {
+                let elapsed = now.duration_since(at).as_secs_f64();
+                if elapsed > 0.0 {
+                    ((usage_usec - previous) as f64 / 1e6 / elapsed) as f32
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        };
+        *last = Some((now, usage_usec));
+        rate
+    }
+
+    /// PSI `some avg10` for a resource, in percent of wall time
+    async fn pressure(&self, resource: &str) -> f32 {
+        let paths = [
+            self.cgroup_root.join(format!("{}.pressure", resource)),
+            self.proc_path.join("pressure").join(resource),
+        ];
+        for path in &paths {
+            if let Some(value) = read_pressure(path).await {
+                return value;
+            }
+        }
+        0.0
+    }
+}
+
+async fn read_pressure(path: &Path) -> Option<f32> {
+    parse_pressure(&fs::read_to_string(path).await.ok()?)
+}
+
+/// Parse the `some avg10` value of a PSI file
+///
+/// ```text
+/// some avg10=1.53 avg60=0.87 avg300=0.22 total=2207344
+/// full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+/// ```
+pub fn parse_pressure(content: &str) -> Option<f32> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+/// Parse `MemTotal` and `MemAvailable` from /proc/meminfo, in bytes
+pub fn parse_meminfo(content: &str) -> (u64, u64) {
+    let field = |name: &str| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| {
+                rest.trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .map(|kb| kb * 1024)
+            .unwrap_or(0)
+    };
+    (field("MemTotal:"), field("MemAvailable:"))
+}
+
+/// Parse a cgroup v2 `cpu.max` file into cores, `None` when unlimited
+pub fn parse_cpu_max(content: &str) -> Option<f32> {
+    let mut parts = content.split_whitespace();
+    let quota: f64 = parts.next()?.parse().ok()?;
+    let period: f64 = parts.next().unwrap_or("100000").parse().ok()?;
+    (period > 0.0).then(|| (quota / period) as f32)
+}
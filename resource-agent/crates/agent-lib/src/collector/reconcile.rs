@@ -0,0 +1,192 @@
+//! Periodic reconciliation of the container registry
+//!
+//! Runtime events and cgroup notifications can be missed, e.g. while the
+//! agent restarts or when the inotify queue overflows. The reconciler
+//! compares the registry with a fresh cgroup scan and, when configured, the
+//! runtime's container list, registering missed containers and dropping
+//! entries whose cgroup has vanished.
+
+use super::discovery::{discover_existing_containers, ContainerRegistry};
+use super::runtime::{RuntimeDiscovery, StandaloneConfig};
+use crate::models::ContainerInfo;
+use crate::observability::AgentMetrics;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Default interval between reconciliations
+pub const DEFAULT_RECONCILE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Drift corrected by one reconciliation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileStats {
+    /// Running containers that were missing from the registry
+    pub added: usize,
+    /// Registered containers whose cgroup no longer exists
+    pub removed: usize,
+}
+
+/// Reconciles the container registry with the node's actual containers
+pub struct Reconciler {
+    cgroup_root: PathBuf,
+    is_v2: bool,
+    runtime: Option<RuntimeDiscovery>,
+    interval: Duration,
+    metrics: Option<AgentMetrics>,
+}
+
+impl Reconciler {
+    pub fn new(cgroup_root: impl Into<PathBuf>, is_v2: bool) -> Self {
+        Self {
+            cgroup_root: cgroup_root.into(),
+            is_v2,
+            runtime: None,
+            interval: DEFAULT_RECONCILE_INTERVAL,
+            metrics: None,
+        }
+    }
+
+    /// Also compare against the container runtime's listing
+    pub fn with_runtime(mut self, config: StandaloneConfig) -> Self {
+        self.runtime = Some(RuntimeDiscovery::new(config, self.cgroup_root.clone()));
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: AgentMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Bring `registry` in line with the cgroup filesystem and runtime
+    ///
+    /// Entries are only removed once their cgroup directory is gone, so a
+    /// container the scan can't recognise is never dropped. A failed runtime
+    /// listing falls back to the cgroup scan alone.
+    pub async fn reconcile(&self, registry: &ContainerRegistry) -> Result<ReconcileStats> {
+        let mut running: HashMap<String, ContainerInfo> =
+            discover_existing_containers(&self.cgroup_root, self.is_v2)
+                .await?
+                .into_iter()
+                .map(|c| (c.container_id.clone(), c))
+                .collect();
+
+        if let Some(runtime) = &self.runtime {
+            match runtime.list_containers().await {
+                // Runtime entries carry label-derived metadata, so prefer them
+                Ok(containers) => {
+                    running.extend(containers.into_iter().map(|c| (c.container_id.clone(), c)))
+                }
+                Err(e) => warn!(
+                    error = %format!("{:#}", e),
+                    "Runtime listing failed, reconciling against cgroups only"
+                ),
+            }
+        }
+
+        let mut stats = ReconcileStats::default();
+        for container in registry.list() {
+            if !running.contains_key(&container.container_id)
+                && !Path::new(&container.cgroup_path).exists()
+            {
+                registry.unregister(&container.container_id);
+                stats.removed += 1;
+            }
+        }
+        for (container_id, container) in running {
+            if registry.get(&container_id).is_none() {
+                registry.register(container);
+                stats.added += 1;
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.add_reconcile_drift("added", stats.added as u64);
+            metrics.add_reconcile_drift("removed", stats.removed as u64);
+        }
+        Ok(stats)
+    }
+
+    /// Reconcile at startup, then every interval until shutdown
+    pub async fn run(
+        self,
+        registry: Arc<ContainerRegistry>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => match self.reconcile(&registry).await {
+                    Ok(stats) if stats.added > 0 || stats.removed > 0 => info!(
+                        added = stats.added,
+                        removed = stats.removed,
+                        "Reconciled container registry drift"
+                    ),
+                    Ok(_) => {}
+                    Err(e) => warn!(error = %format!("{:#}", e), "Container registry reconciliation failed"),
+                },
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContainerRole;
+    use tempfile::TempDir;
+
+    fn container(id: &str, cgroup_path: &Path) -> ContainerInfo {
+        ContainerInfo {
+            container_id: id.to_string(),
+            pod_name: String::new(),
+            namespace: String::new(),
+            owner: None,
+            node_name: String::new(),
+            cgroup_path: cgroup_path.to_string_lossy().to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_fixes_drift() {
+        let temp_dir = TempDir::new().unwrap();
+        let kubepods = temp_dir.path().join("kubepods.slice");
+        let missed = "a".repeat(64);
+        std::fs::create_dir_all(kubepods.join(&missed)).unwrap();
+        std::fs::write(kubepods.join(&missed).join("cpu.stat"), "usage_usec 0\n").unwrap();
+        // Exists but isn't recognised by the scan, so it must be kept
+        let unscanned = temp_dir.path().join("custom");
+        std::fs::create_dir_all(&unscanned).unwrap();
+
+        let registry = ContainerRegistry::new("node");
+        registry.register(container("stale", &kubepods.join("gone")));
+        registry.register(container("unscanned", &unscanned));
+
+        let reconciler = Reconciler::new(temp_dir.path(), true);
+        let stats = reconciler.reconcile(&registry).await.unwrap();
+        assert_eq!(
+            stats,
+            ReconcileStats {
+                added: 1,
+                removed: 1
+            }
+        );
+        assert!(registry.get(&missed).is_some());
+        assert!(registry.get("unscanned").is_some());
+        assert!(registry.get("stale").is_none());
+
+        let stats = reconciler.reconcile(&registry).await.unwrap();
+        assert_eq!(stats, ReconcileStats::default());
+    }
+}
@@ -0,0 +1,679 @@
+//! Container discovery through the container runtime API
+//!
+//! Used in standalone mode, on plain Docker or containerd hosts without
+//! Kubernetes. Containers are listed from the runtime and their workload
+//! metadata is taken from container labels instead of pods:
+//! - Docker: Engine API over its unix socket
+//! - containerd: `containers.v1` gRPC service over its unix socket, with the
+//!   `grpc` feature
+//!
+//! The same clients back event-driven discovery in `events`.
+
+use super::ContainerRegistry;
+use crate::models::{ContainerInfo, ContainerRole, OwnerRef};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "grpc")]
+use std::future::Future;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "grpc")]
+use std::pin::Pin;
+#[cfg(feature = "grpc")]
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+#[cfg(feature = "grpc")]
+use tonic::codegen::Service;
+#[cfg(feature = "grpc")]
+use tonic::transport::{Endpoint, Uri};
+use tracing::{debug, info, warn};
+
+/// Default Docker Engine socket
+pub const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+/// Default containerd socket
+pub const DEFAULT_CONTAINERD_SOCKET: &str = "/run/containerd/containerd.sock";
+
+/// Default label holding the namespace a container is grouped under
+pub const DEFAULT_NAMESPACE_LABEL: &str = "kubewise.namespace";
+
+/// Default label holding the workload a container belongs to
+pub const DEFAULT_WORKLOAD_LABEL: &str = "kubewise.workload";
+
+/// Namespace for containers without a namespace label
+pub const DEFAULT_STANDALONE_NAMESPACE: &str = "standalone";
+
+/// Compose labels used when the configured labels are absent
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+const COMPOSE_SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// Container name label set by nerdctl on containerd
+#[cfg(feature = "grpc")]
+const NERDCTL_NAME_LABEL: &str = "nerdctl/name";
+
+/// Container name within its pod, set by the kubelet through the CRI
+const CRI_CONTAINER_NAME_LABEL: &str = "io.kubernetes.container.name";
+
+/// UID of the container's pod, set by the kubelet through the CRI
+const CRI_POD_UID_LABEL: &str = "io.kubernetes.pod.uid";
+
+/// Maximum size of a Docker API response
+const MAX_DOCKER_RESPONSE: usize = 16 * 1024 * 1024;
+
+/// Container runtime queried for containers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeKind {
+    Docker,
+    Containerd,
+}
+
+impl RuntimeKind {
+    /// Default API socket of the runtime
+    pub fn default_socket(&self) -> &'static str {
+        match self {
+            RuntimeKind::Docker => DEFAULT_DOCKER_SOCKET,
+            RuntimeKind::Containerd => DEFAULT_CONTAINERD_SOCKET,
+        }
+    }
+}
+
+/// Configuration for runtime-based discovery
+#[derive(Debug, Clone)]
+pub struct StandaloneConfig {
+    pub runtime: RuntimeKind,
+    /// Runtime API socket
+    pub socket_path: PathBuf,
+    /// containerd namespace to list containers from
+    pub containerd_namespace: String,
+    /// Label mapped to the container's namespace
+    pub namespace_label: String,
+    /// Label mapped to the container's workload, treated as a deployment
+    pub workload_label: String,
+    /// Namespace for containers without a namespace label
+    pub default_namespace: String,
+    /// How often the runtime is polled for started and stopped containers
+    pub poll_interval: Duration,
+}
+
+impl Default for StandaloneConfig {
+    fn default() -> Self {
+        Self {
+            runtime: RuntimeKind::Docker,
+            socket_path: PathBuf::from(DEFAULT_DOCKER_SOCKET),
+            containerd_namespace: "default".to_string(),
+            namespace_label: DEFAULT_NAMESPACE_LABEL.to_string(),
+            workload_label: DEFAULT_WORKLOAD_LABEL.to_string(),
+            default_namespace: DEFAULT_STANDALONE_NAMESPACE.to_string(),
+            poll_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+/// A container as reported by the runtime
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RuntimeContainer {
+    pub(super) id: String,
+    pub(super) name: String,
+    pub(super) labels: HashMap<String, String>,
+}
+
+/// Entry of the Docker `GET /containers/json` response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerContainer {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+}
+
+/// Discovers containers from the container runtime
+pub struct RuntimeDiscovery {
+    config: StandaloneConfig,
+    cgroup_root: PathBuf,
+}
+
+impl RuntimeDiscovery {
+    pub fn new(config: StandaloneConfig, cgroup_root: impl Into<PathBuf>) -> Self {
+        Self {
+            config,
+            cgroup_root: cgroup_root.into(),
+        }
+    }
+
+    pub fn config(&self) -> &StandaloneConfig {
+        &self.config
+    }
+
+    /// List running containers with label-derived metadata
+    ///
+    /// Containers whose cgroup can't be found are skipped, as nothing could
+    /// be collected for them.
+    pub async fn list_containers(&self) -> Result<Vec<ContainerInfo>> {
+        let containers = match self.config.runtime {
+            RuntimeKind::Docker => list_docker(&self.config.socket_path).await?,
+            RuntimeKind::Containerd => {
+                list_containerd(&self.config.socket_path, &self.config.containerd_namespace).await?
+            }
+        };
+
+        Ok(containers
+            .into_iter()
+            .filter_map(|container| self.resolve(container))
+            .collect())
+    }
+
+    /// Container info of a runtime container, if its cgroup can be found
+    pub(super) fn resolve(&self, container: RuntimeContainer) -> Option<ContainerInfo> {
+        let cgroup_path = self.resolve_cgroup_path(&container.id).or_else(|| {
+            let pod_uid = container.labels.get(CRI_POD_UID_LABEL)?;
+            self.resolve_pod_cgroup_path(&container.id, pod_uid)
+        });
+        if cgroup_path.is_none() {
+            debug!(container_id = %container.id, "No cgroup found for container");
+        }
+        Some(self.container_info(container, cgroup_path?))
+    }
+
+    /// Bring `registry` in line with the runtime's containers
+    ///
+    /// Returns the number of containers added and removed.
+    pub async fn sync_registry(&self, registry: &ContainerRegistry) -> Result<(usize, usize)> {
+        let containers = self.list_containers().await?;
+        let running: HashSet<String> = containers.iter().map(|c| c.container_id.clone()).collect();
+
+        let mut added = 0;
+        for container in containers {
+            if registry.get(&container.container_id).is_none() {
+                added += 1;
+            }
+            registry.register(container);
+        }
+
+        let mut removed = 0;
+        for container in registry.list() {
+            if !running.contains(&container.container_id) {
+                registry.unregister(&container.container_id);
+                removed += 1;
+            }
+        }
+
+        Ok((added, removed))
+    }
+
+    /// Poll the runtime and keep `registry` up to date until shutdown
+    pub async fn run(
+        self,
+        registry: std::sync::Arc<ContainerRegistry>,
+        mut shutdown: tokio::sync::broadcast::Receiver<()>,
+    ) {
+        info!(
+            runtime = ?self.config.runtime,
+            socket = %self.config.socket_path.display(),
+            "Starting runtime container discovery"
+        );
+
+        let mut ticker = tokio::time::interval(self.config.poll_interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => match self.sync_registry(&registry).await {
+                    Ok((added, removed)) if added > 0 || removed > 0 => {
+                        debug!(added = added, removed = removed, "Container registry updated");
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!(error = %format!("{:#}", e), "Runtime container discovery failed"),
+                },
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+
+    /// Map runtime labels onto pod-style metadata
+    pub(super) fn container_info(
+        &self,
+        container: RuntimeContainer,
+        cgroup_path: PathBuf,
+    ) -> ContainerInfo {
+        let labels = &container.labels;
+        let namespace = labels
+            .get(&self.config.namespace_label)
+            .or_else(|| labels.get(COMPOSE_PROJECT_LABEL))
+            .cloned()
+            .unwrap_or_else(|| self.config.default_namespace.clone());
+        let owner = labels
+            .get(&self.config.workload_label)
+            .or_else(|| labels.get(COMPOSE_SERVICE_LABEL))
+            .map(OwnerRef::deployment);
+        let container_name = labels.get(CRI_CONTAINER_NAME_LABEL).cloned();
+        let role = ContainerRole::from_name(container_name.as_deref().unwrap_or(&container.name));
+
+        ContainerInfo {
+            pod_name: container.name,
+            namespace,
+            owner,
+            node_name: String::new(),
+            cgroup_path: cgroup_path.to_string_lossy().to_string(),
+            container_id: container.id,
+            container_name,
+            role,
+        }
+    }
+
+    /// Find a container's cgroup under the common Docker and containerd layouts
+    fn resolve_cgroup_path(&self, id: &str) -> Option<PathBuf> {
+        let candidates = [
+            // systemd cgroup driver
+            format!("system.slice/docker-{}.scope", id),
+            format!("system.slice/nerdctl-{}.scope", id),
+            format!("system.slice/containerd-{}.scope", id),
+            // cgroupfs driver
+            format!("docker/{}", id),
+            format!("{}/{}", self.config.containerd_namespace, id),
+        ];
+
+        self.find_cgroup(&candidates)
+    }
+
+    /// Find the cgroup of a Kubernetes pod's container, in any QoS class
+    fn resolve_pod_cgroup_path(&self, id: &str, pod_uid: &str) -> Option<PathBuf> {
+        // systemd slice names can't hold dashes
+        let slice_uid = pod_uid.replace('-', "_");
+        let mut candidates = Vec::new();
+        for qos in ["", "burstable", "besteffort"] {
+            let (slice, prefix, dir) = if qos.is_empty() {
+                (String::new(), "kubepods".to_string(), String::new())
+            } else {
+                (
+                    format!("kubepods-{}.slice/", qos),
+                    format!("kubepods-{}", qos),
+                    format!("{}/", qos),
+                )
+            };
+            for scope in ["cri-containerd", "docker", "crio"] {
+                candidates.push(format!(
+                    "kubepods.slice/{}{}-pod{}.slice/{}-{}.scope",
+                    slice, prefix, slice_uid, scope, id
+                ));
+            }
+            candidates.push(format!("kubepods/{}pod{}/{}", dir, pod_uid, id));
+        }
+        self.find_cgroup(&candidates)
+    }
+
+    fn find_cgroup(&self, candidates: &[String]) -> Option<PathBuf> {
+        // cgroup v1 keeps per-controller hierarchies, memory is always present
+        [self.cgroup_root.clone(), self.cgroup_root.join("memory")]
+            .iter()
+            .flat_map(|root| candidates.iter().map(move |c| root.join(c)))
+            .find(|path| path.is_dir())
+    }
+}
+
+/// List running containers from the Docker Engine API
+async fn list_docker(socket: &Path) -> Result<Vec<RuntimeContainer>> {
+    let body = docker_get(socket, "/containers/json").await?;
+    let containers: Vec<DockerContainer> =
+        serde_json::from_slice(&body).context("Invalid Docker container list")?;
+
+    Ok(containers
+        .into_iter()
+        .map(|c| RuntimeContainer {
+            name: c
+                .names
+                .first()
+                .map(|name| name.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| short_id(&c.id)),
+            id: c.id,
+            labels: c.labels.unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Send a raw HTTP/1.0 GET to the Docker socket, returning the stream to
+/// read the response from
+pub(super) async fn docker_request(socket: &Path, path: &str) -> Result<UnixStream> {
+    let mut stream = UnixStream::connect(socket)
+        .await
+        .with_context(|| format!("Failed to connect to Docker socket {}", socket.display()))?;
+
+    let request = format!("GET {} HTTP/1.0\r\nHost: docker\r\n\r\n", path);
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .context("Failed to send Docker API request")?;
+    Ok(stream)
+}
+
+/// Issue a GET against the Docker API, returning the response body
+///
+/// HTTP/1.0 keeps the exchange simple: no chunked encoding and the daemon
+/// closes the connection after responding.
+async fn docker_get(socket: &Path, path: &str) -> Result<Vec<u8>> {
+    let mut stream = docker_request(socket, path).await?;
+    let mut response = Vec::new();
+    (&mut stream)
+        .take(MAX_DOCKER_RESPONSE as u64 + 1)
+        .read_to_end(&mut response)
+        .await
+        .context("Failed to read Docker API response")?;
+    if response.len() > MAX_DOCKER_RESPONSE {
+        anyhow::bail!("Docker API response exceeds {} bytes", MAX_DOCKER_RESPONSE);
+    }
+
+    http_body(&response).map(<[u8]>::to_vec)
+}
+
+/// Check the status of a raw HTTP response and return its body
+pub(super) fn http_body(response: &[u8]) -> Result<&[u8]> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("Malformed HTTP response"))?;
+
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status_line = head.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+    if !status.starts_with('2') {
+        anyhow::bail!("Docker API returned {}", status_line);
+    }
+
+    Ok(&response[split + 4..])
+}
+
+/// Shortened container ID used as a fallback name
+pub(super) fn short_id(id: &str) -> String {
+    id.chars().take(12).collect()
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.services.containers.v1.ListContainersRequest`
+#[derive(Clone, PartialEq, prost::Message)]
+struct ListContainersRequest {
+    #[prost(string, repeated, tag = "1")]
+    filters: Vec<String>,
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.services.containers.v1.ListContainersResponse`
+#[derive(Clone, PartialEq, prost::Message)]
+struct ListContainersResponse {
+    #[prost(message, repeated, tag = "1")]
+    containers: Vec<ContainerdContainer>,
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.services.containers.v1.GetContainerRequest`
+#[derive(Clone, PartialEq, prost::Message)]
+struct GetContainerRequest {
+    #[prost(string, tag = "1")]
+    id: String,
+}
+
+#[cfg(feature = "grpc")]
+/// `containerd.services.containers.v1.GetContainerResponse`
+#[derive(Clone, PartialEq, prost::Message)]
+struct GetContainerResponse {
+    #[prost(message, optional, tag = "1")]
+    container: Option<ContainerdContainer>,
+}
+
+#[cfg(feature = "grpc")]
+/// Subset of `containerd.services.containers.v1.Container`
+#[derive(Clone, PartialEq, prost::Message)]
+struct ContainerdContainer {
+    #[prost(string, tag = "1")]
+    id: String,
+    #[prost(map = "string, string", tag = "2")]
+    labels: HashMap<String, String>,
+}
+
+#[cfg(feature = "grpc")]
+impl From<ContainerdContainer> for RuntimeContainer {
+    fn from(c: ContainerdContainer) -> Self {
+        RuntimeContainer {
+            name: c
+                .labels
+                .get(NERDCTL_NAME_LABEL)
+                .cloned()
+                .unwrap_or_else(|| short_id(&c.id)),
+            id: c.id,
+            labels: c.labels,
+        }
+    }
+}
+
+#[cfg(feature = "grpc")]
+/// Connect a gRPC client to the containerd socket
+pub(super) async fn containerd_client(
+    socket: &Path,
+) -> Result<tonic::client::Grpc<tonic::transport::Channel>> {
+    // The URI is ignored, the connector always dials the socket
+    let channel = Endpoint::from_static("http://containerd")
+        .connect_with_connector(UnixConnector(socket.to_path_buf()))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to connect to containerd socket {}",
+                socket.display()
+            )
+        })?;
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .context("containerd service not ready")?;
+    Ok(client)
+}
+
+#[cfg(feature = "grpc")]
+/// Request carrying the containerd namespace it applies to
+fn namespaced<T>(message: T, namespace: &str) -> Result<tonic::Request<T>> {
+    let mut request = tonic::Request::new(message);
+    request.metadata_mut().insert(
+        "containerd-namespace",
+        namespace.parse().context("Invalid containerd namespace")?,
+    );
+    Ok(request)
+}
+
+#[cfg(feature = "grpc")]
+/// List containers in a containerd namespace
+async fn list_containerd(socket: &Path, namespace: &str) -> Result<Vec<RuntimeContainer>> {
+    let mut client = containerd_client(socket).await?;
+    let request = namespaced(ListContainersRequest::default(), namespace)?;
+    let path = tonic::codegen::http::uri::PathAndQuery::from_static(
+        "/containerd.services.containers.v1.Containers/List",
+    );
+    let response: tonic::Response<ListContainersResponse> = client
+        .unary(request, path, tonic::codec::ProstCodec::default())
+        .await
+        .context("Failed to list containerd containers")?;
+
+    Ok(response
+        .into_inner()
+        .containers
+        .into_iter()
+        .map(RuntimeContainer::from)
+        .collect())
+}
+
+/// containerd is only reachable over gRPC
+#[cfg(not(feature = "grpc"))]
+async fn list_containerd(_socket: &Path, _namespace: &str) -> Result<Vec<RuntimeContainer>> {
+    anyhow::bail!("containerd discovery requires the `grpc` feature")
+}
+
+#[cfg(feature = "grpc")]
+/// Look up a container in a containerd namespace
+pub(super) async fn get_containerd(
+    client: &mut tonic::client::Grpc<tonic::transport::Channel>,
+    namespace: &str,
+    id: &str,
+) -> Result<Option<RuntimeContainer>> {
+    client
+        .ready()
+        .await
+        .context("containerd service not ready")?;
+    let request = namespaced(GetContainerRequest { id: id.to_string() }, namespace)?;
+    let path = tonic::codegen::http::uri::PathAndQuery::from_static(
+        "/containerd.services.containers.v1.Containers/Get",
+    );
+    let response: Result<tonic::Response<GetContainerResponse>, _> = client
+        .unary(request, path, tonic::codec::ProstCodec::default())
+        .await;
+    match response {
+        Ok(response) => Ok(response.into_inner().container.map(RuntimeContainer::from)),
+        // Already deleted, e.g. a container that exited at once
+        Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+        Err(status) => Err(status).context("Failed to get containerd container"),
+    }
+}
+
+#[cfg(feature = "grpc")]
+/// Connector dialing a unix socket for tonic
+#[derive(Debug, Clone)]
+struct UnixConnector(PathBuf);
+
+#[cfg(feature = "grpc")]
+impl Service<Uri> for UnixConnector {
+    type Response = UnixStream;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = std::io::Result<UnixStream>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let path = self.0.clone();
+        Box::pin(async move { UnixStream::connect(path).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_container_info_from_labels() {
+        let discovery = RuntimeDiscovery::new(StandaloneConfig::default(), "/sys/fs/cgroup");
+
+        let info = discovery.container_info(
+            RuntimeContainer {
+                id: "abc".to_string(),
+                name: "web-1".to_string(),
+                labels: labels(&[
+                    (COMPOSE_PROJECT_LABEL, "shop"),
+                    (COMPOSE_SERVICE_LABEL, "web"),
+                ]),
+            },
+            PathBuf::from("/sys/fs/cgroup/docker/abc"),
+        );
+        assert_eq!(info.pod_name, "web-1");
+        assert_eq!(info.namespace, "shop");
+        assert_eq!(info.owner, Some(OwnerRef::deployment("web")));
+
+        // Explicit labels win over compose, unlabeled containers get defaults
+        let info = discovery.container_info(
+            RuntimeContainer {
+                id: "def".to_string(),
+                name: "worker".to_string(),
+                labels: labels(&[
+                    (DEFAULT_NAMESPACE_LABEL, "billing"),
+                    (COMPOSE_PROJECT_LABEL, "shop"),
+                ]),
+            },
+            PathBuf::new(),
+        );
+        assert_eq!(info.namespace, "billing");
+        assert_eq!(info.owner, None);
+
+        let info = discovery.container_info(
+            RuntimeContainer {
+                id: "ghi".to_string(),
+                name: "cron".to_string(),
+                labels: HashMap::new(),
+            },
+            PathBuf::new(),
+        );
+        assert_eq!(info.namespace, DEFAULT_STANDALONE_NAMESPACE);
+        assert_eq!(info.role, ContainerRole::Main);
+
+        // Sidecars are recognised by their CRI container name
+        let info = discovery.container_info(
+            RuntimeContainer {
+                id: "jkl".to_string(),
+                name: "k8s_istio-proxy_web-1_shop".to_string(),
+                labels: labels(&[(CRI_CONTAINER_NAME_LABEL, "istio-proxy")]),
+            },
+            PathBuf::new(),
+        );
+        assert_eq!(info.container_name.as_deref(), Some("istio-proxy"));
+        assert_eq!(info.role, ContainerRole::Sidecar);
+    }
+
+    #[test]
+    fn test_http_body() {
+        let response = b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n[]";
+        assert_eq!(http_body(response).unwrap(), b"[]");
+
+        let error = b"HTTP/1.0 500 Internal Server Error\r\n\r\n{}";
+        assert!(http_body(error).is_err());
+        assert!(http_body(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_parse_docker_container_list() {
+        let body = r#"[{"Id":"0123456789abcdef","Names":["/api"],"Labels":{"a":"b"},"State":"running"},
+                       {"Id":"fedcba9876543210","Names":[],"Labels":null}]"#;
+        let containers: Vec<DockerContainer> = serde_json::from_str(body).unwrap();
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].names, vec!["/api"]);
+        assert!(containers[1].labels.is_none());
+        assert_eq!(short_id(&containers[1].id), "fedcba987654");
+    }
+
+    #[test]
+    fn test_resolve_cgroup_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("system.slice/docker-abc.scope");
+        std::fs::create_dir_all(&scope).unwrap();
+        let v1 = temp_dir.path().join("memory/default/def");
+        std::fs::create_dir_all(&v1).unwrap();
+
+        let discovery = RuntimeDiscovery::new(StandaloneConfig::default(), temp_dir.path());
+        assert_eq!(discovery.resolve_cgroup_path("abc"), Some(scope));
+        assert_eq!(discovery.resolve_cgroup_path("def"), Some(v1));
+        assert_eq!(discovery.resolve_cgroup_path("missing"), None);
+    }
+
+    #[test]
+    fn test_resolve_pod_cgroup_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join(
+            "kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod1234_ab.slice/cri-containerd-abc.scope",
+        );
+        std::fs::create_dir_all(&scope).unwrap();
+
+        let discovery = RuntimeDiscovery::new(StandaloneConfig::default(), temp_dir.path());
+        let container = RuntimeContainer {
+            id: "abc".to_string(),
+            name: "abc".to_string(),
+            labels: labels(&[(CRI_POD_UID_LABEL, "1234-ab")]),
+        };
+        let info = discovery.resolve(container).unwrap();
+        assert_eq!(info.cgroup_path, scope.to_string_lossy());
+    }
+}
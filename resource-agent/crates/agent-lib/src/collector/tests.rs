@@ -5,7 +5,9 @@
 
 #[cfg(test)]
 mod mock_cgroup_tests {
-    use crate::collector::{CgroupV1Collector, CgroupV2Collector, MetricsCollector};
+    use crate::collector::{
+        CgroupV1Collector, CgroupV2Collector, CollectorError, MetricsCollector,
+    };
     use std::path::PathBuf;
     use tempfile::TempDir;
     use tokio::fs;
@@ -153,7 +155,10 @@ total_inactive_file 13107200
         let collector = CgroupV2Collector::new(&cgroup_root);
         let result = collector.collect("nonexistent").await;
 
-        assert!(result.is_err());
+        // The container has exited, collecting again won't help
+        let err = result.unwrap_err();
+        assert!(matches!(err, CollectorError::ContainerNotFound { .. }));
+        assert!(!err.is_retryable());
     }
 
     #[tokio::test]
@@ -398,3 +403,245 @@ mod container_id_extraction_tests {
         assert_eq!(id, None);
     }
 }
+
+#[cfg(test)]
+mod node_tests {
+    use crate::collector::{parse_cpu_max, parse_meminfo, parse_pressure, NodeCollector};
+    use tempfile::TempDir;
+    use tokio::fs;
+
+    #[test]
+    fn test_parse_pressure() {
+        let content = "some avg10=1.53 avg60=0.87 avg300=0.22 total=2207344\n\
+                       full avg10=0.40 avg60=0.10 avg300=0.02 total=1000\n";
+        assert_eq!(parse_pressure(content), Some(1.53));
+        assert_eq!(parse_pressure("full avg10=0.40"), None);
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let content = "MemTotal:       16384000 kB\nMemFree:         1024000 kB\nMemAvailable:    8192000 kB\n";
+        assert_eq!(parse_meminfo(content), (16384000 * 1024, 8192000 * 1024));
+    }
+
+    #[test]
+    fn test_parse_cpu_max() {
+        assert_eq!(parse_cpu_max("350000 100000\n"), Some(3.5));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+    }
+
+    #[tokio::test]
+    async fn test_node_collect() {
+        let temp_dir = TempDir::new().unwrap();
+        let cgroup_root = temp_dir.path().join("cgroup");
+        let proc_path = temp_dir.path().join("proc");
+        fs::create_dir_all(cgroup_root.join("kubepods.slice"))
+            .await
+            .unwrap();
+        fs::create_dir_all(proc_path.join("pressure"))
+            .await
+            .unwrap();
+
+        fs::write(
+            proc_path.join("meminfo"),
+            "MemTotal:       8388608 kB\nMemAvailable:   2097152 kB\n",
+        )
+        .await
+        .unwrap();
+        fs::write(
+            proc_path.join("stat"),
+            "cpu  100 0 100 1000\ncpu0 50 0 50 500\ncpu1 50 0 50 500\ncpu2 0 0 0 0\ncpu3 0 0 0 0\nintr 1\n",
+        )
+        .await
+        .unwrap();
+        fs::write(cgroup_root.join("cpu.stat"), "usage_usec 5000000\n")
+            .await
+            .unwrap();
+        fs::write(cgroup_root.join("kubepods.slice/cpu.max"), "max 100000\n")
+            .await
+            .unwrap();
+        fs::write(
+            cgroup_root.join("kubepods.slice/memory.max"),
+            "6442450944\n",
+        )
+        .await
+        .unwrap();
+        fs::write(
+            cgroup_root.join("memory.pressure"),
+            "some avg10=12.50 avg60=8.00 avg300=4.00 total=1\n",
+        )
+        .await
+        .unwrap();
+        fs::write(
+            proc_path.join("pressure/cpu"),
+            "some avg10=3.00 avg60=2.00 avg300=1.00 total=1\n",
+        )
+        .await
+        .unwrap();
+
+        let collector = NodeCollector::with_proc_path(&cgroup_root, &proc_path);
+        let node = collector.collect().await.unwrap();
+
+        assert_eq!(node.cpu_allocatable_cores, 4.0);
+        assert_eq!(node.memory_allocatable_bytes, 6 << 30);
+        assert_eq!(node.memory_usage_bytes, 6 << 30);
+        assert_eq!(node.cpu_usage_cores, 0.0);
+        assert_eq!(node.memory_pressure, 12.5);
+        assert_eq!(node.cpu_pressure, 3.0);
+        assert_eq!(node.io_pressure, 0.0);
+        assert_eq!(node.pressure(), 1.0);
+    }
+}
+
+/// Parsers must cope with whatever an exotic kernel or a truncated read
+/// leaves in a cgroup file
+#[cfg(test)]
+mod property_tests {
+    use crate::collector::{CgroupV1Collector, CgroupV2Collector};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+    use std::fmt::Write;
+
+    /// `content` cut at the char boundary at or before `at`
+    fn truncate(content: &str, at: usize) -> &str {
+        let mut end = at.min(content.len());
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        &content[..end]
+    }
+
+    fn memory_stat(stats: &HashMap<String, u64>) -> String {
+        stats.iter().fold(String::new(), |mut out, (key, value)| {
+            let _ = writeln!(out, "{} {}", key, value);
+            out
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn parsers_never_panic(content in any::<String>()) {
+            let _ = CgroupV2Collector::parse_cpu_stat(&content);
+            let _ = CgroupV2Collector::parse_memory_stat(&content);
+            let _ = CgroupV2Collector::extract_container_id(&content);
+            let _ = CgroupV2Collector::parse_proc_cgroup(&content);
+            let _ = CgroupV1Collector::parse_cpu_stat(&content);
+            let _ = CgroupV1Collector::parse_memory_stat(&content);
+            let _ = CgroupV1Collector::extract_container_id(&content);
+            let _ = CgroupV1Collector::parse_proc_cgroup(&content);
+        }
+
+        #[test]
+        fn cpu_stat_roundtrip(usage in any::<u64>(), periods in any::<u64>(), throttled in any::<u64>()) {
+            let v2 = format!(
+                "usage_usec {}\nuser_usec 1\nnr_periods {}\nnr_throttled {}\n",
+                usage, periods, throttled
+            );
+            prop_assert_eq!(CgroupV2Collector::parse_cpu_stat(&v2).unwrap(), (usage, throttled));
+
+            let v1 = format!("nr_periods {}\nnr_throttled {}\nthrottled_time 5\n", periods, throttled);
+            prop_assert_eq!(CgroupV1Collector::parse_cpu_stat(&v1), (periods, throttled));
+        }
+
+        /// A truncated read never reports more than the whole file
+        #[test]
+        fn truncated_cpu_stat_never_overreports(
+            usage in any::<u64>(),
+            periods in any::<u64>(),
+            throttled in any::<u64>(),
+            at in 0usize..100,
+        ) {
+            let v2 = format!("usage_usec {}\nnr_throttled {}\n", usage, throttled);
+            let (u, t) = CgroupV2Collector::parse_cpu_stat(truncate(&v2, at)).unwrap();
+            prop_assert!(u <= usage && t <= throttled);
+
+            let v1 = format!("nr_periods {}\nnr_throttled {}\n", periods, throttled);
+            let (p, t) = CgroupV1Collector::parse_cpu_stat(truncate(&v1, at));
+            prop_assert!(p <= periods && t <= throttled);
+        }
+
+        /// Lines without a numeric value are skipped rather than read as zero
+        #[test]
+        fn memory_stat_roundtrip(
+            stats in prop::collection::hash_map("[a-z_]{1,20}", any::<u64>(), 0..20),
+            garbage in "[a-z.-]{1,8}",
+        ) {
+            let content = format!("{}x9 {}\nx8\n", memory_stat(&stats), garbage);
+            prop_assert_eq!(&CgroupV2Collector::parse_memory_stat(&content), &stats);
+            prop_assert_eq!(&CgroupV1Collector::parse_memory_stat(&content), &stats);
+        }
+
+        /// A truncated read only loses entries or digits, it never makes them up
+        #[test]
+        fn truncated_memory_stat_is_a_subset(
+            stats in prop::collection::hash_map("[a-z_]{1,20}", any::<u64>(), 1..20),
+            at in 0usize..600,
+        ) {
+            let content = memory_stat(&stats);
+            let parsed = CgroupV2Collector::parse_memory_stat(truncate(&content, at));
+            for (key, value) in &parsed {
+                prop_assert!(stats.get(key).is_some_and(|full| value <= full));
+            }
+        }
+
+        #[test]
+        fn container_id_found_in_runtime_paths(
+            id in "[0-9a-f]{64}",
+            prefix in prop::collection::vec("[a-z.-]{1,12}", 0..4),
+        ) {
+            let prefix = prefix.iter().fold(String::new(), |mut out, part| {
+                let _ = write!(out, "/{}", part);
+                out
+            });
+            for path in [
+                format!("{}/{}", prefix, id),
+                format!("{}/crio-{}", prefix, id),
+                format!("{}/crio-{}.scope", prefix, id),
+            ] {
+                prop_assert_eq!(CgroupV2Collector::extract_container_id(&path), Some(id.clone()));
+                prop_assert_eq!(CgroupV1Collector::extract_container_id(&path), Some(id.clone()));
+            }
+            let path = format!("{}/cri-containerd-{}.scope", prefix, id);
+            prop_assert_eq!(CgroupV1Collector::extract_container_id(&path), Some(id));
+        }
+
+        /// Any path with a non-empty component yields an ID from the path
+        #[test]
+        fn container_id_falls_back_to_path(path in "[a-z0-9/._-]{0,40}") {
+            let empty = path.split('/').all(str::is_empty);
+            for id in [
+                CgroupV2Collector::extract_container_id(&path),
+                CgroupV1Collector::extract_container_id(&path),
+            ] {
+                prop_assert_eq!(id.is_none(), empty);
+                if let Some(id) = id {
+                    prop_assert!(!id.is_empty() && path.contains(&id));
+                }
+            }
+        }
+
+        #[test]
+        fn proc_cgroup_v2_finds_unified_path(
+            path in "/[a-z0-9/.:-]{0,40}",
+            legacy in prop::collection::vec((1u32..12, "[a-z]{1,8}"), 0..5),
+        ) {
+            let mut content = legacy.iter().fold(String::new(), |mut out, (id, controller)| {
+                let _ = writeln!(out, "{}:{}:/legacy", id, controller);
+                out
+            });
+            content.push_str(&format!("0::{}\n", path));
+            prop_assert_eq!(CgroupV2Collector::parse_proc_cgroup(&content), Some(path));
+        }
+
+        #[test]
+        fn proc_cgroup_v1_maps_controllers(
+            paths in prop::collection::hash_map("[a-z_]{1,10}", "/[a-z0-9/:]{0,30}", 0..8),
+        ) {
+            let mut content = String::from("0::/unified\n");
+            for (i, (controller, path)) in paths.iter().enumerate() {
+                content.push_str(&format!("{}:{}:{}\n", i + 1, controller, path));
+            }
+            prop_assert_eq!(CgroupV1Collector::parse_proc_cgroup(&content), paths);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Retryability of errors across the library
+//!
+//! Modules report failures through their own error enums
+//! ([`CollectorError`](crate::collector::CollectorError),
+//! [`SyncError`](crate::sync::SyncError),
+//! [`PredictError`](crate::predictor::PredictError)), each telling whether
+//! the same operation may succeed if tried again. Binaries keep `anyhow` at
+//! their boundaries and classify its chains with [`is_retryable`].
+
+use crate::collector::CollectorError;
+use crate::predictor::PredictError;
+#[cfg(feature = "grpc-sync")]
+use crate::sync::SyncError;
+use std::io;
+#[cfg(feature = "grpc-sync")]
+use tonic::Code;
+
+/// Whether the first classifiable cause in the chain is retryable
+///
+/// Errors with no typed cause are treated as fatal.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<CollectorError>() {
+            return e.is_retryable();
+        }
+        #[cfg(feature = "grpc-sync")]
+        if let Some(e) = cause.downcast_ref::<SyncError>() {
+            return e.is_retryable();
+        }
+        if let Some(e) = cause.downcast_ref::<PredictError>() {
+            return e.is_retryable();
+        }
+        #[cfg(feature = "grpc-sync")]
+        if let Some(e) = cause.downcast_ref::<tonic::Status>() {
+            return is_retryable_status(e);
+        }
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return is_retryable_io(e);
+        }
+    }
+    false
+}
+
+/// Whether an IO error is transient
+pub(crate) fn is_retryable_io(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Whether a gRPC status is transient
+///
+/// tonic reports broken connections as `Unknown`, so it is retried too.
+#[cfg(feature = "grpc-sync")]
+pub(crate) fn is_retryable_status(status: &tonic::Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::ResourceExhausted
+            | Code::Aborted
+            | Code::Unknown
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classify_chain() {
+        let gone = CollectorError::ContainerNotFound {
+            container_id: "abc".to_string(),
+        };
+        assert!(!is_retryable(&anyhow::Error::new(gone)));
+
+        let read: anyhow::Result<()> = Err(io::Error::from(io::ErrorKind::TimedOut).into());
+        assert!(is_retryable(
+            &read.context("Failed to read cpu.stat").unwrap_err()
+        ));
+        assert!(!is_retryable(&anyhow::anyhow!("Invalid endpoint")));
+    }
+
+    #[test]
+    #[cfg(feature = "grpc-sync")]
+    fn test_classify_sync_error() {
+        let rpc = SyncError::Rpc {
+            operation: "Heartbeat",
+            status: tonic::Status::unavailable("connection reset"),
+        };
+        assert!(is_retryable(
+            &anyhow::Error::new(rpc).context("Sync failed")
+        ));
+    }
+}
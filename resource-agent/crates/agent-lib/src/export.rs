@@ -0,0 +1,322 @@
+//! Tabular export of container samples
+//!
+//! Writes samples as CSV or Parquet for training and evaluating models
+//! outside the cluster. Both formats share one flat schema, one row per
+//! sample, with the columns listed in `COLUMNS`:
+//!
+//! | Column | Type | Description |
+//! |--------|------|-------------|
+//! | `timestamp` | int64 | Collection time (Unix seconds) |
+//! | `container_id` | string | Runtime container ID |
+//! | `pod_name` | string | Pod name |
+//! | `namespace` | string | Pod namespace |
+//! | `owner_kind` | string, optional | Kind of the owning workload, e.g. `deployment` |
+//! | `owner_name` | string, optional | Name of the owning workload |
+//! | `cpu_usage_cores` | float | CPU usage in cores |
+//! | `cpu_throttled_periods` | uint64 | Cumulative throttled CFS periods |
+//! | `memory_usage_bytes` | uint64 | Memory usage including page cache |
+//! | `memory_working_set_bytes` | uint64 | Working set memory |
+//! | `memory_cache_bytes` | uint64 | Page cache |
+//! | `network_rx_bytes` | uint64 | Cumulative bytes received |
+//! | `network_tx_bytes` | uint64 | Cumulative bytes sent |
+//!
+//! CSV leaves missing owners empty. Parquet output needs the `parquet`
+//! feature, and stores unsigned columns as `INT64 (UINT_64)`.
+
+use crate::models::ContainerMetrics;
+use anyhow::{bail, Result};
+use std::io::Write;
+
+/// Export columns, in order
+pub const COLUMNS: [&str; 13] = [
+    "timestamp",
+    "container_id",
+    "pod_name",
+    "namespace",
+    "owner_kind",
+    "owner_name",
+    "cpu_usage_cores",
+    "cpu_throttled_periods",
+    "memory_usage_bytes",
+    "memory_working_set_bytes",
+    "memory_cache_bytes",
+    "network_rx_bytes",
+    "network_tx_bytes",
+];
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => bail!("Unknown export format '{}', expected csv or parquet", s),
+        }
+    }
+}
+
+/// Samples encoded in `format`
+pub fn export(metrics: &[ContainerMetrics], format: ExportFormat) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match format {
+        ExportFormat::Csv => write_csv(metrics, &mut out)?,
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => write_parquet(metrics, &mut out)?,
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => bail!("Parquet export requires the `parquet` feature"),
+    }
+    Ok(out)
+}
+
+/// Write samples as CSV with a header row
+pub fn write_csv<W: Write>(metrics: &[ContainerMetrics], mut out: W) -> Result<()> {
+    writeln!(out, "{}", COLUMNS.join(","))?;
+    for m in metrics {
+        let (owner_kind, owner_name) = match &m.owner {
+            Some(owner) => (owner.kind.as_str(), owner.name.as_str()),
+            None => ("", ""),
+        };
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            m.timestamp,
+            csv_field(&m.container_id),
+            csv_field(&m.pod_name),
+            csv_field(&m.namespace),
+            owner_kind,
+            csv_field(owner_name),
+            m.cpu_usage_cores,
+            m.cpu_throttled_periods,
+            m.memory_usage_bytes,
+            m.memory_working_set_bytes,
+            m.memory_cache_bytes,
+            m.network_rx_bytes,
+            m.network_tx_bytes,
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Quote a field holding a separator, quote or line break
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub use self::parquet_export::write_parquet;
+
+#[cfg(feature = "parquet")]
+mod parquet_export {
+    use super::*;
+    use parquet::basic::Compression;
+    use parquet::data_type::{ByteArray, ByteArrayType, FloatType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    /// Rows per row group, bounding the memory used by readers
+    const ROW_GROUP_SIZE: usize = 65_536;
+
+    const SCHEMA: &str = "
+        message container_metrics {
+            REQUIRED INT64 timestamp;
+            REQUIRED BYTE_ARRAY container_id (UTF8);
+            REQUIRED BYTE_ARRAY pod_name (UTF8);
+            REQUIRED BYTE_ARRAY namespace (UTF8);
+            OPTIONAL BYTE_ARRAY owner_kind (UTF8);
+            OPTIONAL BYTE_ARRAY owner_name (UTF8);
+            REQUIRED FLOAT cpu_usage_cores;
+            REQUIRED INT64 cpu_throttled_periods (UINT_64);
+            REQUIRED INT64 memory_usage_bytes (UINT_64);
+            REQUIRED INT64 memory_working_set_bytes (UINT_64);
+            REQUIRED INT64 memory_cache_bytes (UINT_64);
+            REQUIRED INT64 network_rx_bytes (UINT_64);
+            REQUIRED INT64 network_tx_bytes (UINT_64);
+        }
+    ";
+
+    /// Write samples as a Snappy-compressed Parquet file
+    pub fn write_parquet<W: Write + Send>(metrics: &[ContainerMetrics], out: W) -> Result<()> {
+        let schema = Arc::new(parse_message_type(SCHEMA)?);
+        let props = Arc::new(
+            WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build(),
+        );
+        let mut writer = SerializedFileWriter::new(out, schema, props)?;
+
+        for rows in metrics.chunks(ROW_GROUP_SIZE) {
+            let mut row_group = writer.next_row_group()?;
+            let mut column = 0;
+            while let Some(mut col) = row_group.next_column()? {
+                match COLUMNS[column] {
+                    "timestamp" => write_i64(&mut col, rows, |m| m.timestamp)?,
+                    "container_id" => write_str(&mut col, rows, |m| Some(m.container_id.as_str()))?,
+                    "pod_name" => write_str(&mut col, rows, |m| Some(m.pod_name.as_str()))?,
+                    "namespace" => write_str(&mut col, rows, |m| Some(m.namespace.as_str()))?,
+                    "owner_kind" => write_str(&mut col, rows, |m| {
+                        m.owner.as_ref().map(|o| o.kind.as_str())
+                    })?,
+                    "owner_name" => write_str(&mut col, rows, |m| {
+                        m.owner.as_ref().map(|o| o.name.as_str())
+                    })?,
+                    "cpu_usage_cores" => {
+                        let values: Vec<f32> = rows.iter().map(|m| m.cpu_usage_cores).collect();
+                        col.typed::<FloatType>().write_batch(&values, None, None)?;
+                    }
+                    "cpu_throttled_periods" => {
+                        write_i64(&mut col, rows, |m| m.cpu_throttled_periods as i64)?
+                    }
+                    "memory_usage_bytes" => {
+                        write_i64(&mut col, rows, |m| m.memory_usage_bytes as i64)?
+                    }
+                    "memory_working_set_bytes" => {
+                        write_i64(&mut col, rows, |m| m.memory_working_set_bytes as i64)?
+                    }
+                    "memory_cache_bytes" => {
+                        write_i64(&mut col, rows, |m| m.memory_cache_bytes as i64)?
+                    }
+                    "network_rx_bytes" => write_i64(&mut col, rows, |m| m.network_rx_bytes as i64)?,
+                    "network_tx_bytes" => write_i64(&mut col, rows, |m| m.network_tx_bytes as i64)?,
+                    other => bail!("No values for column {}", other),
+                }
+                col.close()?;
+                column += 1;
+            }
+            row_group.close()?;
+        }
+        writer.close()?;
+        Ok(())
+    }
+
+    type ColumnWriter<'a> = parquet::file::writer::SerializedColumnWriter<'a>;
+
+    fn write_i64(
+        col: &mut ColumnWriter<'_>,
+        rows: &[ContainerMetrics],
+        value: impl Fn(&ContainerMetrics) -> i64,
+    ) -> Result<()> {
+        let values: Vec<i64> = rows.iter().map(value).collect();
+        col.typed::<Int64Type>().write_batch(&values, None, None)?;
+        Ok(())
+    }
+
+    /// Write a string column, optional when `value` can return `None`
+    fn write_str<'m>(
+        col: &mut ColumnWriter<'_>,
+        rows: &'m [ContainerMetrics],
+        value: impl Fn(&'m ContainerMetrics) -> Option<&'m str>,
+    ) -> Result<()> {
+        let present: Vec<Option<&str>> = rows.iter().map(value).collect();
+        let values: Vec<ByteArray> = present
+            .iter()
+            .flatten()
+            .map(|s| ByteArray::from(*s))
+            .collect();
+        let def_levels: Vec<i16> = present.iter().map(|v| v.is_some() as i16).collect();
+        let writer = col.typed::<ByteArrayType>();
+        if writer.get_descriptor().max_def_level() > 0 {
+            writer.write_batch(&values, Some(&def_levels), None)?;
+        } else {
+            writer.write_batch(&values, None, None)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OwnerRef;
+
+    fn sample(pod_name: &str, owner: Option<OwnerRef>) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: "c1".to_string(),
+            pod_name: pod_name.to_string(),
+            namespace: "default".to_string(),
+            owner,
+            timestamp: 1_700_000_000,
+            cpu_usage_cores: 0.25,
+            cpu_throttled_periods: 3,
+            memory_usage_bytes: 2048,
+            memory_working_set_bytes: 1024,
+            memory_cache_bytes: 1024,
+            network_rx_bytes: 10,
+            network_tx_bytes: 20,
+        }
+    }
+
+    #[test]
+    fn test_csv_schema() {
+        let metrics = [
+            sample("api-7d4f8b-x2k9p", Some(OwnerRef::deployment("api"))),
+            sample("odd,\"name\"", None),
+        ];
+        let csv = String::from_utf8(export(&metrics, ExportFormat::Csv).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0].split(',').collect::<Vec<_>>(), COLUMNS);
+        assert_eq!(
+            lines[1],
+            "1700000000,c1,api-7d4f8b-x2k9p,default,deployment,api,0.25,3,2048,1024,1024,10,20"
+        );
+        assert_eq!(
+            lines[2],
+            "1700000000,c1,\"odd,\"\"name\"\"\",default,,,0.25,3,2048,1024,1024,10,20"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_roundtrip() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let metrics = [
+            sample("api-7d4f8b-x2k9p", Some(OwnerRef::deployment("api"))),
+            sample("worker", None),
+        ];
+        let file = tempfile::tempfile().unwrap();
+        write_parquet(&metrics, file.try_clone().unwrap()).unwrap();
+        let reader = SerializedFileReader::new(file).unwrap();
+
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let names: Vec<&str> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(names, COLUMNS);
+
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().collect();
+        assert_eq!(rows.len(), 2);
+        let second = rows[1].as_ref().unwrap().to_string();
+        assert!(second.contains("pod_name: \"worker\""));
+        assert!(second.contains("owner_kind: null"));
+    }
+}
@@ -4,8 +4,10 @@
 //! Kubernetes liveness and readiness probes.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 /// Health status of a component
@@ -34,31 +36,47 @@ pub struct ComponentHealth {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
     pub last_check_timestamp: i64,
+    /// Unix time of the component's last successful operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success_timestamp: Option<i64>,
+    /// Failures reported since the agent started
+    #[serde(default)]
+    pub error_count: u64,
+    /// Components this one needs to do its work
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Whether failing makes the agent unready
+    #[serde(default = "default_critical")]
+    pub critical: bool,
+}
+
+fn default_critical() -> bool {
+    true
 }
 
 impl ComponentHealth {
-    pub fn healthy() -> Self {
+    fn new(status: ComponentStatus, message: Option<String>) -> Self {
         Self {
-            status: ComponentStatus::Healthy,
-            message: None,
+            status,
+            message,
             last_check_timestamp: chrono::Utc::now().timestamp(),
+            last_success_timestamp: None,
+            error_count: 0,
+            depends_on: Vec::new(),
+            critical: true,
         }
     }
 
+    pub fn healthy() -> Self {
+        Self::new(ComponentStatus::Healthy, None)
+    }
+
     pub fn degraded(message: impl Into<String>) -> Self {
-        Self {
-            status: ComponentStatus::Degraded,
-            message: Some(message.into()),
-            last_check_timestamp: chrono::Utc::now().timestamp(),
-        }
+        Self::new(ComponentStatus::Degraded, Some(message.into()))
     }
 
     pub fn unhealthy(message: impl Into<String>) -> Self {
-        Self {
-            status: ComponentStatus::Unhealthy,
-            message: Some(message.into()),
-            last_check_timestamp: chrono::Utc::now().timestamp(),
-        }
+        Self::new(ComponentStatus::Unhealthy, Some(message.into()))
     }
 }
 
@@ -96,6 +114,9 @@ pub struct ReadinessResponse {
     pub ready: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Components off the critical path that are degraded or unhealthy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<String>,
 }
 
 /// Component names for health tracking
@@ -104,12 +125,208 @@ pub mod components {
     pub const PREDICTOR: &str = "predictor";
     pub const SYNC_CLIENT: &str = "sync_client";
     pub const BUFFER: &str = "buffer";
+    pub const MODEL_UPDATE: &str = "model_update";
+    pub const WATCHDOG: &str = "watchdog";
+    pub const SELF_LIMIT: &str = "self_limit";
+}
+
+/// Declared place of a component in the dependency graph
+///
+/// Components are critical unless declared otherwise. Readiness fails when a
+/// critical component, or anything it depends on, is unhealthy.
+#[derive(Debug, Clone)]
+pub struct ComponentSpec {
+    pub critical: bool,
+    pub depends_on: Vec<String>,
+}
+
+impl Default for ComponentSpec {
+    fn default() -> Self {
+        Self {
+            critical: true,
+            depends_on: Vec::new(),
+        }
+    }
+}
+
+impl ComponentSpec {
+    /// Report the component's failures without failing readiness
+    pub fn non_critical(mut self) -> Self {
+        self.critical = false;
+        self
+    }
+
+    /// Declare the components this one needs to do its work
+    pub fn depends_on(mut self, components: &[&str]) -> Self {
+        self.depends_on = components.iter().map(|c| c.to_string()).collect();
+        self
+    }
+}
+
+/// Default consecutive failures before a component is degraded
+pub const DEFAULT_DEGRADED_AFTER_FAILURES: u32 = 3;
+
+/// Default consecutive failures before a component is unhealthy
+pub const DEFAULT_UNHEALTHY_AFTER_FAILURES: u32 = 10;
+
+/// How a component's reported activity maps to its health
+#[derive(Debug, Clone, Copy)]
+pub struct HealthPolicy {
+    /// Consecutive failures before the component is degraded
+    pub degraded_after_failures: u32,
+    /// Consecutive failures before the component is unhealthy, if ever
+    pub unhealthy_after_failures: Option<u32>,
+    /// Time without a heartbeat after which the component is unhealthy
+    pub stale_after: Option<Duration>,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            degraded_after_failures: DEFAULT_DEGRADED_AFTER_FAILURES,
+            unhealthy_after_failures: Some(DEFAULT_UNHEALTHY_AFTER_FAILURES),
+            stale_after: None,
+        }
+    }
+}
+
+impl HealthPolicy {
+    /// Mark the component unhealthy when it stops reporting for `after`
+    pub fn stale_after(mut self, after: Duration) -> Self {
+        self.stale_after = Some(after);
+        self
+    }
+
+    /// Never escalate failures beyond degraded
+    ///
+    /// For components whose failures a restart wouldn't fix, like an
+    /// unreachable API.
+    pub fn degrade_only(mut self) -> Self {
+        self.unhealthy_after_failures = None;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct ReporterState {
+    last_heartbeat: Instant,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    /// Unix time of the last success
+    last_success: Option<i64>,
+    /// Failures since the reporter was created
+    error_count: u64,
+    /// Ongoing condition reported by the component itself
+    condition: Option<String>,
+}
+
+/// Handle through which a component reports its own health
+///
+/// Cheap to clone and synchronous, so it can be called from hot paths.
+#[derive(Debug, Clone)]
+pub struct ComponentReporter {
+    policy: HealthPolicy,
+    state: Arc<Mutex<ReporterState>>,
+}
+
+impl ComponentReporter {
+    fn new(policy: HealthPolicy) -> Self {
+        Self {
+            policy,
+            state: Arc::new(Mutex::new(ReporterState {
+                last_heartbeat: Instant::now(),
+                consecutive_failures: 0,
+                last_error: None,
+                last_success: None,
+                error_count: 0,
+                condition: None,
+            })),
+        }
+    }
+
+    /// Record that the component is alive without changing its failure count
+    pub fn heartbeat(&self) {
+        self.state.lock().unwrap().last_heartbeat = Instant::now();
+    }
+
+    /// Record a successful operation, clearing the failure count
+    pub fn success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.last_heartbeat = Instant::now();
+        state.consecutive_failures = 0;
+        state.last_error = None;
+        state.last_success = Some(chrono::Utc::now().timestamp());
+    }
+
+    /// Record a failed operation
+    pub fn failure(&self, error: impl Display) {
+        let mut state = self.state.lock().unwrap();
+        state.last_heartbeat = Instant::now();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        state.error_count = state.error_count.saturating_add(1);
+        state.last_error = Some(error.to_string());
+    }
+
+    /// Time since the component last reported anything
+    pub fn since_heartbeat(&self) -> Duration {
+        self.state.lock().unwrap().last_heartbeat.elapsed()
+    }
+
+    /// Set or clear an ongoing condition that degrades the component
+    pub fn set_condition(&self, condition: Option<String>) {
+        self.state.lock().unwrap().condition = condition;
+    }
+
+    /// Health derived from the reported activity
+    pub fn evaluate(&self) -> ComponentHealth {
+        let state = self.state.lock().unwrap();
+        let mut health = self.status(&state);
+        health.last_success_timestamp = state.last_success;
+        health.error_count = state.error_count;
+        health
+    }
+
+    fn status(&self, state: &ReporterState) -> ComponentHealth {
+        if let Some(stale_after) = self.policy.stale_after {
+            let idle = state.last_heartbeat.elapsed();
+            if idle > stale_after {
+                return ComponentHealth::unhealthy(format!("No heartbeat for {}s", idle.as_secs()));
+            }
+        }
+
+        let failures = state.consecutive_failures;
+        let failure_message = || {
+            format!(
+                "{} consecutive failures: {}",
+                failures,
+                state.last_error.as_deref().unwrap_or("unknown error")
+            )
+        };
+
+        if self
+            .policy
+            .unhealthy_after_failures
+            .is_some_and(|limit| failures >= limit)
+        {
+            ComponentHealth::unhealthy(failure_message())
+        } else if failures >= self.policy.degraded_after_failures {
+            ComponentHealth::degraded(failure_message())
+        } else if let Some(condition) = &state.condition {
+            ComponentHealth::degraded(condition.clone())
+        } else {
+            ComponentHealth::healthy()
+        }
+    }
 }
 
 /// Health registry for tracking component health
 #[derive(Debug, Clone)]
 pub struct HealthRegistry {
     components: Arc<RwLock<HashMap<String, ComponentHealth>>>,
+    /// Components that report their own health
+    reporters: Arc<RwLock<HashMap<String, ComponentReporter>>>,
+    /// Declared criticality and dependencies
+    specs: Arc<RwLock<HashMap<String, ComponentSpec>>>,
     ready: Arc<RwLock<bool>>,
 }
 
@@ -123,6 +340,8 @@ impl HealthRegistry {
     pub fn new() -> Self {
         Self {
             components: Arc::new(RwLock::new(HashMap::new())),
+            reporters: Arc::new(RwLock::new(HashMap::new())),
+            specs: Arc::new(RwLock::new(HashMap::new())),
             ready: Arc::new(RwLock::new(false)),
         }
     }
@@ -133,9 +352,50 @@ impl HealthRegistry {
         components.insert(name.to_string(), ComponentHealth::healthy());
     }
 
+    /// Declare a component's criticality and dependencies
+    pub async fn declare(&self, name: &str, spec: ComponentSpec) {
+        self.specs.write().await.insert(name.to_string(), spec);
+    }
+
+    /// Register a component that reports its own health
+    ///
+    /// Its status is derived from the returned reporter on every health
+    /// check, taking precedence over manual updates.
+    pub async fn reporter(&self, name: &str, policy: HealthPolicy) -> ComponentReporter {
+        let reporter = ComponentReporter::new(policy);
+        self.reporters
+            .write()
+            .await
+            .insert(name.to_string(), reporter.clone());
+        reporter
+    }
+
+    /// Time since a self-reporting component last reported anything
+    pub async fn since_heartbeat(&self, name: &str) -> Option<Duration> {
+        self.reporters
+            .read()
+            .await
+            .get(name)
+            .map(ComponentReporter::since_heartbeat)
+    }
+
     /// Update component health status
-    pub async fn update(&self, name: &str, health: ComponentHealth) {
+    ///
+    /// Failure counts and the last success carry over from earlier updates.
+    pub async fn update(&self, name: &str, mut health: ComponentHealth) {
         let mut components = self.components.write().await;
+        if let Some(previous) = components.get(name) {
+            health.error_count += previous.error_count;
+            health.last_success_timestamp = health
+                .last_success_timestamp
+                .or(previous.last_success_timestamp);
+        }
+        match health.status {
+            ComponentStatus::Healthy => {
+                health.last_success_timestamp = Some(health.last_check_timestamp)
+            }
+            ComponentStatus::Degraded | ComponentStatus::Unhealthy => health.error_count += 1,
+        }
         components.insert(name.to_string(), health);
     }
 
@@ -162,38 +422,86 @@ impl HealthRegistry {
 
     /// Get health response
     pub async fn health(&self) -> HealthResponse {
-        let components = self.components.read().await.clone();
+        let mut components = self.components.read().await.clone();
+        for (name, reporter) in self.reporters.read().await.iter() {
+            components.insert(name.clone(), reporter.evaluate());
+        }
+        for (name, spec) in self.specs.read().await.iter() {
+            if let Some(health) = components.get_mut(name) {
+                health.critical = spec.critical;
+                health.depends_on = spec.depends_on.clone();
+            }
+        }
         let status = HealthResponse::compute_status(&components);
         HealthResponse { status, components }
     }
 
     /// Get readiness response
+    ///
+    /// Only unhealthy components on the critical path fail readiness; other
+    /// components that aren't healthy are listed as degraded.
     pub async fn readiness(&self) -> ReadinessResponse {
         let ready = *self.ready.read().await;
         let health = self.health().await;
 
-        // Not ready if any critical component is unhealthy
-        let critical_healthy = health.status != ComponentStatus::Unhealthy;
+        let critical_path = critical_path(&health.components);
+        let mut failed = Vec::new();
+        let mut degraded = Vec::new();
+        for (name, component) in &health.components {
+            if critical_path.contains(name.as_str()) {
+                if component.status == ComponentStatus::Unhealthy {
+                    failed.push(name.clone());
+                }
+            } else if component.status != ComponentStatus::Healthy {
+                degraded.push(name.clone());
+            }
+        }
+        failed.sort();
+        degraded.sort();
 
         if !ready {
             ReadinessResponse {
                 ready: false,
                 reason: Some("Agent not yet initialized".to_string()),
+                degraded,
             }
-        } else if !critical_healthy {
+        } else if !failed.is_empty() {
             ReadinessResponse {
                 ready: false,
-                reason: Some("Critical component unhealthy".to_string()),
+                reason: Some(format!(
+                    "Critical component unhealthy: {}",
+                    failed.join(", ")
+                )),
+                degraded,
             }
         } else {
             ReadinessResponse {
                 ready: true,
                 reason: None,
+                degraded,
             }
         }
     }
 }
 
+/// Critical components and everything they depend on, transitively
+fn critical_path(components: &HashMap<String, ComponentHealth>) -> HashSet<&str> {
+    let mut path = HashSet::new();
+    let mut pending: Vec<&str> = components
+        .iter()
+        .filter(|(_, health)| health.critical)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    while let Some(name) = pending.pop() {
+        if path.insert(name) {
+            if let Some(health) = components.get(name) {
+                pending.extend(health.depends_on.iter().map(String::as_str));
+            }
+        }
+    }
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -278,4 +586,145 @@ mod tests {
         let readiness = registry.readiness().await;
         assert!(!readiness.ready);
     }
+
+    #[tokio::test]
+    async fn test_readiness_follows_critical_path() {
+        let registry = HealthRegistry::new();
+        for name in [
+            components::COLLECTOR,
+            components::PREDICTOR,
+            components::SYNC_CLIENT,
+        ] {
+            registry.register(name).await;
+        }
+        registry
+            .declare(
+                components::PREDICTOR,
+                ComponentSpec::default().depends_on(&[components::COLLECTOR]),
+            )
+            .await;
+        registry
+            .declare(
+                components::COLLECTOR,
+                ComponentSpec::default().non_critical(),
+            )
+            .await;
+        registry
+            .declare(
+                components::SYNC_CLIENT,
+                ComponentSpec::default().non_critical(),
+            )
+            .await;
+        registry.set_ready(true).await;
+
+        // Off the critical path: reported but still ready
+        registry
+            .set_unhealthy(components::SYNC_CLIENT, "connection refused")
+            .await;
+        let readiness = registry.readiness().await;
+        assert!(readiness.ready);
+        assert_eq!(readiness.degraded, [components::SYNC_CLIENT]);
+
+        // Non-critical itself, but the predictor depends on it
+        registry
+            .set_unhealthy(components::COLLECTOR, "Failed to read cgroups")
+            .await;
+        let readiness = registry.readiness().await;
+        assert!(!readiness.ready);
+        assert!(readiness.reason.unwrap().contains(components::COLLECTOR));
+
+        let health = registry.health().await;
+        assert_eq!(
+            health.components[components::PREDICTOR].depends_on,
+            [components::COLLECTOR]
+        );
+        assert!(!health.components[components::COLLECTOR].critical);
+    }
+
+    #[tokio::test]
+    async fn test_component_error_counts_and_last_success() {
+        let registry = HealthRegistry::new();
+        registry.register(components::COLLECTOR).await;
+        let health = registry.health().await;
+        assert_eq!(health.components[components::COLLECTOR].error_count, 0);
+        assert!(health.components[components::COLLECTOR]
+            .last_success_timestamp
+            .is_none());
+
+        registry.set_degraded(components::COLLECTOR, "slow").await;
+        registry.set_healthy(components::COLLECTOR).await;
+        registry
+            .set_unhealthy(components::COLLECTOR, "failed")
+            .await;
+        let health = registry.health().await;
+        let collector = &health.components[components::COLLECTOR];
+        assert_eq!(collector.error_count, 2);
+        assert!(collector.last_success_timestamp.is_some());
+
+        let reporter = registry
+            .reporter(components::PREDICTOR, HealthPolicy::default())
+            .await;
+        reporter.failure("timeout");
+        reporter.success();
+        reporter.failure("timeout");
+        let predictor = reporter.evaluate();
+        assert_eq!(predictor.error_count, 2);
+        assert!(predictor.last_success_timestamp.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reporter_failure_thresholds() {
+        let registry = HealthRegistry::new();
+        let reporter = registry
+            .reporter(components::PREDICTOR, HealthPolicy::default())
+            .await;
+        assert_eq!(registry.health().await.status, ComponentStatus::Healthy);
+
+        for _ in 0..DEFAULT_DEGRADED_AFTER_FAILURES {
+            reporter.failure("inference timed out");
+        }
+        let health = registry.health().await;
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert!(health.components[components::PREDICTOR]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("inference timed out"));
+
+        for _ in DEFAULT_DEGRADED_AFTER_FAILURES..DEFAULT_UNHEALTHY_AFTER_FAILURES {
+            reporter.failure("inference timed out");
+        }
+        assert_eq!(registry.health().await.status, ComponentStatus::Unhealthy);
+        assert!(!registry.readiness().await.ready);
+
+        reporter.success();
+        assert_eq!(registry.health().await.status, ComponentStatus::Healthy);
+
+        // Degrade-only components never fail the liveness probe
+        let sync = ComponentReporter::new(HealthPolicy::default().degrade_only());
+        for _ in 0..100 {
+            sync.failure("connection refused");
+        }
+        assert_eq!(sync.evaluate().status, ComponentStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn test_reporter_staleness_and_conditions() {
+        let reporter =
+            ComponentReporter::new(HealthPolicy::default().stale_after(Duration::from_millis(20)));
+        reporter.set_condition(Some("Buffer 95% full".to_string()));
+        let health = reporter.evaluate();
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert_eq!(health.message.as_deref(), Some("Buffer 95% full"));
+
+        reporter.set_condition(None);
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Healthy);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Unhealthy);
+
+        reporter.heartbeat();
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Healthy);
+        assert!(reporter.since_heartbeat() < Duration::from_millis(20));
+    }
 }
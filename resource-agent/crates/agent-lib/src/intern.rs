@@ -0,0 +1,133 @@
+//! Interned container identity strings
+//!
+//! Container IDs, pod names and namespaces are held by the registry, the
+//! scheduler, the anomaly store and the sync streams, for every container
+//! and often for every sample. Interning them as `Arc<str>` lets all of
+//! these share one allocation per string, and `ContainerKey` bundles the
+//! three so that a container's identity clones by bumping reference counts.
+//! Strings are converted back to `String` only at the serde and protobuf
+//! boundaries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Pool of interned strings
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: Mutex<HashSet<Arc<str>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shared copy of `value`, added to the pool on first use
+    pub fn intern(&self, value: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = strings.get(value) {
+            return existing.clone();
+        }
+        let interned: Arc<str> = Arc::from(value);
+        strings.insert(interned.clone());
+        interned
+    }
+
+    /// Drop strings nothing refers to anymore
+    pub fn purge(&self) {
+        let mut strings = self.strings.lock().unwrap_or_else(|e| e.into_inner());
+        strings.retain(|s| Arc::strong_count(s) > 1);
+    }
+
+    /// Number of strings in the pool
+    pub fn len(&self) -> usize {
+        self.strings.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Process-wide pool of container identity strings
+pub fn interner() -> &'static Interner {
+    static INTERNER: OnceLock<Interner> = OnceLock::new();
+    INTERNER.get_or_init(Interner::new)
+}
+
+/// Intern `value` in the process-wide pool
+pub fn intern(value: &str) -> Arc<str> {
+    interner().intern(value)
+}
+
+/// Identity of a container, cheap to clone, hash and compare
+///
+/// Serializes as plain strings, like the fields it replaces.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ContainerKey {
+    pub container_id: Arc<str>,
+    pub pod_name: Arc<str>,
+    pub namespace: Arc<str>,
+}
+
+impl ContainerKey {
+    /// Key with interned strings
+    pub fn new(container_id: &str, pod_name: &str, namespace: &str) -> Self {
+        let interner = interner();
+        Self {
+            container_id: interner.intern(container_id),
+            pod_name: interner.intern(pod_name),
+            namespace: interner.intern(namespace),
+        }
+    }
+
+    /// Same key with its strings interned, e.g. after deserializing
+    pub fn interned(&self) -> Self {
+        Self::new(&self.container_id, &self.pod_name, &self.namespace)
+    }
+}
+
+impl fmt::Display for ContainerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.namespace, self.pod_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_share_strings() {
+        let a = ContainerKey::new("c1", "api-7d4f8b-x2k9p", "payments");
+        let b = ContainerKey::new("c2", "api-7d4f8b-x2k9p", "payments");
+        assert!(Arc::ptr_eq(&a.pod_name, &b.pod_name));
+        assert!(Arc::ptr_eq(&a.namespace, &b.namespace));
+        assert_ne!(a, b);
+        assert_eq!(a, ContainerKey::new("c1", "api-7d4f8b-x2k9p", "payments"));
+    }
+
+    #[test]
+    fn test_purge_drops_unused_strings() {
+        let interner = Interner::new();
+        let kept = interner.intern("default");
+        drop(interner.intern("checkout-5c9d7-abcde"));
+        assert_eq!(interner.len(), 2);
+
+        interner.purge();
+        assert_eq!(interner.len(), 1);
+        assert!(Arc::ptr_eq(&kept, &interner.intern("default")));
+    }
+
+    #[test]
+    fn test_key_serializes_as_strings() {
+        let key = ContainerKey::new("c1", "pod", "default");
+        let json = serde_json::to_value(&key).unwrap();
+        assert_eq!(json["container_id"], "c1");
+
+        let back: ContainerKey = serde_json::from_value(json).unwrap();
+        assert!(Arc::ptr_eq(&back.interned().namespace, &key.namespace));
+    }
+}
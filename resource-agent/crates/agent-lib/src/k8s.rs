@@ -0,0 +1,672 @@
+//! Kubernetes custom resources for recommendations and anomalies
+//!
+//! `ResourceRecommendation` matches the `predictor.io/v1` CRD installed by
+//! the Helm chart and reconciled by the Recommendation API's controller.
+//! `AnomalyReport` publishes anomalies detected by agents so they can be
+//! listed with `kubectl get anomalyreports`. Both can be built from the
+//! protobuf models sent over gRPC.
+
+use crate::models::WorkloadKind;
+use crate::proto::{self, AnomalyType, Severity, TimeWindow};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// API group of the custom resources
+pub const GROUP: &str = "predictor.io";
+
+/// Label set on every object published by the agent
+pub const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+
+/// Value of [`MANAGED_BY_LABEL`]
+pub const MANAGED_BY: &str = "resource-agent";
+
+/// Longest name allowed for a Kubernetes object
+const MAX_NAME_LEN: usize = 253;
+
+/// Desired state of a ResourceRecommendation
+#[derive(CustomResource, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[kube(
+    group = "predictor.io",
+    version = "v1",
+    kind = "ResourceRecommendation",
+    namespaced,
+    status = "ResourceRecommendationStatus",
+    shortname = "rr",
+    shortname = "resrec",
+    printcolumn = r#"{"name":"Target","type":"string","jsonPath":".spec.targetRef.name"}"#,
+    printcolumn = r#"{"name":"CPU-Req","type":"string","jsonPath":".spec.recommendation.cpuRequest"}"#,
+    printcolumn = r#"{"name":"Mem-Req","type":"string","jsonPath":".spec.recommendation.memoryRequest"}"#,
+    printcolumn = r#"{"name":"Confidence","type":"number","jsonPath":".spec.recommendation.confidence"}"#,
+    printcolumn = r#"{"name":"Phase","type":"string","jsonPath":".status.phase"}"#,
+    printcolumn = r#"{"name":"Age","type":"date","jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceRecommendationSpec {
+    pub target_ref: TargetRef,
+    pub recommendation: Recommendation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_impact: Option<CostImpact>,
+    #[serde(default)]
+    pub auto_apply: bool,
+    #[serde(default = "default_requires_approval")]
+    pub requires_approval: bool,
+    #[serde(default)]
+    pub risk_level: RiskLevel,
+}
+
+fn default_requires_approval() -> bool {
+    true
+}
+
+/// Workload a recommendation applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetRef {
+    #[serde(default = "default_target_api_version")]
+    pub api_version: String,
+    pub kind: TargetKind,
+    pub name: String,
+    /// Container to resize when the workload has several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+}
+
+fn default_target_api_version() -> String {
+    "apps/v1".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TargetKind {
+    #[default]
+    Deployment,
+    StatefulSet,
+    DaemonSet,
+    ReplicaSet,
+    /// Argo Rollout
+    Rollout,
+}
+
+impl TargetKind {
+    /// Target kind of a workload
+    ///
+    /// `None` for workloads that run to completion, and for bare ReplicaSets,
+    /// whose pods are not replaced when their template changes.
+    pub fn from_workload(kind: WorkloadKind) -> Option<Self> {
+        match kind {
+            WorkloadKind::Deployment => Some(Self::Deployment),
+            WorkloadKind::StatefulSet => Some(Self::StatefulSet),
+            WorkloadKind::DaemonSet => Some(Self::DaemonSet),
+            WorkloadKind::Rollout => Some(Self::Rollout),
+            WorkloadKind::ReplicaSet | WorkloadKind::Job | WorkloadKind::CronJob => None,
+        }
+    }
+
+    /// API version serving the kind
+    pub fn api_version(&self) -> &'static str {
+        match self {
+            Self::Rollout => "argoproj.io/v1alpha1",
+            _ => "apps/v1",
+        }
+    }
+}
+
+/// Recommended requests and limits as Kubernetes quantities
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_request: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_limit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_request: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<String>,
+    /// Between 0 and 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub time_window: RecommendationWindow,
+}
+
+/// Time window a recommendation applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecommendationWindow {
+    Peak,
+    OffPeak,
+    Weekly,
+    #[default]
+    All,
+}
+
+impl RecommendationWindow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecommendationWindow::Peak => "peak",
+            RecommendationWindow::OffPeak => "off-peak",
+            RecommendationWindow::Weekly => "weekly",
+            RecommendationWindow::All => "all",
+        }
+    }
+
+    /// Convert a proto time window value, treating unspecified as all
+    pub fn from_proto(value: i32) -> Result<Self> {
+        match value {
+            v if v == TimeWindow::Unspecified as i32 => Ok(RecommendationWindow::All),
+            v if v == TimeWindow::Peak as i32 => Ok(RecommendationWindow::Peak),
+            v if v == TimeWindow::OffPeak as i32 => Ok(RecommendationWindow::OffPeak),
+            v if v == TimeWindow::Weekly as i32 => Ok(RecommendationWindow::Weekly),
+            other => anyhow::bail!("Unknown time window: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CostImpact {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_monthly_cost: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projected_monthly_cost: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_savings: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+/// Observed state of a ResourceRecommendation, written by the controller
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceRecommendationStatus {
+    #[serde(default)]
+    pub phase: RecommendationPhase,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<Condition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approved_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approved_by: Option<String>,
+    /// Resources in place before the recommendation was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_resources: Option<PreviousResources>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_patch: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum RecommendationPhase {
+    #[default]
+    Pending,
+    Approved,
+    Applied,
+    RolledBack,
+    Failed,
+    Rejected,
+}
+
+/// Standard Kubernetes status condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    /// Ready, Approved, Applied or Healthy
+    #[serde(rename = "type")]
+    pub condition_type: String,
+    pub status: ConditionStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_transition_time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ConditionStatus {
+    True,
+    False,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousResources {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_request: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_limit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_request: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<String>,
+}
+
+/// Workload health observed after applying a recommendation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Outcome {
+    #[serde(default)]
+    pub oom_kills: u32,
+    /// Percentage increase in CPU throttling
+    #[serde(default)]
+    pub cpu_throttle_increase: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observation_period: Option<String>,
+    #[serde(default)]
+    pub healthy: bool,
+}
+
+impl ResourceRecommendation {
+    /// Build a pending recommendation for the profile's owning workload
+    ///
+    /// Profiles without an owner target a deployment named after the
+    /// `deployment` field, or the pod. Named `<workload>-<time window>` so
+    /// that newer profiles for the same workload and window replace the
+    /// object instead of adding one.
+    pub fn from_proto(profile: &proto::ResourceProfile) -> Result<Self> {
+        let (kind, target) = match &profile.owner {
+            Some(owner) => {
+                let workload: WorkloadKind = owner.kind.parse()?;
+                let kind = TargetKind::from_workload(workload).ok_or_else(|| {
+                    anyhow::anyhow!("{} workloads cannot be a recommendation target", workload)
+                })?;
+                (kind, &owner.name)
+            }
+            None if profile.deployment.is_empty() => (TargetKind::Deployment, &profile.pod_name),
+            None => (TargetKind::Deployment, &profile.deployment),
+        };
+        let time_window = RecommendationWindow::from_proto(profile.time_window)?;
+
+        let spec = ResourceRecommendationSpec {
+            target_ref: TargetRef {
+                api_version: kind.api_version().to_string(),
+                kind,
+                name: target.clone(),
+                container_name: None,
+            },
+            recommendation: Recommendation {
+                cpu_request: Some(cpu_quantity(profile.cpu_request_millicores)),
+                cpu_limit: (!profile.cpu_limit_unset)
+                    .then(|| cpu_quantity(profile.cpu_limit_millicores)),
+                memory_request: Some(memory_quantity(profile.memory_request_bytes)),
+                memory_limit: (!profile.memory_limit_unset)
+                    .then(|| memory_quantity(profile.memory_limit_bytes)),
+                confidence: Some(profile.confidence.clamp(0.0, 1.0) as f64),
+                model_version: Some(profile.model_version.clone())
+                    .filter(|version| !version.is_empty()),
+                generated_at: profile.generated_at.as_ref().and_then(timestamp),
+                time_window,
+            },
+            cost_impact: None,
+            auto_apply: false,
+            requires_approval: true,
+            risk_level: RiskLevel::Low,
+        };
+
+        let mut recommendation =
+            ResourceRecommendation::new(&object_name(&[target, time_window.as_str()]), spec);
+        recommendation.metadata.namespace = Some(profile.namespace.clone());
+        recommendation.metadata.labels = Some(managed_labels());
+        Ok(recommendation)
+    }
+}
+
+/// Desired state of an AnomalyReport
+#[derive(CustomResource, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[kube(
+    group = "predictor.io",
+    version = "v1",
+    kind = "AnomalyReport",
+    namespaced,
+    status = "AnomalyReportStatus",
+    shortname = "ar",
+    printcolumn = r#"{"name":"Pod","type":"string","jsonPath":".spec.podName"}"#,
+    printcolumn = r#"{"name":"Type","type":"string","jsonPath":".spec.anomalyType"}"#,
+    printcolumn = r#"{"name":"Severity","type":"string","jsonPath":".spec.severity"}"#,
+    printcolumn = r#"{"name":"Phase","type":"string","jsonPath":".status.phase"}"#,
+    printcolumn = r#"{"name":"Age","type":"date","jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyReportSpec {
+    pub pod_name: String,
+    pub container_id: String,
+    /// Node whose agent detected the anomaly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    pub anomaly_type: AnomalyKind,
+    pub severity: AnomalySeverity,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum AnomalyKind {
+    MemoryLeak,
+    CpuSpike,
+    OomRisk,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::MemoryLeak => "memory-leak",
+            AnomalyKind::CpuSpike => "cpu-spike",
+            AnomalyKind::OomRisk => "oom-risk",
+        }
+    }
+
+    /// Convert a proto anomaly type value
+    pub fn from_proto(value: i32) -> Result<Self> {
+        match value {
+            v if v == AnomalyType::MemoryLeak as i32 => Ok(AnomalyKind::MemoryLeak),
+            v if v == AnomalyType::CpuSpike as i32 => Ok(AnomalyKind::CpuSpike),
+            v if v == AnomalyType::OomRisk as i32 => Ok(AnomalyKind::OomRisk),
+            other => anyhow::bail!("Unknown anomaly type: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum AnomalySeverity {
+    Warning,
+    Critical,
+}
+
+impl AnomalySeverity {
+    /// Convert a proto severity value
+    pub fn from_proto(value: i32) -> Result<Self> {
+        match value {
+            v if v == Severity::Warning as i32 => Ok(AnomalySeverity::Warning),
+            v if v == Severity::Critical as i32 => Ok(AnomalySeverity::Critical),
+            other => anyhow::bail!("Unknown severity: {}", other),
+        }
+    }
+}
+
+/// Observed state of an AnomalyReport
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyReportStatus {
+    #[serde(default)]
+    pub phase: AnomalyPhase,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum AnomalyPhase {
+    #[default]
+    Active,
+    Resolved,
+}
+
+impl AnomalyReport {
+    /// Build a report for an anomaly detected by the agent on `node_name`
+    ///
+    /// Named `<pod>-<type>-<detection time>` so that re-sending the same
+    /// anomaly updates its report.
+    pub fn from_proto(anomaly: &proto::Anomaly, node_name: Option<&str>) -> Result<Self> {
+        let anomaly_type = AnomalyKind::from_proto(anomaly.r#type)?;
+        let detected_at = anomaly.detected_at.as_ref().and_then(timestamp);
+
+        let spec = AnomalyReportSpec {
+            pod_name: anomaly.pod_name.clone(),
+            container_id: anomaly.container_id.clone(),
+            node_name: node_name.map(str::to_string),
+            anomaly_type,
+            severity: AnomalySeverity::from_proto(anomaly.severity)?,
+            message: anomaly.message.clone(),
+            detected_at,
+        };
+
+        let detected = detected_at
+            .map(|time| time.timestamp().to_string())
+            .unwrap_or_default();
+        let name = object_name(&[&anomaly.pod_name, anomaly_type.as_str(), &detected]);
+        let mut report = AnomalyReport::new(&name, spec);
+        report.metadata.namespace = Some(anomaly.namespace.clone());
+        report.metadata.labels = Some(managed_labels());
+        Ok(report)
+    }
+}
+
+fn managed_labels() -> BTreeMap<String, String> {
+    BTreeMap::from([(MANAGED_BY_LABEL.to_string(), MANAGED_BY.to_string())])
+}
+
+fn timestamp(ts: &prost_types::Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(ts.seconds, u32::try_from(ts.nanos).ok()?)
+}
+
+/// Join `parts` into a valid object name (RFC 1123 subdomain)
+fn object_name(parts: &[&str]) -> String {
+    let joined = parts
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    let name: String = joined
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(MAX_NAME_LEN)
+        .collect();
+    name.trim_matches(|c: char| c == '-' || c == '.')
+        .to_string()
+}
+
+/// Format millicores as a CPU quantity, in whole cores when exact
+pub fn cpu_quantity(millicores: u32) -> String {
+    if millicores > 0 && millicores % 1000 == 0 {
+        (millicores / 1000).to_string()
+    } else {
+        format!("{}m", millicores)
+    }
+}
+
+/// Format bytes as a memory quantity in the largest exact binary unit
+pub fn memory_quantity(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 4] = [
+        ("Ti", 1 << 40),
+        ("Gi", 1 << 30),
+        ("Mi", 1 << 20),
+        ("Ki", 1 << 10),
+    ];
+
+    for (suffix, size) in UNITS {
+        if bytes > 0 && bytes % size == 0 {
+            return format!("{}{}", bytes / size, suffix);
+        }
+    }
+    bytes.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::{CustomResourceExt, Resource};
+
+    fn profile() -> proto::ResourceProfile {
+        proto::ResourceProfile {
+            container_id: "abc123".to_string(),
+            pod_name: "api-7d9f-x2k4".to_string(),
+            namespace: "payments".to_string(),
+            deployment: "api".to_string(),
+            cpu_request_millicores: 250,
+            cpu_limit_millicores: 1000,
+            memory_request_bytes: 256 * 1024 * 1024,
+            memory_limit_bytes: 512 * 1024 * 1024 + 1,
+            confidence: 0.87,
+            model_version: "v1.2.0".to_string(),
+            generated_at: Some(prost_types::Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 0,
+            }),
+            time_window: TimeWindow::OffPeak as i32,
+            workload_kind: "deployment".to_string(),
+            owner: None,
+            container_name: String::new(),
+            cpu_limit_unset: false,
+            memory_limit_unset: false,
+            workload_cluster: String::new(),
+            spot_node: false,
+        }
+    }
+
+    #[test]
+    fn test_recommendation_crd_matches_chart() {
+        let crd = ResourceRecommendation::crd();
+        assert_eq!(
+            crd.metadata.name.as_deref(),
+            Some("resourcerecommendations.predictor.io")
+        );
+        assert_eq!(crd.spec.group, GROUP);
+        assert_eq!(crd.spec.scope, "Namespaced");
+        assert_eq!(
+            crd.spec.names.short_names,
+            Some(vec!["rr".to_string(), "resrec".to_string()])
+        );
+        assert!(crd.spec.versions[0].subresources.is_some());
+        assert_eq!(
+            crd.spec.versions[0]
+                .additional_printer_columns
+                .as_ref()
+                .map(Vec::len),
+            Some(6)
+        );
+
+        let crd = AnomalyReport::crd();
+        assert_eq!(
+            crd.metadata.name.as_deref(),
+            Some("anomalyreports.predictor.io")
+        );
+    }
+
+    #[test]
+    fn test_recommendation_from_proto() {
+        let rec = ResourceRecommendation::from_proto(&profile()).unwrap();
+        assert_eq!(rec.metadata.name.as_deref(), Some("api-off-peak"));
+        assert_eq!(rec.metadata.namespace.as_deref(), Some("payments"));
+        assert_eq!(ResourceRecommendation::api_version(&()), "predictor.io/v1");
+
+        let value = serde_json::to_value(&rec).unwrap();
+        assert_eq!(value["spec"]["targetRef"]["kind"], "Deployment");
+        assert_eq!(value["spec"]["targetRef"]["name"], "api");
+        assert_eq!(value["spec"]["recommendation"]["cpuRequest"], "250m");
+        assert_eq!(value["spec"]["recommendation"]["cpuLimit"], "1");
+        assert_eq!(value["spec"]["recommendation"]["memoryRequest"], "256Mi");
+        assert_eq!(value["spec"]["recommendation"]["memoryLimit"], "536870913");
+        assert_eq!(value["spec"]["recommendation"]["timeWindow"], "off-peak");
+        assert_eq!(value["spec"]["requiresApproval"], true);
+        assert_eq!(value["spec"]["riskLevel"], "low");
+    }
+
+    #[test]
+    fn test_recommendation_from_proto_owner() {
+        let mut profile = profile();
+        profile.owner = Some(proto::WorkloadRef {
+            kind: "rollout".to_string(),
+            name: "checkout".to_string(),
+        });
+        let rec = ResourceRecommendation::from_proto(&profile).unwrap();
+        assert_eq!(rec.metadata.name.as_deref(), Some("checkout-off-peak"));
+        assert_eq!(rec.spec.target_ref.kind, TargetKind::Rollout);
+        assert_eq!(rec.spec.target_ref.api_version, "argoproj.io/v1alpha1");
+
+        profile.owner = Some(proto::WorkloadRef {
+            kind: "cronjob".to_string(),
+            name: "report".to_string(),
+        });
+        assert!(ResourceRecommendation::from_proto(&profile).is_err());
+    }
+
+    #[test]
+    fn test_recommendation_deserializes_chart_defaults() {
+        let spec: ResourceRecommendationSpec = serde_json::from_value(serde_json::json!({
+            "targetRef": {"kind": "StatefulSet", "name": "db"},
+            "recommendation": {"cpuRequest": "500m"}
+        }))
+        .unwrap();
+        assert_eq!(spec.target_ref.api_version, "apps/v1");
+        assert_eq!(spec.target_ref.kind, TargetKind::StatefulSet);
+        assert_eq!(spec.recommendation.time_window, RecommendationWindow::All);
+        assert!(spec.requires_approval);
+        assert!(!spec.auto_apply);
+    }
+
+    #[test]
+    fn test_anomaly_report_from_proto() {
+        let anomaly = proto::Anomaly {
+            container_id: "abc123".to_string(),
+            pod_name: "API_7d9f".to_string(),
+            namespace: "payments".to_string(),
+            r#type: AnomalyType::MemoryLeak as i32,
+            severity: Severity::Critical as i32,
+            message: "Memory growing 2MB/min".to_string(),
+            detected_at: Some(prost_types::Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 0,
+            }),
+        };
+
+        let report = AnomalyReport::from_proto(&anomaly, Some("node-1")).unwrap();
+        assert_eq!(
+            report.metadata.name.as_deref(),
+            Some("api-7d9f-memory-leak-1700000000")
+        );
+        assert_eq!(report.spec.severity, AnomalySeverity::Critical);
+        assert_eq!(report.spec.node_name.as_deref(), Some("node-1"));
+
+        let unknown = proto::Anomaly {
+            r#type: 42,
+            ..anomaly
+        };
+        assert!(AnomalyReport::from_proto(&unknown, None).is_err());
+    }
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(cpu_quantity(0), "0m");
+        assert_eq!(cpu_quantity(1500), "1500m");
+        assert_eq!(cpu_quantity(2000), "2");
+        assert_eq!(memory_quantity(0), "0");
+        assert_eq!(memory_quantity(1536 * 1024), "1536Ki");
+        assert_eq!(memory_quantity(2 << 30), "2Gi");
+    }
+}
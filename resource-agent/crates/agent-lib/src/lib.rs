@@ -6,18 +6,62 @@
 //! - Anomaly detection
 //! - API synchronization
 //! - Health checks and observability
+//! - An embeddable runtime running all of the above as one unit
+//! - Typed module errors classified as retryable or fatal
+//! - Pausing alerts and predictions while the node is in maintenance
+//! - Flagging data collected on spot and preemptible nodes
+//! - Interned container identity shared across modules
+//! - Sample timestamps resistant to wall-clock steps
+//! - Live feed of samples and anomalies for streaming clients
+//! - Internal state snapshots for debugging
+//! - CSV and Parquet export of samples (Parquet with the `parquet` feature)
+//! - Offline replay of recorded metrics for tuning thresholds
+//! - Dry-run reports of would-be recommendations and anomalies
+//! - Synthetic metric streams for benchmarks and tests (`synthetic` feature)
+//! - OpenTelemetry trace export (`otel` feature)
+//! - Kubernetes custom resources for recommendations and anomalies (`k8s` feature)
+//! - Mutating admission webhook injecting recommendations into pods (`admission` feature)
+//!
+//! Model inference (`onnx`), API sync (`grpc-sync`), alert delivery
+//! (`alerting`), on-disk state (`persistence`), the Prometheus registry
+//! (`prometheus`), remote-write export (`remote-write`), cgroup watching
+//! (`cgroup-watch`), Kubernetes API lookups (`kube-api`) and Prometheus
+//! backfill (`backfill`) are on by default and can be turned off to embed
+//! only the collector or the predictor.
 
+#[cfg(feature = "admission")]
+pub mod admission;
 pub mod anomaly;
+pub mod clock;
 pub mod collector;
+pub mod error;
+pub mod export;
 pub mod health;
+pub mod intern;
+#[cfg(feature = "k8s")]
+pub mod k8s;
+pub mod live;
+pub mod maintenance;
 pub mod models;
+pub mod node_lifecycle;
 pub mod observability;
+#[cfg(feature = "otel")]
+pub mod otel;
 pub mod predictor;
 pub mod proto;
+pub mod replay;
+pub mod runtime;
+pub mod self_limit;
+pub mod simulation;
+pub mod state;
 pub mod sync;
+#[cfg(feature = "synthetic")]
+pub mod synthetic;
 
 pub use health::{
-    ComponentHealth, ComponentStatus, HealthRegistry, HealthResponse, ReadinessResponse,
+    ComponentHealth, ComponentReporter, ComponentSpec, ComponentStatus, HealthPolicy,
+    HealthRegistry, HealthResponse, ReadinessResponse,
 };
 pub use models::*;
-pub use observability::{AgentMetrics, StructuredLogger};
+pub use observability::{AgentHealthSummary, AgentMetrics, StructuredLogger};
+pub use self_limit::{DegradationLevel, SelfLimiter, SelfLimiterConfig};
@@ -0,0 +1,196 @@
+//! Live feed of metric samples and anomalies
+//!
+//! Collected samples and detected anomalies are broadcast to subscribers as
+//! they happen, so the agent API can stream them to a UI or to
+//! `crp debug tail` without polling. Nothing is buffered for subscribers
+//! that fall behind: they skip ahead and are told how many events they
+//! missed.
+
+use crate::anomaly::AnomalyRecord;
+use crate::models::ContainerMetrics;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Events kept for a subscriber that falls behind
+pub const DEFAULT_LIVE_CAPACITY: usize = 1024;
+
+/// Event published on the live feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Metrics(ContainerMetrics),
+    Anomaly(AnomalyRecord),
+}
+
+impl LiveEvent {
+    /// Event name, e.g. for the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::Metrics(_) => "metrics",
+            LiveEvent::Anomaly(_) => "anomaly",
+        }
+    }
+
+    pub fn container_id(&self) -> &str {
+        match self {
+            LiveEvent::Metrics(m) => &m.container_id,
+            LiveEvent::Anomaly(a) => &a.container_id,
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        match self {
+            LiveEvent::Metrics(m) => &m.namespace,
+            LiveEvent::Anomaly(a) => &a.namespace,
+        }
+    }
+}
+
+/// Subscriber-side selection of live events
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LiveFilter {
+    pub namespace: Option<String>,
+    pub container_id: Option<String>,
+    /// Only anomalies, without metric samples
+    #[serde(default)]
+    pub anomalies_only: bool,
+}
+
+impl LiveFilter {
+    pub fn matches(&self, event: &LiveEvent) -> bool {
+        if self.anomalies_only && matches!(event, LiveEvent::Metrics(_)) {
+            return false;
+        }
+        self.namespace
+            .as_deref()
+            .map(|ns| event.namespace() == ns)
+            .unwrap_or(true)
+            && self
+                .container_id
+                .as_deref()
+                .map(|id| event.container_id() == id)
+                .unwrap_or(true)
+    }
+}
+
+/// Broadcast channel of live events, cheap to clone
+#[derive(Debug, Clone)]
+pub struct LiveFeed {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl LiveFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Subscribe to events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Number of active subscribers
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Publish a collected sample, skipped without subscribers
+    pub fn publish_metrics(&self, metrics: &ContainerMetrics) {
+        if self.subscribers() > 0 {
+            let _ = self.sender.send(LiveEvent::Metrics(metrics.clone()));
+        }
+    }
+
+    /// Publish a detected anomaly, skipped without subscribers
+    pub fn publish_anomaly(&self, record: &AnomalyRecord) {
+        if self.subscribers() > 0 {
+            let _ = self.sender.send(LiveEvent::Anomaly(record.clone()));
+        }
+    }
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIVE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anomaly::{AlertSeverity, AlertType};
+
+    fn metrics(container_id: &str, namespace: &str) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: container_id.to_string(),
+            pod_name: "pod".to_string(),
+            namespace: namespace.to_string(),
+            owner: None,
+            timestamp: 0,
+            cpu_usage_cores: 0.5,
+            cpu_throttled_periods: 0,
+            memory_usage_bytes: 0,
+            memory_working_set_bytes: 0,
+            memory_cache_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        }
+    }
+
+    fn anomaly(container_id: &str) -> AnomalyRecord {
+        AnomalyRecord {
+            container_id: container_id.to_string(),
+            pod_name: "pod".to_string(),
+            namespace: "default".to_string(),
+            deployment: None,
+            anomaly_type: AlertType::MemoryLeak,
+            severity: AlertSeverity::Warning,
+            message: "Memory growing".to_string(),
+            detected_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_subscribers() {
+        let feed = LiveFeed::new(16);
+        // Nothing is sent, or cloned, without subscribers
+        feed.publish_metrics(&metrics("c1", "default"));
+
+        let mut rx = feed.subscribe();
+        feed.publish_metrics(&metrics("c1", "default"));
+        feed.publish_anomaly(&anomaly("c1"));
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.name(), "metrics");
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.name(), "anomaly");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = LiveFilter {
+            namespace: Some("default".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&LiveEvent::Metrics(metrics("c1", "default"))));
+        assert!(!filter.matches(&LiveEvent::Metrics(metrics("c1", "prod"))));
+
+        let filter = LiveFilter {
+            container_id: Some("c2".to_string()),
+            anomalies_only: true,
+            ..Default::default()
+        };
+        assert!(!filter.matches(&LiveEvent::Metrics(metrics("c2", "default"))));
+        assert!(filter.matches(&LiveEvent::Anomaly(anomaly("c2"))));
+        assert!(!filter.matches(&LiveEvent::Anomaly(anomaly("c1"))));
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let json = serde_json::to_value(LiveEvent::Anomaly(anomaly("c1"))).unwrap();
+        assert_eq!(json["type"], "anomaly");
+        assert_eq!(json["data"]["container_id"], "c1");
+    }
+}
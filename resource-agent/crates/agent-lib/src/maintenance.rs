@@ -0,0 +1,291 @@
+//! Node maintenance awareness
+//!
+//! While a node is cordoned and drained, its pods are evicted and restarted
+//! elsewhere, which looks like anomalies and skews recommendations.
+//! `MaintenanceMode` tracks whether the node is in maintenance, either
+//! because it is cordoned or because an operator signalled it through the
+//! agent API, and publishes the status so alerting and prediction can pause.
+//! Maintenance ends by itself once the node is uncordoned and no signal is
+//! left. Cordons are detected with the `kube-api` feature.
+
+#[cfg(feature = "kube-api")]
+use crate::node_lifecycle::NodeLifecycle;
+#[cfg(feature = "kube-api")]
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "kube-api")]
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "kube-api")]
+use tokio::sync::broadcast;
+use tokio::sync::watch;
+use tracing::info;
+#[cfg(feature = "kube-api")]
+use tracing::{debug, warn};
+
+/// Default interval between node object checks
+pub const DEFAULT_NODE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Directory of the in-cluster service account credentials
+#[cfg(feature = "kube-api")]
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// Taint set on cordoned nodes
+const UNSCHEDULABLE_TAINT: &str = "node.kubernetes.io/unschedulable";
+
+/// Whether and why the node is in maintenance
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    /// The node is marked unschedulable
+    pub cordoned: bool,
+    /// Reason given by a local maintenance signal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<String>,
+    /// When maintenance began (Unix seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+}
+
+impl MaintenanceStatus {
+    /// Whether alerting and predictions should pause
+    pub fn active(&self) -> bool {
+        self.cordoned || self.signal.is_some()
+    }
+}
+
+/// Shared maintenance state of the node
+#[derive(Debug, Clone)]
+pub struct MaintenanceMode {
+    sender: Arc<watch::Sender<MaintenanceStatus>>,
+}
+
+impl Default for MaintenanceMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaintenanceMode {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(MaintenanceStatus::default());
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    /// Receive the status whenever it changes
+    pub fn subscribe(&self) -> watch::Receiver<MaintenanceStatus> {
+        self.sender.subscribe()
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        self.sender.borrow().clone()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.sender.borrow().active()
+    }
+
+    /// Record whether the node is cordoned
+    pub fn set_cordoned(&self, cordoned: bool) {
+        self.update(|status| status.cordoned = cordoned);
+    }
+
+    /// Enter maintenance on a local signal
+    pub fn begin(&self, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.update(|status| status.signal = Some(reason));
+    }
+
+    /// Clear the local maintenance signal
+    pub fn end(&self) {
+        self.update(|status| status.signal = None);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut MaintenanceStatus)) {
+        self.sender.send_if_modified(|status| {
+            let before = status.clone();
+            change(status);
+            match (before.active(), status.active()) {
+                (false, true) => {
+                    status.since = Some(unix_now());
+                    info!(
+                        cordoned = status.cordoned,
+                        signal = ?status.signal,
+                        "Node entered maintenance, pausing alerts and predictions"
+                    );
+                }
+                (true, false) => {
+                    status.since = None;
+                    info!("Node left maintenance, resuming alerts and predictions");
+                }
+                _ => {}
+            }
+            *status != before
+        });
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Whether a node object is cordoned
+#[cfg_attr(not(feature = "kube-api"), allow(dead_code))]
+fn is_cordoned(node: &serde_json::Value) -> bool {
+    let spec = &node["spec"];
+    spec["unschedulable"].as_bool().unwrap_or(false)
+        || spec["taints"].as_array().is_some_and(|taints| {
+            taints
+                .iter()
+                .any(|taint| taint["key"].as_str() == Some(UNSCHEDULABLE_TAINT))
+        })
+}
+
+/// Polls the node object for cordons, and optionally its lifecycle
+#[cfg(feature = "kube-api")]
+pub struct NodeWatcher {
+    url: String,
+    token_path: PathBuf,
+    interval: Duration,
+    http: reqwest::Client,
+    lifecycle: Option<watch::Sender<NodeLifecycle>>,
+}
+
+#[cfg(feature = "kube-api")]
+impl NodeWatcher {
+    /// Watch `node_name` with the pod's service account
+    ///
+    /// Fails outside a cluster, where there is no API server to ask.
+    pub fn in_cluster(node_name: &str) -> Result<Self> {
+        let host = std::env::var("KUBERNETES_SERVICE_HOST")
+            .context("KUBERNETES_SERVICE_HOST is not set")?;
+        let port = std::env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".to_string());
+        let dir = Path::new(SERVICE_ACCOUNT_DIR);
+        let ca = std::fs::read(dir.join("ca.crt")).context("Failed to read cluster CA")?;
+        let http = reqwest::Client::builder()
+            .add_root_certificate(
+                reqwest::Certificate::from_pem(&ca).context("Invalid cluster CA")?,
+            )
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Kubernetes API client")?;
+
+        Ok(Self {
+            url: format!("https://{}:{}/api/v1/nodes/{}", host, port, node_name),
+            token_path: dir.join("token"),
+            interval: DEFAULT_NODE_POLL_INTERVAL,
+            http,
+            lifecycle: None,
+        })
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Also publish whether the node is spot or preemptible to `lifecycle`
+    pub fn with_lifecycle(mut self, lifecycle: watch::Sender<NodeLifecycle>) -> Self {
+        self.lifecycle = Some(lifecycle);
+        self
+    }
+
+    /// Fetch the node and check whether it is cordoned
+    pub async fn cordoned(&self) -> Result<bool> {
+        Ok(is_cordoned(&self.fetch_node().await?))
+    }
+
+    async fn fetch_node(&self) -> Result<serde_json::Value> {
+        // Re-read every time: the projected token is rotated by the kubelet
+        let token = tokio::fs::read_to_string(&self.token_path)
+            .await
+            .context("Failed to read service account token")?;
+        let node: serde_json::Value = self
+            .http
+            .get(&self.url)
+            .bearer_auth(token.trim())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("Failed to fetch node")?
+            .json()
+            .await
+            .context("Invalid node object")?;
+        Ok(node)
+    }
+
+    /// Update `mode` every interval until shutdown
+    ///
+    /// A failed check keeps the last known state.
+    pub async fn run(self, mode: MaintenanceMode, mut shutdown: broadcast::Receiver<()>) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => match self.fetch_node().await {
+                    Ok(node) => {
+                        let cordoned = is_cordoned(&node);
+                        debug!(cordoned = cordoned, "Checked node schedulability");
+                        mode.set_cordoned(cordoned);
+                        if let Some(lifecycle) = &self.lifecycle {
+                            let next = NodeLifecycle::from_node(&node);
+                            if lifecycle.send_if_modified(|current| {
+                                std::mem::replace(current, next) != next
+                            }) {
+                                info!(lifecycle = next.as_str(), "Node lifecycle detected");
+                            }
+                        }
+                    }
+                    Err(e) => warn!(
+                        error = %format!("{:#}", e),
+                        "Failed to check node schedulability"
+                    ),
+                },
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_cordoned() {
+        let node = |spec: serde_json::Value| json!({ "spec": spec });
+        assert!(!is_cordoned(&node(json!({}))));
+        assert!(is_cordoned(&node(json!({ "unschedulable": true }))));
+        assert!(is_cordoned(&node(json!({
+            "taints": [{ "key": UNSCHEDULABLE_TAINT, "effect": "NoSchedule" }]
+        }))));
+        assert!(!is_cordoned(&node(json!({
+            "taints": [{ "key": "dedicated", "effect": "NoSchedule" }]
+        }))));
+    }
+
+    #[test]
+    fn test_maintenance_from_cordon_or_signal() {
+        let mode = MaintenanceMode::new();
+        let status = mode.subscribe();
+
+        mode.set_cordoned(true);
+        assert!(mode.is_active());
+        let since = mode.status().since;
+        assert!(since.is_some());
+
+        // Still in maintenance until both the cordon and the signal clear
+        mode.begin("kernel upgrade");
+        mode.set_cordoned(false);
+        assert!(status.borrow().active());
+        assert_eq!(mode.status().since, since);
+
+        mode.end();
+        assert_eq!(mode.status(), MaintenanceStatus::default());
+    }
+}
@@ -1,6 +1,10 @@
 //! Core data models for the resource agent
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub use crate::clock::{HybridClock, HybridTimestamp};
+pub use crate::intern::ContainerKey;
 
 /// Container metrics collected from cgroups
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,7 +12,7 @@ pub struct ContainerMetrics {
     pub container_id: String,
     pub pod_name: String,
     pub namespace: String,
-    pub deployment: Option<String>,
+    pub owner: Option<OwnerRef>,
     pub timestamp: i64,
     pub cpu_usage_cores: f32,
     pub cpu_throttled_periods: u64,
@@ -19,8 +23,61 @@ pub struct ContainerMetrics {
     pub network_tx_bytes: u64,
 }
 
+impl ContainerMetrics {
+    /// Interned identity of the sampled container
+    pub fn key(&self) -> ContainerKey {
+        ContainerKey::new(&self.container_id, &self.pod_name, &self.namespace)
+    }
+}
+
+/// Node capacity, usage and pressure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeMetrics {
+    pub timestamp: i64,
+    /// CPU available to pods, or the node's capacity when not limited
+    pub cpu_allocatable_cores: f32,
+    /// Memory available to pods, or the node's capacity when not limited
+    pub memory_allocatable_bytes: u64,
+    pub cpu_usage_cores: f32,
+    pub memory_usage_bytes: u64,
+    /// Share of time some tasks stalled on CPU over 10s (PSI), in percent
+    pub cpu_pressure: f32,
+    /// Share of time some tasks stalled on memory over 10s (PSI), in percent
+    pub memory_pressure: f32,
+    /// Share of time some tasks stalled on IO over 10s (PSI), in percent
+    pub io_pressure: f32,
+}
+
+impl NodeMetrics {
+    /// Node pressure between 0 and 1
+    ///
+    /// The highest of CPU and memory usage relative to allocatable and of
+    /// CPU and memory stall time.
+    pub fn pressure(&self) -> f32 {
+        let cpu_usage = if self.cpu_allocatable_cores > 0.0 {
+            self.cpu_usage_cores / self.cpu_allocatable_cores
+        } else {
+            0.0
+        };
+        let memory_usage = if self.memory_allocatable_bytes > 0 {
+            (self.memory_usage_bytes as f64 / self.memory_allocatable_bytes as f64) as f32
+        } else {
+            0.0
+        };
+        [
+            cpu_usage,
+            memory_usage,
+            self.cpu_pressure / 100.0,
+            self.memory_pressure / 100.0,
+        ]
+        .into_iter()
+        .fold(0.0f32, f32::max)
+        .clamp(0.0, 1.0)
+    }
+}
+
 /// Resource profile recommendation output
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceProfile {
     pub cpu_request_millicores: u32,
     pub cpu_limit_millicores: u32,
@@ -29,6 +86,228 @@ pub struct ResourceProfile {
     pub confidence: f32,
     pub model_version: String,
     pub generated_at: i64,
+    /// How the recommendation interacts with the workload's HPA, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hpa_note: Option<String>,
+    /// How the recommendation changes the container's QoS class, if it does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos_note: Option<String>,
+    /// How restarting pods to apply it interacts with their disruption
+    /// budgets, if they constrain it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disruption_note: Option<String>,
+    /// Why the recommendation was deferred due to an anomaly, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anomaly_note: Option<String>,
+    /// Kind of workload the profile was predicted for
+    #[serde(default)]
+    pub workload_kind: WorkloadKind,
+    /// No CPU limit is recommended, rather than a limit of zero
+    #[serde(default)]
+    pub cpu_limit_unset: bool,
+    /// No memory limit is recommended, rather than a limit of zero
+    #[serde(default)]
+    pub memory_limit_unset: bool,
+    /// Cluster of workloads with a similar usage shape, see
+    /// `WorkloadFingerprint`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workload_cluster: Option<String>,
+    /// Derived from usage on a spot or preemptible node
+    #[serde(default)]
+    pub spot_node: bool,
+}
+
+impl ResourceProfile {
+    /// Raise the limits that are set to at least the requests
+    pub fn raise_limits_to_requests(&mut self) {
+        if !self.cpu_limit_unset {
+            self.cpu_limit_millicores = self.cpu_limit_millicores.max(self.cpu_request_millicores);
+        }
+        if !self.memory_limit_unset {
+            self.memory_limit_bytes = self.memory_limit_bytes.max(self.memory_request_bytes);
+        }
+    }
+}
+
+/// Recommendation for one container of a pod
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerProfile {
+    pub container_id: String,
+    /// Name of the container in the pod spec, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    pub profile: ResourceProfile,
+}
+
+/// Pod-level summary of the recommendations for a pod's containers
+///
+/// Requests are summed, matching how the scheduler places the pod, while
+/// limits are the largest container limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodProfile {
+    pub pod_name: String,
+    pub namespace: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<OwnerRef>,
+    pub cpu_request_millicores: u32,
+    pub cpu_limit_millicores: u32,
+    pub memory_request_bytes: u64,
+    pub memory_limit_bytes: u64,
+    /// Lowest confidence among the containers
+    pub confidence: f32,
+    pub generated_at: i64,
+    /// Per-container recommendations, empty when aggregated
+    #[serde(default)]
+    pub containers: Vec<ContainerProfile>,
+}
+
+/// How recommendations for the containers of a pod are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PodAggregation {
+    /// A recommendation per container, plus the pod summary
+    #[default]
+    Separate,
+    /// Only the pod summary
+    Aggregate,
+}
+
+/// Kind of workload a container belongs to
+///
+/// Long-running workloads are predicted from usage trends; Jobs and
+/// CronJobs run to completion and are predicted from per-run peaks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkloadKind {
+    #[default]
+    Deployment,
+    StatefulSet,
+    DaemonSet,
+    /// ReplicaSet not managed by a Deployment or Rollout
+    ReplicaSet,
+    Rollout,
+    Job,
+    CronJob,
+}
+
+impl WorkloadKind {
+    /// Whether the workload runs to completion rather than continuously
+    pub fn is_batch(&self) -> bool {
+        matches!(self, Self::Job | Self::CronJob)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deployment => "deployment",
+            Self::StatefulSet => "statefulset",
+            Self::DaemonSet => "daemonset",
+            Self::ReplicaSet => "replicaset",
+            Self::Rollout => "rollout",
+            Self::Job => "job",
+            Self::CronJob => "cronjob",
+        }
+    }
+}
+
+impl fmt::Display for WorkloadKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for WorkloadKind {
+    type Err = anyhow::Error;
+
+    /// Parse a kind as written by `as_str`, or its Kubernetes `Kind` name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "deployment" => Ok(Self::Deployment),
+            "statefulset" => Ok(Self::StatefulSet),
+            "daemonset" => Ok(Self::DaemonSet),
+            "replicaset" => Ok(Self::ReplicaSet),
+            "rollout" => Ok(Self::Rollout),
+            "job" => Ok(Self::Job),
+            "cronjob" => Ok(Self::CronJob),
+            _ => Err(anyhow::anyhow!("Unknown workload kind: {}", s)),
+        }
+    }
+}
+
+/// Workload owning a container
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OwnerRef {
+    pub kind: WorkloadKind,
+    pub name: String,
+}
+
+impl OwnerRef {
+    pub fn new(kind: WorkloadKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+        }
+    }
+
+    pub fn deployment(name: impl Into<String>) -> Self {
+        Self::new(WorkloadKind::Deployment, name)
+    }
+
+    /// Owner from a pod's controller owner reference
+    ///
+    /// Pods only reference their direct owner, so CronJobs are derived from
+    /// the scheduled-time suffix the CronJob controller appends to Job
+    /// names. ReplicaSets are returned as such; whether a Deployment or
+    /// Rollout manages them is only known from the pod's labels.
+    pub fn from_controller(kind: &str, name: &str) -> Option<Self> {
+        match kind {
+            "ReplicaSet" => Some(Self::new(WorkloadKind::ReplicaSet, name)),
+            "StatefulSet" => Some(Self::new(WorkloadKind::StatefulSet, name)),
+            "DaemonSet" => Some(Self::new(WorkloadKind::DaemonSet, name)),
+            "Rollout" => Some(Self::new(WorkloadKind::Rollout, name)),
+            "Job" => Some(match name.rsplit_once('-') {
+                Some((cronjob, suffix))
+                    if suffix.len() >= 8 && suffix.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    Self::new(WorkloadKind::CronJob, cronjob)
+                }
+                _ => Self::new(WorkloadKind::Job, name),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Name of the owner when it is a Deployment
+    pub fn deployment_name(&self) -> Option<&str> {
+        (self.kind == WorkloadKind::Deployment).then_some(self.name.as_str())
+    }
+}
+
+impl fmt::Display for OwnerRef {
+    /// Formats as `<kind>/<name>`, e.g. `statefulset/postgres`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.kind, self.name)
+    }
+}
+
+/// HorizontalPodAutoscaler scaling a workload on CPU utilization
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HpaTarget {
+    pub name: String,
+    pub min_replicas: u32,
+    pub max_replicas: u32,
+    pub current_replicas: u32,
+    /// Target average utilization, in percent of the CPU request
+    pub target_cpu_utilization_percent: u32,
+}
+
+/// PodDisruptionBudget covering a workload's pods
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisruptionBudget {
+    pub name: String,
+    /// Pods that may be evicted right now without violating the budget
+    pub disruptions_allowed: u32,
+    pub current_healthy: u32,
+    pub desired_healthy: u32,
 }
 
 /// Feature vector for ML inference
@@ -46,6 +325,38 @@ pub struct FeatureVector {
     pub hour_of_day: f32,
     pub day_of_week: f32,
     pub workload_age_days: f32,
+    /// Long-term pressure of the node the container runs on, 0 to 1
+    ///
+    /// Context for post-processing; not part of the model input.
+    #[serde(default)]
+    pub node_pressure: f32,
+    /// How likely the node is to be reclaimed, 1 on spot and preemptible
+    /// nodes, see `NodeLifecycle`
+    ///
+    /// Context for post-processing and training; not part of the model
+    /// input.
+    #[serde(default)]
+    pub node_volatility: f32,
+}
+
+impl FeatureVector {
+    /// Model input values, in the order the model was trained on
+    pub fn model_input(&self) -> Vec<f32> {
+        vec![
+            self.cpu_usage_p50,
+            self.cpu_usage_p95,
+            self.cpu_usage_p99,
+            self.mem_usage_p50,
+            self.mem_usage_p95,
+            self.mem_usage_p99,
+            self.cpu_variance,
+            self.mem_trend,
+            self.throttle_ratio,
+            self.hour_of_day,
+            self.day_of_week,
+            self.workload_age_days,
+        ]
+    }
 }
 
 /// Container information for discovery
@@ -54,7 +365,73 @@ pub struct ContainerInfo {
     pub container_id: String,
     pub pod_name: String,
     pub namespace: String,
-    pub deployment: Option<String>,
+    pub owner: Option<OwnerRef>,
     pub node_name: String,
     pub cgroup_path: String,
+    /// Container name within its pod, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(default)]
+    pub role: ContainerRole,
+}
+
+impl ContainerInfo {
+    /// Interned identity of the container
+    pub fn key(&self) -> ContainerKey {
+        ContainerKey::new(&self.container_id, &self.pod_name, &self.namespace)
+    }
+}
+
+/// Sidecars commonly injected by meshes and platform tooling
+pub const WELL_KNOWN_SIDECARS: &[&str] = &[
+    "istio-proxy",
+    "linkerd-proxy",
+    "envoy",
+    "envoy-sidecar",
+    "consul-dataplane",
+    "vault-agent",
+    "cloud-sql-proxy",
+    "datadog-agent",
+    "fluent-bit",
+];
+
+/// Role of a container within its pod
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRole {
+    /// Application container, the default when the role is unknown
+    #[default]
+    Main,
+    /// Runs to completion before the main containers start
+    Init,
+    /// Supporting container next to the application
+    Sidecar,
+}
+
+impl ContainerRole {
+    /// Classify a regular container by name
+    pub fn from_name(name: &str) -> Self {
+        if WELL_KNOWN_SIDECARS.contains(&name) {
+            Self::Sidecar
+        } else {
+            Self::Main
+        }
+    }
+
+    /// Classify an entry of a pod's `initContainers`
+    ///
+    /// Init containers with `restartPolicy: Always` are native sidecars and
+    /// keep running next to the main containers.
+    pub fn from_init_container(restart_policy: Option<&str>) -> Self {
+        if restart_policy == Some("Always") {
+            Self::Sidecar
+        } else {
+            Self::Init
+        }
+    }
+
+    /// Whether resource predictions should be made for the container
+    pub fn is_predicted(&self) -> bool {
+        !matches!(self, Self::Init)
+    }
 }
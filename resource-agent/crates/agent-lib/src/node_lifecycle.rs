@@ -0,0 +1,109 @@
+//! Spot and preemptible node awareness
+//!
+//! Spot and preemptible nodes are reclaimed by the cloud provider at short
+//! notice, so containers on them are restarted often and their usage
+//! windows are short and interrupted. The node's lifecycle is read from the
+//! labels cloud providers and node provisioners set, and attached to
+//! features and recommendations so the recommendation API can weigh data
+//! collected on such nodes differently.
+
+use serde::{Deserialize, Serialize};
+
+/// Labels marking a node as spot or preemptible, and their values when it is
+///
+/// Values are compared case-insensitively.
+const SPOT_LABELS: [(&str, &str); 6] = [
+    ("eks.amazonaws.com/capacityType", "spot"),
+    ("karpenter.sh/capacity-type", "spot"),
+    ("cloud.google.com/gke-spot", "true"),
+    ("cloud.google.com/gke-preemptible", "true"),
+    ("kubernetes.azure.com/scalesetpriority", "spot"),
+    ("node.kubernetes.io/lifecycle", "spot"),
+];
+
+/// Generic lifecycle label value used for preemptible nodes
+const PREEMPTIBLE_LIFECYCLE: &str = "preemptible";
+
+/// Whether the node may be reclaimed by the cloud provider at short notice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeLifecycle {
+    #[default]
+    OnDemand,
+    /// Spot or preemptible capacity
+    Spot,
+}
+
+impl NodeLifecycle {
+    /// Lifecycle of a node object, from its labels
+    pub fn from_node(node: &serde_json::Value) -> Self {
+        let labels = &node["metadata"]["labels"];
+        let label = |key: &str| labels[key].as_str().map(str::to_ascii_lowercase);
+        let spot = SPOT_LABELS
+            .iter()
+            .any(|(key, value)| label(key).as_deref() == Some(*value))
+            || label("node.kubernetes.io/lifecycle").as_deref() == Some(PREEMPTIBLE_LIFECYCLE);
+        if spot {
+            Self::Spot
+        } else {
+            Self::OnDemand
+        }
+    }
+
+    pub fn is_spot(&self) -> bool {
+        *self == Self::Spot
+    }
+
+    /// How likely the node is to be reclaimed, 0 (stable) to 1
+    pub fn volatility(&self) -> f32 {
+        match self {
+            Self::OnDemand => 0.0,
+            Self::Spot => 1.0,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OnDemand => "ondemand",
+            Self::Spot => "spot",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(labels: serde_json::Value) -> serde_json::Value {
+        json!({ "metadata": { "labels": labels } })
+    }
+
+    #[test]
+    fn test_lifecycle_from_labels() {
+        assert_eq!(
+            NodeLifecycle::from_node(&json!({})),
+            NodeLifecycle::OnDemand
+        );
+        assert_eq!(
+            NodeLifecycle::from_node(&node(
+                json!({ "eks.amazonaws.com/capacityType": "ON_DEMAND" })
+            )),
+            NodeLifecycle::OnDemand
+        );
+
+        let spot = [
+            json!({ "eks.amazonaws.com/capacityType": "SPOT" }),
+            json!({ "karpenter.sh/capacity-type": "spot" }),
+            json!({ "cloud.google.com/gke-spot": "true" }),
+            json!({ "cloud.google.com/gke-preemptible": "true" }),
+            json!({ "kubernetes.azure.com/scalesetpriority": "spot" }),
+            json!({ "node.kubernetes.io/lifecycle": "preemptible" }),
+        ];
+        for labels in spot {
+            let lifecycle = NodeLifecycle::from_node(&node(labels.clone()));
+            assert!(lifecycle.is_spot(), "{} is spot", labels);
+            assert_eq!(lifecycle.volatility(), 1.0);
+        }
+    }
+}
@@ -1,20 +1,71 @@
 //! Observability infrastructure for the resource agent
 //!
 //! Provides:
-//! - Prometheus metrics (collection latency, prediction latency, buffer size, model version)
+//! - Prometheus metrics (collection latency, prediction latency, buffer size, model version,
+//!   sync traffic), counted in-process only without the `prometheus` feature
 //! - Structured JSON logging with tracing
+//! - Health summaries for agent heartbeats
+//! - OTLP push export of the Prometheus metrics (`prometheus` feature)
+//! - Prometheus remote-write export of collected container metrics (`remote-write` feature)
+
+#[cfg(not(feature = "prometheus"))]
+mod counters;
+mod labels;
+#[cfg(feature = "prometheus")]
+mod otlp;
+#[cfg(feature = "remote-write")]
+mod remote_write;
+
+pub use labels::{MetricLabelConfig, DEFAULT_MAX_LABEL_SETS, OVERFLOW_LABEL};
+#[cfg(feature = "prometheus")]
+pub use otlp::{
+    OtlpMetricsConfig, OtlpMetricsExporter, DEFAULT_OTLP_METRICS_ENDPOINT,
+    DEFAULT_OTLP_METRICS_INTERVAL,
+};
+#[cfg(feature = "remote-write")]
+pub use remote_write::{
+    RemoteWriteConfig, RemoteWriteExporter, RemoteWriteHandle, RemoteWriteStats,
+    DEFAULT_REMOTE_WRITE_BATCH_SIZE, DEFAULT_REMOTE_WRITE_FLUSH_INTERVAL,
+};
 
+use crate::self_limit::DegradationLevel;
+use crate::sync::CircuitState;
+#[cfg(not(feature = "prometheus"))]
+use counters::{
+    register_gauge_vec, register_histogram, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, GaugeVec, Histogram, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec,
+};
+use labels::LabelLimiter;
+#[cfg(feature = "prometheus")]
 use prometheus::{
-    register_gauge_vec, register_histogram, register_int_gauge, GaugeVec, Histogram, IntGauge,
+    register_gauge_vec, register_histogram, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, GaugeVec, Histogram, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
-use std::sync::OnceLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock};
 use tracing::{info, warn};
 
 /// Default histogram buckets for latency measurements (in seconds)
+#[cfg(feature = "prometheus")]
 const LATENCY_BUCKETS: &[f64] = &[
     0.0001, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
 ];
 
+/// Histogram buckets for sync batch round trips (in seconds)
+#[cfg(feature = "prometheus")]
+const SYNC_LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Histogram buckets for encoded sync batch sizes (in bytes)
+#[cfg(feature = "prometheus")]
+const BATCH_SIZE_BUCKETS: &[f64] = &[
+    1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0,
+];
+
 /// Global metrics instance (registered once)
 static GLOBAL_METRICS: OnceLock<AgentMetricsInner> = OnceLock::new();
 
@@ -26,10 +77,32 @@ struct AgentMetricsInner {
     buffer_items: IntGauge,
     model_version_info: GaugeVec,
     containers_monitored: IntGauge,
-    predictions_generated: IntGauge,
-    anomalies_detected: IntGauge,
-    collection_errors: IntGauge,
-    prediction_errors: IntGauge,
+    predictions_generated: IntCounter,
+    anomalies_detected: IntCounter,
+    collection_errors: IntCounter,
+    prediction_errors: IntCounter,
+    sync_batches_sent: IntCounter,
+    sync_failures: IntCounter,
+    sync_bytes_sent: IntCounter,
+    sync_batch_size_bytes: Histogram,
+    sync_batch_latency_seconds: Histogram,
+    sync_reconnect_attempts: IntCounter,
+    sync_dropped: IntCounterVec,
+    buffer_dropped: IntCounter,
+    model_updates: IntCounterVec,
+    reconcile_drift: IntCounterVec,
+    namespace_containers_monitored: IntGaugeVec,
+    namespace_predictions: IntCounterVec,
+    namespace_anomalies: IntCounterVec,
+    /// Label admission for the per-namespace metrics
+    label_limiter: Mutex<LabelLimiter>,
+    last_collection_timestamp: IntGauge,
+    sync_circuit_state: IntGauge,
+    config_generation: IntGauge,
+    degradation_level: IntGauge,
+    stale_profiles: IntGauge,
+    /// Loaded model version, kept for health summaries
+    model_version: RwLock<Option<String>>,
 }
 
 impl AgentMetricsInner {
@@ -74,29 +147,149 @@ impl AgentMetricsInner {
             )
             .expect("Failed to register containers_monitored"),
 
-            predictions_generated: register_int_gauge!(
+            predictions_generated: register_int_counter!(
                 "resource_agent_predictions_generated_total",
                 "Total number of predictions generated"
             )
             .expect("Failed to register predictions_generated"),
 
-            anomalies_detected: register_int_gauge!(
+            anomalies_detected: register_int_counter!(
                 "resource_agent_anomalies_detected_total",
                 "Total number of anomalies detected"
             )
             .expect("Failed to register anomalies_detected"),
 
-            collection_errors: register_int_gauge!(
+            collection_errors: register_int_counter!(
                 "resource_agent_collection_errors_total",
                 "Total number of metric collection errors"
             )
             .expect("Failed to register collection_errors"),
 
-            prediction_errors: register_int_gauge!(
+            prediction_errors: register_int_counter!(
                 "resource_agent_prediction_errors_total",
                 "Total number of prediction errors"
             )
             .expect("Failed to register prediction_errors"),
+
+            sync_batches_sent: register_int_counter!(
+                "resource_agent_sync_batches_sent_total",
+                "Total number of batches pushed to the recommendation API"
+            )
+            .expect("Failed to register sync_batches_sent"),
+
+            sync_failures: register_int_counter!(
+                "resource_agent_sync_failures_total",
+                "Total number of batches that could not be sent after retries"
+            )
+            .expect("Failed to register sync_failures"),
+
+            sync_bytes_sent: register_int_counter!(
+                "resource_agent_sync_bytes_sent_total",
+                "Total encoded bytes of batches pushed to the recommendation API"
+            )
+            .expect("Failed to register sync_bytes_sent"),
+
+            sync_batch_size_bytes: register_histogram!(
+                "resource_agent_sync_batch_size_bytes",
+                "Encoded size of batches pushed to the recommendation API",
+                BATCH_SIZE_BUCKETS.to_vec()
+            )
+            .expect("Failed to register sync_batch_size_bytes"),
+
+            sync_batch_latency_seconds: register_histogram!(
+                "resource_agent_sync_batch_latency_seconds",
+                "Time spent pushing a batch, including retries",
+                SYNC_LATENCY_BUCKETS.to_vec()
+            )
+            .expect("Failed to register sync_batch_latency_seconds"),
+
+            sync_reconnect_attempts: register_int_counter!(
+                "resource_agent_sync_reconnect_attempts_total",
+                "Total number of failed connections to the recommendation API"
+            )
+            .expect("Failed to register sync_reconnect_attempts"),
+
+            sync_dropped: register_int_counter_vec!(
+                "resource_agent_sync_dropped_total",
+                "Items dropped because the streaming queue was full, by category",
+                &["category"]
+            )
+            .expect("Failed to register sync_dropped"),
+
+            buffer_dropped: register_int_counter!(
+                "resource_agent_buffer_dropped_total",
+                "Total number of buffered samples evicted because the buffer was full"
+            )
+            .expect("Failed to register buffer_dropped"),
+
+            model_updates: register_int_counter_vec!(
+                "resource_agent_model_updates_total",
+                "Model update attempts by result (applied, rejected, failed)",
+                &["result"]
+            )
+            .expect("Failed to register model_updates"),
+
+            reconcile_drift: register_int_counter_vec!(
+                "resource_agent_reconcile_drift_total",
+                "Containers the registry reconciler had to fix, by action (added, removed)",
+                &["action"]
+            )
+            .expect("Failed to register reconcile_drift"),
+
+            namespace_containers_monitored: register_int_gauge_vec!(
+                "resource_agent_namespace_containers_monitored",
+                "Containers currently monitored per namespace",
+                &["namespace", "deployment"]
+            )
+            .expect("Failed to register namespace_containers_monitored"),
+
+            namespace_predictions: register_int_counter_vec!(
+                "resource_agent_namespace_predictions_total",
+                "Predictions generated per namespace",
+                &["namespace", "deployment"]
+            )
+            .expect("Failed to register namespace_predictions"),
+
+            namespace_anomalies: register_int_counter_vec!(
+                "resource_agent_namespace_anomalies_total",
+                "Anomalies detected per namespace",
+                &["namespace", "deployment"]
+            )
+            .expect("Failed to register namespace_anomalies"),
+
+            label_limiter: Mutex::new(LabelLimiter::default()),
+
+            last_collection_timestamp: register_int_gauge!(
+                "resource_agent_last_collection_timestamp_seconds",
+                "Unix time of the last completed metrics collection"
+            )
+            .expect("Failed to register last_collection_timestamp"),
+
+            sync_circuit_state: register_int_gauge!(
+                "resource_agent_sync_circuit_state",
+                "Sync circuit breaker state (0 = closed, 1 = half-open, 2 = open)"
+            )
+            .expect("Failed to register sync_circuit_state"),
+
+            config_generation: register_int_gauge!(
+                "resource_agent_config_generation",
+                "Number of times the agent configuration has been loaded"
+            )
+            .expect("Failed to register config_generation"),
+
+            degradation_level: register_int_gauge!(
+                "resource_agent_degradation_level",
+                "Self resource budget degradation level (0 = normal, 1 = reduced frequency, 2 = prediction paused, 3 = minimal collection)"
+            )
+            .expect("Failed to register degradation_level"),
+
+            stale_profiles: register_int_gauge!(
+                "resource_agent_stale_profiles",
+                "Containers whose latest profile is older than the staleness age"
+            )
+            .expect("Failed to register stale_profiles"),
+
+            model_version: RwLock::new(None),
         }
     }
 }
@@ -130,10 +323,15 @@ impl AgentMetrics {
     }
 
     /// Record a collection latency observation
+    ///
+    /// Also marks a collection as completed now.
     pub fn observe_collection_latency(&self, duration_secs: f64) {
         self.inner()
             .collection_latency_seconds
             .observe(duration_secs);
+        self.inner()
+            .last_collection_timestamp
+            .set(chrono::Utc::now().timestamp());
     }
 
     /// Record a prediction latency observation
@@ -158,6 +356,7 @@ impl AgentMetrics {
             .model_version_info
             .with_label_values(&[version, quantization])
             .set(1.0);
+        *self.inner().model_version.write().unwrap() = Some(version.to_string());
     }
 
     /// Update containers monitored count
@@ -165,6 +364,26 @@ impl AgentMetrics {
         self.inner().containers_monitored.set(count);
     }
 
+    /// Update the sync circuit breaker state gauge
+    pub fn set_sync_circuit_state(&self, state: CircuitState) {
+        self.inner().sync_circuit_state.set(state.gauge_value());
+    }
+
+    /// Update the applied configuration generation
+    pub fn set_config_generation(&self, generation: u64) {
+        self.inner().config_generation.set(generation as i64);
+    }
+
+    /// Update the self resource budget degradation level
+    pub fn set_degradation_level(&self, level: DegradationLevel) {
+        self.inner().degradation_level.set(level.gauge_value());
+    }
+
+    /// Update the number of stale profiles
+    pub fn set_stale_profiles(&self, count: i64) {
+        self.inner().stale_profiles.set(count);
+    }
+
     /// Increment predictions generated counter
     pub fn inc_predictions_generated(&self) {
         self.inner().predictions_generated.inc();
@@ -184,6 +403,155 @@ impl AgentMetrics {
     pub fn inc_prediction_errors(&self) {
         self.inner().prediction_errors.inc();
     }
+
+    /// Record a batch pushed to the recommendation API
+    pub fn observe_batch_sent(&self, bytes: usize, duration_secs: f64) {
+        let inner = self.inner();
+        inner.sync_batches_sent.inc();
+        inner.sync_bytes_sent.inc_by(bytes as u64);
+        inner.sync_batch_size_bytes.observe(bytes as f64);
+        inner.sync_batch_latency_seconds.observe(duration_secs);
+    }
+
+    /// Increment batches that failed to send
+    pub fn inc_sync_failures(&self) {
+        self.inner().sync_failures.inc();
+    }
+
+    /// Increment failed connections to the recommendation API
+    pub fn inc_sync_reconnect_attempts(&self) {
+        self.inner().sync_reconnect_attempts.inc();
+    }
+
+    /// Add items of `category` dropped because the streaming queue was full
+    pub fn add_sync_dropped(&self, category: &str, count: u64) {
+        self.inner()
+            .sync_dropped
+            .with_label_values(&[category])
+            .inc_by(count);
+    }
+
+    /// Add samples evicted from a full buffer
+    pub fn add_buffer_dropped(&self, count: u64) {
+        self.inner().buffer_dropped.inc_by(count);
+    }
+
+    /// Count a model update attempt with its result
+    pub fn inc_model_updates(&self, result: &str) {
+        self.inner()
+            .model_updates
+            .with_label_values(&[result])
+            .inc();
+    }
+
+    /// Add containers the registry reconciler added or removed
+    pub fn add_reconcile_drift(&self, action: &str, count: u64) {
+        self.inner()
+            .reconcile_drift
+            .with_label_values(&[action])
+            .inc_by(count);
+    }
+
+    /// Enable per-namespace metrics and set their cardinality ceiling
+    pub fn configure_labels(&self, config: MetricLabelConfig) {
+        *self.inner().label_limiter.lock().unwrap() = LabelLimiter::new(config);
+    }
+
+    /// Label values for a workload, None when per-namespace metrics are off
+    fn workload_labels(&self, namespace: &str, deployment: Option<&str>) -> Option<[String; 2]> {
+        let (namespace, deployment) = self
+            .inner()
+            .label_limiter
+            .lock()
+            .unwrap()
+            .labels(namespace, deployment)?;
+        Some([namespace, deployment])
+    }
+
+    /// Replace the per-namespace container counts
+    ///
+    /// Takes every monitored container's (namespace, deployment); workloads
+    /// aggregated past the label ceiling are summed into one series.
+    pub fn set_containers_by_namespace<'a>(
+        &self,
+        containers: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+    ) {
+        let inner = self.inner();
+        if !inner.label_limiter.lock().unwrap().enabled() {
+            return;
+        }
+
+        let mut counts: HashMap<[String; 2], i64> = HashMap::new();
+        for (namespace, deployment) in containers {
+            if let Some(labels) = self.workload_labels(namespace, deployment) {
+                *counts.entry(labels).or_default() += 1;
+            }
+        }
+
+        inner.namespace_containers_monitored.reset();
+        for (labels, count) in counts {
+            let labels = [labels[0].as_str(), labels[1].as_str()];
+            inner
+                .namespace_containers_monitored
+                .with_label_values(&labels)
+                .set(count);
+        }
+    }
+
+    /// Count a prediction, also per namespace when enabled
+    pub fn inc_predictions_for(&self, namespace: &str, deployment: Option<&str>) {
+        self.inc_predictions_generated();
+        if let Some([namespace, deployment]) = self.workload_labels(namespace, deployment) {
+            self.inner()
+                .namespace_predictions
+                .with_label_values(&[&namespace, &deployment])
+                .inc();
+        }
+    }
+
+    /// Count an anomaly, also per namespace when enabled
+    pub fn inc_anomalies_for(&self, namespace: &str, deployment: Option<&str>) {
+        self.inc_anomalies_detected();
+        if let Some([namespace, deployment]) = self.workload_labels(namespace, deployment) {
+            self.inner()
+                .namespace_anomalies
+                .with_label_values(&[&namespace, &deployment])
+                .inc();
+        }
+    }
+
+    /// Snapshot of the values reported in agent heartbeats
+    pub fn health_summary(&self) -> AgentHealthSummary {
+        let inner = self.inner();
+        let last_collection = inner.last_collection_timestamp.get();
+
+        AgentHealthSummary {
+            buffer_items: inner.buffer_items.get(),
+            buffer_size_bytes: inner.buffer_size_bytes.get(),
+            containers_monitored: inner.containers_monitored.get(),
+            last_collection_at: (last_collection > 0).then_some(last_collection),
+            model_version: inner.model_version.read().unwrap().clone(),
+            predictions_generated: inner.predictions_generated.get() as i64,
+            anomalies_detected: inner.anomalies_detected.get() as i64,
+            collection_errors: inner.collection_errors.get() as i64,
+            prediction_errors: inner.prediction_errors.get() as i64,
+        }
+    }
+}
+
+/// Point-in-time summary of agent health counters
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AgentHealthSummary {
+    pub buffer_items: i64,
+    pub buffer_size_bytes: i64,
+    pub containers_monitored: i64,
+    /// Unix time of the last completed collection
+    pub last_collection_at: Option<i64>,
+    pub model_version: Option<String>,
+    pub predictions_generated: i64,
+    pub anomalies_detected: i64,
+    pub collection_errors: i64,
+    pub prediction_errors: i64,
 }
 
 /// Structured logger for agent events
@@ -451,6 +819,13 @@ mod tests {
         metrics.set_containers_monitored(5);
         metrics.inc_predictions_generated();
         metrics.inc_anomalies_detected();
+        metrics.observe_batch_sent(2048, 0.05);
+        metrics.inc_model_updates("applied");
+
+        let summary = metrics.health_summary();
+        assert!(summary.last_collection_at.is_some());
+        assert!(summary.model_version.is_some());
+        assert!(summary.predictions_generated >= 1);
     }
 
     #[test]
@@ -0,0 +1,85 @@
+//! In-process stand-ins for the Prometheus metric types
+//!
+//! Without the `prometheus` feature the agent metrics aren't exposed, but
+//! the counters and gauges read back for health summaries still count.
+//! Histograms and labelled metrics only exist for Prometheus and do nothing.
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+/// Stand-in for the `register_*!` macros, which can't fail here
+macro_rules! registered {
+    ($($arg:tt)*) => {
+        Ok::<_, std::convert::Infallible>(Default::default())
+    };
+}
+
+pub(super) use registered as register_gauge_vec;
+pub(super) use registered as register_histogram;
+pub(super) use registered as register_int_counter;
+pub(super) use registered as register_int_counter_vec;
+pub(super) use registered as register_int_gauge;
+pub(super) use registered as register_int_gauge_vec;
+
+#[derive(Debug, Default)]
+pub(super) struct IntCounter(AtomicU64);
+
+impl IntCounter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct IntGauge(AtomicI64);
+
+impl IntGauge {
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Histogram;
+
+impl Histogram {
+    pub fn observe(&self, _value: f64) {}
+}
+
+/// Labelled metric family; its series aren't kept
+#[derive(Debug, Default)]
+pub(super) struct MetricVec;
+
+impl MetricVec {
+    pub fn with_label_values(&self, _values: &[&str]) -> Series {
+        Series
+    }
+
+    pub fn reset(&self) {}
+}
+
+/// One series of a `MetricVec`
+pub(super) struct Series;
+
+impl Series {
+    pub fn set<T>(&self, _value: T) {}
+
+    pub fn inc(&self) {}
+
+    pub fn inc_by(&self, _value: u64) {}
+}
+
+pub(super) type GaugeVec = MetricVec;
+pub(super) type IntCounterVec = MetricVec;
+pub(super) type IntGaugeVec = MetricVec;
@@ -0,0 +1,140 @@
+//! Cardinality control for per-namespace metric labels
+//!
+//! Namespace and deployment labels show which workloads drive agent load,
+//! but every label combination is a new time series. Once the configured
+//! number of combinations is reached, further ones are reported under a
+//! shared overflow label instead.
+
+use std::collections::HashSet;
+
+/// Default ceiling on distinct label combinations per metric family
+pub const DEFAULT_MAX_LABEL_SETS: usize = 100;
+
+/// Label value that combinations beyond the ceiling are aggregated under
+pub const OVERFLOW_LABEL: &str = "_other";
+
+/// Which workload labels are attached to per-namespace metrics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricLabelConfig {
+    /// Export per-namespace metrics
+    pub namespace: bool,
+    /// Also label them by deployment (requires `namespace`)
+    pub deployment: bool,
+    /// Distinct label combinations kept before aggregating
+    pub max_label_sets: usize,
+}
+
+impl Default for MetricLabelConfig {
+    fn default() -> Self {
+        Self {
+            namespace: false,
+            deployment: false,
+            max_label_sets: DEFAULT_MAX_LABEL_SETS,
+        }
+    }
+}
+
+/// Maps workloads to label values, aggregating past the ceiling
+#[derive(Debug, Default)]
+pub(crate) struct LabelLimiter {
+    config: MetricLabelConfig,
+    admitted: HashSet<(String, String)>,
+}
+
+impl LabelLimiter {
+    pub(crate) fn new(config: MetricLabelConfig) -> Self {
+        Self {
+            config,
+            admitted: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.config.namespace
+    }
+
+    /// Label values (namespace, deployment) for a workload, None if disabled
+    ///
+    /// Combinations seen before keep their labels; new ones are admitted
+    /// until the ceiling and aggregated under `OVERFLOW_LABEL` after it.
+    pub(crate) fn labels(
+        &mut self,
+        namespace: &str,
+        deployment: Option<&str>,
+    ) -> Option<(String, String)> {
+        if !self.config.namespace {
+            return None;
+        }
+
+        let deployment = if self.config.deployment {
+            deployment.unwrap_or_default()
+        } else {
+            ""
+        };
+        let key = (namespace.to_string(), deployment.to_string());
+        if self.admitted.contains(&key) {
+            return Some(key);
+        }
+        if self.admitted.len() < self.config.max_label_sets {
+            self.admitted.insert(key.clone());
+            return Some(key);
+        }
+
+        let overflow_deployment = if self.config.deployment {
+            OVERFLOW_LABEL
+        } else {
+            ""
+        };
+        Some((OVERFLOW_LABEL.to_string(), overflow_deployment.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_by_default() {
+        let mut limiter = LabelLimiter::new(MetricLabelConfig::default());
+        assert!(!limiter.enabled());
+        assert_eq!(limiter.labels("default", Some("web")), None);
+    }
+
+    #[test]
+    fn test_aggregates_beyond_ceiling() {
+        let mut limiter = LabelLimiter::new(MetricLabelConfig {
+            namespace: true,
+            deployment: true,
+            max_label_sets: 2,
+        });
+
+        let labels = |ns: &str, deploy: &str| Some((ns.to_string(), deploy.to_string()));
+        assert_eq!(limiter.labels("a", Some("web")), labels("a", "web"));
+        assert_eq!(limiter.labels("b", None), labels("b", ""));
+        assert_eq!(limiter.labels("c", Some("api")), labels("_other", "_other"));
+        // Admitted combinations keep their labels
+        assert_eq!(limiter.labels("a", Some("web")), labels("a", "web"));
+    }
+
+    #[test]
+    fn test_namespace_only() {
+        let mut limiter = LabelLimiter::new(MetricLabelConfig {
+            namespace: true,
+            deployment: false,
+            max_label_sets: 1,
+        });
+        assert_eq!(
+            limiter.labels("a", Some("web")),
+            Some(("a".to_string(), String::new()))
+        );
+        // Deployments collapse into the namespace's single series
+        assert_eq!(
+            limiter.labels("a", Some("api")),
+            Some(("a".to_string(), String::new()))
+        );
+        assert_eq!(
+            limiter.labels("b", None),
+            Some(("_other".to_string(), String::new()))
+        );
+    }
+}
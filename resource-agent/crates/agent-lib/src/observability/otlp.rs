@@ -0,0 +1,353 @@
+//! Push export of agent metrics over OTLP/HTTP
+//!
+//! For fleets without Prometheus scraping. The metrics registered in the
+//! Prometheus registry are converted to OTLP and posted as JSON to the
+//! collector's `/v1/metrics` endpoint at a fixed interval.
+
+use anyhow::{Context, Result};
+use prometheus::proto::{LabelPair, Metric, MetricFamily, MetricType};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+
+/// Default OTLP/HTTP collector endpoint
+pub const DEFAULT_OTLP_METRICS_ENDPOINT: &str = "http://localhost:4318";
+
+/// Default time between pushes
+pub const DEFAULT_OTLP_METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default timeout for a single push
+const DEFAULT_OTLP_METRICS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// OTLP aggregation temporality: values accumulate since process start
+const AGGREGATION_TEMPORALITY_CUMULATIVE: u32 = 2;
+
+/// OTLP metrics export settings
+#[derive(Debug, Clone)]
+pub struct OtlpMetricsConfig {
+    /// Collector base URL, or its full `/v1/metrics` URL
+    pub endpoint: String,
+    /// Time between pushes
+    pub interval: Duration,
+    pub timeout: Duration,
+    /// Reported as the `service.name` resource attribute
+    pub service_name: String,
+    /// Reported as the `host.name` resource attribute
+    pub node_name: Option<String>,
+}
+
+impl Default for OtlpMetricsConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_OTLP_METRICS_ENDPOINT.to_string(),
+            interval: DEFAULT_OTLP_METRICS_INTERVAL,
+            timeout: DEFAULT_OTLP_METRICS_TIMEOUT,
+            service_name: "resource-agent".to_string(),
+            node_name: None,
+        }
+    }
+}
+
+/// Periodically pushes the Prometheus registry to an OTLP collector
+pub struct OtlpMetricsExporter {
+    config: OtlpMetricsConfig,
+    url: String,
+    http: reqwest::Client,
+    /// Start of the cumulative series, in nanoseconds since the epoch
+    start_time_nanos: u64,
+}
+
+impl OtlpMetricsExporter {
+    pub fn new(config: OtlpMetricsConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .context("Failed to build OTLP HTTP client")?;
+        let endpoint = config.endpoint.trim_end_matches('/');
+        let url = if endpoint.ends_with("/v1/metrics") {
+            endpoint.to_string()
+        } else {
+            format!("{}/v1/metrics", endpoint)
+        };
+        Ok(Self {
+            config,
+            url,
+            http,
+            start_time_nanos: unix_nanos(),
+        })
+    }
+
+    /// Push metrics every interval until shutdown, then once more
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        info!(
+            url = %self.url,
+            interval_secs = self.config.interval.as_secs(),
+            "Starting OTLP metrics export"
+        );
+
+        let mut ticker = interval(self.config.interval);
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(e) = self.export().await {
+                        warn!(error = %format!("{:#}", e), "OTLP metrics export failed");
+                    }
+                }
+                _ = shutdown.recv() => {
+                    if let Err(e) = self.export().await {
+                        warn!(error = %format!("{:#}", e), "Final OTLP metrics export failed");
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Push the current value of every registered metric
+    pub async fn export(&self) -> Result<()> {
+        let families = prometheus::gather();
+        let request = encode_metrics(
+            &families,
+            &self.resource_attributes(),
+            self.start_time_nanos,
+            unix_nanos(),
+        );
+
+        self.http
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to push metrics to {}", self.url))?;
+        debug!(families = families.len(), "Pushed metrics over OTLP");
+        Ok(())
+    }
+
+    fn resource_attributes(&self) -> Vec<Value> {
+        let mut attributes = vec![string_attribute("service.name", &self.config.service_name)];
+        if let Some(node_name) = &self.config.node_name {
+            attributes.push(string_attribute("host.name", node_name));
+        }
+        attributes
+    }
+}
+
+/// Convert Prometheus metric families to an OTLP `ExportMetricsServiceRequest`
+///
+/// Counters become monotonic cumulative sums, gauges and untyped metrics
+/// gauges, and histograms and summaries keep their own OTLP types. 64-bit
+/// integers are encoded as strings, as the OTLP JSON mapping requires.
+fn encode_metrics(
+    families: &[MetricFamily],
+    resource: &[Value],
+    start_time_nanos: u64,
+    time_nanos: u64,
+) -> Value {
+    let times = |metric: &Metric| {
+        json!({
+            "attributes": metric.get_label().iter().map(label_attribute).collect::<Vec<_>>(),
+            "startTimeUnixNano": start_time_nanos.to_string(),
+            "timeUnixNano": time_nanos.to_string(),
+        })
+    };
+    let point = |metric: &Metric, fields: Value| {
+        let mut point = times(metric);
+        if let (Some(point), Value::Object(fields)) = (point.as_object_mut(), fields) {
+            point.extend(fields);
+        }
+        point
+    };
+
+    let metrics: Vec<Value> = families
+        .iter()
+        .map(|family| {
+            let samples = family.get_metric();
+            let data = match family.get_field_type() {
+                MetricType::COUNTER => json!({
+                    "sum": {
+                        "dataPoints": samples.iter().map(|m| {
+                            point(m, json!({ "asDouble": m.get_counter().get_value() }))
+                        }).collect::<Vec<_>>(),
+                        "aggregationTemporality": AGGREGATION_TEMPORALITY_CUMULATIVE,
+                        "isMonotonic": true,
+                    }
+                }),
+                MetricType::GAUGE | MetricType::UNTYPED => json!({
+                    "gauge": {
+                        "dataPoints": samples.iter().map(|m| {
+                            let value = if family.get_field_type() == MetricType::GAUGE {
+                                m.get_gauge().get_value()
+                            } else {
+                                m.get_untyped().get_value()
+                            };
+                            point(m, json!({ "asDouble": value }))
+                        }).collect::<Vec<_>>(),
+                    }
+                }),
+                MetricType::HISTOGRAM => json!({
+                    "histogram": {
+                        "dataPoints": samples.iter().map(|m| {
+                            let histogram = m.get_histogram();
+                            let (bounds, counts) = bucket_counts(
+                                histogram.get_bucket().iter().map(|b| {
+                                    (b.get_upper_bound(), b.get_cumulative_count())
+                                }),
+                                histogram.get_sample_count(),
+                            );
+                            point(m, json!({
+                                "count": histogram.get_sample_count().to_string(),
+                                "sum": histogram.get_sample_sum(),
+                                "explicitBounds": bounds,
+                                "bucketCounts": counts
+                                    .iter()
+                                    .map(u64::to_string)
+                                    .collect::<Vec<_>>(),
+                            }))
+                        }).collect::<Vec<_>>(),
+                        "aggregationTemporality": AGGREGATION_TEMPORALITY_CUMULATIVE,
+                    }
+                }),
+                MetricType::SUMMARY => json!({
+                    "summary": {
+                        "dataPoints": samples.iter().map(|m| {
+                            let summary = m.get_summary();
+                            point(m, json!({
+                                "count": summary.get_sample_count().to_string(),
+                                "sum": summary.get_sample_sum(),
+                                "quantileValues": summary.get_quantile().iter().map(|q| {
+                                    json!({ "quantile": q.get_quantile(), "value": q.get_value() })
+                                }).collect::<Vec<_>>(),
+                            }))
+                        }).collect::<Vec<_>>(),
+                    }
+                }),
+            };
+
+            let mut metric = json!({
+                "name": family.get_name(),
+                "description": family.get_help(),
+            });
+            if let (Some(metric), Value::Object(data)) = (metric.as_object_mut(), data) {
+                metric.extend(data);
+            }
+            metric
+        })
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": resource },
+            "scopeMetrics": [{
+                "scope": { "name": "resource-agent" },
+                "metrics": metrics,
+            }],
+        }]
+    })
+}
+
+/// Turn cumulative Prometheus buckets into OTLP bounds and per-bucket counts
+///
+/// OTLP has one more bucket than bounds, counting everything above the
+/// last bound; Prometheus leaves that `+Inf` bucket implicit.
+fn bucket_counts(buckets: impl Iterator<Item = (f64, u64)>, total: u64) -> (Vec<f64>, Vec<u64>) {
+    let mut bounds = Vec::new();
+    let mut counts = Vec::new();
+    let mut previous = 0;
+    for (bound, cumulative) in buckets.filter(|(bound, _)| bound.is_finite()) {
+        bounds.push(bound);
+        counts.push(cumulative.saturating_sub(previous));
+        previous = cumulative;
+    }
+    counts.push(total.saturating_sub(previous));
+    (bounds, counts)
+}
+
+fn label_attribute(label: &LabelPair) -> Value {
+    string_attribute(label.get_name(), label.get_value())
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
+
+    #[test]
+    fn test_encode_metrics() {
+        let registry = Registry::new();
+        let counter =
+            IntCounterVec::new(Opts::new("requests_total", "Requests"), &["code"]).unwrap();
+        let histogram =
+            Histogram::with_opts(HistogramOpts::new("latency", "Latency").buckets(vec![0.1, 1.0]))
+                .unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+
+        counter.with_label_values(&["200"]).inc_by(3);
+        for value in [0.05, 0.5, 0.7, 5.0] {
+            histogram.observe(value);
+        }
+
+        let request = encode_metrics(
+            &registry.gather(),
+            &[string_attribute("service.name", "test")],
+            1,
+            2,
+        );
+        let metrics = &request["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let by_name = |name: &str| {
+            metrics
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|m| m["name"] == name)
+                .unwrap()
+                .clone()
+        };
+
+        let requests = by_name("requests_total");
+        let point = &requests["sum"]["dataPoints"][0];
+        assert_eq!(requests["sum"]["isMonotonic"], true);
+        assert_eq!(point["asDouble"], 3.0);
+        assert_eq!(point["attributes"][0]["key"], "code");
+        assert_eq!(point["timeUnixNano"], "2");
+
+        let latency = by_name("latency");
+        let point = &latency["histogram"]["dataPoints"][0];
+        assert_eq!(point["count"], "4");
+        assert_eq!(point["explicitBounds"], json!([0.1, 1.0]));
+        assert_eq!(point["bucketCounts"], json!(["1", "2", "1"]));
+    }
+
+    #[test]
+    fn test_metrics_url() {
+        let url = |endpoint: &str| {
+            OtlpMetricsExporter::new(OtlpMetricsConfig {
+                endpoint: endpoint.to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+            .url
+        };
+        assert_eq!(url("http://otel:4318"), "http://otel:4318/v1/metrics");
+        assert_eq!(url("http://otel:4318/"), "http://otel:4318/v1/metrics");
+        assert_eq!(
+            url("http://otel:4318/v1/metrics"),
+            "http://otel:4318/v1/metrics"
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Push export of collected container metrics over Prometheus remote-write
+//!
+//! Collected samples are copied to a bounded queue alongside the gRPC sync
+//! and written to a remote-write endpoint as snappy-compressed protobuf in
+//! batches of their own. The exporter runs in its own task: a slow or failing
+//! endpoint only drops remote-write samples and never delays the sync.
+
+use crate::models::ContainerMetrics;
+use anyhow::{Context, Result};
+use prost::Message;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+
+/// Default number of container samples per write request
+pub const DEFAULT_REMOTE_WRITE_BATCH_SIZE: usize = 500;
+
+/// Default time a partial batch waits before it is written
+pub const DEFAULT_REMOTE_WRITE_FLUSH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Default number of samples queued before new ones are dropped
+const DEFAULT_REMOTE_WRITE_QUEUE_CAPACITY: usize = 10_000;
+
+/// Default timeout for a single write request
+const DEFAULT_REMOTE_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default attempts per batch before it is dropped
+const DEFAULT_REMOTE_WRITE_MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled on each further one
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Remote-write export settings
+#[derive(Debug, Clone)]
+pub struct RemoteWriteConfig {
+    /// Remote-write URL, e.g. `http://prometheus:9090/api/v1/write`
+    pub endpoint: String,
+    /// Container samples per write request
+    pub batch_size: usize,
+    /// Longest time a partial batch waits before it is written
+    pub flush_interval: Duration,
+    /// Samples queued before new ones are dropped
+    pub queue_capacity: usize,
+    pub timeout: Duration,
+    /// Attempts per batch on 5xx, 429 and connection errors
+    pub max_attempts: u32,
+    /// Labels added to every series, e.g. `node`
+    pub external_labels: Vec<(String, String)>,
+    /// Sent as `Authorization: Bearer <token>`
+    pub bearer_token: Option<String>,
+}
+
+impl Default for RemoteWriteConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            batch_size: DEFAULT_REMOTE_WRITE_BATCH_SIZE,
+            flush_interval: DEFAULT_REMOTE_WRITE_FLUSH_INTERVAL,
+            queue_capacity: DEFAULT_REMOTE_WRITE_QUEUE_CAPACITY,
+            timeout: DEFAULT_REMOTE_WRITE_TIMEOUT,
+            max_attempts: DEFAULT_REMOTE_WRITE_MAX_ATTEMPTS,
+            external_labels: Vec::new(),
+            bearer_token: None,
+        }
+    }
+}
+
+/// Counters of the remote-write exporter
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    failed_batches: AtomicU64,
+}
+
+/// Snapshot of the exporter's counters, in container samples
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteWriteStats {
+    pub sent: u64,
+    /// Dropped because the queue was full or the batch failed
+    pub dropped: u64,
+    pub failed_batches: u64,
+}
+
+/// Queues collected metrics for the remote-write exporter
+#[derive(Clone)]
+pub struct RemoteWriteHandle {
+    tx: mpsc::Sender<ContainerMetrics>,
+    counters: Arc<Counters>,
+}
+
+impl RemoteWriteHandle {
+    /// Queue samples without waiting, dropping them when the queue is full
+    pub fn submit(&self, metrics: &[ContainerMetrics]) {
+        for m in metrics {
+            if self.tx.try_send(m.clone()).is_err() {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> RemoteWriteStats {
+        RemoteWriteStats {
+            sent: self.counters.sent.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            failed_batches: self.counters.failed_batches.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Batches queued metrics and writes them to a remote-write endpoint
+pub struct RemoteWriteExporter {
+    config: RemoteWriteConfig,
+    http: reqwest::Client,
+    rx: mpsc::Receiver<ContainerMetrics>,
+    counters: Arc<Counters>,
+}
+
+impl RemoteWriteExporter {
+    pub fn new(config: RemoteWriteConfig) -> Result<(Self, RemoteWriteHandle)> {
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .context("Failed to build remote-write HTTP client")?;
+        let (tx, rx) = mpsc::channel(config.queue_capacity.max(1));
+        let counters = Arc::new(Counters::default());
+        let handle = RemoteWriteHandle {
+            tx,
+            counters: counters.clone(),
+        };
+        Ok((
+            Self {
+                config,
+                http,
+                rx,
+                counters,
+            },
+            handle,
+        ))
+    }
+
+    /// Write batches until shutdown, then write what is still queued
+    pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
+        info!(
+            endpoint = %self.config.endpoint,
+            batch_size = self.config.batch_size,
+            "Starting Prometheus remote-write export"
+        );
+
+        let batch_size = self.config.batch_size.max(1);
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut ticker = interval(self.config.flush_interval);
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                received = self.rx.recv() => {
+                    let Some(metrics) = received else {
+                        break;
+                    };
+                    batch.push(metrics);
+                    if batch.len() >= batch_size {
+                        self.flush(&mut batch).await;
+                    }
+                }
+                _ = ticker.tick() => {
+                    self.flush(&mut batch).await;
+                }
+                _ = shutdown.recv() => {
+                    while let Ok(metrics) = self.rx.try_recv() {
+                        batch.push(metrics);
+                    }
+                    break;
+                }
+            }
+        }
+
+        for chunk in batch.chunks(batch_size) {
+            let mut chunk = chunk.to_vec();
+            self.flush(&mut chunk).await;
+        }
+    }
+
+    /// Write and clear the batch, counting its samples as sent or dropped
+    async fn flush(&self, batch: &mut Vec<ContainerMetrics>) {
+        if batch.is_empty() {
+            return;
+        }
+        let count = batch.len() as u64;
+        match self.write(batch).await {
+            Ok(()) => {
+                self.counters.sent.fetch_add(count, Ordering::Relaxed);
+                debug!(samples = count, "Wrote metrics over remote-write");
+            }
+            Err(e) => {
+                self.counters.dropped.fetch_add(count, Ordering::Relaxed);
+                self.counters.failed_batches.fetch_add(1, Ordering::Relaxed);
+                warn!(error = %format!("{:#}", e), samples = count, "Remote-write failed, dropping batch");
+            }
+        }
+        batch.clear();
+    }
+
+    /// Send one write request, retrying recoverable failures
+    async fn write(&self, batch: &[ContainerMetrics]) -> Result<()> {
+        let request = encode_write_request(batch, &self.config.external_labels);
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&request.encode_to_vec())
+            .context("Failed to compress write request")?;
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut request = self
+                .http
+                .post(&self.config.endpoint)
+                .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+                .header(reqwest::header::CONTENT_ENCODING, "snappy")
+                .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+                .body(body.clone());
+            if let Some(token) = &self.config.bearer_token {
+                request = request.bearer_auth(token);
+            }
+
+            let (retryable, error) = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    (
+                        status.is_server_error()
+                            || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+                        anyhow::anyhow!(
+                            "{} returned {}: {}",
+                            self.config.endpoint,
+                            status,
+                            body.trim()
+                        ),
+                    )
+                }
+                Err(e) => (
+                    true,
+                    anyhow::Error::new(e)
+                        .context(format!("Failed to write to {}", self.config.endpoint)),
+                ),
+            };
+            if !retryable || attempt >= self.config.max_attempts {
+                return Err(error);
+            }
+            tokio::time::sleep(RETRY_BACKOFF * 2u32.saturating_pow(attempt - 1)).await;
+        }
+    }
+}
+
+/// `prometheus.WriteRequest`
+#[derive(Clone, PartialEq, Message)]
+pub struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub timeseries: Vec<TimeSeries>,
+}
+
+/// `prometheus.TimeSeries`
+#[derive(Clone, PartialEq, Message)]
+pub struct TimeSeries {
+    /// Sorted by name
+    #[prost(message, repeated, tag = "1")]
+    pub labels: Vec<Label>,
+    /// Sorted by timestamp
+    #[prost(message, repeated, tag = "2")]
+    pub samples: Vec<Sample>,
+}
+
+/// `prometheus.Label`
+#[derive(Clone, PartialEq, Message)]
+pub struct Label {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+/// `prometheus.Sample`
+#[derive(Clone, PartialEq, Message)]
+pub struct Sample {
+    #[prost(double, tag = "1")]
+    pub value: f64,
+    /// Milliseconds since the epoch
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+}
+
+/// Number of series written for each container sample
+const SERIES_PER_SAMPLE: usize = 7;
+
+/// Metric names and values written for a container sample
+fn sample_values(m: &ContainerMetrics) -> [(&'static str, f64); SERIES_PER_SAMPLE] {
+    [
+        ("crp_container_cpu_usage_cores", m.cpu_usage_cores as f64),
+        (
+            "crp_container_cpu_throttled_periods_total",
+            m.cpu_throttled_periods as f64,
+        ),
+        (
+            "crp_container_memory_usage_bytes",
+            m.memory_usage_bytes as f64,
+        ),
+        (
+            "crp_container_memory_working_set_bytes",
+            m.memory_working_set_bytes as f64,
+        ),
+        (
+            "crp_container_memory_cache_bytes",
+            m.memory_cache_bytes as f64,
+        ),
+        (
+            "crp_container_network_receive_bytes_total",
+            m.network_rx_bytes as f64,
+        ),
+        (
+            "crp_container_network_transmit_bytes_total",
+            m.network_tx_bytes as f64,
+        ),
+    ]
+}
+
+/// Convert container samples to a write request, one series per metric
+/// and container
+fn encode_write_request(
+    batch: &[ContainerMetrics],
+    external_labels: &[(String, String)],
+) -> WriteRequest {
+    let mut series: BTreeMap<Vec<(String, String)>, Vec<Sample>> = BTreeMap::new();
+    for m in batch {
+        for (name, value) in sample_values(m) {
+            let mut labels: Vec<(String, String)> = external_labels.to_vec();
+            labels.push(("__name__".to_string(), name.to_string()));
+            labels.push(("container_id".to_string(), m.container_id.clone()));
+            labels.push(("namespace".to_string(), m.namespace.clone()));
+            labels.push(("pod".to_string(), m.pod_name.clone()));
+            if let Some(owner) = &m.owner {
+                labels.push(("owner_kind".to_string(), owner.kind.to_string()));
+                labels.push(("owner_name".to_string(), owner.name.clone()));
+                if let Some(deployment) = owner.deployment_name() {
+                    labels.push(("deployment".to_string(), deployment.to_string()));
+                }
+            }
+            labels.sort();
+            labels.dedup_by(|a, b| a.0 == b.0);
+
+            series.entry(labels).or_default().push(Sample {
+                value,
+                timestamp: m.timestamp * 1000,
+            });
+        }
+    }
+
+    WriteRequest {
+        timeseries: series
+            .into_iter()
+            .map(|(labels, mut samples)| {
+                samples.sort_by_key(|s| s.timestamp);
+                TimeSeries {
+                    labels: labels
+                        .into_iter()
+                        .map(|(name, value)| Label { name, value })
+                        .collect(),
+                    samples,
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OwnerRef;
+
+    fn metrics(container_id: &str, timestamp: i64) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: container_id.to_string(),
+            pod_name: "api-1".to_string(),
+            namespace: "payments".to_string(),
+            owner: Some(OwnerRef::deployment("api")),
+            timestamp,
+            cpu_usage_cores: 0.5,
+            cpu_throttled_periods: 3,
+            memory_usage_bytes: 200,
+            memory_working_set_bytes: 150,
+            memory_cache_bytes: 50,
+            network_rx_bytes: 10,
+            network_tx_bytes: 20,
+        }
+    }
+
+    #[test]
+    fn test_encode_write_request() {
+        let batch = vec![metrics("c1", 20), metrics("c1", 10), metrics("c2", 10)];
+        let external = vec![("node".to_string(), "node-1".to_string())];
+        let request = encode_write_request(&batch, &external);
+
+        assert_eq!(request.timeseries.len(), 2 * SERIES_PER_SAMPLE);
+        let cpu = request
+            .timeseries
+            .iter()
+            .find(|ts| {
+                ts.labels
+                    .iter()
+                    .any(|l| l.name == "__name__" && l.value == "crp_container_cpu_usage_cores")
+                    && ts.labels.iter().any(|l| l.value == "c1")
+            })
+            .unwrap();
+
+        let names: Vec<&str> = cpu.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "__name__",
+                "container_id",
+                "deployment",
+                "namespace",
+                "node",
+                "owner_kind",
+                "owner_name",
+                "pod"
+            ]
+        );
+        let timestamps: Vec<i64> = cpu.samples.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, [10_000, 20_000]);
+        assert_eq!(cpu.samples[0].value, 0.5);
+    }
+
+    #[test]
+    fn test_write_request_roundtrip() {
+        let request = encode_write_request(&[metrics("c1", 10)], &[]);
+        let compressed = snap::raw::Encoder::new()
+            .compress_vec(&request.encode_to_vec())
+            .unwrap();
+        let decompressed = snap::raw::Decoder::new()
+            .decompress_vec(&compressed)
+            .unwrap();
+        assert_eq!(
+            WriteRequest::decode(decompressed.as_slice()).unwrap(),
+            request
+        );
+    }
+
+    #[tokio::test]
+    async fn test_submit_drops_when_queue_full() {
+        let (_exporter, handle) = RemoteWriteExporter::new(RemoteWriteConfig {
+            endpoint: "http://localhost:9/api/v1/write".to_string(),
+            queue_capacity: 2,
+            ..Default::default()
+        })
+        .unwrap();
+
+        handle.submit(&[metrics("c1", 10), metrics("c1", 20), metrics("c1", 30)]);
+        assert_eq!(handle.stats().dropped, 1);
+        assert_eq!(handle.stats().sent, 0);
+    }
+}
@@ -0,0 +1,127 @@
+//! OpenTelemetry trace export
+//!
+//! Exports the agent's tracing spans (collection cycles, feature extraction,
+//! inference and sync batches) over OTLP/gRPC. Configured through the
+//! standard OpenTelemetry environment variables.
+
+use anyhow::{Context, Result};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{self, Sampler, Tracer};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Default service name reported with every span
+pub const DEFAULT_SERVICE_NAME: &str = "resource-agent";
+
+/// Default fraction of traces sampled
+pub const DEFAULT_SAMPLE_RATIO: f64 = 0.1;
+
+/// Trace export settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtelConfig {
+    /// OTLP/gRPC collector endpoint
+    pub endpoint: String,
+    pub service_name: String,
+    /// Fraction of root spans sampled, between 0.0 and 1.0
+    pub sample_ratio: f64,
+    /// Node reported as `host.name`
+    pub node_name: Option<String>,
+}
+
+impl OtelConfig {
+    /// Read settings from the environment, None when no endpoint is set
+    ///
+    /// Uses `OTEL_EXPORTER_OTLP_ENDPOINT`, `OTEL_SERVICE_NAME`,
+    /// `OTEL_TRACES_SAMPLER_ARG` and `NODE_NAME`.
+    pub fn from_env() -> Option<Self> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let endpoint = lookup("OTEL_EXPORTER_OTLP_ENDPOINT").filter(|e| !e.is_empty())?;
+        Some(Self {
+            endpoint,
+            service_name: lookup("OTEL_SERVICE_NAME")
+                .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string()),
+            sample_ratio: lookup("OTEL_TRACES_SAMPLER_ARG")
+                .and_then(|ratio| ratio.parse::<f64>().ok())
+                .map(|ratio| ratio.clamp(0.0, 1.0))
+                .unwrap_or(DEFAULT_SAMPLE_RATIO),
+            node_name: lookup("NODE_NAME"),
+        })
+    }
+}
+
+/// Build a tracing layer that exports spans over OTLP
+///
+/// Must be called from within a Tokio runtime; spans are exported in
+/// batches from a background task.
+pub fn layer<S>(config: &OtelConfig) -> Result<OpenTelemetryLayer<S, Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let mut attributes = vec![KeyValue::new("service.name", config.service_name.clone())];
+    if let Some(node_name) = &config.node_name {
+        attributes.push(KeyValue::new("host.name", node_name.clone()));
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.endpoint),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    config.sample_ratio,
+                ))))
+                .with_resource(Resource::new(attributes)),
+        )
+        .install_batch(runtime::Tokio)
+        .context("Failed to install OTLP trace exporter")?;
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Flush buffered spans and stop the exporter
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_config_from_env() {
+        let env = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            OtelConfig::from_lookup(move |key| vars.get(key).cloned())
+        };
+
+        assert_eq!(env(&[]), None);
+        assert_eq!(env(&[("OTEL_EXPORTER_OTLP_ENDPOINT", "")]), None);
+
+        let config = env(&[("OTEL_EXPORTER_OTLP_ENDPOINT", "http://otel:4317")]).unwrap();
+        assert_eq!(config.service_name, DEFAULT_SERVICE_NAME);
+        assert_eq!(config.sample_ratio, DEFAULT_SAMPLE_RATIO);
+
+        let config = env(&[
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://otel:4317"),
+            ("OTEL_TRACES_SAMPLER_ARG", "5"),
+            ("NODE_NAME", "node-1"),
+        ])
+        .unwrap();
+        assert_eq!(config.sample_ratio, 1.0);
+        assert_eq!(config.node_name.as_deref(), Some("node-1"));
+    }
+}
@@ -0,0 +1,349 @@
+//! Backfill of container history from Prometheus
+//!
+//! A freshly started agent has no samples and cannot predict for hours.
+//! When a Prometheus-compatible endpoint is configured, the last day of
+//! cAdvisor CPU and working set data is read through the HTTP query API
+//! and used to seed the prediction scheduler's buffers.
+
+use super::PredictionScheduler;
+use crate::collector::CgroupV1Collector;
+use crate::models::ContainerMetrics;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+/// Default history requested from Prometheus
+pub const DEFAULT_BACKFILL_LOOKBACK: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Default resolution, matching the collection interval
+pub const DEFAULT_BACKFILL_STEP: Duration = Duration::from_secs(10);
+
+/// Default timeout for each query
+const DEFAULT_BACKFILL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Window of the `rate()` applied to the CPU counter
+const CPU_RATE_WINDOW: &str = "5m";
+
+/// Prometheus backfill settings
+#[derive(Debug, Clone)]
+pub struct BackfillConfig {
+    /// Prometheus base URL, e.g. `http://prometheus:9090`
+    pub endpoint: String,
+    /// How far back to read
+    pub lookback: Duration,
+    /// Resolution of the returned samples
+    pub step: Duration,
+    pub timeout: Duration,
+    /// Extra label matchers for both queries, e.g. `node="ip-10-0-1-23"`
+    pub selector: Option<String>,
+    /// Sent as `Authorization: Bearer <token>`
+    pub bearer_token: Option<String>,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            lookback: DEFAULT_BACKFILL_LOOKBACK,
+            step: DEFAULT_BACKFILL_STEP,
+            timeout: DEFAULT_BACKFILL_TIMEOUT,
+            selector: None,
+            bearer_token: None,
+        }
+    }
+}
+
+/// Outcome of a backfill
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackfillStats {
+    pub containers: usize,
+    /// Samples added to the scheduler, excluding ones overlapping live data
+    pub samples: usize,
+}
+
+/// Reads container history from Prometheus
+pub struct Backfiller {
+    config: BackfillConfig,
+    url: String,
+    http: reqwest::Client,
+}
+
+impl Backfiller {
+    pub fn new(config: BackfillConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .context("Failed to build Prometheus HTTP client")?;
+        let url = format!(
+            "{}/api/v1/query_range",
+            config.endpoint.trim_end_matches('/')
+        );
+        Ok(Self { config, url, http })
+    }
+
+    /// Fetch history and seed the scheduler's buffers with it
+    pub async fn seed(&self, scheduler: &PredictionScheduler) -> Result<BackfillStats> {
+        let history = self.fetch().await?;
+        let containers = history
+            .iter()
+            .map(|m| m.container_id.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+        let samples = scheduler.seed_metrics(history).await;
+        info!(
+            containers,
+            samples, "Backfilled prediction buffers from Prometheus"
+        );
+        Ok(BackfillStats {
+            containers,
+            samples,
+        })
+    }
+
+    /// Fetch per-container CPU and memory samples over the lookback window
+    pub async fn fetch(&self) -> Result<Vec<ContainerMetrics>> {
+        let end = unix_seconds();
+        let start = end - self.config.lookback.as_secs_f64();
+        let matchers = self.matchers();
+
+        let cpu = self
+            .query_range(
+                &format!(
+                    "rate(container_cpu_usage_seconds_total{{{}}}[{}])",
+                    matchers, CPU_RATE_WINDOW
+                ),
+                start,
+                end,
+            )
+            .await?;
+        let memory = self
+            .query_range(
+                &format!("container_memory_working_set_bytes{{{}}}", matchers),
+                start,
+                end,
+            )
+            .await?;
+
+        let metrics = merge_series(&cpu, &memory);
+        debug!(
+            cpu_series = cpu.len(),
+            memory_series = memory.len(),
+            samples = metrics.len(),
+            "Fetched Prometheus history"
+        );
+        Ok(metrics)
+    }
+
+    /// Label matchers selecting application containers on this node
+    fn matchers(&self) -> String {
+        let mut matchers = r#"container!="",container!="POD""#.to_string();
+        if let Some(selector) = self.config.selector.as_deref().map(str::trim) {
+            if !selector.is_empty() {
+                matchers.push(',');
+                matchers.push_str(selector);
+            }
+        }
+        matchers
+    }
+
+    async fn query_range(&self, query: &str, start: f64, end: f64) -> Result<Vec<Series>> {
+        let mut request = self.http.get(&self.url).query(&[
+            ("query", query.to_string()),
+            ("start", format!("{:.3}", start)),
+            ("end", format!("{:.3}", end)),
+            ("step", format!("{}s", self.config.step.as_secs().max(1))),
+        ]);
+        if let Some(token) = &self.config.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response: QueryResponse = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to query {}", self.url))?
+            .json()
+            .await
+            .context("Failed to parse Prometheus response")?;
+        if response.status != "success" {
+            anyhow::bail!(
+                "Prometheus query failed: {}",
+                response.error.unwrap_or(response.status)
+            );
+        }
+        Ok(response.data.map(|d| d.result).unwrap_or_default())
+    }
+}
+
+/// `/api/v1/query_range` response
+#[derive(Debug, Deserialize)]
+struct QueryResponse {
+    status: String,
+    #[serde(default)]
+    data: Option<QueryData>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryData {
+    #[serde(default)]
+    result: Vec<Series>,
+}
+
+/// One matrix series: labels and `[unix seconds, "value"]` pairs
+#[derive(Debug, Deserialize)]
+struct Series {
+    metric: HashMap<String, String>,
+    #[serde(default)]
+    values: Vec<(f64, String)>,
+}
+
+impl Series {
+    /// Container ID from the cAdvisor `id` cgroup label
+    fn container_id(&self) -> Option<String> {
+        let id = CgroupV1Collector::extract_container_id(self.metric.get("id")?)?;
+        (id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+    }
+
+    fn label(&self, name: &str) -> String {
+        self.metric.get(name).cloned().unwrap_or_default()
+    }
+}
+
+/// Join CPU and memory series into samples, by container and timestamp
+///
+/// Only timestamps present in both series become samples. Series without
+/// a container ID in their `id` label are skipped.
+fn merge_series(cpu: &[Series], memory: &[Series]) -> Vec<ContainerMetrics> {
+    let mut cpu_by_container: HashMap<String, BTreeMap<i64, f32>> = HashMap::new();
+    for series in cpu {
+        let Some(container_id) = series.container_id() else {
+            continue;
+        };
+        cpu_by_container
+            .entry(container_id)
+            .or_default()
+            .extend(series.values.iter().filter_map(|(ts, value)| {
+                let cores = value.parse::<f32>().ok().filter(|v| v.is_finite())?;
+                Some((*ts as i64, cores))
+            }));
+    }
+
+    let mut metrics = Vec::new();
+    for series in memory {
+        let Some(container_id) = series.container_id() else {
+            continue;
+        };
+        let Some(cpu) = cpu_by_container.get(&container_id) else {
+            continue;
+        };
+        let pod_name = series.label("pod");
+        let namespace = series.label("namespace");
+        for (ts, value) in &series.values {
+            let timestamp = *ts as i64;
+            let (Some(&cpu_usage_cores), Some(bytes)) =
+                (cpu.get(&timestamp), value.parse::<f64>().ok())
+            else {
+                continue;
+            };
+            let bytes = bytes.max(0.0) as u64;
+            metrics.push(ContainerMetrics {
+                container_id: container_id.clone(),
+                pod_name: pod_name.clone(),
+                namespace: namespace.clone(),
+                owner: None,
+                timestamp,
+                cpu_usage_cores,
+                cpu_throttled_periods: 0,
+                memory_usage_bytes: bytes,
+                memory_working_set_bytes: bytes,
+                memory_cache_bytes: 0,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            });
+        }
+    }
+    metrics
+}
+
+fn unix_seconds() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER_ID: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    fn series(json: serde_json::Value) -> Vec<Series> {
+        let response: QueryResponse = serde_json::from_value(json).unwrap();
+        response.data.unwrap().result
+    }
+
+    #[test]
+    fn test_merge_series() {
+        let id = format!(
+            "/kubepods.slice/kubepods-pod1.slice/cri-containerd-{}.scope",
+            CONTAINER_ID
+        );
+        let cpu = series(serde_json::json!({
+            "status": "success",
+            "data": {"resultType": "matrix", "result": [
+                {"metric": {"id": id, "namespace": "payments", "pod": "api-1"},
+                 "values": [[1700000000, "0.25"], [1700000010, "0.5"], [1700000020, "NaN"]]},
+                {"metric": {"id": "/kubepods.slice", "namespace": "payments", "pod": "api-1"},
+                 "values": [[1700000000, "9"]]}
+            ]}
+        }));
+        let memory = series(serde_json::json!({
+            "status": "success",
+            "data": {"resultType": "matrix", "result": [
+                {"metric": {"id": id, "namespace": "payments", "pod": "api-1"},
+                 "values": [[1700000000, "104857600"], [1700000010, "209715200"], [1700000020, "1"]]}
+            ]}
+        }));
+
+        let metrics = merge_series(&cpu, &memory);
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].container_id, CONTAINER_ID);
+        assert_eq!(metrics[0].namespace, "payments");
+        assert_eq!(metrics[0].pod_name, "api-1");
+        assert_eq!(metrics[0].timestamp, 1_700_000_000);
+        assert_eq!(metrics[1].cpu_usage_cores, 0.5);
+        assert_eq!(metrics[1].memory_working_set_bytes, 209_715_200);
+    }
+
+    #[test]
+    fn test_query_error_response() {
+        let response: QueryResponse = serde_json::from_value(serde_json::json!({
+            "status": "error",
+            "errorType": "bad_data",
+            "error": "invalid parameter \"query\""
+        }))
+        .unwrap();
+        assert_eq!(response.status, "error");
+        assert!(response.data.is_none());
+    }
+
+    #[test]
+    fn test_matchers_and_url() {
+        let backfiller = Backfiller::new(BackfillConfig {
+            endpoint: "http://prometheus:9090/".to_string(),
+            selector: Some(r#"node="node-1""#.to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(backfiller.url, "http://prometheus:9090/api/v1/query_range");
+        assert_eq!(
+            backfiller.matchers(),
+            r#"container!="",container!="POD",node="node-1""#
+        );
+    }
+}
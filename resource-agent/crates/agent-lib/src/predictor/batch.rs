@@ -0,0 +1,251 @@
+//! Prediction for run-to-completion workloads
+//!
+//! Jobs and CronJobs start cold, peak and exit, so the usage trends the
+//! model is trained on say little about them. Each run is summarised by
+//! its duration and peak usage instead, and the recommendation covers the
+//! largest peak of recent runs plus a buffer.
+
+use super::output::{MIN_CPU_MILLICORES, MIN_MEMORY_BYTES};
+use super::series::{SampleColumns, SeriesView};
+use crate::models::{ContainerMetrics, ResourceProfile, WorkloadKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Buffer added on top of the largest run peak (20%)
+pub const BATCH_PEAK_BUFFER: f64 = 0.20;
+
+/// Number of recent runs kept per workload
+pub const DEFAULT_MAX_RUNS: usize = 10;
+
+/// Model version reported on profiles of batch workloads
+pub const BATCH_MODEL_VERSION: &str = "batch-peak";
+
+/// Configuration for batch workload prediction
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// Buffer added on top of the largest run peak
+    pub peak_buffer: f64,
+    /// Number of recent runs kept per workload
+    pub max_runs: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            peak_buffer: BATCH_PEAK_BUFFER,
+            max_runs: DEFAULT_MAX_RUNS,
+        }
+    }
+}
+
+/// Usage summary of a single run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// Timestamp of the first sample (Unix seconds)
+    pub started_at: i64,
+    /// Time between the first and last sample
+    pub duration_secs: u64,
+    pub peak_cpu_cores: f32,
+    pub peak_memory_bytes: u64,
+}
+
+impl RunSummary {
+    /// Summarise the samples of one run, `None` without samples
+    pub fn from_metrics(metrics: &[ContainerMetrics]) -> Option<Self> {
+        Self::from_view(SampleColumns::from(metrics).view())
+    }
+
+    /// Summarise the sample columns of one run, `None` without samples
+    pub fn from_view(view: SeriesView<'_>) -> Option<Self> {
+        let started_at = view.timestamps.iter().copied().min()?;
+        let ended_at = view.timestamps.iter().copied().max()?;
+        Some(Self {
+            started_at,
+            duration_secs: (ended_at - started_at).max(0) as u64,
+            peak_cpu_cores: view.cpu_usage_cores.iter().copied().fold(0.0, f32::max),
+            peak_memory_bytes: view
+                .memory_working_set_bytes
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0),
+        })
+    }
+}
+
+/// Recent runs of Jobs and CronJobs, keyed by workload
+pub struct BatchPredictor {
+    config: BatchConfig,
+    runs: HashMap<String, VecDeque<RunSummary>>,
+}
+
+impl BatchPredictor {
+    pub fn new() -> Self {
+        Self::with_config(BatchConfig::default())
+    }
+
+    pub fn with_config(config: BatchConfig) -> Self {
+        Self {
+            config,
+            runs: HashMap::new(),
+        }
+    }
+
+    /// Record a finished run of a workload, dropping the oldest beyond `max_runs`
+    pub fn record_run(&mut self, workload: &str, run: RunSummary) {
+        let runs = self.runs.entry(workload.to_string()).or_default();
+        runs.push_back(run);
+        while runs.len() > self.config.max_runs.max(1) {
+            runs.pop_front();
+        }
+    }
+
+    /// Number of finished runs recorded for a workload
+    pub fn run_count(&self, workload: &str) -> usize {
+        self.runs.get(workload).map_or(0, VecDeque::len)
+    }
+
+    /// Recorded runs of every workload, oldest first
+    pub fn runs(&self) -> HashMap<String, Vec<RunSummary>> {
+        self.runs
+            .iter()
+            .map(|(workload, runs)| (workload.clone(), runs.iter().copied().collect()))
+            .collect()
+    }
+
+    /// Profile covering the largest peak of recent runs and of `current`,
+    /// the run in progress
+    ///
+    /// Requests and limits are equal: a run that is throttled or evicted
+    /// halfway has to start over, so there is no burst to leave room for.
+    /// Confidence grows with the number of finished runs.
+    pub fn predict(
+        &self,
+        workload: &str,
+        kind: WorkloadKind,
+        current: Option<&RunSummary>,
+    ) -> Option<ResourceProfile> {
+        let finished = self.runs.get(workload);
+        let mut runs = finished.into_iter().flatten().chain(current).peekable();
+        runs.peek()?;
+
+        let (peak_cpu, peak_memory) = runs.fold((0.0f32, 0u64), |(cpu, memory), run| {
+            (
+                cpu.max(run.peak_cpu_cores),
+                memory.max(run.peak_memory_bytes),
+            )
+        });
+        let factor = 1.0 + self.config.peak_buffer;
+        let cpu = ((peak_cpu as f64 * 1000.0 * factor).ceil() as u32).max(MIN_CPU_MILLICORES);
+        let memory = ((peak_memory as f64 * factor).ceil() as u64).max(MIN_MEMORY_BYTES);
+        let completed = finished.map_or(0, VecDeque::len) as f32;
+
+        Some(ResourceProfile {
+            cpu_request_millicores: cpu,
+            cpu_limit_millicores: cpu,
+            memory_request_bytes: memory,
+            memory_limit_bytes: memory,
+            confidence: completed / (completed + 2.0),
+            model_version: BATCH_MODEL_VERSION.to_string(),
+            generated_at: chrono::Utc::now().timestamp(),
+            workload_kind: kind,
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for BatchPredictor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(peak_cpu_cores: f32, peak_memory_bytes: u64) -> RunSummary {
+        RunSummary {
+            started_at: 0,
+            duration_secs: 60,
+            peak_cpu_cores,
+            peak_memory_bytes,
+        }
+    }
+
+    #[test]
+    fn test_run_summary_from_metrics() {
+        let metrics: Vec<ContainerMetrics> = [(100, 0.2, 300), (110, 1.5, 200), (160, 0.1, 500)]
+            .into_iter()
+            .map(|(timestamp, cpu, memory)| ContainerMetrics {
+                container_id: "job".to_string(),
+                pod_name: "job-abc".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                timestamp,
+                cpu_usage_cores: cpu,
+                cpu_throttled_periods: 0,
+                memory_usage_bytes: memory,
+                memory_working_set_bytes: memory,
+                memory_cache_bytes: 0,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            })
+            .collect();
+
+        let summary = RunSummary::from_metrics(&metrics).unwrap();
+        assert_eq!(summary.started_at, 100);
+        assert_eq!(summary.duration_secs, 60);
+        assert_eq!(summary.peak_cpu_cores, 1.5);
+        assert_eq!(summary.peak_memory_bytes, 500);
+        assert!(RunSummary::from_metrics(&[]).is_none());
+    }
+
+    #[test]
+    fn test_predict_peak_plus_buffer() {
+        let gib = 1024 * 1024 * 1024;
+        let mut predictor = BatchPredictor::new();
+        assert!(predictor
+            .predict("batch/report", WorkloadKind::CronJob, None)
+            .is_none());
+
+        predictor.record_run("batch/report", run(1.0, gib));
+        predictor.record_run("batch/report", run(2.0, gib / 2));
+
+        let profile = predictor
+            .predict("batch/report", WorkloadKind::CronJob, None)
+            .unwrap();
+        assert_eq!(profile.cpu_request_millicores, 2400);
+        assert_eq!(profile.cpu_limit_millicores, 2400);
+        assert_eq!(
+            profile.memory_request_bytes,
+            (gib as f64 * 1.2).ceil() as u64
+        );
+        assert_eq!(profile.workload_kind, WorkloadKind::CronJob);
+        assert_eq!(profile.model_version, BATCH_MODEL_VERSION);
+        assert!((profile.confidence - 0.5).abs() < 1e-6);
+
+        // A run in progress counts once it exceeds the recorded peaks
+        let profile = predictor
+            .predict("batch/report", WorkloadKind::CronJob, Some(&run(4.0, 0)))
+            .unwrap();
+        assert_eq!(profile.cpu_request_millicores, 4800);
+    }
+
+    #[test]
+    fn test_record_run_keeps_recent_runs() {
+        let mut predictor = BatchPredictor::with_config(BatchConfig {
+            max_runs: 2,
+            ..BatchConfig::default()
+        });
+        for cpu in [8.0, 1.0, 1.0] {
+            predictor.record_run("batch/report", run(cpu, 0));
+        }
+
+        assert_eq!(predictor.run_count("batch/report"), 2);
+        let profile = predictor
+            .predict("batch/report", WorkloadKind::Job, None)
+            .unwrap();
+        assert_eq!(profile.cpu_request_millicores, 1200);
+    }
+}
@@ -0,0 +1,467 @@
+//! Confidence calibration
+//!
+//! The model's confidence is a score, not a probability: a profile with
+//! confidence 0.9 is not necessarily kept within its limits nine times out
+//! of ten. `ConfidenceCalibrator` tracks whether usage actually stayed within
+//! each prediction's limits over the following `horizon`, fits a mapping
+//! from raw confidence to the observed rate, and replaces the confidence of
+//! new profiles with it, so that thresholds like auto-apply gates mean what
+//! they say.
+//!
+//! Outcomes are kept per model version: a new model starts uncalibrated.
+
+use crate::models::{ContainerMetrics, ResourceProfile};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::debug;
+
+/// Default time over which a prediction's limits must hold
+pub const DEFAULT_CALIBRATION_HORIZON: Duration = Duration::from_secs(60 * 60);
+
+/// Default outcomes required before confidence is calibrated
+pub const DEFAULT_MIN_CALIBRATION_OBSERVATIONS: usize = 50;
+
+/// Default number of most recent outcomes fitted
+pub const DEFAULT_MAX_CALIBRATION_OBSERVATIONS: usize = 5000;
+
+/// New outcomes between refits
+const REFIT_OBSERVATIONS: usize = 25;
+
+/// Unresolved predictions kept per container
+const MAX_PENDING_PER_CONTAINER: usize = 64;
+
+/// How raw confidence is mapped to a probability
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CalibrationMethod {
+    /// Logistic fit, smooth and robust with few outcomes
+    Platt,
+    /// Monotonic step fit, follows any shape given enough outcomes
+    #[default]
+    Isotonic,
+}
+
+/// Configuration of confidence calibration
+#[derive(Debug, Clone)]
+pub struct CalibrationConfig {
+    pub method: CalibrationMethod,
+    /// Time over which a prediction's limits must hold
+    pub horizon: Duration,
+    /// Outcomes required before confidence is calibrated
+    pub min_observations: usize,
+    /// Most recent outcomes fitted
+    pub max_observations: usize,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            method: CalibrationMethod::default(),
+            horizon: DEFAULT_CALIBRATION_HORIZON,
+            min_observations: DEFAULT_MIN_CALIBRATION_OBSERVATIONS,
+            max_observations: DEFAULT_MAX_CALIBRATION_OBSERVATIONS,
+        }
+    }
+}
+
+/// Fitted mapping from raw to calibrated confidence
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Calibration {
+    /// Raw confidence is used as is
+    #[default]
+    Identity,
+    /// `1 / (1 + exp(-(a * raw + b)))`
+    Platt { a: f64, b: f64 },
+    /// Increasing `(raw, probability)` points, interpolated linearly
+    Isotonic { points: Vec<(f64, f64)> },
+}
+
+impl Calibration {
+    /// Fit `method` to `(raw confidence, held)` outcomes
+    pub fn fit(method: CalibrationMethod, observations: &[(f32, bool)]) -> Self {
+        if observations.is_empty() {
+            return Self::Identity;
+        }
+        match method {
+            CalibrationMethod::Platt => fit_platt(observations),
+            CalibrationMethod::Isotonic => fit_isotonic(observations),
+        }
+    }
+
+    /// Calibrated confidence for a raw confidence
+    pub fn apply(&self, raw: f32) -> f32 {
+        let x = raw.clamp(0.0, 1.0) as f64;
+        let p = match self {
+            Self::Identity => x,
+            Self::Platt { a, b } => sigmoid(a * x + b),
+            Self::Isotonic { points } => interpolate(points, x),
+        };
+        p.clamp(0.0, 1.0) as f32
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+/// Logistic regression by Newton's method, with Platt's smoothed targets so
+/// that perfectly separated outcomes don't push the fit to 0 or 1
+fn fit_platt(observations: &[(f32, bool)]) -> Calibration {
+    let held = observations.iter().filter(|(_, held)| *held).count() as f64;
+    let missed = observations.len() as f64 - held;
+    let target_held = (held + 1.0) / (held + 2.0);
+    let target_missed = 1.0 / (missed + 2.0);
+
+    let prior = (held + 1.0) / (observations.len() as f64 + 2.0);
+    let (mut a, mut b) = (0.0, (prior / (1.0 - prior)).ln());
+    for _ in 0..100 {
+        let (mut g_a, mut g_b, mut h_aa, mut h_ab, mut h_bb) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for &(raw, outcome) in observations {
+            let x = raw.clamp(0.0, 1.0) as f64;
+            let t = if outcome { target_held } else { target_missed };
+            let p = sigmoid(a * x + b);
+            let w = (p * (1.0 - p)).max(1e-12);
+            g_a += (p - t) * x;
+            g_b += p - t;
+            h_aa += w * x * x;
+            h_ab += w * x;
+            h_bb += w;
+        }
+        let det = h_aa * h_bb - h_ab * h_ab;
+        if det <= 1e-9 * h_aa * h_bb {
+            // All raw confidences equal: only the intercept is identifiable
+            b -= g_b / h_bb;
+            break;
+        }
+        let da = (h_bb * g_a - h_ab * g_b) / det;
+        let db = (h_aa * g_b - h_ab * g_a) / det;
+        a -= da;
+        b -= db;
+        if da.abs() + db.abs() < 1e-9 {
+            break;
+        }
+    }
+
+    if a.is_finite() && b.is_finite() {
+        Calibration::Platt { a, b }
+    } else {
+        Calibration::Identity
+    }
+}
+
+/// Pool adjacent violators over outcomes sorted by raw confidence
+fn fit_isotonic(observations: &[(f32, bool)]) -> Calibration {
+    let mut sorted: Vec<(f64, f64)> = observations
+        .iter()
+        .map(|&(raw, held)| (raw.clamp(0.0, 1.0) as f64, if held { 1.0 } else { 0.0 }))
+        .collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // (sum of raw, sum of outcomes, count)
+    let mut blocks: Vec<(f64, f64, f64)> = Vec::new();
+    for (x, y) in sorted {
+        blocks.push((x, y, 1.0));
+        while blocks.len() > 1 {
+            let (x2, y2, n2) = blocks[blocks.len() - 1];
+            let (x1, y1, n1) = blocks[blocks.len() - 2];
+            if y1 / n1 < y2 / n2 {
+                break;
+            }
+            blocks.pop();
+            *blocks.last_mut().unwrap() = (x1 + x2, y1 + y2, n1 + n2);
+        }
+    }
+
+    let points = blocks.iter().map(|(x, y, n)| (x / n, y / n)).collect();
+    Calibration::Isotonic { points }
+}
+
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return x;
+    };
+    if x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+    let i = points.partition_point(|(px, _)| *px <= x);
+    let (x0, y0) = points[i - 1];
+    let (x1, y1) = points[i];
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
+
+/// Prediction whose limits are being checked
+#[derive(Debug)]
+struct PendingOutcome {
+    raw_confidence: f32,
+    /// Timestamp of the newest sample when predicted
+    issued_at: i64,
+    /// Limits, `None` when the profile leaves them unset
+    cpu_limit_cores: Option<f32>,
+    memory_limit_bytes: Option<u64>,
+    held: bool,
+}
+
+#[derive(Debug, Default)]
+struct CalibratorState {
+    model_version: String,
+    pending: HashMap<String, VecDeque<PendingOutcome>>,
+    /// `(raw confidence, held)`, oldest first
+    observations: VecDeque<(f32, bool)>,
+    since_fit: usize,
+    calibration: Calibration,
+}
+
+/// Calibration state of a model
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CalibrationStats {
+    pub model_version: String,
+    /// Predictions whose outcome is known
+    pub observations: usize,
+    /// Predictions still within their horizon
+    pub pending: usize,
+    /// Fraction of predictions whose limits held
+    pub held_rate: f64,
+    /// Whether confidence is being calibrated yet
+    pub calibrated: bool,
+}
+
+/// Tracks prediction outcomes and calibrates confidence against them
+#[derive(Debug, Default)]
+pub struct ConfidenceCalibrator {
+    config: CalibrationConfig,
+    state: Mutex<CalibratorState>,
+}
+
+impl ConfidenceCalibrator {
+    pub fn new(config: CalibrationConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CalibratorState::default()),
+        }
+    }
+
+    /// Track a new prediction and replace its confidence with the
+    /// calibrated one
+    ///
+    /// `issued_at` is the timestamp of the container's newest sample, so
+    /// that outcomes follow sample time. Fallback profiles are left alone:
+    /// their confidence is a fixed marker, not the model's.
+    pub fn calibrate(&self, container_id: &str, issued_at: i64, profile: &mut ResourceProfile) {
+        if profile.model_version == "fallback" {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.model_version != profile.model_version {
+            debug!(
+                model_version = %profile.model_version,
+                "Model changed, restarting confidence calibration"
+            );
+            *state = CalibratorState {
+                model_version: profile.model_version.clone(),
+                ..Default::default()
+            };
+        }
+
+        let pending = state.pending.entry(container_id.to_string()).or_default();
+        if pending.len() == MAX_PENDING_PER_CONTAINER {
+            pending.pop_front();
+        }
+        pending.push_back(PendingOutcome {
+            raw_confidence: profile.confidence,
+            issued_at,
+            cpu_limit_cores: (!profile.cpu_limit_unset)
+                .then(|| profile.cpu_limit_millicores as f32 / 1000.0),
+            memory_limit_bytes: (!profile.memory_limit_unset).then_some(profile.memory_limit_bytes),
+            held: true,
+        });
+
+        profile.confidence = state.calibration.apply(profile.confidence);
+    }
+
+    /// Check a sample against the container's pending predictions
+    pub fn observe(&self, metrics: &ContainerMetrics) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(pending) = state.pending.get_mut(metrics.container_id.as_str()) else {
+            return;
+        };
+
+        for outcome in pending
+            .iter_mut()
+            .filter(|outcome| metrics.timestamp > outcome.issued_at)
+        {
+            let cpu_exceeded = outcome
+                .cpu_limit_cores
+                .is_some_and(|limit| metrics.cpu_usage_cores > limit);
+            let memory_exceeded = outcome
+                .memory_limit_bytes
+                .is_some_and(|limit| metrics.memory_working_set_bytes > limit);
+            if cpu_exceeded || memory_exceeded {
+                outcome.held = false;
+            }
+        }
+
+        let horizon = self.config.horizon.as_secs() as i64;
+        let mut resolved = Vec::new();
+        while pending
+            .front()
+            .is_some_and(|outcome| metrics.timestamp >= outcome.issued_at + horizon)
+        {
+            let outcome = pending.pop_front().unwrap();
+            resolved.push((outcome.raw_confidence, outcome.held));
+        }
+        if pending.is_empty() {
+            state.pending.remove(metrics.container_id.as_str());
+        }
+        if resolved.is_empty() {
+            return;
+        }
+
+        state.since_fit += resolved.len();
+        state.observations.extend(resolved);
+        while state.observations.len() > self.config.max_observations {
+            state.observations.pop_front();
+        }
+        if state.observations.len() >= self.config.min_observations
+            && (state.since_fit >= REFIT_OBSERVATIONS || state.calibration == Calibration::Identity)
+        {
+            let observations = state.observations.make_contiguous();
+            state.calibration = Calibration::fit(self.config.method, observations);
+            state.since_fit = 0;
+        }
+    }
+
+    /// Calibrated confidence for a raw confidence of the current model
+    pub fn calibrated(&self, raw: f32) -> f32 {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.calibration.apply(raw)
+    }
+
+    /// Forget a container's pending predictions
+    pub fn remove_container(&self, container_id: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.pending.remove(container_id);
+    }
+
+    pub fn stats(&self) -> CalibrationStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let observations = state.observations.len();
+        let held = state.observations.iter().filter(|(_, held)| *held).count();
+        CalibrationStats {
+            model_version: state.model_version.clone(),
+            observations,
+            pending: state.pending.values().map(VecDeque::len).sum(),
+            held_rate: if observations == 0 {
+                0.0
+            } else {
+                held as f64 / observations as f64
+            },
+            calibrated: state.calibration != Calibration::Identity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Outcomes of an overconfident model: raw confidence `c` holds with
+    /// probability `c - 0.3`
+    fn overconfident() -> Vec<(f32, bool)> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..5000)
+            .map(|_| {
+                let raw: f32 = rng.gen_range(0.4..1.0);
+                (raw, rng.gen::<f32>() < raw - 0.3)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calibration_corrects_overconfidence() {
+        let observations = overconfident();
+        for method in [CalibrationMethod::Platt, CalibrationMethod::Isotonic] {
+            let calibration = Calibration::fit(method, &observations);
+            let high = calibration.apply(0.95);
+            let low = calibration.apply(0.5);
+            assert!((high - 0.65).abs() < 0.1, "{:?}: {}", method, high);
+            assert!((low - 0.2).abs() < 0.1, "{:?}: {}", method, low);
+            assert!(calibration.apply(0.6) <= calibration.apply(0.8));
+        }
+        assert_eq!(Calibration::Identity.apply(0.8), 0.8);
+    }
+
+    fn sample(timestamp: i64, cpu: f32) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: "c1".to_string(),
+            pod_name: "pod".to_string(),
+            namespace: "default".to_string(),
+            owner: None,
+            timestamp,
+            cpu_usage_cores: cpu,
+            cpu_throttled_periods: 0,
+            memory_usage_bytes: 0,
+            memory_working_set_bytes: 100 << 20,
+            memory_cache_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        }
+    }
+
+    fn profile(confidence: f32) -> ResourceProfile {
+        ResourceProfile {
+            cpu_request_millicores: 250,
+            cpu_limit_millicores: 500,
+            memory_request_bytes: 128 << 20,
+            memory_limit_bytes: 256 << 20,
+            confidence,
+            model_version: "v1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calibrator_tracks_outcomes() {
+        let calibrator = ConfidenceCalibrator::new(CalibrationConfig {
+            horizon: Duration::from_secs(60),
+            min_observations: 10,
+            ..Default::default()
+        });
+
+        // Confident predictions whose CPU limit is exceeded every other time
+        for i in 0..20i64 {
+            let issued_at = i * 100;
+            let mut p = profile(0.9);
+            calibrator.calibrate("c1", issued_at, &mut p);
+            let cpu = if i % 2 == 0 { 0.2 } else { 0.8 };
+            calibrator.observe(&sample(issued_at + 30, cpu));
+            calibrator.observe(&sample(issued_at + 60, 0.2));
+        }
+
+        let stats = calibrator.stats();
+        assert_eq!(stats.observations, 20);
+        assert_eq!(stats.pending, 0);
+        assert_eq!(stats.held_rate, 0.5);
+        assert!(stats.calibrated);
+        assert_eq!(calibrator.calibrated(0.9), 0.5);
+
+        let mut p = profile(0.9);
+        calibrator.calibrate("c1", 5000, &mut p);
+        assert_eq!(p.confidence, 0.5);
+
+        // A new model starts over
+        let mut p = ResourceProfile {
+            model_version: "v2".to_string(),
+            ..profile(0.9)
+        };
+        calibrator.calibrate("c1", 6000, &mut p);
+        assert_eq!(p.confidence, 0.9);
+        assert_eq!(calibrator.stats().observations, 0);
+    }
+}
@@ -0,0 +1,202 @@
+//! Checkpoints of the prediction scheduler's state
+//!
+//! Per-container samples, last profiles and finished batch runs live in
+//! memory. They are written to the data directory periodically and restored
+//! on startup, so a restart neither loses recent samples nor re-predicts
+//! every container at once.
+//!
+//! The file is a 4-byte magic and a little-endian `u32` format version,
+//! followed by zstd-compressed JSON. JSON rather than bincode because
+//! profiles omit unset optional fields when serialized. Writing and reading
+//! checkpoints requires the `persistence` feature.
+
+use super::{HysteresisAnchor, RunSummary};
+use crate::models::{ContainerMetrics, ResourceProfile, WorkloadKind};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File magic identifying a scheduler checkpoint
+const MAGIC: &[u8; 4] = b"KWCP";
+
+/// Format version written by this agent
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// zstd compression level
+#[cfg(feature = "persistence")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// Default interval between checkpoints
+pub const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Containers without a sample this recent are not restored
+pub const DEFAULT_CHECKPOINT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Where and how often the scheduler checkpoints its state
+#[derive(Debug, Clone)]
+pub struct CheckpointConfig {
+    pub path: PathBuf,
+    /// Interval between checkpoints
+    pub interval: Duration,
+    /// Containers whose newest sample is older are dropped on restore,
+    /// they most likely stopped while the agent was down
+    pub max_age: Duration,
+}
+
+impl CheckpointConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: DEFAULT_CHECKPOINT_INTERVAL,
+            max_age: DEFAULT_CHECKPOINT_MAX_AGE,
+        }
+    }
+}
+
+/// Scheduler state as stored on disk
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct Checkpoint {
+    /// Time the checkpoint was taken (Unix seconds)
+    pub saved_at: i64,
+    pub containers: Vec<ContainerCheckpoint>,
+    /// Finished runs of Jobs and CronJobs, keyed by workload
+    #[serde(default)]
+    pub batch_runs: HashMap<String, Vec<RunSummary>>,
+}
+
+/// A container's buffer as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ContainerCheckpoint {
+    pub container_id: String,
+    pub metrics: Vec<ContainerMetrics>,
+    #[serde(default)]
+    pub last_profile: Option<ResourceProfile>,
+    /// Time of the last prediction (Unix seconds)
+    #[serde(default)]
+    pub last_predicted_at: Option<i64>,
+    #[serde(default)]
+    pub workload: Option<(WorkloadKind, String)>,
+    /// Profile later recommendations are held near
+    #[serde(default)]
+    pub hysteresis_anchor: Option<HysteresisAnchor>,
+}
+
+/// Write a checkpoint atomically, returning its size in bytes
+pub(super) fn write(path: &Path, checkpoint: &Checkpoint) -> Result<usize> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+
+    let json = serde_json::to_vec(checkpoint).context("Failed to serialize checkpoint")?;
+    let body = compress(&json)?;
+
+    let temp_path = path.with_extension("tmp");
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp_path)
+        .with_context(|| format!("Failed to create temp file {:?}", temp_path))?;
+    file.write_all(MAGIC)?;
+    file.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+    file.write_all(&body)
+        .context("Failed to write checkpoint")?;
+    file.sync_all().context("Failed to sync checkpoint")?;
+
+    std::fs::rename(&temp_path, path)
+        .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
+
+    Ok(MAGIC.len() + 4 + body.len())
+}
+
+/// Read a checkpoint, failing on other files and unknown versions
+pub(super) fn read(path: &Path) -> Result<Checkpoint> {
+    let data =
+        std::fs::read(path).with_context(|| format!("Failed to read checkpoint {:?}", path))?;
+    if data.len() < MAGIC.len() + 4 || &data[..MAGIC.len()] != MAGIC {
+        bail!("{:?} is not a scheduler checkpoint", path);
+    }
+
+    let version = u32::from_le_bytes(data[4..8].try_into().expect("4-byte slice"));
+    if version != CHECKPOINT_VERSION {
+        bail!(
+            "Unsupported checkpoint version {} (expected {})",
+            version,
+            CHECKPOINT_VERSION
+        );
+    }
+
+    let json = decompress(&data[8..])?;
+    serde_json::from_slice(&json).context("Failed to deserialize checkpoint")
+}
+
+#[cfg(feature = "persistence")]
+fn compress(json: &[u8]) -> Result<Vec<u8>> {
+    zstd::encode_all(json, ZSTD_LEVEL).context("Failed to compress checkpoint")
+}
+
+#[cfg(feature = "persistence")]
+fn decompress(body: &[u8]) -> Result<Vec<u8>> {
+    zstd::decode_all(body).context("Failed to decompress checkpoint")
+}
+
+#[cfg(not(feature = "persistence"))]
+fn compress(_json: &[u8]) -> Result<Vec<u8>> {
+    bail!("Checkpoints require the `persistence` feature")
+}
+
+#[cfg(not(feature = "persistence"))]
+fn decompress(_body: &[u8]) -> Result<Vec<u8>> {
+    bail!("Checkpoints require the `persistence` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    #[cfg(feature = "persistence")]
+    fn test_write_read_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("scheduler.ckpt");
+        let checkpoint = Checkpoint {
+            saved_at: 100,
+            containers: vec![ContainerCheckpoint {
+                container_id: "c1".to_string(),
+                metrics: Vec::new(),
+                last_profile: None,
+                last_predicted_at: Some(90),
+                workload: Some((WorkloadKind::CronJob, "batch/report".to_string())),
+                hysteresis_anchor: None,
+            }],
+            batch_runs: HashMap::new(),
+        };
+
+        assert!(write(&path, &checkpoint).unwrap() > 8);
+        let restored = read(&path).unwrap();
+        assert_eq!(restored.saved_at, 100);
+        assert_eq!(restored.containers[0].container_id, "c1");
+        assert_eq!(restored.containers[0].last_predicted_at, Some(90));
+    }
+
+    #[test]
+    fn test_read_rejects_other_versions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("scheduler.ckpt");
+
+        std::fs::write(&path, b"not a checkpoint").unwrap();
+        assert!(read(&path).is_err());
+
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&(CHECKPOINT_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, data).unwrap();
+        let err = read(&path).unwrap_err();
+        assert!(err.to_string().contains("Unsupported checkpoint version"));
+    }
+}
@@ -0,0 +1,421 @@
+//! Prediction deviation samples for model training
+//!
+//! `DeviationCollector` follows a sampled fraction of predictions over
+//! `horizon` and pairs each with the usage observed after it, producing
+//! `(features, prediction, actual)` samples the control plane trains new
+//! models on. Samples carry the model input, the predicted profile and the
+//! observed usage only: no container, pod or namespace names leave the node.
+
+use crate::models::{ContainerMetrics, FeatureVector, ResourceProfile};
+#[cfg(feature = "grpc-sync")]
+use crate::proto;
+#[cfg(feature = "grpc-sync")]
+use crate::sync::{SyncClient, SyncError};
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hasher};
+#[cfg(feature = "grpc-sync")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(feature = "grpc-sync")]
+use tokio::sync::broadcast;
+#[cfg(feature = "grpc-sync")]
+use tracing::warn;
+
+/// Default time usage is observed after a prediction
+pub const DEFAULT_DEVIATION_HORIZON: Duration = Duration::from_secs(60 * 60);
+
+/// Default fraction of predictions sampled
+pub const DEFAULT_DEVIATION_SAMPLE_RATE: f64 = 0.05;
+
+/// Default number of finished samples held for upload
+pub const DEFAULT_MAX_TRAINING_SAMPLES: usize = 10_000;
+
+/// Default interval between uploads
+pub const DEFAULT_TRAINING_UPLOAD_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Samples sent per upload request
+#[cfg(feature = "grpc-sync")]
+const UPLOAD_BATCH_SIZE: usize = 500;
+
+/// Predictions followed at once per container
+const MAX_PENDING_PER_CONTAINER: usize = 16;
+
+/// Configuration of deviation sampling
+#[derive(Debug, Clone)]
+pub struct DeviationConfig {
+    /// Fraction of predictions followed, 0 to 1
+    pub sample_rate: f64,
+    /// Time usage is observed after a prediction
+    pub horizon: Duration,
+    /// Finished samples held for upload; the oldest are dropped beyond it
+    pub max_samples: usize,
+    pub upload_interval: Duration,
+}
+
+impl Default for DeviationConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: DEFAULT_DEVIATION_SAMPLE_RATE,
+            horizon: DEFAULT_DEVIATION_HORIZON,
+            max_samples: DEFAULT_MAX_TRAINING_SAMPLES,
+            upload_interval: DEFAULT_TRAINING_UPLOAD_INTERVAL,
+        }
+    }
+}
+
+/// A prediction paired with the usage that followed it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrainingSample {
+    /// Model input, in model order
+    pub features: Vec<f32>,
+    pub model_version: String,
+    pub predicted_cpu_request_millicores: u32,
+    pub predicted_cpu_limit_millicores: u32,
+    pub predicted_memory_request_bytes: u64,
+    pub predicted_memory_limit_bytes: u64,
+    /// Raw model confidence, before calibration
+    pub confidence: f32,
+    pub actual_cpu_mean_cores: f32,
+    pub actual_cpu_peak_cores: f32,
+    pub actual_memory_peak_bytes: u64,
+    /// Volatility of the node the sample was collected on, see
+    /// `FeatureVector::node_volatility`
+    pub node_volatility: f32,
+}
+
+#[cfg(feature = "grpc-sync")]
+impl TrainingSample {
+    fn to_proto(&self, horizon: Duration) -> proto::TrainingSample {
+        proto::TrainingSample {
+            features: self.features.clone(),
+            model_version: self.model_version.clone(),
+            predicted_cpu_request_millicores: self.predicted_cpu_request_millicores as i32,
+            predicted_cpu_limit_millicores: self.predicted_cpu_limit_millicores as i32,
+            predicted_memory_request_bytes: self.predicted_memory_request_bytes as i64,
+            predicted_memory_limit_bytes: self.predicted_memory_limit_bytes as i64,
+            confidence: self.confidence,
+            actual_cpu_mean_cores: self.actual_cpu_mean_cores,
+            actual_cpu_peak_cores: self.actual_cpu_peak_cores,
+            actual_memory_peak_bytes: self.actual_memory_peak_bytes as i64,
+            horizon_seconds: horizon.as_secs() as i32,
+            node_volatility: self.node_volatility,
+        }
+    }
+}
+
+/// A followed prediction and the usage seen since
+#[derive(Debug)]
+struct PendingSample {
+    sample: TrainingSample,
+    /// Timestamp of the newest sample when predicted
+    issued_at: i64,
+    cpu_sum: f64,
+    observations: u32,
+}
+
+#[derive(Debug, Default)]
+struct CollectorState {
+    pending: HashMap<String, VecDeque<PendingSample>>,
+    ready: VecDeque<TrainingSample>,
+    dropped: u64,
+}
+
+/// Sampling state of the collector
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviationStats {
+    /// Predictions still within their horizon
+    pub pending: usize,
+    /// Finished samples awaiting upload
+    pub ready: usize,
+    /// Finished samples dropped because the buffer was full or the API
+    /// rejected them
+    pub dropped: u64,
+}
+
+/// Collects prediction deviation samples and ships them for training
+#[derive(Debug, Default)]
+pub struct DeviationCollector {
+    config: DeviationConfig,
+    state: Mutex<CollectorState>,
+}
+
+impl DeviationCollector {
+    pub fn new(config: DeviationConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CollectorState::default()),
+        }
+    }
+
+    /// Follow a new prediction, if it is sampled
+    ///
+    /// `issued_at` is the timestamp of the container's newest sample, so
+    /// that the horizon follows sample time. Fallback profiles are not
+    /// model output and are never sampled.
+    pub fn record(
+        &self,
+        container_id: &str,
+        issued_at: i64,
+        features: &FeatureVector,
+        profile: &ResourceProfile,
+    ) {
+        if profile.model_version == "fallback" || !sampled(self.config.sample_rate) {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let pending = state.pending.entry(container_id.to_string()).or_default();
+        if pending.len() == MAX_PENDING_PER_CONTAINER {
+            pending.pop_front();
+        }
+        pending.push_back(PendingSample {
+            sample: TrainingSample {
+                features: features.model_input(),
+                model_version: profile.model_version.clone(),
+                predicted_cpu_request_millicores: profile.cpu_request_millicores,
+                predicted_cpu_limit_millicores: profile.cpu_limit_millicores,
+                predicted_memory_request_bytes: profile.memory_request_bytes,
+                predicted_memory_limit_bytes: profile.memory_limit_bytes,
+                confidence: profile.confidence,
+                actual_cpu_mean_cores: 0.0,
+                actual_cpu_peak_cores: 0.0,
+                actual_memory_peak_bytes: 0,
+                node_volatility: features.node_volatility,
+            },
+            issued_at,
+            cpu_sum: 0.0,
+            observations: 0,
+        });
+    }
+
+    /// Add a sample's usage to the container's followed predictions
+    pub fn observe(&self, metrics: &ContainerMetrics) {
+        let mut guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &mut *guard;
+        let Some(pending) = state.pending.get_mut(metrics.container_id.as_str()) else {
+            return;
+        };
+
+        for followed in pending
+            .iter_mut()
+            .filter(|followed| metrics.timestamp > followed.issued_at)
+        {
+            let sample = &mut followed.sample;
+            sample.actual_cpu_peak_cores =
+                sample.actual_cpu_peak_cores.max(metrics.cpu_usage_cores);
+            sample.actual_memory_peak_bytes = sample
+                .actual_memory_peak_bytes
+                .max(metrics.memory_working_set_bytes);
+            followed.cpu_sum += metrics.cpu_usage_cores as f64;
+            followed.observations += 1;
+        }
+
+        let horizon = self.config.horizon.as_secs() as i64;
+        while pending
+            .front()
+            .is_some_and(|followed| metrics.timestamp >= followed.issued_at + horizon)
+        {
+            let mut followed = pending.pop_front().unwrap();
+            followed.sample.actual_cpu_mean_cores =
+                (followed.cpu_sum / followed.observations.max(1) as f64) as f32;
+            if state.ready.len() >= self.config.max_samples {
+                state.ready.pop_front();
+                state.dropped += 1;
+            }
+            state.ready.push_back(followed.sample);
+        }
+        if pending.is_empty() {
+            state.pending.remove(metrics.container_id.as_str());
+        }
+    }
+
+    /// Stop following a container's predictions
+    pub fn remove_container(&self, container_id: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.pending.remove(container_id);
+    }
+
+    /// Take up to `max` finished samples, oldest first
+    pub fn drain(&self, max: usize) -> Vec<TrainingSample> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let count = max.min(state.ready.len());
+        state.ready.drain(..count).collect()
+    }
+
+    pub fn stats(&self) -> DeviationStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        DeviationStats {
+            pending: state.pending.values().map(VecDeque::len).sum(),
+            ready: state.ready.len(),
+            dropped: state.dropped,
+        }
+    }
+
+    /// Upload the finished samples in batches
+    ///
+    /// A batch that failed on a transient error is put back, to be retried on
+    /// the next upload; one the API can't accept is dropped.
+    #[cfg(feature = "grpc-sync")]
+    pub async fn upload(&self, client: &SyncClient) -> Result<usize, SyncError> {
+        let mut uploaded = 0;
+        loop {
+            let batch = self.drain(UPLOAD_BATCH_SIZE);
+            if batch.is_empty() {
+                return Ok(uploaded);
+            }
+            let samples = batch
+                .iter()
+                .map(|sample| sample.to_proto(self.config.horizon))
+                .collect();
+            if let Err(e) = client.upload_training_samples(samples).await {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if e.is_retryable() {
+                    for sample in batch.into_iter().rev() {
+                        state.ready.push_front(sample);
+                    }
+                } else {
+                    state.dropped += batch.len() as u64;
+                }
+                return Err(e);
+            }
+            uploaded += batch.len();
+        }
+    }
+
+    /// Upload finished samples every interval until shutdown
+    #[cfg(feature = "grpc-sync")]
+    pub async fn run(
+        self: Arc<Self>,
+        client: Arc<SyncClient>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) {
+        let mut ticker = tokio::time::interval(self.config.upload_interval);
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(e) = self.upload(&client).await {
+                        warn!(error = %e, "Failed to upload training samples");
+                    }
+                }
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+/// Whether to sample, with probability `rate`
+///
+/// Every `RandomState` gets distinct SipHash keys, so hashing nothing with a
+/// new one gives a uniform draw without a random number generator.
+fn sampled(rate: f64) -> bool {
+    let draw = RandomState::new().build_hasher().finish() as f64 / (u64::MAX as f64 + 1.0);
+    draw < rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features() -> FeatureVector {
+        FeatureVector {
+            cpu_usage_p50: 0.1,
+            cpu_usage_p95: 0.2,
+            cpu_usage_p99: 0.3,
+            mem_usage_p50: 0.1,
+            mem_usage_p95: 0.2,
+            mem_usage_p99: 0.3,
+            cpu_variance: 0.0,
+            mem_trend: 0.0,
+            throttle_ratio: 0.0,
+            hour_of_day: 0.5,
+            day_of_week: 0.5,
+            workload_age_days: 0.1,
+            node_pressure: 0.0,
+            node_volatility: 0.0,
+        }
+    }
+
+    fn profile(model_version: &str) -> ResourceProfile {
+        ResourceProfile {
+            cpu_request_millicores: 250,
+            cpu_limit_millicores: 500,
+            memory_request_bytes: 128 << 20,
+            memory_limit_bytes: 256 << 20,
+            confidence: 0.8,
+            model_version: model_version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn sample(timestamp: i64, cpu: f32, memory: u64) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: "c1".to_string(),
+            pod_name: "pod".to_string(),
+            namespace: "default".to_string(),
+            owner: None,
+            timestamp,
+            cpu_usage_cores: cpu,
+            cpu_throttled_periods: 0,
+            memory_usage_bytes: memory,
+            memory_working_set_bytes: memory,
+            memory_cache_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_sample_pairs_prediction_with_usage() {
+        let collector = DeviationCollector::new(DeviationConfig {
+            sample_rate: 1.0,
+            horizon: Duration::from_secs(60),
+            ..Default::default()
+        });
+        collector.record("c1", 100, &features(), &profile("v1"));
+        collector.record("c1", 100, &features(), &profile("fallback"));
+
+        collector.observe(&sample(100, 9.0, 1 << 30));
+        collector.observe(&sample(130, 0.2, 100 << 20));
+        collector.observe(&sample(150, 0.4, 200 << 20));
+        assert_eq!(collector.stats().pending, 1);
+        assert!(collector.drain(10).is_empty());
+
+        collector.observe(&sample(160, 0.3, 150 << 20));
+        let samples = collector.drain(10);
+        assert_eq!(samples.len(), 1);
+        let sample = &samples[0];
+        assert_eq!(sample.features.len(), 12);
+        assert_eq!(sample.predicted_cpu_limit_millicores, 500);
+        assert!((sample.actual_cpu_mean_cores - 0.3).abs() < 1e-6);
+        assert_eq!(sample.actual_cpu_peak_cores, 0.4);
+        assert_eq!(sample.actual_memory_peak_bytes, 200 << 20);
+        assert_eq!(collector.stats().pending, 0);
+    }
+
+    #[test]
+    fn test_sample_rate_and_buffer_bound() {
+        let collector = DeviationCollector::new(DeviationConfig {
+            sample_rate: 0.0,
+            ..Default::default()
+        });
+        collector.record("c1", 100, &features(), &profile("v1"));
+        assert_eq!(collector.stats().pending, 0);
+
+        let collector = DeviationCollector::new(DeviationConfig {
+            sample_rate: 1.0,
+            horizon: Duration::from_secs(0),
+            max_samples: 2,
+            ..Default::default()
+        });
+        for ts in 0..3 {
+            collector.record("c1", ts, &features(), &profile("v1"));
+            collector.observe(&sample(ts + 1, 0.1, 1));
+        }
+        let stats = collector.stats();
+        assert_eq!((stats.ready, stats.dropped), (2, 1));
+    }
+}
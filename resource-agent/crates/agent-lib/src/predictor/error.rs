@@ -0,0 +1,33 @@
+//! Errors raised by predictors
+
+/// Failure to load a model or run a prediction
+#[derive(Debug, thiserror::Error)]
+pub enum PredictError {
+    /// Model bytes that can't be parsed or optimized
+    #[error("Invalid model: {0:#}")]
+    InvalidModel(anyhow::Error),
+    /// The model failed to run on the features
+    #[error("Inference failed: {0:#}")]
+    Inference(anyhow::Error),
+    /// The model produced fewer values than a profile needs
+    #[error("Model output has {values} values, expected {expected}")]
+    UnexpectedOutput { values: usize, expected: usize },
+    /// A thread panicked while holding the model
+    #[error("Model lock poisoned")]
+    LockPoisoned,
+}
+
+impl PredictError {
+    /// Whether predicting again may succeed
+    ///
+    /// Inference is deterministic: the same model and features fail the same
+    /// way, so callers fall back or reject the model instead of retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PredictError::InvalidModel(_)
+            | PredictError::Inference(_)
+            | PredictError::UnexpectedOutput { .. }
+            | PredictError::LockPoisoned => false,
+        }
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! Extracts features from raw container metrics for the prediction model.
 //! Features include rolling percentiles, variance, trend indicators, and
-//! temporal context.
+//! temporal context. Samples are aligned to a fixed time grid first, see
+//! `Resampler`.
 
+use super::resample::Resampler;
+use super::series::{SampleColumns, SeriesView};
 use crate::models::{ContainerMetrics, FeatureVector};
 use chrono::{Datelike, Timelike, Utc};
 
@@ -15,6 +18,7 @@ pub struct FeatureExtractor {
     window_size: usize,
     max_cpu_cores: f32,
     max_memory_bytes: u64,
+    resampler: Resampler,
 }
 
 impl FeatureExtractor {
@@ -23,6 +27,7 @@ impl FeatureExtractor {
             window_size,
             max_cpu_cores: 16.0,
             max_memory_bytes: 64 * 1024 * 1024 * 1024,
+            resampler: Resampler::default(),
         }
     }
 
@@ -31,22 +36,63 @@ impl FeatureExtractor {
             window_size,
             max_cpu_cores,
             max_memory_bytes,
+            resampler: Resampler::default(),
         }
     }
 
+    /// Align samples with `resampler`, e.g. to the grid of a non-default
+    /// collection interval
+    pub fn with_resampler(mut self, resampler: Resampler) -> Self {
+        self.resampler = resampler;
+        self
+    }
+
     pub fn has_sufficient_data(&self, metrics: &[ContainerMetrics]) -> bool {
         metrics.len() >= MIN_SAMPLES
     }
 
     pub fn extract(&self, metrics: &[ContainerMetrics]) -> Option<FeatureVector> {
-        if metrics.len() < MIN_SAMPLES {
+        self.extract_view(SampleColumns::from(metrics).view())
+    }
+
+    /// Extract features from sample columns, oldest first
+    ///
+    /// Samples out of order or sharing a timestamp, as left by wall-clock
+    /// steps, are sorted and deduplicated first, then aligned to the
+    /// resampler's grid so that irregular spacing does not bias
+    /// percentiles and trends.
+    #[tracing::instrument(name = "feature_extraction", skip_all, fields(samples = view.len()))]
+    pub fn extract_view(&self, view: SeriesView<'_>) -> Option<FeatureVector> {
+        if view.len() < MIN_SAMPLES {
+            return None;
+        }
+        let resampled = self.resampler.resample(view);
+        self.extract_ordered(resampled.columns.view())
+    }
+
+    fn extract_ordered(&self, view: SeriesView<'_>) -> Option<FeatureVector> {
+        if view.len() < MIN_SAMPLES {
             return None;
         }
-        let samples: Vec<_> = metrics.iter().rev().take(self.window_size).collect();
-        let cpu_values: Vec<f32> = samples.iter().map(|m| m.cpu_usage_cores).collect();
-        let mem_values: Vec<f64> = samples
+        let start = view.len().saturating_sub(self.window_size);
+        // Newest first, the order the model's features were defined on
+        let cpu_values: Vec<f32> = view.cpu_usage_cores[start..]
             .iter()
-            .map(|m| m.memory_working_set_bytes as f64)
+            .rev()
+            .copied()
+            .collect();
+        let mem_values: Vec<f64> = view.memory_working_set_bytes[start..]
+            .iter()
+            .rev()
+            .map(|&m| m as f64)
+            .collect();
+        let newest = view.timestamps.last().copied().unwrap_or(0);
+        // Grid steps back from the newest sample, matching the order above
+        let step = self.resampler.step().as_secs_f64();
+        let offsets: Vec<f64> = view.timestamps[start..]
+            .iter()
+            .rev()
+            .map(|&ts| (newest - ts) as f64 / step)
             .collect();
 
         Some(FeatureVector {
@@ -57,11 +103,13 @@ impl FeatureExtractor {
             mem_usage_p95: self.normalize_memory(percentile_f64(&mem_values, 95.0) as u64),
             mem_usage_p99: self.normalize_memory(percentile_f64(&mem_values, 99.0) as u64),
             cpu_variance: self.normalize_variance(variance(&cpu_values)),
-            mem_trend: self.calculate_memory_trend(&mem_values),
-            throttle_ratio: self.calculate_throttle_ratio(&samples),
-            hour_of_day: self.extract_hour(samples.first().map(|m| m.timestamp).unwrap_or(0)),
-            day_of_week: self.extract_day(samples.first().map(|m| m.timestamp).unwrap_or(0)),
-            workload_age_days: self.calculate_workload_age(metrics),
+            mem_trend: self.calculate_memory_trend(&offsets, &mem_values),
+            throttle_ratio: self.calculate_throttle_ratio(&view, start),
+            hour_of_day: self.extract_hour(newest),
+            day_of_week: self.extract_day(newest),
+            workload_age_days: self.calculate_workload_age(view.timestamps),
+            node_pressure: 0.0,
+            node_volatility: 0.0,
         })
     }
 
@@ -78,25 +126,25 @@ impl FeatureExtractor {
         scaled.tanh().clamp(0.0, 1.0)
     }
 
-    fn calculate_memory_trend(&self, mem_values: &[f64]) -> f32 {
+    /// Memory slope per grid step over `offsets` steps, which skip the gaps
+    /// left unfilled
+    fn calculate_memory_trend(&self, offsets: &[f64], mem_values: &[f64]) -> f32 {
         if mem_values.len() < 2 {
             return 0.0;
         }
-        let slope = linear_regression_slope(mem_values);
+        let slope = regression_slope(offsets, mem_values);
         let max_slope = self.max_memory_bytes as f64 / 3600.0;
         ((slope / max_slope) as f32).clamp(-1.0, 1.0)
     }
 
-    fn calculate_throttle_ratio(&self, samples: &[&ContainerMetrics]) -> f32 {
-        if samples.len() < 2 {
+    fn calculate_throttle_ratio(&self, view: &SeriesView<'_>, start: usize) -> f32 {
+        let last = view.len() - 1;
+        if last <= start {
             return 0.0;
         }
-        let first = samples.last().unwrap();
-        let last = samples.first().unwrap();
-        let throttle_delta = last
-            .cpu_throttled_periods
-            .saturating_sub(first.cpu_throttled_periods);
-        let time_delta = (last.timestamp - first.timestamp).max(1) as f64;
+        let throttle_delta =
+            view.cpu_throttled_periods[last].saturating_sub(view.cpu_throttled_periods[start]);
+        let time_delta = (view.timestamps[last] - view.timestamps[start]).max(1) as f64;
         ((throttle_delta as f64 / time_delta) / 100.0).clamp(0.0, 1.0) as f32
     }
 
@@ -110,12 +158,12 @@ impl FeatureExtractor {
         dt.weekday().num_days_from_monday() as f32 / 7.0
     }
 
-    fn calculate_workload_age(&self, metrics: &[ContainerMetrics]) -> f32 {
-        if metrics.is_empty() {
+    fn calculate_workload_age(&self, timestamps: &[i64]) -> f32 {
+        if timestamps.is_empty() {
             return 0.0;
         }
-        let first = metrics.iter().map(|m| m.timestamp).min().unwrap_or(0);
-        let last = metrics.iter().map(|m| m.timestamp).max().unwrap_or(0);
+        let first = timestamps.iter().copied().min().unwrap_or(0);
+        let last = timestamps.iter().copied().max().unwrap_or(0);
         let age_days = (last - first).max(0) as f64 / 86400.0;
         (age_days / 30.0).clamp(0.0, 1.0) as f32
     }
@@ -152,14 +200,20 @@ fn variance(values: &[f32]) -> f32 {
 
 /// Calculate linear regression slope for trend detection
 pub fn linear_regression_slope(values: &[f64]) -> f64 {
-    if values.len() < 2 {
+    let xs: Vec<f64> = (0..values.len()).map(|i| i as f64).collect();
+    regression_slope(&xs, values)
+}
+
+/// Linear regression slope of `ys` over `xs`
+fn regression_slope(xs: &[f64], ys: &[f64]) -> f64 {
+    if ys.len() < 2 {
         return 0.0;
     }
-    let n = values.len() as f64;
-    let sum_x: f64 = (0..values.len()).map(|i| i as f64).sum();
-    let sum_y: f64 = values.iter().sum();
-    let sum_xy: f64 = values.iter().enumerate().map(|(i, y)| i as f64 * y).sum();
-    let sum_x2: f64 = (0..values.len()).map(|i| (i as f64).powi(2)).sum();
+    let n = ys.len() as f64;
+    let sum_x: f64 = xs.iter().sum();
+    let sum_y: f64 = ys.iter().sum();
+    let sum_xy: f64 = xs.iter().zip(ys).map(|(x, y)| x * y).sum();
+    let sum_x2: f64 = xs.iter().map(|x| x.powi(2)).sum();
     let denom = n * sum_x2 - sum_x.powi(2);
     if denom.abs() < f64::EPSILON {
         return 0.0;
@@ -170,6 +224,8 @@ pub fn linear_regression_slope(values: &[f64]) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::OwnerRef;
+    use std::time::Duration;
 
     fn create_test_metrics(count: usize, cpu_base: f32, mem_base: u64) -> Vec<ContainerMetrics> {
         let now = Utc::now().timestamp();
@@ -178,7 +234,7 @@ mod tests {
                 container_id: "test".to_string(),
                 pod_name: "test-pod".to_string(),
                 namespace: "default".to_string(),
-                deployment: Some("test-deploy".to_string()),
+                owner: Some(OwnerRef::deployment("test-deploy")),
                 timestamp: now - (count - i - 1) as i64 * 10,
                 cpu_usage_cores: cpu_base + (i as f32 * 0.01),
                 cpu_throttled_periods: i as u64 * 10,
@@ -230,6 +286,21 @@ mod tests {
         assert!((linear_regression_slope(&values) - 1.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_unordered_samples() {
+        let extractor = FeatureExtractor::new(100);
+        let metrics = create_test_metrics(20, 0.5, 100_000_000);
+        let expected = extractor.extract(&metrics).unwrap();
+
+        // Shuffled and partly duplicated, as after a wall-clock step back
+        let mut shuffled: Vec<ContainerMetrics> = metrics.iter().rev().cloned().collect();
+        shuffled.extend(metrics[15..].iter().cloned());
+        let f = extractor.extract(&shuffled).unwrap();
+        assert_eq!(f.mem_trend, expected.mem_trend);
+        assert_eq!(f.throttle_ratio, expected.throttle_ratio);
+        assert_eq!(f.cpu_usage_p95, expected.cpu_usage_p95);
+    }
+
     #[test]
     fn test_feature_normalization() {
         let extractor = FeatureExtractor::new(100);
@@ -255,6 +326,49 @@ mod tests {
         assert!(f.mem_trend != 0.0, "Memory trend should be non-zero");
     }
 
+    /// `(timestamp, cpu, memory)` columns of the samples
+    fn view_of(samples: &[(i64, f32, u64)]) -> SampleColumns {
+        let metrics: Vec<ContainerMetrics> = create_test_metrics(samples.len(), 0.0, 0)
+            .into_iter()
+            .zip(samples)
+            .map(|(m, &(timestamp, cpu, memory))| ContainerMetrics {
+                timestamp,
+                cpu_usage_cores: cpu,
+                memory_working_set_bytes: memory,
+                ..m
+            })
+            .collect();
+        SampleColumns::from(metrics.as_slice())
+    }
+
+    #[test]
+    fn test_irregular_spacing_unbiased() {
+        let extractor = FeatureExtractor::new(1000);
+        let unfilled = FeatureExtractor::new(1000)
+            .with_resampler(Resampler::new(Duration::from_secs(10), Duration::ZERO));
+        // 20 minutes idle at 10s, then 40 minutes busy sampled every 30s in
+        // degraded mode, while memory grows steadily
+        let usage = |t: i64| {
+            let cpu = if t < 1200 { 0.2 } else { 1.6 };
+            (t, cpu, 100_000_000 + t as u64 * 10_000)
+        };
+        let irregular: Vec<_> = (0..1200)
+            .step_by(10)
+            .chain((1200..3600).step_by(30))
+            .map(usage)
+            .collect();
+        let regular: Vec<_> = (0..3600).step_by(10).map(usage).collect();
+
+        // Busy most of the time, but in fewer samples than idle
+        let f = extractor.extract_view(view_of(&irregular).view()).unwrap();
+        let biased = unfilled.extract_view(view_of(&irregular).view()).unwrap();
+        assert_eq!(f.cpu_usage_p50, 0.1);
+        assert_eq!(biased.cpu_usage_p50, 0.0125);
+
+        let expected = extractor.extract_view(view_of(&regular).view()).unwrap();
+        assert!((f.mem_trend - expected.mem_trend).abs() < expected.mem_trend.abs() * 0.05);
+    }
+
     #[test]
     fn test_empty_values() {
         assert_eq!(percentile(&[], 50.0), 0.0);
@@ -0,0 +1,199 @@
+//! Workload fingerprinting
+//!
+//! A fingerprint is a compact signature of the shape of a container's
+//! usage: how periodic its CPU usage is, how bursty, and its footprint.
+//! Fingerprints are grouped by quantizing each dimension into a few coarse
+//! levels, so similar workloads share a cluster ID on every node without
+//! any coordination. The ID is attached to each recommendation, letting
+//! the control plane learn per-cluster priors and the CLI list similar
+//! workloads.
+
+use super::series::{SampleColumns, SeriesView};
+use super::MIN_SAMPLES;
+use serde::{Deserialize, Serialize};
+
+/// Periods checked for repeating usage, in seconds
+pub const FINGERPRINT_PERIODS: [i64; 5] = [5 * 60, 15 * 60, 60 * 60, 4 * 60 * 60, 12 * 60 * 60];
+
+/// Periodicity from which usage counts as weakly and strongly periodic
+const PERIODICITY_LEVELS: [f32; 2] = [0.3, 0.6];
+
+/// Burstiness from which usage counts as spiky and bursty
+const BURSTINESS_LEVELS: [f32; 2] = [0.25, 0.6];
+
+/// Compact signature of a container's usage shape
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadFingerprint {
+    /// Strongest autocorrelation of CPU usage at one of
+    /// `FINGERPRINT_PERIODS`, 0 to 1
+    pub periodicity: f32,
+    /// How far CPU peaks rise above typical usage, 0 (flat) to 1
+    pub burstiness: f32,
+    /// 95th percentile CPU usage
+    pub cpu_millicores: u32,
+    /// 95th percentile memory working set
+    pub memory_bytes: u64,
+}
+
+impl WorkloadFingerprint {
+    /// Fingerprint of a container's samples, if there are enough of them
+    pub fn from_view(view: SeriesView<'_>) -> Option<Self> {
+        if !view.is_ordered() {
+            return Self::from_ordered(SampleColumns::ordered(view).view());
+        }
+        Self::from_ordered(view)
+    }
+
+    fn from_ordered(view: SeriesView<'_>) -> Option<Self> {
+        if view.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut cpu: Vec<f32> = view.cpu_usage_cores.to_vec();
+        let mut memory: Vec<u64> = view.memory_working_set_bytes.to_vec();
+        let periodicity = periodicity(view.timestamps, &cpu);
+        cpu.sort_by(|a, b| a.total_cmp(b));
+        memory.sort_unstable();
+
+        let p50 = percentile(&cpu, 0.50);
+        let p99 = percentile(&cpu, 0.99);
+        let burstiness = if p99 > 0.0 {
+            (1.0 - p50 / p99).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            periodicity,
+            burstiness,
+            cpu_millicores: (percentile(&cpu, 0.95) * 1000.0).round() as u32,
+            memory_bytes: percentile(&memory, 0.95),
+        })
+    }
+
+    /// ID of the cluster of similar fingerprints, e.g. `p2b0c3m4`
+    ///
+    /// Periodicity and burstiness are bucketed into three levels, and the
+    /// CPU and memory footprints into factors of four (from 1m and 1Mi),
+    /// so a cluster spans workloads of a similar shape and size.
+    pub fn cluster_id(&self) -> String {
+        format!(
+            "p{}b{}c{}m{}",
+            level(self.periodicity, &PERIODICITY_LEVELS),
+            level(self.burstiness, &BURSTINESS_LEVELS),
+            scale(self.cpu_millicores as u64),
+            scale(self.memory_bytes >> 20),
+        )
+    }
+}
+
+/// Number of thresholds `value` reaches
+fn level(value: f32, thresholds: &[f32]) -> usize {
+    thresholds.iter().filter(|&&t| value >= t).count()
+}
+
+/// Base-4 order of magnitude, 0 for values below 4
+fn scale(value: u64) -> u32 {
+    value.max(1).ilog2() / 2
+}
+
+/// Value at fraction `q` of sorted values
+fn percentile<T: Copy>(sorted: &[T], q: f64) -> T {
+    let idx = (q * (sorted.len() - 1) as f64).round() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+/// Strongest autocorrelation at the periods covered twice by the samples
+fn periodicity(timestamps: &[i64], cpu: &[f32]) -> f32 {
+    let (Some(&first), Some(&last)) = (timestamps.first(), timestamps.last()) else {
+        return 0.0;
+    };
+    let step = (last - first) as f64 / (timestamps.len() - 1).max(1) as f64;
+    if step <= 0.0 {
+        return 0.0;
+    }
+    FINGERPRINT_PERIODS
+        .iter()
+        .map(|&period| (period as f64 / step).round() as usize)
+        .filter(|&lag| lag > 0 && lag * 2 <= cpu.len())
+        .map(|lag| autocorrelation(cpu, lag))
+        .fold(0.0, f32::max)
+        .clamp(0.0, 1.0)
+}
+
+fn autocorrelation(values: &[f32], lag: usize) -> f32 {
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / values.len() as f64;
+    let variance: f64 = values.iter().map(|&v| (v as f64 - mean).powi(2)).sum();
+    if variance <= f64::EPSILON {
+        return 0.0;
+    }
+    let covariance: f64 = values
+        .iter()
+        .zip(&values[lag..])
+        .map(|(&a, &b)| (a as f64 - mean) * (b as f64 - mean))
+        .sum();
+    (covariance / variance) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(cpu: impl Fn(usize) -> f32, memory: u64) -> WorkloadFingerprint {
+        // Two days of minutely samples
+        let n = 2 * 24 * 60;
+        let timestamps: Vec<i64> = (0..n as i64).map(|i| i * 60).collect();
+        let cpu: Vec<f32> = (0..n).map(cpu).collect();
+        let throttled = vec![0; n];
+        let memory = vec![memory; n];
+        WorkloadFingerprint::from_view(SeriesView {
+            timestamps: &timestamps,
+            cpu_usage_cores: &cpu,
+            cpu_throttled_periods: &throttled,
+            memory_working_set_bytes: &memory,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint_shapes() {
+        // Flat usage is neither periodic nor bursty
+        let flat = fingerprint(|_| 0.5, 256 << 20);
+        assert_eq!(flat.periodicity, 0.0);
+        assert_eq!(flat.burstiness, 0.0);
+        assert_eq!(flat.cpu_millicores, 500);
+        assert_eq!(flat.cluster_id(), "p0b0c4m4");
+
+        // An hourly cycle is strongly periodic
+        let hourly = fingerprint(
+            |i| 0.5 + 0.4 * (i as f32 * std::f32::consts::TAU / 60.0).sin(),
+            256 << 20,
+        );
+        assert!(hourly.periodicity > 0.9);
+        assert!(hourly.cluster_id().starts_with("p2"));
+
+        // Rare spikes over an idle baseline are bursty but not periodic
+        let spiky = fingerprint(|i| if i % 50 == 0 { 2.0 } else { 0.1 }, 256 << 20);
+        assert!(spiky.burstiness > 0.9);
+        assert!(spiky.cluster_id().starts_with("p0b2"));
+    }
+
+    #[test]
+    fn test_similar_workloads_share_cluster() {
+        let a = fingerprint(|_| 0.4, 300 << 20);
+        let b = fingerprint(|_| 0.45, 350 << 20);
+        let c = fingerprint(|_| 4.0, 300 << 20);
+        assert_eq!(a.cluster_id(), b.cluster_id());
+        assert_ne!(a.cluster_id(), c.cluster_id());
+    }
+
+    #[test]
+    fn test_too_few_samples() {
+        let view = SeriesView {
+            timestamps: &[0, 10],
+            cpu_usage_cores: &[0.1, 0.2],
+            cpu_throttled_periods: &[0, 0],
+            memory_working_set_bytes: &[1, 2],
+        };
+        assert!(WorkloadFingerprint::from_view(view).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Anomaly-aware prediction gating
+//!
+//! Samples taken during a memory leak, CPU spike or CPU throttling say
+//! little about what a container needs once the anomaly is over, so a
+//! recommendation derived from them is deferred: flagged as such, or held
+//! at the previous recommendation until the anomaly has left the window.
+
+use crate::anomaly::DetectorKind;
+use crate::models::ResourceProfile;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default time after an anomaly during which samples count as tainted,
+/// the feature window at 10s intervals
+pub const DEFAULT_ANOMALY_TAINT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// How recommendations derived from anomalous samples are deferred
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnomalyGate {
+    /// Publish the recommendation with a note on the anomaly
+    #[default]
+    Flag,
+    /// Keep the affected resource at the previous recommendation, or skip
+    /// the prediction when there is none
+    Hold,
+}
+
+impl AnomalyGate {
+    /// Defer the parts of `profile` derived from samples taken during
+    /// `anomalies`
+    ///
+    /// Memory leaks taint the memory recommendation, CPU spikes and
+    /// throttling the CPU one. Sets `anomaly_note` on a deferred profile and
+    /// returns false when it should not be published at all.
+    pub fn apply(
+        self,
+        profile: &mut ResourceProfile,
+        previous: Option<&ResourceProfile>,
+        anomalies: &[DetectorKind],
+    ) -> bool {
+        let cpu = anomalies
+            .iter()
+            .any(|kind| matches!(kind, DetectorKind::CpuSpike | DetectorKind::CpuThrottling));
+        let memory = anomalies.contains(&DetectorKind::MemoryLeak);
+        let resources = match (cpu, memory) {
+            (false, false) => return true,
+            (true, false) => "CPU",
+            (false, true) => "memory",
+            (true, true) => "CPU and memory",
+        };
+        let kinds = anomalies
+            .iter()
+            .filter(|&&kind| kind != DetectorKind::NetworkSpike)
+            .map(|kind| kind.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match self {
+            AnomalyGate::Flag => {
+                profile.anomaly_note = Some(format!(
+                    "Deferred due to anomaly ({}): {} derived from anomalous usage",
+                    kinds, resources
+                ));
+            }
+            AnomalyGate::Hold => {
+                let Some(previous) = previous else {
+                    return false;
+                };
+                if cpu {
+                    profile.cpu_request_millicores = previous.cpu_request_millicores;
+                    profile.cpu_limit_millicores = previous.cpu_limit_millicores;
+                    profile.cpu_limit_unset = previous.cpu_limit_unset;
+                }
+                if memory {
+                    profile.memory_request_bytes = previous.memory_request_bytes;
+                    profile.memory_limit_bytes = previous.memory_limit_bytes;
+                    profile.memory_limit_unset = previous.memory_limit_unset;
+                }
+                profile.raise_limits_to_requests();
+                profile.anomaly_note = Some(format!(
+                    "Deferred due to anomaly ({}): {} kept at the previous recommendation",
+                    kinds, resources
+                ));
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(cpu_request: u32, memory_request: u64) -> ResourceProfile {
+        ResourceProfile {
+            cpu_request_millicores: cpu_request,
+            cpu_limit_millicores: cpu_request * 2,
+            memory_request_bytes: memory_request,
+            memory_limit_bytes: memory_request * 2,
+            confidence: 0.8,
+            model_version: "v1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unaffected_profile_passes() {
+        let mut p = profile(500, 1 << 30);
+        assert!(AnomalyGate::Hold.apply(&mut p, None, &[DetectorKind::NetworkSpike]));
+        assert!(p.anomaly_note.is_none());
+        assert_eq!(p.cpu_request_millicores, 500);
+    }
+
+    #[test]
+    fn test_flag_keeps_values() {
+        let mut p = profile(500, 1 << 30);
+        assert!(AnomalyGate::Flag.apply(&mut p, None, &[DetectorKind::MemoryLeak]));
+        assert_eq!(p.memory_request_bytes, 1 << 30);
+        assert_eq!(
+            p.anomaly_note.as_deref(),
+            Some("Deferred due to anomaly (memory_leak): memory derived from anomalous usage")
+        );
+    }
+
+    #[test]
+    fn test_hold_keeps_previous_resource() {
+        let previous = profile(200, 256 << 20);
+        let mut p = profile(900, 512 << 20);
+        assert!(AnomalyGate::Hold.apply(&mut p, Some(&previous), &[DetectorKind::CpuSpike]));
+        assert_eq!(p.cpu_request_millicores, 200);
+        assert_eq!(p.cpu_limit_millicores, 400);
+        // Memory was not affected
+        assert_eq!(p.memory_request_bytes, 512 << 20);
+        assert!(p.anomaly_note.unwrap().contains("CPU kept"));
+
+        // Nothing to hold on to
+        let mut p = profile(900, 512 << 20);
+        assert!(!AnomalyGate::Hold.apply(&mut p, None, &[DetectorKind::CpuThrottling]));
+    }
+}
@@ -0,0 +1,252 @@
+//! Agent-side recommendation guardrails
+//!
+//! Bounds every profile must satisfy before it leaves the agent, so that a
+//! bad model can't produce a dangerous recommendation even before the API
+//! checks it against namespace policy.
+
+use crate::models::ResourceProfile;
+use serde::Serialize;
+
+/// Requests and limits currently set on a container, zero when unset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CurrentResources {
+    pub cpu_request_millicores: u32,
+    pub cpu_limit_millicores: u32,
+    pub memory_request_bytes: u64,
+    pub memory_limit_bytes: u64,
+}
+
+/// Rule that changed a profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailRule {
+    /// Limits raised to the observed p99 usage
+    P99Floor,
+    /// Requests held within `max_change` of the current ones
+    MaxChange,
+    /// CPU held within the configured bounds
+    CpuBounds,
+    /// Memory held within the configured bounds
+    MemoryBounds,
+}
+
+/// Configured recommendation bounds
+///
+/// Rules apply in order: the p99 floor, the change limit, then the absolute
+/// bounds, which take precedence over the others. Limits always end up at
+/// least as large as requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guardrails {
+    pub min_cpu_millicores: Option<u32>,
+    pub max_cpu_millicores: Option<u32>,
+    pub min_memory_bytes: Option<u64>,
+    pub max_memory_bytes: Option<u64>,
+    /// Largest relative change of requests from the current ones, e.g. 0.5
+    pub max_change: Option<f64>,
+    /// Never set limits below the observed p99 usage
+    pub never_below_p99: bool,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self {
+            min_cpu_millicores: None,
+            max_cpu_millicores: None,
+            min_memory_bytes: None,
+            max_memory_bytes: None,
+            max_change: None,
+            never_below_p99: true,
+        }
+    }
+}
+
+/// Clamp a request and limit to `[min, max]`, returning whether either
+/// changed
+fn clamp<'a, T: Copy + Ord + 'a>(
+    values: impl IntoIterator<Item = &'a mut T>,
+    min: Option<T>,
+    max: Option<T>,
+) -> bool {
+    let mut changed = false;
+    for value in values {
+        let mut clamped = *value;
+        if let Some(min) = min {
+            clamped = clamped.max(min);
+        }
+        if let Some(max) = max {
+            clamped = clamped.min(max);
+        }
+        changed |= clamped != *value;
+        *value = clamped;
+    }
+    changed
+}
+
+/// Hold a request within `max_change` of the current one, returning whether
+/// it changed
+fn limit_change(request: &mut f64, current: f64, max_change: f64) -> bool {
+    if current <= 0.0 {
+        return false;
+    }
+    let lo = (current * (1.0 - max_change)).max(0.0).ceil();
+    let hi = (current * (1.0 + max_change)).floor().max(lo);
+    let clamped = request.clamp(lo, hi);
+    let changed = clamped != *request;
+    *request = clamped;
+    changed
+}
+
+impl Guardrails {
+    /// Guardrails that only enforce the p99 floor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_cpu_bounds(mut self, min: Option<u32>, max: Option<u32>) -> Self {
+        self.min_cpu_millicores = min;
+        self.max_cpu_millicores = max;
+        self
+    }
+
+    pub fn with_memory_bounds(mut self, min: Option<u64>, max: Option<u64>) -> Self {
+        self.min_memory_bytes = min;
+        self.max_memory_bytes = max;
+        self
+    }
+
+    pub fn with_max_change(mut self, max_change: f64) -> Self {
+        self.max_change = Some(max_change);
+        self
+    }
+
+    /// Bring a profile within the guardrails
+    ///
+    /// `p99` is the observed p99 CPU (millicores) and memory (bytes) usage.
+    /// The change limit only applies when `current` requests are known.
+    /// Limits the profile leaves unset stay unset. Returns the rules that
+    /// changed the profile.
+    pub fn apply(
+        &self,
+        profile: &mut ResourceProfile,
+        p99: (u32, u64),
+        current: Option<CurrentResources>,
+    ) -> Vec<GuardrailRule> {
+        let mut applied = Vec::new();
+        let cpu_limited = !profile.cpu_limit_unset;
+        let memory_limited = !profile.memory_limit_unset;
+
+        if self.never_below_p99
+            && ((cpu_limited && profile.cpu_limit_millicores < p99.0)
+                || (memory_limited && profile.memory_limit_bytes < p99.1))
+        {
+            if cpu_limited {
+                profile.cpu_limit_millicores = profile.cpu_limit_millicores.max(p99.0);
+            }
+            if memory_limited {
+                profile.memory_limit_bytes = profile.memory_limit_bytes.max(p99.1);
+            }
+            applied.push(GuardrailRule::P99Floor);
+        }
+
+        if let (Some(max_change), Some(current)) = (self.max_change, current) {
+            let max_change = max_change.max(0.0);
+            let mut cpu = profile.cpu_request_millicores as f64;
+            let mut memory = profile.memory_request_bytes as f64;
+            let cpu_changed =
+                limit_change(&mut cpu, current.cpu_request_millicores as f64, max_change);
+            let memory_changed =
+                limit_change(&mut memory, current.memory_request_bytes as f64, max_change);
+            if cpu_changed || memory_changed {
+                profile.cpu_request_millicores = cpu as u32;
+                profile.memory_request_bytes = memory as u64;
+                applied.push(GuardrailRule::MaxChange);
+            }
+        }
+
+        let mut cpu = vec![&mut profile.cpu_request_millicores];
+        if cpu_limited {
+            cpu.push(&mut profile.cpu_limit_millicores);
+        }
+        if clamp(cpu, self.min_cpu_millicores, self.max_cpu_millicores) {
+            applied.push(GuardrailRule::CpuBounds);
+        }
+        let mut memory = vec![&mut profile.memory_request_bytes];
+        if memory_limited {
+            memory.push(&mut profile.memory_limit_bytes);
+        }
+        if clamp(memory, self.min_memory_bytes, self.max_memory_bytes) {
+            applied.push(GuardrailRule::MemoryBounds);
+        }
+
+        profile.raise_limits_to_requests();
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> ResourceProfile {
+        ResourceProfile {
+            cpu_request_millicores: 100,
+            cpu_limit_millicores: 200,
+            memory_request_bytes: 128 << 20,
+            memory_limit_bytes: 256 << 20,
+            confidence: 0.9,
+            model_version: "v1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_limits_never_below_p99() {
+        let mut p = profile();
+        let applied = Guardrails::new().apply(&mut p, (300, 200 << 20), None);
+        assert_eq!(applied, [GuardrailRule::P99Floor]);
+        assert_eq!(p.cpu_limit_millicores, 300);
+        assert_eq!(p.memory_limit_bytes, 256 << 20);
+
+        let mut p = profile();
+        assert!(Guardrails::new().apply(&mut p, (150, 0), None).is_empty());
+
+        // An unset CPU limit stays unset
+        let mut p = ResourceProfile {
+            cpu_limit_millicores: 0,
+            cpu_limit_unset: true,
+            ..profile()
+        };
+        let guardrails = Guardrails::new().with_cpu_bounds(Some(50), None);
+        assert!(guardrails.apply(&mut p, (300, 0), None).is_empty());
+        assert_eq!(p.cpu_limit_millicores, 0);
+    }
+
+    #[test]
+    fn test_change_limited_then_bounded() {
+        let current = CurrentResources {
+            cpu_request_millicores: 1000,
+            memory_request_bytes: 128 << 20,
+            ..Default::default()
+        };
+        let guardrails = Guardrails::new()
+            .with_max_change(0.5)
+            .with_cpu_bounds(Some(50), Some(400));
+
+        // A drop from 1000m to 100m is held at -50%, then capped at 400m
+        let mut p = profile();
+        let applied = guardrails.apply(&mut p, (0, 0), Some(current));
+        assert_eq!(
+            applied,
+            [GuardrailRule::MaxChange, GuardrailRule::CpuBounds]
+        );
+        assert_eq!(p.cpu_request_millicores, 400);
+        assert_eq!(p.cpu_limit_millicores, 400);
+        assert_eq!(p.memory_request_bytes, 128 << 20);
+
+        // Without current requests only the bounds apply
+        let mut p = profile();
+        let applied = guardrails.apply(&mut p, (0, 0), None);
+        assert!(applied.is_empty());
+        assert_eq!(p.cpu_request_millicores, 100);
+    }
+}
@@ -0,0 +1,234 @@
+//! Rate-of-change limits on consecutive recommendations
+//!
+//! A container is re-predicted every interval, and small swings in its
+//! usage can make consecutive profiles flap, which erodes trust in them.
+//! Each container's profile is held within `max_change` of an anchor, the
+//! profile it had when the current window began, so requests and limits
+//! move by at most ±20% per 24 hours by default. When the window ends the
+//! held profile becomes the new anchor. A profile at least as confident as
+//! `bypass_confidence` is passed on as is and anchors a new window.
+
+use crate::models::ResourceProfile;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default largest relative change within a window
+pub const DEFAULT_HYSTERESIS_MAX_CHANGE: f64 = 0.20;
+
+/// Default window the change is limited over
+pub const DEFAULT_HYSTERESIS_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Default confidence from which profiles are not held back
+pub const DEFAULT_HYSTERESIS_BYPASS_CONFIDENCE: f32 = 0.95;
+
+/// Limits on how fast a container's recommendation may change
+#[derive(Debug, Clone, PartialEq)]
+pub struct HysteresisConfig {
+    /// Largest relative change of requests and limits within a window
+    pub max_change: f64,
+    pub window: Duration,
+    /// Profiles at least this confident are never held back
+    pub bypass_confidence: f32,
+}
+
+impl Default for HysteresisConfig {
+    fn default() -> Self {
+        Self {
+            max_change: DEFAULT_HYSTERESIS_MAX_CHANGE,
+            window: DEFAULT_HYSTERESIS_WINDOW,
+            bypass_confidence: DEFAULT_HYSTERESIS_BYPASS_CONFIDENCE,
+        }
+    }
+}
+
+/// Profile a container's recommendations are held near
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HysteresisAnchor {
+    pub cpu_request_millicores: u32,
+    /// Zero when the anchored profile left the limit unset
+    pub cpu_limit_millicores: u32,
+    pub memory_request_bytes: u64,
+    /// Zero when the anchored profile left the limit unset
+    pub memory_limit_bytes: u64,
+    /// Start of the window (Unix seconds)
+    pub since: i64,
+}
+
+impl HysteresisAnchor {
+    fn of(profile: &ResourceProfile, since: i64) -> Self {
+        Self {
+            cpu_request_millicores: profile.cpu_request_millicores,
+            cpu_limit_millicores: if profile.cpu_limit_unset {
+                0
+            } else {
+                profile.cpu_limit_millicores
+            },
+            memory_request_bytes: profile.memory_request_bytes,
+            memory_limit_bytes: if profile.memory_limit_unset {
+                0
+            } else {
+                profile.memory_limit_bytes
+            },
+            since,
+        }
+    }
+}
+
+/// Hold a value within `max_change` of the anchored one, returning whether
+/// it changed
+///
+/// Nothing is held against an anchor of zero, i.e. an unset limit.
+fn hold(value: &mut f64, anchor: f64, max_change: f64) -> bool {
+    if anchor <= 0.0 {
+        return false;
+    }
+    let lo = (anchor * (1.0 - max_change)).max(0.0).ceil();
+    let hi = (anchor * (1.0 + max_change)).floor().max(lo);
+    let held = value.clamp(lo, hi);
+    let changed = held != *value;
+    *value = held;
+    changed
+}
+
+impl HysteresisConfig {
+    /// Hold `profile` within the allowed change from `anchor`
+    ///
+    /// `now` is the time the profile was predicted for (Unix seconds). The
+    /// first profile of a container, and any profile confident enough to
+    /// bypass the limit, replaces the anchor unchanged. Limits the profile
+    /// leaves unset stay unset. Returns whether the profile was changed.
+    pub fn apply(
+        &self,
+        anchor: &mut Option<HysteresisAnchor>,
+        profile: &mut ResourceProfile,
+        now: i64,
+    ) -> bool {
+        let current = match anchor {
+            Some(current) if profile.confidence < self.bypass_confidence => current,
+            _ => {
+                *anchor = Some(HysteresisAnchor::of(profile, now));
+                return false;
+            }
+        };
+
+        let max_change = self.max_change.max(0.0);
+        let mut changed = false;
+        let mut cpu_request = profile.cpu_request_millicores as f64;
+        changed |= hold(
+            &mut cpu_request,
+            current.cpu_request_millicores as f64,
+            max_change,
+        );
+        profile.cpu_request_millicores = cpu_request as u32;
+        let mut memory_request = profile.memory_request_bytes as f64;
+        changed |= hold(
+            &mut memory_request,
+            current.memory_request_bytes as f64,
+            max_change,
+        );
+        profile.memory_request_bytes = memory_request as u64;
+        if !profile.cpu_limit_unset {
+            let mut cpu_limit = profile.cpu_limit_millicores as f64;
+            changed |= hold(
+                &mut cpu_limit,
+                current.cpu_limit_millicores as f64,
+                max_change,
+            );
+            profile.cpu_limit_millicores = cpu_limit as u32;
+        }
+        if !profile.memory_limit_unset {
+            let mut memory_limit = profile.memory_limit_bytes as f64;
+            changed |= hold(
+                &mut memory_limit,
+                current.memory_limit_bytes as f64,
+                max_change,
+            );
+            profile.memory_limit_bytes = memory_limit as u64;
+        }
+        profile.raise_limits_to_requests();
+
+        if now - current.since >= self.window.as_secs() as i64 {
+            *current = HysteresisAnchor::of(profile, now);
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn profile(cpu_request: u32, confidence: f32) -> ResourceProfile {
+        ResourceProfile {
+            cpu_request_millicores: cpu_request,
+            cpu_limit_millicores: cpu_request * 2,
+            memory_request_bytes: 100 << 20,
+            memory_limit_bytes: 200 << 20,
+            confidence,
+            model_version: "v1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_change_held_within_window() {
+        let config = HysteresisConfig::default();
+        let mut anchor = None;
+
+        let mut p = profile(1000, 0.8);
+        assert!(!config.apply(&mut anchor, &mut p, 0));
+
+        // Flapping between 500m and 1500m stays within ±20% of 1000m
+        let mut p = profile(500, 0.8);
+        assert!(config.apply(&mut anchor, &mut p, 60));
+        assert_eq!(p.cpu_request_millicores, 800);
+        assert_eq!(p.cpu_limit_millicores, 1600);
+        assert_eq!(p.memory_request_bytes, 100 << 20);
+        let mut p = profile(1500, 0.8);
+        assert!(config.apply(&mut anchor, &mut p, 120));
+        assert_eq!(p.cpu_request_millicores, 1200);
+
+        // Once the window is over the held profile anchors the next one
+        let mut p = profile(500, 0.8);
+        config.apply(&mut anchor, &mut p, DAY);
+        assert_eq!(p.cpu_request_millicores, 800);
+        let mut p = profile(500, 0.8);
+        config.apply(&mut anchor, &mut p, DAY + 60);
+        assert_eq!(p.cpu_request_millicores, 640);
+    }
+
+    #[test]
+    fn test_confident_profile_bypasses_limit() {
+        let config = HysteresisConfig::default();
+        let mut anchor = None;
+        config.apply(&mut anchor, &mut profile(1000, 0.8), 0);
+
+        let mut p = profile(300, 0.97);
+        assert!(!config.apply(&mut anchor, &mut p, 60));
+        assert_eq!(p.cpu_request_millicores, 300);
+
+        // ...and anchors the following ones
+        let mut p = profile(1000, 0.8);
+        config.apply(&mut anchor, &mut p, 120);
+        assert_eq!(p.cpu_request_millicores, 360);
+    }
+
+    #[test]
+    fn test_unset_limit_stays_unset() {
+        let config = HysteresisConfig::default();
+        let unset = |cpu_request| ResourceProfile {
+            cpu_limit_millicores: 0,
+            cpu_limit_unset: true,
+            ..profile(cpu_request, 0.8)
+        };
+        let mut anchor = None;
+        config.apply(&mut anchor, &mut unset(1000), 0);
+
+        let mut p = unset(2000);
+        assert!(config.apply(&mut anchor, &mut p, 60));
+        assert_eq!(p.cpu_request_millicores, 1200);
+        assert_eq!(p.cpu_limit_millicores, 0);
+    }
+}
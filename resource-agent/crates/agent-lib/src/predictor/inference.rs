@@ -1,28 +1,38 @@
 //! ONNX Runtime inference using tract
 //!
 //! Provides lightweight ML inference for resource prediction using
-//! quantized int8 models loaded via tract-onnx.
+//! quantized int8 models loaded via tract-onnx. Without the `onnx` feature
+//! no model can be loaded and predictions always use the fallback.
 
 use super::output::OutputFormatter;
-use super::Predictor;
+use super::{PredictError, Predictor};
 use crate::models::{FeatureVector, ResourceProfile};
-use anyhow::{Context, Result};
+#[cfg(feature = "onnx")]
+use anyhow::Context;
 use std::sync::RwLock;
 use std::time::Instant;
 use tracing::{debug, warn};
+#[cfg(feature = "onnx")]
 use tract_onnx::prelude::*;
 
 /// Number of input features expected by the model
+#[cfg(feature = "onnx")]
 const NUM_FEATURES: usize = 12;
 
 /// Number of output values from the model
+#[cfg(feature = "onnx")]
 const NUM_OUTPUTS: usize = 5;
 
 /// Maximum inference latency before warning (5ms target)
 const MAX_INFERENCE_MS: u128 = 5;
 
+#[cfg(feature = "onnx")]
 type TractModel = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
 
+/// No model can be loaded without the `onnx` feature
+#[cfg(not(feature = "onnx"))]
+type TractModel = std::convert::Infallible;
+
 /// ONNX-based predictor using tract for lightweight inference
 pub struct OnnxPredictor {
     model: RwLock<Option<TractModel>>,
@@ -45,7 +55,7 @@ impl OnnxPredictor {
     }
 
     /// Create a new predictor from model bytes
-    pub fn new(model_bytes: &[u8]) -> Result<Self> {
+    pub fn new(model_bytes: &[u8]) -> Result<Self, PredictError> {
         let model = Self::load_model(model_bytes)?;
         Ok(Self {
             model: RwLock::new(Some(model)),
@@ -57,51 +67,84 @@ impl OnnxPredictor {
     }
 
     /// Load and optimize an ONNX model from bytes
-    fn load_model(model_bytes: &[u8]) -> Result<TractModel> {
-        let model = tract_onnx::onnx()
-            .model_for_read(&mut std::io::Cursor::new(model_bytes))
-            .context("Failed to parse ONNX model")?
-            .with_input_fact(0, f32::fact([1, NUM_FEATURES]).into())
-            .context("Failed to set input shape")?
-            .into_optimized()
-            .context("Failed to optimize model")?
-            .into_runnable()
-            .context("Failed to create runnable model")?;
-        Ok(model)
+    #[cfg(feature = "onnx")]
+    fn load_model(model_bytes: &[u8]) -> Result<TractModel, PredictError> {
+        let load = || -> anyhow::Result<TractModel> {
+            tract_onnx::onnx()
+                .model_for_read(&mut std::io::Cursor::new(model_bytes))
+                .context("Failed to parse ONNX model")?
+                .with_input_fact(0, f32::fact([1, NUM_FEATURES]).into())
+                .context("Failed to set input shape")?
+                .into_optimized()
+                .context("Failed to optimize model")?
+                .into_runnable()
+                .context("Failed to create runnable model")
+        };
+        load().map_err(PredictError::InvalidModel)
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn load_model(_model_bytes: &[u8]) -> Result<TractModel, PredictError> {
+        Err(PredictError::InvalidModel(anyhow::anyhow!(
+            "ONNX inference requires the `onnx` feature"
+        )))
+    }
+
+    /// Run the model on a feature vector
+    #[cfg(feature = "onnx")]
+    fn infer(
+        &self,
+        model: &TractModel,
+        features: &FeatureVector,
+        model_version: &str,
+    ) -> Result<ResourceProfile, PredictError> {
+        let input = self.features_to_tensor(features);
+        let result = model
+            .run(tvec!(input.into()))
+            .map_err(PredictError::Inference)?;
+        let output = result.first().ok_or(PredictError::UnexpectedOutput {
+            values: 0,
+            expected: NUM_OUTPUTS,
+        })?;
+        self.tensor_to_profile(output, model_version)
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn infer(
+        &self,
+        model: &TractModel,
+        _features: &FeatureVector,
+        _model_version: &str,
+    ) -> Result<ResourceProfile, PredictError> {
+        match *model {}
     }
 
     /// Convert feature vector to tensor input
+    #[cfg(feature = "onnx")]
     fn features_to_tensor(&self, features: &FeatureVector) -> Tensor {
-        let data = vec![
-            features.cpu_usage_p50,
-            features.cpu_usage_p95,
-            features.cpu_usage_p99,
-            features.mem_usage_p50,
-            features.mem_usage_p95,
-            features.mem_usage_p99,
-            features.cpu_variance,
-            features.mem_trend,
-            features.throttle_ratio,
-            features.hour_of_day,
-            features.day_of_week,
-            features.workload_age_days,
-        ];
+        let data = features.model_input();
         tract_ndarray::Array2::from_shape_vec((1, NUM_FEATURES), data)
             .unwrap()
             .into()
     }
 
     /// Convert model output tensor to ResourceProfile
-    fn tensor_to_profile(&self, output: &Tensor, model_version: &str) -> Result<ResourceProfile> {
-        let output_view = output.to_array_view::<f32>()?;
+    #[cfg(feature = "onnx")]
+    fn tensor_to_profile(
+        &self,
+        output: &Tensor,
+        model_version: &str,
+    ) -> Result<ResourceProfile, PredictError> {
+        let output_view = output
+            .to_array_view::<f32>()
+            .map_err(PredictError::Inference)?;
         let values: Vec<f32> = output_view.iter().copied().collect();
 
         if values.len() < NUM_OUTPUTS {
-            anyhow::bail!(
-                "Model output has {} values, expected {}",
-                values.len(),
-                NUM_OUTPUTS
-            );
+            return Err(PredictError::UnexpectedOutput {
+                values: values.len(),
+                expected: NUM_OUTPUTS,
+            });
         }
 
         // Use OutputFormatter to apply memory buffer and format output
@@ -133,13 +176,11 @@ impl OnnxPredictor {
 }
 
 impl Predictor for OnnxPredictor {
-    fn predict(&self, features: &FeatureVector) -> Result<ResourceProfile> {
+    #[tracing::instrument(name = "inference", skip_all)]
+    fn predict(&self, features: &FeatureVector) -> Result<ResourceProfile, PredictError> {
         let start = Instant::now();
 
-        let model_guard = self
-            .model
-            .read()
-            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        let model_guard = self.model.read().map_err(|_| PredictError::LockPoisoned)?;
 
         // If no model loaded, use fallback
         let model = match model_guard.as_ref() {
@@ -153,11 +194,8 @@ impl Predictor for OnnxPredictor {
         let version = self
             .model_version
             .read()
-            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
-        let input = self.features_to_tensor(features);
-
-        let result = model.run(tvec!(input.into()))?;
-        let output = result.first().context("No output from model")?;
+            .map_err(|_| PredictError::LockPoisoned)?;
+        let profile = self.infer(model, features, &version)?;
 
         let elapsed = start.elapsed();
         self.inference_count
@@ -174,19 +212,16 @@ impl Predictor for OnnxPredictor {
             debug!(elapsed_us = elapsed.as_micros(), "Inference completed");
         }
 
-        self.tensor_to_profile(output, &version)
+        Ok(profile)
     }
 
-    fn update_model(&mut self, weights: &[u8]) -> Result<()> {
+    fn update_model(&mut self, weights: &[u8]) -> Result<(), PredictError> {
         let new_model = Self::load_model(weights)?;
-        let mut model = self
-            .model
-            .write()
-            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        let mut model = self.model.write().map_err(|_| PredictError::LockPoisoned)?;
         let mut version = self
             .model_version
             .write()
-            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+            .map_err(|_| PredictError::LockPoisoned)?;
 
         *model = Some(new_model);
         // Increment version - in production this would come from model metadata
@@ -1,28 +1,78 @@
 //! ML prediction engine
 
+#[cfg(feature = "backfill")]
+mod backfill;
+mod batch;
+mod calibration;
+mod checkpoint;
+mod deviation;
+mod error;
 mod features;
+mod fingerprint;
+mod gating;
+mod guardrails;
+mod hysteresis;
 mod inference;
 mod output;
+mod pod;
+mod resample;
 mod scheduler;
+mod series;
+mod shadow;
 
+#[cfg(feature = "backfill")]
+pub use backfill::{
+    BackfillConfig, BackfillStats, Backfiller, DEFAULT_BACKFILL_LOOKBACK, DEFAULT_BACKFILL_STEP,
+};
+pub use batch::{
+    BatchConfig, BatchPredictor, RunSummary, BATCH_MODEL_VERSION, BATCH_PEAK_BUFFER,
+    DEFAULT_MAX_RUNS,
+};
+pub use calibration::{
+    Calibration, CalibrationConfig, CalibrationMethod, CalibrationStats, ConfidenceCalibrator,
+    DEFAULT_CALIBRATION_HORIZON, DEFAULT_MAX_CALIBRATION_OBSERVATIONS,
+    DEFAULT_MIN_CALIBRATION_OBSERVATIONS,
+};
+pub use checkpoint::{
+    CheckpointConfig, CHECKPOINT_VERSION, DEFAULT_CHECKPOINT_INTERVAL, DEFAULT_CHECKPOINT_MAX_AGE,
+};
+pub use deviation::{
+    DeviationCollector, DeviationConfig, DeviationStats, TrainingSample, DEFAULT_DEVIATION_HORIZON,
+    DEFAULT_DEVIATION_SAMPLE_RATE, DEFAULT_MAX_TRAINING_SAMPLES, DEFAULT_TRAINING_UPLOAD_INTERVAL,
+};
+pub use error::PredictError;
 pub use features::{linear_regression_slope, FeatureExtractor, MIN_SAMPLES};
+pub use fingerprint::{WorkloadFingerprint, FINGERPRINT_PERIODS};
+pub use gating::{AnomalyGate, DEFAULT_ANOMALY_TAINT_WINDOW};
+pub use guardrails::{CurrentResources, GuardrailRule, Guardrails};
+pub use hysteresis::{
+    HysteresisAnchor, HysteresisConfig, DEFAULT_HYSTERESIS_BYPASS_CONFIDENCE,
+    DEFAULT_HYSTERESIS_MAX_CHANGE, DEFAULT_HYSTERESIS_WINDOW,
+};
 pub use inference::{FallbackPredictor, InferenceStats, OnnxPredictor};
-pub use output::{OutputConfig, OutputFormatter, MEMORY_BUFFER_PERCENT};
+pub use output::{
+    BurstLimit, CpuPolicy, CpuStrategy, MemoryPolicy, MemoryStrategy, NamespacePolicy,
+    OutputConfig, OutputFormatter, QosClass, SustainedPercentile, BURST_LIMIT_HEADROOM,
+    MAX_HPA_REQUEST_CHANGE, MEMORY_BUFFER_PERCENT, NODE_PRESSURE_HEADROOM, NODE_PRESSURE_THRESHOLD,
+};
+pub use pod::PodAggregator;
+pub use resample::{Gap, Resampled, Resampler, DEFAULT_MAX_FILL_GAP, DEFAULT_RESAMPLE_STEP};
 pub use scheduler::{
-    PredictionConfig, PredictionResult, PredictionScheduler, SchedulerStats,
-    DEFAULT_PREDICTION_INTERVAL, INFERENCE_TIMEOUT,
+    ContainerPrediction, PredictionConfig, PredictionResult, PredictionScheduler, SchedulerStats,
+    DEFAULT_PREDICTION_INTERVAL, DEFAULT_STALE_AFTER, INFERENCE_TIMEOUT,
 };
+pub use series::{SampleColumns, SampleSeries, SeriesView, MAX_SERIES_SAMPLES};
+pub use shadow::{profile_deviation, ShadowModel, ShadowSlot, ShadowStats};
 
 use crate::models::{FeatureVector, ResourceProfile};
-use anyhow::Result;
 
 /// Trait for prediction implementations
 pub trait Predictor: Send + Sync {
     /// Generate resource profile prediction from features
-    fn predict(&self, features: &FeatureVector) -> Result<ResourceProfile>;
+    fn predict(&self, features: &FeatureVector) -> Result<ResourceProfile, PredictError>;
 
     /// Update model weights
-    fn update_model(&mut self, weights: &[u8]) -> Result<()>;
+    fn update_model(&mut self, weights: &[u8]) -> Result<(), PredictError>;
 
     /// Get current model version
     fn model_version(&self) -> &str;
@@ -1,9 +1,16 @@
 //! Prediction output formatting and post-processing
 //!
 //! Handles conversion of raw model outputs to ResourceProfile with
-//! safety margins and confidence scoring.
+//! safety margins and confidence scoring, adjustment of CPU requests for
+//! workloads scaled by an HPA, limits on how fast recommendations change,
+//! enforcement of guardrails, preservation of the container's QoS class,
+//! and the per-namespace CPU and memory strategies.
 
-use crate::models::ResourceProfile;
+use super::guardrails::{CurrentResources, GuardrailRule, Guardrails};
+use super::hysteresis::{HysteresisAnchor, HysteresisConfig};
+use crate::models::{DisruptionBudget, FeatureVector, HpaTarget, ResourceProfile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Memory safety buffer percentage (20% as per requirement 3.7)
 pub const MEMORY_BUFFER_PERCENT: f64 = 0.20;
@@ -20,6 +27,137 @@ pub const MAX_CPU_CORES: f32 = 16.0;
 /// Maximum memory for normalization (64GB)
 pub const MAX_MEMORY_GB: f64 = 64.0;
 
+/// CPU request change beyond which HPA interaction is flagged (25%)
+pub const MAX_HPA_REQUEST_CHANGE: f64 = 0.25;
+
+/// Node pressure above which requests get extra headroom
+pub const NODE_PRESSURE_THRESHOLD: f32 = 0.8;
+
+/// Largest extra headroom added to requests on a pressured node (20%)
+pub const NODE_PRESSURE_HEADROOM: f64 = 0.20;
+
+/// Headroom added to the observed CPU peak for burst limits (10%)
+pub const BURST_LIMIT_HEADROOM: f64 = 0.10;
+
+/// Sustained usage percentile that burst CPU requests are set from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SustainedPercentile {
+    P50,
+    #[default]
+    P95,
+}
+
+/// How burst CPU limits are set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurstLimit {
+    /// Observed short-window peak plus `BURST_LIMIT_HEADROOM`
+    #[default]
+    Peak,
+    /// No CPU limit, so the container can use idle CPU on the node
+    Unlimited,
+}
+
+/// How CPU requests and limits are recommended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum CpuStrategy {
+    /// Requests and limits as predicted by the model
+    #[default]
+    Model,
+    /// Requests from sustained usage, limits decoupled from them
+    Burst {
+        #[serde(default)]
+        request: SustainedPercentile,
+        #[serde(default)]
+        limit: BurstLimit,
+    },
+}
+
+/// Whether memory limits are recommended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryStrategy {
+    /// Requests and limits as predicted by the model
+    #[default]
+    Model,
+    /// Requests only, for tiers that run without memory limits
+    RequestOnly,
+}
+
+/// Strategy of each namespace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamespacePolicy<T> {
+    /// Strategy of namespaces not listed in `namespaces`
+    pub default: T,
+    pub namespaces: HashMap<String, T>,
+}
+
+impl<T: Copy> NamespacePolicy<T> {
+    /// Strategy for containers in `namespace`
+    pub fn strategy(&self, namespace: &str) -> T {
+        self.namespaces
+            .get(namespace)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// CPU strategy of each namespace
+pub type CpuPolicy = NamespacePolicy<CpuStrategy>;
+
+/// Memory strategy of each namespace
+pub type MemoryPolicy = NamespacePolicy<MemoryStrategy>;
+
+/// Kubernetes QoS class of a container's resources
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QosClass {
+    /// CPU and memory limits set, and equal to the requests
+    Guaranteed,
+    /// Some request or limit set
+    Burstable,
+    /// No requests or limits
+    BestEffort,
+}
+
+impl QosClass {
+    /// Class of a container's requests and limits, zero when unset
+    ///
+    /// Kubernetes defaults an unset request to its limit, so a container
+    /// with only limits is Guaranteed.
+    pub fn of(cpu: (u32, u32), memory: (u64, u64)) -> Self {
+        let (cpu_request, cpu_limit) = cpu;
+        let (memory_request, memory_limit) = memory;
+        if cpu_request == 0 && cpu_limit == 0 && memory_request == 0 && memory_limit == 0 {
+            return Self::BestEffort;
+        }
+        let cpu_guaranteed = cpu_limit > 0 && (cpu_request == 0 || cpu_request == cpu_limit);
+        let memory_guaranteed =
+            memory_limit > 0 && (memory_request == 0 || memory_request == memory_limit);
+        if cpu_guaranteed && memory_guaranteed {
+            Self::Guaranteed
+        } else {
+            Self::Burstable
+        }
+    }
+
+    fn of_profile(profile: &ResourceProfile) -> Self {
+        Self::of(
+            (profile.cpu_request_millicores, profile.cpu_limit_millicores),
+            (profile.memory_request_bytes, profile.memory_limit_bytes),
+        )
+    }
+
+    fn of_current(current: &CurrentResources) -> Self {
+        Self::of(
+            (current.cpu_request_millicores, current.cpu_limit_millicores),
+            (current.memory_request_bytes, current.memory_limit_bytes),
+        )
+    }
+}
+
 /// Configuration for output formatting
 #[derive(Debug, Clone)]
 pub struct OutputConfig {
@@ -31,6 +169,23 @@ pub struct OutputConfig {
     pub min_cpu_millicores: u32,
     /// Low confidence threshold
     pub low_confidence_threshold: f32,
+    /// Relative CPU request change flagged for HPA-scaled workloads
+    pub max_hpa_request_change: f64,
+    /// Node pressure above which requests get extra headroom
+    pub node_pressure_threshold: f32,
+    /// Extra headroom added to requests at full node pressure
+    pub node_pressure_headroom: f64,
+    /// Limits on how fast a container's recommendation changes, None to
+    /// pass every prediction on as is
+    pub hysteresis: Option<HysteresisConfig>,
+    /// Bounds every profile is brought within
+    pub guardrails: Guardrails,
+    /// Keep Guaranteed containers Guaranteed by raising requests to limits
+    pub preserve_qos_class: bool,
+    /// How CPU requests and limits are set in each namespace
+    pub cpu_policy: CpuPolicy,
+    /// Whether memory limits are set in each namespace
+    pub memory_policy: MemoryPolicy,
 }
 
 impl Default for OutputConfig {
@@ -40,6 +195,14 @@ impl Default for OutputConfig {
             min_memory_bytes: MIN_MEMORY_BYTES,
             min_cpu_millicores: MIN_CPU_MILLICORES,
             low_confidence_threshold: 0.7,
+            max_hpa_request_change: MAX_HPA_REQUEST_CHANGE,
+            node_pressure_threshold: NODE_PRESSURE_THRESHOLD,
+            node_pressure_headroom: NODE_PRESSURE_HEADROOM,
+            hysteresis: Some(HysteresisConfig::default()),
+            guardrails: Guardrails::default(),
+            preserve_qos_class: false,
+            cpu_policy: CpuPolicy::default(),
+            memory_policy: MemoryPolicy::default(),
         }
     }
 }
@@ -94,7 +257,234 @@ impl OutputFormatter {
             confidence,
             model_version: model_version.to_string(),
             generated_at: chrono::Utc::now().timestamp(),
+            ..Default::default()
+        }
+    }
+
+    /// Adjust a profile for the HPA scaling its workload on CPU utilization
+    ///
+    /// The HPA measures utilization against the CPU request, so at the same
+    /// load a new request moves the replica count it converges to by the
+    /// ratio of the old request to the new one. A request that would need
+    /// more than `max_replicas` is raised to the lowest one that fits; other
+    /// changes above `max_hpa_request_change` are only flagged. Either way
+    /// the explanation is stored in `hpa_note`.
+    pub fn apply_hpa(
+        &self,
+        profile: &mut ResourceProfile,
+        hpa: &HpaTarget,
+        current_cpu_request_millicores: u32,
+    ) {
+        if current_cpu_request_millicores == 0 || hpa.current_replicas == 0 {
+            return;
+        }
+        let current = current_cpu_request_millicores as f64;
+        let replicas = hpa.current_replicas as f64;
+        let proposed = profile.cpu_request_millicores;
+        let needed = (replicas * current / proposed.max(1) as f64).ceil() as u32;
+        if needed > hpa.max_replicas && hpa.max_replicas > 0 {
+            let floor = (current * replicas / hpa.max_replicas as f64).ceil() as u32;
+            profile.cpu_request_millicores = floor.max(proposed);
+            profile.raise_limits_to_requests();
+            profile.hpa_note = Some(format!(
+                "CPU request raised from {}m to {}m: at {}m HPA {} would need {} replicas, \
+                 above its maximum of {}",
+                proposed,
+                profile.cpu_request_millicores,
+                proposed,
+                hpa.name,
+                needed,
+                hpa.max_replicas
+            ));
+            return;
+        }
+
+        let change = proposed as f64 / current - 1.0;
+        if change.abs() > self.config.max_hpa_request_change {
+            let target = hpa.target_cpu_utilization_percent;
+            let utilization = (target as f64 * current / proposed.max(1) as f64).round();
+            let expected = needed.clamp(hpa.min_replicas, hpa.max_replicas.max(hpa.min_replicas));
+            profile.hpa_note = Some(format!(
+                "CPU request change of {:+.0}% moves HPA {} utilization to {}% of its {}% \
+                 target; expect about {} replicas instead of {}",
+                change * 100.0,
+                hpa.name,
+                utilization,
+                target,
+                expected,
+                hpa.current_replicas
+            ));
+        }
+    }
+
+    /// Add headroom to requests of a container on a pressured node
+    ///
+    /// On a chronically overcommitted node, usage observed so far may be
+    /// held back by contention. Above `node_pressure_threshold`, requests
+    /// are raised by up to `node_pressure_headroom`, in proportion to how
+    /// far pressure is above the threshold.
+    pub fn apply_node_pressure(&self, profile: &mut ResourceProfile, node_pressure: f32) {
+        let threshold = self.config.node_pressure_threshold;
+        if node_pressure <= threshold || threshold >= 1.0 {
+            return;
+        }
+        let excess = ((node_pressure - threshold) / (1.0 - threshold)).min(1.0) as f64;
+        let factor = 1.0 + self.config.node_pressure_headroom * excess;
+
+        profile.cpu_request_millicores =
+            (profile.cpu_request_millicores as f64 * factor).ceil() as u32;
+        profile.memory_request_bytes = (profile.memory_request_bytes as f64 * factor).ceil() as u64;
+        profile.raise_limits_to_requests();
+    }
+
+    /// Set CPU requests and limits with the strategy of `namespace`
+    ///
+    /// Under the burst strategy the request follows sustained usage from
+    /// `features` and the limit follows `peak_cpu_cores`, the highest usage
+    /// over a single sample interval, or is removed, which marks the profile
+    /// `cpu_limit_unset` for later steps to leave alone.
+    pub fn apply_cpu_strategy(
+        &self,
+        profile: &mut ResourceProfile,
+        features: &FeatureVector,
+        peak_cpu_cores: f32,
+        namespace: &str,
+    ) {
+        let CpuStrategy::Burst { request, limit } = self.config.cpu_policy.strategy(namespace)
+        else {
+            return;
+        };
+        let sustained = match request {
+            SustainedPercentile::P50 => features.cpu_usage_p50,
+            SustainedPercentile::P95 => features.cpu_usage_p95,
+        };
+        profile.cpu_request_millicores = self
+            .denormalize_cpu(sustained)
+            .max(self.config.min_cpu_millicores);
+        profile.cpu_limit_unset = limit == BurstLimit::Unlimited;
+        profile.cpu_limit_millicores = match limit {
+            BurstLimit::Peak => {
+                let peak = peak_cpu_cores.max(0.0) as f64 * 1000.0;
+                ((peak * (1.0 + BURST_LIMIT_HEADROOM)).round() as u32)
+                    .max(profile.cpu_request_millicores)
+            }
+            BurstLimit::Unlimited => 0,
+        };
+    }
+
+    /// Drop the memory limit where the policy of `namespace` is requests only
+    pub fn apply_memory_strategy(&self, profile: &mut ResourceProfile, namespace: &str) {
+        if self.config.memory_policy.strategy(namespace) == MemoryStrategy::RequestOnly {
+            profile.memory_limit_bytes = 0;
+            profile.memory_limit_unset = true;
+        }
+    }
+
+    /// Hold a profile within the configured rate of change from the
+    /// container's `anchor`, returning whether it was changed
+    ///
+    /// `now` is the time the profile was predicted for (Unix seconds).
+    pub fn apply_hysteresis(
+        &self,
+        anchor: &mut Option<HysteresisAnchor>,
+        profile: &mut ResourceProfile,
+        now: i64,
+    ) -> bool {
+        match &self.config.hysteresis {
+            Some(hysteresis) => hysteresis.apply(anchor, profile, now),
+            None => false,
+        }
+    }
+
+    /// Bring a profile within the configured guardrails
+    ///
+    /// The p99 floor uses the usage percentiles of `features`. Returns the
+    /// rules that changed the profile.
+    pub fn apply_guardrails(
+        &self,
+        profile: &mut ResourceProfile,
+        features: &FeatureVector,
+        current: Option<CurrentResources>,
+    ) -> Vec<GuardrailRule> {
+        let p99 = (
+            self.denormalize_cpu(features.cpu_usage_p99),
+            self.denormalize_memory(features.mem_usage_p99),
+        );
+        self.config.guardrails.apply(profile, p99, current)
+    }
+
+    /// Preserve or flag a change of the container's QoS class
+    ///
+    /// Recommendations always set requests and limits, and set them apart
+    /// unless the model predicts no headroom, which silently moves
+    /// Guaranteed containers to Burstable and so up the eviction order.
+    /// With `preserve_qos_class` the requests of a Guaranteed container are
+    /// raised to the recommended limits. Any remaining change of class is
+    /// explained in `qos_note`.
+    pub fn apply_qos(&self, profile: &mut ResourceProfile, current: &CurrentResources) {
+        let before = QosClass::of_current(current);
+        if self.config.preserve_qos_class && before == QosClass::Guaranteed {
+            // Guaranteed needs limits, even where the policy removes them
+            if profile.cpu_limit_unset {
+                profile.cpu_limit_millicores = profile.cpu_request_millicores;
+                profile.cpu_limit_unset = false;
+            }
+            if profile.memory_limit_unset {
+                profile.memory_limit_bytes = profile.memory_request_bytes;
+                profile.memory_limit_unset = false;
+            }
+            profile.cpu_request_millicores = profile.cpu_limit_millicores;
+            profile.memory_request_bytes = profile.memory_limit_bytes;
         }
+        let after = QosClass::of_profile(profile);
+        profile.qos_note = (after != before)
+            .then(|| format!("Changes QoS class from {:?} to {:?}", before, after));
+    }
+
+    /// Flag recommendations whose rollout would exceed a disruption budget
+    ///
+    /// Applying changed resources restarts every pod of the workload. When
+    /// the recommendation differs from `current` (or `current` is unknown),
+    /// the tightest PodDisruptionBudget selecting the pods is explained in
+    /// `disruption_note`, so restarts can be staggered to stay within it.
+    pub fn apply_disruption_budgets(
+        &self,
+        profile: &mut ResourceProfile,
+        budgets: &[DisruptionBudget],
+        current: Option<&CurrentResources>,
+    ) {
+        let restarts = current.map_or(true, |current| {
+            let limit = |value: u64, unset: bool| if unset { 0 } else { value };
+            (
+                current.cpu_request_millicores,
+                current.cpu_limit_millicores as u64,
+                current.memory_request_bytes,
+                current.memory_limit_bytes,
+            ) != (
+                profile.cpu_request_millicores,
+                limit(profile.cpu_limit_millicores as u64, profile.cpu_limit_unset),
+                profile.memory_request_bytes,
+                limit(profile.memory_limit_bytes, profile.memory_limit_unset),
+            )
+        });
+        let tightest = budgets
+            .iter()
+            .min_by_key(|budget| budget.disruptions_allowed);
+        profile.disruption_note = restarts
+            .then_some(tightest)
+            .flatten()
+            .map(|budget| match budget.disruptions_allowed {
+                0 => format!(
+                    "PodDisruptionBudget {} allows no disruptions ({}/{} healthy); \
+                     restarts must wait until it does",
+                    budget.name, budget.current_healthy, budget.desired_healthy
+                ),
+                allowed => format!(
+                    "PodDisruptionBudget {} allows {} disruption(s); stagger restarts \
+                     in batches of at most {}",
+                    budget.name, allowed, allowed
+                ),
+            });
     }
 
     /// Denormalize CPU value from 0-1 to millicores
@@ -215,6 +605,285 @@ mod tests {
         assert!(formatter.low_confidence_reason(&profile).is_some());
     }
 
+    fn hpa() -> HpaTarget {
+        HpaTarget {
+            name: "api".to_string(),
+            min_replicas: 2,
+            max_replicas: 10,
+            current_replicas: 4,
+            target_cpu_utilization_percent: 70,
+        }
+    }
+
+    #[test]
+    fn test_hpa_raises_request_within_max_replicas() {
+        let formatter = OutputFormatter::new();
+        let mut profile = formatter.format(&[0.0, 0.0, 0.1, 0.2, 0.9], "v1.0.0");
+        profile.cpu_request_millicores = 100;
+        profile.cpu_limit_millicores = 200;
+
+        // 4 replicas at 500m would need 20 replicas at 100m
+        formatter.apply_hpa(&mut profile, &hpa(), 500);
+        assert_eq!(profile.cpu_request_millicores, 200);
+        assert_eq!(profile.cpu_limit_millicores, 200);
+        assert!(profile
+            .hpa_note
+            .unwrap()
+            .contains("above its maximum of 10"));
+    }
+
+    #[test]
+    fn test_hpa_flags_large_request_change() {
+        let formatter = OutputFormatter::new();
+        let mut profile = formatter.format(&[0.0, 0.0, 0.1, 0.2, 0.9], "v1.0.0");
+        profile.cpu_request_millicores = 250;
+
+        formatter.apply_hpa(&mut profile, &hpa(), 500);
+        assert_eq!(profile.cpu_request_millicores, 250);
+        let note = profile.hpa_note.unwrap();
+        assert!(note.contains("-50%"), "{}", note);
+        assert!(note.contains("140% of its 70% target"), "{}", note);
+        assert!(note.contains("about 8 replicas instead of 4"), "{}", note);
+    }
+
+    #[test]
+    fn test_hpa_small_change_not_flagged() {
+        let formatter = OutputFormatter::new();
+        let mut profile = formatter.format(&[0.0, 0.0, 0.1, 0.2, 0.9], "v1.0.0");
+        profile.cpu_request_millicores = 450;
+
+        formatter.apply_hpa(&mut profile, &hpa(), 500);
+        assert_eq!(profile.cpu_request_millicores, 450);
+        assert!(profile.hpa_note.is_none());
+    }
+
+    #[test]
+    fn test_node_pressure_headroom() {
+        let formatter = OutputFormatter::new();
+        let mut profile = formatter.format(&[0.0, 0.0, 0.1, 0.2, 0.9], "v1.0.0");
+        profile.cpu_request_millicores = 1000;
+        profile.cpu_limit_millicores = 1050;
+        let memory_request = profile.memory_request_bytes;
+
+        let mut calm = profile.clone();
+        formatter.apply_node_pressure(&mut calm, 0.5);
+        assert_eq!(calm.cpu_request_millicores, 1000);
+
+        // Halfway between the threshold and full pressure: +10%
+        formatter.apply_node_pressure(&mut profile, 0.9);
+        assert_eq!(profile.cpu_request_millicores, 1100);
+        assert_eq!(profile.cpu_limit_millicores, 1100);
+        assert!(profile.memory_request_bytes > memory_request);
+        assert!(profile.memory_limit_bytes >= profile.memory_request_bytes);
+    }
+
+    #[test]
+    fn test_guardrails_use_feature_p99() {
+        let formatter = OutputFormatter::with_config(OutputConfig {
+            guardrails: Guardrails::new().with_memory_bounds(None, Some(1 << 30)),
+            ..Default::default()
+        });
+        let mut profile = formatter.format(&[0.01, 0.01, 0.01, 0.01, 0.9], "v1.0.0");
+        let features = FeatureVector {
+            cpu_usage_p50: 0.01,
+            cpu_usage_p95: 0.05,
+            cpu_usage_p99: 0.125,
+            mem_usage_p50: 0.01,
+            mem_usage_p95: 0.01,
+            mem_usage_p99: 0.5,
+            cpu_variance: 0.0,
+            mem_trend: 0.0,
+            throttle_ratio: 0.0,
+            hour_of_day: 0.0,
+            day_of_week: 0.0,
+            workload_age_days: 0.0,
+            node_pressure: 0.0,
+            node_volatility: 0.0,
+        };
+
+        // The limits are raised to p99 usage, 2 cores and 32GiB, but memory
+        // stays within its 1GiB bound
+        let applied = formatter.apply_guardrails(&mut profile, &features, None);
+        assert_eq!(
+            applied,
+            [GuardrailRule::P99Floor, GuardrailRule::MemoryBounds]
+        );
+        assert_eq!(profile.cpu_limit_millicores, 2000);
+        assert_eq!(profile.memory_limit_bytes, 1 << 30);
+    }
+
+    fn features() -> FeatureVector {
+        FeatureVector {
+            cpu_usage_p50: 0.0,
+            cpu_usage_p95: 0.0,
+            cpu_usage_p99: 0.0,
+            mem_usage_p50: 0.0,
+            mem_usage_p95: 0.0,
+            mem_usage_p99: 0.0,
+            cpu_variance: 0.0,
+            mem_trend: 0.0,
+            throttle_ratio: 0.0,
+            hour_of_day: 0.0,
+            day_of_week: 0.0,
+            workload_age_days: 0.0,
+            node_pressure: 0.0,
+            node_volatility: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_burst_cpu_strategy_per_namespace() {
+        let formatter = OutputFormatter::with_config(OutputConfig {
+            cpu_policy: CpuPolicy {
+                default: CpuStrategy::Burst {
+                    request: SustainedPercentile::P50,
+                    limit: BurstLimit::Peak,
+                },
+                namespaces: HashMap::from([(
+                    "batch".to_string(),
+                    CpuStrategy::Burst {
+                        request: SustainedPercentile::P95,
+                        limit: BurstLimit::Unlimited,
+                    },
+                )]),
+            },
+            ..Default::default()
+        });
+        // p50 0.5 cores, p95 1 core, p99 2 cores
+        let features = FeatureVector {
+            cpu_usage_p50: 0.03125,
+            cpu_usage_p95: 0.0625,
+            cpu_usage_p99: 0.125,
+            ..features()
+        };
+
+        let mut profile = formatter.format(&[0.1, 0.2, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_cpu_strategy(&mut profile, &features, 3.0, "web");
+        assert_eq!(profile.cpu_request_millicores, 500);
+        assert_eq!(profile.cpu_limit_millicores, 3300);
+
+        // Without a limit, neither guardrails nor QoS checks add one back
+        let mut profile = formatter.format(&[0.1, 0.2, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_cpu_strategy(&mut profile, &features, 3.0, "batch");
+        formatter.apply_node_pressure(&mut profile, 1.0);
+        formatter.apply_guardrails(&mut profile, &features, None);
+        formatter.apply_qos(&mut profile, &CurrentResources::default());
+        assert!(profile.cpu_request_millicores > 1000);
+        assert_eq!(profile.cpu_limit_millicores, 0);
+        assert!(profile.cpu_limit_unset);
+    }
+
+    #[test]
+    fn test_memory_request_only() {
+        let formatter = OutputFormatter::with_config(OutputConfig {
+            memory_policy: MemoryPolicy {
+                default: MemoryStrategy::RequestOnly,
+                namespaces: HashMap::from([("db".to_string(), MemoryStrategy::Model)]),
+            },
+            preserve_qos_class: true,
+            ..Default::default()
+        });
+        let features = FeatureVector {
+            mem_usage_p99: 0.5,
+            ..features()
+        };
+
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_memory_strategy(&mut profile, "web");
+        formatter.apply_guardrails(&mut profile, &features, None);
+        assert!(profile.memory_limit_unset);
+        assert_eq!(profile.memory_limit_bytes, 0);
+        assert!(profile.memory_request_bytes > 0);
+
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_memory_strategy(&mut profile, "db");
+        assert!(!profile.memory_limit_unset);
+
+        // Guaranteed containers keep a memory limit to stay Guaranteed
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_memory_strategy(&mut profile, "web");
+        formatter.apply_qos(&mut profile, &guaranteed());
+        assert!(!profile.memory_limit_unset);
+        assert_eq!(profile.memory_limit_bytes, profile.memory_request_bytes);
+        assert!(profile.qos_note.is_none());
+    }
+
+    fn guaranteed() -> CurrentResources {
+        CurrentResources {
+            cpu_request_millicores: 500,
+            cpu_limit_millicores: 500,
+            memory_request_bytes: 256 << 20,
+            memory_limit_bytes: 256 << 20,
+        }
+    }
+
+    #[test]
+    fn test_qos_change_flagged() {
+        let formatter = OutputFormatter::new();
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_qos(&mut profile, &guaranteed());
+        assert_eq!(
+            profile.qos_note.as_deref(),
+            Some("Changes QoS class from Guaranteed to Burstable")
+        );
+
+        // Containers without resources can't stay BestEffort either
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_qos(&mut profile, &CurrentResources::default());
+        assert!(profile.qos_note.unwrap().contains("from BestEffort"));
+    }
+
+    #[test]
+    fn test_qos_class_preserved() {
+        let formatter = OutputFormatter::with_config(OutputConfig {
+            preserve_qos_class: true,
+            ..Default::default()
+        });
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_qos(&mut profile, &guaranteed());
+        assert_eq!(profile.cpu_request_millicores, profile.cpu_limit_millicores);
+        assert_eq!(profile.memory_request_bytes, profile.memory_limit_bytes);
+        assert!(profile.qos_note.is_none());
+
+        // Only limits set: Kubernetes defaults requests to them
+        assert_eq!(QosClass::of((0, 500), (0, 1 << 30)), QosClass::Guaranteed);
+        assert_eq!(QosClass::of((100, 500), (0, 1 << 30)), QosClass::Burstable);
+    }
+
+    #[test]
+    fn test_disruption_budget_flagged() {
+        let formatter = OutputFormatter::new();
+        let budget = |name: &str, allowed| DisruptionBudget {
+            name: name.to_string(),
+            disruptions_allowed: allowed,
+            current_healthy: 3,
+            desired_healthy: 3 - allowed.min(1),
+        };
+        let budgets = [budget("api-max", 2), budget("api-min", 0)];
+
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        formatter.apply_disruption_budgets(&mut profile, &budgets, Some(&guaranteed()));
+        let note = profile.disruption_note.clone().unwrap();
+        assert!(note.contains("api-min allows no disruptions (3/3 healthy)"));
+
+        formatter.apply_disruption_budgets(&mut profile, &budgets[..1], None);
+        assert!(profile
+            .disruption_note
+            .unwrap()
+            .contains("batches of at most 2"));
+
+        // Nothing to restart when the resources don't change
+        let mut profile = formatter.format(&[0.01, 0.02, 0.01, 0.02, 0.9], "v1.0.0");
+        let current = CurrentResources {
+            cpu_request_millicores: profile.cpu_request_millicores,
+            cpu_limit_millicores: profile.cpu_limit_millicores,
+            memory_request_bytes: profile.memory_request_bytes,
+            memory_limit_bytes: profile.memory_limit_bytes,
+        };
+        formatter.apply_disruption_budgets(&mut profile, &budgets, Some(&current));
+        assert!(profile.disruption_note.is_none());
+    }
+
     #[test]
     fn test_high_confidence_no_reason() {
         let formatter = OutputFormatter::new();
@@ -0,0 +1,182 @@
+//! Pod-level aggregation of container recommendations
+//!
+//! Teams often size a pod as a whole rather than per container. The
+//! aggregator keeps the latest profile of every container of a pod and
+//! summarises them into a `PodProfile` each time one of them changes.
+
+use super::PredictionResult;
+use crate::models::{ContainerProfile, OwnerRef, PodAggregation, PodProfile};
+use std::collections::{BTreeMap, HashMap};
+
+/// Latest profiles of a pod's containers
+#[derive(Debug, Default)]
+struct PodEntry {
+    owner: Option<OwnerRef>,
+    /// Keyed by container ID, so summaries list containers in a stable order
+    containers: BTreeMap<String, ContainerProfile>,
+}
+
+/// Builds pod summaries from per-container predictions
+#[derive(Debug, Default)]
+pub struct PodAggregator {
+    mode: PodAggregation,
+    /// Keyed by (namespace, pod name)
+    pods: HashMap<(String, String), PodEntry>,
+}
+
+impl PodAggregator {
+    pub fn new(mode: PodAggregation) -> Self {
+        Self {
+            mode,
+            pods: HashMap::new(),
+        }
+    }
+
+    /// Whether per-container results should still be reported
+    pub fn keeps_containers(&self) -> bool {
+        self.mode == PodAggregation::Separate
+    }
+
+    /// Record a container's prediction and return the updated pod summary
+    ///
+    /// Results without a profile leave the pod unchanged and return `None`.
+    pub fn record(
+        &mut self,
+        result: &PredictionResult,
+        container_name: Option<String>,
+    ) -> Option<PodProfile> {
+        let profile = result.profile.clone()?;
+        let key = (result.namespace.clone(), result.pod_name.clone());
+        let entry = self.pods.entry(key).or_default();
+        if result.owner.is_some() {
+            entry.owner = result.owner.clone();
+        }
+        entry.containers.insert(
+            result.container_id.clone(),
+            ContainerProfile {
+                container_id: result.container_id.clone(),
+                container_name,
+                profile,
+            },
+        );
+        self.summary(&result.namespace, &result.pod_name)
+    }
+
+    /// Forget a container, dropping its pod once no container is left
+    pub fn remove_container(&mut self, namespace: &str, pod_name: &str, container_id: &str) {
+        let key = (namespace.to_string(), pod_name.to_string());
+        if let Some(entry) = self.pods.get_mut(&key) {
+            entry.containers.remove(container_id);
+            if entry.containers.is_empty() {
+                self.pods.remove(&key);
+            }
+        }
+    }
+
+    /// Summary of a pod's latest container profiles
+    pub fn summary(&self, namespace: &str, pod_name: &str) -> Option<PodProfile> {
+        let entry = self
+            .pods
+            .get(&(namespace.to_string(), pod_name.to_string()))?;
+        let mut pod = PodProfile {
+            pod_name: pod_name.to_string(),
+            namespace: namespace.to_string(),
+            owner: entry.owner.clone(),
+            cpu_request_millicores: 0,
+            cpu_limit_millicores: 0,
+            memory_request_bytes: 0,
+            memory_limit_bytes: 0,
+            confidence: 1.0,
+            generated_at: 0,
+            containers: Vec::new(),
+        };
+        for profile in entry.containers.values().map(|c| &c.profile) {
+            pod.cpu_request_millicores = pod
+                .cpu_request_millicores
+                .saturating_add(profile.cpu_request_millicores);
+            pod.memory_request_bytes = pod
+                .memory_request_bytes
+                .saturating_add(profile.memory_request_bytes);
+            pod.cpu_limit_millicores = pod.cpu_limit_millicores.max(profile.cpu_limit_millicores);
+            pod.memory_limit_bytes = pod.memory_limit_bytes.max(profile.memory_limit_bytes);
+            pod.confidence = pod.confidence.min(profile.confidence);
+            pod.generated_at = pod.generated_at.max(profile.generated_at);
+        }
+        if self.keeps_containers() {
+            pod.containers = entry.containers.values().cloned().collect();
+        }
+        Some(pod)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ResourceProfile;
+
+    fn result(container_id: &str, cpu: u32, memory: u64, confidence: f32) -> PredictionResult {
+        PredictionResult {
+            container_id: container_id.to_string(),
+            pod_name: "api-7d4f8b-x2k9p".to_string(),
+            namespace: "payments".to_string(),
+            owner: Some(OwnerRef::deployment("api")),
+            profile: Some(ResourceProfile {
+                cpu_request_millicores: cpu,
+                cpu_limit_millicores: cpu * 2,
+                memory_request_bytes: memory,
+                memory_limit_bytes: memory * 2,
+                confidence,
+                model_version: "v1".to_string(),
+                generated_at: 100,
+                ..Default::default()
+            }),
+            skipped_reason: None,
+            duration_us: 0,
+        }
+    }
+
+    #[test]
+    fn test_summary_sums_requests_and_takes_max_limits() {
+        let mut aggregator = PodAggregator::new(PodAggregation::Separate);
+        aggregator.record(&result("app", 500, 1000, 0.9), Some("api".to_string()));
+        let pod = aggregator
+            .record(&result("proxy", 100, 300, 0.6), Some("envoy".to_string()))
+            .unwrap();
+
+        assert_eq!(pod.cpu_request_millicores, 600);
+        assert_eq!(pod.cpu_limit_millicores, 1000);
+        assert_eq!(pod.memory_request_bytes, 1300);
+        assert_eq!(pod.memory_limit_bytes, 2000);
+        assert_eq!(pod.confidence, 0.6);
+        assert_eq!(pod.owner, Some(OwnerRef::deployment("api")));
+        assert_eq!(pod.containers.len(), 2);
+
+        // A newer profile replaces the container's previous one
+        let pod = aggregator
+            .record(&result("proxy", 200, 300, 0.8), None)
+            .unwrap();
+        assert_eq!(pod.cpu_request_millicores, 700);
+
+        aggregator.remove_container("payments", "api-7d4f8b-x2k9p", "proxy");
+        let pod = aggregator.summary("payments", "api-7d4f8b-x2k9p").unwrap();
+        assert_eq!(pod.cpu_request_millicores, 500);
+        aggregator.remove_container("payments", "api-7d4f8b-x2k9p", "app");
+        assert!(aggregator.summary("payments", "api-7d4f8b-x2k9p").is_none());
+    }
+
+    #[test]
+    fn test_aggregate_mode_drops_containers() {
+        let mut aggregator = PodAggregator::new(PodAggregation::Aggregate);
+        assert!(!aggregator.keeps_containers());
+
+        let pod = aggregator
+            .record(&result("app", 500, 1000, 0.9), None)
+            .unwrap();
+        assert_eq!(pod.cpu_request_millicores, 500);
+        assert!(pod.containers.is_empty());
+
+        let mut skipped = result("app", 0, 0, 0.0);
+        skipped.profile = None;
+        assert!(aggregator.record(&skipped, None).is_none());
+    }
+}
@@ -0,0 +1,179 @@
+//! Alignment of samples to a fixed time grid
+//!
+//! Collection jitter and the longer intervals of degraded mode space
+//! samples irregularly, so periods sampled more often weigh more in
+//! percentiles and regressions. Samples are aligned to a grid of the
+//! collection interval before features are extracted or leaks detected:
+//! each sample takes its nearest grid point, short gaps are forward-filled
+//! from the sample before them, and gaps too long to fill are left empty
+//! and reported.
+
+use super::series::{SampleColumns, SeriesView};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Default grid step, the default collection interval
+pub const DEFAULT_RESAMPLE_STEP: Duration = Duration::from_secs(10);
+
+/// Default longest gap forward-filled, enough for the quadrupled interval
+/// of minimal collection
+pub const DEFAULT_MAX_FILL_GAP: Duration = Duration::from_secs(40);
+
+/// Stretch of the grid without samples, too long to fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Gap {
+    /// Grid timestamp of the sample before the gap
+    pub from: i64,
+    /// Grid timestamp of the sample after the gap
+    pub to: i64,
+}
+
+/// Samples aligned to the grid, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct Resampled {
+    pub columns: SampleColumns,
+    pub gaps: Vec<Gap>,
+}
+
+/// Aligns samples to a fixed time grid
+#[derive(Debug, Clone, Copy)]
+pub struct Resampler {
+    /// Grid step in seconds
+    step: i64,
+    /// Most missing grid points forward-filled in a row
+    max_fill: i64,
+}
+
+impl Default for Resampler {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESAMPLE_STEP, DEFAULT_MAX_FILL_GAP)
+    }
+}
+
+impl Resampler {
+    /// Align to a grid of `step`, forward-filling gaps of up to
+    /// `max_fill_gap`
+    pub fn new(step: Duration, max_fill_gap: Duration) -> Self {
+        let step = step.as_secs().max(1) as i64;
+        Self {
+            step,
+            max_fill: max_fill_gap.as_secs() as i64 / step,
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        Duration::from_secs(self.step as u64)
+    }
+
+    /// Align sample columns to the grid
+    ///
+    /// Samples out of order or sharing a timestamp are sorted and
+    /// deduplicated first.
+    pub fn resample(&self, view: SeriesView<'_>) -> Resampled {
+        if !view.is_ordered() {
+            return self.resample(SampleColumns::ordered(view).view());
+        }
+        let (points, gaps) = self.plan(view.timestamps);
+        let columns = SampleColumns {
+            timestamps: points.iter().map(|&(ts, _)| ts).collect(),
+            cpu_usage_cores: points
+                .iter()
+                .map(|&(_, i)| view.cpu_usage_cores[i])
+                .collect(),
+            cpu_throttled_periods: points
+                .iter()
+                .map(|&(_, i)| view.cpu_throttled_periods[i])
+                .collect(),
+            memory_working_set_bytes: points
+                .iter()
+                .map(|&(_, i)| view.memory_working_set_bytes[i])
+                .collect(),
+        };
+        Resampled { columns, gaps }
+    }
+
+    /// Align `(timestamp, value)` samples, ordered by strictly increasing
+    /// timestamp, to the grid
+    pub fn resample_pairs<T: Copy>(&self, samples: &[(i64, T)]) -> (Vec<(i64, T)>, Vec<Gap>) {
+        let timestamps: Vec<i64> = samples.iter().map(|&(ts, _)| ts).collect();
+        let (points, gaps) = self.plan(&timestamps);
+        let samples = points.iter().map(|&(ts, i)| (ts, samples[i].1)).collect();
+        (samples, gaps)
+    }
+
+    /// Grid points with the index of the sample each takes its values
+    /// from, and the gaps left empty
+    ///
+    /// Of samples nearest to the same grid point, the newest is kept.
+    fn plan(&self, timestamps: &[i64]) -> (Vec<(i64, usize)>, Vec<Gap>) {
+        // Grid slot numbers, scaled to timestamps once planned
+        let mut points: Vec<(i64, usize)> = Vec::with_capacity(timestamps.len());
+        let mut gaps = Vec::new();
+        for (i, &ts) in timestamps.iter().enumerate() {
+            let slot = (ts + self.step / 2).div_euclid(self.step);
+            if let Some(&(last, index)) = points.last() {
+                if last == slot {
+                    points.pop();
+                } else if slot - last - 1 > self.max_fill {
+                    gaps.push(Gap {
+                        from: last * self.step,
+                        to: slot * self.step,
+                    });
+                } else {
+                    points.extend((last + 1..slot).map(|missing| (missing, index)));
+                }
+            }
+            points.push((slot, i));
+        }
+        for (slot, _) in &mut points {
+            *slot *= self.step;
+        }
+        (points, gaps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter_aligned_to_grid() {
+        let resampler = Resampler::default();
+        let (samples, gaps) = resampler.resample_pairs(&[(1, 1), (12, 2), (19, 3), (31, 4)]);
+        assert_eq!(samples, vec![(0, 1), (10, 2), (20, 3), (30, 4)]);
+        assert!(gaps.is_empty());
+
+        // Both nearest to 10: the newer one is kept
+        let (samples, _) = resampler.resample_pairs(&[(0, 1), (8, 2), (11, 3), (20, 4)]);
+        assert_eq!(samples, vec![(0, 1), (10, 3), (20, 4)]);
+    }
+
+    #[test]
+    fn test_small_gaps_filled_large_gaps_marked() {
+        let resampler = Resampler::new(Duration::from_secs(10), Duration::from_secs(30));
+        let (samples, gaps) = resampler.resample_pairs(&[(0, 1), (40, 2), (200, 3)]);
+        // 10 to 30 are filled from the sample at 0; 50 to 190 are too long
+        assert_eq!(
+            samples,
+            vec![(0, 1), (10, 1), (20, 1), (30, 1), (40, 2), (200, 3)]
+        );
+        assert_eq!(gaps, vec![Gap { from: 40, to: 200 }]);
+    }
+
+    #[test]
+    fn test_resample_columns() {
+        let view = SeriesView {
+            timestamps: &[20, 0, 30],
+            cpu_usage_cores: &[0.2, 0.1, 0.3],
+            cpu_throttled_periods: &[2, 1, 3],
+            memory_working_set_bytes: &[20, 10, 30],
+        };
+        let resampled = Resampler::default().resample(view);
+        let columns = resampled.columns.view();
+        assert_eq!(columns.timestamps, &[0, 10, 20, 30]);
+        assert_eq!(columns.cpu_usage_cores, &[0.1, 0.1, 0.2, 0.3]);
+        assert_eq!(columns.cpu_throttled_periods, &[1, 1, 2, 3]);
+        assert_eq!(columns.memory_working_set_bytes, &[10, 10, 20, 30]);
+        assert!(resampled.gaps.is_empty());
+    }
+}
@@ -3,13 +3,32 @@
 //! Runs predictions periodically for each container, handling timeouts
 //! and insufficient data gracefully.
 
-use super::{FeatureExtractor, OnnxPredictor, Predictor, MIN_SAMPLES};
-use crate::models::{ContainerMetrics, ResourceProfile};
-use anyhow::Result;
+use super::checkpoint::{self, Checkpoint, ContainerCheckpoint};
+use super::series::SampleSeries;
+use super::{
+    AnomalyGate, BatchPredictor, CheckpointConfig, ConfidenceCalibrator, CurrentResources,
+    DeviationCollector, FeatureExtractor, HysteresisAnchor, OnnxPredictor, OutputConfig,
+    OutputFormatter, Predictor, RunSummary, ShadowSlot, WorkloadFingerprint,
+    DEFAULT_ANOMALY_TAINT_WINDOW, MIN_SAMPLES,
+};
+use crate::anomaly::{AnomalyState, DetectorKind};
+use crate::health::ComponentReporter;
+use crate::intern::{self, intern};
+use crate::maintenance::MaintenanceStatus;
+use crate::models::{
+    ContainerMetrics, FeatureVector, NodeMetrics, OwnerRef, ResourceProfile, WorkloadKind,
+};
+use crate::node_lifecycle::NodeLifecycle;
+use crate::observability::AgentMetrics;
+use crate::self_limit::DegradationLevel;
+use crate::sync::{next_update, RuntimeConfig};
+use anyhow::{Context, Result};
+use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 use tokio::time::interval;
 use tracing::{debug, info, warn};
 
@@ -19,6 +38,12 @@ pub const DEFAULT_PREDICTION_INTERVAL: Duration = Duration::from_secs(5 * 60);
 /// Maximum inference timeout before using fallback
 pub const INFERENCE_TIMEOUT: Duration = Duration::from_millis(100);
 
+/// Default age from which a profile counts as stale
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(30 * 60);
+
+/// Weight of each node sample in the long-term node pressure
+const NODE_PRESSURE_SMOOTHING: f32 = 0.05;
+
 /// Configuration for the prediction scheduler
 #[derive(Debug, Clone)]
 pub struct PredictionConfig {
@@ -30,6 +55,13 @@ pub struct PredictionConfig {
     pub feature_window_size: usize,
     /// Maximum inference timeout
     pub inference_timeout: Duration,
+    /// Age from which a profile is stale: its confidence is halved for
+    /// every further `stale_after` and it is re-predicted first
+    pub stale_after: Duration,
+    /// How recommendations derived from anomalous samples are deferred
+    pub anomaly_gate: AnomalyGate,
+    /// Time after an anomaly during which samples count as anomalous
+    pub anomaly_taint_window: Duration,
 }
 
 impl Default for PredictionConfig {
@@ -39,6 +71,9 @@ impl Default for PredictionConfig {
             min_samples: MIN_SAMPLES,
             feature_window_size: 360, // 1 hour at 10s intervals
             inference_timeout: INFERENCE_TIMEOUT,
+            stale_after: DEFAULT_STALE_AFTER,
+            anomaly_gate: AnomalyGate::default(),
+            anomaly_taint_window: DEFAULT_ANOMALY_TAINT_WINDOW,
         }
     }
 }
@@ -46,27 +81,92 @@ impl Default for PredictionConfig {
 /// Metrics buffer for a single container
 #[derive(Debug)]
 struct ContainerBuffer {
-    metrics: Vec<ContainerMetrics>,
+    samples: SampleSeries,
     last_prediction: Option<Instant>,
     last_profile: Option<ResourceProfile>,
+    /// Kind of the owning workload and its `namespace/name`, when known
+    workload: Option<(WorkloadKind, String)>,
+    /// Requests and limits currently set on the container, when known
+    current_resources: Option<CurrentResources>,
+    /// Profile later recommendations are held near
+    hysteresis_anchor: Option<HysteresisAnchor>,
 }
 
 impl ContainerBuffer {
     fn new() -> Self {
         Self {
-            metrics: Vec::new(),
+            samples: SampleSeries::default(),
             last_prediction: None,
             last_profile: None,
+            workload: None,
+            current_resources: None,
+            hysteresis_anchor: None,
         }
     }
 
-    fn add_metrics(&mut self, metrics: ContainerMetrics) {
-        self.metrics.push(metrics);
-        // Keep only the most recent samples (24 hours at 10s = 8640 samples)
-        const MAX_SAMPLES: usize = 8640;
-        if self.metrics.len() > MAX_SAMPLES {
-            self.metrics.drain(0..self.metrics.len() - MAX_SAMPLES);
+    fn add_metrics(&mut self, metrics: &ContainerMetrics) {
+        if self.workload.is_none() {
+            if let Some(owner) = &metrics.owner {
+                self.set_owner(&metrics.namespace, owner);
+            }
         }
+        self.samples.push(metrics);
+    }
+
+    fn set_owner(&mut self, namespace: &str, owner: &OwnerRef) {
+        self.workload = Some((owner.kind, format!("{}/{}", namespace, owner.name)));
+    }
+
+    /// Insert historical samples ahead of the collected ones
+    ///
+    /// Samples at or after the oldest collected one are dropped so that
+    /// history never overlaps live data.
+    fn seed(&mut self, history: Vec<ContainerMetrics>) -> usize {
+        self.samples.prepend(history)
+    }
+
+    /// Pod name, namespace and owner of the container, once sampled
+    fn metadata(&self) -> Option<(String, String, Option<OwnerRef>)> {
+        (!self.samples.is_empty()).then(|| {
+            (
+                self.samples.pod_name().to_string(),
+                self.samples.namespace().to_string(),
+                self.samples.owner().cloned(),
+            )
+        })
+    }
+
+    /// Time since the latest profile was predicted, if there is one
+    fn profile_age(&self) -> Option<Duration> {
+        self.last_profile.as_ref()?;
+        Some(self.last_prediction?.elapsed())
+    }
+
+    fn is_stale(&self, stale_after: Duration) -> bool {
+        self.profile_age().is_some_and(|age| age >= stale_after)
+    }
+
+    /// Latest profile, with its confidence decayed once it is stale
+    fn profile(&self, stale_after: Duration) -> Option<ResourceProfile> {
+        let mut profile = self.last_profile.clone()?;
+        if let Some(age) = self.profile_age() {
+            profile.confidence = decayed_confidence(profile.confidence, age, stale_after);
+        }
+        Some(profile)
+    }
+
+    /// Latest profile with the container's pod, from its newest sample
+    fn prediction(&self, container_id: &str, stale_after: Duration) -> Option<ContainerPrediction> {
+        let profile = self.profile(stale_after)?;
+        let (pod_name, namespace, owner) = self.metadata().unwrap_or_default();
+        Some(ContainerPrediction {
+            container_id: container_id.to_string(),
+            pod_name,
+            namespace,
+            owner,
+            profile,
+            stale: self.is_stale(stale_after),
+        })
     }
 
     fn should_predict(&self, interval: Duration) -> bool {
@@ -82,8 +182,63 @@ pub struct PredictionScheduler {
     predictor: Arc<RwLock<OnnxPredictor>>,
     feature_extractor: FeatureExtractor,
     config: PredictionConfig,
-    buffers: RwLock<HashMap<String, ContainerBuffer>>,
+    /// Keyed by interned container ID
+    buffers: RwLock<HashMap<Arc<str>, ContainerBuffer>>,
     prediction_tx: mpsc::Sender<PredictionResult>,
+    /// Effective prediction interval in milliseconds, updated at runtime
+    prediction_interval_ms: AtomicU64,
+    /// Config pushed by the control plane
+    runtime: Option<watch::Receiver<RuntimeConfig>>,
+    /// Candidate model evaluated alongside the live one
+    shadow: Option<ShadowSlot>,
+    /// Health reporting for the predictor component
+    health: Option<ComponentReporter>,
+    /// Degradation level set by the self limiter
+    degradation: Option<watch::Receiver<DegradationLevel>>,
+    /// Long-term node pressure as `f32` bits
+    node_pressure: AtomicU32,
+    output_formatter: OutputFormatter,
+    /// Finished runs of Jobs and CronJobs
+    batch: RwLock<BatchPredictor>,
+    /// Where buffers are checkpointed, if anywhere
+    checkpoint: Option<CheckpointConfig>,
+    /// Maps model confidence to the observed rate of limits holding
+    calibrator: Option<Arc<ConfidenceCalibrator>>,
+    /// Pairs sampled predictions with later usage for model training
+    deviation: Option<Arc<DeviationCollector>>,
+    /// Node maintenance status; predictions pause while it is active
+    maintenance: Option<watch::Receiver<MaintenanceStatus>>,
+    /// Anomalies per container; recommendations derived from them are
+    /// deferred
+    anomalies: Option<AnomalyState>,
+    /// Where the stale profile count is reported, if anywhere
+    metrics: Option<AgentMetrics>,
+    /// Whether the node is spot or preemptible
+    node_lifecycle: Option<watch::Receiver<NodeLifecycle>>,
+}
+
+/// Latest profile of a tracked container
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerPrediction {
+    pub container_id: String,
+    pub pod_name: String,
+    pub namespace: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<OwnerRef>,
+    pub profile: ResourceProfile,
+    /// The profile is older than `PredictionConfig::stale_after`, and its
+    /// confidence decayed
+    pub stale: bool,
+}
+
+/// Confidence of a profile of age `age`, halved for every `stale_after`
+/// past `stale_after`
+fn decayed_confidence(confidence: f32, age: Duration, stale_after: Duration) -> f32 {
+    if age < stale_after || stale_after.is_zero() {
+        return confidence;
+    }
+    let half_lives = (age - stale_after).as_secs_f32() / stale_after.as_secs_f32();
+    confidence * 0.5f32.powf(half_lives)
 }
 
 /// Result of a prediction attempt
@@ -92,7 +247,7 @@ pub struct PredictionResult {
     pub container_id: String,
     pub pod_name: String,
     pub namespace: String,
-    pub deployment: Option<String>,
+    pub owner: Option<OwnerRef>,
     pub profile: Option<ResourceProfile>,
     pub skipped_reason: Option<String>,
     pub duration_us: u64,
@@ -108,50 +263,408 @@ impl PredictionScheduler {
         let scheduler = Self {
             predictor,
             feature_extractor: FeatureExtractor::new(config.feature_window_size),
+            prediction_interval_ms: AtomicU64::new(config.prediction_interval.as_millis() as u64),
             config,
             buffers: RwLock::new(HashMap::new()),
             prediction_tx: tx,
+            runtime: None,
+            shadow: None,
+            health: None,
+            degradation: None,
+            node_pressure: AtomicU32::new(0.0f32.to_bits()),
+            output_formatter: OutputFormatter::new(),
+            batch: RwLock::new(BatchPredictor::new()),
+            checkpoint: None,
+            calibrator: None,
+            deviation: None,
+            maintenance: None,
+            anomalies: None,
+            metrics: None,
+            node_lifecycle: None,
         };
         (scheduler, rx)
     }
 
-    /// Add metrics to the buffer for a container
-    pub async fn add_metrics(&self, metrics: ContainerMetrics) {
-        let container_id = metrics.container_id.clone();
+    /// Apply control plane prediction interval updates
+    pub fn with_runtime_config(mut self, runtime: watch::Receiver<RuntimeConfig>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Feed model predictions to whatever shadow model is installed in `slot`
+    pub fn with_shadow(mut self, slot: ShadowSlot) -> Self {
+        self.shadow = Some(slot);
+        self
+    }
+
+    /// Report inference health through `reporter`
+    pub fn with_health(mut self, reporter: ComponentReporter) -> Self {
+        self.health = Some(reporter);
+        self
+    }
+
+    /// Slow down or pause predictions as the self limiter sheds work
+    pub fn with_degradation(mut self, degradation: watch::Receiver<DegradationLevel>) -> Self {
+        self.degradation = Some(degradation);
+        self
+    }
+
+    /// Pause predictions while the node is in maintenance
+    ///
+    /// Samples are still buffered, so predictions resume with full windows.
+    pub fn with_maintenance(mut self, maintenance: watch::Receiver<MaintenanceStatus>) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Defer recommendations derived from samples taken during the
+    /// anomalies in `state`, as set by `PredictionConfig::anomaly_gate`
+    pub fn with_anomaly_state(mut self, state: AnomalyState) -> Self {
+        self.anomalies = Some(state);
+        self
+    }
+
+    /// Report the number of stale profiles to `metrics`
+    pub fn with_metrics(mut self, metrics: AgentMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Flag features and profiles with the node's lifecycle, so data
+    /// collected on spot and preemptible nodes can be told apart
+    pub fn with_node_lifecycle(mut self, lifecycle: watch::Receiver<NodeLifecycle>) -> Self {
+        self.node_lifecycle = Some(lifecycle);
+        self
+    }
+
+    /// Checkpoint buffers periodically and on shutdown
+    ///
+    /// Call `restore_checkpoint` before `run` to pick up the state saved by
+    /// the previous agent.
+    pub fn with_checkpoint(mut self, config: CheckpointConfig) -> Self {
+        self.checkpoint = Some(config);
+        self
+    }
+
+    /// Calibrate model confidence against observed outcomes
+    ///
+    /// Samples added to the scheduler are checked against the limits of the
+    /// predictions before them, and the confidence of new profiles is
+    /// replaced by `calibrator`'s estimate of their limits holding.
+    pub fn with_calibrator(mut self, calibrator: Arc<ConfidenceCalibrator>) -> Self {
+        self.calibrator = Some(calibrator);
+        self
+    }
+
+    /// Sample predictions and the usage that follows them for training
+    pub fn with_deviation_collector(mut self, collector: Arc<DeviationCollector>) -> Self {
+        self.deviation = Some(collector);
+        self
+    }
+
+    /// Format profiles with `config`, e.g. to set guardrails
+    pub fn with_output_config(mut self, config: OutputConfig) -> Self {
+        self.output_formatter = OutputFormatter::with_config(config);
+        self
+    }
+
+    /// Current self limiter degradation level
+    fn degradation_level(&self) -> DegradationLevel {
+        self.degradation
+            .as_ref()
+            .map(|rx| *rx.borrow())
+            .unwrap_or_default()
+    }
+
+    fn node_lifecycle(&self) -> NodeLifecycle {
+        self.node_lifecycle
+            .as_ref()
+            .map(|rx| *rx.borrow())
+            .unwrap_or_default()
+    }
+
+    fn in_maintenance(&self) -> bool {
+        self.maintenance
+            .as_ref()
+            .is_some_and(|rx| rx.borrow().active())
+    }
+
+    /// Current prediction interval
+    pub fn prediction_interval(&self) -> Duration {
+        Duration::from_millis(self.prediction_interval_ms.load(Ordering::Relaxed))
+    }
+
+    /// Change the prediction interval at runtime
+    pub fn set_prediction_interval(&self, interval: Duration) {
+        self.prediction_interval_ms
+            .store(interval.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Fold a node sample into the long-term node pressure
+    ///
+    /// Pressure is smoothed so that only chronic overcommitment, not a short
+    /// burst, makes recommendations more conservative.
+    pub fn update_node_metrics(&self, node: &NodeMetrics) {
+        let previous = self.node_pressure();
+        let next = previous + NODE_PRESSURE_SMOOTHING * (node.pressure() - previous);
+        self.node_pressure.store(next.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Long-term node pressure between 0 and 1
+    pub fn node_pressure(&self) -> f32 {
+        f32::from_bits(self.node_pressure.load(Ordering::Relaxed))
+    }
+
+    /// Record the workload owning a container
+    ///
+    /// Overrides the owner carried by the container's metrics. Containers
+    /// of Jobs and CronJobs are predicted from the peaks of their runs
+    /// rather than by the model.
+    pub async fn set_workload(&self, container_id: &str, namespace: &str, owner: &OwnerRef) {
+        let mut buffers = self.buffers.write().await;
+        buffers
+            .entry(intern(container_id))
+            .or_insert_with(ContainerBuffer::new)
+            .set_owner(namespace, owner);
+    }
+
+    /// Record the requests and limits currently set on a container
+    ///
+    /// Guardrails limit how far recommendations move from them, and they
+    /// decide the QoS class recommendations preserve or flag.
+    pub async fn set_current_resources(&self, container_id: &str, resources: CurrentResources) {
         let mut buffers = self.buffers.write().await;
         buffers
-            .entry(container_id)
+            .entry(intern(container_id))
             .or_insert_with(ContainerBuffer::new)
-            .add_metrics(metrics);
+            .current_resources = Some(resources);
+    }
+
+    /// Add metrics to the buffer for a container
+    pub async fn add_metrics(&self, metrics: ContainerMetrics) {
+        if let Some(calibrator) = &self.calibrator {
+            calibrator.observe(&metrics);
+        }
+        if let Some(deviation) = &self.deviation {
+            deviation.observe(&metrics);
+        }
+        let mut buffers = self.buffers.write().await;
+        match buffers.get_mut(metrics.container_id.as_str()) {
+            Some(buffer) => buffer.add_metrics(&metrics),
+            None => buffers
+                .entry(intern(&metrics.container_id))
+                .or_insert_with(ContainerBuffer::new)
+                .add_metrics(&metrics),
+        }
+    }
+
+    /// Seed container buffers with historical samples, e.g. from a backfill
+    ///
+    /// Returns the number of samples added.
+    pub async fn seed_metrics(&self, history: Vec<ContainerMetrics>) -> usize {
+        let mut by_container: HashMap<String, Vec<ContainerMetrics>> = HashMap::new();
+        for metrics in history {
+            by_container
+                .entry(metrics.container_id.clone())
+                .or_default()
+                .push(metrics);
+        }
+
+        let mut buffers = self.buffers.write().await;
+        by_container
+            .into_iter()
+            .map(|(container_id, history)| {
+                buffers
+                    .entry(intern(&container_id))
+                    .or_insert_with(ContainerBuffer::new)
+                    .seed(history)
+            })
+            .sum()
     }
 
     /// Run the prediction loop
     pub async fn run(self: Arc<Self>, mut shutdown: tokio::sync::broadcast::Receiver<()>) {
         info!(
-            interval_secs = self.config.prediction_interval.as_secs(),
+            interval_secs = self.prediction_interval().as_secs(),
             "Starting prediction scheduler"
         );
 
         let mut ticker = interval(Duration::from_secs(30)); // Check every 30s
+        let mut runtime = self.runtime.clone();
+        let mut checkpoint_ticker = interval(
+            self.checkpoint
+                .as_ref()
+                .map_or(Duration::from_secs(3600), |c| c.interval),
+        );
+        // The first tick completes immediately, right after a restore
+        checkpoint_ticker.tick().await;
 
         loop {
             tokio::select! {
                 _ = ticker.tick() => {
-                    self.run_predictions().await;
+                    // Paused predictions still heartbeat so the watchdog
+                    // doesn't mistake shedding or maintenance for a stall
+                    if self.degradation_level().prediction_enabled() && !self.in_maintenance() {
+                        self.run_predictions().await;
+                    }
+                    if let Some(health) = &self.health {
+                        health.heartbeat();
+                    }
+                }
+                update = next_update(&mut runtime) => {
+                    let interval = update
+                        .prediction_interval
+                        .unwrap_or(self.config.prediction_interval);
+                    if interval != self.prediction_interval() {
+                        info!(interval_secs = interval.as_secs(), "Prediction interval updated");
+                        self.set_prediction_interval(interval);
+                    }
+                }
+                _ = checkpoint_ticker.tick(), if self.checkpoint.is_some() => {
+                    if let Err(e) = self.save_checkpoint().await {
+                        warn!(error = %e, "Failed to checkpoint prediction scheduler");
+                    }
                 }
                 _ = shutdown.recv() => {
                     info!("Shutting down prediction scheduler");
+                    if let Err(e) = self.save_checkpoint().await {
+                        warn!(error = %e, "Failed to checkpoint prediction scheduler");
+                    }
                     break;
                 }
             }
         }
     }
 
+    /// Write container buffers and batch runs to the checkpoint file
+    ///
+    /// Does nothing without a checkpoint configured.
+    pub async fn save_checkpoint(&self) -> Result<()> {
+        let Some(config) = &self.checkpoint else {
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let containers = {
+            let buffers = self.buffers.read().await;
+            buffers
+                .iter()
+                .map(|(id, buffer)| ContainerCheckpoint {
+                    container_id: id.to_string(),
+                    metrics: buffer.samples.to_metrics(id),
+                    last_profile: buffer.last_profile.clone(),
+                    last_predicted_at: buffer
+                        .last_prediction
+                        .map(|at| now - at.elapsed().as_secs() as i64),
+                    workload: buffer.workload.clone(),
+                    hysteresis_anchor: buffer.hysteresis_anchor.clone(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let snapshot = Checkpoint {
+            saved_at: now,
+            containers,
+            batch_runs: self.batch.read().await.runs(),
+        };
+
+        let count = snapshot.containers.len();
+        let path = config.path.clone();
+        let bytes = tokio::task::spawn_blocking(move || checkpoint::write(&path, &snapshot))
+            .await
+            .context("Checkpoint task failed")??;
+
+        debug!(
+            containers = count,
+            bytes, "Prediction scheduler checkpointed"
+        );
+        Ok(())
+    }
+
+    /// Restore container buffers and batch runs from the checkpoint file
+    ///
+    /// Containers without a sample within the configured max age are
+    /// dropped, and containers predicted before the restart keep their
+    /// schedule instead of being re-predicted at once. Returns the number
+    /// of containers restored, 0 without a checkpoint file.
+    pub async fn restore_checkpoint(&self) -> Result<usize> {
+        let Some(config) = &self.checkpoint else {
+            return Ok(0);
+        };
+        if !config.path.exists() {
+            return Ok(0);
+        }
+
+        let path = config.path.clone();
+        let snapshot = tokio::task::spawn_blocking(move || checkpoint::read(&path))
+            .await
+            .context("Checkpoint task failed")??;
+
+        let now = chrono::Utc::now().timestamp();
+        let oldest = now - config.max_age.as_secs() as i64;
+        let mut restored = 0;
+        {
+            let mut buffers = self.buffers.write().await;
+            for container in snapshot.containers {
+                let newest = container.metrics.iter().map(|m| m.timestamp).max();
+                if newest.is_none_or(|ts| ts < oldest) {
+                    continue;
+                }
+
+                let buffer = buffers
+                    .entry(intern(&container.container_id))
+                    .or_insert_with(ContainerBuffer::new);
+                buffer.seed(container.metrics);
+                if buffer.last_profile.is_none() {
+                    buffer.last_profile = container.last_profile;
+                    buffer.last_prediction = container.last_predicted_at.and_then(|at| {
+                        let age = Duration::from_secs((now - at).max(0) as u64);
+                        Instant::now().checked_sub(age)
+                    });
+                }
+                if buffer.workload.is_none() {
+                    buffer.workload = container.workload;
+                }
+                if buffer.hysteresis_anchor.is_none() {
+                    buffer.hysteresis_anchor = container.hysteresis_anchor;
+                }
+                restored += 1;
+            }
+        }
+
+        let mut batch = self.batch.write().await;
+        for (workload, runs) in snapshot.batch_runs {
+            if batch.run_count(&workload) == 0 {
+                for run in runs {
+                    batch.record_run(&workload, run);
+                }
+            }
+        }
+
+        info!(
+            containers = restored,
+            saved_at = snapshot.saved_at,
+            "Restored prediction scheduler checkpoint"
+        );
+        Ok(restored)
+    }
+
     /// Run predictions for all containers that need them
+    #[tracing::instrument(name = "prediction_cycle", skip_all)]
     async fn run_predictions(&self) {
-        let container_ids: Vec<String> = {
+        // Stale profiles are re-predicted first, oldest first
+        let container_ids: Vec<Arc<str>> = {
             let buffers = self.buffers.read().await;
-            buffers.keys().cloned().collect()
+            let mut containers: Vec<_> = buffers
+                .iter()
+                .map(|(id, buffer)| {
+                    let stale_age = buffer
+                        .profile_age()
+                        .filter(|&age| age >= self.config.stale_after);
+                    (id.clone(), stale_age)
+                })
+                .collect();
+            containers.sort_by(|a, b| b.1.cmp(&a.1));
+            containers.into_iter().map(|(id, _)| id).collect()
         };
 
         for container_id in container_ids {
@@ -159,48 +672,80 @@ impl PredictionScheduler {
                 warn!(container_id = %container_id, error = %e, "Prediction failed");
             }
         }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.set_stale_profiles(self.stats().await.stale_predictions as i64);
+        }
     }
 
     /// Run prediction for a single container
+    #[tracing::instrument(name = "prediction", skip(self))]
     async fn predict_container(&self, container_id: &str) -> Result<()> {
         let start = Instant::now();
 
-        let (should_predict, metrics_snapshot, metadata) = {
+        let (samples, metadata, workload, current_resources, previous) = {
             let buffers = self.buffers.read().await;
             let buffer = match buffers.get(container_id) {
                 Some(b) => b,
                 None => return Ok(()),
             };
 
-            let should = buffer.should_predict(self.config.prediction_interval);
-            let metrics = buffer.metrics.clone();
-            let meta = metrics.last().map(|m| {
-                (
-                    m.pod_name.clone(),
-                    m.namespace.clone(),
-                    m.deployment.clone(),
-                )
-            });
-            (should, metrics, meta)
+            let interval =
+                self.prediction_interval() * self.degradation_level().interval_multiplier();
+            if !buffer.should_predict(interval) && !buffer.is_stale(self.config.stale_after) {
+                return Ok(());
+            }
+            (
+                buffer.samples.columns(),
+                buffer.metadata(),
+                buffer.workload.clone(),
+                buffer.current_resources,
+                buffer.last_profile.clone(),
+            )
         };
 
-        if !should_predict {
+        let (pod_name, namespace, owner) = metadata.unwrap_or_default();
+
+        // Batch workloads have no steady state for the model to learn, and
+        // may finish before the trend path has enough samples
+        if let Some((kind, key)) = workload.as_ref().filter(|(kind, _)| kind.is_batch()) {
+            let current = RunSummary::from_view(samples.view());
+            let profile = self
+                .batch
+                .read()
+                .await
+                .predict(key, *kind, current.as_ref())
+                .map(|mut p| {
+                    p.spot_node = self.node_lifecycle().is_spot();
+                    p
+                });
+            let skipped_reason = profile
+                .is_none()
+                .then(|| "No runs observed yet".to_string());
+            let result = PredictionResult {
+                container_id: container_id.to_string(),
+                pod_name,
+                namespace,
+                owner,
+                profile,
+                skipped_reason,
+                duration_us: 0,
+            };
+            self.complete_prediction(result, start).await;
             return Ok(());
         }
 
-        let (pod_name, namespace, deployment) = metadata.unwrap_or_default();
-
         // Check if we have enough samples
-        if metrics_snapshot.len() < self.config.min_samples {
+        if samples.len() < self.config.min_samples {
             let result = PredictionResult {
                 container_id: container_id.to_string(),
                 pod_name,
                 namespace,
-                deployment,
+                owner,
                 profile: None,
                 skipped_reason: Some(format!(
                     "Insufficient data: {} samples, need {}",
-                    metrics_snapshot.len(),
+                    samples.len(),
                     self.config.min_samples
                 )),
                 duration_us: start.elapsed().as_micros() as u64,
@@ -210,14 +755,14 @@ impl PredictionScheduler {
         }
 
         // Extract features
-        let features = match self.feature_extractor.extract(&metrics_snapshot) {
+        let mut features = match self.feature_extractor.extract_view(samples.view()) {
             Some(f) => f,
             None => {
                 let result = PredictionResult {
                     container_id: container_id.to_string(),
                     pod_name,
                     namespace,
-                    deployment,
+                    owner,
                     profile: None,
                     skipped_reason: Some("Feature extraction failed".to_string()),
                     duration_us: start.elapsed().as_micros() as u64,
@@ -227,6 +772,10 @@ impl PredictionScheduler {
             }
         };
 
+        let lifecycle = self.node_lifecycle();
+        features.node_pressure = self.node_pressure();
+        features.node_volatility = lifecycle.volatility();
+
         // Run prediction with timeout
         let profile = {
             let predictor = self.predictor.read().await;
@@ -236,48 +785,157 @@ impl PredictionScheduler {
             .await
         };
 
-        let (profile, skipped_reason) = match profile {
-            Ok(Ok(p)) => (Some(p), None),
+        let (profile, mut skipped_reason) = match profile {
+            Ok(Ok(p)) => {
+                self.observe_shadow(&features, &p).await;
+                if let Some(health) = &self.health {
+                    health.success();
+                }
+                (Some(p), None)
+            }
             Ok(Err(e)) => {
                 warn!(error = %e, "Inference error, using fallback");
+                if let Some(health) = &self.health {
+                    health.failure(format!("Inference error: {}", e));
+                }
                 let fallback = super::FallbackPredictor::predict(&features);
                 (Some(fallback), Some(format!("Fallback used: {}", e)))
             }
             Err(_) => {
                 warn!("Inference timeout, using fallback");
+                if let Some(health) = &self.health {
+                    health.failure("Inference timeout");
+                }
                 let fallback = super::FallbackPredictor::predict(&features);
                 (Some(fallback), Some("Inference timeout".to_string()))
             }
         };
 
-        // Update last prediction time
+        let issued_at = samples.view().timestamps.iter().max().copied();
+        let mut profile = profile;
+        if let Some(p) = &mut profile {
+            self.output_formatter.apply_cpu_strategy(
+                p,
+                &features,
+                samples.view().peak_cpu_cores(),
+                &namespace,
+            );
+            self.output_formatter.apply_memory_strategy(p, &namespace);
+            self.output_formatter
+                .apply_node_pressure(p, features.node_pressure);
+        }
+        let mut deferred = None;
+        if let (Some(state), Some(p), Some(issued_at)) = (&self.anomalies, &mut profile, issued_at)
         {
-            let mut buffers = self.buffers.write().await;
-            if let Some(buffer) = buffers.get_mut(container_id) {
-                buffer.last_prediction = Some(Instant::now());
-                buffer.last_profile = profile.clone();
+            let since = issued_at - self.config.anomaly_taint_window.as_secs() as i64;
+            // Network traffic says nothing about CPU and memory needs
+            let mut anomalies = state.recent(container_id, since);
+            anomalies.retain(|&kind| kind != DetectorKind::NetworkSpike);
+            if !self
+                .config
+                .anomaly_gate
+                .apply(p, previous.as_ref(), &anomalies)
+            {
+                let kinds: Vec<_> = anomalies.iter().map(|kind| kind.as_str()).collect();
+                deferred = Some(format!("Deferred due to anomaly: {}", kinds.join(", ")));
             }
         }
+        if let Some(reason) = deferred {
+            debug!(container_id = %container_id, reason = %reason, "Prediction deferred");
+            skipped_reason = Some(reason);
+            profile = None;
+        }
+        if let (Some(p), Some(issued_at)) = (&mut profile, issued_at) {
+            self.apply_hysteresis(container_id, p, issued_at).await;
+        }
+
+        let profile = profile.map(|mut p| {
+            let applied =
+                self.output_formatter
+                    .apply_guardrails(&mut p, &features, current_resources);
+            if !applied.is_empty() {
+                debug!(container_id = %container_id, rules = ?applied, "Guardrails applied");
+            }
+            if let Some(current) = &current_resources {
+                self.output_formatter.apply_qos(&mut p, current);
+            }
+            if let Some((kind, _)) = &workload {
+                p.workload_kind = *kind;
+            }
+            p.workload_cluster =
+                WorkloadFingerprint::from_view(samples.view()).map(|f| f.cluster_id());
+            p.spot_node = lifecycle.is_spot();
+            if let (Some(deviation), Some(issued_at)) = (&self.deviation, issued_at) {
+                deviation.record(container_id, issued_at, &features, &p);
+            }
+            if let (Some(calibrator), Some(issued_at)) = (&self.calibrator, issued_at) {
+                calibrator.calibrate(container_id, issued_at, &mut p);
+            }
+            p
+        });
 
         let result = PredictionResult {
             container_id: container_id.to_string(),
             pod_name,
             namespace,
-            deployment,
+            owner,
             profile,
             skipped_reason,
-            duration_us: start.elapsed().as_micros() as u64,
+            duration_us: 0,
+        };
+        self.complete_prediction(result, start).await;
+        Ok(())
+    }
+
+    /// Hold a profile near the container's previous recommendations
+    async fn apply_hysteresis(&self, container_id: &str, profile: &mut ResourceProfile, now: i64) {
+        let mut buffers = self.buffers.write().await;
+        let Some(buffer) = buffers.get_mut(container_id) else {
+            return;
         };
+        if self
+            .output_formatter
+            .apply_hysteresis(&mut buffer.hysteresis_anchor, profile, now)
+        {
+            debug!(container_id = %container_id, "Recommendation change held back by hysteresis");
+        }
+    }
+
+    /// Store a prediction on its container's buffer and publish it
+    async fn complete_prediction(&self, mut result: PredictionResult, start: Instant) {
+        {
+            let mut buffers = self.buffers.write().await;
+            if let Some(buffer) = buffers.get_mut(result.container_id.as_str()) {
+                buffer.last_prediction = Some(Instant::now());
+                buffer.last_profile = result.profile.clone();
+            }
+        }
+        result.duration_us = start.elapsed().as_micros() as u64;
 
         debug!(
-            container_id = %container_id,
+            container_id = %result.container_id,
             duration_us = result.duration_us,
             has_profile = result.profile.is_some(),
             "Prediction completed"
         );
 
         let _ = self.prediction_tx.send(result).await;
-        Ok(())
+    }
+
+    /// Compare a live model prediction with the shadow model, if any
+    async fn observe_shadow(&self, features: &FeatureVector, live: &ResourceProfile) {
+        // Fallback output says nothing about the candidate model
+        if live.model_version == "fallback" {
+            return;
+        }
+
+        let Some(slot) = &self.shadow else {
+            return;
+        };
+        let shadow = slot.read().await.clone();
+        if let Some(shadow) = shadow {
+            shadow.observe(features, live);
+        }
     }
 
     /// Get the last prediction for a container
@@ -285,7 +943,48 @@ impl PredictionScheduler {
         let buffers = self.buffers.read().await;
         buffers
             .get(container_id)
-            .and_then(|b| b.last_profile.clone())
+            .and_then(|b| b.profile(self.config.stale_after))
+    }
+
+    /// Latest profile of every container that has one, by container ID
+    pub async fn latest_predictions(&self) -> Vec<ContainerPrediction> {
+        let buffers = self.buffers.read().await;
+        let mut predictions: Vec<ContainerPrediction> = buffers
+            .iter()
+            .filter_map(|(id, buffer)| buffer.prediction(id, self.config.stale_after))
+            .collect();
+        predictions.sort_by(|a, b| a.container_id.cmp(&b.container_id));
+        predictions
+    }
+
+    /// Latest profile of a container, with the pod it runs in
+    pub async fn latest_prediction(&self, container_id: &str) -> Option<ContainerPrediction> {
+        let buffers = self.buffers.read().await;
+        buffers
+            .get(container_id)?
+            .prediction(container_id, self.config.stale_after)
+    }
+
+    /// Samples held for prediction in `[from, to]`, of one container when
+    /// given, oldest first
+    ///
+    /// Values the scheduler does not keep, like network counters, are zero.
+    pub async fn recent_metrics(
+        &self,
+        container_id: Option<&str>,
+        from: i64,
+        to: i64,
+    ) -> Vec<ContainerMetrics> {
+        let selected = |id: &str| container_id.is_none() || container_id == Some(id);
+        let buffers = self.buffers.read().await;
+        let mut metrics: Vec<ContainerMetrics> = buffers
+            .iter()
+            .filter(|(id, _)| selected(id))
+            .flat_map(|(id, buffer)| buffer.samples.to_metrics(id))
+            .filter(|m| (from..=to).contains(&m.timestamp))
+            .collect();
+        metrics.sort_by_key(|m| m.timestamp);
+        metrics
     }
 
     /// Get statistics about the scheduler
@@ -296,33 +995,64 @@ impl PredictionScheduler {
             .values()
             .filter(|b| b.last_profile.is_some())
             .count();
-        let total_samples: usize = buffers.values().map(|b| b.metrics.len()).sum();
+        let stale_predictions = buffers
+            .values()
+            .filter(|b| b.is_stale(self.config.stale_after))
+            .count();
+        let total_samples: usize = buffers.values().map(|b| b.samples.len()).sum();
 
         SchedulerStats {
             total_containers,
             containers_with_predictions,
+            stale_predictions,
             total_samples,
         }
     }
 
     /// Remove a container from tracking
+    ///
+    /// The samples of a Job or CronJob container are kept as a finished run
+    /// of its workload.
     pub async fn remove_container(&self, container_id: &str) {
-        let mut buffers = self.buffers.write().await;
-        buffers.remove(container_id);
+        let Some(buffer) = self.buffers.write().await.remove(container_id) else {
+            return;
+        };
+        let run = match &buffer.workload {
+            Some((kind, workload)) if kind.is_batch() => {
+                RunSummary::from_view(buffer.samples.columns().view())
+                    .map(|run| (workload.clone(), run))
+            }
+            _ => None,
+        };
+        drop(buffer);
+        intern::interner().purge();
+        if let Some(calibrator) = &self.calibrator {
+            calibrator.remove_container(container_id);
+        }
+        if let Some(deviation) = &self.deviation {
+            deviation.remove_container(container_id);
+        }
+
+        if let Some((workload, run)) = run {
+            self.batch.write().await.record_run(&workload, run);
+        }
     }
 }
 
 /// Statistics about the prediction scheduler
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SchedulerStats {
     pub total_containers: usize,
     pub containers_with_predictions: usize,
+    /// Profiles older than `PredictionConfig::stale_after`
+    pub stale_predictions: usize,
     pub total_samples: usize,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::predictor::{ShadowModel, BATCH_MODEL_VERSION};
 
     fn create_test_metrics(container_id: &str, count: usize) -> Vec<ContainerMetrics> {
         let now = chrono::Utc::now().timestamp();
@@ -331,7 +1061,7 @@ mod tests {
                 container_id: container_id.to_string(),
                 pod_name: "test-pod".to_string(),
                 namespace: "default".to_string(),
-                deployment: Some("test-deploy".to_string()),
+                owner: Some(OwnerRef::deployment("test-deploy")),
                 timestamp: now - (count - i - 1) as i64 * 10,
                 cpu_usage_cores: 0.5 + (i as f32 * 0.01),
                 cpu_throttled_periods: i as u64 * 10,
@@ -391,6 +1121,221 @@ mod tests {
         assert!(result.profile.is_some()); // Should use fallback predictor
     }
 
+    #[tokio::test]
+    async fn test_prediction_deferred_during_anomaly() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let config = PredictionConfig {
+            anomaly_gate: AnomalyGate::Hold,
+            ..Default::default()
+        };
+        let state = AnomalyState::new();
+        let (scheduler, mut rx) = PredictionScheduler::new(predictor, config);
+        let scheduler = scheduler.with_anomaly_state(state.clone());
+
+        let metrics = create_test_metrics("container1", 15);
+        let newest = metrics.last().unwrap().timestamp;
+        for m in metrics {
+            scheduler.add_metrics(m).await;
+        }
+        let leak = std::collections::HashSet::from([DetectorKind::MemoryLeak]);
+        state.update("container1", &leak, newest);
+
+        // Nothing to hold the memory recommendation at yet
+        scheduler.predict_container("container1").await.unwrap();
+        let result = rx.try_recv().unwrap();
+        assert!(result.profile.is_none());
+        assert_eq!(
+            result.skipped_reason.as_deref(),
+            Some("Deferred due to anomaly: memory_leak")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shadow_ignores_fallback_predictions() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let slot: ShadowSlot = Arc::default();
+        let (scheduler, mut rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+        let scheduler = scheduler.with_shadow(slot.clone());
+
+        let shadow = Arc::new(ShadowModel::new(
+            "v2",
+            Box::new(OnnxPredictor::new_without_model()),
+        ));
+        *slot.write().await = Some(shadow.clone());
+
+        for m in create_test_metrics("container1", 15) {
+            scheduler.add_metrics(m).await;
+        }
+        scheduler.predict_container("container1").await.unwrap();
+
+        assert!(rx.try_recv().unwrap().profile.is_some());
+        assert_eq!(shadow.stats().samples, 0);
+    }
+
+    #[tokio::test]
+    async fn test_seed_metrics_before_live_samples() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (scheduler, mut rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+
+        let history = create_test_metrics("container1", 20);
+        let live = history[15].timestamp;
+        scheduler.add_metrics(history[15].clone()).await;
+
+        // Samples overlapping the live one are dropped
+        assert_eq!(scheduler.seed_metrics(history).await, 15);
+        assert_eq!(scheduler.stats().await.total_samples, 16);
+        {
+            let buffers = scheduler.buffers.read().await;
+            let columns = buffers["container1"].samples.columns();
+            let timestamps = columns.view().timestamps;
+            assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(timestamps.last(), Some(&live));
+        }
+
+        scheduler.predict_container("container1").await.unwrap();
+        assert!(rx.try_recv().unwrap().profile.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_recent_metrics_window() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (scheduler, _rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+
+        for id in ["container1", "container2"] {
+            for m in create_test_metrics(id, 10) {
+                scheduler.add_metrics(m).await;
+            }
+        }
+
+        let all = scheduler.recent_metrics(None, 0, i64::MAX).await;
+        assert_eq!(all.len(), 20);
+        assert!(all.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+        let newest = all
+            .iter()
+            .filter(|m| m.container_id == "container1")
+            .map(|m| m.timestamp)
+            .max()
+            .unwrap();
+        let recent = scheduler
+            .recent_metrics(Some("container1"), newest - 20, newest)
+            .await;
+        assert_eq!(recent.len(), 3);
+        assert!(recent.iter().all(|m| m.container_id == "container1"));
+    }
+
+    #[tokio::test]
+    async fn test_latest_predictions() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (scheduler, _rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+
+        for id in ["container2", "container1"] {
+            for m in create_test_metrics(id, 15) {
+                scheduler.add_metrics(m).await;
+            }
+        }
+        for m in create_test_metrics("container3", 5) {
+            scheduler.add_metrics(m).await;
+        }
+        assert!(scheduler.latest_predictions().await.is_empty());
+
+        for id in ["container1", "container2", "container3"] {
+            scheduler.predict_container(id).await.unwrap();
+        }
+
+        // Containers without enough samples have no profile yet
+        let predictions = scheduler.latest_predictions().await;
+        let ids: Vec<&str> = predictions
+            .iter()
+            .map(|p| p.container_id.as_str())
+            .collect();
+        assert_eq!(ids, ["container1", "container2"]);
+
+        let prediction = scheduler.latest_prediction("container1").await.unwrap();
+        assert_eq!(prediction.pod_name, "test-pod");
+        assert_eq!(prediction.namespace, "default");
+        assert_eq!(prediction.owner, Some(OwnerRef::deployment("test-deploy")));
+        assert!(scheduler.latest_prediction("container3").await.is_none());
+        assert!(scheduler.latest_prediction("unknown").await.is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "persistence")]
+    async fn test_checkpoint_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CheckpointConfig::new(dir.path().join("scheduler.ckpt"));
+
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (scheduler, _rx) =
+            PredictionScheduler::new(predictor.clone(), PredictionConfig::default());
+        let scheduler = scheduler.with_checkpoint(config.clone());
+        for m in create_test_metrics("container1", 15) {
+            scheduler.add_metrics(m).await;
+        }
+        let mut stale = create_test_metrics("container2", 15);
+        for m in &mut stale {
+            m.timestamp -= 2 * 3600;
+        }
+        for m in stale {
+            scheduler.add_metrics(m).await;
+        }
+        scheduler.predict_container("container1").await.unwrap();
+        scheduler.batch.write().await.record_run(
+            "batch/report",
+            RunSummary::from_metrics(&create_test_metrics("job", 3)).unwrap(),
+        );
+        scheduler.save_checkpoint().await.unwrap();
+
+        let (restored, mut rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+        let restored = restored.with_checkpoint(config);
+        // Containers without recent samples are dropped
+        assert_eq!(restored.restore_checkpoint().await.unwrap(), 1);
+        assert_eq!(restored.stats().await.total_samples, 15);
+        assert!(restored.latest_prediction("container1").await.is_some());
+        assert_eq!(restored.batch.read().await.run_count("batch/report"), 1);
+
+        // The restored container is not re-predicted right away
+        restored.predict_container("container1").await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_decayed_confidence() {
+        let stale_after = Duration::from_secs(600);
+        assert_eq!(
+            decayed_confidence(0.8, Duration::from_secs(300), stale_after),
+            0.8
+        );
+        assert_eq!(decayed_confidence(0.8, stale_after, stale_after), 0.8);
+        let decayed = decayed_confidence(0.8, Duration::from_secs(1800), stale_after);
+        assert!((decayed - 0.2).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_stale_profile_decayed_and_repredicted() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let config = PredictionConfig {
+            stale_after: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let (scheduler, mut rx) = PredictionScheduler::new(predictor, config);
+        for m in create_test_metrics("container1", 15) {
+            scheduler.add_metrics(m).await;
+        }
+        scheduler.predict_container("container1").await.unwrap();
+        let predicted = rx.try_recv().unwrap().profile.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let latest = scheduler.latest_prediction("container1").await.unwrap();
+        assert!(latest.stale);
+        assert!(latest.profile.confidence < predicted.confidence / 2.0);
+        assert_eq!(scheduler.stats().await.stale_predictions, 1);
+
+        // Re-predicted although the prediction interval has not passed
+        scheduler.predict_container("container1").await.unwrap();
+        assert!(rx.try_recv().unwrap().profile.is_some());
+    }
+
     #[tokio::test]
     async fn test_remove_container() {
         let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
@@ -406,4 +1351,77 @@ mod tests {
 
         assert_eq!(scheduler.stats().await.total_containers, 0);
     }
+
+    #[tokio::test]
+    async fn test_node_pressure_smoothed() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (scheduler, _rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+        let node = NodeMetrics {
+            timestamp: 0,
+            cpu_allocatable_cores: 4.0,
+            memory_allocatable_bytes: 8 << 30,
+            cpu_usage_cores: 4.0,
+            memory_usage_bytes: 2 << 30,
+            cpu_pressure: 0.0,
+            memory_pressure: 0.0,
+            io_pressure: 0.0,
+        };
+
+        scheduler.update_node_metrics(&node);
+        assert!(scheduler.node_pressure() < 0.1);
+
+        for _ in 0..200 {
+            scheduler.update_node_metrics(&node);
+        }
+        assert!(scheduler.node_pressure() > 0.99);
+    }
+
+    #[tokio::test]
+    async fn test_spot_node_flagged() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (lifecycle, lifecycle_rx) = watch::channel(NodeLifecycle::OnDemand);
+        let (scheduler, mut rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+        let scheduler = scheduler.with_node_lifecycle(lifecycle_rx);
+        for m in create_test_metrics("container1", 15) {
+            scheduler.add_metrics(m).await;
+        }
+
+        scheduler.predict_container("container1").await.unwrap();
+        assert!(!rx.try_recv().unwrap().profile.unwrap().spot_node);
+
+        lifecycle.send(NodeLifecycle::Spot).unwrap();
+        scheduler.set_prediction_interval(Duration::ZERO);
+        scheduler.predict_container("container1").await.unwrap();
+        assert!(rx.try_recv().unwrap().profile.unwrap().spot_node);
+    }
+
+    #[tokio::test]
+    async fn test_cronjob_predicted_from_run_peaks() {
+        let predictor = Arc::new(RwLock::new(OnnxPredictor::new_without_model()));
+        let (scheduler, mut rx) = PredictionScheduler::new(predictor, PredictionConfig::default());
+
+        // Too few samples for the model, enough for a batch run
+        let report = OwnerRef::new(WorkloadKind::CronJob, "report");
+        scheduler.set_workload("run1", "default", &report).await;
+        for m in create_test_metrics("run1", 5) {
+            scheduler.add_metrics(m).await;
+        }
+        scheduler.predict_container("run1").await.unwrap();
+
+        let profile = rx.try_recv().unwrap().profile.unwrap();
+        assert_eq!(profile.workload_kind, WorkloadKind::CronJob);
+        assert_eq!(profile.model_version, BATCH_MODEL_VERSION);
+        assert!(profile.cpu_request_millicores > 540);
+        assert_eq!(profile.confidence, 0.0);
+        let first_run = profile.cpu_request_millicores;
+
+        // The next run starts from the finished run's peak
+        scheduler.remove_container("run1").await;
+        scheduler.set_workload("run2", "default", &report).await;
+        scheduler.predict_container("run2").await.unwrap();
+
+        let profile = rx.try_recv().unwrap().profile.unwrap();
+        assert_eq!(profile.cpu_request_millicores, first_run);
+        assert!(profile.confidence > 0.3);
+    }
 }
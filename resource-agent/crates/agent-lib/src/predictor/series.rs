@@ -0,0 +1,347 @@
+//! Compact per-container sample storage
+//!
+//! The scheduler keeps up to a day of samples for every container. A full
+//! `ContainerMetrics` repeats the container's IDs and names on each sample,
+//! so the scheduler stores only the values predictions read, as columns of
+//! a ring buffer per container, with the container's interned
+//! `ContainerKey` kept once.
+
+use crate::models::{ContainerKey, ContainerMetrics, OwnerRef};
+use std::collections::VecDeque;
+
+/// Samples kept per container (24 hours at 10s)
+pub const MAX_SERIES_SAMPLES: usize = 8640;
+
+/// Borrowed sample columns, oldest first
+#[derive(Debug, Clone, Copy)]
+pub struct SeriesView<'a> {
+    pub timestamps: &'a [i64],
+    pub cpu_usage_cores: &'a [f32],
+    pub cpu_throttled_periods: &'a [u64],
+    pub memory_working_set_bytes: &'a [u64],
+}
+
+impl SeriesView<'_> {
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Whether timestamps strictly increase
+    pub fn is_ordered(&self) -> bool {
+        self.timestamps.windows(2).all(|w| w[0] < w[1])
+    }
+
+    /// Highest CPU usage over a single sample interval, in cores
+    pub fn peak_cpu_cores(&self) -> f32 {
+        self.cpu_usage_cores.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// Contiguous copy of sample columns, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct SampleColumns {
+    pub(super) timestamps: Vec<i64>,
+    pub(super) cpu_usage_cores: Vec<f32>,
+    pub(super) cpu_throttled_periods: Vec<u64>,
+    pub(super) memory_working_set_bytes: Vec<u64>,
+}
+
+impl SampleColumns {
+    /// Copy of `view` sorted by timestamp
+    ///
+    /// Of samples sharing a timestamp, e.g. after the wall clock stepped
+    /// back, the one added last is kept.
+    pub fn ordered(view: SeriesView<'_>) -> Self {
+        let mut order: Vec<usize> = (0..view.len()).collect();
+        order.sort_by_key(|&i| (view.timestamps[i], std::cmp::Reverse(i)));
+        order.dedup_by_key(|i| view.timestamps[*i]);
+        Self {
+            timestamps: order.iter().map(|&i| view.timestamps[i]).collect(),
+            cpu_usage_cores: order.iter().map(|&i| view.cpu_usage_cores[i]).collect(),
+            cpu_throttled_periods: order
+                .iter()
+                .map(|&i| view.cpu_throttled_periods[i])
+                .collect(),
+            memory_working_set_bytes: order
+                .iter()
+                .map(|&i| view.memory_working_set_bytes[i])
+                .collect(),
+        }
+    }
+
+    pub fn view(&self) -> SeriesView<'_> {
+        SeriesView {
+            timestamps: &self.timestamps,
+            cpu_usage_cores: &self.cpu_usage_cores,
+            cpu_throttled_periods: &self.cpu_throttled_periods,
+            memory_working_set_bytes: &self.memory_working_set_bytes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+}
+
+impl From<&[ContainerMetrics]> for SampleColumns {
+    fn from(metrics: &[ContainerMetrics]) -> Self {
+        Self {
+            timestamps: metrics.iter().map(|m| m.timestamp).collect(),
+            cpu_usage_cores: metrics.iter().map(|m| m.cpu_usage_cores).collect(),
+            cpu_throttled_periods: metrics.iter().map(|m| m.cpu_throttled_periods).collect(),
+            memory_working_set_bytes: metrics.iter().map(|m| m.memory_working_set_bytes).collect(),
+        }
+    }
+}
+
+/// Ring buffer of a container's samples
+///
+/// Keeps the container's pod and owner once, from its newest sample, and
+/// evicts the oldest samples beyond capacity.
+#[derive(Debug)]
+pub struct SampleSeries {
+    capacity: usize,
+    timestamps: VecDeque<i64>,
+    cpu_usage_cores: VecDeque<f32>,
+    cpu_throttled_periods: VecDeque<u64>,
+    memory_working_set_bytes: VecDeque<u64>,
+    key: Option<ContainerKey>,
+    owner: Option<OwnerRef>,
+}
+
+impl SampleSeries {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            timestamps: VecDeque::new(),
+            cpu_usage_cores: VecDeque::new(),
+            cpu_throttled_periods: VecDeque::new(),
+            memory_working_set_bytes: VecDeque::new(),
+            key: None,
+            owner: None,
+        }
+    }
+
+    /// Append a sample, evicting the oldest one when full
+    pub fn push(&mut self, metrics: &ContainerMetrics) {
+        self.set_identity(metrics);
+        if self.len() == self.capacity {
+            self.timestamps.pop_front();
+            self.cpu_usage_cores.pop_front();
+            self.cpu_throttled_periods.pop_front();
+            self.memory_working_set_bytes.pop_front();
+        }
+        self.reserve(1);
+        self.timestamps.push_back(metrics.timestamp);
+        self.cpu_usage_cores.push_back(metrics.cpu_usage_cores);
+        self.cpu_throttled_periods
+            .push_back(metrics.cpu_throttled_periods);
+        self.memory_working_set_bytes
+            .push_back(metrics.memory_working_set_bytes);
+    }
+
+    /// Insert historical samples ahead of the held ones
+    ///
+    /// Samples at or after the oldest held one are dropped so that history
+    /// never overlaps live data, and so are the oldest samples that do not
+    /// fit. Returns the number of samples added.
+    pub fn prepend(&mut self, mut history: Vec<ContainerMetrics>) -> usize {
+        if let Some(oldest) = self.timestamps.front().copied() {
+            history.retain(|m| m.timestamp < oldest);
+        }
+        history.sort_by_key(|m| m.timestamp);
+        if self.key.is_none() {
+            if let Some(newest) = history.last() {
+                self.set_identity(newest);
+            }
+        }
+
+        let room = self.capacity - self.len();
+        self.reserve(history.len().min(room));
+        let mut added = 0;
+        for m in history.iter().rev().take(room) {
+            self.timestamps.push_front(m.timestamp);
+            self.cpu_usage_cores.push_front(m.cpu_usage_cores);
+            self.cpu_throttled_periods
+                .push_front(m.cpu_throttled_periods);
+            self.memory_working_set_bytes
+                .push_front(m.memory_working_set_bytes);
+            added += 1;
+        }
+        added
+    }
+
+    /// Grow the columns for `additional` samples, never past capacity
+    ///
+    /// Doubling would leave a full buffer nearly twice its capacity.
+    fn reserve(&mut self, additional: usize) {
+        let needed = self.len() + additional;
+        if needed <= self.timestamps.capacity() {
+            return;
+        }
+        let target = (self.timestamps.capacity() * 2)
+            .min(self.capacity)
+            .max(needed);
+        let additional = target - self.len();
+        self.timestamps.reserve_exact(additional);
+        self.cpu_usage_cores.reserve_exact(additional);
+        self.cpu_throttled_periods.reserve_exact(additional);
+        self.memory_working_set_bytes.reserve_exact(additional);
+    }
+
+    fn set_identity(&mut self, metrics: &ContainerMetrics) {
+        let unchanged = self.key.as_ref().is_some_and(|key| {
+            *key.container_id == *metrics.container_id
+                && *key.pod_name == *metrics.pod_name
+                && *key.namespace == *metrics.namespace
+        });
+        if !unchanged {
+            self.key = Some(metrics.key());
+        }
+        if metrics.owner.is_some() {
+            self.owner = metrics.owner.clone();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Timestamp of the newest sample
+    pub fn last_timestamp(&self) -> Option<i64> {
+        self.timestamps.back().copied()
+    }
+
+    /// Identity of the container, once sampled
+    pub fn key(&self) -> Option<&ContainerKey> {
+        self.key.as_ref()
+    }
+
+    pub fn pod_name(&self) -> &str {
+        self.key.as_ref().map_or("", |key| &key.pod_name)
+    }
+
+    pub fn namespace(&self) -> &str {
+        self.key.as_ref().map_or("", |key| &key.namespace)
+    }
+
+    pub fn owner(&self) -> Option<&OwnerRef> {
+        self.owner.as_ref()
+    }
+
+    /// Contiguous copy of the samples, e.g. to extract features unlocked
+    pub fn columns(&self) -> SampleColumns {
+        SampleColumns {
+            timestamps: self.timestamps.iter().copied().collect(),
+            cpu_usage_cores: self.cpu_usage_cores.iter().copied().collect(),
+            cpu_throttled_periods: self.cpu_throttled_periods.iter().copied().collect(),
+            memory_working_set_bytes: self.memory_working_set_bytes.iter().copied().collect(),
+        }
+    }
+
+    /// Samples as `ContainerMetrics`, oldest first
+    ///
+    /// Values the series does not keep, like network counters, are zero.
+    pub fn to_metrics(&self, container_id: &str) -> Vec<ContainerMetrics> {
+        (0..self.len())
+            .map(|i| ContainerMetrics {
+                container_id: container_id.to_string(),
+                pod_name: self.pod_name().to_string(),
+                namespace: self.namespace().to_string(),
+                owner: self.owner.clone(),
+                timestamp: self.timestamps[i],
+                cpu_usage_cores: self.cpu_usage_cores[i],
+                cpu_throttled_periods: self.cpu_throttled_periods[i],
+                memory_usage_bytes: 0,
+                memory_working_set_bytes: self.memory_working_set_bytes[i],
+                memory_cache_bytes: 0,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            })
+            .collect()
+    }
+}
+
+impl Default for SampleSeries {
+    fn default() -> Self {
+        Self::new(MAX_SERIES_SAMPLES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, pod_name: &str) -> ContainerMetrics {
+        ContainerMetrics {
+            container_id: "c1".to_string(),
+            pod_name: pod_name.to_string(),
+            namespace: "default".to_string(),
+            owner: None,
+            timestamp,
+            cpu_usage_cores: timestamp as f32,
+            cpu_throttled_periods: 0,
+            memory_usage_bytes: 0,
+            memory_working_set_bytes: timestamp as u64,
+            memory_cache_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_push_evicts_oldest() {
+        let mut series = SampleSeries::new(3);
+        for ts in 0..5 {
+            series.push(&sample(ts, "pod"));
+        }
+
+        let columns = series.columns();
+        assert_eq!(columns.view().timestamps, [2, 3, 4]);
+        assert_eq!(columns.view().cpu_usage_cores, [2.0, 3.0, 4.0]);
+        assert_eq!(series.last_timestamp(), Some(4));
+        assert_eq!(series.to_metrics("c1")[0].pod_name, "pod");
+    }
+
+    #[test]
+    fn test_prepend_fills_remaining_room() {
+        let mut series = SampleSeries::new(4);
+        series.push(&sample(10, "pod"));
+        series.push(&sample(11, "pod"));
+
+        // Overlapping samples are dropped, then the oldest that don't fit
+        let history = (5..12).map(|ts| sample(ts, "pod")).collect();
+        assert_eq!(series.prepend(history), 2);
+        assert_eq!(series.columns().view().timestamps, [8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn test_ordered_sorts_and_drops_duplicates() {
+        let metrics: Vec<ContainerMetrics> = [(10, 1.0), (30, 2.0), (20, 3.0), (30, 4.0)]
+            .into_iter()
+            .map(|(ts, cpu)| ContainerMetrics {
+                cpu_usage_cores: cpu,
+                ..sample(ts, "pod")
+            })
+            .collect();
+        let columns = SampleColumns::from(metrics.as_slice());
+        assert!(!columns.view().is_ordered());
+
+        let ordered = SampleColumns::ordered(columns.view());
+        assert!(ordered.view().is_ordered());
+        assert_eq!(ordered.view().timestamps, [10, 20, 30]);
+        assert_eq!(ordered.view().cpu_usage_cores, [1.0, 3.0, 4.0]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Shadow evaluation of candidate models
+//!
+//! A newly downloaded model can run in shadow next to the live one: the
+//! scheduler feeds it the same features and records how far its predictions
+//! deviate, without its output ever leaving the agent.
+
+use super::Predictor;
+use crate::models::{FeatureVector, ResourceProfile};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// Slot through which a shadow model is installed into the scheduler
+pub type ShadowSlot = Arc<RwLock<Option<Arc<ShadowModel>>>>;
+
+/// Accumulated comparison between shadow and live predictions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShadowStats {
+    /// Predictions compared
+    pub samples: usize,
+    /// Shadow inferences that failed
+    pub errors: usize,
+    pub total_deviation: f64,
+    pub max_deviation: f64,
+}
+
+impl ShadowStats {
+    /// Mean relative deviation across compared predictions
+    pub fn mean_deviation(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.total_deviation / self.samples as f64
+        }
+    }
+}
+
+/// Candidate model evaluated alongside the live model
+pub struct ShadowModel {
+    version: String,
+    predictor: Box<dyn Predictor>,
+    stats: Mutex<ShadowStats>,
+}
+
+impl ShadowModel {
+    /// Create a shadow for a candidate model version
+    pub fn new(version: impl Into<String>, predictor: Box<dyn Predictor>) -> Self {
+        Self {
+            version: version.into(),
+            predictor,
+            stats: Mutex::new(ShadowStats::default()),
+        }
+    }
+
+    /// Candidate model version
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Run the candidate on `features` and compare with the live prediction
+    pub fn observe(&self, features: &FeatureVector, live: &ResourceProfile) {
+        let result = self.predictor.predict(features);
+        let mut stats = self.stats.lock().unwrap();
+
+        match result {
+            Ok(shadow) => {
+                let deviation = profile_deviation(live, &shadow);
+                stats.samples += 1;
+                stats.total_deviation += deviation;
+                stats.max_deviation = stats.max_deviation.max(deviation);
+                debug!(
+                    version = %self.version,
+                    deviation = deviation,
+                    "Shadow prediction compared"
+                );
+            }
+            Err(e) => {
+                stats.errors += 1;
+                debug!(version = %self.version, error = %e, "Shadow inference failed");
+            }
+        }
+    }
+
+    /// Comparison results so far
+    pub fn stats(&self) -> ShadowStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+/// Mean relative difference of the request and limit values of two profiles
+pub fn profile_deviation(live: &ResourceProfile, shadow: &ResourceProfile) -> f64 {
+    let pairs = [
+        (
+            live.cpu_request_millicores as f64,
+            shadow.cpu_request_millicores as f64,
+        ),
+        (
+            live.cpu_limit_millicores as f64,
+            shadow.cpu_limit_millicores as f64,
+        ),
+        (
+            live.memory_request_bytes as f64,
+            shadow.memory_request_bytes as f64,
+        ),
+        (
+            live.memory_limit_bytes as f64,
+            shadow.memory_limit_bytes as f64,
+        ),
+    ];
+
+    let total: f64 = pairs
+        .iter()
+        .map(|&(a, b)| {
+            let scale = a.abs().max(b.abs());
+            if scale == 0.0 {
+                0.0
+            } else {
+                (a - b).abs() / scale
+            }
+        })
+        .sum();
+    total / pairs.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkloadKind;
+    use crate::predictor::PredictError;
+
+    fn profile(cpu: u32, memory: u64) -> ResourceProfile {
+        ResourceProfile {
+            cpu_request_millicores: cpu,
+            cpu_limit_millicores: cpu * 2,
+            memory_request_bytes: memory,
+            memory_limit_bytes: memory * 2,
+            confidence: 0.9,
+            model_version: "test".to_string(),
+            workload_kind: WorkloadKind::Deployment,
+            ..Default::default()
+        }
+    }
+
+    struct FixedPredictor(Option<ResourceProfile>);
+
+    impl Predictor for FixedPredictor {
+        fn predict(&self, _features: &FeatureVector) -> Result<ResourceProfile, PredictError> {
+            self.0
+                .clone()
+                .ok_or_else(|| PredictError::Inference(anyhow::anyhow!("no profile")))
+        }
+
+        fn update_model(&mut self, _weights: &[u8]) -> Result<(), PredictError> {
+            Ok(())
+        }
+
+        fn model_version(&self) -> &str {
+            "fixed"
+        }
+    }
+
+    fn features() -> FeatureVector {
+        FeatureVector {
+            cpu_usage_p50: 0.5,
+            cpu_usage_p95: 0.8,
+            cpu_usage_p99: 0.9,
+            mem_usage_p50: 0.3,
+            mem_usage_p95: 0.4,
+            mem_usage_p99: 0.5,
+            cpu_variance: 0.1,
+            mem_trend: 0.0,
+            throttle_ratio: 0.0,
+            hour_of_day: 0.5,
+            day_of_week: 0.5,
+            workload_age_days: 0.1,
+            node_pressure: 0.0,
+            node_volatility: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_profile_deviation() {
+        assert_eq!(
+            profile_deviation(&profile(100, 1000), &profile(100, 1000)),
+            0.0
+        );
+
+        // CPU values are 25% off, memory matches
+        let deviation = profile_deviation(&profile(100, 1000), &profile(75, 1000));
+        assert!((deviation - 0.125).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_shadow_accumulates_stats() {
+        let shadow = ShadowModel::new("v2", Box::new(FixedPredictor(Some(profile(80, 1000)))));
+        shadow.observe(&features(), &profile(100, 1000));
+        shadow.observe(&features(), &profile(80, 1000));
+
+        let stats = shadow.stats();
+        assert_eq!(stats.samples, 2);
+        assert!((stats.max_deviation - 0.1).abs() < 1e-9);
+        assert!((stats.mean_deviation() - 0.05).abs() < 1e-9);
+
+        let failing = ShadowModel::new("v3", Box::new(FixedPredictor(None)));
+        failing.observe(&features(), &profile(100, 1000));
+        assert_eq!(failing.stats().errors, 1);
+        assert_eq!(failing.stats().samples, 0);
+    }
+}
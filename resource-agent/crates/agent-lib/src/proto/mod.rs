@@ -4,6 +4,7 @@
 //! The code is generated at build time by tonic-build.
 //!
 //! Stub types are provided for development when protoc is not available.
+//! The gRPC client and server require the `grpc` feature.
 pub mod predictor {
     pub mod v1 {
         use prost::Message;
@@ -20,6 +21,10 @@ pub mod predictor {
             pub agent_version: String,
             #[prost(string, tag = "5")]
             pub model_version: String,
+            #[prost(uint32, tag = "6")]
+            pub protocol_version: u32,
+            #[prost(uint64, tag = "7")]
+            pub capabilities: u64,
         }
 
         #[derive(Clone, PartialEq, Message)]
@@ -30,6 +35,22 @@ pub mod predictor {
             pub message: String,
             #[prost(message, optional, tag = "3")]
             pub config: Option<AgentConfig>,
+            #[prost(uint32, tag = "4")]
+            pub protocol_version: u32,
+            #[prost(uint64, tag = "5")]
+            pub capabilities: u64,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+        #[repr(i32)]
+        pub enum Capability {
+            #[default]
+            Unspecified = 0,
+            ChunkedModelDownload = 1,
+            ConfigWatch = 2,
+            Heartbeat = 3,
+            TrainingSamples = 4,
+            DeploymentAnomalies = 5,
         }
 
         #[derive(Clone, PartialEq, Message)]
@@ -42,6 +63,66 @@ pub mod predictor {
             pub sync_interval_seconds: i32,
             #[prost(bool, tag = "4")]
             pub anomaly_detection_enabled: bool,
+            #[prost(int32, repeated, tag = "5")]
+            pub disabled_anomaly_types: Vec<i32>,
+            #[prost(string, repeated, tag = "6")]
+            pub include_namespaces: Vec<String>,
+            #[prost(string, repeated, tag = "7")]
+            pub exclude_namespaces: Vec<String>,
+            #[prost(message, repeated, tag = "8")]
+            pub alert_routes: Vec<AlertRoute>,
+            #[prost(message, optional, tag = "9")]
+            pub privacy: Option<PrivacyConfig>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct PrivacyConfig {
+            #[prost(bool, tag = "1")]
+            pub hash_names: bool,
+            #[prost(bytes = "vec", tag = "2")]
+            pub key: Vec<u8>,
+            #[prost(string, tag = "3")]
+            pub key_id: String,
+            #[prost(map = "string, bytes", tag = "4")]
+            pub namespace_keys: std::collections::HashMap<String, Vec<u8>>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct AlertRoute {
+            #[prost(string, repeated, tag = "1")]
+            pub namespaces: Vec<String>,
+            #[prost(map = "string, string", tag = "2")]
+            pub match_labels: std::collections::HashMap<String, String>,
+            #[prost(map = "string, string", tag = "3")]
+            pub labels: std::collections::HashMap<String, String>,
+            #[prost(string, repeated, tag = "4")]
+            pub webhook_urls: Vec<String>,
+            #[prost(string, tag = "5")]
+            pub slack_webhook_url: String,
+            #[prost(string, tag = "6")]
+            pub slack_channel: String,
+            #[prost(bool, tag = "7")]
+            pub continue_matching: bool,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct WatchConfigRequest {
+            #[prost(string, tag = "1")]
+            pub agent_id: String,
+            #[prost(string, tag = "2")]
+            pub node_name: String,
+            #[prost(int64, tag = "3")]
+            pub applied_version: i64,
+            #[prost(string, tag = "4")]
+            pub error: String,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct ConfigUpdate {
+            #[prost(int64, tag = "1")]
+            pub version: i64,
+            #[prost(message, optional, tag = "2")]
+            pub config: Option<AgentConfig>,
         }
 
         #[derive(Clone, PartialEq, Message)]
@@ -58,6 +139,68 @@ pub mod predictor {
             pub predictions: Vec<ResourceProfile>,
             #[prost(message, repeated, tag = "6")]
             pub anomalies: Vec<Anomaly>,
+            #[prost(message, repeated, tag = "7")]
+            pub container_identities: Vec<ContainerIdentity>,
+            #[prost(message, optional, tag = "8")]
+            pub node_metrics: Option<NodeMetrics>,
+            #[prost(message, repeated, tag = "9")]
+            pub pod_profiles: Vec<PodProfile>,
+            #[prost(message, optional, tag = "10")]
+            pub data_gap: Option<DataGap>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct DataGap {
+            #[prost(message, optional, tag = "1")]
+            pub start: Option<prost_types::Timestamp>,
+            #[prost(message, optional, tag = "2")]
+            pub end: Option<prost_types::Timestamp>,
+            #[prost(uint64, tag = "3")]
+            pub dropped_metrics: u64,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct NodeMetrics {
+            #[prost(message, optional, tag = "1")]
+            pub timestamp: Option<prost_types::Timestamp>,
+            #[prost(float, tag = "2")]
+            pub cpu_allocatable_cores: f32,
+            #[prost(uint64, tag = "3")]
+            pub memory_allocatable_bytes: u64,
+            #[prost(float, tag = "4")]
+            pub cpu_usage_cores: f32,
+            #[prost(uint64, tag = "5")]
+            pub memory_usage_bytes: u64,
+            #[prost(float, tag = "6")]
+            pub cpu_pressure: f32,
+            #[prost(float, tag = "7")]
+            pub memory_pressure: f32,
+            #[prost(float, tag = "8")]
+            pub io_pressure: f32,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct WorkloadRef {
+            #[prost(string, tag = "1")]
+            pub kind: String,
+            #[prost(string, tag = "2")]
+            pub name: String,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct ContainerIdentity {
+            #[prost(uint32, tag = "1")]
+            pub r#ref: u32,
+            #[prost(string, tag = "2")]
+            pub container_id: String,
+            #[prost(string, tag = "3")]
+            pub pod_name: String,
+            #[prost(string, tag = "4")]
+            pub namespace: String,
+            #[prost(string, tag = "5")]
+            pub deployment: String,
+            #[prost(message, optional, tag = "6")]
+            pub owner: Option<WorkloadRef>,
         }
 
         // Type alias for backward compatibility
@@ -93,6 +236,10 @@ pub mod predictor {
             pub network_rx_bytes: u64,
             #[prost(uint64, tag = "14")]
             pub network_tx_bytes: u64,
+            #[prost(uint32, tag = "15")]
+            pub container_ref: u32,
+            #[prost(message, optional, tag = "16")]
+            pub owner: Option<WorkloadRef>,
         }
 
         #[derive(Clone, PartialEq, Message)]
@@ -121,6 +268,44 @@ pub mod predictor {
             pub generated_at: Option<prost_types::Timestamp>,
             #[prost(int32, tag = "12")]
             pub time_window: i32,
+            #[prost(string, tag = "13")]
+            pub workload_kind: String,
+            #[prost(message, optional, tag = "14")]
+            pub owner: Option<WorkloadRef>,
+            #[prost(string, tag = "15")]
+            pub container_name: String,
+            #[prost(bool, tag = "16")]
+            pub cpu_limit_unset: bool,
+            #[prost(bool, tag = "17")]
+            pub memory_limit_unset: bool,
+            #[prost(string, tag = "18")]
+            pub workload_cluster: String,
+            #[prost(bool, tag = "19")]
+            pub spot_node: bool,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct PodProfile {
+            #[prost(string, tag = "1")]
+            pub pod_name: String,
+            #[prost(string, tag = "2")]
+            pub namespace: String,
+            #[prost(message, optional, tag = "3")]
+            pub owner: Option<WorkloadRef>,
+            #[prost(uint32, tag = "4")]
+            pub cpu_request_millicores: u32,
+            #[prost(uint64, tag = "5")]
+            pub memory_request_bytes: u64,
+            #[prost(uint32, tag = "6")]
+            pub cpu_limit_millicores: u32,
+            #[prost(uint64, tag = "7")]
+            pub memory_limit_bytes: u64,
+            #[prost(float, tag = "8")]
+            pub confidence: f32,
+            #[prost(message, optional, tag = "9")]
+            pub generated_at: Option<prost_types::Timestamp>,
+            #[prost(message, repeated, tag = "10")]
+            pub containers: Vec<ResourceProfile>,
         }
 
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
@@ -181,6 +366,44 @@ pub mod predictor {
             Critical = 2,
         }
 
+        #[derive(Clone, PartialEq, Message)]
+        pub struct DeploymentAnomaly {
+            #[prost(string, tag = "1")]
+            pub namespace: String,
+            #[prost(string, tag = "2")]
+            pub deployment: String,
+            #[prost(int32, tag = "3")]
+            pub r#type: i32,
+            #[prost(int32, tag = "4")]
+            pub severity: i32,
+            #[prost(int32, tag = "5")]
+            pub affected_replicas: i32,
+            #[prost(string, repeated, tag = "6")]
+            pub affected_pods: Vec<String>,
+            #[prost(message, optional, tag = "7")]
+            pub first_detected_at: Option<prost_types::Timestamp>,
+            #[prost(message, optional, tag = "8")]
+            pub last_detected_at: Option<prost_types::Timestamp>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct ReportDeploymentAnomalyRequest {
+            #[prost(string, tag = "1")]
+            pub agent_id: String,
+            #[prost(string, tag = "2")]
+            pub node_name: String,
+            #[prost(message, optional, tag = "3")]
+            pub anomaly: Option<DeploymentAnomaly>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct ReportDeploymentAnomalyResponse {
+            #[prost(bool, tag = "1")]
+            pub success: bool,
+            #[prost(string, tag = "2")]
+            pub message: String,
+        }
+
         #[derive(Clone, PartialEq, Message)]
         pub struct SyncMetricsResponse {
             #[prost(bool, tag = "1")]
@@ -219,11 +442,41 @@ pub mod predictor {
             pub checksum: String,
             #[prost(message, optional, tag = "5")]
             pub metadata: Option<ModelMetadata>,
+            #[prost(bool, tag = "6")]
+            pub download_required: bool,
+            #[prost(bytes = "vec", tag = "7")]
+            pub signature: Vec<u8>,
         }
 
         // Type alias for backward compatibility
         pub type ModelResponse = GetModelUpdateResponse;
 
+        #[derive(Clone, PartialEq, Message)]
+        pub struct DownloadModelRequest {
+            #[prost(string, tag = "1")]
+            pub agent_id: String,
+            #[prost(string, tag = "2")]
+            pub version: String,
+            #[prost(int64, tag = "3")]
+            pub offset: i64,
+            #[prost(int32, tag = "4")]
+            pub chunk_size: i32,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct ModelChunk {
+            #[prost(int64, tag = "1")]
+            pub offset: i64,
+            #[prost(bytes = "vec", tag = "2")]
+            pub data: Vec<u8>,
+            #[prost(string, tag = "3")]
+            pub chunk_checksum: String,
+            #[prost(int64, tag = "4")]
+            pub total_size: i64,
+            #[prost(bool, tag = "5")]
+            pub last: bool,
+        }
+
         #[derive(Clone, PartialEq, Message)]
         pub struct ModelMetadata {
             #[prost(string, tag = "1")]
@@ -262,6 +515,133 @@ pub mod predictor {
         // Type alias for backward compatibility
         pub type GradientsResponse = UploadGradientsResponse;
 
+        #[derive(Clone, PartialEq, Message)]
+        pub struct TrainingSample {
+            #[prost(float, repeated, tag = "1")]
+            pub features: Vec<f32>,
+            #[prost(string, tag = "2")]
+            pub model_version: String,
+            #[prost(int32, tag = "3")]
+            pub predicted_cpu_request_millicores: i32,
+            #[prost(int32, tag = "4")]
+            pub predicted_cpu_limit_millicores: i32,
+            #[prost(int64, tag = "5")]
+            pub predicted_memory_request_bytes: i64,
+            #[prost(int64, tag = "6")]
+            pub predicted_memory_limit_bytes: i64,
+            #[prost(float, tag = "7")]
+            pub confidence: f32,
+            #[prost(float, tag = "8")]
+            pub actual_cpu_mean_cores: f32,
+            #[prost(float, tag = "9")]
+            pub actual_cpu_peak_cores: f32,
+            #[prost(int64, tag = "10")]
+            pub actual_memory_peak_bytes: i64,
+            #[prost(int32, tag = "11")]
+            pub horizon_seconds: i32,
+            #[prost(float, tag = "12")]
+            pub node_volatility: f32,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct UploadTrainingSamplesRequest {
+            #[prost(string, tag = "1")]
+            pub agent_id: String,
+            #[prost(message, repeated, tag = "2")]
+            pub samples: Vec<TrainingSample>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct UploadTrainingSamplesResponse {
+            #[prost(bool, tag = "1")]
+            pub success: bool,
+            #[prost(string, tag = "2")]
+            pub message: String,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct HeartbeatRequest {
+            #[prost(string, tag = "1")]
+            pub agent_id: String,
+            #[prost(string, tag = "2")]
+            pub node_name: String,
+            #[prost(string, tag = "3")]
+            pub agent_version: String,
+            #[prost(message, optional, tag = "4")]
+            pub timestamp: Option<prost_types::Timestamp>,
+            #[prost(message, optional, tag = "5")]
+            pub health: Option<AgentHealth>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct AgentHealth {
+            #[prost(string, tag = "1")]
+            pub status: String,
+            #[prost(int64, tag = "2")]
+            pub buffer_items: i64,
+            #[prost(int64, tag = "3")]
+            pub buffer_size_bytes: i64,
+            #[prost(message, optional, tag = "4")]
+            pub last_collection_at: Option<prost_types::Timestamp>,
+            #[prost(string, tag = "5")]
+            pub model_version: String,
+            #[prost(int64, tag = "6")]
+            pub containers_monitored: i64,
+            #[prost(int64, tag = "7")]
+            pub collection_errors: i64,
+            #[prost(int64, tag = "8")]
+            pub prediction_errors: i64,
+            #[prost(int64, tag = "9")]
+            pub sync_errors: i64,
+            #[prost(map = "string, string", tag = "10")]
+            pub unhealthy_components: std::collections::HashMap<String, String>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct HeartbeatResponse {
+            #[prost(bool, tag = "1")]
+            pub acknowledged: bool,
+            #[prost(int32, tag = "2")]
+            pub next_heartbeat_seconds: i32,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct GetMetricsSinceRequest {
+            #[prost(uint64, tag = "1")]
+            pub cursor: u64,
+            #[prost(uint32, tag = "2")]
+            pub max_samples: u32,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct GetMetricsSinceResponse {
+            #[prost(message, optional, tag = "1")]
+            pub batch: Option<SyncMetricsRequest>,
+            #[prost(uint64, tag = "2")]
+            pub next_cursor: u64,
+            #[prost(bool, tag = "3")]
+            pub has_more: bool,
+            #[prost(uint64, tag = "4")]
+            pub dropped: u64,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct GetPredictionsRequest {
+            #[prost(string, tag = "1")]
+            pub namespace: String,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        pub struct GetPredictionsResponse {
+            #[prost(string, tag = "1")]
+            pub agent_id: String,
+            #[prost(string, tag = "2")]
+            pub node_name: String,
+            #[prost(message, repeated, tag = "3")]
+            pub predictions: Vec<ResourceProfile>,
+        }
+
+        #[cfg(feature = "grpc")]
         pub mod predictor_sync_service_client {
             use super::*;
             use tonic::codegen::*;
@@ -312,6 +692,34 @@ pub mod predictor {
                     PredictorSyncServiceClient { inner }
                 }
 
+                /// Compress requests with the given encoding
+                #[must_use]
+                pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                    self.inner = self.inner.send_compressed(encoding);
+                    self
+                }
+
+                /// Enable decompressing responses
+                #[must_use]
+                pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                    self.inner = self.inner.accept_compressed(encoding);
+                    self
+                }
+
+                /// Limits the maximum size of a decoded message
+                #[must_use]
+                pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+                    self.inner = self.inner.max_decoding_message_size(limit);
+                    self
+                }
+
+                /// Limits the maximum size of an encoded message
+                #[must_use]
+                pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+                    self.inner = self.inner.max_encoding_message_size(limit);
+                    self
+                }
+
                 pub async fn register(
                     &mut self,
                     request: impl tonic::IntoRequest<RegisterRequest>,
@@ -366,6 +774,26 @@ pub mod predictor {
                     self.inner.unary(request.into_request(), path, codec).await
                 }
 
+                pub async fn download_model(
+                    &mut self,
+                    request: impl tonic::IntoRequest<DownloadModelRequest>,
+                ) -> Result<tonic::Response<tonic::codec::Streaming<ModelChunk>>, tonic::Status>
+                {
+                    self.inner.ready().await.map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                    let codec = tonic::codec::ProstCodec::default();
+                    let path = http::uri::PathAndQuery::from_static(
+                        "/predictor.v1.PredictorSyncService/DownloadModel",
+                    );
+                    self.inner
+                        .server_streaming(request.into_request(), path, codec)
+                        .await
+                }
+
                 pub async fn upload_gradients(
                     &mut self,
                     request: impl tonic::IntoRequest<UploadGradientsRequest>,
@@ -383,17 +811,279 @@ pub mod predictor {
                     );
                     self.inner.unary(request.into_request(), path, codec).await
                 }
+
+                pub async fn upload_training_samples(
+                    &mut self,
+                    request: impl tonic::IntoRequest<UploadTrainingSamplesRequest>,
+                ) -> Result<tonic::Response<UploadTrainingSamplesResponse>, tonic::Status>
+                {
+                    self.inner.ready().await.map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                    let codec = tonic::codec::ProstCodec::default();
+                    let path = http::uri::PathAndQuery::from_static(
+                        "/predictor.v1.PredictorSyncService/UploadTrainingSamples",
+                    );
+                    self.inner.unary(request.into_request(), path, codec).await
+                }
+
+                pub async fn watch_config(
+                    &mut self,
+                    request: impl tonic::IntoStreamingRequest<Message = WatchConfigRequest>,
+                ) -> Result<tonic::Response<tonic::codec::Streaming<ConfigUpdate>>, tonic::Status>
+                {
+                    self.inner.ready().await.map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                    let codec = tonic::codec::ProstCodec::default();
+                    let path = http::uri::PathAndQuery::from_static(
+                        "/predictor.v1.PredictorSyncService/WatchConfig",
+                    );
+                    self.inner
+                        .streaming(request.into_streaming_request(), path, codec)
+                        .await
+                }
+
+                pub async fn heartbeat(
+                    &mut self,
+                    request: impl tonic::IntoRequest<HeartbeatRequest>,
+                ) -> Result<tonic::Response<HeartbeatResponse>, tonic::Status> {
+                    self.inner.ready().await.map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                    let codec = tonic::codec::ProstCodec::default();
+                    let path = http::uri::PathAndQuery::from_static(
+                        "/predictor.v1.PredictorSyncService/Heartbeat",
+                    );
+                    self.inner.unary(request.into_request(), path, codec).await
+                }
+
+                pub async fn report_deployment_anomaly(
+                    &mut self,
+                    request: impl tonic::IntoRequest<ReportDeploymentAnomalyRequest>,
+                ) -> Result<tonic::Response<ReportDeploymentAnomalyResponse>, tonic::Status>
+                {
+                    self.inner.ready().await.map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                    let codec = tonic::codec::ProstCodec::default();
+                    let path = http::uri::PathAndQuery::from_static(
+                        "/predictor.v1.PredictorSyncService/ReportDeploymentAnomaly",
+                    );
+                    self.inner.unary(request.into_request(), path, codec).await
+                }
+            }
+        }
+
+        #[cfg(feature = "grpc")]
+        pub mod predictor_agent_service_server {
+            use super::*;
+            use tonic::codegen::*;
+
+            /// Server side of `PredictorAgentService`, served by agents in
+            /// scrape mode
+            #[async_trait]
+            pub trait PredictorAgentService: Send + Sync + 'static {
+                async fn get_metrics_since(
+                    &self,
+                    request: tonic::Request<GetMetricsSinceRequest>,
+                ) -> Result<tonic::Response<GetMetricsSinceResponse>, tonic::Status>;
+
+                async fn get_predictions(
+                    &self,
+                    request: tonic::Request<GetPredictionsRequest>,
+                ) -> Result<tonic::Response<GetPredictionsResponse>, tonic::Status>;
+            }
+
+            #[derive(Debug)]
+            pub struct PredictorAgentServiceServer<T: PredictorAgentService> {
+                inner: Arc<T>,
+                accept_compression_encodings: EnabledCompressionEncodings,
+                send_compression_encodings: EnabledCompressionEncodings,
+                max_decoding_message_size: Option<usize>,
+                max_encoding_message_size: Option<usize>,
+            }
+
+            impl<T: PredictorAgentService> PredictorAgentServiceServer<T> {
+                pub fn new(inner: T) -> Self {
+                    Self::from_arc(Arc::new(inner))
+                }
+
+                pub fn from_arc(inner: Arc<T>) -> Self {
+                    Self {
+                        inner,
+                        accept_compression_encodings: Default::default(),
+                        send_compression_encodings: Default::default(),
+                        max_decoding_message_size: None,
+                        max_encoding_message_size: None,
+                    }
+                }
+
+                pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+                where
+                    F: tonic::service::Interceptor,
+                {
+                    InterceptedService::new(Self::new(inner), interceptor)
+                }
+
+                /// Enable decompressing requests with the given encoding
+                #[must_use]
+                pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                    self.accept_compression_encodings.enable(encoding);
+                    self
+                }
+
+                /// Compress responses with the given encoding, if the client supports it
+                #[must_use]
+                pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                    self.send_compression_encodings.enable(encoding);
+                    self
+                }
+
+                /// Limits the maximum size of a decoded message
+                #[must_use]
+                pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+                    self.max_decoding_message_size = Some(limit);
+                    self
+                }
+
+                /// Limits the maximum size of an encoded message
+                #[must_use]
+                pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+                    self.max_encoding_message_size = Some(limit);
+                    self
+                }
+
+                fn grpc<M, R>(&self) -> tonic::server::Grpc<tonic::codec::ProstCodec<M, R>>
+                where
+                    M: Message + Send + 'static,
+                    R: Message + Default + Send + 'static,
+                {
+                    tonic::server::Grpc::new(tonic::codec::ProstCodec::default())
+                        .apply_compression_config(
+                            self.accept_compression_encodings,
+                            self.send_compression_encodings,
+                        )
+                        .apply_max_message_size_config(
+                            self.max_decoding_message_size,
+                            self.max_encoding_message_size,
+                        )
+                }
+            }
+
+            struct GetMetricsSinceSvc<T: PredictorAgentService>(Arc<T>);
+
+            impl<T: PredictorAgentService> tonic::server::UnaryService<GetMetricsSinceRequest>
+                for GetMetricsSinceSvc<T>
+            {
+                type Response = GetMetricsSinceResponse;
+                type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+                fn call(
+                    &mut self,
+                    request: tonic::Request<GetMetricsSinceRequest>,
+                ) -> Self::Future {
+                    let inner = Arc::clone(&self.0);
+                    Box::pin(async move { inner.get_metrics_since(request).await })
+                }
+            }
+
+            struct GetPredictionsSvc<T: PredictorAgentService>(Arc<T>);
+
+            impl<T: PredictorAgentService> tonic::server::UnaryService<GetPredictionsRequest>
+                for GetPredictionsSvc<T>
+            {
+                type Response = GetPredictionsResponse;
+                type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+                fn call(&mut self, request: tonic::Request<GetPredictionsRequest>) -> Self::Future {
+                    let inner = Arc::clone(&self.0);
+                    Box::pin(async move { inner.get_predictions(request).await })
+                }
+            }
+
+            impl<T, B> tonic::codegen::Service<http::Request<B>> for PredictorAgentServiceServer<T>
+            where
+                T: PredictorAgentService,
+                B: Body + Send + 'static,
+                B::Error: Into<StdError> + Send + 'static,
+            {
+                type Response = http::Response<tonic::body::BoxBody>;
+                type Error = std::convert::Infallible;
+                type Future = BoxFuture<Self::Response, Self::Error>;
+
+                fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                    Poll::Ready(Ok(()))
+                }
+
+                fn call(&mut self, req: http::Request<B>) -> Self::Future {
+                    match req.uri().path() {
+                        "/predictor.v1.PredictorAgentService/GetMetricsSince" => {
+                            let method = GetMetricsSinceSvc(Arc::clone(&self.inner));
+                            let mut grpc = self.grpc();
+                            Box::pin(async move { Ok(grpc.unary(method, req).await) })
+                        }
+                        "/predictor.v1.PredictorAgentService/GetPredictions" => {
+                            let method = GetPredictionsSvc(Arc::clone(&self.inner));
+                            let mut grpc = self.grpc();
+                            Box::pin(async move { Ok(grpc.unary(method, req).await) })
+                        }
+                        _ => Box::pin(async move {
+                            Ok(http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap())
+                        }),
+                    }
+                }
+            }
+
+            impl<T: PredictorAgentService> Clone for PredictorAgentServiceServer<T> {
+                fn clone(&self) -> Self {
+                    Self {
+                        inner: Arc::clone(&self.inner),
+                        accept_compression_encodings: self.accept_compression_encodings,
+                        send_compression_encodings: self.send_compression_encodings,
+                        max_decoding_message_size: self.max_decoding_message_size,
+                        max_encoding_message_size: self.max_encoding_message_size,
+                    }
+                }
+            }
+
+            impl<T: PredictorAgentService> tonic::server::NamedService for PredictorAgentServiceServer<T> {
+                const NAME: &'static str = "predictor.v1.PredictorAgentService";
             }
         }
 
         // Backward compatibility alias
+        #[cfg(feature = "grpc")]
         pub mod predictor_sync_client {
             pub use super::predictor_sync_service_client::PredictorSyncServiceClient as PredictorSyncClient;
         }
     }
 }
 
+#[cfg(feature = "grpc")]
+pub use predictor::v1::predictor_agent_service_server::{
+    PredictorAgentService, PredictorAgentServiceServer,
+};
+#[cfg(feature = "grpc")]
 pub use predictor::v1::predictor_sync_service_client::PredictorSyncServiceClient;
 // Backward compatibility alias
+#[cfg(feature = "grpc")]
 pub use predictor::v1::predictor_sync_client::PredictorSyncClient;
 pub use predictor::v1::*;
@@ -0,0 +1,618 @@
+//! Embeddable agent runtime
+//!
+//! `AgentRuntime` wires the collection loop, prediction scheduler, anomaly
+//! pipeline and, optionally, API sync together behind one start and one
+//! shutdown, so the agent can run inside other binaries and be tested as a
+//! whole. Predictions and detected anomalies are published to subscribers
+//! instead of being handled by the runtime itself. API sync needs the
+//! `grpc-sync` feature.
+
+use crate::anomaly::{
+    AnomalyPipeline, AnomalyRecord, AnomalyState, AnomalyStore, DetectedAnomaly, PipelineConfig,
+};
+use crate::collector::{CollectionLoopBuilder, ContainerRegistry, MetricsCollector};
+use crate::health::{components, HealthPolicy, HealthRegistry};
+use crate::intern::ContainerKey;
+use crate::live::LiveFeed;
+use crate::maintenance::MaintenanceStatus;
+use crate::node_lifecycle::NodeLifecycle;
+use crate::observability::AgentMetrics;
+use crate::predictor::{OnnxPredictor, PredictionConfig, PredictionResult, PredictionScheduler};
+#[cfg(feature = "grpc-sync")]
+use crate::sync::{
+    BufferConfig, MetricsStreamer, OfflineBufferManager, StreamingConfig, StreamingWorker,
+    SyncClient, SyncPipeline, SyncPipelineConfig,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Capacity of the prediction and anomaly broadcast channels
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Default collection interval
+const DEFAULT_COLLECTION_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Interval between leak checks per container, each over up to a day of
+/// samples
+const TREND_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Anomaly found by the runtime, with the container it was found in
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeAnomaly {
+    /// Timestamp of the sample that raised it (Unix seconds)
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub key: ContainerKey,
+    #[serde(flatten)]
+    pub anomaly: DetectedAnomaly,
+}
+
+/// Sending collected metrics to the recommendation API
+#[cfg(feature = "grpc-sync")]
+struct SyncSetup {
+    client: Arc<SyncClient>,
+    pipeline: SyncPipelineConfig,
+    streaming: StreamingConfig,
+    buffer: BufferConfig,
+}
+
+/// Builder for [`AgentRuntime`]
+pub struct AgentRuntimeBuilder {
+    node_name: Option<String>,
+    collector: Option<Arc<dyn MetricsCollector>>,
+    registry: Option<Arc<ContainerRegistry>>,
+    collection_interval: Duration,
+    predictor: Option<OnnxPredictor>,
+    prediction: PredictionConfig,
+    anomaly: Option<PipelineConfig>,
+    anomaly_store: Option<Arc<AnomalyStore>>,
+    #[cfg(feature = "grpc-sync")]
+    sync: Option<SyncSetup>,
+    health: Option<HealthRegistry>,
+    metrics: Option<AgentMetrics>,
+    node_lifecycle: Option<watch::Receiver<NodeLifecycle>>,
+    maintenance: Option<watch::Receiver<MaintenanceStatus>>,
+    live_feed: Option<LiveFeed>,
+}
+
+impl AgentRuntimeBuilder {
+    /// Create a new builder with default configuration
+    pub fn new() -> Self {
+        Self {
+            node_name: None,
+            collector: None,
+            registry: None,
+            collection_interval: DEFAULT_COLLECTION_INTERVAL,
+            predictor: None,
+            prediction: PredictionConfig::default(),
+            anomaly: None,
+            anomaly_store: None,
+            #[cfg(feature = "grpc-sync")]
+            sync: None,
+            health: None,
+            metrics: None,
+            node_lifecycle: None,
+            maintenance: None,
+            live_feed: None,
+        }
+    }
+
+    /// Set the name of the node the agent runs on
+    pub fn node_name(mut self, name: impl Into<String>) -> Self {
+        self.node_name = Some(name.into());
+        self
+    }
+
+    /// Set the metrics collector
+    pub fn collector(mut self, collector: Arc<dyn MetricsCollector>) -> Self {
+        self.collector = Some(collector);
+        self
+    }
+
+    /// Share a container registry, e.g. one fed by a discovery watcher
+    ///
+    /// An empty registry for the node is created otherwise.
+    pub fn registry(mut self, registry: Arc<ContainerRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Set the collection interval
+    pub fn collection_interval(mut self, interval: Duration) -> Self {
+        self.collection_interval = interval;
+        self
+    }
+
+    /// Set the predictor backend (the statistical fallback by default)
+    pub fn predictor(mut self, predictor: OnnxPredictor) -> Self {
+        self.predictor = Some(predictor);
+        self
+    }
+
+    /// Set the prediction scheduler configuration
+    pub fn prediction_config(mut self, config: PredictionConfig) -> Self {
+        self.prediction = config;
+        self
+    }
+
+    /// Run anomaly detection on collected metrics
+    pub fn anomaly_pipeline(mut self, config: PipelineConfig) -> Self {
+        self.anomaly = Some(config);
+        self
+    }
+
+    /// Record detected anomalies in `store`, e.g. the one served on /anomalies
+    pub fn anomaly_store(mut self, store: Arc<AnomalyStore>) -> Self {
+        self.anomaly_store = Some(store);
+        self
+    }
+
+    /// Stream collected metrics to the API through `client`
+    ///
+    /// Metrics are buffered locally while the API is unreachable.
+    #[cfg(feature = "grpc-sync")]
+    pub fn sync(mut self, client: Arc<SyncClient>) -> Self {
+        self.sync = Some(SyncSetup {
+            client,
+            pipeline: SyncPipelineConfig::default(),
+            streaming: StreamingConfig::default(),
+            buffer: BufferConfig::default(),
+        });
+        self
+    }
+
+    /// Set the sync pipeline, streaming and offline buffer configuration
+    ///
+    /// Only used together with [`sync`](Self::sync).
+    #[cfg(feature = "grpc-sync")]
+    pub fn sync_config(
+        mut self,
+        pipeline: SyncPipelineConfig,
+        streaming: StreamingConfig,
+        buffer: BufferConfig,
+    ) -> Self {
+        if let Some(sync) = &mut self.sync {
+            sync.pipeline = pipeline;
+            sync.streaming = streaming;
+            sync.buffer = buffer;
+        }
+        self
+    }
+
+    /// Report component health to `registry`
+    pub fn health(mut self, registry: HealthRegistry) -> Self {
+        self.health = Some(registry);
+        self
+    }
+
+    /// Record agent metrics in `metrics`
+    pub fn metrics(mut self, metrics: AgentMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Flag predictions made while the node is spot or preemptible
+    pub fn node_lifecycle(mut self, lifecycle: watch::Receiver<NodeLifecycle>) -> Self {
+        self.node_lifecycle = Some(lifecycle);
+        self
+    }
+
+    /// Pause predictions while the node is in maintenance
+    pub fn maintenance(mut self, maintenance: watch::Receiver<MaintenanceStatus>) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Publish collected samples on `feed`, e.g. the one served on /stream
+    pub fn live_feed(mut self, feed: LiveFeed) -> Self {
+        self.live_feed = Some(feed);
+        self
+    }
+
+    /// Build the runtime
+    pub fn build(self) -> Result<AgentRuntime> {
+        let node_name = self
+            .node_name
+            .ok_or_else(|| anyhow!("Node name is required"))?;
+        let collector = self
+            .collector
+            .ok_or_else(|| anyhow!("Collector is required"))?;
+        if let Some(anomaly) = &self.anomaly {
+            anomaly.validate()?;
+        }
+
+        let registry = self
+            .registry
+            .unwrap_or_else(|| Arc::new(ContainerRegistry::new(node_name.clone())));
+        let (predictions, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let (anomalies, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let (shutdown, _) = broadcast::channel(1);
+
+        Ok(AgentRuntime {
+            node_name,
+            registry,
+            scheduler: None,
+            #[cfg(feature = "grpc-sync")]
+            pipeline: None,
+            parts: Some(Parts {
+                collector,
+                collection_interval: self.collection_interval,
+                predictor: self
+                    .predictor
+                    .unwrap_or_else(OnnxPredictor::new_without_model),
+                prediction: self.prediction,
+                anomaly: self.anomaly,
+                anomaly_store: self.anomaly_store,
+                #[cfg(feature = "grpc-sync")]
+                sync: self.sync,
+                health: self.health,
+                metrics: self.metrics.unwrap_or_default(),
+                node_lifecycle: self.node_lifecycle,
+                maintenance: self.maintenance,
+                live_feed: self.live_feed,
+            }),
+            predictions,
+            anomalies,
+            shutdown,
+            tasks: Vec::new(),
+        })
+    }
+}
+
+impl Default for AgentRuntimeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Components waiting for the runtime to start
+struct Parts {
+    collector: Arc<dyn MetricsCollector>,
+    collection_interval: Duration,
+    predictor: OnnxPredictor,
+    prediction: PredictionConfig,
+    anomaly: Option<PipelineConfig>,
+    anomaly_store: Option<Arc<AnomalyStore>>,
+    #[cfg(feature = "grpc-sync")]
+    sync: Option<SyncSetup>,
+    health: Option<HealthRegistry>,
+    metrics: AgentMetrics,
+    node_lifecycle: Option<watch::Receiver<NodeLifecycle>>,
+    maintenance: Option<watch::Receiver<MaintenanceStatus>>,
+    live_feed: Option<LiveFeed>,
+}
+
+/// Collection, prediction, anomaly detection and sync running as one unit
+pub struct AgentRuntime {
+    node_name: String,
+    registry: Arc<ContainerRegistry>,
+    /// Set once started
+    scheduler: Option<Arc<PredictionScheduler>>,
+    /// Set once started with sync
+    #[cfg(feature = "grpc-sync")]
+    pipeline: Option<Arc<SyncPipeline>>,
+    /// Taken on start
+    parts: Option<Parts>,
+    predictions: broadcast::Sender<PredictionResult>,
+    anomalies: broadcast::Sender<RuntimeAnomaly>,
+    shutdown: broadcast::Sender<()>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl AgentRuntime {
+    /// Create a builder
+    pub fn builder() -> AgentRuntimeBuilder {
+        AgentRuntimeBuilder::new()
+    }
+
+    pub fn node_name(&self) -> &str {
+        &self.node_name
+    }
+
+    /// Registry of the containers metrics are collected from
+    pub fn registry(&self) -> &Arc<ContainerRegistry> {
+        &self.registry
+    }
+
+    /// Prediction scheduler, once started
+    pub fn scheduler(&self) -> Option<&Arc<PredictionScheduler>> {
+        self.scheduler.as_ref()
+    }
+
+    /// Sync pipeline and its offline buffer, once started with sync
+    #[cfg(feature = "grpc-sync")]
+    pub fn pipeline(&self) -> Option<&Arc<SyncPipeline>> {
+        self.pipeline.as_ref()
+    }
+
+    /// Whether the runtime has been started
+    pub fn is_running(&self) -> bool {
+        self.parts.is_none()
+    }
+
+    /// Receive prediction results
+    pub fn subscribe_predictions(&self) -> broadcast::Receiver<PredictionResult> {
+        self.predictions.subscribe()
+    }
+
+    /// Receive anomalies found by the anomaly pipeline
+    pub fn subscribe_anomalies(&self) -> broadcast::Receiver<RuntimeAnomaly> {
+        self.anomalies.subscribe()
+    }
+
+    /// Spawn all components
+    ///
+    /// Fails if the runtime was already started.
+    pub async fn start(&mut self) -> Result<()> {
+        let parts = self
+            .parts
+            .take()
+            .ok_or_else(|| anyhow!("Agent runtime already started"))?;
+
+        let mut collection = CollectionLoopBuilder::new()
+            .collector(parts.collector)
+            .registry(self.registry.clone())
+            .interval(parts.collection_interval);
+        if let Some(feed) = parts.live_feed {
+            collection = collection.live_feed(feed);
+        }
+        let (scheduler, mut prediction_rx) =
+            PredictionScheduler::new(Arc::new(RwLock::new(parts.predictor)), parts.prediction);
+        let mut scheduler = scheduler.with_metrics(parts.metrics.clone());
+        if let Some(lifecycle) = parts.node_lifecycle {
+            scheduler = scheduler.with_node_lifecycle(lifecycle);
+        }
+        if let Some(maintenance) = parts.maintenance {
+            scheduler = scheduler.with_maintenance(maintenance);
+        }
+        if let Some(health) = &parts.health {
+            health.register(components::COLLECTOR).await;
+            health.register(components::PREDICTOR).await;
+            collection = collection.health(
+                health
+                    .reporter(components::COLLECTOR, HealthPolicy::default())
+                    .await,
+            );
+            scheduler = scheduler.with_health(
+                health
+                    .reporter(components::PREDICTOR, HealthPolicy::default())
+                    .await,
+            );
+        }
+
+        // Predictions are deferred while the pipeline finds anomalies
+        let anomaly_state = AnomalyState::new();
+        let mut detector = parts
+            .anomaly
+            .map(|config| AnomalyPipeline::new(config).with_state(anomaly_state.clone()));
+        if detector.is_some() {
+            scheduler = scheduler.with_anomaly_state(anomaly_state);
+        }
+
+        let (collection, mut metrics_rx) = collection.build()?;
+        let scheduler = Arc::new(scheduler);
+        self.scheduler = Some(scheduler.clone());
+
+        #[cfg(feature = "grpc-sync")]
+        let pipeline = match parts.sync {
+            Some(sync) => Some(
+                self.start_sync(sync, parts.health.as_ref(), parts.metrics)
+                    .await,
+            ),
+            None => None,
+        };
+        #[cfg(feature = "grpc-sync")]
+        self.pipeline.clone_from(&pipeline);
+
+        self.tasks
+            .push(tokio::spawn(collection.run(self.shutdown.subscribe())));
+        self.tasks.push(tokio::spawn(
+            scheduler.clone().run(self.shutdown.subscribe()),
+        ));
+
+        // Ends once the collection loop stops and drops its sender
+        let anomalies = self.anomalies.clone();
+        let anomaly_store = parts.anomaly_store;
+        self.tasks.push(tokio::spawn(async move {
+            while let Some(metrics) = metrics_rx.recv().await {
+                if let Some(detector) = &mut detector {
+                    let mut found = detector.observe(&metrics);
+                    let (container_id, timestamp) = (&metrics.container_id, metrics.timestamp);
+                    if detector.trends_due(container_id, timestamp, TREND_CHECK_INTERVAL) {
+                        found.extend(detector.check_trends(container_id));
+                    }
+                    for anomaly in found {
+                        if let Some(store) = &anomaly_store {
+                            if let Some(record) = AnomalyRecord::detected(&metrics, &anomaly) {
+                                store.record(record);
+                            }
+                        }
+                        let _ = anomalies.send(RuntimeAnomaly {
+                            timestamp: metrics.timestamp,
+                            key: metrics.key(),
+                            anomaly,
+                        });
+                    }
+                }
+                #[cfg(feature = "grpc-sync")]
+                if let Some(pipeline) = &pipeline {
+                    pipeline.submit_metrics(vec![metrics.clone()]).await;
+                }
+                scheduler.add_metrics(metrics).await;
+            }
+        }));
+
+        // Drain results so the scheduler never blocks on a full channel
+        let predictions = self.predictions.clone();
+        let mut shutdown = self.shutdown.subscribe();
+        self.tasks.push(tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    result = prediction_rx.recv() => match result {
+                        Some(result) => {
+                            let _ = predictions.send(result);
+                        }
+                        None => break,
+                    },
+                    _ = shutdown.recv() => break,
+                }
+            }
+        }));
+
+        info!(node_name = %self.node_name, "Agent runtime started");
+        Ok(())
+    }
+
+    /// Spawn the streaming worker and sync pipeline
+    #[cfg(feature = "grpc-sync")]
+    async fn start_sync(
+        &mut self,
+        sync: SyncSetup,
+        health: Option<&HealthRegistry>,
+        metrics: AgentMetrics,
+    ) -> Arc<SyncPipeline> {
+        let (streamer, receiver) = MetricsStreamer::new(
+            sync.streaming.clone(),
+            sync.client.agent_id().to_string(),
+            self.node_name.clone(),
+        );
+        let mut worker = StreamingWorker::new(
+            sync.streaming,
+            sync.client.agent_id().to_string(),
+            self.node_name.clone(),
+            receiver,
+            streamer.stats_handle(),
+            streamer.overflow_handle(),
+        );
+        let mut buffer = OfflineBufferManager::new(sync.buffer);
+        if let Some(health) = health {
+            health.register(components::SYNC_CLIENT).await;
+            health.register(components::BUFFER).await;
+            worker = worker.with_health(
+                health
+                    .reporter(components::SYNC_CLIENT, HealthPolicy::default())
+                    .await,
+            );
+            buffer = buffer.with_health(
+                health
+                    .reporter(components::BUFFER, HealthPolicy::default())
+                    .await,
+            );
+        }
+        let pipeline = Arc::new(SyncPipeline::new(sync.pipeline, streamer, buffer, metrics));
+        worker = worker.with_offline_buffer(pipeline.buffer_handle());
+
+        let client = sync.client.clone();
+        let mut shutdown = self.shutdown.subscribe();
+        self.tasks.push(tokio::spawn(async move {
+            tokio::select! {
+                _ = worker.run(client) => {}
+                _ = shutdown.recv() => {}
+            }
+        }));
+        let running = pipeline.clone();
+        let shutdown = self.shutdown.subscribe();
+        self.tasks.push(tokio::spawn(async move {
+            running.run(sync.client, shutdown).await;
+        }));
+        pipeline
+    }
+
+    /// Stop all components and wait for them to finish
+    pub async fn shutdown(mut self) {
+        let _ = self.shutdown.send(());
+        for task in self.tasks.drain(..) {
+            if let Err(e) = task.await {
+                warn!(error = %e, "Agent runtime task failed");
+            }
+        }
+        info!(node_name = %self.node_name, "Agent runtime stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::{async_trait, CollectorError};
+    use crate::models::{ContainerInfo, ContainerMetrics, ContainerRole};
+
+    struct MockCollector;
+
+    #[async_trait]
+    impl MetricsCollector for MockCollector {
+        async fn collect(&self, container_id: &str) -> Result<ContainerMetrics, CollectorError> {
+            Ok(ContainerMetrics {
+                container_id: container_id.to_string(),
+                pod_name: "test-pod".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                timestamp: chrono::Utc::now().timestamp(),
+                cpu_usage_cores: 0.5,
+                cpu_throttled_periods: 0,
+                memory_usage_bytes: 100_000_000,
+                memory_working_set_bytes: 80_000_000,
+                memory_cache_bytes: 20_000_000,
+                network_rx_bytes: 1000,
+                network_tx_bytes: 500,
+            })
+        }
+
+        async fn list_containers(&self) -> Result<Vec<ContainerInfo>, CollectorError> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn test_builder_requires_collector() {
+        let result = AgentRuntime::builder().node_name("test-node").build();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_runtime_collects_until_shutdown() {
+        let health = HealthRegistry::new();
+        let mut runtime = AgentRuntime::builder()
+            .node_name("test-node")
+            .collector(Arc::new(MockCollector))
+            .collection_interval(Duration::from_millis(10))
+            .anomaly_pipeline(PipelineConfig::default())
+            .health(health.clone())
+            .build()
+            .unwrap();
+        runtime.registry().register(ContainerInfo {
+            container_id: "container1".to_string(),
+            pod_name: "test-pod".to_string(),
+            namespace: "default".to_string(),
+            owner: None,
+            node_name: String::new(),
+            cgroup_path: "/test/path1".to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
+        });
+
+        runtime.start().await.unwrap();
+        assert!(runtime.start().await.is_err());
+
+        let scheduler = runtime.scheduler().unwrap().clone();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while scheduler.stats().await.total_samples < 3 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("samples should reach the scheduler");
+        assert!(health
+            .since_heartbeat(components::COLLECTOR)
+            .await
+            .is_some());
+
+        tokio::time::timeout(Duration::from_secs(5), runtime.shutdown())
+            .await
+            .expect("runtime should stop");
+    }
+}
@@ -0,0 +1,403 @@
+//! Enforcement of the agent's own resource budget
+//!
+//! The agent shares the node with the workloads it watches, so it must stay
+//! cheap even when a node runs many containers. The `SelfLimiter` samples the
+//! agent's own CPU and RSS and, while they stay over budget, sheds work one
+//! level at a time, in this order:
+//!
+//! 1. `ReducedFrequency`: collection and prediction intervals are doubled
+//! 2. `PredictionPaused`: no inference runs, metrics are still collected
+//! 3. `MinimalCollection`: collection interval is quadrupled and collected
+//!    metrics are no longer enriched with container metadata
+//!
+//! Levels are lifted again, one at a time, once usage stays comfortably
+//! under budget.
+
+use crate::health::ComponentReporter;
+use crate::observability::AgentMetrics;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Default CPU budget in millicores
+pub const DEFAULT_CPU_BUDGET_MILLICORES: f64 = 50.0;
+
+/// Default RSS budget (75% of the chart's 64Mi limit)
+pub const DEFAULT_MEMORY_BUDGET_BYTES: u64 = 48 * 1024 * 1024;
+
+/// Clock ticks per second used by /proc/<pid>/stat (USER_HZ, fixed on Linux)
+const USER_HZ: f64 = 100.0;
+
+/// How far the agent sheds work to stay within budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DegradationLevel {
+    #[default]
+    Normal,
+    ReducedFrequency,
+    PredictionPaused,
+    MinimalCollection,
+}
+
+impl DegradationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DegradationLevel::Normal => "normal",
+            DegradationLevel::ReducedFrequency => "reduced_frequency",
+            DegradationLevel::PredictionPaused => "prediction_paused",
+            DegradationLevel::MinimalCollection => "minimal_collection",
+        }
+    }
+
+    /// Value exported by the degradation level gauge
+    pub fn gauge_value(&self) -> i64 {
+        *self as i64
+    }
+
+    /// Factor applied to collection and prediction intervals
+    pub fn interval_multiplier(&self) -> u32 {
+        match self {
+            DegradationLevel::Normal => 1,
+            DegradationLevel::ReducedFrequency | DegradationLevel::PredictionPaused => 2,
+            DegradationLevel::MinimalCollection => 4,
+        }
+    }
+
+    /// Whether predictions may run
+    pub fn prediction_enabled(&self) -> bool {
+        *self < DegradationLevel::PredictionPaused
+    }
+
+    /// Whether collected metrics are enriched with container metadata
+    pub fn metadata_enabled(&self) -> bool {
+        *self < DegradationLevel::MinimalCollection
+    }
+
+    fn escalate(self) -> Self {
+        match self {
+            DegradationLevel::Normal => DegradationLevel::ReducedFrequency,
+            DegradationLevel::ReducedFrequency => DegradationLevel::PredictionPaused,
+            DegradationLevel::PredictionPaused | DegradationLevel::MinimalCollection => {
+                DegradationLevel::MinimalCollection
+            }
+        }
+    }
+
+    fn relax(self) -> Self {
+        match self {
+            DegradationLevel::Normal | DegradationLevel::ReducedFrequency => {
+                DegradationLevel::Normal
+            }
+            DegradationLevel::PredictionPaused => DegradationLevel::ReducedFrequency,
+            DegradationLevel::MinimalCollection => DegradationLevel::PredictionPaused,
+        }
+    }
+}
+
+/// Configuration for the self limiter
+#[derive(Debug, Clone)]
+pub struct SelfLimiterConfig {
+    /// CPU budget in millicores
+    pub cpu_budget_millicores: f64,
+    /// Resident memory budget in bytes
+    pub memory_budget_bytes: u64,
+    /// Time between usage samples
+    pub check_interval: Duration,
+    /// Consecutive over-budget samples before shedding the next level
+    pub escalate_after: u32,
+    /// Consecutive comfortable samples before restoring a level
+    pub relax_after: u32,
+    /// Fraction of the budget usage must stay under to count as comfortable
+    pub relax_margin: f64,
+}
+
+impl Default for SelfLimiterConfig {
+    fn default() -> Self {
+        Self {
+            cpu_budget_millicores: DEFAULT_CPU_BUDGET_MILLICORES,
+            memory_budget_bytes: DEFAULT_MEMORY_BUDGET_BYTES,
+            check_interval: Duration::from_secs(10),
+            escalate_after: 3,
+            relax_after: 6,
+            relax_margin: 0.7,
+        }
+    }
+}
+
+/// The agent's own resource usage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfUsage {
+    pub cpu_millicores: f64,
+    pub rss_bytes: u64,
+}
+
+/// Keeps the agent's own CPU and memory within budget
+pub struct SelfLimiter {
+    config: SelfLimiterConfig,
+    proc_path: PathBuf,
+    level: watch::Sender<DegradationLevel>,
+    /// Previous CPU time sample (taken at, clock ticks)
+    last_cpu: Option<(Instant, u64)>,
+    over_budget: u32,
+    under_budget: u32,
+    metrics: Option<AgentMetrics>,
+    health: Option<ComponentReporter>,
+}
+
+impl SelfLimiter {
+    /// Create a limiter for the current process
+    pub fn new(config: SelfLimiterConfig) -> Self {
+        let (level, _) = watch::channel(DegradationLevel::Normal);
+        Self {
+            config,
+            proc_path: PathBuf::from("/proc/self"),
+            level,
+            last_cpu: None,
+            over_budget: 0,
+            under_budget: 0,
+            metrics: None,
+            health: None,
+        }
+    }
+
+    /// Read usage from another /proc/<pid> directory (for testing)
+    pub fn with_proc_path(mut self, proc_path: impl Into<PathBuf>) -> Self {
+        self.proc_path = proc_path.into();
+        self
+    }
+
+    /// Export the degradation level as a metric
+    pub fn with_metrics(mut self, metrics: AgentMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Report degradation through `reporter`
+    pub fn with_health(mut self, reporter: ComponentReporter) -> Self {
+        self.health = Some(reporter);
+        self
+    }
+
+    /// Receive the degradation level whenever it changes
+    pub fn subscribe(&self) -> watch::Receiver<DegradationLevel> {
+        self.level.subscribe()
+    }
+
+    /// Current degradation level
+    pub fn level(&self) -> DegradationLevel {
+        *self.level.borrow()
+    }
+
+    /// Sample usage and adjust the level until shutdown
+    pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
+        info!(
+            cpu_budget_millicores = self.config.cpu_budget_millicores,
+            memory_budget_bytes = self.config.memory_budget_bytes,
+            "Starting self resource limiter"
+        );
+
+        let mut ticker = tokio::time::interval(self.config.check_interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => match self.sample() {
+                    Ok(Some(usage)) => {
+                        self.observe(usage);
+                    }
+                    Ok(None) => {}
+                    Err(e) => debug!(error = %e, "Failed to sample own resource usage"),
+                },
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+
+    /// Read current usage; CPU needs two samples, so the first returns None
+    pub fn sample(&mut self) -> Result<Option<SelfUsage>> {
+        let ticks = read_cpu_ticks(&self.proc_path.join("stat"))?;
+        let rss_bytes = read_rss_bytes(&self.proc_path.join("status"))?;
+        let now = Instant::now();
+
+        let previous = self.last_cpu.replace((now, ticks));
+        Ok(previous.and_then(|(at, previous_ticks)| {
+            let elapsed = now.duration_since(at).as_secs_f64();
+            (elapsed > 0.0).then(|| SelfUsage {
+                cpu_millicores: ticks.saturating_sub(previous_ticks) as f64 / USER_HZ / elapsed
+                    * 1000.0,
+                rss_bytes,
+            })
+        }))
+    }
+
+    /// Feed a usage sample, returning the resulting level
+    pub fn observe(&mut self, usage: SelfUsage) -> DegradationLevel {
+        let config = &self.config;
+        let cpu_ratio = usage.cpu_millicores / config.cpu_budget_millicores;
+        let memory_ratio = usage.rss_bytes as f64 / config.memory_budget_bytes as f64;
+        let ratio = cpu_ratio.max(memory_ratio);
+
+        let current = self.level();
+        let mut next = current;
+        if ratio > 1.0 {
+            self.under_budget = 0;
+            self.over_budget += 1;
+            if self.over_budget >= config.escalate_after {
+                self.over_budget = 0;
+                next = current.escalate();
+            }
+        } else if ratio < config.relax_margin {
+            self.over_budget = 0;
+            self.under_budget += 1;
+            if self.under_budget >= config.relax_after {
+                self.under_budget = 0;
+                next = current.relax();
+            }
+        } else {
+            self.over_budget = 0;
+            self.under_budget = 0;
+        }
+
+        if next != current {
+            if next > current {
+                warn!(
+                    level = next.as_str(),
+                    cpu_millicores = usage.cpu_millicores,
+                    rss_bytes = usage.rss_bytes,
+                    "Agent over resource budget, shedding work"
+                );
+            } else {
+                info!(level = next.as_str(), "Agent back within resource budget");
+            }
+            self.level.send_replace(next);
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.set_degradation_level(next);
+        }
+        if let Some(health) = &self.health {
+            health.set_condition((next != DegradationLevel::Normal).then(|| {
+                format!(
+                    "Over resource budget, degraded to {} ({:.0}m CPU, {} MiB RSS)",
+                    next.as_str(),
+                    usage.cpu_millicores,
+                    usage.rss_bytes / (1024 * 1024)
+                )
+            }));
+            health.heartbeat();
+        }
+        next
+    }
+}
+
+/// Total user and system CPU time from /proc/<pid>/stat, in clock ticks
+fn read_cpu_ticks(path: &Path) -> Result<u64> {
+    let stat =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    parse_cpu_ticks(&stat).with_context(|| format!("Malformed {:?}", path))
+}
+
+fn parse_cpu_ticks(stat: &str) -> Result<u64> {
+    // The command name may contain spaces, so fields are counted after its
+    // closing parenthesis; utime and stime are fields 14 and 15
+    let rest = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| anyhow::anyhow!("Missing command name"))?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let field = |index: usize| -> Result<u64> {
+        fields
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("Missing field {}", index + 3))?
+            .parse()
+            .context("Invalid CPU time")
+    };
+    // Fields after the command name start at field 3
+    Ok(field(11)? + field(12)?)
+}
+
+/// Resident set size from /proc/<pid>/status
+fn read_rss_bytes(path: &Path) -> Result<u64> {
+    let status =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    parse_rss_bytes(&status).with_context(|| format!("Malformed {:?}", path))
+}
+
+fn parse_rss_bytes(status: &str) -> Result<u64> {
+    let line = status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .ok_or_else(|| anyhow::anyhow!("Missing VmRSS"))?;
+    let kib: u64 = line
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!("Missing VmRSS value"))?
+        .parse()
+        .context("Invalid VmRSS value")?;
+    Ok(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(cpu_millicores: f64, rss_mib: u64) -> SelfUsage {
+        SelfUsage {
+            cpu_millicores,
+            rss_bytes: rss_mib * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_parse_proc_files() {
+        let stat = "1234 (resource agent) S 1 1234 1234 0 -1 4194560 5000 0 0 0 \
+                    250 75 0 0 20 0 8 0 100 50000000 4000 18446744073709551615";
+        assert_eq!(parse_cpu_ticks(stat).unwrap(), 325);
+        assert!(parse_cpu_ticks("garbage").is_err());
+
+        let status = "Name:\tresource-agent\nVmPeak:\t  60000 kB\nVmRSS:\t   20480 kB\n";
+        assert_eq!(parse_rss_bytes(status).unwrap(), 20 * 1024 * 1024);
+        assert!(parse_rss_bytes("Name:\tx\n").is_err());
+    }
+
+    #[test]
+    fn test_levels_escalate_and_relax_in_order() {
+        let mut limiter = SelfLimiter::new(SelfLimiterConfig {
+            escalate_after: 2,
+            relax_after: 2,
+            ..Default::default()
+        });
+        let over = usage(80.0, 10);
+        let comfortable = usage(10.0, 10);
+
+        // A single spike doesn't shed work
+        assert_eq!(limiter.observe(over), DegradationLevel::Normal);
+        assert_eq!(limiter.observe(comfortable), DegradationLevel::Normal);
+
+        let mut levels = Vec::new();
+        for _ in 0..8 {
+            levels.push(limiter.observe(over));
+        }
+        assert_eq!(levels[1], DegradationLevel::ReducedFrequency);
+        assert_eq!(levels[3], DegradationLevel::PredictionPaused);
+        assert_eq!(levels[7], DegradationLevel::MinimalCollection);
+        assert!(!limiter.level().prediction_enabled());
+        assert!(!limiter.level().metadata_enabled());
+
+        // Memory alone can hold the level
+        assert_eq!(
+            limiter.observe(usage(10.0, 60)),
+            DegradationLevel::MinimalCollection
+        );
+
+        limiter.observe(comfortable);
+        assert_eq!(
+            limiter.observe(comfortable),
+            DegradationLevel::PredictionPaused
+        );
+        for _ in 0..4 {
+            limiter.observe(comfortable);
+        }
+        assert_eq!(limiter.level(), DegradationLevel::Normal);
+    }
+}
@@ -0,0 +1,285 @@
+//! Simulation (dry-run) reports
+//!
+//! In simulation mode the agent collects, predicts and detects anomalies as
+//! usual but never syncs or alerts. `SimulationRecorder` gathers what it
+//! would have done, the latest recommendation per container and every
+//! anomaly, into a `SimulationReport` written to a local JSON file, so
+//! Kubewise can be evaluated on a cluster before it is trusted.
+
+use crate::models::{OwnerRef, ResourceProfile};
+use crate::predictor::PredictionResult;
+use crate::runtime::RuntimeAnomaly;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, info, warn};
+
+/// Default interval between report writes
+pub const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Anomalies kept in a report, later ones are only counted
+pub const MAX_REPORTED_ANOMALIES: usize = 10_000;
+
+/// Recommendation the agent would have sent for a container
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedRecommendation {
+    pub container_id: String,
+    pub pod_name: String,
+    pub namespace: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<OwnerRef>,
+    /// Latest predicted profile
+    pub profile: ResourceProfile,
+    /// Predictions made for the container
+    pub predictions: u64,
+}
+
+/// What the agent would have done since it started
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub node_name: String,
+    /// Unix time the simulation started
+    pub started_at: i64,
+    /// Unix time the report was written
+    pub generated_at: i64,
+    /// Predictions made, including superseded ones
+    pub predictions: u64,
+    /// Predictions skipped, e.g. for lack of samples
+    pub skipped_predictions: u64,
+    /// Anomalies beyond `MAX_REPORTED_ANOMALIES`, not listed
+    pub dropped_anomalies: u64,
+    /// Events missed because the recorder fell behind
+    pub missed_events: u64,
+    /// Latest recommendation per container, by namespace and pod
+    pub recommendations: Vec<SimulatedRecommendation>,
+    /// Anomalies in the order they were detected
+    pub anomalies: Vec<RuntimeAnomaly>,
+}
+
+/// Gathers predictions and anomalies into a simulation report
+pub struct SimulationRecorder {
+    node_name: String,
+    path: PathBuf,
+    started_at: i64,
+    recommendations: BTreeMap<String, SimulatedRecommendation>,
+    anomalies: Vec<RuntimeAnomaly>,
+    predictions: u64,
+    skipped_predictions: u64,
+    dropped_anomalies: u64,
+    missed_events: u64,
+}
+
+impl SimulationRecorder {
+    /// Recorder writing its report to `path`
+    pub fn new(node_name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            node_name: node_name.into(),
+            path: path.into(),
+            started_at: chrono::Utc::now().timestamp(),
+            recommendations: BTreeMap::new(),
+            anomalies: Vec::new(),
+            predictions: 0,
+            skipped_predictions: 0,
+            dropped_anomalies: 0,
+            missed_events: 0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a prediction, replacing the container's previous one
+    pub fn record_prediction(&mut self, result: PredictionResult) {
+        let Some(profile) = result.profile else {
+            self.skipped_predictions += 1;
+            return;
+        };
+        self.predictions += 1;
+        let predictions = self
+            .recommendations
+            .get(&result.container_id)
+            .map_or(0, |previous| previous.predictions);
+        self.recommendations.insert(
+            result.container_id.clone(),
+            SimulatedRecommendation {
+                container_id: result.container_id,
+                pod_name: result.pod_name,
+                namespace: result.namespace,
+                owner: result.owner,
+                profile,
+                predictions: predictions + 1,
+            },
+        );
+    }
+
+    pub fn record_anomaly(&mut self, anomaly: RuntimeAnomaly) {
+        if self.anomalies.len() < MAX_REPORTED_ANOMALIES {
+            self.anomalies.push(anomaly);
+        } else {
+            self.dropped_anomalies += 1;
+        }
+    }
+
+    /// Report of everything recorded so far
+    pub fn report(&self) -> SimulationReport {
+        let mut recommendations: Vec<_> = self.recommendations.values().cloned().collect();
+        recommendations.sort_by(|a, b| {
+            (&a.namespace, &a.pod_name, &a.container_id).cmp(&(
+                &b.namespace,
+                &b.pod_name,
+                &b.container_id,
+            ))
+        });
+        SimulationReport {
+            node_name: self.node_name.clone(),
+            started_at: self.started_at,
+            generated_at: chrono::Utc::now().timestamp(),
+            predictions: self.predictions,
+            skipped_predictions: self.skipped_predictions,
+            dropped_anomalies: self.dropped_anomalies,
+            missed_events: self.missed_events,
+            recommendations,
+            anomalies: self.anomalies.clone(),
+        }
+    }
+
+    /// Write the report to the recorder's path
+    pub fn write(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        let json = serde_json::to_vec_pretty(&self.report())
+            .context("Failed to serialize simulation report")?;
+
+        // Write atomically so a reader never sees a partial report
+        let temp_path = self.path.with_extension("tmp");
+        std::fs::write(&temp_path, json)
+            .with_context(|| format!("Failed to write {:?}", temp_path))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, self.path))?;
+        Ok(())
+    }
+
+    /// Record events every interval until shutdown, then write a final report
+    pub async fn run(
+        mut self,
+        mut predictions: broadcast::Receiver<PredictionResult>,
+        mut anomalies: broadcast::Receiver<RuntimeAnomaly>,
+        interval: Duration,
+        mut shutdown: broadcast::Receiver<()>,
+    ) {
+        info!(path = %self.path.display(), "Recording simulation report");
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                result = predictions.recv() => match result {
+                    Ok(result) => self.record_prediction(result),
+                    Err(RecvError::Lagged(missed)) => self.missed_events += missed,
+                    Err(RecvError::Closed) => break,
+                },
+                result = anomalies.recv() => match result {
+                    Ok(anomaly) => self.record_anomaly(anomaly),
+                    Err(RecvError::Lagged(missed)) => self.missed_events += missed,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick() => match self.write() {
+                    Ok(()) => debug!(
+                        recommendations = self.recommendations.len(),
+                        anomalies = self.anomalies.len(),
+                        "Simulation report written"
+                    ),
+                    Err(e) => warn!(error = %format!("{:#}", e), "Failed to write simulation report"),
+                },
+                _ = shutdown.recv() => break,
+            }
+        }
+
+        match self.write() {
+            Ok(()) => info!(
+                path = %self.path.display(),
+                recommendations = self.recommendations.len(),
+                anomalies = self.anomalies.len(),
+                "Simulation report written"
+            ),
+            Err(e) => warn!(error = %format!("{:#}", e), "Failed to write simulation report"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anomaly::{DetectedAnomaly, SpikeAnomaly};
+    use crate::intern::ContainerKey;
+    use tempfile::TempDir;
+
+    fn prediction(container_id: &str, cpu_request: u32) -> PredictionResult {
+        PredictionResult {
+            container_id: container_id.to_string(),
+            pod_name: format!("pod-{}", container_id),
+            namespace: "default".to_string(),
+            owner: None,
+            profile: Some(ResourceProfile {
+                cpu_request_millicores: cpu_request,
+                cpu_limit_millicores: cpu_request * 2,
+                memory_request_bytes: 128 << 20,
+                memory_limit_bytes: 256 << 20,
+                confidence: 0.8,
+                model_version: "fallback".to_string(),
+                ..Default::default()
+            }),
+            skipped_reason: None,
+            duration_us: 10,
+        }
+    }
+
+    #[test]
+    fn test_report_keeps_latest_recommendation() {
+        let dir = TempDir::new().unwrap();
+        let mut recorder = SimulationRecorder::new("node-1", dir.path().join("report.json"));
+
+        recorder.record_prediction(prediction("b", 100));
+        recorder.record_prediction(prediction("a", 100));
+        recorder.record_prediction(prediction("a", 300));
+        recorder.record_prediction(PredictionResult {
+            profile: None,
+            skipped_reason: Some("Insufficient samples".to_string()),
+            ..prediction("c", 0)
+        });
+        recorder.record_anomaly(RuntimeAnomaly {
+            timestamp: 1_700_000_000,
+            key: ContainerKey::new("a", "pod-a", "default"),
+            anomaly: DetectedAnomaly::CpuSpike(SpikeAnomaly {
+                current_usage: 4.0,
+                expected_usage: 0.5,
+                z_score: 35.0,
+                std_dev: 0.1,
+                threshold: 3.0,
+            }),
+        });
+
+        let report = recorder.report();
+        assert_eq!(report.predictions, 3);
+        assert_eq!(report.skipped_predictions, 1);
+        assert_eq!(report.recommendations.len(), 2);
+        assert_eq!(report.recommendations[0].container_id, "a");
+        assert_eq!(
+            report.recommendations[0].profile.cpu_request_millicores,
+            300
+        );
+        assert_eq!(report.recommendations[0].predictions, 2);
+
+        recorder.write().unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(recorder.path()).unwrap()).unwrap();
+        assert_eq!(written["node_name"], "node-1");
+        assert_eq!(written["anomalies"][0]["container_id"], "a");
+        assert_eq!(written["anomalies"][0]["detector"], "cpu_spike");
+    }
+}
@@ -0,0 +1,272 @@
+//! Snapshot of the agent's internal state for debugging
+//!
+//! Served as JSON on the agent's `/state` endpoint so support engineers can
+//! see what an agent is doing without shell access to its node. Every part
+//! is optional: the snapshot includes whichever components were attached.
+//! The sync components can only be attached with the `grpc-sync` feature.
+
+use crate::anomaly::PipelineConfig;
+use crate::collector::ContainerRegistry;
+use crate::models::ContainerInfo;
+use crate::predictor::{PredictionScheduler, SchedulerStats};
+use crate::sync::BufferStats;
+#[cfg(feature = "grpc-sync")]
+use crate::sync::{
+    ConnectionStats, MetricsStreamer, ModelUpdateClient, ModelUpdateStats, StreamingStats,
+    SyncClient, SyncPipeline,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Point-in-time dump of the agent's components
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentState {
+    pub node_name: String,
+    /// Unix time the snapshot was taken
+    pub captured_at: i64,
+    pub containers: Vec<ContainerInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduler: Option<SchedulerStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buffer: Option<BufferStats>,
+    #[cfg(feature = "grpc-sync")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streaming: Option<StreamingState>,
+    #[cfg(feature = "grpc-sync")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_update: Option<ModelUpdateStats>,
+    #[cfg(feature = "grpc-sync")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection: Option<ConnectionState>,
+    /// Anomaly detectors currently configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anomaly_pipeline: Option<PipelineConfig>,
+}
+
+/// Serializable form of `StreamingStats`
+#[cfg(feature = "grpc-sync")]
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamingState {
+    pub batches_sent: u64,
+    pub metrics_sent: u64,
+    pub predictions_sent: u64,
+    pub anomalies_sent: u64,
+    pub failures: u64,
+    pub streams_opened: u64,
+    /// Seconds since the last successful push
+    pub last_sync_secs_ago: Option<u64>,
+    pub last_error: Option<String>,
+    pub throttled: bool,
+    pub throttled_batches: u64,
+    pub throttle_wait_secs: f64,
+    pub dropped_metrics: u64,
+    pub dropped_node_metrics: u64,
+}
+
+#[cfg(feature = "grpc-sync")]
+impl From<StreamingStats> for StreamingState {
+    fn from(stats: StreamingStats) -> Self {
+        Self {
+            batches_sent: stats.batches_sent,
+            metrics_sent: stats.metrics_sent,
+            predictions_sent: stats.predictions_sent,
+            anomalies_sent: stats.anomalies_sent,
+            failures: stats.failures,
+            streams_opened: stats.streams_opened,
+            last_sync_secs_ago: stats.last_sync_time.map(|at| at.elapsed().as_secs()),
+            last_error: stats.last_error,
+            throttled: stats.throttled,
+            throttled_batches: stats.throttled_batches,
+            throttle_wait_secs: stats.throttle_wait.as_secs_f64(),
+            dropped_metrics: stats.dropped_metrics,
+            dropped_node_metrics: stats.dropped_node_metrics,
+        }
+    }
+}
+
+/// Serializable form of `ConnectionStats`
+#[cfg(feature = "grpc-sync")]
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionState {
+    pub connected: bool,
+    pub reconnect_attempts: u32,
+    pub last_error: Option<String>,
+    pub circuit_state: String,
+    pub circuit_retry_in_secs: Option<u64>,
+}
+
+#[cfg(feature = "grpc-sync")]
+impl From<ConnectionStats> for ConnectionState {
+    fn from(stats: ConnectionStats) -> Self {
+        Self {
+            connected: stats.connected,
+            reconnect_attempts: stats.reconnect_attempts,
+            last_error: stats.last_error,
+            circuit_state: stats.circuit_state.as_str().to_string(),
+            circuit_retry_in_secs: stats.circuit_retry_in.map(|d| d.as_secs()),
+        }
+    }
+}
+
+/// Gathers `AgentState` snapshots from the attached components
+#[derive(Clone, Default)]
+pub struct StateCollector {
+    node_name: String,
+    registry: Option<Arc<ContainerRegistry>>,
+    scheduler: Option<Arc<PredictionScheduler>>,
+    #[cfg(feature = "grpc-sync")]
+    pipeline: Option<Arc<SyncPipeline>>,
+    #[cfg(feature = "grpc-sync")]
+    streamer: Option<Arc<MetricsStreamer>>,
+    #[cfg(feature = "grpc-sync")]
+    model_updates: Option<Arc<ModelUpdateClient>>,
+    #[cfg(feature = "grpc-sync")]
+    sync_client: Option<Arc<SyncClient>>,
+    anomaly_pipeline: Option<watch::Receiver<PipelineConfig>>,
+}
+
+impl StateCollector {
+    pub fn new(node_name: impl Into<String>) -> Self {
+        Self {
+            node_name: node_name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_registry(mut self, registry: Arc<ContainerRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    pub fn with_scheduler(mut self, scheduler: Arc<PredictionScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Include the offline buffer of `pipeline`
+    #[cfg(feature = "grpc-sync")]
+    pub fn with_pipeline(mut self, pipeline: Arc<SyncPipeline>) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    #[cfg(feature = "grpc-sync")]
+    pub fn with_streamer(mut self, streamer: Arc<MetricsStreamer>) -> Self {
+        self.streamer = Some(streamer);
+        self
+    }
+
+    #[cfg(feature = "grpc-sync")]
+    pub fn with_model_updates(mut self, client: Arc<ModelUpdateClient>) -> Self {
+        self.model_updates = Some(client);
+        self
+    }
+
+    /// Include the API connection state of `client`
+    #[cfg(feature = "grpc-sync")]
+    pub fn with_sync_client(mut self, client: Arc<SyncClient>) -> Self {
+        self.sync_client = Some(client);
+        self
+    }
+
+    /// Include the anomaly pipeline configuration, following reloads
+    pub fn with_anomaly_pipeline(mut self, config: watch::Receiver<PipelineConfig>) -> Self {
+        self.anomaly_pipeline = Some(config);
+        self
+    }
+
+    /// Take a snapshot of every attached component
+    pub async fn snapshot(&self) -> AgentState {
+        let mut containers = self
+            .registry
+            .as_ref()
+            .map(|registry| registry.list())
+            .unwrap_or_default();
+        containers.sort_by(|a, b| {
+            (&a.namespace, &a.pod_name, &a.container_id).cmp(&(
+                &b.namespace,
+                &b.pod_name,
+                &b.container_id,
+            ))
+        });
+
+        AgentState {
+            node_name: self.node_name.clone(),
+            captured_at: chrono::Utc::now().timestamp(),
+            containers,
+            scheduler: match &self.scheduler {
+                Some(scheduler) => Some(scheduler.stats().await),
+                None => None,
+            },
+            buffer: self.buffer_stats().await,
+            #[cfg(feature = "grpc-sync")]
+            streaming: match &self.streamer {
+                Some(streamer) => Some(streamer.stats().await.into()),
+                None => None,
+            },
+            #[cfg(feature = "grpc-sync")]
+            model_update: match &self.model_updates {
+                Some(client) => Some(client.stats().await),
+                None => None,
+            },
+            #[cfg(feature = "grpc-sync")]
+            connection: match &self.sync_client {
+                Some(client) => Some(client.connection_stats().await.into()),
+                None => None,
+            },
+            anomaly_pipeline: self
+                .anomaly_pipeline
+                .as_ref()
+                .map(|config| config.borrow().clone()),
+        }
+    }
+
+    #[cfg(feature = "grpc-sync")]
+    async fn buffer_stats(&self) -> Option<BufferStats> {
+        match &self.pipeline {
+            Some(pipeline) => Some(pipeline.buffer_stats().await),
+            None => None,
+        }
+    }
+
+    #[cfg(not(feature = "grpc-sync"))]
+    async fn buffer_stats(&self) -> Option<BufferStats> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContainerRole;
+
+    #[tokio::test]
+    async fn test_snapshot_includes_attached_components() {
+        let registry = Arc::new(ContainerRegistry::new("node-1"));
+        for (id, namespace) in [("b", "prod"), ("a", "dev")] {
+            registry.register(ContainerInfo {
+                container_id: id.to_string(),
+                pod_name: format!("pod-{}", id),
+                namespace: namespace.to_string(),
+                owner: None,
+                node_name: String::new(),
+                cgroup_path: String::new(),
+                container_name: None,
+                role: ContainerRole::Main,
+            });
+        }
+
+        let state = StateCollector::new("node-1")
+            .with_registry(registry)
+            .snapshot()
+            .await;
+        assert_eq!(state.containers.len(), 2);
+        assert_eq!(state.containers[0].namespace, "dev");
+
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["node_name"], "node-1");
+        assert!(json.get("scheduler").is_none());
+        assert!(json.get("connection").is_none());
+    }
+}
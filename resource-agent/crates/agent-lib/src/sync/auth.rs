@@ -0,0 +1,352 @@
+//! Token-based authentication for the gRPC client
+//!
+//! Alternative to mTLS for clusters without a client-certificate PKI:
+//! - Bearer token read from a file, reloaded when the file changes
+//! - Kubernetes service account token (projected, auto-rotated by kubelet)
+//! - OIDC client-credentials flow against a token endpoint
+//!
+//! Tokens are attached to every request by [`AuthInterceptor`].
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tonic::metadata::MetadataValue;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::{debug, info};
+
+/// Default path of the Kubernetes service account token
+pub const DEFAULT_SERVICE_ACCOUNT_TOKEN_PATH: &str =
+    "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Refresh OIDC tokens this long before they expire
+const OIDC_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Authentication method used by the sync client
+#[derive(Debug, Clone, Default)]
+pub enum AuthConfig {
+    /// Mutual TLS with the configured client certificate (default)
+    #[default]
+    Mtls,
+    /// Bearer token read from a file
+    BearerTokenFile { path: PathBuf },
+    /// Kubernetes service account token
+    ServiceAccount { path: PathBuf },
+    /// OIDC client-credentials grant
+    Oidc {
+        token_url: String,
+        client_id: String,
+        client_secret_path: PathBuf,
+        scope: Option<String>,
+    },
+    /// SPIFFE X.509 SVID from the Workload API (`SPIFFE_ENDPOINT_SOCKET` if no path)
+    #[cfg(feature = "spiffe")]
+    Spiffe { socket_path: Option<String> },
+}
+
+impl AuthConfig {
+    /// Service account token at the default in-cluster path
+    pub fn service_account() -> Self {
+        AuthConfig::ServiceAccount {
+            path: PathBuf::from(DEFAULT_SERVICE_ACCOUNT_TOKEN_PATH),
+        }
+    }
+
+    /// Whether this method authenticates with client certificate files
+    pub fn uses_client_certificate(&self) -> bool {
+        matches!(self, AuthConfig::Mtls)
+    }
+
+    /// Build the token provider for this method, if it uses tokens
+    pub fn provider(&self) -> Option<Arc<dyn AuthProvider>> {
+        match self {
+            AuthConfig::Mtls => None,
+            AuthConfig::BearerTokenFile { path } | AuthConfig::ServiceAccount { path } => {
+                Some(Arc::new(FileTokenProvider::new(path.clone())))
+            }
+            AuthConfig::Oidc {
+                token_url,
+                client_id,
+                client_secret_path,
+                scope,
+            } => Some(Arc::new(OidcProvider::new(
+                token_url.clone(),
+                client_id.clone(),
+                client_secret_path.clone(),
+                scope.clone(),
+            ))),
+            #[cfg(feature = "spiffe")]
+            AuthConfig::Spiffe { .. } => None,
+        }
+    }
+}
+
+/// Source of bearer tokens for outgoing requests
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    /// Refresh the cached token if it changed or is about to expire
+    async fn refresh(&self) -> Result<()>;
+
+    /// Currently cached token
+    fn token(&self) -> Option<String>;
+}
+
+/// Token read from a file, reloaded when its modification time changes
+pub struct FileTokenProvider {
+    path: PathBuf,
+    cached: RwLock<Option<(String, SystemTime)>>,
+}
+
+impl FileTokenProvider {
+    /// Create a provider for the given token file
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cached: RwLock::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for FileTokenProvider {
+    async fn refresh(&self) -> Result<()> {
+        let modified = tokio::fs::metadata(&self.path)
+            .await
+            .and_then(|m| m.modified())
+            .with_context(|| format!("Failed to stat token file {:?}", self.path))?;
+
+        let unchanged = self
+            .cached
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|(_, cached_at)| *cached_at == modified)
+            .unwrap_or(false);
+        if unchanged {
+            return Ok(());
+        }
+
+        let token = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read token file {:?}", self.path))?;
+        let token = token.trim().to_string();
+        if token.is_empty() {
+            anyhow::bail!("Token file {:?} is empty", self.path);
+        }
+
+        info!(path = %self.path.display(), "Loaded bearer token");
+        *self.cached.write().unwrap() = Some((token, modified));
+        Ok(())
+    }
+
+    fn token(&self) -> Option<String> {
+        self.cached.read().unwrap().as_ref().map(|(t, _)| t.clone())
+    }
+}
+
+/// Token endpoint response for the client-credentials grant
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// OIDC client-credentials token provider
+pub struct OidcProvider {
+    token_url: String,
+    client_id: String,
+    client_secret_path: PathBuf,
+    scope: Option<String>,
+    http: reqwest::Client,
+    cached: RwLock<Option<(String, Option<Instant>)>>,
+}
+
+impl OidcProvider {
+    /// Create a provider for the given token endpoint
+    pub fn new(
+        token_url: String,
+        client_id: String,
+        client_secret_path: PathBuf,
+        scope: Option<String>,
+    ) -> Self {
+        Self {
+            token_url,
+            client_id,
+            client_secret_path,
+            scope,
+            http: reqwest::Client::new(),
+            cached: RwLock::new(None),
+        }
+    }
+
+    fn needs_refresh(&self) -> bool {
+        match self.cached.read().unwrap().as_ref() {
+            None => true,
+            Some((_, None)) => false,
+            Some((_, Some(expires_at))) => Instant::now() + OIDC_EXPIRY_MARGIN >= *expires_at,
+        }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for OidcProvider {
+    async fn refresh(&self) -> Result<()> {
+        if !self.needs_refresh() {
+            return Ok(());
+        }
+
+        let secret = tokio::fs::read_to_string(&self.client_secret_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read OIDC client secret from {:?}",
+                    self.client_secret_path
+                )
+            })?;
+
+        let mut form = vec![
+            ("grant_type", "client_credentials".to_string()),
+            ("client_id", self.client_id.clone()),
+            ("client_secret", secret.trim().to_string()),
+        ];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.clone()));
+        }
+
+        let response: TokenResponse = self
+            .http
+            .post(&self.token_url)
+            .form(&form)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach token endpoint {}", self.token_url))?
+            .error_for_status()
+            .context("Token endpoint rejected client credentials")?
+            .json()
+            .await
+            .context("Invalid token endpoint response")?;
+
+        let expires_at = response
+            .expires_in
+            .map(|secs| Instant::now() + Duration::from_secs(secs));
+        debug!(
+            expires_in = ?response.expires_in,
+            "Obtained OIDC access token"
+        );
+
+        *self.cached.write().unwrap() = Some((response.access_token, expires_at));
+        Ok(())
+    }
+
+    fn token(&self) -> Option<String> {
+        self.cached.read().unwrap().as_ref().map(|(t, _)| t.clone())
+    }
+}
+
+/// Interceptor adding `authorization: Bearer <token>` to requests
+#[derive(Clone, Default)]
+pub struct AuthInterceptor {
+    provider: Option<Arc<dyn AuthProvider>>,
+}
+
+impl AuthInterceptor {
+    /// Create an interceptor; `None` passes requests through (mTLS)
+    pub fn new(provider: Option<Arc<dyn AuthProvider>>) -> Self {
+        Self { provider }
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> std::result::Result<Request<()>, Status> {
+        let Some(provider) = &self.provider else {
+            return Ok(request);
+        };
+
+        let token = provider
+            .token()
+            .ok_or_else(|| Status::unauthenticated("No bearer token available"))?;
+        let value = MetadataValue::try_from(format!("Bearer {}", token))
+            .map_err(|_| Status::unauthenticated("Bearer token is not valid header data"))?;
+
+        request.metadata_mut().insert("authorization", value);
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_file_token_reload() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("token");
+        std::fs::write(&path, "first-token\n").unwrap();
+
+        let provider = FileTokenProvider::new(path.clone());
+        assert!(provider.token().is_none());
+
+        provider.refresh().await.unwrap();
+        assert_eq!(provider.token().unwrap(), "first-token");
+
+        // Rewrite with a distinct modification time
+        std::fs::write(&path, "second-token").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        provider.refresh().await.unwrap();
+        assert_eq!(provider.token().unwrap(), "second-token");
+    }
+
+    #[test]
+    fn test_interceptor_adds_bearer_header() {
+        struct Static;
+
+        #[async_trait]
+        impl AuthProvider for Static {
+            async fn refresh(&self) -> Result<()> {
+                Ok(())
+            }
+
+            fn token(&self) -> Option<String> {
+                Some("abc".to_string())
+            }
+        }
+
+        let mut interceptor = AuthInterceptor::new(Some(Arc::new(Static)));
+        let request = interceptor.call(Request::new(())).unwrap();
+        assert_eq!(
+            request.metadata().get("authorization").unwrap(),
+            "Bearer abc"
+        );
+
+        // mTLS mode leaves the request untouched
+        let mut passthrough = AuthInterceptor::default();
+        let request = passthrough.call(Request::new(())).unwrap();
+        assert!(request.metadata().get("authorization").is_none());
+    }
+
+    #[test]
+    fn test_interceptor_rejects_missing_token() {
+        let provider = FileTokenProvider::new(PathBuf::from("/nonexistent"));
+        let mut interceptor = AuthInterceptor::new(Some(Arc::new(provider)));
+        let status = interceptor.call(Request::new(())).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn test_token_response_parsing() {
+        let response: TokenResponse = serde_json::from_str(
+            r#"{"access_token":"tok","token_type":"Bearer","expires_in":300}"#,
+        )
+        .unwrap();
+        assert_eq!(response.access_token, "tok");
+        assert_eq!(response.expires_in, Some(300));
+    }
+}
@@ -1,15 +1,22 @@
 //! Local metric buffer for offline operation
 //!
 //! This module provides a ring buffer for storing metrics during API disconnection:
-//! - Memory-mapped ring buffer for persistence
-//! - 24-hour retention with FIFO eviction
+//! - Compressed, append-only on-disk log for persistence (`persistence` feature)
+//! - 24-hour retention, downsampling old data as the buffer nears capacity
 //! - Sync buffered data on reconnection
 
+#[cfg(feature = "persistence")]
+use super::buffer_log::{self, Frame, PersistedEntry};
+use super::downsample::{self, DownsampleConfig};
+use crate::clock;
+use crate::health::ComponentReporter;
 use crate::models::ContainerMetrics;
-use anyhow::{Context, Result};
+use crate::observability::AgentMetrics;
+#[cfg(feature = "persistence")]
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
 use std::collections::VecDeque;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::{debug, info, warn};
@@ -20,6 +27,16 @@ const DEFAULT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
 /// Default maximum buffer size (100,000 entries)
 const DEFAULT_MAX_SIZE: usize = 100_000;
 
+/// Fraction of max_size at which downsampling kicks in
+const DOWNSAMPLE_WATERMARK: f64 = 0.9;
+
+/// Minimum time between downsampling passes
+const DOWNSAMPLE_MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Consumed entries left on disk before the log is compacted
+#[cfg(feature = "persistence")]
+const COMPACTION_MIN_CONSUMED: u64 = 10_000;
+
 /// Configuration for the metrics buffer
 #[derive(Debug, Clone)]
 pub struct BufferConfig {
@@ -31,6 +48,8 @@ pub struct BufferConfig {
     pub persistence_path: Option<PathBuf>,
     /// Flush interval for persistence
     pub flush_interval: Duration,
+    /// Downsampling of old data near capacity (FIFO eviction only if None)
+    pub downsample: Option<DownsampleConfig>,
 }
 
 impl Default for BufferConfig {
@@ -40,6 +59,7 @@ impl Default for BufferConfig {
             max_size: DEFAULT_MAX_SIZE,
             persistence_path: None,
             flush_interval: Duration::from_secs(60),
+            downsample: Some(DownsampleConfig::default()),
         }
     }
 }
@@ -54,13 +74,28 @@ pub struct MetricsBuffer {
     last_flush: SystemTime,
     /// Dirty flag for persistence
     dirty: bool,
+    /// Number of entries at the front of the buffer that are already on disk
+    persisted_len: usize,
+    /// Persisted entries removed from the front since the last flush
+    pending_consume: usize,
+    /// Entries on disk that have since been consumed (reclaimed by compaction)
+    #[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+    disk_consumed: u64,
+    /// Set when entries were rewritten in place and the log must be compacted
+    #[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+    needs_compaction: bool,
+    /// Time of the last downsampling pass
+    last_downsample: Option<SystemTime>,
+    /// Health reporting for the buffer component
+    health: Option<ComponentReporter>,
+    metrics: AgentMetrics,
 }
 
 /// Metrics with timestamp for retention management
 #[derive(Debug, Clone)]
-struct TimestampedMetrics {
-    metrics: ContainerMetrics,
-    buffered_at: SystemTime,
+pub(super) struct TimestampedMetrics {
+    pub metrics: ContainerMetrics,
+    pub buffered_at: SystemTime,
 }
 
 impl MetricsBuffer {
@@ -75,6 +110,13 @@ impl MetricsBuffer {
             },
             last_flush: SystemTime::now(),
             dirty: false,
+            persisted_len: 0,
+            pending_consume: 0,
+            disk_consumed: 0,
+            needs_compaction: false,
+            last_downsample: None,
+            health: None,
+            metrics: AgentMetrics::new(),
         }
     }
 
@@ -85,6 +127,13 @@ impl MetricsBuffer {
             config,
             last_flush: SystemTime::now(),
             dirty: false,
+            persisted_len: 0,
+            pending_consume: 0,
+            disk_consumed: 0,
+            needs_compaction: false,
+            last_downsample: None,
+            health: None,
+            metrics: AgentMetrics::new(),
         }
     }
 
@@ -107,11 +156,26 @@ impl MetricsBuffer {
         Ok(buffer)
     }
 
+    /// Report buffer health through `reporter`
+    pub fn with_health(mut self, reporter: ComponentReporter) -> Self {
+        self.health = Some(reporter);
+        self
+    }
+
     /// Add metrics to buffer
     pub fn push(&mut self, metrics: ContainerMetrics) {
-        // Evict old entries if at capacity
+        // Thin old data before it has to be dropped
+        self.downsample_if_needed();
+
+        // Evict old entries if still at capacity
+        let mut dropped = 0;
         while self.buffer.len() >= self.config.max_size {
             self.buffer.pop_front();
+            self.note_front_removed(1);
+            dropped += 1;
+        }
+        if dropped > 0 {
+            self.metrics.add_buffer_dropped(dropped);
         }
 
         // Evict expired entries
@@ -119,9 +183,28 @@ impl MetricsBuffer {
 
         self.buffer.push_back(TimestampedMetrics {
             metrics,
-            buffered_at: SystemTime::now(),
+            buffered_at: clock::now().system_time(),
         });
         self.dirty = true;
+
+        self.report_fill(dropped > 0);
+    }
+
+    /// Degrade buffer health while it is close to or at capacity
+    fn report_fill(&self, dropped: bool) {
+        let Some(health) = &self.health else {
+            return;
+        };
+
+        let fill = self.buffer.len() as f64 / self.config.max_size.max(1) as f64;
+        let condition = if dropped {
+            Some("Buffer full, dropping oldest metrics".to_string())
+        } else if fill >= DOWNSAMPLE_WATERMARK {
+            Some(format!("Buffer {:.0}% full", fill * 100.0))
+        } else {
+            None
+        };
+        health.set_condition(condition);
     }
 
     /// Add multiple metrics to buffer
@@ -134,14 +217,57 @@ impl MetricsBuffer {
     /// Drain all buffered metrics
     pub fn drain(&mut self) -> Vec<ContainerMetrics> {
         self.dirty = true;
-        self.buffer.drain(..).map(|tm| tm.metrics).collect()
+        self.note_front_removed(self.buffer.len());
+        let drained = self.buffer.drain(..).map(|tm| tm.metrics).collect();
+        self.report_fill(false);
+        drained
     }
 
     /// Drain metrics up to a limit
     pub fn drain_batch(&mut self, limit: usize) -> Vec<ContainerMetrics> {
+        self.take_batch(limit)
+            .into_iter()
+            .map(|tm| tm.metrics)
+            .collect()
+    }
+
+    /// Drain entries up to a limit, with the time each was buffered
+    pub(super) fn take_batch(&mut self, limit: usize) -> Vec<TimestampedMetrics> {
         let count = limit.min(self.buffer.len());
         self.dirty = true;
-        self.buffer.drain(..count).map(|tm| tm.metrics).collect()
+        self.note_front_removed(count);
+        let taken = self.buffer.drain(..count).collect();
+        self.report_fill(false);
+        taken
+    }
+
+    /// Put entries taken from the front back where they were
+    ///
+    /// Unlike `push`, entries keep the time they were buffered and never
+    /// trigger downsampling or eviction. If newer metrics filled the buffer
+    /// in the meantime, the oldest restored entries are dropped.
+    #[cfg_attr(not(feature = "grpc-sync"), allow(dead_code))]
+    pub(super) fn restore_front(&mut self, entries: Vec<TimestampedMetrics>) {
+        if entries.is_empty() {
+            return;
+        }
+
+        let room = self.config.max_size.saturating_sub(self.buffer.len());
+        let dropped = entries.len().saturating_sub(room);
+        if dropped > 0 {
+            self.metrics.add_buffer_dropped(dropped as u64);
+        }
+        for entry in entries.into_iter().skip(dropped).rev() {
+            self.buffer.push_front(entry);
+        }
+
+        // Restored entries precede the on-disk log, so it must be rewritten
+        self.dirty = true;
+        self.needs_compaction = true;
+        self.persisted_len = 0;
+        self.pending_consume = 0;
+
+        self.report_fill(dropped > 0);
     }
 
     /// Peek at buffered metrics without removing them
@@ -153,6 +279,41 @@ impl MetricsBuffer {
             .collect()
     }
 
+    /// Samples of a container with timestamps in `[from, to]`, oldest first
+    pub fn query(&self, container_id: &str, from: i64, to: i64) -> Vec<ContainerMetrics> {
+        self.samples_between(from, to)
+            .filter(|m| m.container_id == container_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Samples of all containers with timestamps in `[from, to]`, oldest first
+    pub fn query_range(&self, from: i64, to: i64) -> Vec<ContainerMetrics> {
+        self.samples_between(from, to).cloned().collect()
+    }
+
+    /// Timestamps of the oldest and newest buffered samples
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        let timestamps = self.buffer.iter().map(|tm| tm.metrics.timestamp);
+        Some((timestamps.clone().min()?, timestamps.max()?))
+    }
+
+    /// Buffered samples in `[from, to]`, in timestamp order
+    ///
+    /// Samples are mostly buffered in collection order, but downsampling
+    /// rewrites entries in place, so the result is sorted rather than relying
+    /// on buffer order.
+    fn samples_between(&self, from: i64, to: i64) -> impl Iterator<Item = &ContainerMetrics> {
+        let mut samples: Vec<&ContainerMetrics> = self
+            .buffer
+            .iter()
+            .map(|tm| &tm.metrics)
+            .filter(|m| (from..=to).contains(&m.timestamp))
+            .collect();
+        samples.sort_by_key(|m| m.timestamp);
+        samples.into_iter()
+    }
+
     /// Get buffer size
     pub fn len(&self) -> usize {
         self.buffer.len()
@@ -176,12 +337,13 @@ impl MetricsBuffer {
 
     /// Evict expired entries based on retention period
     fn evict_expired(&mut self) {
-        let now = SystemTime::now();
+        let now = clock::now().system_time();
         let cutoff = now - self.config.max_retention;
 
         while let Some(front) = self.buffer.front() {
             if front.buffered_at < cutoff {
                 self.buffer.pop_front();
+                self.note_front_removed(1);
                 self.dirty = true;
             } else {
                 break;
@@ -189,14 +351,66 @@ impl MetricsBuffer {
         }
     }
 
+    /// Run a downsampling pass when the buffer nears capacity
+    fn downsample_if_needed(&mut self) {
+        let Some(config) = self.config.downsample.as_ref() else {
+            return;
+        };
+
+        let watermark = (self.config.max_size as f64 * DOWNSAMPLE_WATERMARK) as usize;
+        if self.buffer.len() < watermark {
+            return;
+        }
+
+        let now = clock::now().system_time();
+        let recently_ran = self
+            .last_downsample
+            .map(|t| now.duration_since(t).unwrap_or_default() < DOWNSAMPLE_MIN_INTERVAL)
+            .unwrap_or(false);
+        if recently_ran {
+            return;
+        }
+        self.last_downsample = Some(now);
+
+        let removed = downsample::downsample(&mut self.buffer, config, now);
+        if removed > 0 {
+            debug!(
+                removed = removed,
+                remaining = self.buffer.len(),
+                "Downsampled old buffered metrics"
+            );
+
+            // Entries changed in place, so the on-disk log must be rewritten
+            self.dirty = true;
+            self.needs_compaction = true;
+            self.persisted_len = 0;
+            self.pending_consume = 0;
+        }
+    }
+
+    /// Track entries removed from the front for the on-disk log
+    fn note_front_removed(&mut self, count: usize) {
+        let from_disk = count.min(self.persisted_len);
+        self.persisted_len -= from_disk;
+        self.pending_consume += from_disk;
+    }
+
     /// Flush buffer to disk if persistence is enabled
     pub fn flush(&mut self) -> Result<()> {
         if !self.dirty {
             return Ok(());
         }
 
-        if let Some(ref path) = self.config.persistence_path {
-            self.save_to_disk(path)?;
+        if let Some(path) = self.config.persistence_path.clone() {
+            if let Err(e) = self.save_to_disk(&path) {
+                if let Some(health) = &self.health {
+                    health.failure(format!("Failed to persist buffer: {:#}", e));
+                }
+                return Err(e);
+            }
+            if let Some(health) = &self.health {
+                health.success();
+            }
             self.dirty = false;
             self.last_flush = SystemTime::now();
             debug!(path = %path.display(), entries = self.buffer.len(), "Buffer flushed to disk");
@@ -212,68 +426,142 @@ impl MetricsBuffer {
             && self.last_flush.elapsed().unwrap_or_default() >= self.config.flush_interval
     }
 
-    /// Save buffer to disk
-    fn save_to_disk(&self, path: &Path) -> Result<()> {
+    /// Save buffer changes to disk
+    ///
+    /// New entries and front removals are appended to the log; once enough
+    /// consumed entries pile up, the log is compacted into a fresh snapshot.
+    #[cfg(feature = "persistence")]
+    fn save_to_disk(&mut self, path: &Path) -> Result<()> {
         // Create parent directories if needed
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create directory {:?}", parent))?;
         }
 
-        // Serialize metrics to JSON
-        let metrics: Vec<&ContainerMetrics> = self.buffer.iter().map(|tm| &tm.metrics).collect();
-        let json = serde_json::to_vec(&metrics).context("Failed to serialize metrics")?;
+        let consumed = self.disk_consumed + self.pending_consume as u64;
+        if self.needs_compaction
+            || consumed >= COMPACTION_MIN_CONSUMED && consumed > self.persisted_len as u64
+        {
+            return self.compact(path);
+        }
+
+        let mut frames = Vec::new();
+        if self.pending_consume > 0 {
+            frames.push(Frame::Consume(self.pending_consume as u64));
+        }
+        let appended: Vec<PersistedEntry> = self
+            .buffer
+            .iter()
+            .skip(self.persisted_len)
+            .map(to_persisted)
+            .collect();
+        if !appended.is_empty() {
+            frames.push(Frame::Append(appended));
+        }
 
-        // Write atomically using temp file
-        let temp_path = path.with_extension("tmp");
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&temp_path)
-            .with_context(|| format!("Failed to create temp file {:?}", temp_path))?;
+        if !frames.is_empty() {
+            buffer_log::append_frames(path, &frames)?;
+        }
 
-        file.write_all(&json)
-            .context("Failed to write buffer data")?;
-        file.sync_all().context("Failed to sync buffer file")?;
+        self.disk_consumed = consumed;
+        self.pending_consume = 0;
+        self.persisted_len = self.buffer.len();
+        Ok(())
+    }
 
-        // Rename temp file to final path
-        std::fs::rename(&temp_path, path)
-            .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
+    #[cfg(not(feature = "persistence"))]
+    fn save_to_disk(&mut self, _path: &Path) -> Result<()> {
+        anyhow::bail!("Buffer persistence requires the `persistence` feature")
+    }
 
+    /// Rewrite the on-disk log as a snapshot of the current buffer
+    #[cfg(feature = "persistence")]
+    fn compact(&mut self, path: &Path) -> Result<()> {
+        let entries = self.buffer.iter().map(to_persisted).collect();
+        let size = buffer_log::write_snapshot(path, entries)?;
+
+        debug!(
+            path = %path.display(),
+            reclaimed = self.disk_consumed + self.pending_consume as u64,
+            size_bytes = size,
+            "Compacted buffer log"
+        );
+
+        self.disk_consumed = 0;
+        self.pending_consume = 0;
+        self.persisted_len = self.buffer.len();
+        self.needs_compaction = false;
         Ok(())
     }
 
     /// Load buffer from disk
+    #[cfg(feature = "persistence")]
     fn load_from_disk(&mut self) -> Result<()> {
         let path = self
             .config
             .persistence_path
-            .as_ref()
+            .clone()
             .ok_or_else(|| anyhow::anyhow!("No persistence path configured"))?;
 
-        let mut file =
-            File::open(path).with_context(|| format!("Failed to open buffer file {:?}", path))?;
-
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)
-            .context("Failed to read buffer file")?;
-
-        let metrics: Vec<ContainerMetrics> =
-            serde_json::from_slice(&data).context("Failed to deserialize buffer data")?;
+        let data = buffer_log::read_file(&path)?;
+
+        let (entries, needs_compaction) = if buffer_log::has_magic(&data) {
+            let replay = buffer_log::replay(&data);
+            if replay.damaged {
+                warn!(
+                    path = %path.display(),
+                    valid_bytes = replay.valid_len,
+                    total_bytes = data.len(),
+                    "Buffer file damaged, recovered entries before the damaged frame"
+                );
+            }
+            self.disk_consumed = replay.consumed;
+            (replay.entries, replay.damaged || replay.legacy)
+        } else {
+            // Legacy JSON format without buffering timestamps
+            let metrics: Vec<ContainerMetrics> =
+                serde_json::from_slice(&data).context("Failed to deserialize buffer data")?;
+            let now = to_unix_millis(SystemTime::now());
+            let entries = metrics
+                .into_iter()
+                .map(|metrics| PersistedEntry {
+                    metrics,
+                    buffered_at_ms: now,
+                })
+                .collect();
+            (entries, true)
+        };
 
-        let now = SystemTime::now();
-        for m in metrics {
+        for entry in entries {
             self.buffer.push_back(TimestampedMetrics {
-                metrics: m,
-                buffered_at: now,
+                metrics: entry.metrics,
+                buffered_at: UNIX_EPOCH + Duration::from_millis(entry.buffered_at_ms),
             });
         }
+        self.persisted_len = self.buffer.len();
+
+        // Apply retention and capacity limits to what was restored
+        self.evict_expired();
+        while self.buffer.len() > self.config.max_size {
+            self.buffer.pop_front();
+            self.note_front_removed(1);
+        }
+
+        if needs_compaction {
+            // Drop the damaged tail (or rewrite a legacy file) so later appends are valid
+            self.compact(&path)?;
+        }
+        self.dirty = self.pending_consume > 0;
 
         info!(path = %path.display(), entries = self.buffer.len(), "Loaded buffer from disk");
         Ok(())
     }
 
+    #[cfg(not(feature = "persistence"))]
+    fn load_from_disk(&mut self) -> Result<()> {
+        anyhow::bail!("Buffer persistence requires the `persistence` feature")
+    }
+
     /// Get statistics about the buffer
     pub fn stats(&self) -> BufferStats {
         let oldest = self.buffer.front().map(|tm| {
@@ -301,8 +589,25 @@ impl MetricsBuffer {
     }
 }
 
+/// Convert a buffered sample to its on-disk form
+#[cfg(feature = "persistence")]
+fn to_persisted(tm: &TimestampedMetrics) -> PersistedEntry {
+    PersistedEntry {
+        metrics: tm.metrics.clone(),
+        buffered_at_ms: to_unix_millis(tm.buffered_at),
+    }
+}
+
+/// Convert a wall-clock time to Unix milliseconds
+#[cfg(feature = "persistence")]
+fn to_unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 /// Buffer statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BufferStats {
     /// Number of entries in buffer
     pub entries: usize,
@@ -346,6 +651,12 @@ impl OfflineBufferManager {
         })
     }
 
+    /// Report buffer health through `reporter`
+    pub fn with_health(mut self, reporter: ComponentReporter) -> Self {
+        self.buffer = self.buffer.with_health(reporter);
+        self
+    }
+
     /// Mark as offline and start buffering
     pub fn go_offline(&mut self) {
         if !self.offline {
@@ -400,11 +711,31 @@ impl OfflineBufferManager {
         self.buffer.drain_batch(limit)
     }
 
+    /// Take a batch of buffered metrics for sync, keeping their buffer times
+    #[cfg_attr(not(feature = "grpc-sync"), allow(dead_code))]
+    pub(super) fn take_batch_for_sync(&mut self, limit: usize) -> Vec<TimestampedMetrics> {
+        self.buffer.take_batch(limit)
+    }
+
+    /// Return metrics taken for sync but not sent to the front of the buffer
+    #[cfg_attr(not(feature = "grpc-sync"), allow(dead_code))]
+    pub(super) fn restore_front(&mut self, entries: Vec<TimestampedMetrics>) {
+        self.buffer.restore_front(entries);
+    }
+
     /// Check if there's data to sync
     pub fn has_data_to_sync(&self) -> bool {
         !self.buffer.is_empty()
     }
 
+    /// Buffered samples in `[from, to]`, of one container when given
+    pub fn query(&self, container_id: Option<&str>, from: i64, to: i64) -> Vec<ContainerMetrics> {
+        match container_id {
+            Some(id) => self.buffer.query(id, from, to),
+            None => self.buffer.query_range(from, to),
+        }
+    }
+
     /// Get number of entries waiting to sync
     pub fn pending_sync_count(&self) -> usize {
         self.buffer.len()
@@ -427,13 +758,18 @@ impl OfflineBufferManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::OwnerRef;
+    #[cfg(feature = "persistence")]
+    use std::fs::OpenOptions;
+    #[cfg(feature = "persistence")]
+    use std::io::Write;
 
     fn create_test_metrics(id: &str) -> ContainerMetrics {
         ContainerMetrics {
             container_id: id.to_string(),
             pod_name: "test-pod".to_string(),
             namespace: "default".to_string(),
-            deployment: Some("test-deployment".to_string()),
+            owner: Some(OwnerRef::deployment("test-deployment")),
             timestamp: 1234567890,
             cpu_usage_cores: 0.5,
             cpu_throttled_periods: 10,
@@ -502,6 +838,31 @@ mod tests {
         assert_eq!(buffer.len(), 5); // Buffer unchanged
     }
 
+    #[test]
+    fn test_buffer_query_time_range() {
+        let mut buffer = MetricsBuffer::new(Duration::from_secs(3600), 100);
+        assert!(buffer.time_range().is_none());
+
+        for (id, timestamp) in [("a", 130), ("b", 110), ("a", 100), ("a", 120), ("a", 150)] {
+            let mut m = create_test_metrics(id);
+            m.timestamp = timestamp;
+            buffer.push(m);
+        }
+
+        let timestamps: Vec<i64> = buffer
+            .query("a", 110, 140)
+            .iter()
+            .map(|m| m.timestamp)
+            .collect();
+        assert_eq!(timestamps, [120, 130]);
+        assert_eq!(buffer.query_range(100, 120).len(), 3);
+        assert!(buffer.query("c", 0, i64::MAX).is_empty());
+        assert_eq!(buffer.time_range(), Some((100, 150)));
+
+        // Queries leave the buffer untouched
+        assert_eq!(buffer.len(), 5);
+    }
+
     #[test]
     fn test_buffer_stats() {
         let mut buffer = MetricsBuffer::new(Duration::from_secs(3600), 100);
@@ -546,6 +907,115 @@ mod tests {
         assert!(!manager.has_data_to_sync());
     }
 
+    #[test]
+    #[cfg(feature = "persistence")]
+    fn test_persistence_preserves_buffered_at_and_appends() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("buffer.bin");
+
+        let buffered_at = {
+            let mut buffer = MetricsBuffer::with_persistence(path.clone()).unwrap();
+            for i in 0..5 {
+                buffer.push(create_test_metrics(&format!("container-{}", i)));
+            }
+            buffer.flush().unwrap();
+            let size_after_first = std::fs::metadata(&path).unwrap().len();
+
+            buffer.drain_batch(2);
+            buffer.push(create_test_metrics("container-5"));
+            buffer.flush().unwrap();
+
+            // Second flush appends to the log rather than rewriting it
+            assert!(std::fs::metadata(&path).unwrap().len() > size_after_first);
+            buffer.stats().oldest_timestamp
+        };
+
+        let buffer = MetricsBuffer::with_persistence(path).unwrap();
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.peek(1)[0].container_id, "container-2");
+        assert_eq!(buffer.stats().oldest_timestamp, buffered_at);
+    }
+
+    #[test]
+    #[cfg(feature = "persistence")]
+    fn test_persistence_recovers_from_corrupt_tail() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("buffer.bin");
+
+        {
+            let mut buffer = MetricsBuffer::with_persistence(path.clone()).unwrap();
+            for i in 0..3 {
+                buffer.push(create_test_metrics(&format!("container-{}", i)));
+            }
+            buffer.flush().unwrap();
+        }
+
+        // Simulate a torn write at the end of the file
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[1, 0xff, 0xff, 0, 0, 1, 2]).unwrap();
+        drop(file);
+
+        let mut buffer = MetricsBuffer::with_persistence(path.clone()).unwrap();
+        assert_eq!(buffer.len(), 3);
+
+        // The damaged tail was dropped, so new appends remain readable
+        buffer.push(create_test_metrics("container-3"));
+        buffer.flush().unwrap();
+        let buffer = MetricsBuffer::with_persistence(path).unwrap();
+        assert_eq!(buffer.len(), 4);
+    }
+
+    #[test]
+    fn test_downsampling_before_fifo_eviction() {
+        let mut buffer = MetricsBuffer::new(Duration::from_secs(24 * 60 * 60), 10);
+
+        // Old samples from one container, all in the same minute
+        let old = SystemTime::now() - Duration::from_secs(7 * 60 * 60);
+        for i in 0..8 {
+            let mut m = create_test_metrics("old");
+            m.timestamp = 1_000_000 + i;
+            buffer.buffer.push_back(TimestampedMetrics {
+                metrics: m,
+                buffered_at: old,
+            });
+        }
+        buffer.push(create_test_metrics("a"));
+        buffer.push(create_test_metrics("b"));
+
+        // At the watermark the old samples are thinned instead of the newest
+        // data being pushed out
+        assert_eq!(buffer.len(), 3);
+        let ids: Vec<_> = buffer
+            .peek(3)
+            .iter()
+            .map(|m| m.container_id.clone())
+            .collect();
+        assert_eq!(ids, vec!["old", "a", "b"]);
+    }
+
+    #[test]
+    fn test_restore_front_keeps_order_and_buffer_time() {
+        let mut buffer = MetricsBuffer::new(Duration::from_secs(24 * 60 * 60), 4);
+        for id in ["a", "b", "c"] {
+            buffer.push(create_test_metrics(id));
+        }
+        let buffered_at = buffer.buffer[1].buffered_at;
+
+        let taken = buffer.take_batch(2);
+        buffer.push(create_test_metrics("d"));
+        buffer.push(create_test_metrics("e"));
+        buffer.restore_front(taken);
+
+        // Only room for one of the two: the oldest is dropped, not the newest
+        let ids: Vec<_> = buffer
+            .peek(4)
+            .iter()
+            .map(|m| m.container_id.clone())
+            .collect();
+        assert_eq!(ids, vec!["b", "c", "d", "e"]);
+        assert_eq!(buffer.buffer[0].buffered_at, buffered_at);
+    }
+
     #[test]
     fn test_buffer_config_default() {
         let config = BufferConfig::default();
@@ -553,4 +1023,42 @@ mod tests {
         assert_eq!(config.max_size, 100_000);
         assert!(config.persistence_path.is_none());
     }
+
+    #[tokio::test]
+    async fn test_buffer_reports_fill_level() {
+        use crate::health::{ComponentStatus, HealthPolicy, HealthRegistry};
+
+        let registry = HealthRegistry::new();
+        let reporter = registry.reporter("buffer", HealthPolicy::default()).await;
+        let mut buffer = MetricsBuffer::with_config(BufferConfig {
+            max_size: 10,
+            downsample: None,
+            ..Default::default()
+        })
+        .with_health(reporter.clone());
+
+        for i in 0..8 {
+            buffer.push(create_test_metrics(&format!("container-{}", i)));
+        }
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Healthy);
+
+        buffer.push(create_test_metrics("container-8"));
+        assert_eq!(
+            reporter.evaluate().message.as_deref(),
+            Some("Buffer 90% full")
+        );
+
+        buffer.push(create_test_metrics("container-9"));
+        buffer.push(create_test_metrics("container-10"));
+        let health = registry.health().await;
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert!(health.components["buffer"]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("dropping"));
+
+        buffer.drain();
+        assert_eq!(reporter.evaluate().status, ComponentStatus::Healthy);
+    }
 }
@@ -0,0 +1,387 @@
+//! On-disk format for the metrics buffer
+//!
+//! The file starts with a 4-byte magic followed by an append-only log of frames:
+//! - Append: zstd-compressed bincode of newly buffered entries
+//! - Consume: number of entries removed from the front of the buffer
+//!
+//! Each frame is `[kind: u8][len: u32 LE][crc32: u32 LE][payload]`. On load the
+//! log is replayed up to the first damaged frame, so a torn write or a corrupt
+//! tail only loses the data after it.
+//!
+//! Version 1 files, written before metrics carried an owner kind, are still
+//! read; their deployment name becomes a Deployment owner.
+
+use crate::models::{ContainerMetrics, OwnerRef};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// File magic identifying the binary buffer format (version 2)
+pub(super) const MAGIC: &[u8; 4] = b"KWB2";
+
+/// File magic of version 1, where metrics carry a deployment name
+const MAGIC_V1: &[u8; 4] = b"KWB1";
+
+/// Size of the per-frame header (kind + length + checksum)
+const FRAME_HEADER_LEN: usize = 9;
+
+/// Upper bound on a single frame payload, to reject garbage lengths early
+const MAX_FRAME_LEN: usize = 256 * 1024 * 1024;
+
+/// zstd compression level for append frames
+const ZSTD_LEVEL: i32 = 3;
+
+const KIND_APPEND: u8 = 1;
+const KIND_CONSUME: u8 = 2;
+
+/// A buffered sample as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct PersistedEntry {
+    pub metrics: ContainerMetrics,
+    /// Time the sample entered the buffer (Unix milliseconds)
+    pub buffered_at_ms: u64,
+}
+
+/// Metrics as stored by version 1
+#[derive(Deserialize)]
+struct LegacyMetrics {
+    container_id: String,
+    pod_name: String,
+    namespace: String,
+    deployment: Option<String>,
+    timestamp: i64,
+    cpu_usage_cores: f32,
+    cpu_throttled_periods: u64,
+    memory_usage_bytes: u64,
+    memory_working_set_bytes: u64,
+    memory_cache_bytes: u64,
+    network_rx_bytes: u64,
+    network_tx_bytes: u64,
+}
+
+/// A buffered sample as stored by version 1
+#[derive(Deserialize)]
+struct LegacyEntry {
+    metrics: LegacyMetrics,
+    buffered_at_ms: u64,
+}
+
+impl From<LegacyEntry> for PersistedEntry {
+    fn from(entry: LegacyEntry) -> Self {
+        let m = entry.metrics;
+        Self {
+            metrics: ContainerMetrics {
+                container_id: m.container_id,
+                pod_name: m.pod_name,
+                namespace: m.namespace,
+                owner: m.deployment.map(OwnerRef::deployment),
+                timestamp: m.timestamp,
+                cpu_usage_cores: m.cpu_usage_cores,
+                cpu_throttled_periods: m.cpu_throttled_periods,
+                memory_usage_bytes: m.memory_usage_bytes,
+                memory_working_set_bytes: m.memory_working_set_bytes,
+                memory_cache_bytes: m.memory_cache_bytes,
+                network_rx_bytes: m.network_rx_bytes,
+                network_tx_bytes: m.network_tx_bytes,
+            },
+            buffered_at_ms: entry.buffered_at_ms,
+        }
+    }
+}
+
+/// A single log frame
+pub(super) enum Frame {
+    /// Entries pushed to the back of the buffer
+    Append(Vec<PersistedEntry>),
+    /// Number of entries removed from the front of the buffer
+    Consume(u64),
+}
+
+/// Result of replaying a buffer log
+pub(super) struct Replay {
+    /// Live entries, oldest first
+    pub entries: VecDeque<PersistedEntry>,
+    /// Number of entries on disk that were later consumed
+    pub consumed: u64,
+    /// Length of the valid prefix of the file
+    pub valid_len: u64,
+    /// Whether a damaged frame was found (and everything after it ignored)
+    pub damaged: bool,
+    /// Whether the file uses an older format and should be rewritten
+    pub legacy: bool,
+}
+
+/// Encode a frame with its header
+fn encode_frame(frame: &Frame) -> Result<Vec<u8>> {
+    let (kind, payload) = match frame {
+        Frame::Append(entries) => {
+            let raw = bincode::serialize(entries).context("Failed to serialize buffer entries")?;
+            let compressed = zstd::encode_all(raw.as_slice(), ZSTD_LEVEL)
+                .context("Failed to compress buffer entries")?;
+            (KIND_APPEND, compressed)
+        }
+        Frame::Consume(count) => (KIND_CONSUME, count.to_le_bytes().to_vec()),
+    };
+
+    let mut out = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    out.push(kind);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Decode a frame payload, returning `None` if it is damaged
+fn decode_frame(kind: u8, payload: &[u8], legacy: bool) -> Option<Frame> {
+    match kind {
+        KIND_APPEND if legacy => {
+            let raw = zstd::decode_all(payload).ok()?;
+            let entries: Vec<LegacyEntry> = bincode::deserialize(&raw).ok()?;
+            Some(Frame::Append(entries.into_iter().map(Into::into).collect()))
+        }
+        KIND_APPEND => {
+            let raw = zstd::decode_all(payload).ok()?;
+            bincode::deserialize(&raw).ok().map(Frame::Append)
+        }
+        KIND_CONSUME => {
+            let bytes: [u8; 8] = payload.try_into().ok()?;
+            Some(Frame::Consume(u64::from_le_bytes(bytes)))
+        }
+        _ => None,
+    }
+}
+
+/// Check if data starts with the magic of any binary buffer version
+pub(super) fn has_magic(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || data.starts_with(MAGIC_V1)
+}
+
+/// Replay a buffer log into the live entries it describes
+pub(super) fn replay(data: &[u8]) -> Replay {
+    let mut replay = Replay {
+        entries: VecDeque::new(),
+        consumed: 0,
+        valid_len: MAGIC.len() as u64,
+        damaged: false,
+        legacy: data.starts_with(MAGIC_V1),
+    };
+
+    let mut offset = MAGIC.len();
+    while offset < data.len() {
+        let Some(header) = data.get(offset..offset + FRAME_HEADER_LEN) else {
+            replay.damaged = true;
+            break;
+        };
+
+        let kind = header[0];
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let crc = u32::from_le_bytes([header[5], header[6], header[7], header[8]]);
+
+        let start = offset + FRAME_HEADER_LEN;
+        let payload = match data.get(start..start.saturating_add(len)) {
+            Some(payload) if len <= MAX_FRAME_LEN && crc32fast::hash(payload) == crc => payload,
+            _ => {
+                replay.damaged = true;
+                break;
+            }
+        };
+
+        match decode_frame(kind, payload, replay.legacy) {
+            Some(Frame::Append(entries)) => replay.entries.extend(entries),
+            Some(Frame::Consume(count)) => {
+                let count = (count as usize).min(replay.entries.len());
+                replay.entries.drain(..count);
+                replay.consumed += count as u64;
+            }
+            None => {
+                replay.damaged = true;
+                break;
+            }
+        }
+
+        offset = start + len;
+        replay.valid_len = offset as u64;
+    }
+
+    replay
+}
+
+/// Append frames to an existing log, creating it if needed
+///
+/// Returns the new file length.
+pub(super) fn append_frames(path: &Path, frames: &[Frame]) -> Result<u64> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open buffer file {:?}", path))?;
+
+    if file.metadata()?.len() == 0 {
+        file.write_all(MAGIC)
+            .context("Failed to write buffer header")?;
+    }
+
+    for frame in frames {
+        file.write_all(&encode_frame(frame)?)
+            .context("Failed to append buffer frame")?;
+    }
+    file.sync_data().context("Failed to sync buffer file")?;
+
+    Ok(file.metadata()?.len())
+}
+
+/// Rewrite the log as a single snapshot of the given entries
+///
+/// Returns the new file length.
+pub(super) fn write_snapshot(path: &Path, entries: Vec<PersistedEntry>) -> Result<u64> {
+    let mut data = MAGIC.to_vec();
+    if !entries.is_empty() {
+        data.extend(encode_frame(&Frame::Append(entries))?);
+    }
+
+    // Write atomically using temp file
+    let temp_path = path.with_extension("tmp");
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp_path)
+        .with_context(|| format!("Failed to create temp file {:?}", temp_path))?;
+
+    file.write_all(&data)
+        .context("Failed to write buffer data")?;
+    file.sync_all().context("Failed to sync buffer file")?;
+
+    std::fs::rename(&temp_path, path)
+        .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
+
+    Ok(data.len() as u64)
+}
+
+/// Read a whole buffer file
+pub(super) fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open buffer file {:?}", path))?;
+
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .context("Failed to read buffer file")?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, buffered_at_ms: u64) -> PersistedEntry {
+        PersistedEntry {
+            metrics: ContainerMetrics {
+                container_id: id.to_string(),
+                pod_name: "test-pod".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                timestamp: 1234567890,
+                cpu_usage_cores: 0.5,
+                cpu_throttled_periods: 10,
+                memory_usage_bytes: 1024 * 1024,
+                memory_working_set_bytes: 512 * 1024,
+                memory_cache_bytes: 256 * 1024,
+                network_rx_bytes: 1000,
+                network_tx_bytes: 2000,
+            },
+            buffered_at_ms,
+        }
+    }
+
+    fn encode_log(frames: &[Frame]) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        for frame in frames {
+            data.extend(encode_frame(frame).unwrap());
+        }
+        data
+    }
+
+    #[test]
+    fn test_replay_append_and_consume() {
+        let data = encode_log(&[
+            Frame::Append(vec![entry("a", 1), entry("b", 2)]),
+            Frame::Append(vec![entry("c", 3)]),
+            Frame::Consume(2),
+        ]);
+
+        let replay = replay(&data);
+        assert!(!replay.damaged);
+        assert_eq!(replay.consumed, 2);
+        assert_eq!(replay.valid_len, data.len() as u64);
+        assert_eq!(replay.entries.len(), 1);
+        assert_eq!(replay.entries[0].metrics.container_id, "c");
+        assert_eq!(replay.entries[0].buffered_at_ms, 3);
+    }
+
+    #[test]
+    fn test_replay_stops_at_torn_frame() {
+        let good = encode_log(&[Frame::Append(vec![entry("a", 1)])]);
+        let mut data = good.clone();
+        let torn = encode_frame(&Frame::Append(vec![entry("b", 2)])).unwrap();
+        data.extend_from_slice(&torn[..torn.len() / 2]);
+
+        let replay = replay(&data);
+        assert!(replay.damaged);
+        assert_eq!(replay.entries.len(), 1);
+        assert_eq!(replay.valid_len, good.len() as u64);
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_checksum() {
+        let mut data = encode_log(&[Frame::Append(vec![entry("a", 1)])]);
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+
+        let replay = replay(&data);
+        assert!(replay.damaged);
+        assert!(replay.entries.is_empty());
+    }
+
+    #[test]
+    fn test_replay_version_1_log() {
+        // Version 1 entries, laid out field by field as bincode stores them
+        let entries = vec![(
+            (
+                "a",
+                "api-7d4f8b-x2k9p",
+                "default",
+                Some("api"),
+                100i64,
+                0.5f32,
+                0u64,
+                1024u64,
+                1024u64,
+                0u64,
+                0u64,
+                0u64,
+            ),
+            7u64,
+        )];
+        let raw = bincode::serialize(&entries).unwrap();
+        let payload = zstd::encode_all(raw.as_slice(), ZSTD_LEVEL).unwrap();
+        let mut data = MAGIC_V1.to_vec();
+        data.push(KIND_APPEND);
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        data.extend_from_slice(&payload);
+
+        assert!(has_magic(&data));
+        let replay = replay(&data);
+        assert!(replay.legacy);
+        assert!(!replay.damaged);
+        assert_eq!(replay.entries.len(), 1);
+        assert_eq!(replay.entries[0].buffered_at_ms, 7);
+        assert_eq!(
+            replay.entries[0].metrics.owner,
+            Some(OwnerRef::deployment("api"))
+        );
+    }
+}
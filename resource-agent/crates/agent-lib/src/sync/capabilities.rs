@@ -0,0 +1,107 @@
+//! Protocol versioning and capability negotiation
+//!
+//! Agents and the API are upgraded independently, so a fleet can mix old
+//! agents with a new API and the other way around. At registration both
+//! sides send their protocol version and a bitmap of the optional RPCs they
+//! support, and the agent then makes only the calls both sides support. An
+//! API predating negotiation sends neither and gets the original protocol.
+//!
+//! Fields added to existing messages need no capability: proto3 peers skip
+//! fields they don't know, and read missing ones as their zero value.
+
+use crate::proto::{Capability, RegisterResponse};
+
+/// Protocol version spoken by this agent
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Optional RPCs this agent can make
+const SUPPORTED: [Capability; 5] = [
+    Capability::ChunkedModelDownload,
+    Capability::ConfigWatch,
+    Capability::Heartbeat,
+    Capability::TrainingSamples,
+    Capability::DeploymentAnomalies,
+];
+
+/// Set of capabilities, as the bitmap sent at registration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    /// Capabilities from a registration bitmap
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Capabilities supported by this agent
+    pub fn supported() -> Self {
+        SUPPORTED
+            .into_iter()
+            .fold(Self::default(), |caps, capability| caps.with(capability))
+    }
+
+    /// Capabilities agreed with an API from its registration response
+    ///
+    /// Unknown bits, from an API newer than the agent, are dropped.
+    pub fn negotiate(response: &RegisterResponse) -> Self {
+        if response.protocol_version == 0 {
+            return Self::default();
+        }
+        Self(Self::supported().0 & response.capabilities)
+    }
+
+    pub fn with(self, capability: Capability) -> Self {
+        Self(self.0 | bit(capability))
+    }
+
+    pub fn without(self, capability: Capability) -> Self {
+        Self(self.0 & !bit(capability))
+    }
+
+    pub fn contains(self, capability: Capability) -> bool {
+        self.0 & bit(capability) != 0
+    }
+}
+
+fn bit(capability: Capability) -> u64 {
+    1 << capability as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let response = |protocol_version, capabilities| RegisterResponse {
+            protocol_version,
+            capabilities,
+            ..Default::default()
+        };
+
+        // An API predating negotiation gets the original protocol only
+        let legacy = Capabilities::negotiate(&response(0, u64::MAX));
+        assert!(!legacy.contains(Capability::Heartbeat));
+
+        // Only capabilities both sides support, unknown bits dropped
+        let api = Capabilities::default()
+            .with(Capability::Heartbeat)
+            .with(Capability::ConfigWatch)
+            .bits()
+            | 1 << 40;
+        let negotiated = Capabilities::negotiate(&response(2, api));
+        assert_eq!(
+            negotiated,
+            Capabilities::default()
+                .with(Capability::Heartbeat)
+                .with(Capability::ConfigWatch)
+        );
+        assert!(!negotiated
+            .without(Capability::Heartbeat)
+            .contains(Capability::Heartbeat));
+    }
+}
@@ -0,0 +1,218 @@
+//! Circuit breaker and jittered backoff for API connections
+//!
+//! After repeated failures the breaker opens and operations fail fast for a
+//! jittered, exponentially growing period. Once it elapses, a single probe
+//! connection is let through (half-open); traffic resumes only if it succeeds.
+
+#[cfg(feature = "grpc-sync")]
+use rand::Rng;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A half-open probe that hasn't reported back after this long is abandoned
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Circuit breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests fail fast until the open period elapses
+    Open,
+    /// A single probe is testing the API
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Lowercase name for logs and status output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+
+    /// Value exported by the Prometheus state gauge
+    pub fn gauge_value(&self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        }
+    }
+}
+
+/// Consecutive-failure circuit breaker
+#[derive(Debug)]
+pub(super) struct CircuitBreaker {
+    state: CircuitState,
+    failure_threshold: u32,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub(super) fn new(failure_threshold: u32) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            failure_threshold: failure_threshold.max(1),
+            consecutive_failures: 0,
+            open_until: None,
+            probe_started: None,
+        }
+    }
+
+    pub(super) fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    /// Time left until the breaker lets a probe through
+    pub(super) fn retry_in(&self, now: Instant) -> Option<Duration> {
+        match self.state {
+            CircuitState::Open => self.open_until.map(|t| t.saturating_duration_since(now)),
+            _ => None,
+        }
+    }
+
+    /// Ask to make a connection attempt
+    ///
+    /// Returns the time to wait when the attempt must fail fast.
+    pub(super) fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let open_until = self.open_until.unwrap_or(now);
+                if now < open_until {
+                    return Err(open_until - now);
+                }
+                self.state = CircuitState::HalfOpen;
+                self.probe_started = Some(now);
+                Ok(())
+            }
+            CircuitState::HalfOpen => {
+                let started = self.probe_started.unwrap_or(now);
+                if now.saturating_duration_since(started) >= PROBE_TIMEOUT {
+                    // The previous probe never reported back
+                    self.probe_started = Some(now);
+                    Ok(())
+                } else {
+                    Err(Duration::ZERO)
+                }
+            }
+        }
+    }
+
+    /// A probe connection succeeded; resume traffic
+    ///
+    /// Failures are not forgotten until a request succeeds, so a connection
+    /// whose first request fails reopens the breaker.
+    pub(super) fn on_connected(&mut self) {
+        self.state = CircuitState::Closed;
+        self.open_until = None;
+        self.probe_started = None;
+    }
+
+    /// A request succeeded
+    pub(super) fn on_success(&mut self) {
+        self.on_connected();
+        self.consecutive_failures = 0;
+    }
+
+    /// A connection or request failed; opens the breaker for `backoff` if tripped
+    pub(super) fn on_failure(&mut self, now: Instant, backoff: Duration) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+
+        let tripped = self.state == CircuitState::HalfOpen
+            || self.consecutive_failures >= self.failure_threshold;
+        if tripped {
+            self.state = CircuitState::Open;
+            self.open_until = Some(now + backoff);
+            self.probe_started = None;
+        }
+    }
+}
+
+/// Exponential backoff with full jitter, bounded below by `initial`
+///
+/// The delay is drawn uniformly from `[initial, min(max, initial * 2^attempt)]`.
+#[cfg(feature = "grpc-sync")]
+pub(super) fn jittered_backoff(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    let ceiling = initial
+        .checked_mul(1u32 << attempt.min(20))
+        .unwrap_or(max)
+        .min(max);
+    if ceiling <= initial {
+        return ceiling;
+    }
+    rand::thread_rng().gen_range(initial..=ceiling)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_after_threshold() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(3);
+
+        breaker.on_failure(now, Duration::from_secs(10));
+        breaker.on_failure(now, Duration::from_secs(10));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire(now).is_ok());
+
+        breaker.on_failure(now, Duration::from_secs(10));
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.try_acquire(now), Err(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_half_open_allows_single_probe() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(1);
+        breaker.on_failure(now, Duration::from_secs(5));
+
+        let later = now + Duration::from_secs(5);
+        assert!(breaker.try_acquire(later).is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // Concurrent callers fail fast while the probe is in flight
+        assert!(breaker.try_acquire(later).is_err());
+
+        // Failed probe reopens immediately
+        breaker.on_failure(later, Duration::from_secs(10));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Successful probe closes the breaker
+        let much_later = later + Duration::from_secs(10);
+        assert!(breaker.try_acquire(much_later).is_ok());
+        breaker.on_connected();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.consecutive_failures, 2);
+
+        // ...but a failing first request reopens it straight away
+        breaker.on_failure(much_later, Duration::from_secs(20));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        breaker.on_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.consecutive_failures, 0);
+    }
+
+    #[cfg(feature = "grpc-sync")]
+    #[test]
+    fn test_jittered_backoff_bounds() {
+        let initial = Duration::from_secs(1);
+        let max = Duration::from_secs(300);
+
+        assert_eq!(jittered_backoff(initial, max, 0), initial);
+        for attempt in 1..40 {
+            let backoff = jittered_backoff(initial, max, attempt);
+            assert!(backoff >= initial);
+            assert!(backoff <= max);
+            assert!(backoff <= initial * (1 << attempt.min(20)));
+        }
+    }
+}
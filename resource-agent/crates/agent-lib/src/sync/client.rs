@@ -1,23 +1,88 @@
 //! gRPC client for Recommendation API communication with mTLS support
 //!
 //! This module provides a secure gRPC client that:
-//! - Uses mTLS for authentication
+//! - Uses mTLS or bearer tokens for authentication
 //! - Supports certificate rotation
-//! - Implements connection pooling and keepalive
-//! - Handles reconnection with exponential backoff
-
+//! - Implements connection pooling and keepalive, with readiness probes
+//! - Handles reconnection with jittered exponential backoff
+//! - Fails fast through a circuit breaker while the API is unreachable
+//! - Compresses payloads and bounds message sizes
+//! - Connects through HTTP/SOCKS5 proxies and trusts extra CA bundles
+//! - Makes optional RPCs only when the API supports them
+
+use super::auth::{AuthConfig, AuthInterceptor, AuthProvider};
+use super::capabilities::{Capabilities, PROTOCOL_VERSION};
+use super::circuit_breaker::{jittered_backoff, CircuitBreaker, CircuitState};
+use super::error::SyncError;
+use super::proxy::{resolve_proxy, ProxyConnector};
+#[cfg(feature = "spiffe")]
+use super::spiffe::SpiffeIdentity;
+use crate::anomaly::{AlertSeverity, AlertType, CorrelatedAnomaly, CorrelationScope};
+use crate::observability::AgentMetrics;
 use crate::proto::{
-    predictor_sync_client::PredictorSyncClient, ModelRequest, ModelResponse, RegisterRequest,
-    RegisterResponse,
+    predictor_sync_client::PredictorSyncClient, AgentHealth, AnomalyType, Capability, ConfigUpdate,
+    DeploymentAnomaly, DownloadModelRequest, HeartbeatRequest, HeartbeatResponse, ModelChunk,
+    ModelRequest, ModelResponse, RegisterRequest, RegisterResponse, ReportDeploymentAnomalyRequest,
+    Severity, TrainingSample, UploadTrainingSamplesRequest, WatchConfigRequest,
 };
 use anyhow::{Context, Result};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+use tokio::time::Instant;
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::{http, Service};
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
 use tracing::{debug, info, warn};
 
+/// Default maximum gRPC message size (4 MiB, the tonic/Go server default)
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Default number of consecutive failures that opens the circuit breaker
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Payload compression for gRPC requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrpcCompression {
+    /// Send uncompressed payloads
+    None,
+    /// gzip, supported by every gRPC server
+    #[default]
+    Gzip,
+    /// zstd, better ratio and speed if the server supports it
+    Zstd,
+}
+
+impl GrpcCompression {
+    fn encoding(self) -> Option<CompressionEncoding> {
+        match self {
+            GrpcCompression::None => None,
+            GrpcCompression::Gzip => Some(CompressionEncoding::Gzip),
+            GrpcCompression::Zstd => Some(CompressionEncoding::Zstd),
+        }
+    }
+}
+
+impl std::str::FromStr for GrpcCompression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "" => Ok(GrpcCompression::None),
+            "gzip" => Ok(GrpcCompression::Gzip),
+            "zstd" => Ok(GrpcCompression::Zstd),
+            other => anyhow::bail!("Unknown gRPC compression: {}", other),
+        }
+    }
+}
+
+/// gRPC client type returned for streaming operations
+pub type SyncGrpcClient = PredictorSyncClient<InterceptedService<Channel, AuthInterceptor>>;
+
 /// Configuration for the gRPC client
 #[derive(Debug, Clone)]
 pub struct ClientConfig {
@@ -25,10 +90,14 @@ pub struct ClientConfig {
     pub endpoint: String,
     /// Path to CA certificate for server verification
     pub ca_cert_path: PathBuf,
+    /// Additional CA bundles trusted for server verification
+    pub extra_ca_paths: Vec<PathBuf>,
     /// Path to client certificate for mTLS
     pub client_cert_path: PathBuf,
     /// Path to client private key
     pub client_key_path: PathBuf,
+    /// Authentication method (mTLS by default)
+    pub auth: AuthConfig,
     /// Connection timeout
     pub connect_timeout: Duration,
     /// Request timeout
@@ -41,6 +110,16 @@ pub struct ClientConfig {
     pub initial_backoff: Duration,
     /// Maximum backoff for reconnection
     pub max_backoff: Duration,
+    /// Consecutive failures before the circuit breaker opens
+    pub failure_threshold: u32,
+    /// Compression applied to outgoing requests
+    pub compression: GrpcCompression,
+    /// Maximum encoded/decoded message size in bytes
+    pub max_message_size: usize,
+    /// Proxy URL (`http://` or `socks5://`), overrides the environment
+    pub proxy: Option<String>,
+    /// Use `HTTPS_PROXY`/`ALL_PROXY`/`NO_PROXY` when no proxy is configured
+    pub proxy_from_env: bool,
 }
 
 impl Default for ClientConfig {
@@ -48,42 +127,73 @@ impl Default for ClientConfig {
         Self {
             endpoint: "https://recommendation-api:8443".to_string(),
             ca_cert_path: PathBuf::from("/etc/predictor/certs/ca.crt"),
+            extra_ca_paths: Vec::new(),
             client_cert_path: PathBuf::from("/etc/predictor/certs/client.crt"),
             client_key_path: PathBuf::from("/etc/predictor/certs/client.key"),
+            auth: AuthConfig::default(),
             connect_timeout: Duration::from_secs(10),
             request_timeout: Duration::from_secs(30),
             keepalive_interval: Duration::from_secs(30),
             keepalive_timeout: Duration::from_secs(10),
             initial_backoff: Duration::from_secs(1),
             max_backoff: Duration::from_secs(300), // 5 minutes
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            compression: GrpcCompression::default(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            proxy: None,
+            proxy_from_env: true,
         }
     }
 }
 
 /// Connection state for tracking reconnection attempts
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct ConnectionState {
     connected: bool,
     last_error: Option<String>,
     reconnect_attempts: u32,
     current_backoff: Duration,
+    breaker: CircuitBreaker,
 }
 
-impl Default for ConnectionState {
-    fn default() -> Self {
+impl ConnectionState {
+    fn new(config: &ClientConfig) -> Self {
         Self {
             connected: false,
             last_error: None,
             reconnect_attempts: 0,
-            current_backoff: Duration::from_secs(1),
+            current_backoff: config.initial_backoff,
+            breaker: CircuitBreaker::new(config.failure_threshold),
         }
     }
 }
 
+/// Snapshot of the client's connection health
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionStats {
+    pub connected: bool,
+    /// Consecutive failed connection or request attempts
+    pub reconnect_attempts: u32,
+    pub last_error: Option<String>,
+    pub circuit_state: CircuitState,
+    /// Time until the open circuit lets a probe through
+    pub circuit_retry_in: Option<Duration>,
+}
+
+/// Version of the TLS credentials a configuration was built from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CredentialVersion {
+    /// Modification time of the watched certificate file
+    Modified(std::time::SystemTime),
+    /// SPIFFE identity rotation counter
+    #[cfg(feature = "spiffe")]
+    Spiffe(u64),
+}
+
 /// TLS configuration holder that can be refreshed
 struct TlsState {
     config: ClientTlsConfig,
-    cert_modified_time: std::time::SystemTime,
+    version: CredentialVersion,
 }
 
 /// gRPC client for syncing with Recommendation API
@@ -94,18 +204,31 @@ pub struct SyncClient {
     channel: Arc<RwLock<Option<Channel>>>,
     connection_state: Arc<RwLock<ConnectionState>>,
     tls_state: Arc<RwLock<Option<TlsState>>>,
+    auth_provider: Option<Arc<dyn AuthProvider>>,
+    metrics: AgentMetrics,
+    /// Capabilities bitmap, all supported ones until negotiated
+    capabilities: AtomicU64,
+    #[cfg(feature = "spiffe")]
+    spiffe: tokio::sync::OnceCell<SpiffeIdentity>,
 }
 
 impl SyncClient {
     /// Create a new SyncClient with the given configuration
     pub fn new(config: ClientConfig, agent_id: String, node_name: String) -> Self {
+        let auth_provider = config.auth.provider();
+        let connection_state = ConnectionState::new(&config);
         Self {
             config,
             agent_id,
             node_name,
             channel: Arc::new(RwLock::new(None)),
-            connection_state: Arc::new(RwLock::new(ConnectionState::default())),
+            connection_state: Arc::new(RwLock::new(connection_state)),
             tls_state: Arc::new(RwLock::new(None)),
+            auth_provider,
+            metrics: AgentMetrics::new(),
+            capabilities: AtomicU64::new(Capabilities::supported().bits()),
+            #[cfg(feature = "spiffe")]
+            spiffe: tokio::sync::OnceCell::new(),
         }
     }
 
@@ -138,10 +261,155 @@ impl SyncClient {
         self.config.connect_timeout
     }
 
+    /// Get the maximum message size
+    pub fn max_message_size(&self) -> usize {
+        self.config.max_message_size
+    }
+
+    /// Capabilities the API was negotiated to support
+    ///
+    /// Before registration every capability of the agent is assumed.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::from_bits(self.capabilities.load(Ordering::Relaxed))
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities().contains(capability)
+    }
+
+    /// Fail without a request if the API doesn't support `capability`
+    fn require(&self, capability: Capability, operation: &'static str) -> Result<(), SyncError> {
+        if self.supports(capability) {
+            Ok(())
+        } else {
+            Err(SyncError::Unsupported { operation })
+        }
+    }
+
+    /// Record a failed call to an optional RPC
+    ///
+    /// An API that turns out not to implement it is not called again.
+    async fn optional_rpc_failed(
+        &self,
+        capability: Capability,
+        operation: &'static str,
+        status: tonic::Status,
+    ) -> SyncError {
+        if status.code() == tonic::Code::Unimplemented {
+            let remaining = self.capabilities().without(capability);
+            self.capabilities.store(remaining.bits(), Ordering::Relaxed);
+            self.handle_request_success().await;
+            warn!(
+                operation,
+                "Recommendation API does not implement the call, disabling it"
+            );
+            return SyncError::Unsupported { operation };
+        }
+        self.handle_connection_failure(&status.to_string()).await;
+        SyncError::Rpc { operation, status }
+    }
+
+    /// Wrap a channel in a client with compression and size limits applied
+    fn new_client(&self, channel: Channel) -> SyncGrpcClient {
+        let interceptor = AuthInterceptor::new(self.auth_provider.clone());
+        let mut client = PredictorSyncClient::with_interceptor(channel, interceptor)
+            .max_encoding_message_size(self.config.max_message_size)
+            .max_decoding_message_size(self.config.max_message_size);
+
+        if let Some(encoding) = self.config.compression.encoding() {
+            client = client.send_compressed(encoding).accept_compressed(encoding);
+        }
+
+        client
+    }
+
+    /// Refresh the bearer token if token authentication is configured
+    async fn refresh_auth(&self) -> Result<()> {
+        if let Some(provider) = &self.auth_provider {
+            provider
+                .refresh()
+                .await
+                .context("Failed to refresh authentication token")?;
+        }
+        Ok(())
+    }
+
+    /// Get a channel with fresh credentials, recording failures
+    ///
+    /// Fails fast without touching the network while the circuit is open.
+    async fn connect(&self) -> Result<Channel, SyncError> {
+        {
+            let mut state = self.connection_state.write().await;
+            let acquired = state.breaker.try_acquire(Instant::now());
+            self.metrics.set_sync_circuit_state(state.breaker.state());
+            if let Err(retry_in) = acquired {
+                return Err(SyncError::CircuitOpen { retry_in });
+            }
+        }
+
+        let result = match self.refresh_auth().await {
+            Ok(()) => self.get_channel().await,
+            Err(e) => Err(SyncError::Auth(e)),
+        };
+
+        if let Err(e) = &result {
+            self.handle_connection_failure(&e.to_string()).await;
+        }
+        result
+    }
+
+    /// File whose modification signals a TLS credential rotation
+    fn watched_cert_path(&self) -> &PathBuf {
+        if self.config.auth.uses_client_certificate() {
+            &self.config.client_cert_path
+        } else {
+            &self.config.ca_cert_path
+        }
+    }
+
+    /// Get the SPIFFE identity, connecting to the Workload API on first use
+    #[cfg(feature = "spiffe")]
+    async fn spiffe_identity(&self, socket_path: Option<&str>) -> Result<&SpiffeIdentity> {
+        self.spiffe
+            .get_or_try_init(|| SpiffeIdentity::connect(socket_path))
+            .await
+    }
+
+    /// Build TLS configuration from the SPIFFE SVID and trust bundle
+    #[cfg(feature = "spiffe")]
+    async fn load_spiffe_tls_config(&self, socket_path: Option<&str>) -> Result<ClientTlsConfig> {
+        let material = self.spiffe_identity(socket_path).await?.material();
+
+        Ok(ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(material.bundle_pem))
+            .identity(Identity::from_pem(
+                material.cert_chain_pem,
+                material.key_pem,
+            ))
+            .domain_name(self.extract_domain()?))
+    }
+
+    /// Current version of the TLS credentials
+    async fn credential_version(&self) -> Result<CredentialVersion> {
+        #[cfg(feature = "spiffe")]
+        if let AuthConfig::Spiffe { socket_path } = &self.config.auth {
+            let identity = self.spiffe_identity(socket_path.as_deref()).await?;
+            return Ok(CredentialVersion::Spiffe(identity.generation()));
+        }
+
+        let metadata = tokio::fs::metadata(self.watched_cert_path()).await?;
+        Ok(CredentialVersion::Modified(metadata.modified()?))
+    }
+
     /// Load TLS configuration from certificate files
     async fn load_tls_config(&self) -> Result<ClientTlsConfig> {
-        // Read CA certificate
-        let ca_cert = tokio::fs::read(&self.config.ca_cert_path)
+        #[cfg(feature = "spiffe")]
+        if let AuthConfig::Spiffe { socket_path } = &self.config.auth {
+            return self.load_spiffe_tls_config(socket_path.as_deref()).await;
+        }
+
+        // Read CA certificate, followed by any extra bundles
+        let mut ca_cert = tokio::fs::read(&self.config.ca_cert_path)
             .await
             .with_context(|| {
                 format!(
@@ -149,8 +417,24 @@ impl SyncClient {
                     self.config.ca_cert_path
                 )
             })?;
+        for path in &self.config.extra_ca_paths {
+            let bundle = tokio::fs::read(path)
+                .await
+                .with_context(|| format!("Failed to read CA bundle from {:?}", path))?;
+            ca_cert.push(b'\n');
+            ca_cert.extend_from_slice(&bundle);
+        }
         let ca = Certificate::from_pem(ca_cert);
 
+        let tls_config = ClientTlsConfig::new()
+            .ca_certificate(ca)
+            .domain_name(self.extract_domain()?);
+
+        // Token auth only needs to verify the server
+        if !self.config.auth.uses_client_certificate() {
+            return Ok(tls_config);
+        }
+
         // Read client certificate and key
         let client_cert = tokio::fs::read(&self.config.client_cert_path)
             .await
@@ -170,13 +454,7 @@ impl SyncClient {
             })?;
         let identity = Identity::from_pem(client_cert, client_key);
 
-        // Build TLS config
-        let tls_config = ClientTlsConfig::new()
-            .ca_certificate(ca)
-            .identity(identity)
-            .domain_name(self.extract_domain()?);
-
-        Ok(tls_config)
+        Ok(tls_config.identity(identity))
     }
 
     /// Extract domain name from endpoint URL
@@ -190,12 +468,11 @@ impl SyncClient {
 
     /// Check if certificates have been rotated
     async fn check_cert_rotation(&self) -> Result<bool> {
-        let metadata = tokio::fs::metadata(&self.config.client_cert_path).await?;
-        let modified = metadata.modified()?;
+        let version = self.credential_version().await?;
 
         let tls_state = self.tls_state.read().await;
         if let Some(state) = tls_state.as_ref() {
-            Ok(modified > state.cert_modified_time)
+            Ok(version != state.version)
         } else {
             Ok(true) // No previous state, need to load
         }
@@ -209,15 +486,14 @@ impl SyncClient {
 
         info!("Certificate rotation detected, refreshing TLS configuration");
 
+        // Read the version first so a rotation during loading is picked up next time
+        let version = self.credential_version().await?;
         let new_config = self.load_tls_config().await?;
-        let modified_time = tokio::fs::metadata(&self.config.client_cert_path)
-            .await?
-            .modified()?;
 
         let mut tls_state = self.tls_state.write().await;
         *tls_state = Some(TlsState {
             config: new_config,
-            cert_modified_time: modified_time,
+            version,
         });
 
         // Force reconnection with new certificates
@@ -228,7 +504,26 @@ impl SyncClient {
     }
 
     /// Create a new gRPC channel with mTLS
-    async fn create_channel(&self) -> Result<Channel> {
+    async fn create_channel(&self) -> Result<Channel, SyncError> {
+        let (endpoint, proxy) = self.build_endpoint().await.map_err(SyncError::Config)?;
+
+        match proxy {
+            Some(proxy) => {
+                debug!(proxy = %proxy.host_str().unwrap_or_default(), "Connecting via proxy");
+                endpoint
+                    .connect_with_connector(ProxyConnector::new(proxy))
+                    .await
+            }
+            None => endpoint.connect().await,
+        }
+        .map_err(|source| SyncError::Connect {
+            endpoint: self.config.endpoint.clone(),
+            source,
+        })
+    }
+
+    /// Endpoint settings and proxy for a new channel
+    async fn build_endpoint(&self) -> Result<(Endpoint, Option<url::Url>)> {
         // Ensure TLS config is loaded
         self.refresh_tls_if_needed().await?;
 
@@ -238,25 +533,35 @@ impl SyncClient {
             .map(|s| s.config.clone())
             .ok_or_else(|| anyhow::anyhow!("TLS configuration not loaded"))?;
 
-        let channel = Channel::from_shared(self.config.endpoint.clone())?
+        let endpoint = Channel::from_shared(self.config.endpoint.clone())?
             .tls_config(tls_config)?
             .connect_timeout(self.config.connect_timeout)
             .timeout(self.config.request_timeout)
             .http2_keep_alive_interval(self.config.keepalive_interval)
             .keep_alive_timeout(self.config.keepalive_timeout)
-            .keep_alive_while_idle(true)
-            .connect()
-            .await
-            .with_context(|| format!("Failed to connect to {}", self.config.endpoint))?;
+            .keep_alive_while_idle(true);
 
-        Ok(channel)
+        Ok((endpoint, self.resolve_proxy()?))
+    }
+
+    /// Proxy to tunnel through for the configured endpoint, if any
+    fn resolve_proxy(&self) -> Result<Option<url::Url>> {
+        let target = url::Url::parse(&self.config.endpoint)
+            .with_context(|| format!("Invalid endpoint URL: {}", self.config.endpoint))?;
+        resolve_proxy(
+            self.config.proxy.as_deref(),
+            self.config.proxy_from_env,
+            &target,
+        )
     }
 
     /// Get or create a connected channel
-    async fn get_channel(&self) -> Result<Channel> {
+    async fn get_channel(&self) -> Result<Channel, SyncError> {
         // Check for certificate rotation
         if self.check_cert_rotation().await.unwrap_or(false) {
-            self.refresh_tls_if_needed().await?;
+            self.refresh_tls_if_needed()
+                .await
+                .map_err(SyncError::Config)?;
         }
 
         // Try to use existing channel
@@ -274,12 +579,13 @@ impl SyncClient {
         let mut channel = self.channel.write().await;
         *channel = Some(new_channel.clone());
 
-        // Update connection state
+        // Update connection state; failure counts are kept until a request
+        // succeeds, so a probe that connects but then fails reopens the circuit
         let mut state = self.connection_state.write().await;
         state.connected = true;
-        state.reconnect_attempts = 0;
-        state.current_backoff = self.config.initial_backoff;
         state.last_error = None;
+        state.breaker.on_connected();
+        self.metrics.set_sync_circuit_state(state.breaker.state());
 
         info!(
             endpoint = %self.config.endpoint,
@@ -289,17 +595,32 @@ impl SyncClient {
         Ok(new_channel)
     }
 
-    /// Handle connection failure with exponential backoff
+    /// Handle connection failure with jittered exponential backoff
     async fn handle_connection_failure(&self, error: &str) {
         let mut state = self.connection_state.write().await;
+
+        // Calls rejected by the open circuit say nothing new about the API
+        if state.breaker.state() == CircuitState::Open {
+            return;
+        }
+
         state.connected = false;
         state.last_error = Some(error.to_string());
         state.reconnect_attempts += 1;
+        self.metrics.inc_sync_reconnect_attempts();
 
-        // Calculate next backoff with exponential increase
-        let next_backoff = std::cmp::min(state.current_backoff * 2, self.config.max_backoff);
+        let next_backoff = jittered_backoff(
+            self.config.initial_backoff,
+            self.config.max_backoff,
+            state.reconnect_attempts,
+        );
         state.current_backoff = next_backoff;
 
+        let previous = state.breaker.state();
+        state.breaker.on_failure(Instant::now(), next_backoff);
+        let circuit_state = state.breaker.state();
+        self.metrics.set_sync_circuit_state(circuit_state);
+
         // Clear the channel
         let mut channel = self.channel.write().await;
         *channel = None;
@@ -307,9 +628,32 @@ impl SyncClient {
         warn!(
             error = %error,
             attempts = state.reconnect_attempts,
-            next_backoff_secs = next_backoff.as_secs(),
+            next_backoff_ms = next_backoff.as_millis() as u64,
+            circuit_state = circuit_state.as_str(),
             "Connection to Recommendation API failed"
         );
+        if circuit_state == CircuitState::Open && previous != CircuitState::Open {
+            warn!(
+                attempts = state.reconnect_attempts,
+                open_secs = next_backoff.as_secs(),
+                "Circuit breaker opened for Recommendation API"
+            );
+        }
+    }
+
+    /// Record a successful request, closing the circuit and resetting backoff
+    async fn handle_request_success(&self) {
+        let mut state = self.connection_state.write().await;
+        if state.breaker.state() != CircuitState::Closed || state.reconnect_attempts > 0 {
+            info!(
+                attempts = state.reconnect_attempts,
+                "Recommendation API requests succeeding again"
+            );
+        }
+        state.reconnect_attempts = 0;
+        state.current_backoff = self.config.initial_backoff;
+        state.breaker.on_success();
+        self.metrics.set_sync_circuit_state(state.breaker.state());
     }
 
     /// Get current backoff duration for reconnection
@@ -325,31 +669,29 @@ impl SyncClient {
     }
 
     /// Get connection statistics
-    pub async fn connection_stats(&self) -> (bool, u32, Option<String>) {
+    pub async fn connection_stats(&self) -> ConnectionStats {
         let state = self.connection_state.read().await;
-        (
-            state.connected,
-            state.reconnect_attempts,
-            state.last_error.clone(),
-        )
+        ConnectionStats {
+            connected: state.connected,
+            reconnect_attempts: state.reconnect_attempts,
+            last_error: state.last_error.clone(),
+            circuit_state: state.breaker.state(),
+            circuit_retry_in: state.breaker.retry_in(Instant::now()),
+        }
     }
 
     /// Register agent with the API
+    ///
+    /// Optional RPCs are limited to the capabilities negotiated here.
     pub async fn register(
         &self,
         kubernetes_version: &str,
         agent_version: &str,
         model_version: &str,
-    ) -> Result<RegisterResponse> {
-        let channel = match self.get_channel().await {
-            Ok(ch) => ch,
-            Err(e) => {
-                self.handle_connection_failure(&e.to_string()).await;
-                return Err(e);
-            }
-        };
+    ) -> Result<RegisterResponse, SyncError> {
+        let channel = self.connect().await?;
 
-        let mut client = PredictorSyncClient::new(channel);
+        let mut client = self.new_client(channel);
 
         let request = tonic::Request::new(RegisterRequest {
             agent_id: self.agent_id.clone(),
@@ -357,34 +699,45 @@ impl SyncClient {
             kubernetes_version: kubernetes_version.to_string(),
             agent_version: agent_version.to_string(),
             model_version: model_version.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Capabilities::supported().bits(),
         });
 
         match client.register(request).await {
             Ok(response) => {
-                debug!(
-                    agent_id = %self.agent_id,
-                    "Successfully registered with API"
-                );
-                Ok(response.into_inner())
+                self.handle_request_success().await;
+                let response = response.into_inner();
+                if response.success {
+                    let negotiated = Capabilities::negotiate(&response);
+                    self.capabilities
+                        .store(negotiated.bits(), Ordering::Relaxed);
+                    debug!(
+                        agent_id = %self.agent_id,
+                        protocol_version = response.protocol_version,
+                        capabilities = negotiated.bits(),
+                        "Successfully registered with API"
+                    );
+                }
+                Ok(response)
             }
             Err(e) => {
                 self.handle_connection_failure(&e.to_string()).await;
-                Err(anyhow::anyhow!("Registration failed: {}", e))
+                Err(SyncError::Rpc {
+                    operation: "Registration",
+                    status: e,
+                })
             }
         }
     }
 
     /// Check for model updates
-    pub async fn get_model_update(&self, current_version: &str) -> Result<Option<ModelResponse>> {
-        let channel = match self.get_channel().await {
-            Ok(ch) => ch,
-            Err(e) => {
-                self.handle_connection_failure(&e.to_string()).await;
-                return Err(e);
-            }
-        };
+    pub async fn get_model_update(
+        &self,
+        current_version: &str,
+    ) -> Result<Option<ModelResponse>, SyncError> {
+        let channel = self.connect().await?;
 
-        let mut client = PredictorSyncClient::new(channel);
+        let mut client = self.new_client(channel);
 
         let request = tonic::Request::new(ModelRequest {
             agent_id: self.agent_id.clone(),
@@ -393,6 +746,7 @@ impl SyncClient {
 
         match client.get_model_update(request).await {
             Ok(response) => {
+                self.handle_request_success().await;
                 let model_response = response.into_inner();
                 if model_response.update_available {
                     info!(
@@ -408,15 +762,237 @@ impl SyncClient {
             }
             Err(e) => {
                 self.handle_connection_failure(&e.to_string()).await;
-                Err(anyhow::anyhow!("Model update check failed: {}", e))
+                Err(SyncError::Rpc {
+                    operation: "Model update check",
+                    status: e,
+                })
             }
         }
     }
 
+    /// Open a chunked model download starting at `offset`
+    pub async fn download_model(
+        &self,
+        version: &str,
+        offset: u64,
+        chunk_size: usize,
+    ) -> Result<tonic::Streaming<ModelChunk>, SyncError> {
+        self.require(Capability::ChunkedModelDownload, "Model download")?;
+        let channel = self.connect().await?;
+
+        let mut client = self.new_client(channel);
+
+        let request = tonic::Request::new(DownloadModelRequest {
+            agent_id: self.agent_id.clone(),
+            version: version.to_string(),
+            offset: offset as i64,
+            chunk_size: chunk_size as i32,
+        });
+
+        match client.download_model(request).await {
+            Ok(response) => {
+                self.handle_request_success().await;
+                debug!(version = %version, offset = offset, "Opened model download stream");
+                Ok(response.into_inner())
+            }
+            Err(e) => Err(self
+                .optional_rpc_failed(Capability::ChunkedModelDownload, "Model download", e)
+                .await),
+        }
+    }
+
+    /// Report agent liveness and health to the API
+    pub async fn heartbeat(
+        &self,
+        agent_version: &str,
+        health: AgentHealth,
+    ) -> Result<HeartbeatResponse, SyncError> {
+        self.require(Capability::Heartbeat, "Heartbeat")?;
+        let channel = self.connect().await?;
+
+        let mut client = self.new_client(channel);
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let request = tonic::Request::new(HeartbeatRequest {
+            agent_id: self.agent_id.clone(),
+            node_name: self.node_name.clone(),
+            agent_version: agent_version.to_string(),
+            timestamp: Some(prost_types::Timestamp {
+                seconds: now.as_secs() as i64,
+                nanos: now.subsec_nanos() as i32,
+            }),
+            health: Some(health),
+        });
+
+        match client.heartbeat(request).await {
+            Ok(response) => {
+                self.handle_request_success().await;
+                Ok(response.into_inner())
+            }
+            Err(e) => Err(self
+                .optional_rpc_failed(Capability::Heartbeat, "Heartbeat", e)
+                .await),
+        }
+    }
+
+    /// Upload prediction deviation samples for model training
+    pub async fn upload_training_samples(
+        &self,
+        samples: Vec<TrainingSample>,
+    ) -> Result<(), SyncError> {
+        self.require(Capability::TrainingSamples, "Training sample upload")?;
+        let channel = self.connect().await?;
+
+        let mut client = self.new_client(channel);
+
+        let count = samples.len();
+        let request = tonic::Request::new(UploadTrainingSamplesRequest {
+            agent_id: self.agent_id.clone(),
+            samples,
+        });
+
+        match client.upload_training_samples(request).await {
+            Ok(response) => {
+                self.handle_request_success().await;
+                let response = response.into_inner();
+                if !response.success {
+                    return Err(SyncError::Rejected {
+                        what: "Training samples",
+                        message: response.message,
+                    });
+                }
+                debug!(samples = count, "Uploaded training samples");
+                Ok(())
+            }
+            Err(e) => Err(self
+                .optional_rpc_failed(Capability::TrainingSamples, "Training sample upload", e)
+                .await),
+        }
+    }
+
+    /// Report an anomaly shared by several replicas of one deployment
+    ///
+    /// The API raises one incident per deployment from it rather than one
+    /// per pod. Node-wide anomalies have no deployment and are not sent.
+    pub async fn report_deployment_anomaly(
+        &self,
+        anomaly: &CorrelatedAnomaly,
+    ) -> Result<(), SyncError> {
+        let Some(anomaly) = deployment_anomaly(anomaly) else {
+            return Ok(());
+        };
+        self.require(Capability::DeploymentAnomalies, "Deployment anomaly report")?;
+        let channel = self.connect().await?;
+
+        let mut client = self.new_client(channel);
+
+        let (namespace, deployment) = (anomaly.namespace.clone(), anomaly.deployment.clone());
+        let request = tonic::Request::new(ReportDeploymentAnomalyRequest {
+            agent_id: self.agent_id.clone(),
+            node_name: self.node_name.clone(),
+            anomaly: Some(anomaly),
+        });
+
+        match client.report_deployment_anomaly(request).await {
+            Ok(response) => {
+                self.handle_request_success().await;
+                let response = response.into_inner();
+                if !response.success {
+                    return Err(SyncError::Rejected {
+                        what: "Deployment anomaly",
+                        message: response.message,
+                    });
+                }
+                debug!(
+                    namespace = %namespace,
+                    deployment = %deployment,
+                    "Reported deployment anomaly"
+                );
+                Ok(())
+            }
+            Err(e) => Err(self
+                .optional_rpc_failed(
+                    Capability::DeploymentAnomalies,
+                    "Deployment anomaly report",
+                    e,
+                )
+                .await),
+        }
+    }
+
+    /// Open the bidirectional config watch stream
+    ///
+    /// Acks sent on `requests` report the applied config version back to the
+    /// control plane; pushed updates arrive on the returned stream.
+    pub async fn watch_config(
+        &self,
+        requests: impl tonic::IntoStreamingRequest<Message = WatchConfigRequest>,
+    ) -> Result<tonic::Streaming<ConfigUpdate>, SyncError> {
+        self.require(Capability::ConfigWatch, "Config watch")?;
+        let channel = self.connect().await?;
+
+        let mut client = self.new_client(channel);
+
+        match client.watch_config(requests).await {
+            Ok(response) => {
+                self.handle_request_success().await;
+                debug!(agent_id = %self.agent_id, "Opened config watch stream");
+                Ok(response.into_inner())
+            }
+            Err(e) => Err(self
+                .optional_rpc_failed(Capability::ConfigWatch, "Config watch", e)
+                .await),
+        }
+    }
+
     /// Get a client for streaming operations
-    pub async fn get_streaming_client(&self) -> Result<PredictorSyncClient<Channel>> {
-        let channel = self.get_channel().await?;
-        Ok(PredictorSyncClient::new(channel))
+    pub async fn get_streaming_client(&self) -> Result<SyncGrpcClient, SyncError> {
+        let channel = self.connect().await?;
+        Ok(self.new_client(channel))
+    }
+
+    /// Report that a stream opened on this client failed
+    ///
+    /// Drops the channel and advances the reconnect backoff.
+    pub async fn report_stream_failure(&self, error: &str) {
+        self.handle_connection_failure(error).await;
+    }
+
+    /// Report that a stream opened on this client completed successfully
+    ///
+    /// Closes the circuit breaker and resets the reconnect backoff.
+    pub async fn report_stream_success(&self) {
+        self.handle_request_success().await;
+    }
+
+    /// Check that the open channel still accepts requests
+    ///
+    /// A connection whose keepalive pings go unanswered is torn down by the
+    /// transport, after which the channel stops becoming ready. A channel that
+    /// isn't ready within the keepalive timeout is dropped and recorded as a
+    /// connection failure, so callers can go offline before a send fails.
+    /// Without an open channel there is nothing to probe and the last known
+    /// state is returned.
+    pub async fn probe_connection(&self) -> bool {
+        let Some(mut channel) = self.channel.read().await.clone() else {
+            return self.is_connected().await;
+        };
+
+        let ready = std::future::poll_fn(|cx| {
+            Service::<http::Request<BoxBody>>::poll_ready(&mut channel, cx)
+        });
+        let error = match tokio::time::timeout(self.config.keepalive_timeout, ready).await {
+            Ok(Ok(())) => return true,
+            Ok(Err(e)) => format!("Connection probe failed: {}", e),
+            Err(_) => format!(
+                "Connection not ready within {}ms",
+                self.config.keepalive_timeout.as_millis()
+            ),
+        };
+        self.handle_connection_failure(&error).await;
+        false
     }
 
     /// Force reconnection (useful after certificate rotation)
@@ -433,7 +1009,10 @@ impl SyncClient {
         {
             let mut state = self.connection_state.write().await;
             state.connected = false;
+            state.reconnect_attempts = 0;
             state.current_backoff = self.config.initial_backoff;
+            state.breaker.on_success();
+            self.metrics.set_sync_circuit_state(state.breaker.state());
         }
 
         // Attempt to reconnect
@@ -519,6 +1098,41 @@ impl SyncClientBuilder {
         self
     }
 
+    pub fn failure_threshold(mut self, failures: u32) -> Self {
+        self.config.failure_threshold = failures;
+        self
+    }
+
+    pub fn auth(mut self, auth: AuthConfig) -> Self {
+        self.config.auth = auth;
+        self
+    }
+
+    pub fn compression(mut self, compression: GrpcCompression) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.config.max_message_size = size;
+        self
+    }
+
+    pub fn extra_ca_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.extra_ca_paths.push(path.into());
+        self
+    }
+
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.config.proxy = Some(url.into());
+        self
+    }
+
+    pub fn proxy_from_env(mut self, enabled: bool) -> Self {
+        self.config.proxy_from_env = enabled;
+        self
+    }
+
     pub fn agent_id(mut self, id: impl Into<String>) -> Self {
         self.agent_id = Some(id.into());
         self
@@ -547,6 +1161,34 @@ impl Default for SyncClientBuilder {
     }
 }
 
+/// Convert a deployment-level correlated anomaly to proto format
+fn deployment_anomaly(anomaly: &CorrelatedAnomaly) -> Option<DeploymentAnomaly> {
+    let CorrelationScope::Deployment { namespace, name } = &anomaly.scope else {
+        return None;
+    };
+    let anomaly_type = match anomaly.anomaly_type {
+        AlertType::MemoryLeak => AnomalyType::MemoryLeak,
+        AlertType::CpuSpike => AnomalyType::CpuSpike,
+        AlertType::OomRisk => AnomalyType::OomRisk,
+    };
+    let severity = match anomaly.severity {
+        AlertSeverity::Warning => Severity::Warning,
+        AlertSeverity::Critical => Severity::Critical,
+    };
+    let timestamp = |seconds| Some(prost_types::Timestamp { seconds, nanos: 0 });
+
+    Some(DeploymentAnomaly {
+        namespace: namespace.clone(),
+        deployment: name.clone(),
+        r#type: anomaly_type as i32,
+        severity: severity as i32,
+        affected_replicas: anomaly.affected_pods.len() as i32,
+        affected_pods: anomaly.affected_pods.clone(),
+        first_detected_at: timestamp(anomaly.first_detected_at),
+        last_detected_at: timestamp(anomaly.last_detected_at),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -574,6 +1216,74 @@ mod tests {
         assert_eq!(client.config.connect_timeout, Duration::from_secs(5));
     }
 
+    #[test]
+    fn test_grpc_compression_parse() {
+        assert_eq!(
+            "gzip".parse::<GrpcCompression>().unwrap(),
+            GrpcCompression::Gzip
+        );
+        assert_eq!(
+            "ZSTD".parse::<GrpcCompression>().unwrap(),
+            GrpcCompression::Zstd
+        );
+        assert_eq!(
+            "none".parse::<GrpcCompression>().unwrap(),
+            GrpcCompression::None
+        );
+        assert!("brotli".parse::<GrpcCompression>().is_err());
+    }
+
+    #[test]
+    fn test_capabilities_assumed_until_registered() {
+        let client = SyncClient::with_defaults(
+            "https://test:8443".to_string(),
+            "test-agent".to_string(),
+            "test-node".to_string(),
+        );
+        assert_eq!(client.capabilities(), Capabilities::supported());
+
+        client.capabilities.store(
+            Capabilities::supported()
+                .without(Capability::Heartbeat)
+                .bits(),
+            Ordering::Relaxed,
+        );
+        assert!(matches!(
+            client.require(Capability::Heartbeat, "Heartbeat"),
+            Err(SyncError::Unsupported { .. })
+        ));
+        assert!(client
+            .require(Capability::ConfigWatch, "Config watch")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_deployment_anomaly() {
+        let mut anomaly = CorrelatedAnomaly {
+            scope: CorrelationScope::Deployment {
+                namespace: "shop".to_string(),
+                name: "cart".to_string(),
+            },
+            anomaly_type: AlertType::MemoryLeak,
+            severity: AlertSeverity::Critical,
+            affected_pods: (0..8).map(|i| format!("shop/cart-{}", i)).collect(),
+            first_detected_at: 1000,
+            last_detected_at: 1030,
+        };
+
+        let report = deployment_anomaly(&anomaly).unwrap();
+        assert_eq!(report.deployment, "cart");
+        assert_eq!(report.r#type, AnomalyType::MemoryLeak as i32);
+        assert_eq!(report.severity, Severity::Critical as i32);
+        assert_eq!(report.affected_replicas, 8);
+        assert_eq!(report.last_detected_at.unwrap().seconds, 1030);
+
+        anomaly.scope = CorrelationScope::Node {
+            name: "node-1".to_string(),
+        };
+        assert!(deployment_anomaly(&anomaly).is_none());
+    }
+
     #[test]
     fn test_builder_missing_agent_id() {
         let result = SyncClientBuilder::new()
@@ -594,9 +1304,54 @@ mod tests {
             .unwrap();
 
         assert!(!client.is_connected().await);
-        let (connected, attempts, error) = client.connection_stats().await;
-        assert!(!connected);
-        assert_eq!(attempts, 0);
-        assert!(error.is_none());
+        let stats = client.connection_stats().await;
+        assert!(!stats.connected);
+        assert_eq!(stats.reconnect_attempts, 0);
+        assert!(stats.last_error.is_none());
+        assert_eq!(stats.circuit_state, CircuitState::Closed);
+
+        // Nothing to probe before a channel is open
+        assert!(!client.probe_connection().await);
+        assert_eq!(client.connection_stats().await.reconnect_attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_fails_fast() {
+        let client = SyncClientBuilder::new()
+            .endpoint("https://test:8443")
+            .agent_id("test-agent")
+            .node_name("test-node")
+            .initial_backoff(Duration::from_secs(60))
+            .failure_threshold(2)
+            .build()
+            .unwrap();
+
+        client.report_stream_failure("unavailable").await;
+        assert_eq!(
+            client.connection_stats().await.circuit_state,
+            CircuitState::Closed
+        );
+
+        client.report_stream_failure("unavailable").await;
+        let stats = client.connection_stats().await;
+        assert_eq!(stats.circuit_state, CircuitState::Open);
+        assert_eq!(stats.reconnect_attempts, 2);
+        assert!(stats.circuit_retry_in.unwrap() > Duration::ZERO);
+
+        // Fails fast without counting another attempt
+        let err = client.connect().await.unwrap_err();
+        assert!(err.to_string().contains("Circuit breaker open"));
+        assert!(matches!(err, SyncError::CircuitOpen { .. }));
+        assert!(err.is_retryable());
+        assert_eq!(client.connection_stats().await.reconnect_attempts, 2);
+
+        client.report_stream_success().await;
+        let stats = client.connection_stats().await;
+        assert_eq!(stats.circuit_state, CircuitState::Closed);
+        assert_eq!(stats.reconnect_attempts, 0);
+        assert_eq!(
+            client.get_reconnect_backoff().await,
+            Duration::from_secs(60)
+        );
     }
 }
@@ -0,0 +1,271 @@
+//! Progressive downsampling for the metrics buffer
+//!
+//! When the buffer nears capacity, old samples are thinned instead of being
+//! dropped outright, so a long outage still leaves representative history:
+//! - Samples older than `thin_after` keep one sample per container per `thin_interval`
+//! - Samples older than `aggregate_after` collapse into per-container bucket averages
+//!
+//! Both passes are keyed on sample timestamps, so running them repeatedly is
+//! idempotent.
+
+use super::buffer::TimestampedMetrics;
+use crate::models::ContainerMetrics;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, SystemTime};
+
+/// Default age after which samples are thinned (6 hours)
+const DEFAULT_THIN_AFTER: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Default thinning interval (1-in-6 at the default 10s collection interval)
+const DEFAULT_THIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default age after which samples are aggregated (12 hours)
+const DEFAULT_AGGREGATE_AFTER: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Default aggregation bucket (5 minutes)
+const DEFAULT_AGGREGATE_BUCKET: Duration = Duration::from_secs(5 * 60);
+
+/// Configuration for buffer downsampling
+#[derive(Debug, Clone)]
+pub struct DownsampleConfig {
+    /// Samples older than this are thinned
+    pub thin_after: Duration,
+    /// Keep at most one sample per container per interval when thinning
+    pub thin_interval: Duration,
+    /// Samples older than this are aggregated into bucket averages
+    pub aggregate_after: Duration,
+    /// Width of aggregation buckets
+    pub aggregate_bucket: Duration,
+}
+
+impl Default for DownsampleConfig {
+    fn default() -> Self {
+        Self {
+            thin_after: DEFAULT_THIN_AFTER,
+            thin_interval: DEFAULT_THIN_INTERVAL,
+            aggregate_after: DEFAULT_AGGREGATE_AFTER,
+            aggregate_bucket: DEFAULT_AGGREGATE_BUCKET,
+        }
+    }
+}
+
+/// Downsample buffered entries in place, returning how many were removed
+pub(super) fn downsample(
+    entries: &mut VecDeque<TimestampedMetrics>,
+    config: &DownsampleConfig,
+    now: SystemTime,
+) -> usize {
+    let before = entries.len();
+    let aggregate_cutoff = now - config.aggregate_after;
+    let thin_cutoff = now - config.thin_after;
+
+    let mut aggregate = Vec::new();
+    let mut thin = Vec::new();
+    let mut recent = VecDeque::new();
+    for tm in entries.drain(..) {
+        if tm.buffered_at < aggregate_cutoff {
+            aggregate.push(tm);
+        } else if tm.buffered_at < thin_cutoff {
+            thin.push(tm);
+        } else {
+            recent.push_back(tm);
+        }
+    }
+
+    let mut result = aggregate_buckets(aggregate, config.aggregate_bucket);
+    result.extend(thin_samples(thin, config.thin_interval));
+
+    // Aggregates take the latest buffering time of their bucket, so restore order
+    result.sort_by_key(|tm| tm.buffered_at);
+    result.extend(recent);
+
+    *entries = result.into();
+    before - entries.len()
+}
+
+/// Keep the first sample per container in each interval
+fn thin_samples(entries: Vec<TimestampedMetrics>, interval: Duration) -> Vec<TimestampedMetrics> {
+    let interval = interval.as_secs().max(1) as i64;
+    let mut seen = HashSet::new();
+
+    entries
+        .into_iter()
+        .filter(|tm| {
+            let slot = tm.metrics.timestamp.div_euclid(interval);
+            seen.insert((tm.metrics.container_id.clone(), slot))
+        })
+        .collect()
+}
+
+/// Collapse samples into per-container bucket averages
+fn aggregate_buckets(
+    entries: Vec<TimestampedMetrics>,
+    bucket: Duration,
+) -> Vec<TimestampedMetrics> {
+    let bucket = bucket.as_secs().max(1) as i64;
+    let mut order = Vec::new();
+    let mut groups: HashMap<(String, i64), Vec<TimestampedMetrics>> = HashMap::new();
+
+    for tm in entries {
+        let key = (
+            tm.metrics.container_id.clone(),
+            tm.metrics.timestamp.div_euclid(bucket),
+        );
+        let group = groups.entry(key.clone()).or_default();
+        if group.is_empty() {
+            order.push(key);
+        }
+        group.push(tm);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|key| groups.remove(&key))
+        .map(average)
+        .collect()
+}
+
+/// Average a group of samples for one container
+///
+/// Gauges are averaged; cumulative counters take the latest value.
+fn average(group: Vec<TimestampedMetrics>) -> TimestampedMetrics {
+    let n = group.len() as f64;
+    let mean = |f: fn(&ContainerMetrics) -> u64| -> u64 {
+        (group.iter().map(|tm| f(&tm.metrics) as f64).sum::<f64>() / n).round() as u64
+    };
+
+    let first = &group[0];
+    let last = &group[group.len() - 1];
+
+    let metrics = ContainerMetrics {
+        container_id: first.metrics.container_id.clone(),
+        pod_name: last.metrics.pod_name.clone(),
+        namespace: last.metrics.namespace.clone(),
+        owner: last.metrics.owner.clone(),
+        timestamp: first.metrics.timestamp,
+        cpu_usage_cores: group
+            .iter()
+            .map(|tm| tm.metrics.cpu_usage_cores)
+            .sum::<f32>()
+            / n as f32,
+        cpu_throttled_periods: last.metrics.cpu_throttled_periods,
+        memory_usage_bytes: mean(|m| m.memory_usage_bytes),
+        memory_working_set_bytes: mean(|m| m.memory_working_set_bytes),
+        memory_cache_bytes: mean(|m| m.memory_cache_bytes),
+        network_rx_bytes: last.metrics.network_rx_bytes,
+        network_tx_bytes: last.metrics.network_tx_bytes,
+    };
+
+    let buffered_at = group
+        .iter()
+        .map(|tm| tm.buffered_at)
+        .max()
+        .unwrap_or(last.buffered_at);
+
+    TimestampedMetrics {
+        metrics,
+        buffered_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(
+        id: &str,
+        timestamp: i64,
+        memory: u64,
+        buffered_at: SystemTime,
+    ) -> TimestampedMetrics {
+        TimestampedMetrics {
+            metrics: ContainerMetrics {
+                container_id: id.to_string(),
+                pod_name: "test-pod".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                timestamp,
+                cpu_usage_cores: 1.0,
+                cpu_throttled_periods: timestamp as u64,
+                memory_usage_bytes: memory,
+                memory_working_set_bytes: memory,
+                memory_cache_bytes: 0,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            },
+            buffered_at,
+        }
+    }
+
+    /// Build 24h of 10-second samples for one container, oldest first
+    fn day_of_samples(now: SystemTime) -> VecDeque<TimestampedMetrics> {
+        let start = now - Duration::from_secs(24 * 60 * 60);
+        (0..24 * 360)
+            .map(|i| {
+                let offset = Duration::from_secs(i * 10);
+                sample("c1", 1_000_000 + (i * 10) as i64, 100, start + offset)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_downsample_keeps_recent_and_thins_old() {
+        let now = SystemTime::now();
+        let mut entries = day_of_samples(now);
+        let config = DownsampleConfig::default();
+
+        let removed = downsample(&mut entries, &config, now);
+        assert!(removed > 0);
+
+        let recent_cutoff = now - config.thin_after;
+        let recent = entries
+            .iter()
+            .filter(|tm| tm.buffered_at >= recent_cutoff)
+            .count();
+        assert_eq!(recent, 6 * 360);
+
+        // 6-12h: one per minute; >12h: one per 5 minutes
+        assert!(entries.len() <= 6 * 360 + 6 * 60 + 1 + 12 * 12 + 1);
+
+        // Still ordered by buffering time
+        assert!(entries
+            .iter()
+            .zip(entries.iter().skip(1))
+            .all(|(a, b)| a.buffered_at <= b.buffered_at));
+    }
+
+    #[test]
+    fn test_downsample_is_idempotent() {
+        let now = SystemTime::now();
+        let mut entries = day_of_samples(now);
+        let config = DownsampleConfig::default();
+
+        downsample(&mut entries, &config, now);
+        let len = entries.len();
+        assert_eq!(downsample(&mut entries, &config, now), 0);
+        assert_eq!(entries.len(), len);
+    }
+
+    #[test]
+    fn test_aggregate_averages_gauges() {
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(13 * 60 * 60);
+        let mut entries: VecDeque<_> = vec![
+            sample("c1", 300, 100, old),
+            sample("c1", 310, 300, old),
+            sample("c2", 300, 50, old),
+        ]
+        .into();
+
+        downsample(&mut entries, &DownsampleConfig::default(), now);
+        assert_eq!(entries.len(), 2);
+
+        let c1 = entries
+            .iter()
+            .find(|tm| tm.metrics.container_id == "c1")
+            .unwrap();
+        assert_eq!(c1.metrics.memory_usage_bytes, 200);
+        assert_eq!(c1.metrics.cpu_throttled_periods, 310);
+        assert_eq!(c1.metrics.timestamp, 300);
+    }
+}
@@ -0,0 +1,50 @@
+//! Errors raised while talking to the Recommendation API
+
+use std::time::Duration;
+
+/// Failure of a call to the Recommendation API
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    /// The circuit breaker is open, no request was made
+    #[error("Circuit breaker open for Recommendation API, retry in {}ms", retry_in.as_millis())]
+    CircuitOpen { retry_in: Duration },
+    /// Invalid endpoint, TLS material or proxy settings
+    #[error(transparent)]
+    Config(anyhow::Error),
+    /// Credentials could not be obtained or refreshed
+    #[error(transparent)]
+    Auth(anyhow::Error),
+    /// The API could not be reached
+    #[error("Failed to connect to {endpoint}")]
+    Connect {
+        endpoint: String,
+        #[source]
+        source: tonic::transport::Error,
+    },
+    /// A call reached the API and failed
+    #[error("{operation} failed: {status}")]
+    Rpc {
+        operation: &'static str,
+        #[source]
+        status: tonic::Status,
+    },
+    /// The API accepted the call but rejected its contents
+    #[error("{what} rejected: {message}")]
+    Rejected { what: &'static str, message: String },
+    /// The API does not support the call, no request was made
+    #[error("{operation} is not supported by the Recommendation API")]
+    Unsupported { operation: &'static str },
+}
+
+impl SyncError {
+    /// Whether the call may succeed if made again, after backing off
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SyncError::CircuitOpen { .. } | SyncError::Auth(_) | SyncError::Connect { .. } => true,
+            SyncError::Rpc { status, .. } => crate::error::is_retryable_status(status),
+            SyncError::Config(_) | SyncError::Rejected { .. } | SyncError::Unsupported { .. } => {
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Agent heartbeats
+//!
+//! Periodically reports a health summary (buffer depth, last collection time,
+//! model version, error counters) to the Recommendation API so per-node agent
+//! status is available without scraping Prometheus.
+
+use super::client::SyncClient;
+use crate::health::{ComponentStatus, HealthRegistry};
+use crate::observability::{AgentHealthSummary, AgentMetrics};
+use crate::proto::{AgentHealth, Capability};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Default heartbeat interval
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Shortest heartbeat interval the server may request
+const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Configuration for agent heartbeats
+#[derive(Debug, Clone)]
+pub struct HeartbeatConfig {
+    /// Interval between heartbeats, unless the server requests another
+    pub interval: Duration,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_HEARTBEAT_INTERVAL,
+        }
+    }
+}
+
+/// Background worker sending heartbeats
+pub struct HeartbeatWorker {
+    config: HeartbeatConfig,
+    agent_version: String,
+    metrics: AgentMetrics,
+    health: HealthRegistry,
+}
+
+impl HeartbeatWorker {
+    /// Create a new heartbeat worker
+    pub fn new(
+        config: HeartbeatConfig,
+        agent_version: impl Into<String>,
+        metrics: AgentMetrics,
+        health: HealthRegistry,
+    ) -> Self {
+        Self {
+            config,
+            agent_version: agent_version.into(),
+            metrics,
+            health,
+        }
+    }
+
+    /// Send heartbeats until the task is dropped, or for as long as the
+    /// API supports them
+    pub async fn run(&self, sync_client: Arc<SyncClient>) {
+        let mut interval = self.config.interval;
+
+        loop {
+            if !sync_client.supports(Capability::Heartbeat) {
+                info!("Recommendation API does not accept heartbeats, stopping them");
+                return;
+            }
+            let health = self.collect(&sync_client).await;
+            match sync_client.heartbeat(&self.agent_version, health).await {
+                Ok(response) => {
+                    debug!(acknowledged = response.acknowledged, "Heartbeat sent");
+                    if response.next_heartbeat_seconds > 0 {
+                        interval = Duration::from_secs(response.next_heartbeat_seconds as u64)
+                            .max(MIN_HEARTBEAT_INTERVAL);
+                    }
+                }
+                Err(e) => warn!(error = %e, "Failed to send heartbeat"),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Build the health report for the next heartbeat
+    async fn collect(&self, sync_client: &SyncClient) -> AgentHealth {
+        let health = self.health.health().await;
+        let connection = sync_client.connection_stats().await;
+
+        let mut report = to_proto(self.metrics.health_summary());
+        report.status = status_name(health.status).to_string();
+        report.sync_errors = connection.reconnect_attempts as i64;
+        report.unhealthy_components = health
+            .components
+            .into_iter()
+            .filter(|(_, component)| component.status != ComponentStatus::Healthy)
+            .map(|(name, component)| {
+                let message = component
+                    .message
+                    .unwrap_or_else(|| status_name(component.status).to_string());
+                (name, message)
+            })
+            .collect();
+
+        report
+    }
+}
+
+/// Convert a metrics summary to the heartbeat message
+fn to_proto(summary: AgentHealthSummary) -> AgentHealth {
+    AgentHealth {
+        status: String::new(),
+        buffer_items: summary.buffer_items,
+        buffer_size_bytes: summary.buffer_size_bytes,
+        last_collection_at: summary
+            .last_collection_at
+            .map(|seconds| prost_types::Timestamp { seconds, nanos: 0 }),
+        model_version: summary.model_version.unwrap_or_default(),
+        containers_monitored: summary.containers_monitored,
+        collection_errors: summary.collection_errors,
+        prediction_errors: summary.prediction_errors,
+        sync_errors: 0,
+        unhealthy_components: Default::default(),
+    }
+}
+
+fn status_name(status: ComponentStatus) -> &'static str {
+    match status {
+        ComponentStatus::Healthy => "healthy",
+        ComponentStatus::Degraded => "degraded",
+        ComponentStatus::Unhealthy => "unhealthy",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_proto() {
+        let report = to_proto(AgentHealthSummary {
+            buffer_items: 42,
+            buffer_size_bytes: 4096,
+            containers_monitored: 7,
+            last_collection_at: Some(1_700_000_000),
+            model_version: Some("v1.2.0".to_string()),
+            collection_errors: 3,
+            ..Default::default()
+        });
+
+        assert_eq!(report.buffer_items, 42);
+        assert_eq!(report.buffer_size_bytes, 4096);
+        assert_eq!(report.containers_monitored, 7);
+        assert_eq!(report.last_collection_at.unwrap().seconds, 1_700_000_000);
+        assert_eq!(report.model_version, "v1.2.0");
+        assert_eq!(report.collection_errors, 3);
+
+        let empty = to_proto(AgentHealthSummary::default());
+        assert!(empty.last_collection_at.is_none());
+        assert!(empty.model_version.is_empty());
+    }
+}
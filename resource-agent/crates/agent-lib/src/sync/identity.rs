@@ -0,0 +1,184 @@
+//! Dictionary encoding of container identity fields
+//!
+//! Every `ContainerMetrics` message would otherwise repeat the container ID,
+//! pod name, namespace and owner strings. On each stream, an identity is
+//! sent once as a `ContainerIdentity` and later samples carry only its
+//! `container_ref`. References are scoped to a single stream, so a new
+//! encoder is used whenever a stream is opened.
+
+use crate::intern::{intern, ContainerKey};
+use crate::proto::{ContainerIdentity, ContainerMetrics, MetricsBatch, WorkloadRef};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Identity fields of a container: key, deployment and owner kind and name
+type IdentityKey = (ContainerKey, Arc<str>, Option<(Arc<str>, Arc<str>)>);
+
+/// Replaces repeated identity fields with stream-scoped references
+#[derive(Debug, Default)]
+pub(super) struct IdentityEncoder {
+    refs: HashMap<IdentityKey, u32>,
+}
+
+impl IdentityEncoder {
+    /// Encode a batch in place, registering identities new to this stream
+    pub(super) fn encode(&mut self, batch: &mut MetricsBatch) {
+        for metrics in &mut batch.metrics {
+            // Interned, so known identities are looked up without copying
+            let key = (
+                ContainerKey::new(&metrics.container_id, &metrics.pod_name, &metrics.namespace),
+                intern(&metrics.deployment),
+                metrics
+                    .owner
+                    .take()
+                    .map(|owner| (intern(&owner.kind), intern(&owner.name))),
+            );
+            metrics.container_id.clear();
+            metrics.pod_name.clear();
+            metrics.namespace.clear();
+            metrics.deployment.clear();
+
+            let container_ref = match self.refs.get(&key) {
+                Some(container_ref) => *container_ref,
+                None => {
+                    let next_ref = self.refs.len() as u32 + 1;
+                    batch.container_identities.push(ContainerIdentity {
+                        r#ref: next_ref,
+                        container_id: key.0.container_id.to_string(),
+                        pod_name: key.0.pod_name.to_string(),
+                        namespace: key.0.namespace.to_string(),
+                        deployment: key.1.to_string(),
+                        owner: key.2.as_ref().map(|(kind, name)| WorkloadRef {
+                            kind: kind.to_string(),
+                            name: name.to_string(),
+                        }),
+                    });
+                    self.refs.insert(key, next_ref);
+                    next_ref
+                }
+            };
+            metrics.container_ref = container_ref;
+        }
+    }
+}
+
+/// Restores identity fields from stream-scoped references
+#[derive(Debug, Default)]
+pub struct IdentityDecoder {
+    identities: HashMap<u32, ContainerIdentity>,
+}
+
+impl IdentityDecoder {
+    /// Create a decoder for a new stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode a batch in place, remembering identities it registers
+    pub fn decode(&mut self, batch: &mut MetricsBatch) -> Result<()> {
+        for identity in batch.container_identities.drain(..) {
+            self.identities.insert(identity.r#ref, identity);
+        }
+
+        for metrics in &mut batch.metrics {
+            self.resolve(metrics)?;
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, metrics: &mut ContainerMetrics) -> Result<()> {
+        if metrics.container_ref == 0 {
+            return Ok(());
+        }
+
+        let identity = self
+            .identities
+            .get(&metrics.container_ref)
+            .ok_or_else(|| anyhow::anyhow!("Unknown container ref {}", metrics.container_ref))?;
+
+        metrics.container_id = identity.container_id.clone();
+        metrics.pod_name = identity.pod_name.clone();
+        metrics.namespace = identity.namespace.clone();
+        metrics.deployment = identity.deployment.clone();
+        metrics.owner = identity.owner.clone();
+        metrics.container_ref = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    fn batch(containers: usize, samples: usize) -> MetricsBatch {
+        let metrics = (0..samples)
+            .flat_map(|sample| {
+                (0..containers).map(move |c| ContainerMetrics {
+                    container_id: format!("containerd://{:064x}", c),
+                    pod_name: format!("checkout-service-7d9f8b6c5-{:05}", c),
+                    namespace: "production".to_string(),
+                    deployment: "checkout-service".to_string(),
+                    owner: Some(WorkloadRef {
+                        kind: "deployment".to_string(),
+                        name: "checkout-service".to_string(),
+                    }),
+                    cpu_usage_cores: 0.25,
+                    memory_usage_bytes: 128 * 1024 * 1024 + sample as u64,
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        MetricsBatch {
+            agent_id: "agent".to_string(),
+            node_name: "node".to_string(),
+            metrics,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_across_batches() {
+        let mut encoder = IdentityEncoder::default();
+        let mut decoder = IdentityDecoder::new();
+
+        for _ in 0..3 {
+            let original = batch(5, 2);
+            let mut encoded = original.clone();
+            encoder.encode(&mut encoded);
+
+            assert!(encoded.metrics.iter().all(|m| m.container_id.is_empty()));
+
+            decoder.decode(&mut encoded).unwrap();
+            assert_eq!(encoded, original);
+        }
+    }
+
+    #[test]
+    fn test_identities_sent_once_per_stream() {
+        let mut encoder = IdentityEncoder::default();
+
+        let mut first = batch(5, 2);
+        encoder.encode(&mut first);
+        assert_eq!(first.container_identities.len(), 5);
+
+        let mut second = batch(5, 2);
+        encoder.encode(&mut second);
+        assert!(second.container_identities.is_empty());
+
+        // Refs from another stream are unknown to a fresh decoder
+        assert!(IdentityDecoder::new().decode(&mut second).is_err());
+    }
+
+    #[test]
+    fn test_encoding_reduces_size() {
+        let original = batch(50, 6);
+        let mut encoded = original.clone();
+        IdentityEncoder::default().encode(&mut encoded);
+
+        let saved = 1.0 - encoded.encoded_len() as f64 / original.encoded_len() as f64;
+        assert!(saved > 0.6, "saved only {:.0}%", saved * 100.0);
+    }
+}
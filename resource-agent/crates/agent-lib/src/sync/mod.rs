@@ -1,25 +1,109 @@
 //! Synchronization with Recommendation API
 //!
+//! The gRPC client and everything built on it require the `grpc-sync`
+//! feature; the offline buffer and runtime config are always available.
+//!
 //! This module provides:
-//! - gRPC client with mTLS support for secure API communication
-//! - Local metrics buffer for offline operation
+//! - gRPC client with mTLS or token authentication for secure API communication
+//! - Egress through HTTP/SOCKS5 proxies
+//! - Jittered reconnect backoff and a circuit breaker for API outages
+//! - SPIFFE workload identity (with the `spiffe` feature)
+//! - Local metrics buffer for offline operation, downsampled near capacity
 //! - Metrics streaming with backpressure handling
-//! - Model update client with validation
+//! - Sync pipeline routing metrics through the offline buffer during outages
+//! - Model update client with signature validation, resumable chunked downloads
+//!   and shadow canarying of new models
+//! - Server-pushed agent configuration applied at runtime
+//! - Periodic heartbeats with an agent health summary
+//! - A scrape server for clusters where the API pulls from agents
+//! - Hashing of workload names before they leave the node, in privacy mode
+//! - Protocol version and capability negotiation with the API at registration
 
+#[cfg(feature = "grpc-sync")]
+mod auth;
 mod buffer;
+#[cfg(feature = "persistence")]
+mod buffer_log;
+#[cfg(feature = "grpc-sync")]
+mod capabilities;
+#[cfg_attr(not(feature = "grpc-sync"), allow(dead_code))]
+mod circuit_breaker;
+#[cfg(feature = "grpc-sync")]
 mod client;
+mod downsample;
+#[cfg(feature = "grpc-sync")]
+mod error;
+#[cfg(feature = "grpc-sync")]
+mod heartbeat;
+#[cfg(feature = "grpc-sync")]
+mod identity;
+#[cfg(feature = "grpc-sync")]
+mod model_download;
+#[cfg(feature = "grpc-sync")]
 mod model_update;
+#[cfg(feature = "grpc-sync")]
+mod pipeline;
+mod privacy;
+#[cfg(feature = "grpc-sync")]
+mod proxy;
+#[cfg(feature = "grpc-sync")]
+mod rate_limit;
+mod remote_config;
+#[cfg(feature = "grpc-sync")]
+mod scrape;
+#[cfg(feature = "grpc-sync")]
+mod signature;
+#[cfg(feature = "spiffe")]
+mod spiffe;
+#[cfg(feature = "grpc-sync")]
 mod streaming;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "grpc-sync"))]
 mod tests;
 
+#[cfg(feature = "grpc-sync")]
+pub use auth::{
+    AuthConfig, AuthInterceptor, AuthProvider, FileTokenProvider, OidcProvider,
+    DEFAULT_SERVICE_ACCOUNT_TOKEN_PATH,
+};
 pub use buffer::{BufferConfig, BufferStats, MetricsBuffer, OfflineBufferManager};
-pub use client::{ClientConfig, SyncClient, SyncClientBuilder};
+#[cfg(feature = "grpc-sync")]
+pub use capabilities::{Capabilities, PROTOCOL_VERSION};
+pub use circuit_breaker::CircuitState;
+#[cfg(feature = "grpc-sync")]
+pub use client::{
+    ClientConfig, ConnectionStats, GrpcCompression, SyncClient, SyncClientBuilder, SyncGrpcClient,
+    DEFAULT_FAILURE_THRESHOLD, DEFAULT_MAX_MESSAGE_SIZE,
+};
+pub use downsample::DownsampleConfig;
+#[cfg(feature = "grpc-sync")]
+pub use error::SyncError;
+#[cfg(feature = "grpc-sync")]
+pub use heartbeat::{HeartbeatConfig, HeartbeatWorker};
+#[cfg(feature = "grpc-sync")]
+pub use identity::IdentityDecoder;
+#[cfg(feature = "grpc-sync")]
+pub use model_download::DownloadProgress;
+#[cfg(feature = "grpc-sync")]
 pub use model_update::{
-    ModelUpdateClient, ModelUpdateConfig, ModelUpdateStats, ModelUpdateWorker, ModelVersion,
-    ValidationResult,
+    CanaryReport, ModelUpdateClient, ModelUpdateConfig, ModelUpdateStats, ModelUpdateWorker,
+    ModelVersion, ValidationResult,
+};
+#[cfg(feature = "grpc-sync")]
+pub use pipeline::{SyncPipeline, SyncPipelineConfig};
+pub use privacy::{NamePseudonymizer, PrivacyConfig, MIN_PRIVACY_KEY_LEN};
+pub(crate) use remote_config::next_update;
+pub use remote_config::{ConfigWatcher, RuntimeConfig};
+#[cfg(feature = "grpc-sync")]
+pub use scrape::{
+    ScrapeBuffer, ScrapePage, ScrapeServer, DEFAULT_SCRAPE_CAPACITY, DEFAULT_SCRAPE_PAGE,
 };
+#[cfg(feature = "grpc-sync")]
+pub use signature::parse_public_key;
+#[cfg(feature = "spiffe")]
+pub use spiffe::{SpiffeIdentity, SpiffeMaterial};
+#[cfg(feature = "grpc-sync")]
 pub use streaming::{
-    AnomalyData, MetricsStreamer, PendingData, StreamingConfig, StreamingStats, StreamingWorker,
+    proto_batch, AnomalyData, DataGap, MetricsStreamer, Overflow, PendingData, StreamingConfig,
+    StreamingStats, StreamingWorker,
 };
@@ -0,0 +1,205 @@
+//! Chunked, resumable model downloads
+//!
+//! Models too large for a single `GetModelUpdate` response are streamed with
+//! `DownloadModel`. Verified chunks are appended to a `.part` file next to the
+//! final model, so an interrupted transfer resumes from the last good byte
+//! instead of starting over.
+
+use crate::proto::ModelChunk;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Extension of in-progress download files
+pub(super) const PARTIAL_EXTENSION: &str = "part";
+
+/// Progress of an in-flight model download
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DownloadProgress {
+    pub version: String,
+    pub bytes_received: u64,
+    /// Total size, once the first chunk has arrived
+    pub total_bytes: Option<u64>,
+    /// Times the transfer was resumed after a failure
+    pub resumes: u32,
+    pub started_at: i64,
+}
+
+impl DownloadProgress {
+    pub(super) fn new(version: &str, bytes_received: u64) -> Self {
+        Self {
+            version: version.to_string(),
+            bytes_received,
+            total_bytes: None,
+            resumes: 0,
+            started_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Completed fraction (0.0-1.0), if the total size is known
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_bytes
+            .filter(|&total| total > 0)
+            .map(|total| self.bytes_received as f64 / total as f64)
+    }
+}
+
+/// Partially downloaded model file
+#[derive(Debug)]
+pub(super) struct PartialDownload {
+    path: PathBuf,
+    file: File,
+    offset: u64,
+    total_size: Option<u64>,
+    max_size: u64,
+}
+
+impl PartialDownload {
+    /// Open a partial file, resuming after any bytes it already holds
+    pub(super) fn open(path: &Path, max_size: u64) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open partial model file {:?}", path))?;
+        let offset = file
+            .metadata()
+            .with_context(|| format!("Failed to stat partial model file {:?}", path))?
+            .len();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            offset,
+            total_size: None,
+            max_size,
+        })
+    }
+
+    /// Bytes received so far
+    pub(super) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Total model size reported by the server
+    pub(super) fn total_size(&self) -> Option<u64> {
+        self.total_size
+    }
+
+    /// Verify and append a chunk, returning whether the download is complete
+    pub(super) fn write_chunk(&mut self, chunk: &ModelChunk) -> Result<bool> {
+        if chunk.offset < 0 || chunk.offset as u64 != self.offset {
+            anyhow::bail!(
+                "Out-of-order model chunk at offset {}, expected {}",
+                chunk.offset,
+                self.offset
+            );
+        }
+
+        let total = u64::try_from(chunk.total_size)
+            .ok()
+            .filter(|&total| total > 0)
+            .ok_or_else(|| anyhow::anyhow!("Invalid model size {}", chunk.total_size))?;
+        if total > self.max_size {
+            anyhow::bail!("Model size {} exceeds maximum {}", total, self.max_size);
+        }
+        self.total_size = Some(total);
+
+        let end = self.offset + chunk.data.len() as u64;
+        if end > total {
+            anyhow::bail!("Model chunk ends at {} past model size {}", end, total);
+        }
+
+        let checksum = hex::encode(Sha256::digest(&chunk.data));
+        if checksum != chunk.chunk_checksum {
+            anyhow::bail!(
+                "Chunk checksum mismatch at offset {}: expected {}, got {}",
+                chunk.offset,
+                chunk.chunk_checksum,
+                checksum
+            );
+        }
+
+        self.file
+            .write_all(&chunk.data)
+            .context("Failed to write model chunk")?;
+        self.offset = end;
+
+        if chunk.last && end != total {
+            anyhow::bail!("Model download ended at {} of {} bytes", end, total);
+        }
+        Ok(end == total)
+    }
+
+    /// Flush the completed download and read it back
+    pub(super) fn finish(self) -> Result<Vec<u8>> {
+        self.file
+            .sync_all()
+            .context("Failed to sync partial model file")?;
+        fs::read(&self.path).with_context(|| format!("Failed to read {:?}", self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn chunk(data: &[u8], offset: i64, total_size: i64) -> ModelChunk {
+        ModelChunk {
+            offset,
+            data: data.to_vec(),
+            chunk_checksum: hex::encode(Sha256::digest(data)),
+            total_size,
+            last: offset + data.len() as i64 == total_size,
+        }
+    }
+
+    #[test]
+    fn test_download_resumes_from_partial_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("model_v2.onnx.part");
+
+        let mut download = PartialDownload::open(&path, 1024).unwrap();
+        assert_eq!(download.offset(), 0);
+        assert!(!download.write_chunk(&chunk(b"hello ", 0, 11)).unwrap());
+        drop(download);
+
+        // Reopening continues where the interrupted transfer stopped
+        let mut download = PartialDownload::open(&path, 1024).unwrap();
+        assert_eq!(download.offset(), 6);
+        assert!(download.write_chunk(&chunk(b"world", 6, 11)).unwrap());
+        assert_eq!(download.total_size(), Some(11));
+        assert_eq!(download.finish().unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn test_rejects_corrupt_and_misplaced_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("model.part");
+        let mut download = PartialDownload::open(&path, 1024).unwrap();
+
+        let mut corrupt = chunk(b"abcd", 0, 8);
+        corrupt.data[0] = b'x';
+        assert!(download.write_chunk(&corrupt).is_err());
+        assert_eq!(download.offset(), 0);
+
+        assert!(download.write_chunk(&chunk(b"efgh", 4, 8)).is_err());
+        assert!(download.write_chunk(&chunk(b"abcd", 0, 4096)).is_err());
+        assert!(download.write_chunk(&chunk(b"abcd", 0, 8)).is_ok());
+        assert_eq!(download.offset(), 4);
+    }
+
+    #[test]
+    fn test_progress_fraction() {
+        let mut progress = DownloadProgress::new("v1", 0);
+        assert_eq!(progress.fraction(), None);
+
+        progress.bytes_received = 25;
+        progress.total_bytes = Some(100);
+        assert_eq!(progress.fraction(), Some(0.25));
+    }
+}
@@ -2,21 +2,45 @@
 //!
 //! This module provides:
 //! - Polling for model updates during low-activity periods
-//! - Checksum validation before applying updates
+//! - Chunked, resumable downloads for models too large for one response
+//! - Checksum and Ed25519 signature validation before applying updates
+//! - Canary evaluation of new models in shadow before promotion
 //! - Rollback support on validation failure
 
-use crate::proto::{ModelResponse, PredictorSyncClient};
+use super::model_download::{DownloadProgress, PartialDownload, PARTIAL_EXTENSION};
+use super::signature::ModelVerifier;
+use crate::health::ComponentReporter;
+use crate::observability::AgentMetrics;
+use crate::predictor::{OnnxPredictor, Predictor, ShadowModel, ShadowSlot, ShadowStats};
+use crate::proto::{DownloadModelRequest, ModelResponse, PredictorSyncClient};
 use anyhow::{Context, Result};
 use chrono::Timelike;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tonic::transport::Channel;
 use tracing::{debug, error, info, warn};
 
+/// Default preferred size of streamed model chunks
+const DEFAULT_DOWNLOAD_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Default number of times an interrupted download is resumed
+const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+
+/// Base delay before resuming an interrupted download
+const DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Default time a candidate model runs in shadow
+const DEFAULT_CANARY_DURATION: Duration = Duration::from_secs(3600);
+
+/// Default number of shadow comparisons needed to promote a candidate
+const DEFAULT_CANARY_MIN_SAMPLES: usize = 20;
+
 /// Configuration for model updates
 #[derive(Debug, Clone)]
 pub struct ModelUpdateConfig {
@@ -34,6 +58,22 @@ pub struct ModelUpdateConfig {
     pub versions_to_keep: usize,
     /// Maximum deviation threshold for auto-rollback (0.0-1.0)
     pub max_deviation_threshold: f32,
+    /// Preferred chunk size for streamed downloads
+    pub download_chunk_size: usize,
+    /// Times an interrupted download is resumed before giving up
+    pub download_retries: u32,
+    /// Trusted Ed25519 model signing keys (base64 or PEM)
+    pub signing_public_keys: Vec<String>,
+    /// File of additional trusted keys, e.g. from a mounted ConfigMap
+    pub signing_keys_path: Option<PathBuf>,
+    /// Refuse models that carry no signature
+    pub require_signature: bool,
+    /// Run new models in shadow before promoting them
+    pub canary_enabled: bool,
+    /// How long a candidate model runs in shadow
+    pub canary_duration: Duration,
+    /// Shadow comparisons required before a candidate can be promoted
+    pub canary_min_samples: usize,
 }
 
 impl Default for ModelUpdateConfig {
@@ -46,6 +86,14 @@ impl Default for ModelUpdateConfig {
             max_model_size: 100 * 1024,               // 100KB
             versions_to_keep: 5,
             max_deviation_threshold: 0.20, // 20%
+            download_chunk_size: DEFAULT_DOWNLOAD_CHUNK_SIZE,
+            download_retries: DEFAULT_DOWNLOAD_RETRIES,
+            signing_public_keys: Vec::new(),
+            signing_keys_path: None,
+            require_signature: true,
+            canary_enabled: false,
+            canary_duration: DEFAULT_CANARY_DURATION,
+            canary_min_samples: DEFAULT_CANARY_MIN_SAMPLES,
         }
     }
 }
@@ -67,6 +115,9 @@ pub struct ModelUpdateClient {
     agent_id: String,
     current_version: RwLock<Option<ModelVersion>>,
     previous_versions: RwLock<Vec<ModelVersion>>,
+    download: RwLock<Option<DownloadProgress>>,
+    verifier: ModelVerifier,
+    last_canary: RwLock<Option<CanaryReport>>,
 }
 
 impl ModelUpdateClient {
@@ -76,11 +127,20 @@ impl ModelUpdateClient {
         fs::create_dir_all(&config.model_dir)
             .with_context(|| format!("Failed to create model directory {:?}", config.model_dir))?;
 
+        let verifier = ModelVerifier::new(
+            &config.signing_public_keys,
+            config.signing_keys_path.as_deref(),
+            config.require_signature,
+        )?;
+
         let client = Self {
             config,
             agent_id,
             current_version: RwLock::new(None),
             previous_versions: RwLock::new(Vec::new()),
+            download: RwLock::new(None),
+            verifier,
+            last_canary: RwLock::new(None),
         };
 
         Ok(client)
@@ -117,10 +177,26 @@ impl ModelUpdateClient {
             .map(|v| v.path.clone())
     }
 
-    /// Check for and download model updates
+    /// Check for, download and apply model updates
     pub async fn check_for_update(
         &self,
         client: &mut PredictorSyncClient<Channel>,
+    ) -> Result<Option<ModelVersion>> {
+        let Some(new_version) = self.fetch_update(client).await? else {
+            return Ok(None);
+        };
+
+        self.promote(new_version.clone()).await;
+        Ok(Some(new_version))
+    }
+
+    /// Check for and download a model update without applying it
+    ///
+    /// The returned version is validated and saved to disk; it becomes
+    /// current only once passed to [`promote`](Self::promote).
+    pub async fn fetch_update(
+        &self,
+        client: &mut PredictorSyncClient<Channel>,
     ) -> Result<Option<ModelVersion>> {
         let current_version = self.current_version().await.unwrap_or_default();
 
@@ -135,7 +211,7 @@ impl ModelUpdateClient {
             current_model_version: current_version.clone(),
         });
 
-        let response = client
+        let mut response = client
             .get_model_update(request)
             .await
             .context("Failed to check for model update")?
@@ -152,14 +228,143 @@ impl ModelUpdateClient {
             "Model update available"
         );
 
-        // Validate and apply the update
-        let new_version = self.apply_update(response).await?;
+        if !response.download_required {
+            return self.stage_update(response).map(Some);
+        }
+
+        let version = response.new_version.clone();
+        response.model_weights = self.download_model(client, &version).await?;
+        let result = self.stage_update(response);
 
-        Ok(Some(new_version))
+        // A complete download that failed validation can't be resumed
+        if let Err(e) = fs::remove_file(self.partial_path(&version)) {
+            debug!(error = %e, "Failed to remove partial model file");
+        }
+
+        result.map(Some)
+    }
+
+    /// Path of the in-progress download for a version
+    fn partial_path(&self, version: &str) -> PathBuf {
+        self.config
+            .model_dir
+            .join(format!("model_{}.onnx.{}", version, PARTIAL_EXTENSION))
+    }
+
+    /// Remove partial downloads of versions other than `keep`
+    fn remove_stale_partials(&self, keep: &Path) {
+        let Ok(entries) = fs::read_dir(&self.config.model_dir) else {
+            return;
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let partial = path.extension().is_some_and(|ext| ext == PARTIAL_EXTENSION);
+            if partial && path != keep {
+                debug!(path = %path.display(), "Removing stale partial model download");
+                fs::remove_file(&path).ok();
+            }
+        }
     }
 
-    /// Apply a model update
-    async fn apply_update(&self, response: ModelResponse) -> Result<ModelVersion> {
+    /// Stream a model with `DownloadModel`, resuming after interruptions
+    async fn download_model(
+        &self,
+        client: &mut PredictorSyncClient<Channel>,
+        version: &str,
+    ) -> Result<Vec<u8>> {
+        let path = self.partial_path(version);
+        self.remove_stale_partials(&path);
+
+        let mut download = PartialDownload::open(&path, self.config.max_model_size as u64)?;
+        if download.offset() > 0 {
+            info!(
+                version = %version,
+                offset = download.offset(),
+                "Resuming interrupted model download"
+            );
+        }
+        *self.download.write().await = Some(DownloadProgress::new(version, download.offset()));
+
+        let mut attempt = 0;
+        loop {
+            match self.stream_chunks(client, version, &mut download).await {
+                Ok(()) => break,
+                Err(e) if attempt < self.config.download_retries => {
+                    attempt += 1;
+                    let delay = DOWNLOAD_RETRY_DELAY * attempt;
+                    warn!(
+                        version = %version,
+                        error = %e,
+                        offset = download.offset(),
+                        attempt = attempt,
+                        retry_in_secs = delay.as_secs(),
+                        "Model download interrupted, resuming"
+                    );
+                    if let Some(progress) = self.download.write().await.as_mut() {
+                        progress.resumes += 1;
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    return Err(e.context(format!(
+                        "Model download failed at byte {}",
+                        download.offset()
+                    )))
+                }
+            }
+        }
+
+        *self.download.write().await = None;
+        info!(
+            version = %version,
+            size = download.offset(),
+            resumes = attempt,
+            "Model download complete"
+        );
+        download.finish()
+    }
+
+    /// Receive chunks from a single `DownloadModel` stream
+    async fn stream_chunks(
+        &self,
+        client: &mut PredictorSyncClient<Channel>,
+        version: &str,
+        download: &mut PartialDownload,
+    ) -> Result<()> {
+        let request = tonic::Request::new(DownloadModelRequest {
+            agent_id: self.agent_id.clone(),
+            version: version.to_string(),
+            offset: download.offset() as i64,
+            chunk_size: self.config.download_chunk_size as i32,
+        });
+
+        let mut stream = client
+            .download_model(request)
+            .await
+            .context("Failed to open model download stream")?
+            .into_inner();
+
+        while let Some(chunk) = stream
+            .message()
+            .await
+            .context("Model download stream failed")?
+        {
+            let complete = download.write_chunk(&chunk)?;
+
+            if let Some(progress) = self.download.write().await.as_mut() {
+                progress.bytes_received = download.offset();
+                progress.total_bytes = download.total_size();
+            }
+            if complete {
+                return Ok(());
+            }
+        }
+
+        anyhow::bail!("Model download stream ended at byte {}", download.offset())
+    }
+
+    /// Validate a model update and save it to disk
+    fn stage_update(&self, response: ModelResponse) -> Result<ModelVersion> {
         // Validate model size
         if response.model_weights.len() > self.config.max_model_size {
             return Err(anyhow::anyhow!(
@@ -179,11 +384,21 @@ impl ModelUpdateClient {
             ));
         }
 
+        self.verifier
+            .verify(&response.model_weights, &response.signature)
+            .with_context(|| {
+                format!(
+                    "Refusing model {}: signature verification failed",
+                    response.new_version
+                )
+            })?;
+
         info!(
             version = %response.new_version,
             size = response.model_weights.len(),
             checksum = %computed_checksum,
-            "Model checksum validated"
+            signed = !response.signature.is_empty(),
+            "Model checksum and signature validated"
         );
 
         // Save model to disk
@@ -204,6 +419,11 @@ impl ModelUpdateClient {
             downloaded_at: chrono::Utc::now().timestamp(),
         };
 
+        Ok(new_version)
+    }
+
+    /// Make a downloaded version current, keeping the old one for rollback
+    pub async fn promote(&self, new_version: ModelVersion) {
         // Move current version to previous versions
         {
             let mut current = self.current_version.write().await;
@@ -234,8 +454,17 @@ impl ModelUpdateClient {
             path = %new_version.path.display(),
             "Model update applied successfully"
         );
+    }
 
-        Ok(new_version)
+    /// Delete a downloaded version that won't be promoted
+    pub fn discard(&self, version: &ModelVersion) {
+        if let Err(e) = fs::remove_file(&version.path) {
+            warn!(
+                path = %version.path.display(),
+                error = %e,
+                "Failed to remove discarded model file"
+            );
+        }
     }
 
     /// Save model weights to disk
@@ -373,6 +602,8 @@ impl ModelUpdateClient {
             current_size_bytes: current.as_ref().map(|v| v.size_bytes),
             available_rollback_versions: previous.len(),
             last_update_time: current.as_ref().map(|v| v.downloaded_at),
+            download: self.download.read().await.clone(),
+            last_canary: self.last_canary.read().await.clone(),
         }
     }
 }
@@ -394,26 +625,77 @@ pub struct ValidationResult {
 }
 
 /// Model update statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ModelUpdateStats {
     pub current_version: Option<String>,
     pub current_size_bytes: Option<usize>,
     pub available_rollback_versions: usize,
     pub last_update_time: Option<i64>,
+    /// In-flight streamed download, if any
+    pub download: Option<DownloadProgress>,
+    /// Outcome of the most recent canary evaluation
+    pub last_canary: Option<CanaryReport>,
+}
+
+/// Outcome of running a candidate model in shadow
+#[derive(Debug, Clone, Serialize)]
+pub struct CanaryReport {
+    pub version: String,
+    pub promoted: bool,
+    /// Why the candidate was promoted or discarded
+    pub reason: String,
+    pub samples: usize,
+    pub errors: usize,
+    pub mean_deviation: f64,
+    pub max_deviation: f64,
+    pub finished_at: i64,
+}
+
+/// Decide whether shadow results allow promoting a candidate
+fn evaluate_canary(
+    stats: &ShadowStats,
+    min_samples: usize,
+    max_deviation: f64,
+) -> std::result::Result<(), String> {
+    if stats.errors > 0 {
+        return Err(format!("{} shadow inferences failed", stats.errors));
+    }
+    if stats.samples < min_samples {
+        return Err(format!(
+            "Only {} of {} required shadow samples",
+            stats.samples, min_samples
+        ));
+    }
+    if stats.mean_deviation() > max_deviation {
+        return Err(format!(
+            "Mean deviation {:.1}% exceeds {:.1}%",
+            stats.mean_deviation() * 100.0,
+            max_deviation * 100.0
+        ));
+    }
+    Ok(())
 }
 
 /// Background model update worker
 pub struct ModelUpdateWorker {
-    client: ModelUpdateClient,
+    client: Arc<ModelUpdateClient>,
     grpc_client: Option<PredictorSyncClient<Channel>>,
+    predictor: Option<Arc<RwLock<OnnxPredictor>>>,
+    shadow: Option<ShadowSlot>,
+    health: Option<ComponentReporter>,
+    metrics: AgentMetrics,
 }
 
 impl ModelUpdateWorker {
     /// Create a new model update worker
     pub fn new(config: ModelUpdateConfig, agent_id: String) -> Result<Self> {
         Ok(Self {
-            client: ModelUpdateClient::new(config, agent_id)?,
+            client: Arc::new(ModelUpdateClient::new(config, agent_id)?),
             grpc_client: None,
+            predictor: None,
+            shadow: None,
+            health: None,
+            metrics: AgentMetrics::new(),
         })
     }
 
@@ -422,6 +704,21 @@ impl ModelUpdateWorker {
         self.grpc_client = Some(client);
     }
 
+    /// Set the live predictor that promoted models are loaded into
+    pub fn set_predictor(&mut self, predictor: Arc<RwLock<OnnxPredictor>>) {
+        self.predictor = Some(predictor);
+    }
+
+    /// Set the slot shared with the prediction scheduler for canary runs
+    pub fn set_shadow_slot(&mut self, slot: ShadowSlot) {
+        self.shadow = Some(slot);
+    }
+
+    /// Set the reporter for model update health
+    pub fn set_health(&mut self, reporter: ComponentReporter) {
+        self.health = Some(reporter);
+    }
+
     /// Run the update check loop
     pub async fn run(&mut self) {
         let poll_interval = self.client.config.poll_interval;
@@ -429,6 +726,9 @@ impl ModelUpdateWorker {
         loop {
             // Wait for poll interval
             tokio::time::sleep(poll_interval).await;
+            if let Some(health) = &self.health {
+                health.heartbeat();
+            }
 
             // Check if we're in the update window
             if !self.client.is_update_window() {
@@ -437,32 +737,160 @@ impl ModelUpdateWorker {
             }
 
             // Check for updates
-            if let Some(ref mut grpc_client) = self.grpc_client {
-                match self.client.check_for_update(grpc_client).await {
-                    Ok(Some(version)) => {
-                        info!(version = %version.version, "Model updated successfully");
-                    }
-                    Ok(None) => {
-                        debug!("No model update available");
-                    }
-                    Err(e) => {
-                        error!(error = %e, "Failed to check for model update");
+            let Some(grpc_client) = self.grpc_client.as_mut() else {
+                warn!("No gRPC client configured for model updates");
+                continue;
+            };
+
+            let candidate = match self.client.fetch_update(grpc_client).await {
+                Ok(Some(candidate)) => candidate,
+                Ok(None) => {
+                    debug!("No model update available");
+                    self.report(Ok(()));
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to check for model update");
+                    self.report(Err(&e));
+                    continue;
+                }
+            };
+
+            let version = candidate.version.clone();
+            match self.roll_out(candidate).await {
+                Ok(true) => {
+                    info!(version = %version, "Model updated successfully");
+                    self.metrics.inc_model_updates("applied");
+                    self.report(Ok(()));
+                }
+                Ok(false) => {
+                    self.metrics.inc_model_updates("rejected");
+                    self.report(Ok(()));
+                }
+                Err(e) => {
+                    error!(version = %version, error = %e, "Failed to apply model update");
+                    self.metrics.inc_model_updates("failed");
+                    self.report(Err(&e));
+                }
+            }
+        }
+    }
+
+    /// Record the outcome of an update check
+    fn report(&self, outcome: Result<(), &anyhow::Error>) {
+        let Some(health) = &self.health else {
+            return;
+        };
+        match outcome {
+            Ok(()) => health.success(),
+            Err(e) => health.failure(format!("{:#}", e)),
+        }
+    }
+
+    /// Canary a downloaded model if enabled, then promote it
+    ///
+    /// Returns whether the model was promoted.
+    async fn roll_out(&self, candidate: ModelVersion) -> Result<bool> {
+        let weights = fs::read(&candidate.path)
+            .with_context(|| format!("Failed to read model file {:?}", candidate.path))?;
+
+        if self.client.config.canary_enabled {
+            match &self.shadow {
+                Some(slot) => {
+                    let report = self.run_canary(slot, &candidate, &weights).await;
+                    let promoted = report.promoted;
+                    *self.client.last_canary.write().await = Some(report);
+                    if !promoted {
+                        self.client.discard(&candidate);
+                        return Ok(false);
                     }
                 }
-            } else {
-                warn!("No gRPC client configured for model updates");
+                None => warn!("Model canary enabled without a shadow slot, promoting directly"),
             }
         }
+
+        if let Some(predictor) = &self.predictor {
+            if let Err(e) = predictor.write().await.update_model(&weights) {
+                self.client.discard(&candidate);
+                return Err(anyhow::Error::new(e).context("Failed to load new model"));
+            }
+        }
+
+        self.client.promote(candidate).await;
+        Ok(true)
     }
 
-    /// Get the underlying client for direct access
-    pub fn client(&self) -> &ModelUpdateClient {
-        &self.client
+    /// Run a candidate in shadow for the canary period and judge the results
+    async fn run_canary(
+        &self,
+        slot: &ShadowSlot,
+        candidate: &ModelVersion,
+        weights: &[u8],
+    ) -> CanaryReport {
+        let config = &self.client.config;
+
+        let stats = match OnnxPredictor::new(weights) {
+            Ok(predictor) => {
+                let shadow = Arc::new(ShadowModel::new(&candidate.version, Box::new(predictor)));
+                *slot.write().await = Some(shadow.clone());
+                info!(
+                    version = %candidate.version,
+                    duration_secs = config.canary_duration.as_secs(),
+                    "Running model canary in shadow"
+                );
+
+                tokio::time::sleep(config.canary_duration).await;
+                *slot.write().await = None;
+                Ok(shadow.stats())
+            }
+            Err(e) => Err(format!("Failed to load candidate model: {}", e)),
+        };
+
+        let verdict = stats.as_ref().map_err(Clone::clone).and_then(|stats| {
+            evaluate_canary(
+                stats,
+                config.canary_min_samples,
+                config.max_deviation_threshold as f64,
+            )
+        });
+        let stats = stats.unwrap_or_default();
+
+        let report = CanaryReport {
+            version: candidate.version.clone(),
+            promoted: verdict.is_ok(),
+            reason: verdict
+                .err()
+                .unwrap_or_else(|| "Shadow deviation within threshold".to_string()),
+            samples: stats.samples,
+            errors: stats.errors,
+            mean_deviation: stats.mean_deviation(),
+            max_deviation: stats.max_deviation,
+            finished_at: chrono::Utc::now().timestamp(),
+        };
+
+        if report.promoted {
+            info!(
+                version = %report.version,
+                samples = report.samples,
+                mean_deviation = report.mean_deviation,
+                "Model canary passed"
+            );
+        } else {
+            warn!(
+                version = %report.version,
+                samples = report.samples,
+                mean_deviation = report.mean_deviation,
+                max_deviation = report.max_deviation,
+                reason = %report.reason,
+                "Model canary failed, discarding candidate"
+            );
+        }
+        report
     }
 
-    /// Get mutable access to the underlying client
-    pub fn client_mut(&mut self) -> &mut ModelUpdateClient {
-        &mut self.client
+    /// Get the underlying client, shareable with e.g. the state endpoint
+    pub fn client(&self) -> &Arc<ModelUpdateClient> {
+        &self.client
     }
 }
 
@@ -552,6 +980,62 @@ mod tests {
         assert!(result.unwrap().is_none());
     }
 
+    #[test]
+    fn test_evaluate_canary() {
+        let stats = ShadowStats {
+            samples: 30,
+            errors: 0,
+            total_deviation: 3.0,
+            max_deviation: 0.4,
+        };
+        assert!(evaluate_canary(&stats, 20, 0.20).is_ok());
+
+        // Too few samples, too much deviation or any shadow error all fail
+        assert!(evaluate_canary(&stats, 50, 0.20).is_err());
+        assert!(evaluate_canary(&stats, 20, 0.05).is_err());
+        let failing = ShadowStats { errors: 1, ..stats };
+        assert!(evaluate_canary(&failing, 20, 0.20)
+            .unwrap_err()
+            .contains("failed"));
+    }
+
+    #[tokio::test]
+    async fn test_discarded_candidate_keeps_current() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ModelUpdateConfig {
+            model_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let client = ModelUpdateClient::new(config, "test-agent".to_string()).unwrap();
+
+        let current_path = temp_dir.path().join("model_v1.onnx");
+        fs::write(&current_path, b"v1").unwrap();
+        client
+            .load_existing_model("v1", &current_path)
+            .await
+            .unwrap();
+
+        let candidate_path = temp_dir.path().join("model_v2.onnx");
+        fs::write(&candidate_path, b"v2").unwrap();
+        let candidate = ModelVersion {
+            version: "v2".to_string(),
+            path: candidate_path.clone(),
+            checksum: compute_checksum(b"v2"),
+            size_bytes: 2,
+            validation_accuracy: None,
+            downloaded_at: 0,
+        };
+
+        client.discard(&candidate);
+        assert!(!candidate_path.exists());
+        assert_eq!(client.current_version().await, Some("v1".to_string()));
+
+        fs::write(&candidate_path, b"v2").unwrap();
+        client.promote(candidate).await;
+        assert_eq!(client.current_version().await, Some("v2".to_string()));
+        assert_eq!(client.available_rollback_versions().await, vec!["v1"]);
+    }
+
     #[test]
     fn test_exceeds_deviation_threshold() {
         let config = ModelUpdateConfig {
@@ -0,0 +1,281 @@
+//! Sync pipeline tying the offline buffer to the metrics streamer
+//!
+//! Routes collected metrics either straight to the streamer or, while the
+//! Recommendation API is unreachable, into the offline buffer. On reconnection
+//! the buffer is drained oldest-first in rate-limited batches.
+//!
+//! While connected, the pipeline periodically probes the client's channel, so
+//! a silently dropped connection sends metrics to the buffer as soon as
+//! keepalive notices, instead of once a send fails.
+
+use super::buffer::TimestampedMetrics;
+use super::{BufferStats, MetricsStreamer, OfflineBufferManager, PendingData, SyncClient};
+use crate::models::ContainerMetrics;
+use crate::observability::AgentMetrics;
+#[cfg(feature = "remote-write")]
+use crate::observability::RemoteWriteHandle;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Default number of buffered samples sent per drain tick
+const DEFAULT_DRAIN_BATCH_SIZE: usize = 500;
+
+/// Default interval between drain ticks
+const DEFAULT_DRAIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default interval between connection probes while connected
+const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Configuration for the sync pipeline
+#[derive(Debug, Clone)]
+pub struct SyncPipelineConfig {
+    /// Maximum buffered samples sent per drain tick
+    pub drain_batch_size: usize,
+    /// Interval between drain ticks (limits replay rate after an outage)
+    pub drain_interval: Duration,
+    /// Interval between connection probes while connected (zero disables)
+    pub probe_interval: Duration,
+}
+
+impl Default for SyncPipelineConfig {
+    fn default() -> Self {
+        Self {
+            drain_batch_size: DEFAULT_DRAIN_BATCH_SIZE,
+            drain_interval: DEFAULT_DRAIN_INTERVAL,
+            probe_interval: DEFAULT_PROBE_INTERVAL,
+        }
+    }
+}
+
+/// Routes metrics through the offline buffer or the streamer
+pub struct SyncPipeline {
+    config: SyncPipelineConfig,
+    streamer: MetricsStreamer,
+    buffer: Arc<Mutex<OfflineBufferManager>>,
+    metrics: AgentMetrics,
+    #[cfg(feature = "remote-write")]
+    remote_write: Option<RemoteWriteHandle>,
+}
+
+impl SyncPipeline {
+    /// Create a new sync pipeline
+    pub fn new(
+        config: SyncPipelineConfig,
+        streamer: MetricsStreamer,
+        buffer: OfflineBufferManager,
+        metrics: AgentMetrics,
+    ) -> Self {
+        Self {
+            config,
+            streamer,
+            buffer: Arc::new(Mutex::new(buffer)),
+            metrics,
+            #[cfg(feature = "remote-write")]
+            remote_write: None,
+        }
+    }
+
+    /// Also copy submitted metrics to a remote-write exporter
+    #[cfg(feature = "remote-write")]
+    pub fn with_remote_write(mut self, handle: RemoteWriteHandle) -> Self {
+        self.remote_write = Some(handle);
+        self
+    }
+
+    /// Get a clone of the offline buffer handle, to pass to the worker
+    pub fn buffer_handle(&self) -> Arc<Mutex<OfflineBufferManager>> {
+        Arc::clone(&self.buffer)
+    }
+
+    /// Update the connection state reported by the sync client
+    pub async fn set_connected(&self, connected: bool) {
+        let mut buffer = self.buffer.lock().await;
+        if connected {
+            buffer.go_online();
+        } else {
+            buffer.go_offline();
+        }
+    }
+
+    /// Check if the pipeline is currently buffering
+    pub async fn is_offline(&self) -> bool {
+        self.buffer.lock().await.is_offline()
+    }
+
+    /// Number of samples waiting in the offline buffer
+    pub async fn pending_count(&self) -> usize {
+        self.buffer.lock().await.pending_sync_count()
+    }
+
+    /// Buffered samples in `[from, to]`, of one container when given
+    pub async fn query_buffered(
+        &self,
+        container_id: Option<&str>,
+        from: i64,
+        to: i64,
+    ) -> Vec<ContainerMetrics> {
+        self.buffer.lock().await.query(container_id, from, to)
+    }
+
+    /// Offline buffer statistics
+    pub async fn buffer_stats(&self) -> BufferStats {
+        self.buffer.lock().await.stats()
+    }
+
+    /// Submit freshly collected metrics
+    ///
+    /// Metrics are buffered while offline, and also while older buffered data
+    /// is still draining so that the API receives samples in order.
+    pub async fn submit_metrics(&self, metrics: Vec<ContainerMetrics>) {
+        if metrics.is_empty() {
+            return;
+        }
+
+        #[cfg(feature = "remote-write")]
+        if let Some(remote_write) = &self.remote_write {
+            remote_write.submit(&metrics);
+        }
+
+        let mut buffer = self.buffer.lock().await;
+
+        if !buffer.is_offline() && !buffer.has_data_to_sync() {
+            let data = PendingData {
+                metrics,
+                ..Default::default()
+            };
+            let Err(data) = self.streamer.try_send(data) else {
+                return;
+            };
+
+            debug!("Streaming channel full, buffering metrics locally");
+            for m in data.metrics {
+                buffer.buffer(m);
+            }
+        } else {
+            for m in metrics {
+                buffer.buffer(m);
+            }
+        }
+
+        self.update_gauges(&buffer);
+    }
+
+    /// Send one batch of buffered metrics, oldest first
+    ///
+    /// Returns the number of samples handed to the streamer.
+    pub async fn drain_buffered(&self) -> Result<usize> {
+        let mut buffer = self.buffer.lock().await;
+        if buffer.is_offline() || !buffer.has_data_to_sync() {
+            return Ok(0);
+        }
+
+        let batch = buffer.take_batch_for_sync(self.config.drain_batch_size);
+        let count = batch.len();
+        let (metrics, buffered_at): (Vec<_>, Vec<_>) = batch
+            .into_iter()
+            .map(|tm| (tm.metrics, tm.buffered_at))
+            .unzip();
+
+        let data = PendingData {
+            metrics,
+            ..Default::default()
+        };
+        if let Err(data) = self.streamer.try_send(data) {
+            // Put the batch back at the front, as it was buffered
+            let batch = data
+                .metrics
+                .into_iter()
+                .zip(buffered_at)
+                .map(|(metrics, buffered_at)| TimestampedMetrics {
+                    metrics,
+                    buffered_at,
+                })
+                .collect();
+            buffer.restore_front(batch);
+            debug!("Streaming channel full, deferring buffer drain");
+            return Ok(0);
+        }
+
+        self.update_gauges(&buffer);
+
+        if !buffer.has_data_to_sync() {
+            info!("Offline buffer fully drained");
+        }
+
+        Ok(count)
+    }
+
+    /// Run the pipeline, tracking connectivity and draining the buffer
+    ///
+    /// Returns once `shutdown` fires, after a last flush of the buffer.
+    pub async fn run(&self, sync_client: Arc<SyncClient>, mut shutdown: broadcast::Receiver<()>) {
+        let mut interval = tokio::time::interval(self.config.drain_interval);
+        let mut next_probe = Instant::now();
+        let mut next_health_check = Instant::now() + self.config.probe_interval;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.recv() => break,
+            }
+
+            let mut connected = sync_client.is_connected().await;
+
+            // Nothing else opens a connection while we're buffering, so probe
+            // the API ourselves, respecting the client's backoff
+            if !connected && Instant::now() >= next_probe {
+                match sync_client.get_streaming_client().await {
+                    Ok(_) => connected = true,
+                    Err(e) => {
+                        debug!(error = %e, "Recommendation API still unreachable");
+                        next_probe = Instant::now() + sync_client.get_reconnect_backoff().await;
+                    }
+                }
+            }
+
+            // Catch connections that died silently before data piles up
+            // behind them in the streamer
+            if connected
+                && !self.config.probe_interval.is_zero()
+                && Instant::now() >= next_health_check
+            {
+                next_health_check = Instant::now() + self.config.probe_interval;
+                if !sync_client.probe_connection().await {
+                    info!("Connection probe failed, buffering metrics locally");
+                    connected = false;
+                }
+            }
+
+            self.set_connected(connected).await;
+
+            if connected {
+                if let Err(e) = self.drain_buffered().await {
+                    warn!(error = %e, "Failed to drain offline buffer");
+                }
+            }
+
+            self.flush().await;
+        }
+
+        self.flush().await;
+        info!("Sync pipeline stopped");
+    }
+
+    /// Persist the offline buffer, if it is backed by disk
+    async fn flush(&self) {
+        if let Err(e) = self.buffer.lock().await.flush() {
+            warn!(error = %e, "Failed to flush offline buffer");
+        }
+    }
+
+    /// Update the buffer gauges from current buffer state
+    fn update_gauges(&self, buffer: &OfflineBufferManager) {
+        let stats = buffer.stats();
+        self.metrics
+            .set_buffer_size(stats.memory_bytes as i64, stats.entries as i64);
+    }
+}
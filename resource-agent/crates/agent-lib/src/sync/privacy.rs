@@ -0,0 +1,352 @@
+//! Pseudonymization of workload names leaving the node
+//!
+//! In privacy mode, pod, namespace and owner names are replaced by keyed
+//! HMAC-SHA256 hashes before batches are sent to the API, so the control
+//! plane can group and join data without learning workload names. Keys are
+//! provisioned by the control plane; tenants with their own namespace key
+//! get names that can't be correlated with other tenants' data. The
+//! hash-to-name mapping stays on the node, for rendering alerts that refer
+//! to hashed names.
+
+use super::RuntimeConfig;
+use crate::proto::{self, MetricsBatch, WorkloadRef};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+/// Shortest accepted HMAC key
+pub const MIN_PRIVACY_KEY_LEN: usize = 16;
+
+/// Prefix marking a hashed name
+const HASH_PREFIX: &str = "h-";
+
+/// Hex characters of the HMAC kept in a hashed name (96 bits)
+const HASH_HEX_LEN: usize = 24;
+
+/// Keys for hashing workload names
+#[derive(Clone, PartialEq)]
+pub struct PrivacyConfig {
+    /// Key for namespaces, and for names in namespaces without their own key
+    pub key: Vec<u8>,
+    /// Identifies the key, so the control plane can tell rotations apart
+    pub key_id: String,
+    /// Per-tenant keys for names within a namespace
+    pub namespace_keys: HashMap<String, Vec<u8>>,
+}
+
+impl std::fmt::Debug for PrivacyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut namespaces: Vec<_> = self.namespace_keys.keys().collect();
+        namespaces.sort();
+        f.debug_struct("PrivacyConfig")
+            .field("key_id", &self.key_id)
+            .field("namespace_keys", &namespaces)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PrivacyConfig {
+    /// Validate and convert the privacy settings received from the control
+    /// plane, `None` when name hashing is off
+    pub fn from_proto(config: &proto::PrivacyConfig) -> anyhow::Result<Option<Self>> {
+        if !config.hash_names {
+            return Ok(None);
+        }
+        if config.key.len() < MIN_PRIVACY_KEY_LEN {
+            anyhow::bail!("Privacy key must be at least {} bytes", MIN_PRIVACY_KEY_LEN);
+        }
+        if let Some((namespace, _)) = config
+            .namespace_keys
+            .iter()
+            .find(|(_, key)| key.len() < MIN_PRIVACY_KEY_LEN)
+        {
+            anyhow::bail!(
+                "Privacy key for namespace {} must be at least {} bytes",
+                namespace,
+                MIN_PRIVACY_KEY_LEN
+            );
+        }
+        Ok(Some(Self {
+            key: config.key.clone(),
+            key_id: config.key_id.clone(),
+            namespace_keys: config.namespace_keys.clone(),
+        }))
+    }
+
+    /// Hashed form of a namespace
+    pub fn hash_namespace(&self, namespace: &str) -> String {
+        hashed_name(&self.key, namespace)
+    }
+
+    /// Hashed form of a pod or owner name in `namespace`
+    pub fn hash_name(&self, namespace: &str, name: &str) -> String {
+        let key = self.namespace_keys.get(namespace).unwrap_or(&self.key);
+        hashed_name(key, name)
+    }
+}
+
+/// HMAC-SHA256 as defined in RFC 2104
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_LEN: usize = 64;
+    let mut block = [0u8; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<_>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn hashed_name(key: &[u8], name: &str) -> String {
+    let mut digest = hex::encode(hmac_sha256(key, name.as_bytes()));
+    digest.truncate(HASH_HEX_LEN);
+    format!("{}{}", HASH_PREFIX, digest)
+}
+
+/// Hashes names in outgoing batches while the control plane enables it
+///
+/// Empty names are left empty, so fields that were never set stay unset.
+pub struct NamePseudonymizer {
+    runtime: watch::Receiver<RuntimeConfig>,
+    /// Hashed name to original name, for rendering alerts locally
+    names: Mutex<HashMap<String, String>>,
+}
+
+impl NamePseudonymizer {
+    pub fn new(runtime: watch::Receiver<RuntimeConfig>) -> Self {
+        Self {
+            runtime,
+            names: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether names are currently hashed
+    pub fn enabled(&self) -> bool {
+        self.runtime.borrow().privacy.is_some()
+    }
+
+    /// Hash the names in a batch in place
+    pub fn apply(&self, batch: &mut MetricsBatch) {
+        let Some(config) = self.runtime.borrow().privacy.clone() else {
+            return;
+        };
+        let mut names = self.names.lock().unwrap_or_else(|e| e.into_inner());
+        let mut hasher = Hasher {
+            config: &config,
+            names: &mut names,
+        };
+
+        for metrics in &mut batch.metrics {
+            let namespace = std::mem::take(&mut metrics.namespace);
+            hasher.name(&namespace, &mut metrics.pod_name);
+            hasher.name(&namespace, &mut metrics.deployment);
+            hasher.owner(&namespace, metrics.owner.as_mut());
+            metrics.namespace = hasher.namespace(&namespace);
+        }
+        for profile in &mut batch.predictions {
+            hasher.profile(profile);
+        }
+        for anomaly in &mut batch.anomalies {
+            let namespace = std::mem::take(&mut anomaly.namespace);
+            let pod_name = anomaly.pod_name.clone();
+            hasher.name(&namespace, &mut anomaly.pod_name);
+            let hashed_namespace = hasher.namespace(&namespace);
+            // Messages may name the workload
+            anomaly.message = replace_names(
+                &anomaly.message,
+                &[
+                    (pod_name.as_str(), anomaly.pod_name.as_str()),
+                    (namespace.as_str(), hashed_namespace.as_str()),
+                ],
+            );
+            anomaly.namespace = hashed_namespace;
+        }
+        for pod in &mut batch.pod_profiles {
+            let namespace = std::mem::take(&mut pod.namespace);
+            hasher.name(&namespace, &mut pod.pod_name);
+            hasher.owner(&namespace, pod.owner.as_mut());
+            pod.namespace = hasher.namespace(&namespace);
+            for profile in &mut pod.containers {
+                hasher.profile(profile);
+            }
+        }
+    }
+
+    /// Hash the names of a profile in place
+    pub fn apply_profile(&self, profile: &mut proto::ResourceProfile) {
+        let Some(config) = self.runtime.borrow().privacy.clone() else {
+            return;
+        };
+        let mut names = self.names.lock().unwrap_or_else(|e| e.into_inner());
+        Hasher {
+            config: &config,
+            names: &mut names,
+        }
+        .profile(profile);
+    }
+
+    /// Original name of a hashed name sent from this node
+    pub fn reveal(&self, hashed: &str) -> Option<String> {
+        let names = self.names.lock().unwrap_or_else(|e| e.into_inner());
+        names.get(hashed).cloned()
+    }
+
+    /// Replace the hashed names in `text` with their original names
+    pub fn reveal_text(&self, text: &str) -> String {
+        let names = self.names.lock().unwrap_or_else(|e| e.into_inner());
+        let mut revealed = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(HASH_PREFIX) {
+            let end = start + HASH_PREFIX.len() + HASH_HEX_LEN;
+            match rest.get(start..end).and_then(|hashed| names.get(hashed)) {
+                Some(name) => {
+                    revealed.push_str(&rest[..start]);
+                    revealed.push_str(name);
+                    rest = &rest[end..];
+                }
+                None => {
+                    let skip = start + HASH_PREFIX.len();
+                    revealed.push_str(&rest[..skip]);
+                    rest = &rest[skip..];
+                }
+            }
+        }
+        revealed.push_str(rest);
+        revealed
+    }
+}
+
+/// Replace names in `text` in a single pass, preferring the longest match
+///
+/// A single pass keeps a name from matching inside an already hashed one.
+fn replace_names(text: &str, replacements: &[(&str, &str)]) -> String {
+    let mut replacements: Vec<_> = replacements
+        .iter()
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    replacements.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut replaced = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match replacements.iter().find(|(name, _)| rest.starts_with(name)) {
+            Some((name, hashed)) => {
+                replaced.push_str(hashed);
+                rest = &rest[name.len()..];
+            }
+            None => {
+                replaced.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    replaced
+}
+
+/// Hashes names with one config, remembering the originals
+struct Hasher<'a> {
+    config: &'a PrivacyConfig,
+    names: &'a mut HashMap<String, String>,
+}
+
+impl Hasher<'_> {
+    fn remember(&mut self, hashed: String, name: &str) -> String {
+        self.names
+            .entry(hashed.clone())
+            .or_insert_with(|| name.to_string());
+        hashed
+    }
+
+    fn namespace(&mut self, namespace: &str) -> String {
+        if namespace.is_empty() {
+            return String::new();
+        }
+        let hashed = self.config.hash_namespace(namespace);
+        self.remember(hashed, namespace)
+    }
+
+    fn name(&mut self, namespace: &str, name: &mut String) {
+        if !name.is_empty() {
+            let hashed = self.config.hash_name(namespace, name);
+            *name = self.remember(hashed, name);
+        }
+    }
+
+    fn owner(&mut self, namespace: &str, owner: Option<&mut WorkloadRef>) {
+        if let Some(owner) = owner {
+            self.name(namespace, &mut owner.name);
+        }
+    }
+
+    fn profile(&mut self, profile: &mut proto::ResourceProfile) {
+        let namespace = std::mem::take(&mut profile.namespace);
+        self.name(&namespace, &mut profile.pod_name);
+        self.name(&namespace, &mut profile.deployment);
+        self.owner(&namespace, profile.owner.as_mut());
+        profile.namespace = self.namespace(&namespace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::ContainerMetrics;
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_batch_names_hashed_per_tenant() {
+        let privacy = PrivacyConfig {
+            key: vec![1; 32],
+            key_id: "k1".to_string(),
+            namespace_keys: HashMap::from([("tenant-a".to_string(), vec![2; 32])]),
+        };
+        let (tx, rx) = watch::channel(RuntimeConfig::default());
+        let pseudonymizer = NamePseudonymizer::new(rx);
+
+        let metrics = |namespace: &str| ContainerMetrics {
+            container_id: "abc".to_string(),
+            pod_name: "checkout-0".to_string(),
+            namespace: namespace.to_string(),
+            ..Default::default()
+        };
+        let mut batch = MetricsBatch {
+            metrics: vec![metrics("tenant-a"), metrics("tenant-b")],
+            ..Default::default()
+        };
+
+        pseudonymizer.apply(&mut batch);
+        assert_eq!(batch.metrics[0].pod_name, "checkout-0");
+
+        tx.send_modify(|config| config.privacy = Some(privacy));
+        pseudonymizer.apply(&mut batch);
+        let (a, b) = (&batch.metrics[0], &batch.metrics[1]);
+        assert!(a.namespace.starts_with(HASH_PREFIX));
+        assert_eq!(a.container_id, "abc");
+        // The same pod name hashes differently under each tenant's key
+        assert_ne!(a.pod_name, b.pod_name);
+        assert_eq!(pseudonymizer.reveal(&a.pod_name).unwrap(), "checkout-0");
+        assert_eq!(
+            pseudonymizer.reveal_text(&format!("OOM risk in {}/{}", b.namespace, b.pod_name)),
+            "OOM risk in tenant-b/checkout-0"
+        );
+    }
+}
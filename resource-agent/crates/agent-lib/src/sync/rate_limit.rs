@@ -0,0 +1,137 @@
+//! Token-bucket rate limiting for sync traffic
+//!
+//! Keeps post-outage buffer drains from saturating the node network or
+//! overwhelming the API. Limits apply to bytes and batches per second.
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Token bucket refilling at a fixed rate up to a burst capacity
+///
+/// Reservations larger than the available tokens drive the balance negative,
+/// so oversized requests are delayed rather than rejected.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket
+    fn new(rate: f64, capacity: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take `amount` tokens, returning how long to wait before using them
+    fn reserve(&mut self, amount: f64, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= amount;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Combined bytes/sec and batches/sec limiter
+#[derive(Debug, Default)]
+pub(super) struct RateLimiter {
+    bytes: Option<TokenBucket>,
+    batches: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Create a limiter; `None` leaves that dimension unlimited
+    ///
+    /// Each bucket allows a burst of one second's worth of traffic.
+    pub(super) fn new(max_bytes_per_sec: Option<u64>, max_batches_per_sec: Option<f64>) -> Self {
+        let now = Instant::now();
+        Self {
+            bytes: max_bytes_per_sec
+                .filter(|&rate| rate > 0)
+                .map(|rate| TokenBucket::new(rate as f64, rate as f64, now)),
+            batches: max_batches_per_sec
+                .filter(|&rate| rate > 0.0)
+                .map(|rate| TokenBucket::new(rate, rate.max(1.0), now)),
+        }
+    }
+
+    /// Whether any limit is configured
+    pub(super) fn is_enabled(&self) -> bool {
+        self.bytes.is_some() || self.batches.is_some()
+    }
+
+    /// Reserve capacity for one batch of `bytes`, returning the required delay
+    pub(super) fn reserve(&mut self, bytes: usize, now: Instant) -> Duration {
+        let byte_wait = self
+            .bytes
+            .as_mut()
+            .map(|bucket| bucket.reserve(bytes as f64, now))
+            .unwrap_or_default();
+        let batch_wait = self
+            .batches
+            .as_mut()
+            .map(|bucket| bucket.reserve(1.0, now))
+            .unwrap_or_default();
+
+        byte_wait.max(batch_wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_throttles() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100.0, 100.0, start);
+
+        assert_eq!(bucket.reserve(100.0, start), Duration::ZERO);
+
+        // Bucket is empty: 50 more tokens take half a second
+        let wait = bucket.reserve(50.0, start);
+        assert_eq!(wait, Duration::from_millis(500));
+
+        // After refilling, the debt is paid off
+        let later = start + Duration::from_secs(2);
+        assert_eq!(bucket.reserve(10.0, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_limiter_uses_slowest_dimension() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Some(1_000_000), Some(2.0));
+        assert!(limiter.is_enabled());
+
+        assert_eq!(limiter.reserve(1000, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(1000, start), Duration::ZERO);
+
+        // Third batch within the same instant waits on the batch limit
+        assert_eq!(limiter.reserve(1000, start), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_unlimited_limiter() {
+        let mut limiter = RateLimiter::new(None, None);
+        assert!(!limiter.is_enabled());
+        assert_eq!(limiter.reserve(usize::MAX, Instant::now()), Duration::ZERO);
+    }
+}
@@ -0,0 +1,385 @@
+//! Server-pushed agent configuration
+//!
+//! The control plane sends an `AgentConfig` in the registration response and
+//! pushes later changes over the `WatchConfig` stream. Applied config is
+//! published on a watch channel so the collection loop, prediction scheduler
+//! and streaming worker pick up changes without restarting the agent.
+
+#[cfg(feature = "grpc-sync")]
+use super::client::SyncClient;
+use super::privacy::PrivacyConfig;
+use crate::anomaly::{AlertRoute, SlackDestination};
+#[cfg(feature = "grpc-sync")]
+use crate::proto::WatchConfigRequest;
+use crate::proto::{self, AgentConfig, AnomalyType, RegisterResponse};
+use anyhow::Result;
+#[cfg(feature = "grpc-sync")]
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "grpc-sync")]
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+#[cfg(feature = "grpc-sync")]
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+/// Shortest interval the control plane may set
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration currently applied from the control plane
+///
+/// Intervals left unset keep the agent's local configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeConfig {
+    /// Version of the last applied update (0 = registration or local)
+    pub version: i64,
+    pub collection_interval: Option<Duration>,
+    pub prediction_interval: Option<Duration>,
+    pub sync_interval: Option<Duration>,
+    pub anomaly_detection_enabled: bool,
+    /// Anomaly types that are not reported
+    pub disabled_anomaly_types: Vec<AnomalyType>,
+    /// Only these namespaces are collected (all when empty)
+    pub include_namespaces: Vec<String>,
+    /// These namespaces are never collected
+    pub exclude_namespaces: Vec<String>,
+    /// Per-team alert routing, evaluated in order
+    pub alert_routes: Vec<AlertRoute>,
+    /// Keys for hashing workload names, when privacy mode is on
+    pub privacy: Option<PrivacyConfig>,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            collection_interval: None,
+            prediction_interval: None,
+            sync_interval: None,
+            anomaly_detection_enabled: true,
+            disabled_anomaly_types: Vec::new(),
+            include_namespaces: Vec::new(),
+            exclude_namespaces: Vec::new(),
+            alert_routes: Vec::new(),
+            privacy: None,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Validate and convert a config received from the control plane
+    pub fn from_proto(version: i64, config: &AgentConfig) -> Result<Self> {
+        Ok(Self {
+            version,
+            collection_interval: interval("collection", config.collection_interval_seconds)?,
+            prediction_interval: interval("prediction", config.prediction_interval_seconds)?,
+            sync_interval: interval("sync", config.sync_interval_seconds)?,
+            anomaly_detection_enabled: config.anomaly_detection_enabled,
+            disabled_anomaly_types: config
+                .disabled_anomaly_types
+                .iter()
+                .map(|&t| anomaly_type(t))
+                .collect::<Result<_>>()?,
+            include_namespaces: config.include_namespaces.clone(),
+            exclude_namespaces: config.exclude_namespaces.clone(),
+            alert_routes: config
+                .alert_routes
+                .iter()
+                .enumerate()
+                .map(|(i, route)| alert_route(i, route))
+                .collect::<Result<_>>()?,
+            privacy: match &config.privacy {
+                Some(privacy) => PrivacyConfig::from_proto(privacy)?,
+                None => None,
+            },
+        })
+    }
+
+    /// Check if containers in a namespace should be collected
+    pub fn namespace_allowed(&self, namespace: &str) -> bool {
+        if self.exclude_namespaces.iter().any(|ns| ns == namespace) {
+            return false;
+        }
+        self.include_namespaces.is_empty()
+            || self.include_namespaces.iter().any(|ns| ns == namespace)
+    }
+
+    /// Check if anomalies of the given proto type should be reported
+    pub fn anomaly_enabled(&self, anomaly_type: i32) -> bool {
+        self.anomaly_detection_enabled
+            && !self
+                .disabled_anomaly_types
+                .iter()
+                .any(|&t| t as i32 == anomaly_type)
+    }
+}
+
+/// Convert an interval in seconds, where 0 means "not set"
+fn interval(name: &str, seconds: i32) -> Result<Option<Duration>> {
+    match seconds {
+        0 => Ok(None),
+        s if s < 0 || Duration::from_secs(s as u64) < MIN_INTERVAL => {
+            anyhow::bail!("Invalid {} interval: {}s", name, s)
+        }
+        s => Ok(Some(Duration::from_secs(s as u64))),
+    }
+}
+
+/// Convert a proto anomaly type value
+fn anomaly_type(value: i32) -> Result<AnomalyType> {
+    match value {
+        1 => Ok(AnomalyType::MemoryLeak),
+        2 => Ok(AnomalyType::CpuSpike),
+        3 => Ok(AnomalyType::OomRisk),
+        other => anyhow::bail!("Unknown anomaly type: {}", other),
+    }
+}
+
+/// Convert a proto alert route, rejecting routes that do nothing
+fn alert_route(index: usize, route: &proto::AlertRoute) -> Result<AlertRoute> {
+    let route = AlertRoute {
+        namespaces: route.namespaces.clone(),
+        match_labels: route.match_labels.clone().into_iter().collect(),
+        labels: route.labels.clone().into_iter().collect(),
+        webhook_urls: route.webhook_urls.clone(),
+        slack: (!route.slack_webhook_url.is_empty()).then(|| SlackDestination {
+            webhook_url: route.slack_webhook_url.clone(),
+            channel: (!route.slack_channel.is_empty()).then(|| route.slack_channel.clone()),
+        }),
+        continue_matching: route.continue_matching,
+    };
+    if route.labels.is_empty() && route.webhook_urls.is_empty() && route.slack.is_none() {
+        anyhow::bail!("Alert route {} has no labels or destinations", index);
+    }
+    Ok(route)
+}
+
+/// Wait for the next config change
+///
+/// Never resolves when there is no receiver or its sender is gone, so it can
+/// be used directly as a `tokio::select!` branch.
+pub(crate) async fn next_update<T: Clone>(receiver: &mut Option<watch::Receiver<T>>) -> T {
+    if let Some(rx) = receiver {
+        if rx.changed().await.is_ok() {
+            return rx.borrow_and_update().clone();
+        }
+    }
+    std::future::pending().await
+}
+
+/// Applies control plane config and watches for pushed updates
+pub struct ConfigWatcher {
+    sender: watch::Sender<RuntimeConfig>,
+}
+
+impl ConfigWatcher {
+    /// Create a watcher with no remote config applied
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(RuntimeConfig::default());
+        Self { sender }
+    }
+
+    /// Subscribe to config changes
+    pub fn subscribe(&self) -> watch::Receiver<RuntimeConfig> {
+        self.sender.subscribe()
+    }
+
+    /// Currently applied config
+    pub fn current(&self) -> RuntimeConfig {
+        self.sender.borrow().clone()
+    }
+
+    /// Apply the config returned at registration
+    pub fn apply_registration(&self, response: &RegisterResponse) -> Result<bool> {
+        match &response.config {
+            Some(config) => self.apply(0, config),
+            None => Ok(false),
+        }
+    }
+
+    /// Apply a config version, returning whether anything changed
+    ///
+    /// Pushed versions older than the applied one are ignored.
+    pub fn apply(&self, version: i64, config: &AgentConfig) -> Result<bool> {
+        let current_version = self.sender.borrow().version;
+        if version > 0 && version < current_version {
+            warn!(
+                version = version,
+                applied_version = current_version,
+                "Ignoring stale config update"
+            );
+            return Ok(false);
+        }
+
+        let updated = RuntimeConfig::from_proto(version, config)?;
+        let changed = self.sender.send_if_modified(|current| {
+            if *current == updated {
+                return false;
+            }
+            *current = updated.clone();
+            true
+        });
+
+        if changed {
+            info!(
+                version = version,
+                collection_interval_secs = ?updated.collection_interval.map(|d| d.as_secs()),
+                prediction_interval_secs = ?updated.prediction_interval.map(|d| d.as_secs()),
+                sync_interval_secs = ?updated.sync_interval.map(|d| d.as_secs()),
+                anomaly_detection = updated.anomaly_detection_enabled,
+                privacy_mode = updated.privacy.is_some(),
+                "Applied agent config from control plane"
+            );
+        }
+        Ok(changed)
+    }
+
+    /// Watch for pushed config, reconnecting with backoff until the task is dropped
+    #[cfg(feature = "grpc-sync")]
+    pub async fn run(&self, sync_client: Arc<SyncClient>) {
+        loop {
+            if !sync_client.supports(proto::Capability::ConfigWatch) {
+                info!("Recommendation API does not push config, keeping the local config");
+                return;
+            }
+            let result = self.watch_once(&sync_client).await;
+            let backoff = sync_client.get_reconnect_backoff().await;
+            match result {
+                Ok(()) => info!("Config watch stream closed by server, reopening"),
+                Err(e) => warn!(
+                    error = %e,
+                    retry_in_secs = backoff.as_secs(),
+                    "Config watch failed"
+                ),
+            }
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Run a single watch stream until it ends
+    #[cfg(feature = "grpc-sync")]
+    async fn watch_once(&self, sync_client: &SyncClient) -> Result<()> {
+        let (ack_tx, ack_rx) = mpsc::channel(4);
+        let ack = |applied_version: i64, error: String| WatchConfigRequest {
+            agent_id: sync_client.agent_id().to_string(),
+            node_name: sync_client.node_name().to_string(),
+            applied_version,
+            error,
+        };
+
+        // Subscribe with the version we already have
+        ack_tx
+            .send(ack(self.current().version, String::new()))
+            .await
+            .ok();
+        let mut updates = sync_client
+            .watch_config(ReceiverStream::new(ack_rx))
+            .await?;
+
+        while let Some(update) = updates.message().await? {
+            let result = match &update.config {
+                Some(config) => self.apply(update.version, config),
+                None => Err(anyhow::anyhow!("Config update has no config")),
+            };
+
+            let error = match result {
+                Ok(_) => String::new(),
+                Err(e) => {
+                    warn!(version = update.version, error = %e, "Rejected config update");
+                    e.to_string()
+                }
+            };
+
+            if ack_tx
+                .send(ack(self.current().version, error))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent_config(collection: i32) -> AgentConfig {
+        AgentConfig {
+            collection_interval_seconds: collection,
+            prediction_interval_seconds: 0,
+            sync_interval_seconds: 30,
+            anomaly_detection_enabled: true,
+            disabled_anomaly_types: vec![AnomalyType::CpuSpike as i32],
+            include_namespaces: vec![],
+            exclude_namespaces: vec!["kube-system".to_string()],
+            alert_routes: vec![],
+            privacy: None,
+        }
+    }
+
+    #[test]
+    fn test_from_proto() {
+        let config = RuntimeConfig::from_proto(3, &agent_config(15)).unwrap();
+        assert_eq!(config.collection_interval, Some(Duration::from_secs(15)));
+        assert_eq!(config.prediction_interval, None);
+        assert_eq!(config.sync_interval, Some(Duration::from_secs(30)));
+
+        assert!(config.anomaly_enabled(AnomalyType::MemoryLeak as i32));
+        assert!(!config.anomaly_enabled(AnomalyType::CpuSpike as i32));
+
+        assert!(config.namespace_allowed("default"));
+        assert!(!config.namespace_allowed("kube-system"));
+
+        assert!(RuntimeConfig::from_proto(1, &agent_config(-5)).is_err());
+
+        let mut routed = agent_config(15);
+        routed.alert_routes.push(proto::AlertRoute {
+            namespaces: vec!["payments".to_string()],
+            slack_webhook_url: "https://hooks.slack.test/payments".to_string(),
+            ..Default::default()
+        });
+        let config = RuntimeConfig::from_proto(4, &routed).unwrap();
+        assert_eq!(config.alert_routes[0].slack.as_ref().unwrap().channel, None);
+        routed.alert_routes[0].slack_webhook_url.clear();
+        assert!(RuntimeConfig::from_proto(5, &routed).is_err());
+    }
+
+    #[test]
+    fn test_include_namespaces() {
+        let config = RuntimeConfig {
+            include_namespaces: vec!["prod".to_string()],
+            ..Default::default()
+        };
+        assert!(config.namespace_allowed("prod"));
+        assert!(!config.namespace_allowed("staging"));
+    }
+
+    #[tokio::test]
+    async fn test_watcher_publishes_changes_and_ignores_stale() {
+        let watcher = ConfigWatcher::new();
+        let mut rx = Some(watcher.subscribe());
+
+        assert!(watcher.apply(2, &agent_config(15)).unwrap());
+        let update = next_update(&mut rx).await;
+        assert_eq!(update.version, 2);
+        assert_eq!(update.collection_interval, Some(Duration::from_secs(15)));
+
+        // Older version and identical config are both no-ops
+        assert!(!watcher.apply(1, &agent_config(20)).unwrap());
+        assert!(!watcher.apply(2, &agent_config(15)).unwrap());
+        assert_eq!(
+            watcher.current().collection_interval,
+            Some(Duration::from_secs(15))
+        );
+    }
+}
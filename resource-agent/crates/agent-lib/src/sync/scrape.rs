@@ -0,0 +1,351 @@
+//! Scrape-style sync, where the API pulls from agents
+//!
+//! Some clusters do not allow agents to open egress connections. In scrape
+//! mode the agent keeps its recent samples in memory and serves them over
+//! `PredictorAgentService` instead of streaming them with `SyncMetrics`:
+//! - `GetMetricsSince` returns the samples collected after a cursor
+//! - `GetPredictions` returns the latest prediction of every container
+//!
+//! Responses carry the same batches the push stream sends.
+
+use super::streaming::{convert_prediction, proto_batch};
+use super::{NamePseudonymizer, PendingData};
+use crate::models::{ContainerMetrics, NodeMetrics};
+use crate::predictor::PredictionScheduler;
+use crate::proto::{
+    GetMetricsSinceRequest, GetMetricsSinceResponse, GetPredictionsRequest, GetPredictionsResponse,
+    PredictorAgentService, PredictorAgentServiceServer,
+};
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+/// Samples kept for scraping (about 10 minutes of 100 containers at 10s)
+pub const DEFAULT_SCRAPE_CAPACITY: usize = 6000;
+
+/// Samples returned by a scrape that does not set `max_samples`
+pub const DEFAULT_SCRAPE_PAGE: usize = 1000;
+
+/// Samples returned by `ScrapeBuffer::since`
+#[derive(Debug, Clone, Default)]
+pub struct ScrapePage {
+    pub metrics: Vec<ContainerMetrics>,
+    /// Cursor to pass on the next scrape
+    pub next_cursor: u64,
+    /// Whether more samples are waiting beyond the page
+    pub has_more: bool,
+    /// Samples evicted before they were scraped
+    pub dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct ScrapeState {
+    /// Samples with their sequence number, oldest first
+    samples: VecDeque<(u64, ContainerMetrics)>,
+    /// Sequence number of the newest sample, 0 before the first one
+    last_seq: u64,
+    node_metrics: Option<NodeMetrics>,
+}
+
+/// Bounded buffer of recent samples, read by cursor
+///
+/// Every sample gets a sequence number; a scrape returns the samples after
+/// the cursor it passes. Once full, the oldest samples are evicted and
+/// reported as dropped to the next scrape that missed them.
+#[derive(Debug)]
+pub struct ScrapeBuffer {
+    capacity: usize,
+    state: Mutex<ScrapeState>,
+}
+
+impl ScrapeBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(ScrapeState::default()),
+        }
+    }
+
+    /// Add samples, evicting the oldest beyond capacity
+    pub fn push(&self, metrics: Vec<ContainerMetrics>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        for m in metrics {
+            state.last_seq += 1;
+            let seq = state.last_seq;
+            state.samples.push_back((seq, m));
+        }
+        while state.samples.len() > self.capacity {
+            state.samples.pop_front();
+        }
+    }
+
+    /// Replace the latest node sample
+    pub fn set_node_metrics(&self, node_metrics: NodeMetrics) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.node_metrics = Some(node_metrics);
+    }
+
+    /// Latest node sample, if any
+    pub fn node_metrics(&self) -> Option<NodeMetrics> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.node_metrics.clone()
+    }
+
+    /// Up to `max` samples collected after `cursor`
+    ///
+    /// A cursor ahead of the buffer, e.g. from before an agent restart,
+    /// starts over from the oldest sample.
+    pub fn since(&self, cursor: u64, max: usize) -> ScrapePage {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let cursor = if cursor > state.last_seq { 0 } else { cursor };
+        let first_seq = state
+            .samples
+            .front()
+            .map_or(state.last_seq + 1, |(seq, _)| *seq);
+
+        let mut pending = state.samples.iter().filter(|(seq, _)| *seq > cursor);
+        let metrics: Vec<ContainerMetrics> = pending
+            .by_ref()
+            .take(max.max(1))
+            .map(|(_, m)| m.clone())
+            .collect();
+        let has_more = pending.next().is_some();
+        let next_cursor = match metrics.len() {
+            0 => state.last_seq,
+            n => cursor.max(first_seq - 1) + n as u64,
+        };
+
+        ScrapePage {
+            metrics,
+            next_cursor,
+            has_more,
+            dropped: first_seq.saturating_sub(cursor + 1),
+        }
+    }
+
+    /// Number of samples held
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ScrapeBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_SCRAPE_CAPACITY)
+    }
+}
+
+/// `PredictorAgentService` served by agents in scrape mode
+pub struct ScrapeServer {
+    agent_id: String,
+    node_name: String,
+    buffer: Arc<ScrapeBuffer>,
+    scheduler: Option<Arc<PredictionScheduler>>,
+    pseudonymizer: Option<Arc<NamePseudonymizer>>,
+}
+
+impl ScrapeServer {
+    pub fn new(
+        agent_id: impl Into<String>,
+        node_name: impl Into<String>,
+        buffer: Arc<ScrapeBuffer>,
+    ) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            node_name: node_name.into(),
+            buffer,
+            scheduler: None,
+            pseudonymizer: None,
+        }
+    }
+
+    /// Serve the latest predictions of `scheduler` on `GetPredictions`
+    pub fn with_scheduler(mut self, scheduler: Arc<PredictionScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Hash workload names in responses while privacy mode is on
+    pub fn with_pseudonymizer(mut self, pseudonymizer: Arc<NamePseudonymizer>) -> Self {
+        self.pseudonymizer = Some(pseudonymizer);
+        self
+    }
+
+    /// Serve until `shutdown` fires
+    pub async fn serve(
+        self,
+        addr: SocketAddr,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Result<()> {
+        info!(addr = %addr, "Serving scrape sync");
+        tonic::transport::Server::builder()
+            .add_service(PredictorAgentServiceServer::new(self))
+            .serve_with_shutdown(addr, async move {
+                let _ = shutdown.recv().await;
+            })
+            .await
+            .context("Scrape sync server failed")
+    }
+}
+
+#[tonic::async_trait]
+impl PredictorAgentService for ScrapeServer {
+    async fn get_metrics_since(
+        &self,
+        request: Request<GetMetricsSinceRequest>,
+    ) -> Result<Response<GetMetricsSinceResponse>, Status> {
+        let request = request.into_inner();
+        let max = match request.max_samples {
+            0 => DEFAULT_SCRAPE_PAGE,
+            n => n as usize,
+        };
+        let page = self.buffer.since(request.cursor, max);
+        let data = PendingData {
+            metrics: page.metrics,
+            node_metrics: self.buffer.node_metrics(),
+            ..Default::default()
+        };
+
+        let mut batch = proto_batch(&self.agent_id, &self.node_name, data);
+        if let Some(pseudonymizer) = &self.pseudonymizer {
+            pseudonymizer.apply(&mut batch);
+        }
+
+        Ok(Response::new(GetMetricsSinceResponse {
+            batch: Some(batch),
+            next_cursor: page.next_cursor,
+            has_more: page.has_more,
+            dropped: page.dropped,
+        }))
+    }
+
+    async fn get_predictions(
+        &self,
+        request: Request<GetPredictionsRequest>,
+    ) -> Result<Response<GetPredictionsResponse>, Status> {
+        let Some(scheduler) = &self.scheduler else {
+            return Err(Status::unavailable("Predictor is not running"));
+        };
+        let namespace = request.into_inner().namespace;
+
+        let predictions = scheduler
+            .latest_predictions()
+            .await
+            .into_iter()
+            .filter(|p| namespace.is_empty() || p.namespace == namespace)
+            .map(|p| {
+                let mut profile = convert_prediction(p);
+                if let Some(pseudonymizer) = &self.pseudonymizer {
+                    pseudonymizer.apply_profile(&mut profile);
+                }
+                profile
+            })
+            .collect();
+
+        Ok(Response::new(GetPredictionsResponse {
+            agent_id: self.agent_id.clone(),
+            node_name: self.node_name.clone(),
+            predictions,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(count: usize) -> Vec<ContainerMetrics> {
+        (0..count)
+            .map(|i| ContainerMetrics {
+                container_id: format!("container-{}", i),
+                pod_name: "pod".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                timestamp: i as i64,
+                cpu_usage_cores: 0.1,
+                cpu_throttled_periods: 0,
+                memory_usage_bytes: 0,
+                memory_working_set_bytes: 0,
+                memory_cache_bytes: 0,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_since_pages_by_cursor() {
+        let buffer = ScrapeBuffer::new(100);
+        buffer.push(metrics(5));
+
+        let page = buffer.since(0, 3);
+        assert_eq!(page.metrics.len(), 3);
+        assert_eq!(page.next_cursor, 3);
+        assert!(page.has_more);
+        assert_eq!(page.dropped, 0);
+
+        let page = buffer.since(page.next_cursor, 3);
+        assert_eq!(page.metrics[0].container_id, "container-3");
+        assert_eq!(page.next_cursor, 5);
+        assert!(!page.has_more);
+
+        // Nothing new keeps the cursor
+        let page = buffer.since(5, 3);
+        assert!(page.metrics.is_empty());
+        assert_eq!(page.next_cursor, 5);
+    }
+
+    #[test]
+    fn test_since_reports_evicted_samples() {
+        let buffer = ScrapeBuffer::new(4);
+        buffer.push(metrics(10));
+        assert_eq!(buffer.len(), 4);
+
+        let page = buffer.since(2, 10);
+        assert_eq!(page.dropped, 4);
+        assert_eq!(page.metrics.len(), 4);
+        assert_eq!(page.metrics[0].container_id, "container-6");
+        assert_eq!(page.next_cursor, 10);
+
+        // A cursor from before a restart starts over
+        let page = buffer.since(50, 10);
+        assert_eq!(page.metrics.len(), 4);
+        assert_eq!(page.next_cursor, 10);
+    }
+
+    #[tokio::test]
+    async fn test_get_metrics_since_builds_batch() {
+        let buffer = Arc::new(ScrapeBuffer::new(100));
+        buffer.push(metrics(3));
+        let server = ScrapeServer::new("agent-1", "node-1", buffer);
+
+        let response = server
+            .get_metrics_since(Request::new(GetMetricsSinceRequest {
+                cursor: 1,
+                max_samples: 0,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let batch = response.batch.unwrap();
+        assert_eq!(batch.agent_id, "agent-1");
+        assert_eq!(batch.node_name, "node-1");
+        assert_eq!(batch.metrics.len(), 2);
+        assert_eq!(response.next_cursor, 3);
+
+        let status = server
+            .get_predictions(Request::new(GetPredictionsRequest::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+    }
+}
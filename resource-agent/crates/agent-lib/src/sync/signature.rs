@@ -0,0 +1,214 @@
+//! Ed25519 signature verification for model updates
+//!
+//! Checksums only detect corruption in transit. Models are also signed
+//! offline, and the agent refuses any model whose signature doesn't verify
+//! against a locally trusted public key, so a compromised API cannot push
+//! arbitrary weights.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey, PUBLIC_KEY_LENGTH};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the raw key
+const SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Parse a public key given as base64 of the raw 32 bytes or as PEM
+pub fn parse_public_key(encoded: &str) -> Result<VerifyingKey> {
+    let body: String = encoded
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let der = BASE64
+        .decode(body.as_bytes())
+        .context("Public key is not valid base64")?;
+
+    let raw = match der.len() {
+        PUBLIC_KEY_LENGTH => der.as_slice(),
+        len if len == SPKI_PREFIX.len() + PUBLIC_KEY_LENGTH && der.starts_with(&SPKI_PREFIX) => {
+            &der[SPKI_PREFIX.len()..]
+        }
+        len => anyhow::bail!("Unexpected Ed25519 public key length {}", len),
+    };
+
+    let bytes: [u8; PUBLIC_KEY_LENGTH] = raw.try_into().expect("length checked above");
+    VerifyingKey::from_bytes(&bytes).context("Invalid Ed25519 public key")
+}
+
+/// Parse a key file holding PEM blocks or one base64 key per line
+///
+/// Blank lines and `#` comments are ignored.
+fn parse_key_file(contents: &str) -> Result<Vec<VerifyingKey>> {
+    let mut keys = Vec::new();
+    let mut pem: Option<String> = None;
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with("-----BEGIN") {
+            pem = Some(String::new());
+        } else if line.starts_with("-----END") {
+            let block = pem.take().context("PEM END without BEGIN")?;
+            keys.push(parse_public_key(&block)?);
+        } else if let Some(block) = pem.as_mut() {
+            block.push_str(line);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            keys.push(parse_public_key(line)?);
+        }
+    }
+
+    if pem.is_some() {
+        anyhow::bail!("Unterminated PEM block");
+    }
+    Ok(keys)
+}
+
+/// Verifies model signatures against trusted public keys
+#[derive(Debug)]
+pub(super) struct ModelVerifier {
+    keys: Vec<VerifyingKey>,
+    keys_path: Option<PathBuf>,
+    require_signature: bool,
+}
+
+impl ModelVerifier {
+    /// Create a verifier, parsing the configured keys
+    pub(super) fn new(
+        public_keys: &[String],
+        keys_path: Option<&Path>,
+        require_signature: bool,
+    ) -> Result<Self> {
+        let keys = public_keys
+            .iter()
+            .map(|key| parse_public_key(key))
+            .collect::<Result<_>>()
+            .context("Invalid model signing key")?;
+
+        Ok(Self {
+            keys,
+            keys_path: keys_path.map(Path::to_path_buf),
+            require_signature,
+        })
+    }
+
+    /// Trusted keys, re-reading the key file so rotations apply without a restart
+    fn trusted_keys(&self) -> Result<Vec<VerifyingKey>> {
+        let mut keys = self.keys.clone();
+        if let Some(path) = &self.keys_path {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read model signing keys {:?}", path))?;
+            keys.extend(
+                parse_key_file(&contents)
+                    .with_context(|| format!("Invalid model signing keys in {:?}", path))?,
+            );
+        }
+        Ok(keys)
+    }
+
+    /// Check a model's signature over its weights
+    ///
+    /// Unsigned models are only accepted when signatures aren't required.
+    pub(super) fn verify(&self, weights: &[u8], signature: &[u8]) -> Result<()> {
+        if signature.is_empty() {
+            if self.require_signature {
+                anyhow::bail!("Model update is not signed");
+            }
+            warn!("Accepting unsigned model update");
+            return Ok(());
+        }
+
+        let signature = Signature::from_slice(signature).context("Malformed model signature")?;
+        let keys = self.trusted_keys()?;
+        if keys.is_empty() {
+            anyhow::bail!("No trusted model signing keys configured");
+        }
+
+        if keys
+            .iter()
+            .any(|key| key.verify_strict(weights, &signature).is_ok())
+        {
+            Ok(())
+        } else {
+            anyhow::bail!("Model signature does not match any trusted key")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use tempfile::TempDir;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn encoded(key: &SigningKey) -> String {
+        BASE64.encode(key.verifying_key().as_bytes())
+    }
+
+    #[test]
+    fn test_parse_raw_and_pem_keys() {
+        let key = signing_key(1).verifying_key();
+
+        assert_eq!(parse_public_key(&encoded(&signing_key(1))).unwrap(), key);
+
+        let mut spki = SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(key.as_bytes());
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            BASE64.encode(&spki)
+        );
+        assert_eq!(parse_public_key(&pem).unwrap(), key);
+
+        assert!(parse_public_key("not a key").is_err());
+        assert!(parse_public_key(&BASE64.encode([0u8; 16])).is_err());
+    }
+
+    #[test]
+    fn test_verify_signed_model() {
+        let key = signing_key(1);
+        let weights = b"model weights";
+        let signature = key.sign(weights).to_bytes();
+
+        let verifier = ModelVerifier::new(&[encoded(&key)], None, true).unwrap();
+        assert!(verifier.verify(weights, &signature).is_ok());
+
+        // Tampered weights, unsigned models and untrusted signers are refused
+        assert!(verifier.verify(b"other weights", &signature).is_err());
+        assert!(verifier.verify(weights, &[]).is_err());
+        let forged = signing_key(2).sign(weights).to_bytes();
+        assert!(verifier.verify(weights, &forged).is_err());
+    }
+
+    #[test]
+    fn test_keys_from_file_and_optional_signature() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("keys.pem");
+        std::fs::write(
+            &path,
+            format!(
+                "# rotated keys\n{}\n\n{}\n",
+                encoded(&signing_key(1)),
+                encoded(&signing_key(2))
+            ),
+        )
+        .unwrap();
+
+        let verifier = ModelVerifier::new(&[], Some(&path), false).unwrap();
+        let weights = b"model weights";
+        assert!(verifier
+            .verify(weights, &signing_key(2).sign(weights).to_bytes())
+            .is_ok());
+
+        // Unsigned models pass when not required, bad signatures never do
+        assert!(verifier.verify(weights, &[]).is_ok());
+        assert!(verifier
+            .verify(weights, &signing_key(3).sign(weights).to_bytes())
+            .is_err());
+    }
+}
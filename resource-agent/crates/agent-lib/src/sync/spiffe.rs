@@ -0,0 +1,168 @@
+//! SPIFFE workload identity for the gRPC client
+//!
+//! Fetches the agent's X.509 SVID and trust bundle from the SPIFFE Workload
+//! API socket and keeps them current by watching the Workload API stream, so
+//! TLS identity rotates without PEM files on disk.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use spiffe::workload_api::client::WorkloadApiClient;
+use spiffe::workload_api::x509_context::X509Context;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio_stream::StreamExt;
+use tracing::{info, warn};
+
+/// TLS material derived from an X.509 SVID
+#[derive(Debug, Clone)]
+pub struct SpiffeMaterial {
+    /// SPIFFE ID of the agent
+    pub spiffe_id: String,
+    /// PEM-encoded SVID certificate chain
+    pub cert_chain_pem: Vec<u8>,
+    /// PEM-encoded PKCS#8 private key
+    pub key_pem: Vec<u8>,
+    /// PEM-encoded trust bundle for the agent's trust domain
+    pub bundle_pem: Vec<u8>,
+}
+
+impl SpiffeMaterial {
+    /// Extract TLS material from a Workload API X.509 context
+    fn from_context(context: &X509Context) -> Result<Self> {
+        let svid = context
+            .default_svid()
+            .ok_or_else(|| anyhow::anyhow!("Workload API returned no X.509 SVID"))?;
+
+        let trust_domain = svid.spiffe_id().trust_domain();
+        let bundle = context
+            .bundle_set()
+            .get_bundle(trust_domain)
+            .ok_or_else(|| anyhow::anyhow!("No trust bundle for {}", trust_domain))?;
+
+        let cert_chain_pem = svid
+            .cert_chain()
+            .iter()
+            .flat_map(|cert| to_pem("CERTIFICATE", cert.content()))
+            .collect();
+        let bundle_pem = bundle
+            .authorities()
+            .iter()
+            .flat_map(|cert| to_pem("CERTIFICATE", cert.content()))
+            .collect();
+
+        Ok(Self {
+            spiffe_id: svid.spiffe_id().to_string(),
+            cert_chain_pem,
+            key_pem: to_pem("PRIVATE KEY", svid.private_key().content()),
+            bundle_pem,
+        })
+    }
+}
+
+/// Continuously updated SVID and trust bundle
+pub struct SpiffeIdentity {
+    material: Arc<RwLock<SpiffeMaterial>>,
+    /// Incremented on every rotation
+    generation: Arc<AtomicU64>,
+}
+
+impl SpiffeIdentity {
+    /// Connect to the Workload API and start watching for rotations
+    ///
+    /// Uses `SPIFFE_ENDPOINT_SOCKET` when no socket path is given.
+    pub async fn connect(socket_path: Option<&str>) -> Result<Self> {
+        let mut client = match socket_path {
+            Some(path) => WorkloadApiClient::new_from_path(path).await,
+            None => WorkloadApiClient::default().await,
+        }
+        .context("Failed to connect to SPIFFE Workload API")?;
+
+        let context = client
+            .fetch_x509_context()
+            .await
+            .context("Failed to fetch X.509 SVID")?;
+        let material = SpiffeMaterial::from_context(&context)?;
+        info!(spiffe_id = %material.spiffe_id, "Fetched SPIFFE identity");
+
+        let identity = Self {
+            material: Arc::new(RwLock::new(material)),
+            generation: Arc::new(AtomicU64::new(1)),
+        };
+        identity.spawn_watcher(client);
+
+        Ok(identity)
+    }
+
+    /// Watch the Workload API stream and swap in rotated material
+    fn spawn_watcher(&self, mut client: WorkloadApiClient) {
+        let material = Arc::clone(&self.material);
+        let generation = Arc::clone(&self.generation);
+
+        tokio::spawn(async move {
+            let mut stream = match client.stream_x509_contexts().await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!(error = %e, "Failed to watch SPIFFE Workload API, SVID will not rotate");
+                    return;
+                }
+            };
+
+            while let Some(update) = stream.next().await {
+                match update
+                    .map_err(anyhow::Error::from)
+                    .and_then(|context| SpiffeMaterial::from_context(&context))
+                {
+                    Ok(updated) => {
+                        info!(spiffe_id = %updated.spiffe_id, "SPIFFE identity rotated");
+                        *material.write().unwrap() = updated;
+                        generation.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Err(e) => warn!(error = %e, "Ignoring invalid SPIFFE Workload API update"),
+                }
+            }
+
+            warn!("SPIFFE Workload API stream ended, SVID will not rotate");
+        });
+    }
+
+    /// Current TLS material
+    pub fn material(&self) -> SpiffeMaterial {
+        self.material.read().unwrap().clone()
+    }
+
+    /// Rotation counter, changes whenever new material is received
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
+
+/// Encode DER bytes as a PEM block
+fn to_pem(label: &str, der: &[u8]) -> Vec<u8> {
+    let encoded = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pem_wraps_lines() {
+        let der = vec![0xabu8; 100];
+        let pem = String::from_utf8(to_pem("CERTIFICATE", &der)).unwrap();
+
+        assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+        assert!(pem.ends_with("-----END CERTIFICATE-----\n"));
+        assert!(pem.lines().all(|line| line.len() <= 64));
+
+        let body: String = pem.lines().filter(|l| !l.starts_with("-----")).collect();
+        assert_eq!(STANDARD.decode(body).unwrap(), der);
+    }
+}
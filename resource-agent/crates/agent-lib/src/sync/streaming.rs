@@ -2,20 +2,42 @@
 //!
 //! This module provides streaming functionality for syncing metrics to the API:
 //! - Batches metrics into MetricsBatch messages
-//! - Streams to API with backpressure handling
-//! - Handles connection failures gracefully
-
-use crate::models::{ContainerMetrics as LocalMetrics, ResourceProfile as LocalProfile};
+//! - Streams to API over a long-lived client stream with backpressure handling
+//! - Handles connection failures and server-side stream termination gracefully
+//! - Returns metrics the server never acknowledged to the offline buffer
+//! - Rate limits bytes and batches per second
+//! - Optionally sends each container identity once per stream
+//! - Sheds raw metrics first when the queue is full, reporting the gap
+
+use super::buffer::TimestampedMetrics;
+use super::identity::IdentityEncoder;
+use super::rate_limit::RateLimiter;
+use super::{
+    next_update, NamePseudonymizer, OfflineBufferManager, RuntimeConfig, SyncClient,
+    DEFAULT_MAX_MESSAGE_SIZE,
+};
+use crate::clock;
+use crate::health::ComponentReporter;
+use crate::models::{
+    ContainerMetrics as LocalMetrics, NodeMetrics as LocalNodeMetrics, OwnerRef,
+    PodProfile as LocalPodProfile, ResourceProfile as LocalProfile,
+};
+use crate::observability::AgentMetrics;
+use crate::predictor::ContainerPrediction;
 use crate::proto::{
-    Anomaly as ProtoAnomaly, ContainerMetrics as ProtoMetrics, MetricsBatch, PredictorSyncClient,
-    ResourceProfile as ProtoProfile, SyncResponse,
+    Anomaly as ProtoAnomaly, ContainerMetrics as ProtoMetrics, DataGap as ProtoDataGap,
+    MetricsBatch, NodeMetrics as ProtoNodeMetrics, PodProfile as ProtoPodProfile,
+    ResourceProfile as ProtoProfile, SyncResponse, WorkloadRef,
 };
 use anyhow::{Context, Result};
-use std::sync::Arc;
+use prost::Message;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 use tokio::time::Instant;
-use tonic::transport::Channel;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, error, info, warn};
 
 /// Configuration for metrics streaming
@@ -31,6 +53,22 @@ pub struct StreamingConfig {
     pub retry_delay: Duration,
     /// Maximum retries before giving up
     pub max_retries: u32,
+    /// Close and reopen the stream after this long to collect the server ack
+    pub max_stream_lifetime: Duration,
+    /// Maximum serialized size of a single batch message in bytes
+    pub max_message_size: usize,
+    /// Upper bound on bytes sent per second (unlimited when `None`)
+    pub max_bytes_per_sec: Option<u64>,
+    /// Upper bound on batches sent per second (unlimited when `None`)
+    pub max_batches_per_sec: Option<f64>,
+    /// Send each container identity once per stream and reference it by index
+    ///
+    /// Requires an API that understands `container_ref`.
+    pub encode_identities: bool,
+    /// Stream anomalies to the API
+    ///
+    /// Standalone agents deliver anomalies to webhooks instead.
+    pub send_anomalies: bool,
 }
 
 impl Default for StreamingConfig {
@@ -41,6 +79,12 @@ impl Default for StreamingConfig {
             channel_buffer_size: 1000,
             retry_delay: Duration::from_secs(5),
             max_retries: 3,
+            max_stream_lifetime: Duration::from_secs(10 * 60),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_bytes_per_sec: None,
+            max_batches_per_sec: None,
+            encode_identities: false,
+            send_anomalies: true,
         }
     }
 }
@@ -51,8 +95,71 @@ pub struct PendingData {
     pub metrics: Vec<LocalMetrics>,
     pub predictions: Vec<LocalProfile>,
     pub anomalies: Vec<AnomalyData>,
+    /// Latest node sample; a newer one replaces it before sending
+    pub node_metrics: Option<LocalNodeMetrics>,
+    pub pod_profiles: Vec<LocalPodProfile>,
+    /// Metrics shed under back-pressure, reported with the next batch
+    pub data_gap: Option<DataGap>,
+}
+
+impl PendingData {
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+            && self.predictions.is_empty()
+            && self.anomalies.is_empty()
+            && self.node_metrics.is_none()
+            && self.pod_profiles.is_empty()
+            && self.data_gap.is_none()
+    }
+}
+
+/// Raw metrics dropped because the streaming queue was full
+///
+/// Lets the API tell missing samples apart from idle containers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataGap {
+    /// Timestamp of the oldest dropped sample (Unix seconds)
+    pub start: i64,
+    /// Timestamp of the newest dropped sample (Unix seconds)
+    pub end: i64,
+    pub dropped_metrics: u64,
 }
 
+impl DataGap {
+    /// Gap covering `metrics`, `None` without metrics
+    pub fn from_metrics(metrics: &[LocalMetrics]) -> Option<Self> {
+        Some(Self {
+            start: metrics.iter().map(|m| m.timestamp).min()?,
+            end: metrics.iter().map(|m| m.timestamp).max()?,
+            dropped_metrics: metrics.len() as u64,
+        })
+    }
+
+    /// Gap covering both gaps
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+            dropped_metrics: self.dropped_metrics + other.dropped_metrics,
+        }
+    }
+}
+
+/// Merge `gap` into `pending`
+fn merge_gap(pending: &mut Option<DataGap>, gap: Option<DataGap>) {
+    *pending = match (*pending, gap) {
+        (Some(held), Some(gap)) => Some(held.merge(gap)),
+        (held, gap) => held.or(gap),
+    };
+}
+
+/// Data held back while the streaming channel is full
+///
+/// Shared by the streamer and the worker, which merges it into its next
+/// batch. Only holds data that is never dropped, and the gap left by the
+/// metrics that were.
+pub type Overflow = Arc<Mutex<PendingData>>;
+
 /// Anomaly data for streaming
 #[derive(Debug, Clone)]
 pub struct AnomalyData {
@@ -72,6 +179,10 @@ pub struct MetricsStreamer {
     node_name: String,
     sender: mpsc::Sender<PendingData>,
     stats: Arc<tokio::sync::RwLock<StreamingStats>>,
+    overflow: Overflow,
+    dropped_metrics: AtomicU64,
+    dropped_node_metrics: AtomicU64,
+    metrics: AgentMetrics,
 }
 
 /// Statistics for streaming operations
@@ -82,8 +193,19 @@ pub struct StreamingStats {
     pub predictions_sent: u64,
     pub anomalies_sent: u64,
     pub failures: u64,
+    pub streams_opened: u64,
     pub last_sync_time: Option<Instant>,
     pub last_error: Option<String>,
+    /// Whether the worker is currently waiting on the rate limiter
+    pub throttled: bool,
+    /// Batches delayed by the rate limiter
+    pub throttled_batches: u64,
+    /// Total time spent waiting on the rate limiter
+    pub throttle_wait: Duration,
+    /// Metrics shed because the channel was full
+    pub dropped_metrics: u64,
+    /// Node samples replaced by a newer one while the channel was full
+    pub dropped_node_metrics: u64,
 }
 
 impl MetricsStreamer {
@@ -100,6 +222,10 @@ impl MetricsStreamer {
             node_name,
             sender,
             stats: Arc::new(tokio::sync::RwLock::new(StreamingStats::default())),
+            overflow: Overflow::default(),
+            dropped_metrics: AtomicU64::new(0),
+            dropped_node_metrics: AtomicU64::new(0),
+            metrics: AgentMetrics::new(),
         };
         (streamer, receiver)
     }
@@ -142,6 +268,25 @@ impl MetricsStreamer {
         Ok(())
     }
 
+    /// Queue pod summaries for streaming
+    pub async fn queue_pod_profiles(&self, pod_profiles: Vec<LocalPodProfile>) -> Result<()> {
+        if pod_profiles.is_empty() {
+            return Ok(());
+        }
+
+        let data = PendingData {
+            pod_profiles,
+            ..Default::default()
+        };
+
+        self.sender
+            .send(data)
+            .await
+            .map_err(|_| anyhow::anyhow!("Streaming channel closed"))?;
+
+        Ok(())
+    }
+
     /// Queue anomalies for streaming
     pub async fn queue_anomalies(&self, anomalies: Vec<AnomalyData>) -> Result<()> {
         if anomalies.is_empty() {
@@ -161,14 +306,85 @@ impl MetricsStreamer {
         Ok(())
     }
 
-    /// Try to queue data without blocking (returns false if channel is full)
+    /// Queue a node sample, sent with the next batch
+    pub async fn queue_node_metrics(&self, node_metrics: LocalNodeMetrics) -> Result<()> {
+        let data = PendingData {
+            node_metrics: Some(node_metrics),
+            ..Default::default()
+        };
+
+        self.sender
+            .send(data)
+            .await
+            .map_err(|_| anyhow::anyhow!("Streaming channel closed"))?;
+
+        Ok(())
+    }
+
+    /// Try to queue data without blocking, shedding raw metrics if the
+    /// channel is full
+    ///
+    /// Under back-pressure the metrics are dropped and reported to the API
+    /// as a data gap. Predictions, anomalies and pod profiles are never
+    /// dropped: they are held for the worker's next batch, like the node
+    /// sample, which a newer one replaces. Returns false if the channel was
+    /// full.
     pub fn try_queue(&self, data: PendingData) -> bool {
-        self.sender.try_send(data).is_ok()
+        match self.try_send(data) {
+            Ok(()) => true,
+            Err(data) => {
+                self.shed(data);
+                false
+            }
+        }
+    }
+
+    /// Apply the drop policy to data that did not fit in the channel
+    fn shed(&self, data: PendingData) {
+        let gap = DataGap::from_metrics(&data.metrics);
+        let mut held = self.overflow.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(gap) = gap {
+            self.dropped_metrics
+                .fetch_add(gap.dropped_metrics, Ordering::Relaxed);
+            self.metrics
+                .add_sync_dropped("metrics", gap.dropped_metrics);
+            debug!(
+                dropped = gap.dropped_metrics,
+                "Streaming channel full, dropping metrics"
+            );
+        }
+        merge_gap(&mut held.data_gap, gap);
+        merge_gap(&mut held.data_gap, data.data_gap);
+
+        if data.node_metrics.is_some() {
+            if held.node_metrics.is_some() {
+                self.dropped_node_metrics.fetch_add(1, Ordering::Relaxed);
+                self.metrics.add_sync_dropped("node_metrics", 1);
+            }
+            held.node_metrics = data.node_metrics;
+        }
+        held.predictions.extend(data.predictions);
+        held.anomalies.extend(data.anomalies);
+        held.pod_profiles.extend(data.pod_profiles);
+    }
+
+    /// Try to queue data without blocking, handing it back if the channel
+    /// is full or closed
+    ///
+    /// Lets callers fall back to buffering without cloning every batch up
+    /// front.
+    #[allow(clippy::result_large_err)]
+    pub fn try_send(&self, data: PendingData) -> Result<(), PendingData> {
+        self.sender.try_send(data).map_err(|e| e.into_inner())
     }
 
     /// Get current streaming statistics
     pub async fn stats(&self) -> StreamingStats {
-        self.stats.read().await.clone()
+        let mut stats = self.stats.read().await.clone();
+        stats.dropped_metrics = self.dropped_metrics.load(Ordering::Relaxed);
+        stats.dropped_node_metrics = self.dropped_node_metrics.load(Ordering::Relaxed);
+        stats
     }
 
     /// Get the agent ID
@@ -190,6 +406,45 @@ impl MetricsStreamer {
     pub fn stats_handle(&self) -> Arc<tokio::sync::RwLock<StreamingStats>> {
         Arc::clone(&self.stats)
     }
+
+    /// Get a clone of the overflow handle, to pass to the worker
+    pub fn overflow_handle(&self) -> Overflow {
+        Arc::clone(&self.overflow)
+    }
+}
+
+/// Number of batches that can be in flight on an open stream
+const STREAM_CHANNEL_SIZE: usize = 16;
+
+/// Time to wait for the server ack when closing a stream
+const STREAM_CLOSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An open `SyncMetrics` client stream
+struct ActiveStream {
+    /// Batches pushed here are forwarded to the server
+    sender: mpsc::Sender<MetricsBatch>,
+    /// The in-flight RPC, resolving once either side ends the stream
+    response: JoinHandle<std::result::Result<tonic::Response<SyncResponse>, tonic::Status>>,
+    opened_at: Instant,
+    /// Data pushed onto the stream that the server has not acknowledged
+    unacked: Unacked,
+    /// Identities already registered on this stream
+    identities: IdentityEncoder,
+}
+
+/// Data pushed onto a stream, pending the server's acknowledgement
+///
+/// The server acknowledges a stream only once it ends, so its batches count
+/// as sent when it closes successfully. Until then their metrics are kept,
+/// to be returned to the offline buffer if the stream fails.
+#[derive(Default)]
+struct Unacked {
+    batches: u64,
+    metrics: u64,
+    predictions: u64,
+    anomalies: u64,
+    /// Metrics to buffer again on failure, kept only with an offline buffer
+    retained: Vec<TimestampedMetrics>,
 }
 
 /// Background streaming worker
@@ -199,8 +454,24 @@ pub struct StreamingWorker {
     node_name: String,
     receiver: mpsc::Receiver<PendingData>,
     stats: Arc<tokio::sync::RwLock<StreamingStats>>,
+    /// Data the streamer held back while the channel was full
+    overflow: Overflow,
     pending_batch: PendingData,
     last_batch_time: Instant,
+    stream: Option<ActiveStream>,
+    /// Config pushed by the control plane
+    runtime: Option<watch::Receiver<RuntimeConfig>>,
+    /// Locally configured batch delay, restored when the override is cleared
+    local_batch_delay: Duration,
+    /// Bandwidth and batch rate limits
+    limiter: RateLimiter,
+    /// Health reporting for the sync component
+    health: Option<ComponentReporter>,
+    /// Hashes workload names in privacy mode
+    pseudonymizer: Option<Arc<NamePseudonymizer>>,
+    /// Takes back metrics the server never acknowledged
+    offline_buffer: Option<Arc<tokio::sync::Mutex<OfflineBufferManager>>>,
+    metrics: AgentMetrics,
 }
 
 impl StreamingWorker {
@@ -211,59 +482,152 @@ impl StreamingWorker {
         node_name: String,
         receiver: mpsc::Receiver<PendingData>,
         stats: Arc<tokio::sync::RwLock<StreamingStats>>,
+        overflow: Overflow,
     ) -> Self {
         Self {
+            local_batch_delay: config.max_batch_delay,
+            limiter: RateLimiter::new(config.max_bytes_per_sec, config.max_batches_per_sec),
             config,
             agent_id,
             node_name,
             receiver,
             stats,
+            overflow,
             pending_batch: PendingData::default(),
             last_batch_time: Instant::now(),
+            stream: None,
+            runtime: None,
+            health: None,
+            pseudonymizer: None,
+            offline_buffer: None,
+            metrics: AgentMetrics::new(),
         }
     }
 
-    /// Run the streaming worker
-    pub async fn run(&mut self, mut client: PredictorSyncClient<Channel>) {
+    /// Apply control plane sync interval and anomaly toggle updates
+    pub fn with_runtime_config(mut self, runtime: watch::Receiver<RuntimeConfig>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Report sync health through `reporter`
+    pub fn with_health(mut self, reporter: ComponentReporter) -> Self {
+        self.health = Some(reporter);
+        self
+    }
+
+    /// Hash workload names before sending while privacy mode is on
+    pub fn with_pseudonymizer(mut self, pseudonymizer: Arc<NamePseudonymizer>) -> Self {
+        self.pseudonymizer = Some(pseudonymizer);
+        self
+    }
+
+    /// Return metrics from failed streams to `buffer` instead of losing them
+    pub fn with_offline_buffer(
+        mut self,
+        buffer: Arc<tokio::sync::Mutex<OfflineBufferManager>>,
+    ) -> Self {
+        self.offline_buffer = Some(buffer);
+        self
+    }
+
+    /// Run the streaming worker until the streamer is dropped
+    pub async fn run(&mut self, sync_client: Arc<SyncClient>) {
         info!(
             agent_id = %self.agent_id,
             "Starting metrics streaming worker"
         );
 
+        let mut flush_interval = tokio::time::interval(self.config.max_batch_delay);
+
         loop {
             tokio::select! {
                 // Receive new data
-                Some(data) = self.receiver.recv() => {
+                data = self.receiver.recv() => {
+                    let Some(data) = data else {
+                        break;
+                    };
                     self.add_to_batch(data);
+                    self.take_overflow();
 
                     // Check if batch is ready to send
                     if self.should_send_batch() {
-                        self.send_batch(&mut client).await;
+                        self.send_batch(&sync_client).await;
                     }
                 }
 
                 // Timeout - send partial batch
-                _ = tokio::time::sleep(self.config.max_batch_delay) => {
+                _ = flush_interval.tick() => {
+                    self.take_overflow();
                     if !self.is_batch_empty() {
                         debug!("Sending partial batch due to timeout");
-                        self.send_batch(&mut client).await;
+                        self.send_batch(&sync_client).await;
+                    }
+                    if let Some(health) = &self.health {
+                        health.heartbeat();
+                    }
+                }
+
+                // Sync interval pushed by the control plane
+                update = next_update(&mut self.runtime) => {
+                    let delay = update.sync_interval.unwrap_or(self.local_batch_delay);
+                    if delay != self.config.max_batch_delay {
+                        info!(interval_secs = delay.as_secs(), "Sync interval updated");
+                        self.config.max_batch_delay = delay;
+                        flush_interval = tokio::time::interval(delay);
                     }
                 }
             }
         }
+
+        // Channel closed: send what is left and close the stream cleanly
+        self.take_overflow();
+        if !self.is_batch_empty() {
+            self.send_batch(&sync_client).await;
+        }
+        self.close_stream(&sync_client).await;
+
+        info!(agent_id = %self.agent_id, "Metrics streaming worker stopped");
     }
 
     /// Add data to the pending batch
     fn add_to_batch(&mut self, data: PendingData) {
-        self.pending_batch.metrics.extend(data.metrics);
-        self.pending_batch.predictions.extend(data.predictions);
-        self.pending_batch.anomalies.extend(data.anomalies);
+        append(&mut self.pending_batch.metrics, data.metrics);
+        append(&mut self.pending_batch.predictions, data.predictions);
+        append(&mut self.pending_batch.pod_profiles, data.pod_profiles);
+        if data.node_metrics.is_some() {
+            self.pending_batch.node_metrics = data.node_metrics;
+        }
+        merge_gap(&mut self.pending_batch.data_gap, data.data_gap);
+
+        if !self.config.send_anomalies {
+            return;
+        }
+
+        // Drop anomaly types disabled by the control plane
+        match self.runtime.as_ref().map(|rx| rx.borrow().clone()) {
+            Some(runtime) => self.pending_batch.anomalies.extend(
+                data.anomalies
+                    .into_iter()
+                    .filter(|a| runtime.anomaly_enabled(a.anomaly_type)),
+            ),
+            None => self.pending_batch.anomalies.extend(data.anomalies),
+        }
+    }
+
+    /// Move data held back by the streamer into the pending batch
+    fn take_overflow(&mut self) {
+        let held = std::mem::take(&mut *self.overflow.lock().unwrap_or_else(|e| e.into_inner()));
+        if !held.is_empty() {
+            self.add_to_batch(held);
+        }
     }
 
     /// Check if batch should be sent
     fn should_send_batch(&self) -> bool {
         let total_items = self.pending_batch.metrics.len()
             + self.pending_batch.predictions.len()
+            + self.pending_batch.pod_profiles.len()
             + self.pending_batch.anomalies.len();
 
         total_items >= self.config.max_batch_size
@@ -272,51 +636,119 @@ impl StreamingWorker {
 
     /// Check if batch is empty
     fn is_batch_empty(&self) -> bool {
-        self.pending_batch.metrics.is_empty()
-            && self.pending_batch.predictions.is_empty()
-            && self.pending_batch.anomalies.is_empty()
+        self.pending_batch.is_empty()
     }
 
-    /// Send the current batch
-    async fn send_batch(&mut self, client: &mut PredictorSyncClient<Channel>) {
+    /// Push the current batch onto the open stream
+    #[tracing::instrument(
+        name = "sync_batch",
+        skip_all,
+        fields(
+            metrics = self.pending_batch.metrics.len(),
+            predictions = self.pending_batch.predictions.len(),
+            anomalies = self.pending_batch.anomalies.len()
+        )
+    )]
+    async fn send_batch(&mut self, sync_client: &SyncClient) {
         let batch = std::mem::take(&mut self.pending_batch);
         self.last_batch_time = Instant::now();
 
-        let metrics_count = batch.metrics.len();
-        let predictions_count = batch.predictions.len();
-        let anomalies_count = batch.anomalies.len();
+        // Keep the metrics until the server acknowledges them
+        let taken_at = clock::now().system_time();
+        let mut retained: Vec<_> = match self.offline_buffer {
+            Some(_) => batch
+                .metrics
+                .iter()
+                .map(|metrics| TimestampedMetrics {
+                    metrics: metrics.clone(),
+                    buffered_at: taken_at,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
 
-        // Convert to proto batch
+        // Convert to proto batch, split to stay under the message size limit
         let proto_batch = self.create_proto_batch(batch);
+        let chunks = split_batch(proto_batch, self.config.max_message_size);
+        if chunks.len() > 1 {
+            debug!(
+                chunks = chunks.len(),
+                max_message_size = self.config.max_message_size,
+                "Split oversized batch"
+            );
+        }
+
+        for chunk in chunks {
+            let count = chunk.metrics.len().min(retained.len());
+            let metrics = retained.drain(..count).collect();
+            self.send_chunk(sync_client, chunk, metrics).await;
+        }
+
+        // Periodically end the stream so the server acknowledges what it received
+        let expired = self
+            .stream
+            .as_ref()
+            .map(|s| s.opened_at.elapsed() >= self.config.max_stream_lifetime)
+            .unwrap_or(false);
+        if expired {
+            debug!("Rotating long-lived sync stream");
+            self.close_stream(sync_client).await;
+        }
+    }
 
-        // Try to send with retries
+    /// Push a single message onto the stream, retrying with reconnection
+    ///
+    /// `retained` holds the message's metrics, to be buffered again if it is
+    /// never acknowledged.
+    async fn send_chunk(
+        &mut self,
+        sync_client: &SyncClient,
+        mut proto_batch: MetricsBatch,
+        retained: Vec<TimestampedMetrics>,
+    ) {
+        let metrics_count = proto_batch.metrics.len();
+        let predictions_count = proto_batch.predictions.len();
+        let anomalies_count = proto_batch.anomalies.len();
+        let encoded_len = proto_batch.encoded_len();
+
+        self.throttle(encoded_len).await;
+
+        // Try to send with retries, reconnecting between attempts
+        let started = Instant::now();
         let mut retries = 0;
         loop {
-            match self.send_single_batch(client, proto_batch.clone()).await {
-                Ok(response) => {
+            // Identity encoding strips the batch, so keep a copy to retry with
+            let retry_copy = self.config.encode_identities.then(|| proto_batch.clone());
+            match self.push_to_stream(sync_client, proto_batch).await {
+                Ok(()) => {
                     debug!(
                         metrics = metrics_count,
                         predictions = predictions_count,
                         anomalies = anomalies_count,
-                        "Batch sent successfully"
+                        "Batch pushed to stream"
                     );
-
-                    // Update stats
-                    let mut stats = self.stats.write().await;
-                    stats.batches_sent += 1;
-                    stats.metrics_sent += metrics_count as u64;
-                    stats.predictions_sent += predictions_count as u64;
-                    stats.anomalies_sent += anomalies_count as u64;
-                    stats.last_sync_time = Some(Instant::now());
-                    stats.last_error = None;
-
-                    if !response.success {
-                        warn!(message = %response.message, "API reported sync issue");
+                    self.metrics
+                        .observe_batch_sent(encoded_len, started.elapsed().as_secs_f64());
+
+                    // Counted as sent once the server acknowledges the stream
+                    if let Some(stream) = self.stream.as_mut() {
+                        let unacked = &mut stream.unacked;
+                        unacked.batches += 1;
+                        unacked.metrics += metrics_count as u64;
+                        unacked.predictions += predictions_count as u64;
+                        unacked.anomalies += anomalies_count as u64;
+                        unacked.retained.extend(retained);
+                    }
+                    if let Some(health) = &self.health {
+                        health.success();
                     }
                     break;
                 }
-                Err(e) => {
+                Err((e, returned)) => {
+                    proto_batch = retry_copy.unwrap_or(returned);
                     retries += 1;
+                    sync_client.report_stream_failure(&e.to_string()).await;
+
                     if retries >= self.config.max_retries {
                         error!(
                             error = %e,
@@ -324,61 +756,316 @@ impl StreamingWorker {
                             "Failed to send batch after max retries"
                         );
 
+                        self.restore_unacked(retained).await;
+
                         // Update failure stats
+                        self.metrics.inc_sync_failures();
                         let mut stats = self.stats.write().await;
                         stats.failures += 1;
                         stats.last_error = Some(e.to_string());
+                        if let Some(health) = &self.health {
+                            health.failure(format!("Failed to send batch: {}", e));
+                        }
                         break;
                     }
 
+                    let backoff = sync_client
+                        .get_reconnect_backoff()
+                        .await
+                        .max(self.config.retry_delay);
                     warn!(
                         error = %e,
                         retry = retries,
-                        "Failed to send batch, retrying"
+                        backoff_secs = backoff.as_secs(),
+                        "Failed to send batch, reconnecting"
                     );
-                    tokio::time::sleep(self.config.retry_delay).await;
+                    tokio::time::sleep(backoff).await;
                 }
             }
         }
     }
 
-    /// Send a single batch to the API
-    async fn send_single_batch(
-        &self,
-        client: &mut PredictorSyncClient<Channel>,
-        batch: MetricsBatch,
-    ) -> Result<SyncResponse> {
-        // Create a stream with a single batch
-        let stream = tokio_stream::once(batch);
+    /// Wait until the rate limiter admits a message of `bytes`
+    async fn throttle(&mut self, bytes: usize) {
+        if !self.limiter.is_enabled() {
+            return;
+        }
+
+        let wait = self.limiter.reserve(bytes, Instant::now());
+        if wait.is_zero() {
+            self.stats.write().await.throttled = false;
+            return;
+        }
+
+        {
+            let mut stats = self.stats.write().await;
+            stats.throttled = true;
+            stats.throttled_batches += 1;
+            stats.throttle_wait += wait;
+        }
+        debug!(
+            bytes = bytes,
+            wait_ms = wait.as_millis() as u64,
+            "Throttling sync traffic"
+        );
+        tokio::time::sleep(wait).await;
+    }
 
-        let response = client
-            .sync_metrics(stream)
+    /// Push a batch onto the open stream, opening one if needed
+    ///
+    /// A batch that was not sent is handed back with the error, so that it
+    /// can be retried without copying it up front.
+    async fn push_to_stream(
+        &mut self,
+        sync_client: &SyncClient,
+        mut batch: MetricsBatch,
+    ) -> Result<(), (anyhow::Error, MetricsBatch)> {
+        // A finished RPC means the server ended the stream
+        if self
+            .stream
+            .as_ref()
+            .map(|s| s.response.is_finished())
+            .unwrap_or(false)
+        {
+            self.close_stream(sync_client).await;
+        }
+
+        if self.stream.is_none() {
+            match self.open_stream(sync_client).await {
+                Ok(stream) => self.stream = Some(stream),
+                Err(e) => return Err((e, batch)),
+            }
+        }
+
+        let Some(stream) = self.stream.as_mut() else {
+            return Err((anyhow::anyhow!("Sync stream not open"), batch));
+        };
+
+        if self.config.encode_identities {
+            stream.identities.encode(&mut batch);
+        }
+
+        if let Err(mpsc::error::SendError(batch)) = stream.sender.send(batch).await {
+            // Receiver dropped: the RPC terminated underneath us
+            self.close_stream(sync_client).await;
+            return Err((anyhow::anyhow!("Sync stream terminated by server"), batch));
+        }
+
+        Ok(())
+    }
+
+    /// Open a new `SyncMetrics` client stream
+    async fn open_stream(&self, sync_client: &SyncClient) -> Result<ActiveStream> {
+        let mut client = sync_client
+            .get_streaming_client()
             .await
-            .context("Failed to sync metrics")?;
+            .context("Failed to open sync stream")?;
+
+        let (sender, receiver) = mpsc::channel(STREAM_CHANNEL_SIZE);
+        let response =
+            tokio::spawn(async move { client.sync_metrics(ReceiverStream::new(receiver)).await });
+
+        self.stats.write().await.streams_opened += 1;
+        debug!(agent_id = %self.agent_id, "Opened sync stream");
+
+        Ok(ActiveStream {
+            sender,
+            response,
+            opened_at: Instant::now(),
+            unacked: Unacked::default(),
+            identities: IdentityEncoder::default(),
+        })
+    }
+
+    /// End the open stream, if any, and wait for the server's response
+    async fn close_stream(&mut self, sync_client: &SyncClient) {
+        let Some(stream) = self.stream.take() else {
+            return;
+        };
+
+        // Dropping the sender ends the client stream
+        drop(stream.sender);
+        let unacked = stream.unacked;
+
+        match tokio::time::timeout(STREAM_CLOSE_TIMEOUT, stream.response).await {
+            Ok(Ok(Ok(response))) => {
+                let response = response.into_inner();
+                debug!(batches = unacked.batches, "Sync stream closed");
+                sync_client.report_stream_success().await;
+                if !response.success {
+                    warn!(message = %response.message, "API reported sync issue");
+                }
+
+                let mut stats = self.stats.write().await;
+                stats.batches_sent += unacked.batches;
+                stats.metrics_sent += unacked.metrics;
+                stats.predictions_sent += unacked.predictions;
+                stats.anomalies_sent += unacked.anomalies;
+                if unacked.batches > 0 {
+                    stats.last_sync_time = Some(Instant::now());
+                }
+                stats.last_error = None;
+                return;
+            }
+            Ok(Ok(Err(status))) => {
+                warn!(
+                    code = ?status.code(),
+                    message = %status.message(),
+                    batches = unacked.batches,
+                    "Sync stream terminated with error"
+                );
+                sync_client.report_stream_failure(status.message()).await;
+                self.stats.write().await.last_error = Some(status.to_string());
+            }
+            Ok(Err(e)) => {
+                error!(error = %e, "Sync stream task panicked");
+            }
+            Err(_) => {
+                warn!(
+                    timeout_secs = STREAM_CLOSE_TIMEOUT.as_secs(),
+                    "Timed out waiting for sync stream response"
+                );
+            }
+        }
+
+        self.restore_unacked(unacked.retained).await;
+    }
+
+    /// Return metrics the server never acknowledged to the offline buffer
+    async fn restore_unacked(&self, metrics: Vec<TimestampedMetrics>) {
+        let Some(buffer) = &self.offline_buffer else {
+            return;
+        };
+        if metrics.is_empty() {
+            return;
+        }
 
-        Ok(response.into_inner())
+        debug!(
+            metrics = metrics.len(),
+            "Returning unacknowledged metrics to the offline buffer"
+        );
+        buffer.lock().await.restore_front(metrics);
     }
 
     /// Create a proto batch from local data
     fn create_proto_batch(&self, data: PendingData) -> MetricsBatch {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default();
-
-        MetricsBatch {
-            agent_id: self.agent_id.clone(),
-            node_name: self.node_name.clone(),
-            timestamp: Some(prost_types::Timestamp {
-                seconds: now.as_secs() as i64,
-                nanos: now.subsec_nanos() as i32,
-            }),
-            metrics: data.metrics.into_iter().map(convert_metrics).collect(),
-            predictions: data.predictions.into_iter().map(convert_profile).collect(),
-            anomalies: data.anomalies.into_iter().map(convert_anomaly).collect(),
+        let mut batch = proto_batch(&self.agent_id, &self.node_name, data);
+        if let Some(pseudonymizer) = &self.pseudonymizer {
+            pseudonymizer.apply(&mut batch);
         }
+        batch
+    }
+}
+
+/// Move `items` to the end of `pending`, reusing their allocation when
+/// nothing is pending yet
+fn append<T>(pending: &mut Vec<T>, mut items: Vec<T>) {
+    if pending.is_empty() {
+        *pending = items;
+    } else {
+        pending.append(&mut items);
     }
 }
 
+/// Convert pending data to a proto batch stamped with the current time
+///
+/// Shared by the push stream and the scrape server so both send the same
+/// batches. Strings are moved into the proto messages rather than copied,
+/// so converting costs little beyond allocating the message vectors.
+pub fn proto_batch(agent_id: &str, node_name: &str, data: PendingData) -> MetricsBatch {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    MetricsBatch {
+        agent_id: agent_id.to_string(),
+        node_name: node_name.to_string(),
+        timestamp: Some(prost_types::Timestamp {
+            seconds: now.as_secs() as i64,
+            nanos: now.subsec_nanos() as i32,
+        }),
+        metrics: data.metrics.into_iter().map(convert_metrics).collect(),
+        predictions: data.predictions.into_iter().map(convert_profile).collect(),
+        anomalies: data.anomalies.into_iter().map(convert_anomaly).collect(),
+        container_identities: Vec::new(),
+        node_metrics: data.node_metrics.map(convert_node_metrics),
+        pod_profiles: data
+            .pod_profiles
+            .into_iter()
+            .map(convert_pod_profile)
+            .collect(),
+        data_gap: data.data_gap.map(convert_data_gap),
+    }
+}
+
+/// Serialized size of a repeated field entry (tag + length prefix + body)
+fn field_len(body_len: usize) -> usize {
+    1 + prost::encoding::encoded_len_varint(body_len as u64) + body_len
+}
+
+/// Split a batch into messages that each serialize to at most `max_size` bytes
+///
+/// Items are packed greedily in order. An item that alone exceeds the budget
+/// is sent in its own message and left for the server to reject. The node
+/// sample and data gap go with the first message.
+fn split_batch(batch: MetricsBatch, max_size: usize) -> Vec<MetricsBatch> {
+    if batch.encoded_len() <= max_size {
+        return vec![batch];
+    }
+
+    let empty = MetricsBatch {
+        metrics: Vec::new(),
+        predictions: Vec::new(),
+        anomalies: Vec::new(),
+        container_identities: Vec::new(),
+        node_metrics: None,
+        pod_profiles: Vec::new(),
+        data_gap: None,
+        ..batch.clone()
+    };
+    let header_len = empty.encoded_len();
+
+    let mut chunks = Vec::new();
+    let mut current = MetricsBatch {
+        node_metrics: batch.node_metrics.clone(),
+        data_gap: batch.data_gap.clone(),
+        ..empty.clone()
+    };
+    let mut current_len = current.encoded_len();
+
+    macro_rules! pack {
+        ($field:ident) => {
+            for item in batch.$field {
+                let len = field_len(item.encoded_len());
+                if current_len + len > max_size && current_len > header_len {
+                    chunks.push(std::mem::replace(&mut current, empty.clone()));
+                    current_len = header_len;
+                }
+                if header_len + len > max_size {
+                    warn!(
+                        size = len,
+                        max_size = max_size,
+                        "Single item exceeds max message size"
+                    );
+                }
+                current.$field.push(item);
+                current_len += len;
+            }
+        };
+    }
+
+    pack!(metrics);
+    pack!(predictions);
+    pack!(pod_profiles);
+    pack!(anomalies);
+
+    if current_len > header_len {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
 /// Convert local metrics to proto format
 fn convert_metrics(m: LocalMetrics) -> ProtoMetrics {
     let timestamp = prost_types::Timestamp {
@@ -390,7 +1077,12 @@ fn convert_metrics(m: LocalMetrics) -> ProtoMetrics {
         container_id: m.container_id,
         pod_name: m.pod_name,
         namespace: m.namespace,
-        deployment: m.deployment.unwrap_or_default(),
+        deployment: m
+            .owner
+            .as_ref()
+            .and_then(OwnerRef::deployment_name)
+            .unwrap_or_default()
+            .to_string(),
         timestamp: Some(timestamp),
         cpu_usage_cores: m.cpu_usage_cores,
         cpu_throttled_periods: m.cpu_throttled_periods,
@@ -401,6 +1093,16 @@ fn convert_metrics(m: LocalMetrics) -> ProtoMetrics {
         memory_rss_bytes: 0,
         network_rx_bytes: m.network_rx_bytes,
         network_tx_bytes: m.network_tx_bytes,
+        container_ref: 0,
+        owner: m.owner.map(convert_owner),
+    }
+}
+
+/// Convert a local owner to proto format
+fn convert_owner(owner: OwnerRef) -> WorkloadRef {
+    WorkloadRef {
+        kind: owner.kind.as_str().to_string(),
+        name: owner.name,
     }
 }
 
@@ -424,6 +1126,103 @@ fn convert_profile(p: LocalProfile) -> ProtoProfile {
         model_version: p.model_version,
         generated_at: Some(timestamp),
         time_window: 0,
+        workload_kind: p.workload_kind.as_str().to_string(),
+        owner: None,
+        container_name: String::new(),
+        cpu_limit_unset: p.cpu_limit_unset,
+        memory_limit_unset: p.memory_limit_unset,
+        workload_cluster: p.workload_cluster.unwrap_or_default(),
+        spot_node: p.spot_node,
+    }
+}
+
+/// Convert a container's latest prediction to proto format, with its identity
+pub(super) fn convert_prediction(p: ContainerPrediction) -> ProtoProfile {
+    ProtoProfile {
+        container_id: p.container_id,
+        pod_name: p.pod_name,
+        namespace: p.namespace,
+        deployment: p
+            .owner
+            .as_ref()
+            .and_then(OwnerRef::deployment_name)
+            .unwrap_or_default()
+            .to_string(),
+        owner: p.owner.map(convert_owner),
+        ..convert_profile(p.profile)
+    }
+}
+
+/// Convert a local pod summary to proto format, with identity set on the
+/// container profiles
+fn convert_pod_profile(p: LocalPodProfile) -> ProtoPodProfile {
+    let deployment = p
+        .owner
+        .as_ref()
+        .and_then(OwnerRef::deployment_name)
+        .unwrap_or_default()
+        .to_string();
+    let owner = p.owner.map(convert_owner);
+    let containers = p
+        .containers
+        .into_iter()
+        .map(|c| ProtoProfile {
+            container_id: c.container_id,
+            pod_name: p.pod_name.clone(),
+            namespace: p.namespace.clone(),
+            deployment: deployment.clone(),
+            owner: owner.clone(),
+            container_name: c.container_name.unwrap_or_default(),
+            ..convert_profile(c.profile)
+        })
+        .collect();
+
+    ProtoPodProfile {
+        pod_name: p.pod_name,
+        namespace: p.namespace,
+        owner,
+        cpu_request_millicores: p.cpu_request_millicores,
+        memory_request_bytes: p.memory_request_bytes,
+        cpu_limit_millicores: p.cpu_limit_millicores,
+        memory_limit_bytes: p.memory_limit_bytes,
+        confidence: p.confidence,
+        generated_at: Some(prost_types::Timestamp {
+            seconds: p.generated_at,
+            nanos: 0,
+        }),
+        containers,
+    }
+}
+
+/// Convert a local node sample to proto format
+fn convert_node_metrics(n: LocalNodeMetrics) -> ProtoNodeMetrics {
+    ProtoNodeMetrics {
+        timestamp: Some(prost_types::Timestamp {
+            seconds: n.timestamp,
+            nanos: 0,
+        }),
+        cpu_allocatable_cores: n.cpu_allocatable_cores,
+        memory_allocatable_bytes: n.memory_allocatable_bytes,
+        cpu_usage_cores: n.cpu_usage_cores,
+        memory_usage_bytes: n.memory_usage_bytes,
+        cpu_pressure: n.cpu_pressure,
+        memory_pressure: n.memory_pressure,
+        io_pressure: n.io_pressure,
+    }
+}
+
+/// Convert a data gap to proto format
+fn convert_data_gap(gap: DataGap) -> ProtoDataGap {
+    ProtoDataGap {
+        start: Some(prost_types::Timestamp {
+            seconds: gap.start,
+            nanos: 0,
+        }),
+        end: Some(prost_types::Timestamp {
+            seconds: gap.end,
+            nanos: 0,
+        }),
+        dropped_metrics: gap.dropped_metrics,
     }
 }
 
@@ -448,12 +1247,15 @@ fn convert_anomaly(a: AnomalyData) -> ProtoAnomaly {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{ContainerProfile, WorkloadKind};
 
     #[test]
     fn test_streaming_config_default() {
         let config = StreamingConfig::default();
         assert_eq!(config.max_batch_size, 100);
         assert_eq!(config.max_batch_delay, Duration::from_secs(10));
+        assert_eq!(config.max_stream_lifetime, Duration::from_secs(600));
+        assert_eq!(config.max_message_size, DEFAULT_MAX_MESSAGE_SIZE);
     }
 
     #[test]
@@ -485,13 +1287,143 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_unacknowledged_metrics_return_to_buffer() {
+        let client = super::super::SyncClientBuilder::new()
+            .endpoint("https://test-api:8443")
+            .agent_id("test-agent")
+            .node_name("test-node")
+            .build()
+            .unwrap();
+        let (streamer, receiver) = MetricsStreamer::new(
+            StreamingConfig::default(),
+            "test-agent".to_string(),
+            "test-node".to_string(),
+        );
+        let buffer = Arc::new(tokio::sync::Mutex::new(OfflineBufferManager::new(
+            Default::default(),
+        )));
+        let mut worker = StreamingWorker::new(
+            StreamingConfig::default(),
+            "test-agent".to_string(),
+            "test-node".to_string(),
+            receiver,
+            streamer.stats_handle(),
+            streamer.overflow_handle(),
+        )
+        .with_offline_buffer(buffer.clone());
+
+        // A stream with one batch pushed, ended by the server with `response`
+        let stream =
+            |response: std::result::Result<tonic::Response<SyncResponse>, tonic::Status>| {
+                let metrics = LocalMetrics {
+                    container_id: "c1".to_string(),
+                    pod_name: "pod-1".to_string(),
+                    namespace: "default".to_string(),
+                    owner: None,
+                    timestamp: 1234567890,
+                    cpu_usage_cores: 0.5,
+                    cpu_throttled_periods: 0,
+                    memory_usage_bytes: 1024,
+                    memory_working_set_bytes: 1024,
+                    memory_cache_bytes: 0,
+                    network_rx_bytes: 0,
+                    network_tx_bytes: 0,
+                };
+                ActiveStream {
+                    sender: mpsc::channel(1).0,
+                    response: tokio::spawn(async move { response }),
+                    opened_at: Instant::now(),
+                    unacked: Unacked {
+                        batches: 1,
+                        metrics: 1,
+                        retained: vec![TimestampedMetrics {
+                            metrics,
+                            buffered_at: std::time::SystemTime::now(),
+                        }],
+                        ..Default::default()
+                    },
+                    identities: IdentityEncoder::default(),
+                }
+            };
+
+        // A failed stream hands its metrics back, and nothing counts as sent
+        worker.stream = Some(stream(Err(tonic::Status::unavailable("API restarting"))));
+        worker.close_stream(&client).await;
+        assert_eq!(buffer.lock().await.pending_sync_count(), 1);
+        assert_eq!(streamer.stats().await.batches_sent, 0);
+
+        // An acknowledged stream counts as sent and keeps nothing
+        worker.stream = Some(stream(Ok(tonic::Response::new(SyncResponse {
+            success: true,
+            ..Default::default()
+        }))));
+        worker.close_stream(&client).await;
+        assert_eq!(buffer.lock().await.pending_sync_count(), 1);
+        let stats = streamer.stats().await;
+        assert_eq!(stats.batches_sent, 1);
+        assert_eq!(stats.metrics_sent, 1);
+    }
+
+    #[test]
+    fn test_split_batch_respects_budget() {
+        let worker_metrics: Vec<LocalMetrics> = (0..200)
+            .map(|i| LocalMetrics {
+                container_id: format!("container-{}", i),
+                pod_name: format!("pod-{}", i),
+                namespace: "default".to_string(),
+                owner: Some(OwnerRef::deployment("test-deployment")),
+                timestamp: 1234567890 + i,
+                cpu_usage_cores: 0.5,
+                cpu_throttled_periods: 10,
+                memory_usage_bytes: 1024 * 1024,
+                memory_working_set_bytes: 512 * 1024,
+                memory_cache_bytes: 256 * 1024,
+                network_rx_bytes: 1000,
+                network_tx_bytes: 2000,
+            })
+            .collect();
+
+        let batch = MetricsBatch {
+            agent_id: "test-agent".to_string(),
+            node_name: "test-node".to_string(),
+            timestamp: None,
+            metrics: worker_metrics.into_iter().map(convert_metrics).collect(),
+            predictions: Vec::new(),
+            anomalies: Vec::new(),
+            container_identities: Vec::new(),
+            node_metrics: None,
+            pod_profiles: Vec::new(),
+            data_gap: Some(convert_data_gap(DataGap {
+                start: 1234567800,
+                end: 1234567890,
+                dropped_metrics: 40,
+            })),
+        };
+        let total = batch.encoded_len();
+
+        // Fits: returned unchanged
+        assert_eq!(split_batch(batch.clone(), total).len(), 1);
+
+        // Too big: every chunk fits and nothing is lost
+        let max_size = total / 4;
+        let chunks = split_batch(batch, max_size);
+        assert!(chunks.len() >= 4);
+        assert!(chunks.iter().all(|c| c.encoded_len() <= max_size));
+        assert!(chunks.iter().all(|c| c.agent_id == "test-agent"));
+        assert_eq!(chunks.iter().map(|c| c.metrics.len()).sum::<usize>(), 200);
+        assert_eq!(chunks[0].metrics[0].container_id, "container-0");
+        assert!(chunks[0].data_gap.is_some());
+        assert!(chunks[1..].iter().all(|c| c.data_gap.is_none()));
+    }
+
     #[test]
     fn test_convert_metrics() {
         let local = LocalMetrics {
             container_id: "test-container".to_string(),
             pod_name: "test-pod".to_string(),
             namespace: "default".to_string(),
-            deployment: Some("test-deployment".to_string()),
+            owner: Some(OwnerRef::deployment("test-deployment")),
             timestamp: 1234567890,
             cpu_usage_cores: 0.5,
             cpu_throttled_periods: 10,
@@ -502,11 +1434,69 @@ mod tests {
             network_tx_bytes: 2000,
         };
 
-        let proto = convert_metrics(local);
+        let proto = convert_metrics(local.clone());
         assert_eq!(proto.container_id, "test-container");
         assert_eq!(proto.pod_name, "test-pod");
         assert_eq!(proto.namespace, "default");
         assert_eq!(proto.deployment, "test-deployment");
+        assert_eq!(proto.owner.unwrap().kind, "deployment");
         assert_eq!(proto.cpu_usage_cores, 0.5);
+
+        // Only Deployment owners fill the deprecated deployment field
+        let proto = convert_metrics(LocalMetrics {
+            owner: Some(OwnerRef::new(WorkloadKind::StatefulSet, "postgres")),
+            ..local
+        });
+        assert!(proto.deployment.is_empty());
+        assert_eq!(
+            proto.owner,
+            Some(WorkloadRef {
+                kind: "statefulset".to_string(),
+                name: "postgres".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_convert_pod_profile() {
+        let profile = LocalProfile {
+            cpu_request_millicores: 250,
+            cpu_limit_millicores: 500,
+            memory_request_bytes: 1024,
+            memory_limit_bytes: 2048,
+            confidence: 0.8,
+            model_version: "v1".to_string(),
+            generated_at: 1234567890,
+            workload_kind: WorkloadKind::StatefulSet,
+            ..Default::default()
+        };
+        let pod = LocalPodProfile {
+            pod_name: "postgres-0".to_string(),
+            namespace: "db".to_string(),
+            owner: Some(OwnerRef::new(WorkloadKind::StatefulSet, "postgres")),
+            cpu_request_millicores: 250,
+            cpu_limit_millicores: 500,
+            memory_request_bytes: 1024,
+            memory_limit_bytes: 2048,
+            confidence: 0.8,
+            generated_at: 1234567890,
+            containers: vec![ContainerProfile {
+                container_id: "c1".to_string(),
+                container_name: Some("postgres".to_string()),
+                profile,
+            }],
+        };
+
+        let proto = convert_pod_profile(pod);
+        assert_eq!(proto.pod_name, "postgres-0");
+        assert_eq!(proto.cpu_request_millicores, 250);
+        assert_eq!(proto.owner.as_ref().unwrap().kind, "statefulset");
+        assert_eq!(proto.containers.len(), 1);
+        let container = &proto.containers[0];
+        assert_eq!(container.container_name, "postgres");
+        assert_eq!(container.namespace, "db");
+        assert_eq!(container.owner, proto.owner);
+        assert!(container.deployment.is_empty());
+        assert_eq!(container.workload_kind, "statefulset");
     }
 }
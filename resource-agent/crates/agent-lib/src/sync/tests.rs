@@ -2,10 +2,11 @@
 //!
 //! These tests verify:
 //! - Reconnection with buffered data
+//! - Sync pipeline behaviour across an API outage
 //! - Model update flow
 
 use super::*;
-use crate::models::ContainerMetrics;
+use crate::models::{ContainerMetrics, OwnerRef};
 use std::time::Duration;
 use tempfile::TempDir;
 
@@ -15,7 +16,7 @@ fn create_test_metrics(id: &str, timestamp: i64) -> ContainerMetrics {
         container_id: id.to_string(),
         pod_name: format!("pod-{}", id),
         namespace: "default".to_string(),
-        deployment: Some("test-deployment".to_string()),
+        owner: Some(OwnerRef::deployment("test-deployment")),
         timestamp,
         cpu_usage_cores: 0.5,
         cpu_throttled_periods: 10,
@@ -37,6 +38,7 @@ mod buffer_reconnection_tests {
             max_size: 1000,
             persistence_path: None,
             flush_interval: Duration::from_secs(60),
+            downsample: None,
         };
 
         let mut manager = OfflineBufferManager::new(config);
@@ -76,6 +78,7 @@ mod buffer_reconnection_tests {
             max_size: 1000,
             persistence_path: None,
             flush_interval: Duration::from_secs(60),
+            downsample: None,
         };
 
         let mut manager = OfflineBufferManager::new(config);
@@ -103,6 +106,7 @@ mod buffer_reconnection_tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "persistence")]
     async fn test_buffer_persistence() {
         let temp_dir = TempDir::new().unwrap();
         let persistence_path = temp_dir.path().join("buffer.json");
@@ -114,6 +118,7 @@ mod buffer_reconnection_tests {
                 max_size: 1000,
                 persistence_path: Some(persistence_path.clone()),
                 flush_interval: Duration::from_secs(1),
+                downsample: None,
             };
 
             let mut buffer = MetricsBuffer::with_config(config);
@@ -143,6 +148,7 @@ mod buffer_reconnection_tests {
             max_size: 1000,
             persistence_path: None,
             flush_interval: Duration::from_secs(60),
+            downsample: None,
         };
 
         let mut buffer = MetricsBuffer::with_config(config);
@@ -170,6 +176,7 @@ mod buffer_reconnection_tests {
             max_size: 10, // Small capacity
             persistence_path: None,
             flush_interval: Duration::from_secs(60),
+            downsample: None,
         };
 
         let mut buffer = MetricsBuffer::with_config(config);
@@ -399,6 +406,59 @@ mod streaming_tests {
         assert!(!result);
     }
 
+    #[tokio::test]
+    async fn test_streamer_sheds_metrics_first() {
+        let config = StreamingConfig {
+            channel_buffer_size: 1,
+            ..Default::default()
+        };
+        let (streamer, _receiver) =
+            MetricsStreamer::new(config, "test-agent".to_string(), "test-node".to_string());
+        streamer
+            .queue_metrics(vec![create_test_metrics("c0", 1000)])
+            .await
+            .unwrap();
+
+        // Channel full: metrics are dropped, the anomaly is held
+        let data = PendingData {
+            metrics: vec![
+                create_test_metrics("c1", 2000),
+                create_test_metrics("c2", 2010),
+            ],
+            anomalies: vec![AnomalyData {
+                container_id: "c1".to_string(),
+                pod_name: "pod-c1".to_string(),
+                namespace: "default".to_string(),
+                anomaly_type: 1,
+                severity: 2,
+                message: "Memory leak".to_string(),
+                detected_at: 2010,
+            }],
+            ..Default::default()
+        };
+        assert!(!streamer.try_queue(data));
+        let data = PendingData {
+            metrics: vec![create_test_metrics("c1", 1990)],
+            ..Default::default()
+        };
+        assert!(!streamer.try_queue(data));
+
+        let stats = streamer.stats().await;
+        assert_eq!(stats.dropped_metrics, 3);
+
+        let held = streamer.overflow_handle().lock().unwrap().clone();
+        assert!(held.metrics.is_empty());
+        assert_eq!(held.anomalies.len(), 1);
+        assert_eq!(
+            held.data_gap,
+            Some(DataGap {
+                start: 1990,
+                end: 2010,
+                dropped_metrics: 3,
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_streaming_stats() {
         let config = StreamingConfig::default();
@@ -446,10 +506,11 @@ mod client_tests {
         // Initially not connected
         assert!(!client.is_connected().await);
 
-        let (connected, attempts, error) = client.connection_stats().await;
-        assert!(!connected);
-        assert_eq!(attempts, 0);
-        assert!(error.is_none());
+        let stats = client.connection_stats().await;
+        assert!(!stats.connected);
+        assert_eq!(stats.reconnect_attempts, 0);
+        assert!(stats.last_error.is_none());
+        assert_eq!(stats.circuit_state, CircuitState::Closed);
     }
 
     #[tokio::test]
@@ -466,3 +527,323 @@ mod client_tests {
         assert_eq!(backoff, Duration::from_secs(1));
     }
 }
+
+mod pipeline_tests {
+    use super::*;
+    use crate::observability::AgentMetrics;
+    use crate::proto::{SyncMetricsRequest, SyncMetricsResponse};
+    use std::future::Future;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tokio::sync::{broadcast, Mutex};
+    use tonic::codec::{CompressionEncoding, EnabledCompressionEncodings, ProstCodec};
+    use tonic::codegen::{http, Body, BoxFuture, Context, Poll, Service, StdError};
+
+    /// Recommendation API that only serves `SyncMetrics`, keeping each batch
+    #[derive(Clone, Default)]
+    struct FakeApi {
+        received: Arc<Mutex<Vec<SyncMetricsRequest>>>,
+    }
+
+    impl FakeApi {
+        /// Timestamps of the metrics received so far, in arrival order
+        async fn timestamps(&self) -> Vec<i64> {
+            self.received
+                .lock()
+                .await
+                .iter()
+                .flat_map(|batch| &batch.metrics)
+                .map(|m| m.timestamp.as_ref().map_or(0, |t| t.seconds))
+                .collect()
+        }
+
+        async fn serve(self, addr: SocketAddr) {
+            tonic::transport::Server::builder()
+                .add_service(self)
+                .serve(addr)
+                .await
+                .unwrap();
+        }
+    }
+
+    struct SyncMetricsSvc(FakeApi);
+
+    impl tonic::server::ClientStreamingService<SyncMetricsRequest> for SyncMetricsSvc {
+        type Response = SyncMetricsResponse;
+        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+        fn call(
+            &mut self,
+            request: tonic::Request<tonic::Streaming<SyncMetricsRequest>>,
+        ) -> Self::Future {
+            let api = self.0.clone();
+            Box::pin(async move {
+                let mut stream = request.into_inner();
+                let mut metrics_received = 0;
+                while let Some(batch) = stream.message().await? {
+                    metrics_received += batch.metrics.len() as i64;
+                    api.received.lock().await.push(batch);
+                }
+                Ok(tonic::Response::new(SyncMetricsResponse {
+                    success: true,
+                    metrics_received,
+                    ..Default::default()
+                }))
+            })
+        }
+    }
+
+    impl<B> Service<http::Request<B>> for FakeApi
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<B>) -> Self::Future {
+            let method = SyncMetricsSvc(self.clone());
+            Box::pin(async move {
+                if request.uri().path() != "/predictor.v1.PredictorSyncService/SyncMetrics" {
+                    return Ok(tonic::Status::unimplemented("").to_http());
+                }
+                let mut gzip = EnabledCompressionEncodings::default();
+                gzip.enable(CompressionEncoding::Gzip);
+                let mut grpc = tonic::server::Grpc::new(ProstCodec::default())
+                    .apply_compression_config(gzip, gzip);
+                Ok(grpc.client_streaming(method, request).await)
+            })
+        }
+    }
+
+    impl tonic::server::NamedService for FakeApi {
+        const NAME: &'static str = "predictor.v1.PredictorSyncService";
+    }
+
+    /// Local address with nothing listening on it yet
+    fn unused_addr() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    async fn within_5s(what: &str, condition: impl Future<Output = ()>) {
+        tokio::time::timeout(Duration::from_secs(5), condition)
+            .await
+            .unwrap_or_else(|_| panic!("timed out waiting for {}", what));
+    }
+
+    fn test_pipeline(
+        drain_batch_size: usize,
+    ) -> (SyncPipeline, tokio::sync::mpsc::Receiver<PendingData>) {
+        let (streamer, receiver) = MetricsStreamer::new(
+            StreamingConfig::default(),
+            "test-agent".to_string(),
+            "test-node".to_string(),
+        );
+        let buffer = OfflineBufferManager::new(BufferConfig::default());
+        let config = SyncPipelineConfig {
+            drain_batch_size,
+            ..Default::default()
+        };
+
+        (
+            SyncPipeline::new(config, streamer, buffer, AgentMetrics::new()),
+            receiver,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_streams_when_online() {
+        let (pipeline, mut receiver) = test_pipeline(10);
+        pipeline.set_connected(true).await;
+
+        pipeline
+            .submit_metrics(vec![create_test_metrics("c1", 1000)])
+            .await;
+
+        let received = receiver.try_recv().unwrap();
+        assert_eq!(received.metrics.len(), 1);
+        assert_eq!(pipeline.pending_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_drains_backlog_in_batches() {
+        let (pipeline, mut receiver) = test_pipeline(10);
+
+        // API goes away: everything is buffered locally
+        pipeline.set_connected(false).await;
+        for i in 0..25 {
+            pipeline
+                .submit_metrics(vec![create_test_metrics(&format!("c{}", i), 1000 + i)])
+                .await;
+        }
+        assert!(pipeline.is_offline().await);
+        assert_eq!(pipeline.pending_count().await, 25);
+        assert!(receiver.try_recv().is_err());
+
+        // Draining does nothing while offline
+        assert_eq!(pipeline.drain_buffered().await.unwrap(), 0);
+
+        // API comes back: new samples queue behind the backlog
+        pipeline.set_connected(true).await;
+        pipeline
+            .submit_metrics(vec![create_test_metrics("late", 2000)])
+            .await;
+        assert_eq!(pipeline.pending_count().await, 26);
+
+        // Drain in rate-limited batches, oldest first
+        let mut timestamps = Vec::new();
+        while pipeline.pending_count().await > 0 {
+            let sent = pipeline.drain_buffered().await.unwrap();
+            assert!(sent > 0 && sent <= 10);
+
+            let batch = receiver.try_recv().unwrap();
+            timestamps.extend(batch.metrics.iter().map(|m| m.timestamp));
+        }
+
+        assert_eq!(timestamps.len(), 26);
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(*timestamps.last().unwrap(), 2000);
+
+        // Once drained, fresh metrics bypass the buffer again
+        pipeline
+            .submit_metrics(vec![create_test_metrics("c-new", 3000)])
+            .await;
+        assert_eq!(pipeline.pending_count().await, 0);
+        assert_eq!(receiver.try_recv().unwrap().metrics[0].timestamp, 3000);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_outage_and_recovery() {
+        let addr = unused_addr();
+        let dir = TempDir::new().unwrap();
+        let ca = dir.path().join("ca.crt");
+        let token = dir.path().join("token");
+        std::fs::write(&ca, "").unwrap();
+        std::fs::write(&token, "test-token").unwrap();
+
+        // Plaintext endpoint, so the empty CA is loaded but never used
+        let client = Arc::new(
+            SyncClientBuilder::new()
+                .endpoint(format!("http://{}", addr))
+                .ca_cert_path(&ca)
+                .auth(AuthConfig::BearerTokenFile { path: token })
+                .agent_id("test-agent")
+                .node_name("test-node")
+                .connect_timeout(Duration::from_millis(500))
+                .initial_backoff(Duration::from_millis(20))
+                .max_backoff(Duration::from_millis(100))
+                .proxy_from_env(false)
+                .build()
+                .unwrap(),
+        );
+
+        let streaming = StreamingConfig {
+            max_batch_delay: Duration::from_millis(20),
+            retry_delay: Duration::from_millis(20),
+            max_stream_lifetime: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let (streamer, receiver) = MetricsStreamer::new(
+            streaming.clone(),
+            "test-agent".to_string(),
+            "test-node".to_string(),
+        );
+        let worker = StreamingWorker::new(
+            streaming,
+            "test-agent".to_string(),
+            "test-node".to_string(),
+            receiver,
+            streamer.stats_handle(),
+            streamer.overflow_handle(),
+        );
+        let config = SyncPipelineConfig {
+            drain_batch_size: 10,
+            drain_interval: Duration::from_millis(20),
+            probe_interval: Duration::from_millis(50),
+        };
+        let buffer = OfflineBufferManager::new(BufferConfig::default());
+        let pipeline = Arc::new(SyncPipeline::new(
+            config,
+            streamer,
+            buffer,
+            AgentMetrics::new(),
+        ));
+        let mut worker = worker.with_offline_buffer(pipeline.buffer_handle());
+
+        let (shutdown, _) = broadcast::channel(1);
+        let worker_task = tokio::spawn({
+            let client = client.clone();
+            async move { worker.run(client).await }
+        });
+        let pipeline_task = tokio::spawn({
+            let pipeline = pipeline.clone();
+            let (client, shutdown) = (client.clone(), shutdown.subscribe());
+            async move { pipeline.run(client, shutdown).await }
+        });
+
+        // API unreachable: everything is buffered locally
+        within_5s("the pipeline to go offline", async {
+            while !pipeline.is_offline().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        for i in 0..25 {
+            pipeline
+                .submit_metrics(vec![create_test_metrics(&format!("c{}", i), 1000 + i)])
+                .await;
+        }
+        assert_eq!(pipeline.pending_count().await, 25);
+
+        // API comes back: the backlog reaches it oldest first, ahead of new samples
+        let api = FakeApi::default();
+        let server = tokio::spawn(api.clone().serve(addr));
+        pipeline
+            .submit_metrics(vec![create_test_metrics("late", 2000)])
+            .await;
+        within_5s("the backlog to reach the API", async {
+            while api.timestamps().await.len() < 26 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        let timestamps = api.timestamps().await;
+        assert_eq!(timestamps.len(), 26);
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(*timestamps.last().unwrap(), 2000);
+        assert!(client.is_connected().await);
+        assert!(!pipeline.is_offline().await);
+        assert_eq!(pipeline.pending_count().await, 0);
+
+        // Once drained, fresh metrics bypass the buffer again
+        pipeline
+            .submit_metrics(vec![create_test_metrics("c-new", 3000)])
+            .await;
+        assert_eq!(pipeline.pending_count().await, 0);
+        within_5s("fresh metrics to reach the API", async {
+            while api.timestamps().await.last() != Some(&3000) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert_eq!(api.timestamps().await.len(), 27);
+
+        // The pipeline stops on shutdown instead of running forever
+        shutdown.send(()).unwrap();
+        within_5s("the pipeline to stop", async {
+            pipeline_task.await.unwrap();
+        })
+        .await;
+        worker_task.abort();
+        server.abort();
+    }
+}
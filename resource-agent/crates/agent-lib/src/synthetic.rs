@@ -0,0 +1,369 @@
+//! Synthetic metric streams
+//!
+//! Generates samples shaped like common workloads, for benchmarks and for
+//! tests that need realistic series rather than constants, along with the
+//! cgroup files the collectors would have parsed them from. Streams are
+//! seeded, so the same seed always yields the same samples.
+
+use crate::models::{ContainerMetrics, OwnerRef, WorkloadKind};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::TAU;
+use std::fmt::Write;
+
+/// Default time between samples, the agent's collection interval
+pub const DEFAULT_SAMPLE_INTERVAL_SECS: i64 = 10;
+
+/// Default timestamp of the first sample (Unix seconds)
+pub const DEFAULT_START: i64 = 1_700_000_000;
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Shape of a synthetic workload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkloadPattern {
+    /// Web service following a daily traffic cycle, busiest mid-afternoon
+    DiurnalWeb,
+    /// Job busy for 40 minutes of every hour, memory growing over each run
+    Batch,
+    /// JVM whose heap grows steadily, with a small drop at each GC
+    LeakyJvm,
+    /// Mostly idle service running a CPU-heavy task every 15 minutes
+    SpikyCron,
+}
+
+impl WorkloadPattern {
+    pub const ALL: [WorkloadPattern; 4] = [
+        WorkloadPattern::DiurnalWeb,
+        WorkloadPattern::Batch,
+        WorkloadPattern::LeakyJvm,
+        WorkloadPattern::SpikyCron,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            WorkloadPattern::DiurnalWeb => "web",
+            WorkloadPattern::Batch => "batch",
+            WorkloadPattern::LeakyJvm => "jvm",
+            WorkloadPattern::SpikyCron => "cron",
+        }
+    }
+
+    fn owner_kind(&self) -> WorkloadKind {
+        match self {
+            WorkloadPattern::Batch => WorkloadKind::Job,
+            _ => WorkloadKind::Deployment,
+        }
+    }
+}
+
+/// Endless stream of samples of one synthetic container
+pub struct SyntheticStream {
+    pattern: WorkloadPattern,
+    container_id: String,
+    pod_name: String,
+    owner: OwnerRef,
+    rng: StdRng,
+    interval: i64,
+    start: i64,
+    index: i64,
+    throttled_periods: u64,
+    network_rx_bytes: u64,
+    network_tx_bytes: u64,
+}
+
+impl SyntheticStream {
+    /// Stream of `pattern`, its container named after `seed`
+    pub fn new(pattern: WorkloadPattern, seed: u64) -> Self {
+        let name = format!("{}-{}", pattern.name(), seed);
+        Self {
+            pattern,
+            container_id: format!("containerd://{:064x}", seed),
+            pod_name: format!("{}-7d9f8b6c5-{:05}", name, seed % 100_000),
+            owner: OwnerRef::new(pattern.owner_kind(), name),
+            rng: StdRng::seed_from_u64(seed),
+            interval: DEFAULT_SAMPLE_INTERVAL_SECS,
+            start: DEFAULT_START,
+            index: 0,
+            throttled_periods: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        }
+    }
+
+    /// Set the time between samples
+    pub fn with_interval(mut self, secs: i64) -> Self {
+        self.interval = secs.max(1);
+        self
+    }
+
+    /// Set the timestamp of the first sample
+    pub fn with_start(mut self, timestamp: i64) -> Self {
+        self.start = timestamp;
+        self
+    }
+
+    /// Usage at sample `i`, as (cpu cores, working set bytes, cache bytes)
+    fn usage(&mut self, timestamp: i64, i: i64) -> (f64, f64, f64) {
+        let noise = self.rng.gen_range(-1.0..1.0);
+        match self.pattern {
+            WorkloadPattern::DiurnalWeb => {
+                let day = timestamp.rem_euclid(86_400) as f64 / 86_400.0;
+                let load = 0.5 + 0.5 * (TAU * (day - 14.0 / 24.0)).cos();
+                (
+                    0.2 + load + noise * 0.05,
+                    512.0 * MIB + load * 128.0 * MIB + noise * 4.0 * MIB,
+                    64.0 * MIB,
+                )
+            }
+            WorkloadPattern::Batch => {
+                let elapsed = timestamp.rem_euclid(3600);
+                if elapsed < 2400 {
+                    let progress = elapsed as f64 / 2400.0;
+                    (
+                        1.5 + noise * 0.2,
+                        128.0 * MIB + progress * 1920.0 * MIB,
+                        256.0 * MIB,
+                    )
+                } else {
+                    (0.01, 64.0 * MIB, 16.0 * MIB)
+                }
+            }
+            WorkloadPattern::LeakyJvm => {
+                let elapsed = (timestamp - self.start) as f64;
+                let heap = 768.0 * MIB + elapsed * 20.0 * 1024.0;
+                if i % 30 == 29 {
+                    (0.8 + noise * 0.1, heap * 0.99, 32.0 * MIB)
+                } else {
+                    (0.3 + noise * 0.05, heap, 32.0 * MIB)
+                }
+            }
+            WorkloadPattern::SpikyCron => {
+                if timestamp.rem_euclid(900) < 60 {
+                    (2.0 + noise * 0.1, 160.0 * MIB, 8.0 * MIB)
+                } else {
+                    (0.05 + noise * 0.01, 128.0 * MIB + noise * MIB, 8.0 * MIB)
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for SyntheticStream {
+    type Item = ContainerMetrics;
+
+    fn next(&mut self) -> Option<ContainerMetrics> {
+        let i = self.index;
+        self.index += 1;
+        let timestamp = self.start + i * self.interval;
+        let (cpu, working_set, cache) = self.usage(timestamp, i);
+        let cpu = cpu.max(0.0);
+
+        // Throttled above a one-core limit
+        if cpu > 1.0 {
+            self.throttled_periods += ((cpu - 1.0) * self.interval as f64 * 10.0) as u64;
+        }
+        self.network_rx_bytes += (cpu * 1_000_000.0) as u64;
+        self.network_tx_bytes += (cpu * 400_000.0) as u64;
+
+        let working_set = working_set.max(0.0) as u64;
+        let cache = cache as u64;
+        Some(ContainerMetrics {
+            container_id: self.container_id.clone(),
+            pod_name: self.pod_name.clone(),
+            namespace: "synthetic".to_string(),
+            owner: Some(self.owner.clone()),
+            timestamp,
+            cpu_usage_cores: cpu as f32,
+            cpu_throttled_periods: self.throttled_periods,
+            memory_usage_bytes: working_set + cache,
+            memory_working_set_bytes: working_set,
+            memory_cache_bytes: cache,
+            network_rx_bytes: self.network_rx_bytes,
+            network_tx_bytes: self.network_tx_bytes,
+        })
+    }
+}
+
+/// Samples of `containers` streams cycling through every pattern, in
+/// timestamp order
+pub fn fleet(containers: usize, samples: usize, seed: u64) -> Vec<ContainerMetrics> {
+    let mut streams: Vec<SyntheticStream> = (0..containers)
+        .map(|i| {
+            let pattern = WorkloadPattern::ALL[i % WorkloadPattern::ALL.len()];
+            SyntheticStream::new(pattern, seed.wrapping_add(i as u64))
+        })
+        .collect();
+    let mut metrics = Vec::with_capacity(containers * samples);
+    for _ in 0..samples {
+        metrics.extend(streams.iter_mut().filter_map(Iterator::next));
+    }
+    metrics
+}
+
+/// cgroup v2 `cpu.stat` the sample could have been read from
+///
+/// `usage_usec` is the container's cumulative CPU time.
+pub fn cpu_stat_v2(sample: &ContainerMetrics, usage_usec: u64) -> String {
+    let periods = sample.cpu_throttled_periods * 4 + 1000;
+    format!(
+        "usage_usec {}\nuser_usec {}\nsystem_usec {}\nnr_periods {}\nnr_throttled {}\nthrottled_usec {}\nnr_bursts 0\nburst_usec 0\n",
+        usage_usec,
+        usage_usec / 10 * 8,
+        usage_usec / 10 * 2,
+        periods,
+        sample.cpu_throttled_periods,
+        sample.cpu_throttled_periods * 25_000,
+    )
+}
+
+/// cgroup v1 `cpu.stat` the sample could have been read from
+pub fn cpu_stat_v1(sample: &ContainerMetrics) -> String {
+    format!(
+        "nr_periods {}\nnr_throttled {}\nthrottled_time {}\n",
+        sample.cpu_throttled_periods * 4 + 1000,
+        sample.cpu_throttled_periods,
+        sample.cpu_throttled_periods * 25_000_000,
+    )
+}
+
+/// cgroup v2 `memory.stat` the sample could have been read from
+///
+/// Working set splits into anonymous memory and active file pages, and the
+/// page cache into active and inactive file pages.
+pub fn memory_stat_v2(sample: &ContainerMetrics) -> String {
+    let active_file = sample.memory_cache_bytes / 4;
+    let inactive_file = sample.memory_cache_bytes - active_file;
+    let anon = sample.memory_working_set_bytes.saturating_sub(active_file);
+    let stats: [(&str, u64); 24] = [
+        ("anon", anon),
+        ("file", sample.memory_cache_bytes),
+        ("kernel", 4 * 1024 * 1024),
+        ("kernel_stack", 327_680),
+        ("pagetables", 1_048_576),
+        ("sec_pagetables", 0),
+        ("percpu", 96_000),
+        ("sock", 0),
+        ("vmalloc", 0),
+        ("shmem", 0),
+        ("file_mapped", active_file / 2),
+        ("file_dirty", 4096),
+        ("file_writeback", 0),
+        ("swapcached", 0),
+        ("anon_thp", 0),
+        ("inactive_anon", anon),
+        ("active_anon", 0),
+        ("inactive_file", inactive_file),
+        ("active_file", active_file),
+        ("unevictable", 0),
+        ("slab_reclaimable", 262_144),
+        ("slab_unreclaimable", 393_216),
+        ("pgfault", sample.network_rx_bytes / 1000),
+        ("pgmajfault", 12),
+    ];
+    render_stats(&stats)
+}
+
+/// cgroup v1 `memory.stat` the sample could have been read from
+pub fn memory_stat_v1(sample: &ContainerMetrics) -> String {
+    let active_file = sample.memory_cache_bytes / 4;
+    let inactive_file = sample.memory_cache_bytes - active_file;
+    let rss = sample.memory_working_set_bytes.saturating_sub(active_file);
+    let stats: [(&str, u64); 18] = [
+        ("cache", sample.memory_cache_bytes),
+        ("rss", rss),
+        ("rss_huge", 0),
+        ("shmem", 0),
+        ("mapped_file", active_file / 2),
+        ("dirty", 4096),
+        ("writeback", 0),
+        ("pgpgin", sample.network_rx_bytes / 4096),
+        ("pgpgout", sample.network_tx_bytes / 4096),
+        ("pgfault", sample.network_rx_bytes / 1000),
+        ("pgmajfault", 12),
+        ("inactive_anon", rss),
+        ("active_anon", 0),
+        ("inactive_file", inactive_file),
+        ("active_file", active_file),
+        ("unevictable", 0),
+        ("hierarchical_memory_limit", 9_223_372_036_854_771_712),
+        ("total_inactive_file", inactive_file),
+    ];
+    render_stats(&stats)
+}
+
+fn render_stats(stats: &[(&str, u64)]) -> String {
+    let mut content = String::with_capacity(stats.len() * 24);
+    for (name, value) in stats {
+        let _ = writeln!(content, "{} {}", name, value);
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anomaly::LeakDetector;
+    use crate::collector::CgroupV2Collector;
+    use std::time::Duration;
+
+    #[test]
+    fn test_streams_are_seeded() {
+        let a: Vec<_> = SyntheticStream::new(WorkloadPattern::DiurnalWeb, 7)
+            .take(100)
+            .collect();
+        let b: Vec<_> = SyntheticStream::new(WorkloadPattern::DiurnalWeb, 7)
+            .take(100)
+            .collect();
+        assert_eq!(
+            serde_json::to_value(&a).unwrap(),
+            serde_json::to_value(&b).unwrap()
+        );
+
+        let metrics = fleet(8, 10, 1);
+        assert_eq!(metrics.len(), 80);
+        assert!(metrics.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+
+    #[test]
+    fn test_patterns_have_their_shape() {
+        let detector = LeakDetector::new(Duration::from_secs(3600), 1024.0);
+        let memory = |pattern| -> Vec<(i64, u64)> {
+            SyntheticStream::new(pattern, 1)
+                .take(360)
+                .map(|m| (m.timestamp, m.memory_working_set_bytes))
+                .collect()
+        };
+        assert!(detector
+            .detect(&memory(WorkloadPattern::LeakyJvm))
+            .is_some());
+        assert!(detector
+            .detect(&memory(WorkloadPattern::SpikyCron))
+            .is_none());
+
+        let cron: Vec<_> = SyntheticStream::new(WorkloadPattern::SpikyCron, 1)
+            .take(90)
+            .collect();
+        let busy = cron.iter().filter(|m| m.cpu_usage_cores > 1.0).count();
+        assert_eq!(busy, 6);
+    }
+
+    #[test]
+    fn test_cgroup_files_parse_back() {
+        let sample = SyntheticStream::new(WorkloadPattern::Batch, 3)
+            .nth(100)
+            .unwrap();
+
+        let (usage, throttled) =
+            CgroupV2Collector::parse_cpu_stat(&cpu_stat_v2(&sample, 5_000_000)).unwrap();
+        assert_eq!(usage, 5_000_000);
+        assert_eq!(throttled, sample.cpu_throttled_periods);
+
+        let stats = CgroupV2Collector::parse_memory_stat(&memory_stat_v2(&sample));
+        assert_eq!(
+            stats["anon"] + stats["active_file"],
+            sample.memory_working_set_bytes
+        );
+        assert_eq!(stats["file"], sample.memory_cache_bytes);
+    }
+}
@@ -1,12 +1,34 @@
 //! HTTP API for health checks and Prometheus metrics
 
+#[cfg(feature = "profiling")]
+use crate::profiling;
 use agent_lib::{
+    anomaly::{AnomalyRecord, AnomalyStore},
+    export::ExportFormat,
     health::{ComponentStatus, HealthRegistry},
+    live::{LiveFeed, LiveFilter},
+    maintenance::{MaintenanceMode, MaintenanceStatus},
+    models::ContainerMetrics,
     observability::AgentMetrics,
+    predictor::{ContainerPrediction, PredictionScheduler},
+    state::{AgentState, StateCollector},
+    sync::SyncPipeline,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
 use prometheus::{Encoder, TextEncoder};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 use tracing::info;
 
 /// Shared application state
@@ -15,15 +37,173 @@ pub struct AppState {
     pub health_registry: HealthRegistry,
     #[allow(dead_code)]
     pub metrics: AgentMetrics,
+    pub anomaly_store: Arc<AnomalyStore>,
+    /// Whether /metrics is served for Prometheus scraping
+    pub serve_prometheus: bool,
+    /// Whether the /debug/pprof endpoints are served
+    #[cfg(feature = "profiling")]
+    pub serve_profiling: bool,
+    /// Source of /state snapshots
+    pub state: StateCollector,
+    /// Source of /predictions, unset until the predictor runs
+    pub scheduler: Option<Arc<PredictionScheduler>>,
+    /// Source of /stream events
+    pub live: LiveFeed,
+    /// Source of `/export?source=buffer`, the offline buffer of the sync
+    /// pipeline
+    pub pipeline: Option<Arc<SyncPipeline>>,
+    /// Maintenance status served and signalled on /maintenance
+    pub maintenance: MaintenanceMode,
+    /// Whether maintenance can be signalled with PUT and DELETE /maintenance
+    pub maintenance_api: bool,
 }
 
 impl AppState {
-    pub fn new(health_registry: HealthRegistry, metrics: AgentMetrics) -> Self {
+    pub fn new(
+        health_registry: HealthRegistry,
+        metrics: AgentMetrics,
+        anomaly_store: Arc<AnomalyStore>,
+    ) -> Self {
         Self {
             health_registry,
             metrics,
+            anomaly_store,
+            serve_prometheus: true,
+            #[cfg(feature = "profiling")]
+            serve_profiling: false,
+            state: StateCollector::default(),
+            scheduler: None,
+            live: LiveFeed::default(),
+            pipeline: None,
+            maintenance: MaintenanceMode::default(),
+            maintenance_api: false,
         }
     }
+
+    /// Serve or hide the Prometheus /metrics endpoint
+    pub fn with_prometheus(mut self, enabled: bool) -> Self {
+        self.serve_prometheus = enabled;
+        self
+    }
+
+    /// Report the components attached to `collector` on /state
+    pub fn with_state_collector(mut self, collector: StateCollector) -> Self {
+        self.state = collector;
+        self
+    }
+
+    /// Serve the latest predictions of `scheduler` on /predictions
+    pub fn with_scheduler(mut self, scheduler: Arc<PredictionScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Stream the events published on `feed` on /stream
+    pub fn with_live_feed(mut self, feed: LiveFeed) -> Self {
+        self.live = feed;
+        self
+    }
+
+    /// Export the samples buffered by `pipeline` on /export
+    pub fn with_pipeline(mut self, pipeline: Arc<SyncPipeline>) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Serve and signal the maintenance status of `mode` on /maintenance
+    pub fn with_maintenance(mut self, mode: MaintenanceMode) -> Self {
+        self.maintenance = mode;
+        self
+    }
+
+    /// Accept or reject maintenance signals on /maintenance
+    pub fn with_maintenance_api(mut self, enabled: bool) -> Self {
+        self.maintenance_api = enabled;
+        self
+    }
+
+    /// Serve or hide the /debug/pprof profiling endpoints
+    #[cfg(feature = "profiling")]
+    pub fn with_profiling(mut self, enabled: bool) -> Self {
+        self.serve_profiling = enabled;
+        self
+    }
+}
+
+/// Default number of anomalies returned by /anomalies
+const DEFAULT_ANOMALY_LIMIT: usize = 100;
+
+/// Query parameters for /anomalies
+#[derive(Debug, Deserialize)]
+pub struct AnomalyQuery {
+    pub container_id: Option<String>,
+    pub namespace: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Response body for /anomalies
+#[derive(Debug, Serialize)]
+pub struct AnomaliesResponse {
+    pub anomalies: Vec<AnomalyRecord>,
+    pub total: usize,
+}
+
+/// Query parameters for /predictions
+#[derive(Debug, Deserialize)]
+pub struct PredictionQuery {
+    pub namespace: Option<String>,
+}
+
+/// Response body for /predictions
+#[derive(Debug, Serialize)]
+pub struct PredictionsResponse {
+    pub predictions: Vec<ContainerPrediction>,
+    pub total: usize,
+}
+
+/// Window returned by /history without `from`, in seconds
+const DEFAULT_HISTORY_WINDOW_SECS: i64 = 3600;
+
+/// Query parameters for /history
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub container_id: Option<String>,
+    /// Start of the window (Unix seconds), an hour before `to` by default
+    pub from: Option<i64>,
+    /// End of the window (Unix seconds), now by default
+    pub to: Option<i64>,
+}
+
+/// Response body for /history
+#[derive(Debug, Serialize)]
+pub struct HistoryResponse {
+    pub from: i64,
+    pub to: i64,
+    pub samples: Vec<ContainerMetrics>,
+    pub total: usize,
+}
+
+/// Query parameters for /export
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// `csv` (default) or `parquet`
+    pub format: Option<String>,
+    /// `buffer` for the offline buffer, or `live` for the samples held for
+    /// prediction; `buffer` by default when metrics are synced, else `live`
+    pub source: Option<String>,
+    pub container_id: Option<String>,
+    pub namespace: Option<String>,
+    /// Start of the window (Unix seconds), everything held by default
+    pub from: Option<i64>,
+    /// End of the window (Unix seconds), now by default
+    pub to: Option<i64>,
+}
+
+/// Query parameters for PUT /maintenance
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceQuery {
+    /// Why the node is in maintenance, e.g. `kernel upgrade`
+    pub reason: Option<String>,
 }
 
 /// Health check response - returns 200 if healthy, 503 if degraded/unhealthy
@@ -52,6 +232,227 @@ async fn readyz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     (status_code, Json(readiness))
 }
 
+/// Recent anomaly history, newest first
+async fn anomalies(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnomalyQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_ANOMALY_LIMIT);
+
+    let anomalies: Vec<AnomalyRecord> = match &query.container_id {
+        Some(id) => state
+            .anomaly_store
+            .for_container(id)
+            .into_iter()
+            .take(limit)
+            .collect(),
+        None => state
+            .anomaly_store
+            .recent(query.namespace.as_deref(), limit),
+    };
+
+    let total = anomalies.len();
+    Json(AnomaliesResponse { anomalies, total })
+}
+
+/// Latest prediction of every container on the node
+async fn predictions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PredictionQuery>,
+) -> Response {
+    let Some(scheduler) = &state.scheduler else {
+        return predictor_unavailable();
+    };
+
+    let mut predictions = scheduler.latest_predictions().await;
+    if let Some(namespace) = &query.namespace {
+        predictions.retain(|p| &p.namespace == namespace);
+    }
+
+    let total = predictions.len();
+    Json(PredictionsResponse { predictions, total }).into_response()
+}
+
+/// Latest prediction of a container, 404 until it has one
+async fn container_prediction(
+    State(state): State<Arc<AppState>>,
+    Path(container_id): Path<String>,
+) -> Response {
+    let Some(scheduler) = &state.scheduler else {
+        return predictor_unavailable();
+    };
+
+    match scheduler.latest_prediction(&container_id).await {
+        Some(prediction) => Json(prediction).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            format!("No prediction for container {}", container_id),
+        )
+            .into_response(),
+    }
+}
+
+/// The agent only collects and predicts in simulation mode for now
+fn predictor_unavailable() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Predictions disabled: the agent only runs its predictor with --simulate",
+    )
+        .into_response()
+}
+
+/// Samples held for prediction in a time window, oldest first
+async fn history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    let to = query.to.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let from = query.from.unwrap_or(to - DEFAULT_HISTORY_WINDOW_SECS);
+    if from > to {
+        return (StatusCode::BAD_REQUEST, "from is after to").into_response();
+    }
+
+    let Some(scheduler) = &state.scheduler else {
+        return predictor_unavailable();
+    };
+    let samples = scheduler
+        .recent_metrics(query.container_id.as_deref(), from, to)
+        .await;
+    let total = samples.len();
+    Json(HistoryResponse {
+        from,
+        to,
+        samples,
+        total,
+    })
+    .into_response()
+}
+
+/// Buffered or live samples as CSV or Parquet, in the schema of
+/// `agent_lib::export`
+async fn export(State(state): State<Arc<AppState>>, Query(query): Query<ExportQuery>) -> Response {
+    let format: ExportFormat = match query.format.as_deref().unwrap_or("csv").parse() {
+        Ok(format) => format,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    let to = query.to.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let from = query.from.unwrap_or(0);
+    if from > to {
+        return (StatusCode::BAD_REQUEST, "from is after to").into_response();
+    }
+
+    let container_id = query.container_id.as_deref();
+    let default_source = if state.pipeline.is_some() {
+        "buffer"
+    } else {
+        "live"
+    };
+    let mut samples = match query.source.as_deref().unwrap_or(default_source) {
+        "buffer" => match &state.pipeline {
+            Some(pipeline) => pipeline.query_buffered(container_id, from, to).await,
+            None => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Metrics buffer is not running",
+                )
+                    .into_response()
+            }
+        },
+        "live" => match &state.scheduler {
+            Some(scheduler) => scheduler.recent_metrics(container_id, from, to).await,
+            None => return predictor_unavailable(),
+        },
+        other => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown source '{}', expected buffer or live", other),
+            )
+                .into_response()
+        }
+    };
+
+    if let Some(namespace) = &query.namespace {
+        samples.retain(|m| &m.namespace == namespace);
+    }
+
+    match agent_lib::export::export(&samples, format) {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"metrics.{}\"", format.extension()),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Live samples and anomalies as server-sent events
+///
+/// Each event is named after its type (`metrics` or `anomaly`). A client
+/// that falls behind gets a `lagged` event with the number it missed.
+async fn stream(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<LiveFilter>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let events =
+        BroadcastStream::new(state.live.subscribe()).filter_map(move |event| match event {
+            Ok(event) => filter
+                .matches(&event)
+                .then(|| Event::default().event(event.name()).json_data(&event)),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(Event::default()
+                .event("lagged")
+                .data(missed.to_string()))),
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Internal state snapshot endpoint
+async fn agent_state(State(state): State<Arc<AppState>>) -> Json<AgentState> {
+    Json(state.state.snapshot().await)
+}
+
+/// Whether alerts and predictions are paused for maintenance
+async fn maintenance_status(State(state): State<Arc<AppState>>) -> Json<MaintenanceStatus> {
+    Json(state.maintenance.status())
+}
+
+/// Signal maintenance until DELETE /maintenance
+async fn begin_maintenance(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MaintenanceQuery>,
+) -> Response {
+    if !state.maintenance_api {
+        return maintenance_api_disabled();
+    }
+    let reason = query.reason.unwrap_or_else(|| "maintenance".to_string());
+    state.maintenance.begin(reason);
+    Json(state.maintenance.status()).into_response()
+}
+
+/// Clear the maintenance signal; a cordon still keeps the node in maintenance
+async fn end_maintenance(State(state): State<Arc<AppState>>) -> Response {
+    if !state.maintenance_api {
+        return maintenance_api_disabled();
+    }
+    state.maintenance.end();
+    Json(state.maintenance.status()).into_response()
+}
+
+/// The API is unauthenticated, so signals are only accepted when enabled
+fn maintenance_api_disabled() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        "Maintenance signals are disabled, set AGENT_MAINTENANCE_API_ENABLED",
+    )
+        .into_response()
+}
+
 /// Prometheus metrics endpoint
 async fn metrics() -> impl IntoResponse {
     let encoder = TextEncoder::new();
@@ -69,11 +470,30 @@ async fn metrics() -> impl IntoResponse {
 
 /// Create the API router
 pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
+    let mut router = Router::new()
         .route("/healthz", get(healthz))
         .route("/readyz", get(readyz))
-        .route("/metrics", get(metrics))
-        .with_state(state)
+        .route("/anomalies", get(anomalies))
+        .route("/predictions", get(predictions))
+        .route("/predictions/:container_id", get(container_prediction))
+        .route("/stream", get(stream))
+        .route("/history", get(history))
+        .route("/export", get(export))
+        .route("/state", get(agent_state))
+        .route(
+            "/maintenance",
+            get(maintenance_status)
+                .put(begin_maintenance)
+                .delete(end_maintenance),
+        );
+    if state.serve_prometheus {
+        router = router.route("/metrics", get(metrics));
+    }
+    #[cfg(feature = "profiling")]
+    if state.serve_profiling {
+        router = router.merge(profiling::routes());
+    }
+    router.with_state(state)
 }
 
 /// Start the API server
@@ -88,3 +508,214 @@ pub async fn serve(port: u16, state: Arc<AppState>) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_lib::anomaly::PipelineConfig;
+    use agent_lib::collector::{async_trait, CollectorError, MetricsCollector};
+    use agent_lib::models::{ContainerInfo, ContainerRole};
+    use agent_lib::runtime::{AgentRuntime, AgentRuntimeBuilder};
+    use axum::body::Body;
+    use axum::http::{Method, Request};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    /// Steady CPU usage that spikes after 20 samples
+    #[derive(Default)]
+    struct SpikingCollector {
+        samples: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl MetricsCollector for SpikingCollector {
+        async fn collect(&self, container_id: &str) -> Result<ContainerMetrics, CollectorError> {
+            let n = self.samples.fetch_add(1, Ordering::Relaxed);
+            let cpu_usage_cores = if n < 20 {
+                0.5 + (n % 2) as f32 * 0.1
+            } else {
+                8.0
+            };
+            Ok(ContainerMetrics {
+                container_id: container_id.to_string(),
+                pod_name: "web-1".to_string(),
+                namespace: "default".to_string(),
+                owner: None,
+                timestamp: chrono::Utc::now().timestamp(),
+                cpu_usage_cores,
+                cpu_throttled_periods: 0,
+                memory_usage_bytes: 100_000_000,
+                memory_working_set_bytes: 80_000_000,
+                memory_cache_bytes: 20_000_000,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            })
+        }
+
+        async fn list_containers(&self) -> Result<Vec<ContainerInfo>, CollectorError> {
+            Ok(vec![])
+        }
+    }
+
+    /// Start `runtime` collecting from one container every 10ms
+    async fn start_runtime(runtime: AgentRuntimeBuilder) -> AgentRuntime {
+        let mut runtime = runtime
+            .node_name("test-node")
+            .collector(Arc::new(SpikingCollector::default()))
+            .collection_interval(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        runtime.registry().register(ContainerInfo {
+            container_id: "c1".to_string(),
+            pod_name: "web-1".to_string(),
+            namespace: "default".to_string(),
+            owner: None,
+            node_name: String::new(),
+            cgroup_path: "/test/c1".to_string(),
+            container_name: None,
+            role: ContainerRole::Main,
+        });
+        runtime.start().await.unwrap();
+        runtime
+    }
+
+    fn test_state(anomaly_store: Arc<AnomalyStore>) -> AppState {
+        AppState::new(HealthRegistry::new(), AgentMetrics::new(), anomaly_store)
+    }
+
+    async fn get_json(router: &Router, uri: &str) -> serde_json::Value {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn put_maintenance(state: AppState) -> StatusCode {
+        let request = Request::builder()
+            .method(Method::PUT)
+            .uri("/maintenance?reason=upgrade")
+            .body(Body::empty())
+            .unwrap();
+        create_router(Arc::new(state))
+            .oneshot(request)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_detected_anomalies_served() {
+        let store = Arc::new(AnomalyStore::default());
+        let runtime = start_runtime(
+            AgentRuntime::builder()
+                .anomaly_pipeline(PipelineConfig::default())
+                .anomaly_store(store.clone()),
+        )
+        .await;
+        let router = create_router(Arc::new(test_state(store)));
+
+        let anomalies = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let body = get_json(&router, "/anomalies").await;
+                if body["total"] != 0 {
+                    break body["anomalies"].clone();
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("the CPU spike should be recorded");
+        assert_eq!(anomalies[0]["container_id"], "c1");
+        assert_eq!(anomalies[0]["anomaly_type"], "cpu_spike");
+
+        runtime.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_predictions_disabled_without_scheduler() {
+        let router = create_router(Arc::new(test_state(Arc::new(AnomalyStore::default()))));
+        let request = Request::builder()
+            .uri("/predictions")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).starts_with("Predictions disabled"));
+    }
+
+    #[tokio::test]
+    async fn test_history_serves_collected_samples() {
+        let runtime = start_runtime(AgentRuntime::builder()).await;
+        let scheduler = runtime.scheduler().unwrap().clone();
+        let router = create_router(Arc::new(
+            test_state(Arc::new(AnomalyStore::default())).with_scheduler(scheduler),
+        ));
+
+        let samples = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let body = get_json(&router, "/history?container_id=c1").await;
+                if body["total"].as_u64() >= Some(3) {
+                    break body["samples"].clone();
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("collected samples should be served");
+        assert_eq!(samples[0]["container_id"], "c1");
+
+        runtime.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_stream_emits_metrics() {
+        let live = LiveFeed::default();
+        let runtime = start_runtime(AgentRuntime::builder().live_feed(live.clone())).await;
+        let router = create_router(Arc::new(
+            test_state(Arc::new(AnomalyStore::default())).with_live_feed(live),
+        ));
+
+        let request = Request::builder()
+            .uri("/stream?container_id=c1")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut body = response.into_body().into_data_stream();
+        let event = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let chunk = body.next().await.unwrap().unwrap();
+                let chunk = String::from_utf8_lossy(&chunk).into_owned();
+                if chunk.contains("event: metrics") {
+                    break chunk;
+                }
+            }
+        })
+        .await
+        .expect("a metrics event should be streamed");
+        assert!(event.contains("\"container_id\":\"c1\""));
+
+        runtime.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_signal_requires_flag() {
+        let state = test_state(Arc::new(AnomalyStore::default()));
+        let mode = state.maintenance.clone();
+
+        assert_eq!(put_maintenance(state.clone()).await, StatusCode::FORBIDDEN);
+        assert!(!mode.is_active());
+
+        let state = state.with_maintenance_api(true);
+        assert_eq!(put_maintenance(state).await, StatusCode::OK);
+        assert!(mode.is_active());
+    }
+}
@@ -1,7 +1,74 @@
 //! Agent configuration
 
+use agent_lib::anomaly::SidecarAlertPolicy;
+use agent_lib::collector::{RuntimeKind, StandaloneConfig};
+use agent_lib::maintenance::DEFAULT_NODE_POLL_INTERVAL;
+use agent_lib::models::PodAggregation;
+use agent_lib::observability::{
+    MetricLabelConfig, OtlpMetricsConfig, RemoteWriteConfig, DEFAULT_MAX_LABEL_SETS,
+    DEFAULT_OTLP_METRICS_ENDPOINT, DEFAULT_OTLP_METRICS_INTERVAL, DEFAULT_REMOTE_WRITE_BATCH_SIZE,
+    DEFAULT_REMOTE_WRITE_FLUSH_INTERVAL,
+};
+use agent_lib::predictor::{
+    AnomalyGate, BackfillConfig, CheckpointConfig, DEFAULT_BACKFILL_LOOKBACK,
+    DEFAULT_CHECKPOINT_INTERVAL, DEFAULT_STALE_AFTER,
+};
+use agent_lib::self_limit::{
+    SelfLimiterConfig, DEFAULT_CPU_BUDGET_MILLICORES, DEFAULT_MEMORY_BUDGET_BYTES,
+};
+use agent_lib::sync::ModelUpdateConfig;
 use anyhow::Result;
 use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How metrics leave the agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsExport {
+    /// Scraped from the /metrics endpoint
+    #[default]
+    Prometheus,
+    /// Pushed to an OTLP collector, /metrics is not served
+    Otlp,
+    /// Both scraped and pushed
+    Both,
+}
+
+impl MetricsExport {
+    /// Whether the /metrics endpoint is served
+    pub fn prometheus(&self) -> bool {
+        matches!(self, MetricsExport::Prometheus | MetricsExport::Both)
+    }
+
+    /// Whether metrics are pushed over OTLP
+    pub fn otlp(&self) -> bool {
+        matches!(self, MetricsExport::Otlp | MetricsExport::Both)
+    }
+}
+
+/// Environment the agent runs in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentMode {
+    /// DaemonSet pod, containers discovered from kubelet cgroups
+    #[default]
+    Kubernetes,
+    /// Plain Docker/containerd host, containers discovered from the runtime
+    Standalone,
+}
+
+/// How collected data reaches the Recommendation API
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMode {
+    /// The agent streams to the API
+    #[default]
+    Push,
+    /// The API scrapes the agent's gRPC server, for agents without egress
+    Scrape,
+}
 
 /// Agent configuration
 #[derive(Debug, Clone, Deserialize)]
@@ -21,23 +88,231 @@ pub struct AgentConfig {
 
     /// Metrics collection interval in seconds
     #[serde(default = "default_collection_interval")]
-    #[allow(dead_code)]
     pub collection_interval_secs: u64,
 
     /// Prediction interval in seconds
     #[serde(default = "default_prediction_interval")]
-    #[allow(dead_code)]
     pub prediction_interval_secs: u64,
+
+    /// Age in seconds from which a container's profile is stale, its
+    /// confidence decayed and its re-prediction prioritized
+    #[serde(default = "default_stale_after")]
+    pub prediction_stale_after_secs: u64,
+
+    /// Seconds between prediction scheduler checkpoints, 0 disables them
+    #[serde(default = "default_checkpoint_interval")]
+    #[allow(dead_code)]
+    pub checkpoint_interval_secs: u64,
+
+    /// Directory for locally persisted agent state
+    #[serde(default = "default_data_dir")]
+    pub data_dir: PathBuf,
+
+    /// Ed25519 public key trusted to sign model updates (base64 or PEM)
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub model_signing_public_key: Option<String>,
+
+    /// File of trusted model signing keys, e.g. a mounted ConfigMap
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub model_signing_keys_path: Option<PathBuf>,
+
+    /// Kubernetes DaemonSet or standalone container host
+    #[serde(default)]
+    pub mode: AgentMode,
+
+    /// Container runtime queried in standalone mode
+    #[serde(default = "default_runtime")]
+    pub runtime: RuntimeKind,
+
+    /// Runtime API socket, the runtime's standard socket if unset
+    #[serde(default)]
+    pub runtime_socket: Option<PathBuf>,
+
+    /// Comma-separated webhook URLs that anomaly alerts are delivered to
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub alert_webhook_urls: Option<String>,
+
+    /// Comma-separated sidecar container names excluded from anomaly
+    /// alerts, `*` for all sidecars and `well-known` for the built-in list
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub alert_excluded_sidecars: Option<String>,
+
+    /// Report multi-container pods per container plus a pod summary
+    /// (`separate`) or only as a pod summary (`aggregate`)
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub pod_aggregation: PodAggregation,
+
+    /// Flag recommendations derived from anomalous usage (`flag`) or keep
+    /// the affected resource at the previous recommendation (`hold`)
+    #[serde(default)]
+    pub anomaly_gate: AnomalyGate,
+
+    /// Prometheus scrape, OTLP push, or both
+    #[serde(default)]
+    pub metrics_export: MetricsExport,
+
+    /// OTLP/HTTP collector that metrics are pushed to
+    #[serde(default = "default_otlp_metrics_endpoint")]
+    pub otlp_metrics_endpoint: String,
+
+    /// Seconds between OTLP metrics pushes
+    #[serde(default = "default_otlp_metrics_interval")]
+    pub otlp_metrics_interval_secs: u64,
+
+    /// Label container, prediction and anomaly metrics by namespace
+    #[serde(default)]
+    pub metrics_namespace_labels: bool,
+
+    /// Also label namespace metrics by deployment
+    #[serde(default)]
+    pub metrics_deployment_labels: bool,
+
+    /// Namespace/deployment label combinations kept before aggregating
+    #[serde(default = "default_max_label_sets")]
+    pub metrics_max_label_sets: usize,
+
+    /// Serve /debug/pprof CPU and heap profiling endpoints, in agents built
+    /// with the `profiling` feature
+    #[serde(default)]
+    pub profiling_enabled: bool,
+
+    /// CPU the agent may use before shedding work, in millicores
+    #[serde(default = "default_cpu_budget")]
+    pub cpu_budget_millicores: f64,
+
+    /// Resident memory the agent may use before shedding work, in bytes
+    #[serde(default = "default_memory_budget")]
+    pub memory_budget_bytes: u64,
+
+    /// Prometheus endpoint read at startup to backfill prediction history
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub backfill_prometheus_url: Option<String>,
+
+    /// Hours of history read from Prometheus
+    #[serde(default = "default_backfill_lookback_hours")]
+    #[allow(dead_code)]
+    pub backfill_lookback_hours: u64,
+
+    /// Extra label matchers for backfill queries, e.g. `node="ip-10-0-1-23"`
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub backfill_selector: Option<String>,
+
+    /// Prometheus remote-write URL that collected metrics are also pushed to
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub remote_write_url: Option<String>,
+
+    /// Container samples per remote-write request
+    #[serde(default = "default_remote_write_batch_size")]
+    #[allow(dead_code)]
+    pub remote_write_batch_size: usize,
+
+    /// Longest time a partial remote-write batch waits, in seconds
+    #[serde(default = "default_remote_write_flush_interval")]
+    #[allow(dead_code)]
+    pub remote_write_flush_interval_secs: u64,
+
+    /// Push to the API or serve scrapes from it
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub sync_mode: SyncMode,
+
+    /// Port of the gRPC server the API scrapes in scrape mode
+    #[serde(default = "default_scrape_port")]
+    #[allow(dead_code)]
+    pub scrape_port: u16,
+
+    /// Seconds between checks of the node for cordons, 0 to disable
+    #[serde(default = "default_node_watch_interval")]
+    pub node_watch_interval_secs: u64,
+
+    /// Accept maintenance signals with PUT and DELETE /maintenance; off by
+    /// default as the API is unauthenticated and listens on all interfaces
+    #[serde(default)]
+    pub maintenance_api_enabled: bool,
+
+    /// Collect, predict and detect anomalies without syncing or alerting,
+    /// writing a local report instead; also enabled with `--simulate`
+    #[serde(default)]
+    pub simulate: bool,
+
+    /// File the simulation report is written to, in the data directory if
+    /// unset
+    #[serde(default)]
+    pub simulation_report_path: Option<PathBuf>,
+
+    /// Config file passed with `--config`, watched for runtime changes
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
 }
 
+/// Node name from the downward API, or the hostname outside Kubernetes
 fn default_node_name() -> String {
-    std::env::var("NODE_NAME").unwrap_or_else(|_| "unknown".to_string())
+    std::env::var("NODE_NAME")
+        .ok()
+        .or_else(hostname)
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn default_runtime() -> RuntimeKind {
+    RuntimeKind::Docker
+}
+
+fn default_otlp_metrics_endpoint() -> String {
+    DEFAULT_OTLP_METRICS_ENDPOINT.to_string()
+}
+
+fn default_otlp_metrics_interval() -> u64 {
+    DEFAULT_OTLP_METRICS_INTERVAL.as_secs()
+}
+
+fn default_max_label_sets() -> usize {
+    DEFAULT_MAX_LABEL_SETS
+}
+
+fn default_cpu_budget() -> f64 {
+    DEFAULT_CPU_BUDGET_MILLICORES
+}
+
+fn default_memory_budget() -> u64 {
+    DEFAULT_MEMORY_BUDGET_BYTES
+}
+
+fn default_backfill_lookback_hours() -> u64 {
+    DEFAULT_BACKFILL_LOOKBACK.as_secs() / 3600
+}
+
+fn default_remote_write_batch_size() -> usize {
+    DEFAULT_REMOTE_WRITE_BATCH_SIZE
+}
+
+fn default_remote_write_flush_interval() -> u64 {
+    DEFAULT_REMOTE_WRITE_FLUSH_INTERVAL.as_secs()
 }
 
 fn default_api_port() -> u16 {
     8080
 }
 
+fn default_scrape_port() -> u16 {
+    9091
+}
+
 fn default_api_endpoint() -> String {
     "http://recommendation-api:9090".to_string()
 }
@@ -50,6 +325,41 @@ fn default_prediction_interval() -> u64 {
     300
 }
 
+fn default_stale_after() -> u64 {
+    DEFAULT_STALE_AFTER.as_secs()
+}
+
+fn default_checkpoint_interval() -> u64 {
+    DEFAULT_CHECKPOINT_INTERVAL.as_secs()
+}
+
+fn default_node_watch_interval() -> u64 {
+    DEFAULT_NODE_POLL_INTERVAL.as_secs()
+}
+
+fn default_data_dir() -> PathBuf {
+    PathBuf::from("/var/lib/predictor")
+}
+
+/// Path given as `--config=<path>` or `--config <path>`
+fn config_file_arg(args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+/// Whether `--simulate` was passed
+fn simulate_arg(mut args: impl Iterator<Item = String>) -> bool {
+    args.any(|arg| arg == "--simulate")
+}
+
 impl AgentConfig {
     /// Load configuration from environment and config file
     pub fn load() -> Result<Self> {
@@ -57,12 +367,178 @@ impl AgentConfig {
             .add_source(config::Environment::with_prefix("AGENT"))
             .build()?;
 
-        Ok(config.try_deserialize().unwrap_or_else(|_| AgentConfig {
+        let mut config = config.try_deserialize().unwrap_or_else(|_| AgentConfig {
             node_name: default_node_name(),
             api_port: default_api_port(),
             api_endpoint: default_api_endpoint(),
             collection_interval_secs: default_collection_interval(),
             prediction_interval_secs: default_prediction_interval(),
-        }))
+            prediction_stale_after_secs: default_stale_after(),
+            checkpoint_interval_secs: default_checkpoint_interval(),
+            data_dir: default_data_dir(),
+            model_signing_public_key: None,
+            model_signing_keys_path: None,
+            mode: AgentMode::default(),
+            runtime: default_runtime(),
+            runtime_socket: None,
+            alert_webhook_urls: None,
+            alert_excluded_sidecars: None,
+            pod_aggregation: PodAggregation::default(),
+            anomaly_gate: AnomalyGate::default(),
+            metrics_export: MetricsExport::default(),
+            otlp_metrics_endpoint: default_otlp_metrics_endpoint(),
+            otlp_metrics_interval_secs: default_otlp_metrics_interval(),
+            metrics_namespace_labels: false,
+            metrics_deployment_labels: false,
+            metrics_max_label_sets: default_max_label_sets(),
+            profiling_enabled: false,
+            cpu_budget_millicores: default_cpu_budget(),
+            memory_budget_bytes: default_memory_budget(),
+            backfill_prometheus_url: None,
+            backfill_lookback_hours: default_backfill_lookback_hours(),
+            backfill_selector: None,
+            remote_write_url: None,
+            remote_write_batch_size: default_remote_write_batch_size(),
+            remote_write_flush_interval_secs: default_remote_write_flush_interval(),
+            sync_mode: SyncMode::default(),
+            scrape_port: default_scrape_port(),
+            node_watch_interval_secs: default_node_watch_interval(),
+            maintenance_api_enabled: false,
+            simulate: false,
+            simulation_report_path: None,
+            config_file: None,
+        });
+        config.config_file = config_file_arg(std::env::args());
+        config.simulate |= simulate_arg(std::env::args().skip(1));
+        Ok(config)
+    }
+
+    /// Model update settings, trusting the configured signing keys
+    #[allow(dead_code)]
+    pub fn model_update_config(&self) -> ModelUpdateConfig {
+        ModelUpdateConfig {
+            model_dir: self.data_dir.join("models"),
+            signing_public_keys: self.model_signing_public_key.iter().cloned().collect(),
+            signing_keys_path: self.model_signing_keys_path.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Prediction scheduler checkpoint settings, None when disabled
+    #[allow(dead_code)]
+    pub fn checkpoint_config(&self) -> Option<CheckpointConfig> {
+        (self.checkpoint_interval_secs > 0).then(|| CheckpointConfig {
+            interval: Duration::from_secs(self.checkpoint_interval_secs),
+            ..CheckpointConfig::new(self.data_dir.join("scheduler.ckpt"))
+        })
+    }
+
+    /// Runtime discovery settings for standalone mode
+    pub fn standalone_config(&self) -> StandaloneConfig {
+        StandaloneConfig {
+            runtime: self.runtime,
+            socket_path: self
+                .runtime_socket
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(self.runtime.default_socket())),
+            ..Default::default()
+        }
+    }
+
+    /// File the simulation report is written to
+    pub fn simulation_report_path(&self) -> PathBuf {
+        self.simulation_report_path
+            .clone()
+            .unwrap_or_else(|| self.data_dir.join("simulation-report.json"))
+    }
+
+    /// OTLP metrics push settings
+    pub fn otlp_metrics_config(&self) -> OtlpMetricsConfig {
+        OtlpMetricsConfig {
+            endpoint: self.otlp_metrics_endpoint.clone(),
+            interval: Duration::from_secs(self.otlp_metrics_interval_secs),
+            node_name: Some(self.node_name.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Per-namespace metric label settings
+    pub fn metric_label_config(&self) -> MetricLabelConfig {
+        MetricLabelConfig {
+            namespace: self.metrics_namespace_labels,
+            deployment: self.metrics_deployment_labels,
+            max_label_sets: self.metrics_max_label_sets,
+        }
+    }
+
+    /// Self resource budget settings
+    pub fn self_limiter_config(&self) -> SelfLimiterConfig {
+        SelfLimiterConfig {
+            cpu_budget_millicores: self.cpu_budget_millicores,
+            memory_budget_bytes: self.memory_budget_bytes,
+            ..Default::default()
+        }
+    }
+
+    /// Prometheus backfill settings, None when no endpoint is configured
+    #[allow(dead_code)]
+    pub fn backfill_config(&self) -> Option<BackfillConfig> {
+        let endpoint = self
+            .backfill_prometheus_url
+            .clone()
+            .filter(|url| !url.is_empty())?;
+        Some(BackfillConfig {
+            endpoint,
+            lookback: Duration::from_secs(self.backfill_lookback_hours * 3600),
+            step: Duration::from_secs(self.collection_interval_secs.max(1)),
+            selector: self.backfill_selector.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// Remote-write export settings, None when no URL is configured
+    #[allow(dead_code)]
+    pub fn remote_write_config(&self) -> Option<RemoteWriteConfig> {
+        let endpoint = self
+            .remote_write_url
+            .clone()
+            .filter(|url| !url.is_empty())?;
+        Some(RemoteWriteConfig {
+            endpoint,
+            batch_size: self.remote_write_batch_size,
+            flush_interval: Duration::from_secs(self.remote_write_flush_interval_secs.max(1)),
+            external_labels: vec![("node".to_string(), self.node_name.clone())],
+            ..Default::default()
+        })
+    }
+
+    /// Webhook URLs that anomaly alerts are delivered to
+    #[allow(dead_code)]
+    pub fn alert_webhook_urls(&self) -> Vec<String> {
+        self.alert_webhook_urls
+            .iter()
+            .flat_map(|urls| urls.split(','))
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Sidecars kept out of anomaly alerts
+    #[allow(dead_code)]
+    pub fn sidecar_alert_policy(&self) -> SidecarAlertPolicy {
+        let names: Vec<&str> = self
+            .alert_excluded_sidecars
+            .iter()
+            .flat_map(|names| names.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        match names.as_slice() {
+            [] => SidecarAlertPolicy::Include,
+            ["*"] => SidecarAlertPolicy::ExcludeAll,
+            ["well-known"] => SidecarAlertPolicy::well_known(),
+            names => SidecarAlertPolicy::Exclude(names.iter().map(|n| n.to_string()).collect()),
+        }
     }
 }
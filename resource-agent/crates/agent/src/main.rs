@@ -4,25 +4,70 @@
 //! collecting metrics and running local ML inference.
 
 use agent_lib::{
-    health::{components, HealthRegistry},
-    observability::{AgentMetrics, StructuredLogger},
+    anomaly::{Alerter, AnomalyStore, PipelineConfig},
+    health::{components, ComponentSpec, HealthPolicy, HealthRegistry},
+    live::LiveFeed,
+    maintenance::{MaintenanceMode, NodeWatcher},
+    node_lifecycle::NodeLifecycle,
+    observability::{AgentMetrics, OtlpMetricsExporter, StructuredLogger},
+    runtime::AgentRuntime,
+    self_limit::SelfLimiter,
+    state::StateCollector,
 };
 use anyhow::Result;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, watch};
+use tracing::{info, warn};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 mod api;
 mod config;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod reload;
+mod simulate;
+mod supervisor;
 
 const AGENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// How often anomaly history and alert state are persisted, so a crash or
+/// an OOM kill loses at most this much
+const STATE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// jemalloc options: heap profiling is built in but only sampled once the
+/// profiling endpoints are enabled
+#[cfg(feature = "profiling")]
+#[allow(non_upper_case_globals)]
+#[export_name = "malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:false,lg_prof_sample:19\0";
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing with JSON output and env filter
+    // Initialize tracing with JSON output and an env filter that can be
+    // swapped when the config file changes
+    let env_filter = EnvFilter::try_from_default_env().ok();
+    let log_level_from_env = env_filter.is_some();
+    let (log_filter, log_filter_handle) = tracing_subscriber::reload::Layer::new(
+        env_filter.unwrap_or_else(|| EnvFilter::new("info")),
+    );
+    // Export spans over OTLP when built with `otel` and an endpoint is set
+    #[cfg(feature = "otel")]
+    let otel_layer = agent_lib::otel::OtelConfig::from_env()
+        .map(|config| agent_lib::otel::layer(&config))
+        .transpose()?;
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<tracing_subscriber::layer::Identity> = None;
+
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(log_filter)
         .with(fmt::layer().json())
+        .with(otel_layer)
         .init();
 
     info!("Starting resource-agent");
@@ -30,6 +75,12 @@ async fn main() -> Result<()> {
     // Load configuration
     let config = config::AgentConfig::load()?;
     info!(node_name = %config.node_name, "Agent configured");
+    if config.mode == config::AgentMode::Standalone {
+        info!(
+            runtime = ?config.runtime,
+            "Running in standalone mode: containers discovered from the runtime, anomalies sent to webhooks only"
+        );
+    }
 
     // Initialize health registry
     let health_registry = HealthRegistry::new();
@@ -38,16 +89,175 @@ async fn main() -> Result<()> {
     health_registry.register(components::SYNC_CLIENT).await;
     health_registry.register(components::BUFFER).await;
 
+    // Sync, buffering, model updates and self-limiting are reported by
+    // /readyz without failing it
+    health_registry
+        .declare(
+            components::PREDICTOR,
+            ComponentSpec::default().depends_on(&[components::COLLECTOR]),
+        )
+        .await;
+    for component in [
+        components::SYNC_CLIENT,
+        components::BUFFER,
+        components::MODEL_UPDATE,
+        components::SELF_LIMIT,
+    ] {
+        health_registry
+            .declare(component, ComponentSpec::default().non_critical())
+            .await;
+    }
+
     // Initialize metrics
     let metrics = AgentMetrics::new();
     metrics.set_model_version("v0.1.0", "int8");
+    metrics.configure_labels(config.metric_label_config());
+
+    let (shutdown_tx, _) = broadcast::channel(1);
+
+    // Shed work when the agent itself goes over its resource budget
+    let self_limiter = SelfLimiter::new(config.self_limiter_config())
+        .with_metrics(metrics.clone())
+        .with_health(
+            health_registry
+                .reporter(
+                    components::SELF_LIMIT,
+                    HealthPolicy::default().degrade_only(),
+                )
+                .await,
+        );
+    tokio::spawn(self_limiter.run(shutdown_tx.subscribe()));
+
+    // Anomaly detectors as configured, reported on /state
+    let (anomaly_pipeline_tx, anomaly_pipeline) = watch::channel(PipelineConfig::default());
+
+    // Apply the config file and re-apply it when it changes
+    if let Some(path) = config.config_file.clone() {
+        let settings = reload::ReloadableSettings::load(&path, &config).unwrap_or_else(|e| {
+            warn!(error = %format!("{:#}", e), "Invalid config file, using defaults");
+            reload::ReloadableSettings::from_config(&config)
+        });
+        anomaly_pipeline_tx.send_replace(settings.anomaly.clone());
+        if !log_level_from_env {
+            if let Err(e) = log_filter_handle.reload(EnvFilter::new(&settings.log_level)) {
+                warn!(error = %e, "Failed to apply configured log level");
+            }
+        }
+
+        let reloader = reload::ConfigReloader::new(
+            path,
+            config.clone(),
+            settings,
+            log_filter_handle,
+            metrics.clone(),
+        );
+        let mut reloaded = reloader.subscribe();
+        tokio::spawn(async move {
+            while reloaded.changed().await.is_ok() {
+                let anomaly = reloaded.borrow_and_update().anomaly.clone();
+                anomaly_pipeline_tx.send_replace(anomaly);
+            }
+        });
+        let shutdown = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = reloader.run(shutdown).await {
+                warn!(error = %format!("{:#}", e), "Config reloading disabled");
+            }
+        });
+    }
 
     // Initialize structured logger
     let logger = StructuredLogger::new(&config.node_name);
     logger.log_startup(AGENT_VERSION, "v0.1.0");
 
-    // Create shared application state
-    let app_state = Arc::new(api::AppState::new(health_registry.clone(), metrics.clone()));
+    // Samples and anomalies streamed to /stream clients
+    let live_feed = LiveFeed::default();
+
+    // Load persisted anomaly history
+    let anomaly_store = Arc::new(
+        AnomalyStore::with_persistence(config.data_dir.join("anomalies.json"))?
+            .with_live_feed(live_feed.clone()),
+    );
+
+    // Pause alerts while the node is cordoned or an operator signals
+    // maintenance through the API, and flag predictions made on spot nodes
+    let maintenance = MaintenanceMode::new();
+    let (node_lifecycle, node_lifecycle_rx) = watch::channel(NodeLifecycle::default());
+    if config.mode == config::AgentMode::Kubernetes && config.node_watch_interval_secs > 0 {
+        match NodeWatcher::in_cluster(&config.node_name) {
+            Ok(watcher) => {
+                let watcher = watcher
+                    .with_interval(Duration::from_secs(config.node_watch_interval_secs))
+                    .with_lifecycle(node_lifecycle);
+                tokio::spawn(watcher.run(maintenance.clone(), shutdown_tx.subscribe()));
+            }
+            Err(e) => warn!(error = %format!("{:#}", e), "Cordon detection disabled"),
+        }
+    }
+
+    // Restore alert dedup state so a restart doesn't re-fire active alerts;
+    // nothing is alerted on in simulation mode
+    let alerter = (!config.simulate).then(|| {
+        Arc::new(
+            Alerter::new(config.node_name.clone())
+                .with_persistence(config.data_dir.join("alerts.json"))
+                .with_maintenance(maintenance.subscribe()),
+        )
+    });
+
+    if config.profiling_enabled {
+        #[cfg(feature = "profiling")]
+        profiling::activate_heap_profiling().await;
+        #[cfg(not(feature = "profiling"))]
+        warn!("Profiling endpoints unavailable: built without the `profiling` feature");
+    }
+
+    // Push metrics to an OTLP collector alongside or instead of scraping
+    if config.metrics_export.otlp() {
+        let exporter = OtlpMetricsExporter::new(config.otlp_metrics_config())?;
+        tokio::spawn(exporter.run(shutdown_tx.subscribe()));
+    }
+
+    // Dry run: collect, predict and detect, but only write a local report
+    let simulation = if config.simulate {
+        let runtime = AgentRuntime::builder()
+            .anomaly_pipeline(anomaly_pipeline.borrow().clone())
+            .anomaly_store(anomaly_store.clone())
+            .health(health_registry.clone())
+            .node_lifecycle(node_lifecycle_rx)
+            .maintenance(maintenance.subscribe())
+            .live_feed(live_feed.clone());
+        Some(simulate::start(&config, runtime, metrics.clone(), &shutdown_tx).await?)
+    } else {
+        None
+    };
+
+    // Create shared application state, serving what the runtime produces
+    let mut app_state = api::AppState::new(
+        health_registry.clone(),
+        metrics.clone(),
+        anomaly_store.clone(),
+    )
+    .with_prometheus(config.metrics_export.prometheus())
+    .with_state_collector(
+        StateCollector::new(&config.node_name).with_anomaly_pipeline(anomaly_pipeline.clone()),
+    )
+    .with_live_feed(live_feed)
+    .with_maintenance(maintenance)
+    .with_maintenance_api(config.maintenance_api_enabled);
+    #[cfg(feature = "profiling")]
+    {
+        app_state = app_state.with_profiling(config.profiling_enabled);
+    }
+    if let Some(runtime) = &simulation {
+        if let Some(scheduler) = runtime.scheduler() {
+            app_state = app_state.with_scheduler(scheduler.clone());
+        }
+        if let Some(pipeline) = runtime.pipeline() {
+            app_state = app_state.with_pipeline(pipeline.clone());
+        }
+    }
+    let app_state = Arc::new(app_state);
 
     // Mark agent as ready after initialization
     health_registry.set_ready(true).await;
@@ -55,10 +265,62 @@ async fn main() -> Result<()> {
     // Start health and metrics server
     let _api_handle = tokio::spawn(api::serve(config.api_port, app_state));
 
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c().await?;
-    logger.log_shutdown("SIGINT received");
+    // Restart internal tasks that get stuck
+    let supervisor = supervisor::Supervisor::new(
+        health_registry.clone(),
+        supervisor::SupervisorConfig::default(),
+    );
+    let supervisor_handle = tokio::spawn(supervisor.run(shutdown_tx.subscribe()));
+
+    // Persist anomaly history and alert state periodically
+    let flush_handle = {
+        let anomaly_store = anomaly_store.clone();
+        let alerter = alerter.clone();
+        let mut shutdown = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATE_FLUSH_INTERVAL);
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => flush_state(&anomaly_store, alerter.as_deref()),
+                    _ = shutdown.recv() => break,
+                }
+            }
+        })
+    };
+
+    // Wait for SIGINT, or SIGTERM sent by the kubelet when the pod stops
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let reason = tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            "SIGINT received"
+        }
+        _ = sigterm.recv() => "SIGTERM received",
+    };
+    logger.log_shutdown(reason);
+    let _ = shutdown_tx.send(());
+    let _ = supervisor_handle.await;
+    let _ = flush_handle.await;
+    if let Some(runtime) = simulation {
+        runtime.shutdown().await;
+    }
+    flush_state(&anomaly_store, alerter.as_deref());
     info!("Shutting down");
+    #[cfg(feature = "otel")]
+    agent_lib::otel::shutdown();
 
     Ok(())
 }
+
+/// Persist anomaly history and alert dedup state to the data directory
+fn flush_state(anomaly_store: &AnomalyStore, alerter: Option<&Alerter>) {
+    if let Err(e) = anomaly_store.flush() {
+        warn!(error = %e, "Failed to persist anomaly history");
+    }
+    if let Some(alerter) = alerter {
+        if let Err(e) = alerter.flush() {
+            warn!(error = %e, "Failed to persist alert state");
+        }
+    }
+}
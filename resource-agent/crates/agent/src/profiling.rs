@@ -0,0 +1,167 @@
+//! Profiling endpoints for live agents
+//!
+//! `/debug/pprof/profile` samples CPU with pprof-rs for a number of seconds
+//! and `/debug/pprof/heap` dumps a jemalloc heap profile. Both return pprof
+//! protobufs that `go tool pprof` reads directly; the CPU profile can also
+//! be rendered as a flamegraph SVG with `?format=flamegraph`. The routes are
+//! only served when profiling is enabled in the agent config.
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use pprof::protos::Message;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Default CPU profile duration
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+
+/// Longest CPU profile that can be requested
+const MAX_PROFILE_SECONDS: u64 = 120;
+
+/// CPU sampling frequency; not a divisor of common timer rates
+const PROFILE_FREQUENCY_HZ: i32 = 99;
+
+/// Set while a CPU profile is running, only one can run at a time
+static CPU_PROFILE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Query parameters for /debug/pprof/profile
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    pub seconds: Option<u64>,
+    /// `flamegraph` for an SVG, a pprof protobuf otherwise
+    pub format: Option<String>,
+}
+
+/// Routes for the profiling endpoints
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/debug/pprof/profile", get(cpu_profile))
+        .route("/debug/pprof/heap", get(heap_profile))
+}
+
+/// Start sampling allocations so heap profiles can be taken
+pub async fn activate_heap_profiling() {
+    let Some(ctl) = jemalloc_pprof::PROF_CTL.as_ref() else {
+        warn!("Heap profiling unavailable: jemalloc profiling is not compiled in");
+        return;
+    };
+    match ctl.lock().await.activate() {
+        Ok(()) => info!("Heap profiling activated"),
+        Err(e) => warn!(error = %e, "Failed to activate heap profiling"),
+    }
+}
+
+/// Clears the running flag even if the request is cancelled mid-profile
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        CPU_PROFILE_RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// CPU profile endpoint
+async fn cpu_profile(Query(query): Query<ProfileQuery>) -> Response {
+    if CPU_PROFILE_RUNNING.swap(true, Ordering::AcqRel) {
+        return (StatusCode::CONFLICT, "A CPU profile is already running").into_response();
+    }
+    let _running = RunningGuard;
+
+    let seconds = query
+        .seconds
+        .unwrap_or(DEFAULT_PROFILE_SECONDS)
+        .clamp(1, MAX_PROFILE_SECONDS);
+    let flamegraph = query.format.as_deref() == Some("flamegraph");
+    info!(
+        seconds = seconds,
+        flamegraph = flamegraph,
+        "Collecting CPU profile"
+    );
+
+    // The profiler is driven by signals and sleeps for the whole duration,
+    // so keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        collect_cpu_profile(Duration::from_secs(seconds), flamegraph)
+    })
+    .await
+    .context("CPU profiling task failed")
+    .and_then(|result| result);
+
+    let content_type = if flamegraph {
+        "image/svg+xml"
+    } else {
+        "application/octet-stream"
+    };
+    match result {
+        Ok(body) => ([(header::CONTENT_TYPE, content_type)], body).into_response(),
+        Err(e) => {
+            warn!(error = %format!("{:#}", e), "CPU profile failed");
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+        }
+    }
+}
+
+fn collect_cpu_profile(duration: Duration, flamegraph: bool) -> Result<Vec<u8>> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(PROFILE_FREQUENCY_HZ)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .context("Failed to start CPU profiler")?;
+    std::thread::sleep(duration);
+    let report = guard
+        .report()
+        .build()
+        .context("Failed to build CPU profile")?;
+
+    let mut body = Vec::new();
+    if flamegraph {
+        report
+            .flamegraph(&mut body)
+            .context("Failed to render flamegraph")?;
+    } else {
+        report
+            .pprof()
+            .context("Failed to build pprof profile")?
+            .encode(&mut body)
+            .context("Failed to encode pprof profile")?;
+    }
+    Ok(body)
+}
+
+/// Heap profile endpoint
+async fn heap_profile() -> Response {
+    let Some(ctl) = jemalloc_pprof::PROF_CTL.as_ref() else {
+        return (
+            StatusCode::NOT_IMPLEMENTED,
+            "Heap profiling is not compiled in",
+        )
+            .into_response();
+    };
+
+    let mut ctl = ctl.lock().await;
+    if !ctl.activated() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Heap profiling is not active",
+        )
+            .into_response();
+    }
+    match ctl.dump_pprof() {
+        Ok(body) => ([(header::CONTENT_TYPE, "application/octet-stream")], body).into_response(),
+        Err(e) => {
+            warn!(error = %format!("{:#}", e), "Heap profile failed");
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+        }
+    }
+}
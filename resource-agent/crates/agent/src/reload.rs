@@ -0,0 +1,276 @@
+//! Runtime reload of the agent configuration file
+//!
+//! The config file is re-read when it changes on disk (including the
+//! symlink swap Kubernetes does when a mounted ConfigMap is updated) or when
+//! the agent receives SIGHUP. Only settings that are safe to change while
+//! running are re-applied; everything else still needs a restart.
+
+use crate::config::AgentConfig;
+use agent_lib::anomaly::{DetectorKind, PipelineConfig};
+use agent_lib::observability::AgentMetrics;
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, mpsc, watch};
+use tracing::{debug, info, warn};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Quiet period after a file event before reloading, to coalesce bursts
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Handle for swapping the log filter at runtime
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Settings that can change without restarting the agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReloadableSettings {
+    pub collection_interval: Duration,
+    pub anomaly: PipelineConfig,
+    pub api_endpoint: String,
+    pub log_level: String,
+}
+
+/// Sections of the config file that hold reloadable settings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FileConfig {
+    collection: CollectionSection,
+    anomaly: AnomalySection,
+    api: ApiSection,
+    logging: LoggingSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CollectionSection {
+    interval_seconds: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnomalySection {
+    spike_threshold_sigma: Option<f64>,
+    leak_slope_threshold: Option<f64>,
+    /// Overrides of the default detectors, by kind
+    detectors: Vec<DetectorSection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectorSection {
+    kind: DetectorKind,
+    enabled: Option<bool>,
+    threshold: Option<f64>,
+    window_seconds: Option<u64>,
+    windows_seconds: Option<Vec<u64>>,
+    #[serde(default)]
+    namespace_thresholds: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ApiSection {
+    endpoint: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LoggingSection {
+    level: Option<String>,
+}
+
+impl ReloadableSettings {
+    /// Settings from the startup configuration alone
+    pub fn from_config(config: &AgentConfig) -> Self {
+        Self {
+            collection_interval: Duration::from_secs(config.collection_interval_secs),
+            anomaly: PipelineConfig::default(),
+            api_endpoint: config.api_endpoint.clone(),
+            log_level: "info".to_string(),
+        }
+    }
+
+    /// Read the config file, falling back to `base` for unset values
+    pub fn load(path: &Path, base: &AgentConfig) -> Result<Self> {
+        let file: FileConfig = config::Config::builder()
+            .add_source(config::File::from(path).format(config::FileFormat::Yaml))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .with_context(|| format!("Failed to load config file {:?}", path))?;
+
+        let defaults = Self::from_config(base);
+        let mut anomaly = defaults.anomaly;
+        if let Some(threshold) = file.anomaly.spike_threshold_sigma {
+            anomaly.set_threshold(DetectorKind::CpuSpike, threshold);
+        }
+        if let Some(threshold) = file.anomaly.leak_slope_threshold {
+            anomaly.set_threshold(DetectorKind::MemoryLeak, threshold);
+        }
+        for section in file.anomaly.detectors {
+            let detector = anomaly.detector_mut(section.kind);
+            detector.enabled = section.enabled.unwrap_or(true);
+            if let Some(threshold) = section.threshold {
+                detector.threshold = threshold;
+            }
+            // A single window replaces the default set unless one is given too
+            if let Some(window) = section.window_seconds {
+                detector.window_secs = window;
+                detector.windows_secs.clear();
+            }
+            if let Some(windows) = section.windows_seconds {
+                detector.windows_secs = windows;
+            }
+            detector
+                .namespace_thresholds
+                .extend(section.namespace_thresholds);
+        }
+
+        let settings = Self {
+            collection_interval: file
+                .collection
+                .interval_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.collection_interval),
+            anomaly,
+            api_endpoint: file.api.endpoint.unwrap_or(defaults.api_endpoint),
+            log_level: file.logging.level.unwrap_or(defaults.log_level),
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.collection_interval.is_zero() {
+            anyhow::bail!("collection.interval_seconds must be positive");
+        }
+        self.anomaly
+            .validate()
+            .context("Invalid anomaly settings")?;
+        if self.api_endpoint.is_empty() {
+            anyhow::bail!("api.endpoint must not be empty");
+        }
+        EnvFilter::try_new(&self.log_level)
+            .with_context(|| format!("Invalid logging.level {:?}", self.log_level))?;
+        Ok(())
+    }
+}
+
+/// Watches the config file and re-applies changed settings
+pub struct ConfigReloader {
+    path: PathBuf,
+    base: AgentConfig,
+    settings: watch::Sender<ReloadableSettings>,
+    log_filter: LogFilterHandle,
+    metrics: AgentMetrics,
+    generation: u64,
+}
+
+impl ConfigReloader {
+    /// Create a reloader starting from already applied `settings`
+    pub fn new(
+        path: PathBuf,
+        base: AgentConfig,
+        settings: ReloadableSettings,
+        log_filter: LogFilterHandle,
+        metrics: AgentMetrics,
+    ) -> Self {
+        let (settings, _) = watch::channel(settings);
+        metrics.set_config_generation(1);
+        Self {
+            path,
+            base,
+            settings,
+            log_filter,
+            metrics,
+            generation: 1,
+        }
+    }
+
+    /// Receive the settings whenever a reload changes them
+    pub fn subscribe(&self) -> watch::Receiver<ReloadableSettings> {
+        self.settings.subscribe()
+    }
+
+    /// Reload on file changes and SIGHUP until shutdown
+    pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                if res.is_ok() {
+                    let _ = tx.send(());
+                }
+            },
+            notify::Config::default(),
+        )
+        .context("Failed to create config file watcher")?;
+
+        // Watch the directory: ConfigMap updates replace a symlink rather than
+        // writing to the file itself
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+        let mut hangup = signal(SignalKind::hangup()).context("Failed to listen for SIGHUP")?;
+        info!(path = %self.path.display(), "Watching agent config for changes");
+
+        loop {
+            tokio::select! {
+                Some(()) = rx.recv() => {
+                    tokio::time::sleep(RELOAD_DEBOUNCE).await;
+                    while rx.try_recv().is_ok() {}
+                    self.reload("file changed");
+                }
+                _ = hangup.recv() => self.reload("SIGHUP"),
+                _ = shutdown.recv() => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-read the config file and apply what changed
+    fn reload(&mut self, trigger: &str) {
+        let updated = match ReloadableSettings::load(&self.path, &self.base) {
+            Ok(updated) => updated,
+            Err(e) => {
+                warn!(
+                    trigger = trigger,
+                    error = %format!("{:#}", e),
+                    "Config reload failed, keeping current settings"
+                );
+                return;
+            }
+        };
+
+        let current = self.settings.borrow().clone();
+        if updated == current {
+            debug!(trigger = trigger, "Config unchanged");
+            return;
+        }
+
+        if updated.log_level != current.log_level {
+            match EnvFilter::try_new(&updated.log_level)
+                .map_err(anyhow::Error::from)
+                .and_then(|filter| Ok(self.log_filter.reload(filter)?))
+            {
+                Ok(()) => info!(level = %updated.log_level, "Log level updated"),
+                Err(e) => warn!(error = %e, "Failed to update log level"),
+            }
+        }
+
+        self.generation += 1;
+        self.metrics.set_config_generation(self.generation);
+        info!(
+            trigger = trigger,
+            generation = self.generation,
+            collection_interval_secs = updated.collection_interval.as_secs(),
+            anomaly_detectors = ?updated.anomaly.enabled(),
+            api_endpoint = %updated.api_endpoint,
+            "Config reloaded"
+        );
+        self.settings.send_replace(updated);
+    }
+}
@@ -0,0 +1,82 @@
+//! Simulation (dry-run) mode
+//!
+//! With `--simulate` the agent collects, predicts and detects anomalies on
+//! its node, but nothing is synced to the API and no alert is sent. The
+//! recommendations and anomalies it would have produced are written to a
+//! local report instead, for evaluating the agent before trusting it.
+
+use crate::config::{AgentConfig, AgentMode};
+use agent_lib::collector::{
+    create_collector, detect_cgroup_version, CgroupVersion, ContainerRegistry, Reconciler,
+};
+use agent_lib::observability::AgentMetrics;
+use agent_lib::predictor::PredictionConfig;
+use agent_lib::runtime::{AgentRuntime, AgentRuntimeBuilder};
+use agent_lib::simulation::{SimulationRecorder, DEFAULT_REPORT_INTERVAL};
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::info;
+
+/// Root of the cgroup hierarchy containers are collected from
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Interval between scans for started and stopped containers
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Start collection, prediction and anomaly detection with a report
+/// recorder in place of sync and alerting
+///
+/// `runtime` carries what the runtime shares with the rest of the agent;
+/// the collector and intervals are added here from `config`. The report is
+/// written every minute and once more on shutdown.
+pub async fn start(
+    config: &AgentConfig,
+    runtime: AgentRuntimeBuilder,
+    metrics: AgentMetrics,
+    shutdown: &broadcast::Sender<()>,
+) -> Result<AgentRuntime> {
+    let cgroup_root = Path::new(CGROUP_ROOT);
+    let collector = create_collector(cgroup_root).await?;
+    let is_v2 = detect_cgroup_version(cgroup_root).await != CgroupVersion::V1;
+
+    let registry = Arc::new(ContainerRegistry::new(config.node_name.clone()));
+    let mut reconciler = Reconciler::new(cgroup_root, is_v2)
+        .with_interval(DISCOVERY_INTERVAL)
+        .with_metrics(metrics.clone());
+    if config.mode == AgentMode::Standalone {
+        reconciler = reconciler.with_runtime(config.standalone_config());
+    }
+    tokio::spawn(reconciler.run(registry.clone(), shutdown.subscribe()));
+
+    let mut runtime = runtime
+        .node_name(config.node_name.clone())
+        .collector(collector)
+        .registry(registry)
+        .collection_interval(Duration::from_secs(config.collection_interval_secs.max(1)))
+        .prediction_config(PredictionConfig {
+            prediction_interval: Duration::from_secs(config.prediction_interval_secs.max(1)),
+            stale_after: Duration::from_secs(config.prediction_stale_after_secs.max(1)),
+            anomaly_gate: config.anomaly_gate,
+            ..Default::default()
+        })
+        .metrics(metrics)
+        .build()?;
+
+    let recorder = SimulationRecorder::new(&config.node_name, config.simulation_report_path());
+    info!(
+        report = %recorder.path().display(),
+        "Running in simulation mode: nothing is synced and no alerts are sent"
+    );
+    tokio::spawn(recorder.run(
+        runtime.subscribe_predictions(),
+        runtime.subscribe_anomalies(),
+        DEFAULT_REPORT_INTERVAL,
+        shutdown.subscribe(),
+    ));
+
+    runtime.start().await?;
+    Ok(runtime)
+}
@@ -0,0 +1,196 @@
+//! Watchdog for the agent's long-running tasks
+//!
+//! Each supervised task reports heartbeats through its component in the
+//! `HealthRegistry`. A task that exits or stops heartbeating is aborted and
+//! started again; if a task keeps getting stuck, the agent is marked unready
+//! and unhealthy so Kubernetes restarts the pod.
+
+use agent_lib::health::{components, HealthRegistry};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Instant};
+use tracing::{error, info, warn};
+
+/// Default time between task checks
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Default restarts tolerated within the restart window
+const DEFAULT_MAX_RESTARTS: usize = 3;
+
+/// Default window over which restarts are counted
+const DEFAULT_RESTART_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+type TaskFactory = Box<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Watchdog configuration
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    /// Time between task checks
+    pub check_interval: Duration,
+    /// Restarts of one task tolerated within `restart_window` before escalating
+    pub max_restarts: usize,
+    pub restart_window: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            restart_window: DEFAULT_RESTART_WINDOW,
+        }
+    }
+}
+
+/// A task the watchdog can restart
+struct SupervisedTask {
+    /// Health component the task heartbeats through
+    component: &'static str,
+    /// Time without progress after which the task counts as stuck
+    stall_after: Duration,
+    factory: TaskFactory,
+    handle: JoinHandle<()>,
+    started_at: Instant,
+    restarts: VecDeque<Instant>,
+}
+
+/// Restarts stuck tasks and escalates repeated failures
+pub struct Supervisor {
+    registry: HealthRegistry,
+    config: SupervisorConfig,
+    tasks: Vec<SupervisedTask>,
+    escalated: bool,
+}
+
+impl Supervisor {
+    pub fn new(registry: HealthRegistry, config: SupervisorConfig) -> Self {
+        Self {
+            registry,
+            config,
+            tasks: Vec::new(),
+            escalated: false,
+        }
+    }
+
+    /// Spawn a task and restart it whenever it exits or stops heartbeating
+    ///
+    /// `factory` must build a fresh task each time, reporting through the
+    /// reporter registered for `component`.
+    #[allow(dead_code)]
+    pub fn spawn<F, Fut>(&mut self, component: &'static str, stall_after: Duration, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let factory: TaskFactory = Box::new(move || Box::pin(factory()));
+        let handle = tokio::spawn(factory());
+        self.tasks.push(SupervisedTask {
+            component,
+            stall_after,
+            factory,
+            handle,
+            started_at: Instant::now(),
+            restarts: VecDeque::new(),
+        });
+    }
+
+    /// Check tasks until shutdown, then stop them
+    pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
+        self.registry.register(components::WATCHDOG).await;
+        info!(
+            tasks = self.tasks.len(),
+            check_interval_secs = self.config.check_interval.as_secs(),
+            "Starting task watchdog"
+        );
+
+        let mut ticker = interval(self.config.check_interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.check().await,
+                _ = shutdown.recv() => break,
+            }
+        }
+
+        for task in &self.tasks {
+            task.handle.abort();
+        }
+    }
+
+    /// Restart tasks that exited or stalled
+    async fn check(&mut self) {
+        for index in 0..self.tasks.len() {
+            let Some(reason) = self.stall_reason(&self.tasks[index]).await else {
+                continue;
+            };
+
+            let component = self.tasks[index].component;
+            let restarts = self.restart(index);
+            warn!(
+                component = component,
+                reason = %reason,
+                restarts = restarts,
+                "Restarted stuck task"
+            );
+
+            if restarts > self.config.max_restarts && !self.escalated {
+                self.escalate(component, restarts).await;
+            }
+        }
+    }
+
+    /// Why a task needs restarting, if it does
+    async fn stall_reason(&self, task: &SupervisedTask) -> Option<String> {
+        if task.handle.is_finished() {
+            return Some("task exited".to_string());
+        }
+
+        // A freshly restarted task gets a full stall period to report in
+        let since_start = task.started_at.elapsed();
+        let idle = match self.registry.since_heartbeat(task.component).await {
+            Some(since_heartbeat) => since_heartbeat.min(since_start),
+            None => since_start,
+        };
+        (idle > task.stall_after).then(|| format!("no heartbeat for {}s", idle.as_secs()))
+    }
+
+    /// Abort and respawn a task, returning its restarts within the window
+    fn restart(&mut self, index: usize) -> usize {
+        let window = self.config.restart_window;
+        let task = &mut self.tasks[index];
+        task.handle.abort();
+        task.handle = tokio::spawn((task.factory)());
+
+        let now = Instant::now();
+        task.started_at = now;
+        task.restarts.push_back(now);
+        while task
+            .restarts
+            .front()
+            .is_some_and(|&at| now.duration_since(at) > window)
+        {
+            task.restarts.pop_front();
+        }
+        task.restarts.len()
+    }
+
+    /// Give up on in-process recovery and let Kubernetes restart the agent
+    async fn escalate(&mut self, component: &str, restarts: usize) {
+        self.escalated = true;
+        let message = format!(
+            "{} restarted {} times within {}s",
+            component,
+            restarts,
+            self.config.restart_window.as_secs()
+        );
+        error!(component = component, restarts = restarts, "{}", message);
+
+        self.registry
+            .set_unhealthy(components::WATCHDOG, message)
+            .await;
+        self.registry.set_ready(false).await;
+    }
+}
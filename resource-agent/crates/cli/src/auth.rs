@@ -0,0 +1,550 @@
+//! Authentication for the Recommendation API
+//!
+//! - Static bearer token
+//! - OIDC device authorization grant (RFC 8628), with tokens cached under
+//!   `~/.cache/crp/oidc` and refreshed before they expire
+//! - Client certificate (mTLS) and custom CA, configured on the HTTP client
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::error::{CliError, ErrorKind};
+
+/// Scopes requested when none are configured; `offline_access` yields a refresh token
+pub const DEFAULT_OIDC_SCOPES: &str = "openid offline_access";
+
+/// Refresh tokens this long before they expire
+const EXPIRY_MARGIN_SECS: i64 = 60;
+
+/// Poll interval when the device authorization response doesn't set one
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// How the CLI authenticates to the API
+#[derive(Debug, Clone, Default)]
+pub struct AuthOptions {
+    /// Static bearer token, preferred over OIDC when both are set
+    pub token: Option<String>,
+    /// OIDC provider for the device flow
+    pub oidc: Option<OidcConfig>,
+    /// PEM client certificate for mTLS
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key for the client certificate
+    pub client_key: Option<PathBuf>,
+    /// PEM CA bundle to trust in addition to the system roots
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl AuthOptions {
+    /// Fill unset options from `fallback`
+    pub fn or(self, fallback: AuthOptions) -> AuthOptions {
+        AuthOptions {
+            token: self.token.or(fallback.token),
+            oidc: self.oidc.or(fallback.oidc),
+            client_cert: self.client_cert.or(fallback.client_cert),
+            client_key: self.client_key.or(fallback.client_key),
+            ca_cert: self.ca_cert.or(fallback.ca_cert),
+        }
+    }
+
+    /// Configure client certificate and CA on an HTTP client builder
+    pub fn apply_tls(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let mut pem = std::fs::read(cert).with_context(|| {
+                    format!("Failed to read client certificate {}", cert.display())
+                })?;
+                pem.push(b'\n');
+                pem.extend(
+                    std::fs::read(key)
+                        .with_context(|| format!("Failed to read client key {}", key.display()))?,
+                );
+                let identity = reqwest::Identity::from_pem(&pem)
+                    .context("Invalid client certificate or key")?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => bail!("--client-cert and --client-key must be given together"),
+        }
+
+        if let Some(ca) = &self.ca_cert {
+            let pem = std::fs::read(ca)
+                .with_context(|| format!("Failed to read CA certificate {}", ca.display()))?;
+            let certificate =
+                reqwest::Certificate::from_pem(&pem).context("Invalid CA certificate")?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        Ok(builder)
+    }
+
+    /// Credentials to attach to API requests
+    pub fn credentials(&self) -> Result<Credentials> {
+        Ok(match (&self.token, &self.oidc) {
+            (Some(token), _) => Credentials::Bearer(token.clone()),
+            (None, Some(oidc)) => Credentials::Oidc(Arc::new(OidcSession::new(oidc.clone())?)),
+            (None, None) => Credentials::None,
+        })
+    }
+}
+
+/// Credentials attached to each API request
+#[derive(Clone, Default)]
+pub enum Credentials {
+    #[default]
+    None,
+    Bearer(String),
+    Oidc(Arc<OidcSession>),
+}
+
+impl Credentials {
+    /// Bearer token for the next request, refreshing OIDC tokens as needed
+    pub async fn bearer_token(&self) -> Result<Option<String>> {
+        match self {
+            Credentials::None => Ok(None),
+            Credentials::Bearer(token) => Ok(Some(token.clone())),
+            Credentials::Oidc(session) => session.access_token().await.map(Some),
+        }
+    }
+}
+
+/// OIDC provider and public client used for the device flow
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub scopes: String,
+}
+
+/// Tokens as cached on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedToken {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    /// Unix seconds
+    expires_at: i64,
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        self.expires_at - EXPIRY_MARGIN_SECS > chrono::Utc::now().timestamp()
+    }
+}
+
+/// Endpoints from the provider's discovery document
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    device_authorization_endpoint: Option<String>,
+    token_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default)]
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// OIDC tokens for one provider and client, loaded lazily from the cache
+pub struct OidcSession {
+    config: OidcConfig,
+    http: reqwest::Client,
+    cached: Mutex<Option<CachedToken>>,
+    /// `~/.cache/crp/oidc`, if the platform has a cache directory
+    cache_dir: Option<PathBuf>,
+}
+
+impl OidcSession {
+    pub fn new(config: OidcConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self {
+            config,
+            http,
+            cached: Mutex::new(None),
+            cache_dir: dirs_next::cache_dir().map(|dir| dir.join("crp").join("oidc")),
+        })
+    }
+
+    /// Cache tokens under `dir` instead of the user's cache directory
+    #[cfg(test)]
+    fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
+    }
+
+    /// A valid access token from memory, the disk cache, a refresh, or a
+    /// device login when running in a terminal
+    pub async fn access_token(&self) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+        if cached.is_none() {
+            *cached = self.read_cache();
+        }
+        if let Some(token) = cached.as_ref().filter(|t| t.is_fresh()) {
+            return Ok(token.access_token.clone());
+        }
+
+        let refresh_token = cached.as_ref().and_then(|t| t.refresh_token.clone());
+        let mut refresh_error = None;
+        if let Some(refresh_token) = refresh_token {
+            match self.refresh(&refresh_token).await {
+                Ok(token) => return Ok(self.store(&mut cached, token)?.access_token.clone()),
+                Err(e) => refresh_error = Some(e),
+            }
+        }
+
+        if !std::io::stdin().is_terminal() {
+            let reason = refresh_error
+                .map(|e| format!(" ({:#})", e))
+                .unwrap_or_default();
+            return Err(CliError::new(
+                ErrorKind::Auth,
+                format!("OIDC login required{}; run: crp login", reason),
+            )
+            .into());
+        }
+        let token = self.device_login().await?;
+        Ok(self.store(&mut cached, token)?.access_token.clone())
+    }
+
+    /// Run the device flow and cache the resulting tokens
+    pub async fn login(&self) -> Result<()> {
+        let token = self.device_login().await?;
+        let mut cached = self.cached.lock().await;
+        self.store(&mut cached, token)?;
+        Ok(())
+    }
+
+    /// Remove cached tokens; returns whether there were any
+    pub fn logout(&self) -> Result<bool> {
+        let path = self.cache_path()?;
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+        }
+    }
+
+    async fn discover(&self) -> Result<Discovery> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer.trim_end_matches('/')
+        );
+        self.http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to fetch OIDC discovery document {}", url))?
+            .json()
+            .await
+            .context("Invalid OIDC discovery document")
+    }
+
+    async fn device_login(&self) -> Result<CachedToken> {
+        let discovery = self.discover().await?;
+        let endpoint = discovery
+            .device_authorization_endpoint
+            .context("OIDC provider does not support the device authorization grant")?;
+
+        let authorization: DeviceAuthorization = self
+            .http
+            .post(&endpoint)
+            .form(&[
+                ("client_id", self.config.client_id.as_str()),
+                ("scope", self.config.scopes.as_str()),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .context("Device authorization request failed")?
+            .json()
+            .await
+            .context("Invalid device authorization response")?;
+
+        // Instructions go to stderr so command output on stdout stays clean
+        eprintln!(
+            "{} Open {} and enter code {}",
+            "→".cyan().bold(),
+            authorization
+                .verification_uri_complete
+                .as_deref()
+                .unwrap_or(&authorization.verification_uri),
+            authorization.user_code.bold()
+        );
+
+        let mut interval =
+            Duration::from_secs(authorization.interval.unwrap_or(DEFAULT_POLL_INTERVAL_SECS));
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(authorization.expires_in);
+        loop {
+            tokio::time::sleep(interval).await;
+            if tokio::time::Instant::now() >= deadline {
+                bail!("Device code expired before login completed");
+            }
+
+            let response = self
+                .http
+                .post(&discovery.token_endpoint)
+                .form(&[
+                    ("grant_type", DEVICE_CODE_GRANT),
+                    ("device_code", authorization.device_code.as_str()),
+                    ("client_id", self.config.client_id.as_str()),
+                ])
+                .send()
+                .await
+                .context("Token request failed")?;
+            if response.status().is_success() {
+                let token: TokenResponse =
+                    response.json().await.context("Invalid token response")?;
+                return Ok(self.cached_token(token, None));
+            }
+
+            let error: TokenError = response.json().await.context("Invalid token error")?;
+            match error.error.as_str() {
+                "authorization_pending" => {}
+                "slow_down" => interval += Duration::from_secs(5),
+                _ => bail!(
+                    "Login failed: {}",
+                    error.error_description.unwrap_or(error.error)
+                ),
+            }
+        }
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<CachedToken> {
+        let discovery = self.discover().await?;
+        let token: TokenResponse = self
+            .http
+            .post(&discovery.token_endpoint)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", self.config.client_id.as_str()),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .context("Token refresh failed")?
+            .json()
+            .await
+            .context("Invalid token response")?;
+        Ok(self.cached_token(token, Some(refresh_token)))
+    }
+
+    /// Providers may omit the refresh token on refresh; keep the old one then
+    fn cached_token(&self, token: TokenResponse, previous_refresh: Option<&str>) -> CachedToken {
+        CachedToken {
+            access_token: token.access_token,
+            refresh_token: token
+                .refresh_token
+                .or_else(|| previous_refresh.map(str::to_string)),
+            expires_at: chrono::Utc::now().timestamp() + token.expires_in.unwrap_or(3600),
+        }
+    }
+
+    fn store<'a>(
+        &self,
+        cached: &'a mut Option<CachedToken>,
+        token: CachedToken,
+    ) -> Result<&'a CachedToken> {
+        self.write_cache(&token)?;
+        Ok(cached.insert(token))
+    }
+
+    /// Cache file for this issuer and client
+    fn cache_path(&self) -> Result<PathBuf> {
+        let dir = self
+            .cache_dir
+            .as_ref()
+            .context("Could not determine cache directory")?;
+        let key: String = format!("{}_{}", self.config.issuer, self.config.client_id)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Ok(dir.join(format!("{}.json", key)))
+    }
+
+    fn read_cache(&self) -> Option<CachedToken> {
+        let content = std::fs::read_to_string(self.cache_path().ok()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_cache(&self, token: &CachedToken) -> Result<()> {
+        let path = self.cache_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create token cache directory")?;
+        }
+        std::fs::write(&path, serde_json::to_string(token)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+                .context("Failed to restrict token cache permissions")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server, ServerGuard};
+
+    fn session(server: &ServerGuard, cache: &tempfile::TempDir) -> OidcSession {
+        OidcSession::new(OidcConfig {
+            issuer: server.url(),
+            client_id: "crp".to_string(),
+            scopes: DEFAULT_OIDC_SCOPES.to_string(),
+        })
+        .unwrap()
+        .with_cache_dir(cache.path().to_path_buf())
+    }
+
+    fn token(access_token: &str, refresh_token: Option<&str>, expires_in: i64) -> CachedToken {
+        CachedToken {
+            access_token: access_token.to_string(),
+            refresh_token: refresh_token.map(str::to_string),
+            expires_at: chrono::Utc::now().timestamp() + expires_in,
+        }
+    }
+
+    /// Discovery document without a device endpoint, so no test can start a login
+    async fn discovery(server: &mut ServerGuard) -> mockito::Mock {
+        let body = serde_json::json!({ "token_endpoint": format!("{}/token", server.url()) });
+        server
+            .mock("GET", "/.well-known/openid-configuration")
+            .with_body(body.to_string())
+            .create_async()
+            .await
+    }
+
+    fn refresh_request(refresh_token: &str) -> Matcher {
+        Matcher::AllOf(vec![
+            Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+            Matcher::UrlEncoded("refresh_token".into(), refresh_token.into()),
+            Matcher::UrlEncoded("client_id".into(), "crp".into()),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_cached_token_used_while_fresh() {
+        let mut server = Server::new_async().await;
+        let discovery = discovery(&mut server).await.expect(0);
+        let cache = tempfile::tempdir().unwrap();
+        let session = session(&server, &cache);
+        session
+            .write_cache(&token("cached", Some("refresh"), 3600))
+            .unwrap();
+
+        assert_eq!(session.access_token().await.unwrap(), "cached");
+        // Later calls are served from memory even if the file goes away
+        assert!(session.logout().unwrap());
+        assert_eq!(session.access_token().await.unwrap(), "cached");
+        discovery.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_refreshed() {
+        let mut server = Server::new_async().await;
+        discovery(&mut server).await;
+        let refresh = server
+            .mock("POST", "/token")
+            .match_body(refresh_request("refresh"))
+            .with_body(r#"{"access_token":"renewed","expires_in":1800}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let cache = tempfile::tempdir().unwrap();
+        let session = session(&server, &cache);
+        // Within the expiry margin counts as expired
+        session
+            .write_cache(&token("stale", Some("refresh"), EXPIRY_MARGIN_SECS / 2))
+            .unwrap();
+
+        assert_eq!(session.access_token().await.unwrap(), "renewed");
+        assert_eq!(session.access_token().await.unwrap(), "renewed");
+        refresh.assert_async().await;
+
+        // The provider sent no new refresh token, so the old one is kept
+        let stored = session.read_cache().unwrap();
+        assert_eq!(stored.access_token, "renewed");
+        assert_eq!(stored.refresh_token.as_deref(), Some("refresh"));
+        assert!(stored.is_fresh());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_failure() {
+        let mut server = Server::new_async().await;
+        discovery(&mut server).await;
+        let refresh = server
+            .mock("POST", "/token")
+            .match_body(refresh_request("revoked"))
+            .with_status(400)
+            .with_body(r#"{"error":"invalid_grant"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let cache = tempfile::tempdir().unwrap();
+        let session = session(&server, &cache);
+        session
+            .write_cache(&token("expired", Some("revoked"), -60))
+            .unwrap();
+
+        let err = session.access_token().await.unwrap_err();
+        if !std::io::stdin().is_terminal() {
+            let message = format!("{:#}", err);
+            assert!(message.contains("OIDC login required"), "{}", message);
+            assert!(message.contains("Token refresh failed"), "{}", message);
+        }
+        refresh.assert_async().await;
+
+        // The expired token stays cached for a later login to replace
+        let stored = session.read_cache().unwrap();
+        assert_eq!(stored.access_token, "expired");
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_without_refresh_token() {
+        let mut server = Server::new_async().await;
+        let refresh = server.mock("POST", "/token").expect(0).create_async().await;
+        let cache = tempfile::tempdir().unwrap();
+        let session = session(&server, &cache);
+        session.write_cache(&token("expired", None, -60)).unwrap();
+
+        assert!(session.access_token().await.is_err());
+        refresh.assert_async().await;
+    }
+}
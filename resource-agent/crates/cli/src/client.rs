@@ -1,36 +1,166 @@
 //! API client for communicating with the Recommendation API
 
+use agent_lib::anomaly::PipelineConfig;
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
 use url::Url;
 
+use crate::auth::{AuthOptions, Credentials};
+
+/// Per-request timeout used unless overridden with `with_timeout`
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Retries of a failed GET used unless overridden with `with_retries`
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// Delay before the first retry, doubled for each further attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound on a single retry delay, including server `Retry-After`
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Recommendations requested per page when listing
+const PAGE_SIZE: usize = 500;
+
 /// API client for the Recommendation API
+#[derive(Clone)]
 pub struct ApiClient {
     client: Client,
     base_url: Url,
+    credentials: Credentials,
+    timeout: Duration,
+    retries: u32,
 }
 
 impl ApiClient {
-    /// Create a new API client
+    /// Create a new API client without authentication
     pub fn new(base_url: &str) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+        Self::with_auth(base_url, &AuthOptions::default())
+    }
+
+    /// Create a new API client authenticating with `auth`
+    pub fn with_auth(base_url: &str, auth: &AuthOptions) -> Result<Self> {
+        let client = auth
+            .apply_tls(Client::builder())?
             .build()
             .context("Failed to create HTTP client")?;
 
         let base_url = Url::parse(base_url).context("Invalid API URL")?;
 
-        Ok(Self { client, base_url })
+        Ok(Self {
+            client,
+            base_url,
+            credentials: auth.credentials()?,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            retries: DEFAULT_RETRIES,
+        })
+    }
+
+    /// Fail requests that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry failed GETs up to `retries` times
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Attach credentials, if any, to a request
+    async fn authorize(&self, request: RequestBuilder) -> Result<RequestBuilder> {
+        Ok(match self.credentials.bearer_token().await? {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
     }
 
     /// Make a GET request
     pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send_get(path)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse response")
+    }
+
+    /// Make a GET request for a plain text body, e.g. Prometheus metrics
+    pub async fn get_text(&self, path: &str) -> Result<String> {
+        self.send_get(path)
+            .await?
+            .text()
+            .await
+            .context("Failed to read response")
+    }
+
+    /// Make a GET request for a binary body, e.g. a Parquet export
+    pub async fn get_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let body = self
+            .send_get(path)
+            .await?
+            .bytes()
+            .await
+            .context("Failed to read response")?;
+        Ok(body.to_vec())
+    }
+
+    /// Send a GET request and check its status
+    ///
+    /// Connection errors, timeouts and 429/502/503/504 responses are retried
+    /// with exponential backoff, honouring `Retry-After` when the server sends it.
+    async fn send_get(&self, path: &str) -> Result<Response> {
+        let url = self.base_url.join(path).context("Invalid path")?;
+
+        let mut attempt = 0;
+        let response = loop {
+            let result = self
+                .authorize(self.client.get(url.clone()))
+                .await?
+                .timeout(self.timeout)
+                .send()
+                .await;
+
+            let delay = match &result {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    Some(retry_after(response).unwrap_or_else(|| backoff(attempt)))
+                }
+                Err(e) if e.is_timeout() || e.is_connect() => Some(backoff(attempt)),
+                _ => None,
+            };
+            match delay {
+                Some(delay) if attempt < self.retries => {
+                    attempt += 1;
+                    tokio::time::sleep(delay.min(MAX_RETRY_BACKOFF)).await;
+                }
+                _ => break result,
+            }
+        };
+        let response = response
+            .with_context(|| format!("Failed to send request after {} attempt(s)", attempt + 1))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::Status { status, body }.into());
+        }
+
+        Ok(response)
+    }
+
+    /// Make a POST request with JSON body
+    ///
+    /// POSTs change state, so they are never retried.
+    pub async fn post<T: DeserializeOwned, B: Serialize>(&self, path: &str, body: &B) -> Result<T> {
         let url = self.base_url.join(path).context("Invalid path")?;
 
         let response = self
-            .client
-            .get(url)
+            .authorize(self.client.post(url))
+            .await?
+            .timeout(self.timeout)
+            .json(body)
             .send()
             .await
             .context("Failed to send request")?;
@@ -38,19 +168,20 @@ impl ApiClient {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("API error ({}): {}", status, body);
+            return Err(ApiError::Status { status, body }.into());
         }
 
         response.json().await.context("Failed to parse response")
     }
 
-    /// Make a POST request with JSON body
-    pub async fn post<T: DeserializeOwned, B: Serialize>(&self, path: &str, body: &B) -> Result<T> {
+    /// Make a PUT request
+    pub async fn put<T: DeserializeOwned, B: Serialize>(&self, path: &str, body: &B) -> Result<T> {
         let url = self.base_url.join(path).context("Invalid path")?;
 
         let response = self
-            .client
-            .post(url)
+            .authorize(self.client.put(url))
+            .await?
+            .timeout(self.timeout)
             .json(body)
             .send()
             .await
@@ -59,11 +190,104 @@ impl ApiClient {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("API error ({}): {}", status, body);
+            return Err(ApiError::Status { status, body }.into());
         }
 
         response.json().await.context("Failed to parse response")
     }
+
+    /// Make a DELETE request, ignoring any response body
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        let url = self.base_url.join(path).context("Invalid path")?;
+
+        let response = self
+            .authorize(self.client.delete(url))
+            .await?
+            .timeout(self.timeout)
+            .send()
+            .await
+            .context("Failed to send request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::Status { status, body }.into());
+        }
+
+        Ok(())
+    }
+
+    /// List all recommendations, optionally in one namespace, following pages
+    pub async fn list_recommendations(
+        &self,
+        namespace: Option<&str>,
+    ) -> Result<RecommendationList> {
+        let path = match namespace {
+            Some(ns) => format!("api/v1/recommendations/{}", ns),
+            None => "api/v1/recommendations".to_string(),
+        };
+
+        let mut list = RecommendationList {
+            recommendations: Vec::new(),
+            total: 0,
+            next_page_token: None,
+        };
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = url::form_urlencoded::Serializer::new(String::new());
+            query.append_pair("limit", &PAGE_SIZE.to_string());
+            if let Some(token) = &page_token {
+                query.append_pair("page_token", token);
+            }
+            let page: RecommendationList =
+                self.get(&format!("{}?{}", path, query.finish())).await?;
+
+            list.recommendations.extend(page.recommendations);
+            list.total = page.total;
+            match page.next_page_token {
+                Some(next) if page_token.as_ref() != Some(&next) => page_token = Some(next),
+                Some(next) => anyhow::bail!("API returned the same page token twice: {}", next),
+                None => break,
+            }
+        }
+
+        Ok(list)
+    }
+}
+
+/// Error response from the API
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("API error ({status}): {body}")]
+    Status { status: StatusCode, body: String },
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Delay before retry `attempt` (0-based)
+fn backoff(attempt: u32) -> Duration {
+    RETRY_BACKOFF.saturating_mul(2u32.saturating_pow(attempt))
+}
+
+/// Delay requested by a `Retry-After: <seconds>` header
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
 }
 
 // API response types
@@ -72,7 +296,11 @@ impl ApiClient {
 pub struct Recommendation {
     pub id: String,
     pub namespace: String,
+    /// Name of the owning workload
     pub deployment: String,
+    /// Kind of the owning workload, a Deployment when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_kind: Option<String>,
     pub cpu_request_millicores: u32,
     pub cpu_limit_millicores: u32,
     pub memory_request_bytes: u64,
@@ -88,6 +316,49 @@ pub struct Recommendation {
     pub current_resources: Option<ResourceSpec>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recommended_resources: Option<ResourceSpec>,
+    /// Set for workloads whose pods run several predicted containers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod_summary: Option<PodSummary>,
+    /// Cluster of workloads with a similar usage shape, when the agent
+    /// fingerprinted the workload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workload_cluster: Option<String>,
+}
+
+/// Pod-level summary of the recommendations for a pod's containers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodSummary {
+    /// Sum of the container requests
+    pub cpu_request_millicores: u32,
+    pub memory_request_bytes: u64,
+    /// Largest container limit
+    pub cpu_limit_millicores: u32,
+    pub memory_limit_bytes: u64,
+    /// Per-container recommendations, empty when the agent aggregates pods
+    #[serde(default)]
+    pub containers: Vec<ContainerRecommendation>,
+}
+
+/// Recommendation for one container of a pod summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerRecommendation {
+    pub name: String,
+    pub cpu_request_millicores: u32,
+    pub cpu_limit_millicores: u32,
+    pub memory_request_bytes: u64,
+    pub memory_limit_bytes: u64,
+}
+
+impl Recommendation {
+    /// Kind of the owning workload, lowercase
+    pub fn workload_kind(&self) -> &str {
+        self.owner_kind.as_deref().unwrap_or("deployment")
+    }
+
+    /// Owning workload as `kind/name`
+    pub fn owner(&self) -> String {
+        format!("{}/{}", self.workload_kind(), self.deployment)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +373,9 @@ pub struct ResourceSpec {
 pub struct RecommendationList {
     pub recommendations: Vec<Recommendation>,
     pub total: i32,
+    /// Set while more pages remain; `ApiClient::list_recommendations` follows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -134,6 +408,102 @@ pub struct ApproveResponse {
     pub approver: Option<String>,
 }
 
+/// Approval decision recorded for a recommendation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalHistory {
+    pub id: String,
+    pub recommendation_id: String,
+    /// approved, rejected or auto_approved
+    pub action: String,
+    pub approver: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+/// Rollback of an applied recommendation, automatic or manual
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackEvent {
+    pub id: String,
+    pub original_recommendation_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_recommendation_id: Option<String>,
+    /// oom_increase, throttle_increase or manual
+    pub trigger_reason: String,
+    #[serde(default)]
+    pub oom_kills_detected: i32,
+    #[serde(default)]
+    pub throttle_increase_percent: f64,
+    pub auto_triggered: bool,
+    pub created_at: String,
+}
+
+/// Guardrail policy that recommendations must satisfy before being applied
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Policy {
+    pub name: String,
+    /// Namespaces the policy applies to; empty means all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub namespaces: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_cpu_millicores: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_millicores: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_memory_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_namespaces: Vec<String>,
+    #[serde(default)]
+    pub require_approval: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    pub policy: String,
+    /// min_cpu, max_cpu, min_memory, max_memory, excluded_namespace or approval_required
+    pub rule: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyCheckResult {
+    pub recommendation_id: String,
+    pub namespace: String,
+    pub deployment: String,
+    pub allowed: bool,
+    pub policies_checked: usize,
+    #[serde(default)]
+    pub violations: Vec<PolicyViolation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_revision: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackResponse {
+    pub id: String,
+    pub status: String,
+    pub message: String,
+    /// Revision the workload was rolled back to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u32>,
+    /// Resources in place before the rollback
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_resources: Option<ResourceSpec>,
+    /// Resources restored by the rollback
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_resources: Option<ResourceSpec>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CostAnalysis {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -144,6 +514,9 @@ pub struct CostAnalysis {
     pub currency: String,
     pub deployment_count: i32,
     pub last_updated: String,
+    /// Pricing provider the CLI re-priced the analysis with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,6 +527,9 @@ pub struct SavingsReport {
     pub savings_by_month: Vec<MonthlySaving>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub savings_by_team: Option<Vec<TeamSaving>>,
+    /// Pricing provider the CLI re-priced the report with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -184,6 +560,32 @@ pub struct ModelList {
     pub total: i32,
 }
 
+/// Anomaly reported by an agent, as served by /api/v1/anomalies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anomaly {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub anomaly_type: String,
+    pub severity: String,
+    pub namespace: String,
+    pub deployment: String,
+    pub container: String,
+    pub detected_at: String,
+    #[serde(default)]
+    pub resolved_at: Option<String>,
+    pub status: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub leak_rate_bytes_per_second: Option<f64>,
+    /// Z-score of a CPU spike
+    #[serde(default)]
+    pub spike_magnitude: Option<f64>,
+    #[serde(default)]
+    pub projected_oom_at: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PredictionHistory {
     pub deployment: String,
@@ -212,6 +614,129 @@ pub struct AgentStatus {
     pub buffer_size_bytes: u64,
     pub collection_latency_ms: f64,
     pub prediction_latency_ms: f64,
+    /// Latest heartbeat health report from the agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heartbeat: Option<AgentHeartbeat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentHeartbeat {
+    pub received_at: String,
+    pub agent_version: String,
+    pub buffer_items: i64,
+    #[serde(default)]
+    pub last_collection_at: Option<String>,
+    pub collection_errors: i64,
+    pub prediction_errors: i64,
+    pub sync_errors: i64,
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub unhealthy_components: std::collections::HashMap<String, String>,
+}
+
+/// Samples served by an agent's /history endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentHistory {
+    pub from: i64,
+    pub to: i64,
+    pub samples: Vec<agent_lib::models::ContainerMetrics>,
+    pub total: usize,
+}
+
+/// Anomaly history served by an agent's /anomalies endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentAnomalies {
+    pub anomalies: Vec<AgentAnomaly>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentAnomaly {
+    pub container_id: String,
+    pub pod_name: String,
+    pub namespace: String,
+    pub anomaly_type: String,
+    pub severity: String,
+    pub message: String,
+    pub detected_at: i64,
+}
+
+/// Internal state snapshot served by an agent's /state endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentState {
+    pub node_name: String,
+    pub captured_at: i64,
+    #[serde(default)]
+    pub containers: Vec<AgentContainer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduler: Option<AgentSchedulerState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer: Option<AgentBufferState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming: Option<AgentStreamingState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_update: Option<AgentModelUpdateState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<AgentConnectionState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anomaly_pipeline: Option<PipelineConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentContainer {
+    pub container_id: String,
+    pub pod_name: String,
+    pub namespace: String,
+    #[serde(default)]
+    pub deployment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSchedulerState {
+    pub total_containers: usize,
+    pub containers_with_predictions: usize,
+    #[serde(default)]
+    pub stale_predictions: usize,
+    pub total_samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentBufferState {
+    pub entries: usize,
+    pub capacity: usize,
+    pub memory_bytes: u64,
+    #[serde(default)]
+    pub oldest_timestamp: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStreamingState {
+    pub batches_sent: u64,
+    pub failures: u64,
+    pub streams_opened: u64,
+    #[serde(default)]
+    pub last_sync_secs_ago: Option<u64>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub throttled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentModelUpdateState {
+    #[serde(default)]
+    pub current_version: Option<String>,
+    pub available_rollback_versions: usize,
+    #[serde(default)]
+    pub last_update_time: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentConnectionState {
+    pub connected: bool,
+    pub reconnect_attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    pub circuit_state: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -239,3 +764,192 @@ pub struct ErrorResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use serde_json::{json, Value};
+
+    fn api_status(err: &anyhow::Error) -> Option<StatusCode> {
+        match err.downcast_ref::<ApiError>()? {
+            ApiError::Status { status, .. } => Some(*status),
+        }
+    }
+
+    fn page(ids: &[&str], next_page_token: Option<&str>) -> String {
+        let recommendations: Vec<Value> = ids
+            .iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "namespace": "default",
+                    "deployment": id,
+                    "cpu_request_millicores": 100,
+                    "cpu_limit_millicores": 200,
+                    "memory_request_bytes": 1 << 20,
+                    "memory_limit_bytes": 2 << 20,
+                    "confidence": 0.9,
+                    "model_version": "v1",
+                    "status": "pending",
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "time_window": "7d",
+                })
+            })
+            .collect();
+        json!({
+            "recommendations": recommendations,
+            "total": 5,
+            "next_page_token": next_page_token,
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_until_success() {
+        let mut server = Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/health")
+            .with_status(503)
+            .with_header("retry-after", "0")
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("GET", "/health")
+            .with_body(r#"{"status":"ok"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(&server.url()).unwrap().with_retries(3);
+        let health: Value = client.get("health").await.unwrap();
+        assert_eq!(health["status"], "ok");
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_stop_at_limit() {
+        let mut server = Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/health")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(&server.url()).unwrap().with_retries(2);
+        let err = client.get::<Value>("health").await.unwrap_err();
+        assert_eq!(api_status(&err), Some(StatusCode::TOO_MANY_REQUESTS));
+        unavailable.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_internal_errors_not_retried() {
+        let mut server = Server::new_async().await;
+        let failing = server
+            .mock("GET", "/health")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(&server.url()).unwrap().with_retries(3);
+        let err = client.get::<Value>("health").await.unwrap_err();
+        assert_eq!(api_status(&err), Some(StatusCode::INTERNAL_SERVER_ERROR));
+        failing.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_writes_not_retried() {
+        let mut server = Server::new_async().await;
+        let mocks = [
+            server.mock("POST", "/items"),
+            server.mock("PUT", "/items/1"),
+            server.mock("DELETE", "/items/1"),
+        ];
+        let mut created = Vec::new();
+        for mock in mocks {
+            let mock = mock
+                .with_status(503)
+                .with_header("retry-after", "0")
+                .expect(1)
+                .create_async()
+                .await;
+            created.push(mock);
+        }
+
+        let client = ApiClient::new(&server.url()).unwrap().with_retries(3);
+        let body = json!({"name": "item"});
+        let err = client.post::<Value, _>("items", &body).await.unwrap_err();
+        assert_eq!(api_status(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+        let err = client.put::<Value, _>("items/1", &body).await.unwrap_err();
+        assert_eq!(api_status(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+        let err = client.delete("items/1").await.unwrap_err();
+        assert_eq!(api_status(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+        for mock in &created {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_follows_pages() {
+        let mut server = Server::new_async().await;
+        let limit = Matcher::UrlEncoded("limit".into(), PAGE_SIZE.to_string());
+        let first = server
+            .mock("GET", "/api/v1/recommendations/default")
+            .match_query(Matcher::Regex(format!("^limit={}$", PAGE_SIZE)))
+            .with_body(page(&["a", "b"], Some("page-2")))
+            .expect(1)
+            .create_async()
+            .await;
+        let second = server
+            .mock("GET", "/api/v1/recommendations/default")
+            .match_query(Matcher::AllOf(vec![
+                limit.clone(),
+                Matcher::UrlEncoded("page_token".into(), "page-2".into()),
+            ]))
+            .with_body(page(&["c", "d"], Some("page-3")))
+            .expect(1)
+            .create_async()
+            .await;
+        let last = server
+            .mock("GET", "/api/v1/recommendations/default")
+            .match_query(Matcher::AllOf(vec![
+                limit,
+                Matcher::UrlEncoded("page_token".into(), "page-3".into()),
+            ]))
+            .with_body(page(&["e"], None))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(&server.url()).unwrap();
+        let list = client.list_recommendations(Some("default")).await.unwrap();
+        let ids: Vec<&str> = list.recommendations.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+        assert_eq!(list.total, 5);
+        assert_eq!(list.next_page_token, None);
+        for mock in [first, second, last] {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_rejects_repeated_page_token() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/api/v1/recommendations")
+            .match_query(Matcher::Any)
+            .with_body(page(&["a"], Some("stuck")))
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(&server.url()).unwrap();
+        let err = client.list_recommendations(None).await.unwrap_err();
+        assert!(err.to_string().contains("same page token twice"), "{}", err);
+    }
+}
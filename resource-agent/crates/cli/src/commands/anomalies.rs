@@ -0,0 +1,174 @@
+//! Anomalies reported by agents: memory leaks, CPU spikes and OOM risk
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::time::Duration;
+use tabled::Tabled;
+
+use crate::client::{Anomaly, ApiClient};
+use crate::output::{
+    color_status, format_bytes, format_timestamp, parse_period, print_error, print_list,
+    print_warning, OutputFormat, Watcher,
+};
+
+/// Row for anomalies table
+#[derive(Tabled)]
+struct AnomalyRow {
+    #[tabled(rename = "Detected")]
+    detected_at: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Deployment")]
+    deployment: String,
+    #[tabled(rename = "Type")]
+    anomaly_type: String,
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Leak Rate")]
+    leak_rate: String,
+    #[tabled(rename = "Spike")]
+    spike: String,
+    #[tabled(rename = "Projected OOM")]
+    projected_oom: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+/// Filters for listing anomalies
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyFilters {
+    pub namespace: Option<String>,
+    pub severity: Option<String>,
+    /// Only anomalies detected within this window, e.g. `30m`, `6h`, `7d`
+    pub since: Option<String>,
+}
+
+/// List recent anomalies
+pub async fn get_anomalies(
+    client: &ApiClient,
+    filters: &AnomalyFilters,
+    format: &OutputFormat,
+) -> Result<()> {
+    let anomalies = fetch_anomalies(client, filters).await?;
+    print_anomalies(&anomalies, format)
+}
+
+/// Poll anomalies and redraw them until interrupted
+///
+/// Anomalies first seen since the previous poll are listed under the table.
+pub async fn watch_anomalies(
+    client: &ApiClient,
+    filters: &AnomalyFilters,
+    interval: Duration,
+    format: &OutputFormat,
+) -> Result<()> {
+    // Validate --since once up front rather than on every poll
+    anomalies_path(filters)?;
+
+    let mut watcher = Watcher::new(interval, format);
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut first_poll = true;
+    while watcher.next().await {
+        let anomalies = match fetch_anomalies(client, filters).await {
+            Ok(anomalies) => anomalies,
+            Err(e) => {
+                print_error(&format!("{:#}", e));
+                continue;
+            }
+        };
+        print_anomalies(&anomalies, format)?;
+
+        let new: Vec<&Anomaly> = anomalies
+            .iter()
+            .filter(|a| seen.insert(a.id.clone()))
+            .collect();
+        if !first_poll && !new.is_empty() {
+            if let OutputFormat::Table = format {
+                println!("\nNew since last poll:");
+                for anomaly in new {
+                    println!(
+                        "  {}/{}: {} ({})",
+                        anomaly.namespace,
+                        anomaly.deployment,
+                        anomaly.anomaly_type,
+                        color_status(&anomaly.severity)
+                    );
+                }
+            }
+        }
+        first_poll = false;
+    }
+
+    Ok(())
+}
+
+/// Fetch anomalies matching the filters, most recent first
+async fn fetch_anomalies(client: &ApiClient, filters: &AnomalyFilters) -> Result<Vec<Anomaly>> {
+    client.get(&anomalies_path(filters)?).await
+}
+
+/// API path with the filters as query parameters
+fn anomalies_path(filters: &AnomalyFilters) -> Result<String> {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(namespace) = &filters.namespace {
+        query.append_pair("namespace", namespace);
+    }
+    if let Some(severity) = &filters.severity {
+        query.append_pair("severity", &severity.to_lowercase());
+    }
+    if let Some(since) = &filters.since {
+        let start = chrono::Utc::now() - parse_period(since)?;
+        query.append_pair(
+            "startDate",
+            &start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        );
+    }
+
+    let query = query.finish();
+    if query.is_empty() {
+        Ok("api/v1/anomalies".to_string())
+    } else {
+        Ok(format!("api/v1/anomalies?{}", query))
+    }
+}
+
+fn print_anomalies(anomalies: &[Anomaly], format: &OutputFormat) -> Result<()> {
+    if let OutputFormat::Table = format {
+        if anomalies.is_empty() {
+            print_warning("No anomalies found");
+            return Ok(());
+        }
+    }
+
+    print_list(anomalies, format, |anomalies| {
+        anomalies
+            .iter()
+            .map(|a| AnomalyRow {
+                detected_at: format_timestamp(&a.detected_at),
+                namespace: a.namespace.clone(),
+                deployment: a.deployment.clone(),
+                anomaly_type: a.anomaly_type.clone(),
+                severity: color_status(&a.severity),
+                leak_rate: a
+                    .leak_rate_bytes_per_second
+                    .map(|rate| format!("{}/s", format_bytes(rate.max(0.0) as u64)))
+                    .unwrap_or_else(|| "-".to_string()),
+                spike: a
+                    .spike_magnitude
+                    .map(|z| format!("{:.1}σ", z))
+                    .unwrap_or_else(|| "-".to_string()),
+                projected_oom: a
+                    .projected_oom_at
+                    .as_deref()
+                    .map(format_timestamp)
+                    .unwrap_or_else(|| "-".to_string()),
+                status: color_status(&a.status),
+            })
+            .collect()
+    })?;
+    if let OutputFormat::Table = format {
+        println!("\nTotal: {} anomalies", anomalies.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,354 @@
+//! Bulk approve and apply across many recommendations
+//!
+//! Selects recommendations by namespace, owner and minimum savings,
+//! shows them for confirmation, then sends the requests with bounded
+//! concurrency and a request rate limit so large batches don't overload
+//! the API.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tabled::Tabled;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::recommendations::OwnerFilter;
+use crate::client::{
+    ApiClient, ApplyRequest, ApplyResponse, ApproveRequest, ApproveResponse, Recommendation,
+};
+use crate::error::{CliError, ErrorKind};
+use crate::output::{
+    color_status, confirm, format_bytes, format_cpu, parse_cpu_millicores, parse_memory_bytes,
+    print_list, print_success, print_warning, OutputFormat,
+};
+
+/// Default number of requests in flight
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Default maximum requests per second
+pub const DEFAULT_RATE_LIMIT: f64 = 5.0;
+
+/// Which recommendations to act on and how fast
+#[derive(Debug, Clone)]
+pub struct BulkOptions {
+    pub namespace: Option<String>,
+    pub owner: Option<OwnerFilter>,
+    /// Minimum reduction of CPU or memory requests, in percent
+    pub min_savings: Option<f64>,
+    pub concurrency: usize,
+    /// Requests per second
+    pub rate_limit: f64,
+    /// Skip the confirmation prompt
+    pub yes: bool,
+}
+
+/// Row for the table of selected recommendations
+#[derive(Tabled)]
+struct SelectionRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Owner")]
+    owner: String,
+    #[tabled(rename = "CPU Req")]
+    cpu_request: String,
+    #[tabled(rename = "Mem Req")]
+    memory_request: String,
+    #[tabled(rename = "Savings")]
+    savings: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Dry-Run")]
+    dry_run: String,
+}
+
+/// Outcome of one request in a bulk operation
+#[derive(Debug, Serialize)]
+struct BulkResult {
+    id: String,
+    namespace: String,
+    owner: String,
+    success: bool,
+    status: String,
+    message: String,
+}
+
+/// Row for the results table
+#[derive(Tabled)]
+struct ResultRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Owner")]
+    owner: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+/// Approve every pending recommendation matching the filters
+pub async fn approve_all(
+    client: &ApiClient,
+    options: &BulkOptions,
+    approver: &str,
+    reason: Option<String>,
+    format: &OutputFormat,
+) -> Result<()> {
+    let selected = select(client, options, &["pending"]).await?;
+    if selected.is_empty() {
+        print_warning("No pending recommendations match the filters");
+        return Ok(());
+    }
+
+    if let OutputFormat::Table = format {
+        print_selection(&selected, &[]);
+    }
+    if !options.yes && !confirm(&format!("Approve {} recommendations?", selected.len()))? {
+        print_warning("Bulk approve cancelled");
+        return Ok(());
+    }
+
+    let request = ApproveRequest {
+        approver: approver.to_string(),
+        reason,
+    };
+    let results = run(client, options, selected, move |client, rec| {
+        let request = request.clone();
+        async move {
+            let path = format!("api/v1/recommendation/{}/approve", rec.id);
+            let response: ApproveResponse = client.post(&path, &request).await?;
+            Ok((response.status, response.message))
+        }
+    })
+    .await;
+
+    report(&results, "approved", format)
+}
+
+/// Apply every pending or approved recommendation matching the filters
+///
+/// Each recommendation is dry-run first and the results shown for
+/// confirmation; with `dry_run` nothing is applied.
+pub async fn apply_all(
+    client: &ApiClient,
+    options: &BulkOptions,
+    dry_run: bool,
+    format: &OutputFormat,
+) -> Result<()> {
+    let selected = select(client, options, &["pending", "approved"]).await?;
+    if selected.is_empty() {
+        print_warning("No pending or approved recommendations match the filters");
+        return Ok(());
+    }
+
+    let previews = run(
+        client,
+        options,
+        selected.clone(),
+        |client, rec| async move { apply(&client, &rec.id, true).await },
+    )
+    .await;
+
+    if dry_run {
+        return report(&previews, "dry-run", format);
+    }
+
+    if let OutputFormat::Table = format {
+        print_selection(&selected, &previews);
+    }
+    // Only apply what passed its dry-run
+    let selected: Vec<Recommendation> = selected
+        .into_iter()
+        .filter(|rec| previews.iter().any(|p| p.id == rec.id && p.success))
+        .collect();
+    let failed = previews.iter().filter(|p| !p.success).count();
+    if failed > 0 {
+        print_warning(&format!(
+            "Skipping {} recommendations that failed dry-run",
+            failed
+        ));
+    }
+    if selected.is_empty() {
+        bail!("No recommendations passed dry-run");
+    }
+    if !options.yes && !confirm(&format!("Apply {} recommendations?", selected.len()))? {
+        print_warning("Bulk apply cancelled");
+        return Ok(());
+    }
+
+    let results = run(client, options, selected, |client, rec| async move {
+        apply(&client, &rec.id, false).await
+    })
+    .await;
+
+    report(&results, "applied", format)
+}
+
+async fn apply(client: &ApiClient, id: &str, dry_run: bool) -> Result<(String, String)> {
+    let path = format!("api/v1/recommendation/{}/apply", id);
+    let response: ApplyResponse = client.post(&path, &ApplyRequest { dry_run }).await?;
+    Ok((response.status, response.message))
+}
+
+/// Fetch recommendations in one of `statuses` that match the filters
+async fn select(
+    client: &ApiClient,
+    options: &BulkOptions,
+    statuses: &[&str],
+) -> Result<Vec<Recommendation>> {
+    let result = client
+        .list_recommendations(options.namespace.as_deref())
+        .await?;
+
+    Ok(result
+        .recommendations
+        .into_iter()
+        .filter(|r| statuses.iter().any(|s| r.status.eq_ignore_ascii_case(s)))
+        .filter(|r| options.owner.as_ref().map(|o| o.matches(r)).unwrap_or(true))
+        .filter(|r| match options.min_savings {
+            Some(min) => request_reduction(r).is_some_and(|savings| savings >= min),
+            None => true,
+        })
+        .collect())
+}
+
+/// Send one request per recommendation, bounded by concurrency and rate
+async fn run<F, Fut>(
+    client: &ApiClient,
+    options: &BulkOptions,
+    recommendations: Vec<Recommendation>,
+    request: F,
+) -> Vec<BulkResult>
+where
+    F: Fn(ApiClient, Recommendation) -> Fut,
+    Fut: std::future::Future<Output = Result<(String, String)>> + Send + 'static,
+{
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut ticker =
+        tokio::time::interval(Duration::from_secs_f64(1.0 / options.rate_limit.max(0.1)));
+    let mut tasks = JoinSet::new();
+
+    for rec in recommendations {
+        ticker.tick().await;
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            break;
+        };
+        let (id, namespace, owner) = (rec.id.clone(), rec.namespace.clone(), rec.owner());
+        let call = request(client.clone(), rec);
+        tasks.spawn(async move {
+            let result = call.await;
+            drop(permit);
+            let (success, status, message) = match result {
+                Ok((status, message)) => (true, status, message),
+                Err(e) => (false, "failed".to_string(), format!("{:#}", e)),
+            };
+            BulkResult {
+                id,
+                namespace,
+                owner,
+                success,
+                status,
+                message,
+            }
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(result) = result {
+            results.push(result);
+        }
+    }
+    results.sort_by(|a, b| (&a.namespace, &a.owner).cmp(&(&b.namespace, &b.owner)));
+    results
+}
+
+fn print_selection(selected: &[Recommendation], previews: &[BulkResult]) {
+    let rows: Vec<SelectionRow> = selected
+        .iter()
+        .map(|r| SelectionRow {
+            id: r.id.clone(),
+            namespace: r.namespace.clone(),
+            owner: r.owner(),
+            cpu_request: format_cpu(r.cpu_request_millicores),
+            memory_request: format_bytes(r.memory_request_bytes),
+            savings: request_reduction(r)
+                .map(|savings| format!("{:.0}%", savings))
+                .unwrap_or_else(|| "-".to_string()),
+            status: color_status(&r.status),
+            dry_run: previews
+                .iter()
+                .find(|p| p.id == r.id)
+                .map(|p| p.message.clone())
+                .unwrap_or_else(|| "-".to_string()),
+        })
+        .collect();
+
+    let table = tabled::Table::new(rows)
+        .with(tabled::settings::Style::rounded())
+        .to_string();
+    println!("{}", table);
+}
+
+/// Print results and fail if any request failed
+fn report(results: &[BulkResult], action: &str, format: &OutputFormat) -> Result<()> {
+    print_list(results, format, |results| {
+        results
+            .iter()
+            .map(|r| ResultRow {
+                id: r.id.clone(),
+                namespace: r.namespace.clone(),
+                owner: r.owner.clone(),
+                status: color_status(&r.status),
+                message: r.message.clone(),
+            })
+            .collect()
+    })?;
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    if failed > 0 {
+        let kind = if failed < results.len() {
+            ErrorKind::PartialFailure
+        } else {
+            ErrorKind::Failure
+        };
+        return Err(CliError::new(
+            kind,
+            format!("{} of {} recommendations failed", failed, results.len()),
+        )
+        .into());
+    }
+    if let OutputFormat::Table = format {
+        print_success(&format!("{} recommendations {}", results.len(), action));
+    }
+    Ok(())
+}
+
+/// Largest reduction of the CPU or memory request, in percent
+///
+/// None when the current requests are unknown.
+fn request_reduction(rec: &Recommendation) -> Option<f64> {
+    let current = rec.current_resources.as_ref()?;
+    let reduction = |current: Option<f64>, recommended: f64| {
+        current
+            .filter(|c| *c > 0.0)
+            .map(|c| (1.0 - recommended / c) * 100.0)
+    };
+
+    let cpu = reduction(
+        parse_cpu_millicores(&current.cpu_request),
+        rec.cpu_request_millicores as f64,
+    );
+    let memory = reduction(
+        parse_memory_bytes(&current.memory_request),
+        rec.memory_request_bytes as f64,
+    );
+    match (cpu, memory) {
+        (Some(cpu), Some(memory)) => Some(cpu.max(memory)),
+        (cpu, memory) => cpu.or(memory),
+    }
+}
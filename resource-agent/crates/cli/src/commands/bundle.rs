@@ -0,0 +1,531 @@
+//! Debug bundles: agent status, state, metrics, health and logs collected
+//! into a tar.gz to attach to support tickets
+//!
+//! Values that look like credentials are always redacted. Namespace names
+//! are replaced with pseudonyms when requested.
+
+use anyhow::{Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use kube::api::{ListParams, LogParams};
+use kube::Api;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::client::ApiClient;
+use crate::error::{CliError, ErrorKind};
+use crate::k8s::{self, KubeOptions};
+use crate::output::{print_info, print_success, print_warning};
+
+/// Port the agent serves /state, /metrics and health checks on by default
+pub const DEFAULT_AGENT_PORT: u16 = 8080;
+
+/// Log lines collected from the agent container by default
+pub const DEFAULT_LOG_LINES: i64 = 2000;
+
+/// Label of the resource agent pods deployed by the Helm chart
+const AGENT_POD_SELECTOR: &str = "app.kubernetes.io/component=resource-agent";
+
+const AGENT_CONTAINER: &str = "resource-agent";
+
+/// JSON keys whose string values are redacted
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "password",
+    "secret",
+    "authorization",
+    "credential",
+    "api_key",
+    "private_key",
+];
+
+/// Text after these markers, up to the next delimiter, is redacted
+const SECRET_MARKERS: &[&str] = &[
+    "bearer ",
+    "token=",
+    "token: ",
+    "token\":\"",
+    "password=",
+    "password: ",
+    "password\":\"",
+    "secret=",
+    "authorization: ",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Options for `crp debug bundle`
+#[derive(Debug, Clone)]
+pub struct BundleOptions {
+    pub node: String,
+    /// Archive path, `crp-bundle-<node>-<time>.tar.gz` when unset
+    pub output: Option<PathBuf>,
+    /// Agent URL to use instead of the Kubernetes API server's pod proxy
+    pub agent_url: Option<String>,
+    pub agent_port: u16,
+    pub log_lines: i64,
+    pub redact_namespaces: bool,
+}
+
+/// A bundle file, or why it couldn't be collected
+#[derive(Debug, Serialize)]
+struct BundleItem {
+    file: String,
+    collected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `manifest.json` describing the bundle
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    crp_version: &'static str,
+    node: &'a str,
+    created_at: String,
+    agent_pod: Option<String>,
+    namespaces_redacted: bool,
+    items: &'a [BundleItem],
+}
+
+/// Files collected so far, with secrets already redacted
+#[derive(Default)]
+struct Bundle {
+    files: Vec<(String, String)>,
+    items: Vec<BundleItem>,
+}
+
+impl Bundle {
+    fn add(&mut self, file: &str, content: Result<String>) {
+        match content {
+            Ok(content) => {
+                print_success(&format!("Collected {}", file));
+                self.files
+                    .push((file.to_string(), redact_secrets(&content)));
+                self.items.push(BundleItem {
+                    file: file.to_string(),
+                    collected: true,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                print_warning(&format!("Skipped {}: {:#}", file, e));
+                self.items.push(BundleItem {
+                    file: file.to_string(),
+                    collected: false,
+                    error: Some(redact_secrets(&format!("{:#}", e))),
+                });
+            }
+        }
+    }
+
+    fn add_json(&mut self, file: &str, value: Result<Value>) {
+        self.add(
+            file,
+            value.and_then(|mut value| {
+                redact_json(&mut value);
+                Ok(serde_json::to_string_pretty(&value)?)
+            }),
+        );
+    }
+
+    fn missing(&self) -> usize {
+        self.items.iter().filter(|item| !item.collected).count()
+    }
+}
+
+/// Resource agent pod running on the node
+struct AgentPod {
+    namespace: String,
+    name: String,
+}
+
+/// How the agent's HTTP endpoints are reached
+enum AgentEndpoint {
+    /// Directly, e.g. through `kubectl port-forward`
+    Url(ApiClient),
+    /// Through the Kubernetes API server's pod proxy
+    Proxy {
+        client: kube::Client,
+        namespace: String,
+        pod: String,
+        port: u16,
+    },
+}
+
+impl AgentEndpoint {
+    async fn get(&self, path: &str) -> Result<String> {
+        match self {
+            AgentEndpoint::Url(agent) => agent.get_text(path).await,
+            AgentEndpoint::Proxy {
+                client,
+                namespace,
+                pod,
+                port,
+            } => {
+                let uri = format!(
+                    "/api/v1/namespaces/{}/pods/{}:{}/proxy/{}",
+                    namespace, pod, port, path
+                );
+                let request = http::Request::get(uri)
+                    .body(Vec::new())
+                    .context("Invalid proxy request")?;
+                client
+                    .request_text(request)
+                    .await
+                    .with_context(|| format!("Failed to proxy /{} to pod {}", path, pod))
+            }
+        }
+    }
+}
+
+/// Collect a debug bundle for the agent on `options.node`
+///
+/// Items that can't be collected are listed in `manifest.json`; the bundle
+/// is still written as long as anything was collected.
+pub async fn collect_bundle(
+    client: &ApiClient,
+    kube: &KubeOptions,
+    options: &BundleOptions,
+) -> Result<()> {
+    let node = options.node.as_str();
+    let mut bundle = Bundle::default();
+
+    bundle.add_json(
+        "agent-status.json",
+        client.get(&format!("api/v1/agents/{}", node)).await,
+    );
+
+    let kube_client = match k8s::client(kube).await {
+        Ok(client) => Some(client),
+        Err(e) => {
+            print_warning(&format!("Kubernetes API unavailable: {:#}", e));
+            None
+        }
+    };
+    let pod = match &kube_client {
+        Some(client) => match find_agent_pod(client, node).await {
+            Ok(pod) => Some(pod),
+            Err(e) => {
+                print_warning(&format!("{:#}", e));
+                None
+            }
+        },
+        None => None,
+    };
+
+    let endpoint = match (&options.agent_url, &kube_client, &pod) {
+        (Some(url), _, _) => Some(AgentEndpoint::Url(ApiClient::new(url)?)),
+        (None, Some(client), Some(pod)) => Some(AgentEndpoint::Proxy {
+            client: client.clone(),
+            namespace: pod.namespace.clone(),
+            pod: pod.name.clone(),
+            port: options.agent_port,
+        }),
+        _ => None,
+    };
+    match &endpoint {
+        Some(endpoint) => collect_agent(&mut bundle, endpoint).await,
+        None => {
+            for file in ["health.txt", "state.json", "buffer.json", "metrics.txt"] {
+                bundle.add(
+                    file,
+                    Err(anyhow::anyhow!(
+                        "agent unreachable; pass --agent-url or check Kubernetes access"
+                    )),
+                );
+            }
+        }
+    }
+
+    match (&kube_client, &pod) {
+        (Some(client), Some(pod)) => collect_logs(&mut bundle, client, pod, options).await,
+        _ => bundle.add(
+            "logs/agent.log",
+            Err(anyhow::anyhow!("agent pod not found")),
+        ),
+    }
+
+    let collected = bundle.items.len() - bundle.missing();
+    if collected == 0 {
+        return Err(CliError::new(
+            ErrorKind::Failure,
+            "Nothing could be collected for the bundle",
+        )
+        .into());
+    }
+
+    let created_at = chrono::Utc::now();
+    let manifest = Manifest {
+        crp_version: env!("CARGO_PKG_VERSION"),
+        node,
+        created_at: created_at.to_rfc3339(),
+        agent_pod: pod
+            .as_ref()
+            .map(|pod| format!("{}/{}", pod.namespace, pod.name)),
+        namespaces_redacted: options.redact_namespaces,
+        items: &bundle.items,
+    };
+    let manifest = serde_json::to_string_pretty(&manifest)?;
+    let mut files = bundle.files;
+    files.push(("manifest.json".to_string(), manifest));
+
+    if options.redact_namespaces {
+        let mut namespaces = namespaces_in(&files);
+        if let Some(client) = &kube_client {
+            let api: Api<Namespace> = Api::all(client.clone());
+            if let Ok(list) = api.list(&ListParams::default()).await {
+                namespaces.extend(list.items.into_iter().filter_map(|ns| ns.metadata.name));
+            }
+        }
+        redact_namespaces(&mut files, &namespaces);
+    }
+
+    let stem = format!("crp-bundle-{}-{}", node, created_at.format("%Y%m%d-%H%M%S"));
+    let path = options
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.tar.gz", stem)));
+    write_archive(&path, &stem, &files)?;
+
+    print_success(&format!("Bundle written to {}", path.display()));
+    let missing = bundle.items.len() - collected;
+    if missing > 0 {
+        print_info("See manifest.json in the bundle for why items are missing");
+        return Err(CliError::new(
+            ErrorKind::PartialFailure,
+            format!(
+                "{} of {} bundle items could not be collected",
+                missing,
+                bundle.items.len()
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+async fn collect_agent(bundle: &mut Bundle, endpoint: &AgentEndpoint) {
+    // Unhealthy agents answer with an error status, which is what we want to record
+    let mut health = String::new();
+    for path in ["healthz", "readyz"] {
+        let status = match endpoint.get(path).await {
+            Ok(body) => body,
+            Err(e) => format!("{:#}", e),
+        };
+        health.push_str(&format!("/{}: {}\n", path, status.trim()));
+    }
+    bundle.add("health.txt", Ok(health));
+
+    let state = endpoint
+        .get("state")
+        .await
+        .and_then(|body| serde_json::from_str::<Value>(&body).context("Invalid /state response"));
+    let buffer = match &state {
+        Ok(state) => state
+            .get("buffer")
+            .filter(|buffer| !buffer.is_null())
+            .cloned()
+            .context("agent state has no buffer section"),
+        Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+    };
+    bundle.add_json("state.json", state);
+    bundle.add_json("buffer.json", buffer);
+
+    bundle.add("metrics.txt", endpoint.get("metrics").await);
+}
+
+async fn collect_logs(
+    bundle: &mut Bundle,
+    client: &kube::Client,
+    pod: &AgentPod,
+    options: &BundleOptions,
+) {
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &pod.namespace);
+    let params = LogParams {
+        container: Some(AGENT_CONTAINER.to_string()),
+        tail_lines: Some(options.log_lines),
+        timestamps: true,
+        ..Default::default()
+    };
+    bundle.add(
+        "logs/agent.log",
+        pods.logs(&pod.name, &params)
+            .await
+            .context("Failed to fetch agent logs"),
+    );
+
+    // Only present if the agent restarted, so not worth a manifest entry otherwise
+    let previous = LogParams {
+        previous: true,
+        ..params
+    };
+    if let Ok(logs) = pods.logs(&pod.name, &previous).await {
+        bundle.add("logs/agent.previous.log", Ok(logs));
+    }
+}
+
+async fn find_agent_pod(client: &kube::Client, node: &str) -> Result<AgentPod> {
+    let pods: Api<Pod> = Api::all(client.clone());
+    let params = ListParams::default()
+        .labels(AGENT_POD_SELECTOR)
+        .fields(&format!("spec.nodeName={}", node));
+    let list = pods
+        .list(&params)
+        .await
+        .context("Failed to list agent pods")?;
+
+    list.items
+        .into_iter()
+        .find_map(|pod| {
+            Some(AgentPod {
+                namespace: pod.metadata.namespace?,
+                name: pod.metadata.name?,
+            })
+        })
+        .with_context(|| format!("No resource agent pod found on node {}", node))
+}
+
+/// Replace values of secret-looking JSON keys and markers in strings
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if value.is_string() && SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        Value::String(text) => *text = redact_secrets(text),
+        _ => {}
+    }
+}
+
+/// Replace text following secret markers such as `Bearer ` or `token=`
+fn redact_secrets(text: &str) -> String {
+    // ASCII lowercasing keeps byte offsets identical to `text`
+    let lower = text.to_ascii_lowercase();
+    let mut redacted = String::with_capacity(text.len());
+    let mut pos = 0;
+    while let Some((start, marker)) = SECRET_MARKERS
+        .iter()
+        .filter_map(|marker| lower[pos..].find(marker).map(|i| (pos + i, *marker)))
+        .min_by_key(|(start, _)| *start)
+    {
+        let value_start = start + marker.len();
+        let value_end = text[value_start..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | '&' | ';'))
+            .map_or(text.len(), |i| value_start + i);
+        redacted.push_str(&text[pos..value_start]);
+        if value_end > value_start {
+            redacted.push_str(REDACTED);
+        }
+        pos = value_end;
+    }
+    redacted.push_str(&text[pos..]);
+    redacted
+}
+
+/// Namespace names appearing as `namespace` fields in the JSON files
+fn namespaces_in(files: &[(String, String)]) -> BTreeSet<String> {
+    fn walk(value: &Value, namespaces: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match value {
+                        Value::String(name) if key == "namespace" => {
+                            namespaces.insert(name.clone());
+                        }
+                        _ => walk(value, namespaces),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| walk(item, namespaces)),
+            _ => {}
+        }
+    }
+
+    let mut namespaces = BTreeSet::new();
+    for (file, content) in files {
+        if file.ends_with(".json") {
+            if let Ok(value) = serde_json::from_str::<Value>(content) {
+                walk(&value, &mut namespaces);
+            }
+        }
+    }
+    namespaces
+}
+
+/// Replace whole-word namespace names with `namespace-<n>` in every file
+fn redact_namespaces(files: &mut [(String, String)], namespaces: &BTreeSet<String>) {
+    let mut pseudonyms: Vec<(&String, String)> = namespaces
+        .iter()
+        .filter(|name| !name.is_empty())
+        .enumerate()
+        .map(|(i, name)| (name, format!("namespace-{}", i + 1)))
+        .collect();
+
+    // Longest first so a namespace isn't partly replaced by a shorter one
+    pseudonyms.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    for (_, content) in files.iter_mut() {
+        for (name, pseudonym) in &pseudonyms {
+            *content = replace_word(content, name, pseudonym);
+        }
+    }
+}
+
+/// Replace occurrences of `word` not embedded in a longer name
+fn replace_word(text: &str, word: &str, replacement: &str) -> String {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let mut replaced = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(word) {
+        let before = if pos > 0 {
+            rest[..pos].chars().next_back()
+        } else {
+            replaced.chars().next_back()
+        };
+        let after = rest[pos + word.len()..].chars().next();
+        replaced.push_str(&rest[..pos]);
+        if before.map_or(true, |c| !is_name_char(c)) && after.map_or(true, |c| !is_name_char(c)) {
+            replaced.push_str(replacement);
+        } else {
+            replaced.push_str(word);
+        }
+        rest = &rest[pos + word.len()..];
+    }
+    replaced.push_str(rest);
+    replaced
+}
+
+fn write_archive(path: &Path, root: &str, files: &[(String, String)]) -> Result<()> {
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mtime = chrono::Utc::now().timestamp().max(0) as u64;
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        archive
+            .append_data(
+                &mut header,
+                format!("{}/{}", root, name),
+                content.as_bytes(),
+            )
+            .with_context(|| format!("Failed to add {} to the bundle", name))?;
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
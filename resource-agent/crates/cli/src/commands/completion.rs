@@ -0,0 +1,86 @@
+//! Shell completions and man pages for packaging
+
+use anyhow::{Context, Result};
+use clap::Command;
+use clap_complete::Shell;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+
+use crate::client::ApiClient;
+
+/// Name of the hidden subcommand that lists namespaces for completion
+///
+/// No leading `__`: the bash generator splits subcommand paths on it.
+pub const COMPLETE_NAMESPACES: &str = "complete-namespaces";
+
+/// Bash wrapper completing `--namespace` values from the API
+const BASH_DYNAMIC: &str = r#"
+_crp_dynamic() {
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$prev" == "--namespace" || "$prev" == "-n" ]]; then
+        COMPREPLY=( $(compgen -W "$(crp complete-namespaces 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}") )
+        return 0
+    fi
+    _crp "$@"
+}
+complete -F _crp_dynamic -o bashdefault -o default crp
+"#;
+
+/// Fish completion of `--namespace` values from the API
+const FISH_DYNAMIC: &str = r#"
+complete -c crp -l namespace -s n -x -a "(crp complete-namespaces 2>/dev/null)"
+"#;
+
+/// Print the completion script for `shell`
+///
+/// Bash and fish scripts also complete namespaces by querying the API
+/// through the hidden `complete-namespaces` subcommand.
+pub fn print_completions(cmd: &mut Command, shell: Shell) -> Result<()> {
+    let name = cmd.get_name().to_string();
+    let mut stdout = std::io::stdout();
+    clap_complete::generate(shell, cmd, name, &mut stdout);
+
+    let dynamic = match shell {
+        Shell::Bash => Some(BASH_DYNAMIC),
+        Shell::Fish => Some(FISH_DYNAMIC),
+        _ => None,
+    };
+    if let Some(script) = dynamic {
+        stdout.write_all(script.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Print namespaces that have recommendations, one per line
+pub async fn complete_namespaces(client: &ApiClient) -> Result<()> {
+    let list = client.list_recommendations(None).await?;
+    let namespaces: BTreeSet<_> = list
+        .recommendations
+        .into_iter()
+        .map(|r| r.namespace)
+        .collect();
+    for namespace in namespaces {
+        println!("{}", namespace);
+    }
+    Ok(())
+}
+
+/// Write man pages for every command to `output_dir`, or the top-level page to stdout
+pub fn generate_man(cmd: Command, output_dir: Option<&Path>) -> Result<()> {
+    match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            clap_mangen::generate_to(cmd, dir)
+                .with_context(|| format!("Failed to write man pages to {}", dir.display()))?;
+            println!("Man pages written to {}", dir.display());
+        }
+        None => {
+            clap_mangen::Man::new(cmd)
+                .render(&mut std::io::stdout())
+                .context("Failed to render man page")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! Cluster context management: `crp config get-contexts|use-context|set-context`
+
+use anyhow::Result;
+use serde::Serialize;
+use tabled::Tabled;
+
+use crate::config::{ClusterContext, Config};
+use crate::error::{CliError, ErrorKind};
+use crate::output::{print_list, print_success, OutputFormat};
+
+/// Row for contexts table
+#[derive(Tabled)]
+struct ContextRow {
+    #[tabled(rename = "Current")]
+    current: &'static str,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "API URL")]
+    api_url: String,
+    #[tabled(rename = "Kube Context")]
+    kube_context: String,
+    #[tabled(rename = "Auth")]
+    auth: &'static str,
+}
+
+/// A context as listed, without its credentials
+#[derive(Debug, Serialize)]
+struct ContextEntry<'a> {
+    name: &'a str,
+    current: bool,
+    api_url: Option<&'a str>,
+    kube_context: Option<&'a str>,
+    auth: &'static str,
+}
+
+/// List configured contexts, marking the current one
+pub fn get_contexts(config: &Config, format: &OutputFormat) -> Result<()> {
+    let entries: Vec<ContextEntry> = config
+        .contexts
+        .iter()
+        .map(|(name, context)| ContextEntry {
+            name,
+            current: config.current_context.as_deref() == Some(name.as_str()),
+            api_url: context.api_url.as_deref(),
+            kube_context: context.kube_context.as_deref(),
+            auth: auth_kind(context),
+        })
+        .collect();
+
+    print_list(&entries, format, |entries| {
+        entries
+            .iter()
+            .map(|e| ContextRow {
+                current: if e.current { "*" } else { "" },
+                name: e.name.to_string(),
+                api_url: e.api_url.unwrap_or("-").to_string(),
+                kube_context: e.kube_context.unwrap_or("-").to_string(),
+                auth: e.auth,
+            })
+            .collect()
+    })
+}
+
+/// How a context authenticates to the API
+fn auth_kind(context: &ClusterContext) -> &'static str {
+    if context.token.is_some() {
+        "token"
+    } else if context.oidc_issuer.is_some() {
+        "oidc"
+    } else if context.client_cert.is_some() {
+        "mtls"
+    } else {
+        "none"
+    }
+}
+
+/// Make `name` the current context
+pub fn use_context(config: &mut Config, name: &str) -> Result<()> {
+    if !config.contexts.contains_key(name) {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!(
+                "Context '{}' not found, create it with: crp config set-context {} --api-url <URL>",
+                name, name
+            ),
+        )
+        .into());
+    }
+    config.current_context = Some(name.to_string());
+    config.save()?;
+    print_success(&format!("Switched to context \"{}\"", name));
+    Ok(())
+}
+
+/// Create context `name`, or update the fields given in `update`
+///
+/// The first context created becomes the current one.
+pub fn set_context(config: &mut Config, name: &str, update: ClusterContext) -> Result<()> {
+    let created = !config.contexts.contains_key(name);
+    let context = config.contexts.entry(name.to_string()).or_default();
+    if update.api_url.is_some() {
+        context.api_url = update.api_url;
+    }
+    if update.token.is_some() {
+        context.token = update.token;
+    }
+    if update.kube_context.is_some() {
+        context.kube_context = update.kube_context;
+    }
+    if update.kubeconfig.is_some() {
+        context.kubeconfig = update.kubeconfig;
+    }
+    if update.oidc_issuer.is_some() {
+        context.oidc_issuer = update.oidc_issuer;
+    }
+    if update.oidc_client_id.is_some() {
+        context.oidc_client_id = update.oidc_client_id;
+    }
+    if update.oidc_scopes.is_some() {
+        context.oidc_scopes = update.oidc_scopes;
+    }
+    if update.client_cert.is_some() {
+        context.client_cert = update.client_cert;
+    }
+    if update.client_key.is_some() {
+        context.client_key = update.client_key;
+    }
+    if update.ca_cert.is_some() {
+        context.ca_cert = update.ca_cert;
+    }
+    if config.current_context.is_none() {
+        config.current_context = Some(name.to_string());
+    }
+    config.save()?;
+
+    let action = if created { "created" } else { "modified" };
+    print_success(&format!("Context \"{}\" {}", name, action));
+    Ok(())
+}
@@ -1,11 +1,23 @@
 //! Cost-related CLI commands
 
 use anyhow::Result;
+use chrono::{DateTime, Datelike, Months, Utc};
 use colored::Colorize;
+use serde::Serialize;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
 use tabled::Tabled;
 
-use crate::client::{ApiClient, CostAnalysis, SavingsReport};
-use crate::output::{format_currency, OutputFormat};
+use super::recommendations::fetch_recommendations;
+use crate::client::{
+    ApiClient, CostAnalysis, MonthlySaving, Recommendation, ResourceSpec, SavingsReport,
+};
+use crate::k8s;
+use crate::output::{
+    format_currency, format_timestamp, parse_cpu_millicores, parse_memory_bytes, parse_period,
+    print_object, print_warning, OutputFormat,
+};
+use crate::pricing::{PricingProvider, HOURS_PER_MONTH};
 
 /// Row for savings by month table
 #[derive(Tabled)]
@@ -25,108 +37,248 @@ struct TeamSavingRow {
     savings: String,
 }
 
+/// Row for forecast savings by namespace table
+#[derive(Tabled)]
+struct NamespaceForecastRow {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Deployments")]
+    deployments: usize,
+    #[tabled(rename = "Monthly Savings")]
+    monthly_savings: String,
+}
+
+/// Row for forecast savings by deployment table
+#[derive(Tabled)]
+struct DeploymentForecastRow {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Deployment")]
+    deployment: String,
+    #[tabled(rename = "Current")]
+    current: String,
+    #[tabled(rename = "Recommended")]
+    recommended: String,
+    #[tabled(rename = "Monthly Savings")]
+    savings: String,
+}
+
+/// Row for what-if table
+#[derive(Tabled)]
+struct WhatIfRow {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Deployment")]
+    deployment: String,
+    #[tabled(rename = "Current")]
+    current: String,
+    #[tabled(rename = "Recommended")]
+    recommended: String,
+    #[tabled(rename = "With Buffers")]
+    what_if: String,
+}
+
+/// Projected savings of applying every pending recommendation
+#[derive(Debug, Serialize)]
+struct SavingsForecast {
+    currency: String,
+    pricing: String,
+    pending: usize,
+    monthly_savings: f64,
+    annual_savings: f64,
+    namespaces: Vec<NamespaceForecast>,
+    deployments: Vec<DeploymentForecast>,
+}
+
+#[derive(Debug, Serialize)]
+struct NamespaceForecast {
+    namespace: String,
+    deployments: usize,
+    monthly_savings: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct DeploymentForecast {
+    namespace: String,
+    deployment: String,
+    recommendation_id: String,
+    current_monthly_cost: f64,
+    recommended_monthly_cost: f64,
+    monthly_savings: f64,
+}
+
+/// Costs under a buffer policy compared to the current recommendations
+#[derive(Debug, Serialize)]
+struct WhatIfAnalysis {
+    cpu_buffer_percent: f64,
+    memory_buffer_percent: f64,
+    currency: String,
+    pricing: String,
+    current_monthly_cost: f64,
+    recommended_monthly_cost: f64,
+    what_if_monthly_cost: f64,
+    what_if_savings: f64,
+    deployments: Vec<WhatIfDeployment>,
+}
+
+#[derive(Debug, Serialize)]
+struct WhatIfDeployment {
+    namespace: String,
+    deployment: String,
+    current_monthly_cost: f64,
+    recommended_monthly_cost: f64,
+    what_if_monthly_cost: f64,
+}
+
 /// Show cost analysis
+///
+/// With `pricing`, costs are estimated from recommendations at those rates
+/// instead of taken from the API.
 pub async fn show_costs(
     client: &ApiClient,
     namespace: Option<String>,
-    format: OutputFormat,
+    pricing: Option<&dyn PricingProvider>,
+    format: &OutputFormat,
 ) -> Result<()> {
-    let path = match &namespace {
-        Some(ns) => format!("api/v1/costs/{}", ns),
-        None => "api/v1/costs".to_string(),
+    let result = match pricing {
+        Some(pricing) => {
+            let (result, unpriced) = estimate_costs(client, namespace, pricing).await?;
+            if unpriced > 0 {
+                if let OutputFormat::Table = format {
+                    print_warning(&format!(
+                        "{} deployments skipped: current resources unknown",
+                        unpriced
+                    ));
+                }
+            }
+            result
+        }
+        None => {
+            let path = match &namespace {
+                Some(ns) => format!("api/v1/costs/{}", ns),
+                None => "api/v1/costs".to_string(),
+            };
+            client.get(&path).await?
+        }
     };
 
-    let result: CostAnalysis = client.get(&path).await?;
+    print_object(&result, format, || {
+        println!("{}", "Cost Analysis".bold());
+        println!("{}", "=".repeat(50));
 
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&result)?;
-            println!("{}", json);
+        if let Some(ns) = &result.namespace {
+            println!("Namespace:              {}", ns.cyan());
+        } else {
+            println!("Scope:                  {}", "Cluster-wide".cyan());
         }
-        OutputFormat::Table => {
-            println!("{}", "Cost Analysis".bold());
-            println!("{}", "=".repeat(50));
 
-            if let Some(ns) = &result.namespace {
-                println!("Namespace:              {}", ns.cyan());
-            } else {
-                println!("Scope:                  {}", "Cluster-wide".cyan());
-            }
+        println!("Deployments:            {}", result.deployment_count);
+        if let Some(pricing) = &result.pricing {
+            println!("Pricing:                {}", pricing);
+        }
+        println!();
 
-            println!("Deployments:            {}", result.deployment_count);
-            println!();
+        println!("{}", "Monthly Costs".bold());
+        println!("{}", "-".repeat(50));
+        println!(
+            "Current:                {}",
+            format_currency(result.current_monthly_cost, &result.currency)
+        );
+        println!(
+            "Recommended:            {}",
+            format_currency(result.recommended_monthly_cost, &result.currency).green()
+        );
+        println!();
 
-            println!("{}", "Monthly Costs".bold());
-            println!("{}", "-".repeat(50));
-            println!(
-                "Current:                {}",
-                format_currency(result.current_monthly_cost, &result.currency)
-            );
-            println!(
-                "Recommended:            {}",
-                format_currency(result.recommended_monthly_cost, &result.currency).green()
-            );
-            println!();
+        let savings_str = format_currency(result.potential_savings, &result.currency);
+        let savings_pct = if result.current_monthly_cost > 0.0 {
+            (result.potential_savings / result.current_monthly_cost) * 100.0
+        } else {
+            0.0
+        };
 
-            let savings_str = format_currency(result.potential_savings, &result.currency);
-            let savings_pct = if result.current_monthly_cost > 0.0 {
-                (result.potential_savings / result.current_monthly_cost) * 100.0
-            } else {
-                0.0
-            };
+        println!(
+            "{} {} ({:.1}%)",
+            "Potential Savings:".bold(),
+            savings_str.green().bold(),
+            savings_pct
+        );
 
-            println!(
-                "{} {} ({:.1}%)",
-                "Potential Savings:".bold(),
-                savings_str.green().bold(),
-                savings_pct
-            );
+        println!();
+        println!(
+            "Last updated: {}",
+            format_timestamp(&result.last_updated).dimmed()
+        );
+        Ok(())
+    })
+}
 
-            println!();
-            println!(
-                "Last updated: {}",
-                format_timestamp(&result.last_updated).dimmed()
-            );
+/// Show savings report
+///
+/// With `pricing`, savings are estimated from applied recommendations at
+/// those rates instead of taken from the API.
+pub async fn show_savings(
+    client: &ApiClient,
+    since: &str,
+    pricing: Option<&dyn PricingProvider>,
+    format: &OutputFormat,
+) -> Result<()> {
+    let result = match pricing {
+        Some(pricing) => estimate_savings(client, since, pricing).await?,
+        None => {
+            let path = format!("api/v1/savings?since={}", since);
+            client.get(&path).await?
         }
-    }
+    };
 
-    Ok(())
-}
+    print_object(&result, format, || {
+        println!("{}", "Savings Report".bold());
+        println!("{}", "=".repeat(50));
+        println!("Period:                 {}", result.period);
+        if let Some(pricing) = &result.pricing {
+            println!("Pricing:                {}", pricing);
+        }
+        println!(
+            "{}  {}",
+            "Total Savings:".bold(),
+            format_currency(result.total_savings, &result.currency)
+                .green()
+                .bold()
+        );
+        println!();
 
-/// Show savings report
-pub async fn show_savings(client: &ApiClient, since: &str, format: OutputFormat) -> Result<()> {
-    let path = format!("api/v1/savings?since={}", since);
-    let result: SavingsReport = client.get(&path).await?;
-
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&result)?;
-            println!("{}", json);
-        }
-        OutputFormat::Table => {
-            println!("{}", "Savings Report".bold());
-            println!("{}", "=".repeat(50));
-            println!("Period:                 {}", result.period);
-            println!(
-                "{}  {}",
-                "Total Savings:".bold(),
-                format_currency(result.total_savings, &result.currency)
-                    .green()
-                    .bold()
-            );
+        // Monthly breakdown
+        if !result.savings_by_month.is_empty() {
+            println!("{}", "Savings by Month".bold());
+            println!("{}", "-".repeat(50));
+
+            let rows: Vec<MonthlySavingRow> = result
+                .savings_by_month
+                .iter()
+                .map(|m| MonthlySavingRow {
+                    month: m.month.clone(),
+                    savings: format_currency(m.savings, &result.currency),
+                })
+                .collect();
+
+            let table = tabled::Table::new(rows)
+                .with(tabled::settings::Style::rounded())
+                .to_string();
+            println!("{}", table);
             println!();
+        }
 
-            // Monthly breakdown
-            if !result.savings_by_month.is_empty() {
-                println!("{}", "Savings by Month".bold());
+        // Team breakdown
+        if let Some(teams) = &result.savings_by_team {
+            if !teams.is_empty() {
+                println!("{}", "Savings by Team".bold());
                 println!("{}", "-".repeat(50));
 
-                let rows: Vec<MonthlySavingRow> = result
-                    .savings_by_month
+                let rows: Vec<TeamSavingRow> = teams
                     .iter()
-                    .map(|m| MonthlySavingRow {
-                        month: m.month.clone(),
-                        savings: format_currency(m.savings, &result.currency),
+                    .map(|t| TeamSavingRow {
+                        team: t.team.clone(),
+                        savings: format_currency(t.savings, &result.currency),
                     })
                     .collect();
 
@@ -134,40 +286,610 @@ pub async fn show_savings(client: &ApiClient, since: &str, format: OutputFormat)
                     .with(tabled::settings::Style::rounded())
                     .to_string();
                 println!("{}", table);
-                println!();
             }
+        }
+        Ok(())
+    })
+}
+
+/// Project savings if every pending recommendation were applied
+pub async fn show_forecast(
+    client: &ApiClient,
+    namespace: Option<String>,
+    pricing: &dyn PricingProvider,
+    format: &OutputFormat,
+) -> Result<()> {
+    let pending = Some("pending".to_string());
+    let recommendations = fetch_recommendations(client, &namespace, &None, &pending).await?;
+    let latest = latest_per_deployment(recommendations);
+    let forecast = forecast_savings(&latest, pricing);
+    let unpriced = latest.len() - forecast.deployments.len();
+
+    print_object(&forecast, format, || {
+        let currency = &forecast.currency;
+        println!("{}", "Savings Forecast".bold());
+        println!("{}", "=".repeat(50));
+        println!("Pending recommendations: {}", forecast.pending);
+        println!("Pricing:                 {}", forecast.pricing);
+        println!(
+            "{} {} per month, {} per year",
+            "Projected Savings:".bold(),
+            format_currency(forecast.monthly_savings, currency)
+                .green()
+                .bold(),
+            format_currency(forecast.annual_savings, currency)
+        );
+        if unpriced > 0 {
+            print_warning(&format!(
+                "{} deployments skipped: current resources unknown",
+                unpriced
+            ));
+        }
+        if forecast.deployments.is_empty() {
+            return Ok(());
+        }
+
+        println!();
+        println!("{}", "Savings by Namespace".bold());
+        println!("{}", "-".repeat(50));
+        let rows: Vec<NamespaceForecastRow> = forecast
+            .namespaces
+            .iter()
+            .map(|n| NamespaceForecastRow {
+                namespace: n.namespace.clone(),
+                deployments: n.deployments,
+                monthly_savings: format_currency(n.monthly_savings, currency),
+            })
+            .collect();
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+
+        println!();
+        println!("{}", "Savings by Deployment".bold());
+        println!("{}", "-".repeat(50));
+        let rows: Vec<DeploymentForecastRow> = forecast
+            .deployments
+            .iter()
+            .map(|d| DeploymentForecastRow {
+                namespace: d.namespace.clone(),
+                deployment: d.deployment.clone(),
+                current: format_currency(d.current_monthly_cost, currency),
+                recommended: format_currency(d.recommended_monthly_cost, currency),
+                savings: format_currency(d.monthly_savings, currency),
+            })
+            .collect();
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+        Ok(())
+    })
+}
+
+/// Compare recommendation costs under a different buffer policy
+///
+/// Buffers are added on top of recommended requests, which costs are based
+/// on, to show what a cluster-wide policy change would cost before making it.
+pub async fn show_what_if(
+    client: &ApiClient,
+    namespace: Option<String>,
+    cpu_buffer: f64,
+    memory_buffer: f64,
+    pricing: &dyn PricingProvider,
+    format: &OutputFormat,
+) -> Result<()> {
+    let recommendations = fetch_recommendations(client, &namespace, &None, &None).await?;
+    let latest = latest_per_deployment(recommendations);
+    let analysis = what_if_costs(&latest, cpu_buffer, memory_buffer, pricing);
+    let unpriced = latest.len() - analysis.deployments.len();
+
+    print_object(&analysis, format, || {
+        let currency = &analysis.currency;
+        println!("{}", "What-If Analysis".bold());
+        println!("{}", "=".repeat(50));
+        println!(
+            "Buffers:                CPU +{}%, memory +{}%",
+            analysis.cpu_buffer_percent, analysis.memory_buffer_percent
+        );
+        println!("Pricing:                {}", analysis.pricing);
+        println!("Deployments:            {}", analysis.deployments.len());
+        println!();
+
+        println!("{}", "Monthly Costs".bold());
+        println!("{}", "-".repeat(50));
+        println!(
+            "Current:                {}",
+            format_currency(analysis.current_monthly_cost, currency)
+        );
+        println!(
+            "Recommended:            {}",
+            format_currency(analysis.recommended_monthly_cost, currency)
+        );
+        println!(
+            "With buffers:           {}",
+            format_currency(analysis.what_if_monthly_cost, currency).cyan()
+        );
+        println!();
+
+        let savings = format_currency(analysis.what_if_savings, currency);
+        println!(
+            "{} {} (buffers cost {} on top of recommendations)",
+            "Savings With Buffers:".bold(),
+            if analysis.what_if_savings >= 0.0 {
+                savings.green().bold()
+            } else {
+                savings.red().bold()
+            },
+            format_currency(
+                analysis.what_if_monthly_cost - analysis.recommended_monthly_cost,
+                currency
+            )
+        );
+        if unpriced > 0 {
+            print_warning(&format!(
+                "{} deployments skipped: current resources unknown",
+                unpriced
+            ));
+        }
+        if analysis.deployments.is_empty() {
+            return Ok(());
+        }
+
+        println!();
+        let rows: Vec<WhatIfRow> = analysis
+            .deployments
+            .iter()
+            .map(|d| WhatIfRow {
+                namespace: d.namespace.clone(),
+                deployment: d.deployment.clone(),
+                current: format_currency(d.current_monthly_cost, currency),
+                recommended: format_currency(d.recommended_monthly_cost, currency),
+                what_if: format_currency(d.what_if_monthly_cost, currency),
+            })
+            .collect();
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+        Ok(())
+    })
+}
+
+/// Savings of applying each deployment's pending recommendation
+fn forecast_savings(latest: &[Recommendation], pricing: &dyn PricingProvider) -> SavingsForecast {
+    let mut deployments: Vec<DeploymentForecast> = latest
+        .iter()
+        .filter_map(|rec| {
+            let (current, recommended) = price_change(pricing, rec)?;
+            Some(DeploymentForecast {
+                namespace: rec.namespace.clone(),
+                deployment: rec.deployment.clone(),
+                recommendation_id: rec.id.clone(),
+                current_monthly_cost: round_cents(current),
+                recommended_monthly_cost: round_cents(recommended),
+                monthly_savings: round_cents(current - recommended),
+            })
+        })
+        .collect();
+    deployments.sort_by(|a, b| b.monthly_savings.total_cmp(&a.monthly_savings));
 
-            // Team breakdown
-            if let Some(teams) = &result.savings_by_team {
-                if !teams.is_empty() {
-                    println!("{}", "Savings by Team".bold());
-                    println!("{}", "-".repeat(50));
-
-                    let rows: Vec<TeamSavingRow> = teams
-                        .iter()
-                        .map(|t| TeamSavingRow {
-                            team: t.team.clone(),
-                            savings: format_currency(t.savings, &result.currency),
-                        })
-                        .collect();
-
-                    let table = tabled::Table::new(rows)
-                        .with(tabled::settings::Style::rounded())
-                        .to_string();
-                    println!("{}", table);
+    let mut by_namespace: BTreeMap<&str, NamespaceForecast> = BTreeMap::new();
+    for d in &deployments {
+        let total = by_namespace
+            .entry(d.namespace.as_str())
+            .or_insert_with(|| NamespaceForecast {
+                namespace: d.namespace.clone(),
+                deployments: 0,
+                monthly_savings: 0.0,
+            });
+        total.deployments += 1;
+        total.monthly_savings = round_cents(total.monthly_savings + d.monthly_savings);
+    }
+    let mut namespaces: Vec<NamespaceForecast> = by_namespace.into_values().collect();
+    namespaces.sort_by(|a, b| b.monthly_savings.total_cmp(&a.monthly_savings));
+
+    let monthly_savings = round_cents(deployments.iter().map(|d| d.monthly_savings).sum());
+    SavingsForecast {
+        currency: pricing.currency().to_string(),
+        pricing: pricing.name(),
+        pending: deployments.len(),
+        monthly_savings,
+        annual_savings: round_cents(monthly_savings * 12.0),
+        namespaces,
+        deployments,
+    }
+}
+
+/// Costs of each deployment's latest recommendation with buffers added
+fn what_if_costs(
+    latest: &[Recommendation],
+    cpu_buffer: f64,
+    memory_buffer: f64,
+    pricing: &dyn PricingProvider,
+) -> WhatIfAnalysis {
+    let deployments: Vec<WhatIfDeployment> = latest
+        .iter()
+        .filter_map(|rec| {
+            let (before, after) = price_change(pricing, rec)?;
+            let buffered = with_buffer(&recommended_spec(rec), cpu_buffer, memory_buffer)?;
+            let what_if = pricing.monthly_cost(&rec.namespace, &buffered)?;
+            Some(WhatIfDeployment {
+                namespace: rec.namespace.clone(),
+                deployment: rec.deployment.clone(),
+                current_monthly_cost: round_cents(live_cost(rec, before, after)),
+                recommended_monthly_cost: round_cents(after),
+                what_if_monthly_cost: round_cents(what_if),
+            })
+        })
+        .collect();
+
+    let current: f64 = deployments.iter().map(|d| d.current_monthly_cost).sum();
+    let recommended: f64 = deployments.iter().map(|d| d.recommended_monthly_cost).sum();
+    let what_if: f64 = deployments.iter().map(|d| d.what_if_monthly_cost).sum();
+    WhatIfAnalysis {
+        cpu_buffer_percent: cpu_buffer,
+        memory_buffer_percent: memory_buffer,
+        currency: pricing.currency().to_string(),
+        pricing: pricing.name(),
+        current_monthly_cost: round_cents(current),
+        recommended_monthly_cost: round_cents(recommended),
+        what_if_monthly_cost: round_cents(what_if),
+        what_if_savings: round_cents(current - what_if),
+        deployments,
+    }
+}
+
+/// Price the latest recommendation of each deployment
+///
+/// Applied recommendations count at their recommended resources and rolled
+/// back ones are ignored. Also returns how many deployments couldn't be
+/// priced because the recommendation lacks current resources.
+async fn estimate_costs(
+    client: &ApiClient,
+    namespace: Option<String>,
+    pricing: &dyn PricingProvider,
+) -> Result<(CostAnalysis, usize)> {
+    let recommendations = fetch_recommendations(client, &namespace, &None, &None).await?;
+    let latest = latest_per_deployment(recommendations);
+
+    let (mut current, mut recommended, mut priced) = (0.0, 0.0, 0);
+    for rec in &latest {
+        let Some((before, after)) = price_change(pricing, rec) else {
+            continue;
+        };
+        priced += 1;
+        recommended += after;
+        current += live_cost(rec, before, after);
+    }
+
+    let analysis = CostAnalysis {
+        namespace,
+        current_monthly_cost: round_cents(current),
+        recommended_monthly_cost: round_cents(recommended),
+        potential_savings: round_cents(current - recommended),
+        currency: pricing.currency().to_string(),
+        deployment_count: priced as i32,
+        last_updated: Utc::now().to_rfc3339(),
+        pricing: Some(pricing.name()),
+    };
+    Ok((analysis, latest.len() - priced))
+}
+
+/// Savings of applied recommendations within `since`, prorated per month
+async fn estimate_savings(
+    client: &ApiClient,
+    since: &str,
+    pricing: &dyn PricingProvider,
+) -> Result<SavingsReport> {
+    let now = Utc::now();
+    let start = now - parse_period(since)?;
+    let applied = Some("applied".to_string());
+    let recommendations = fetch_recommendations(client, &None, &None, &applied).await?;
+
+    let mut by_month: BTreeMap<String, f64> = BTreeMap::new();
+    for rec in &recommendations {
+        let Some(applied_at) = rec
+            .applied_at
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        else {
+            continue;
+        };
+        let Some((before, after)) = price_change(pricing, rec) else {
+            continue;
+        };
+
+        let mut from = applied_at.with_timezone(&Utc).max(start);
+        while from < now {
+            let Some(next_month) = start_of_next_month(from) else {
+                break;
+            };
+            let to = next_month.min(now);
+            let hours = (to - from).num_seconds() as f64 / 3600.0;
+            *by_month
+                .entry(from.format("%Y-%m").to_string())
+                .or_default() += (before - after) * hours / HOURS_PER_MONTH;
+            from = to;
+        }
+    }
+
+    let savings_by_month: Vec<MonthlySaving> = by_month
+        .into_iter()
+        .rev()
+        .map(|(month, savings)| MonthlySaving {
+            month,
+            savings: round_cents(savings),
+        })
+        .collect();
+    Ok(SavingsReport {
+        total_savings: round_cents(savings_by_month.iter().map(|m| m.savings).sum()),
+        currency: pricing.currency().to_string(),
+        period: since.to_string(),
+        savings_by_month,
+        savings_by_team: None,
+        pricing: Some(pricing.name()),
+    })
+}
+
+/// Latest recommendation of each deployment, ignoring rolled back ones
+fn latest_per_deployment(recommendations: Vec<Recommendation>) -> Vec<Recommendation> {
+    let mut latest: BTreeMap<(String, String), Recommendation> = BTreeMap::new();
+    for rec in recommendations
+        .into_iter()
+        .filter(|r| !r.status.eq_ignore_ascii_case("rolled_back"))
+    {
+        match latest.entry((rec.namespace.clone(), rec.deployment.clone())) {
+            Entry::Occupied(mut entry) => {
+                if rec.created_at > entry.get().created_at {
+                    entry.insert(rec);
                 }
             }
+            Entry::Vacant(entry) => {
+                entry.insert(rec);
+            }
         }
     }
-
-    Ok(())
+    latest.into_values().collect()
 }
 
-/// Format timestamp for display
-fn format_timestamp(ts: &str) -> String {
-    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(ts) {
-        dt.format("%Y-%m-%d %H:%M:%S").to_string()
+/// Cost of what is running now: applied recommendations are already live
+fn live_cost(rec: &Recommendation, before: f64, after: f64) -> f64 {
+    if rec.status.eq_ignore_ascii_case("applied") {
+        after
     } else {
-        ts.to_string()
+        before
+    }
+}
+
+/// Monthly (current, recommended) cost of a recommendation's target
+fn price_change(pricing: &dyn PricingProvider, rec: &Recommendation) -> Option<(f64, f64)> {
+    let current = pricing.monthly_cost(&rec.namespace, rec.current_resources.as_ref()?)?;
+    let recommended = pricing.monthly_cost(&rec.namespace, &recommended_spec(rec))?;
+    Some((current, recommended))
+}
+
+fn recommended_spec(rec: &Recommendation) -> ResourceSpec {
+    let (cpu_request, cpu_limit, memory_request, memory_limit) = k8s::recommended_quantities(rec);
+    ResourceSpec {
+        cpu_request,
+        cpu_limit,
+        memory_request,
+        memory_limit,
+    }
+}
+
+/// `spec` with its requests raised by the given percentages
+fn with_buffer(spec: &ResourceSpec, cpu_buffer: f64, memory_buffer: f64) -> Option<ResourceSpec> {
+    let cpu = parse_cpu_millicores(&spec.cpu_request)? * (1.0 + cpu_buffer / 100.0);
+    let memory = parse_memory_bytes(&spec.memory_request)? * (1.0 + memory_buffer / 100.0);
+    Some(ResourceSpec {
+        cpu_request: format!("{}m", cpu.ceil() as u64),
+        memory_request: (memory.ceil() as u64).to_string(),
+        ..spec.clone()
+    })
+}
+
+/// Parse a percentage such as `30%` or `30`
+pub fn parse_percent(value: &str) -> Result<f64, String> {
+    let percent: f64 = value
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid percentage '{}', expected e.g. 30%", value))?;
+    if !(0.0..=1000.0).contains(&percent) {
+        return Err(format!(
+            "percentage must be between 0% and 1000%, got {}",
+            value
+        ));
+    }
+    Ok(percent)
+}
+
+fn start_of_next_month(time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let first = time.date_naive().with_day(1)?;
+    Some(
+        first
+            .checked_add_months(Months::new(1))?
+            .and_hms_opt(0, 0, 0)?
+            .and_utc(),
+    )
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{CustomRates, Rates};
+
+    /// 0.10 per core-hour and 0.01 per GiB-hour: 73.00 per core and 7.30
+    /// per GiB each month
+    fn pricing() -> CustomRates {
+        CustomRates {
+            currency: "USD".to_string(),
+            rates: Rates {
+                cpu_per_core_hour: 0.1,
+                memory_per_gib_hour: 0.01,
+            },
+        }
+    }
+
+    fn spec(cpu: &str, memory: &str) -> ResourceSpec {
+        ResourceSpec {
+            cpu_request: cpu.to_string(),
+            cpu_limit: cpu.to_string(),
+            memory_request: memory.to_string(),
+            memory_limit: memory.to_string(),
+        }
+    }
+
+    fn recommendation(
+        namespace: &str,
+        deployment: &str,
+        current: Option<ResourceSpec>,
+        recommended: ResourceSpec,
+    ) -> Recommendation {
+        Recommendation {
+            id: format!("{}-{}", namespace, deployment),
+            namespace: namespace.to_string(),
+            deployment: deployment.to_string(),
+            owner_kind: None,
+            cpu_request_millicores: 0,
+            cpu_limit_millicores: 0,
+            memory_request_bytes: 0,
+            memory_limit_bytes: 0,
+            confidence: 0.9,
+            model_version: "v1".to_string(),
+            status: "pending".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            applied_at: None,
+            time_window: "7d".to_string(),
+            current_resources: current,
+            recommended_resources: Some(recommended),
+            pod_summary: None,
+            workload_cluster: None,
+        }
+    }
+
+    #[test]
+    fn test_forecast_projection() {
+        let latest = vec![
+            // Saves a core: 73.00
+            recommendation("prod", "api", Some(spec("2", "1Gi")), spec("1", "1Gi")),
+            // Saves 512Mi: 3.65
+            recommendation(
+                "prod",
+                "web",
+                Some(spec("500m", "1Gi")),
+                spec("500m", "512Mi"),
+            ),
+            // Saves half a core: 36.50
+            recommendation("dev", "worker", Some(spec("1", "2Gi")), spec("500m", "2Gi")),
+        ];
+        let forecast = forecast_savings(&latest, &pricing());
+
+        assert_eq!(forecast.pending, 3);
+        assert_eq!(forecast.monthly_savings, 113.15);
+        assert_eq!(forecast.annual_savings, 1357.8);
+
+        let savings: Vec<(&str, f64)> = forecast
+            .deployments
+            .iter()
+            .map(|d| (d.deployment.as_str(), d.monthly_savings))
+            .collect();
+        assert_eq!(savings, [("api", 73.0), ("worker", 36.5), ("web", 3.65)]);
+        assert_eq!(forecast.deployments[0].current_monthly_cost, 153.3);
+        assert_eq!(forecast.deployments[0].recommended_monthly_cost, 80.3);
+
+        let namespaces: Vec<(&str, usize, f64)> = forecast
+            .namespaces
+            .iter()
+            .map(|n| (n.namespace.as_str(), n.deployments, n.monthly_savings))
+            .collect();
+        assert_eq!(namespaces, [("prod", 2, 76.65), ("dev", 1, 36.5)]);
+    }
+
+    #[test]
+    fn test_forecast_zero_and_negative_savings() {
+        let latest = vec![
+            recommendation("prod", "same", Some(spec("1", "1Gi")), spec("1", "1Gi")),
+            // Scaling up costs a core
+            recommendation("prod", "grow", Some(spec("1", "1Gi")), spec("2", "1Gi")),
+            // Not priced without current resources
+            recommendation("prod", "unknown", None, spec("1", "1Gi")),
+        ];
+        let forecast = forecast_savings(&latest, &pricing());
+
+        assert_eq!(forecast.pending, 2);
+        assert_eq!(forecast.deployments[0].deployment, "same");
+        assert_eq!(forecast.deployments[0].monthly_savings, 0.0);
+        assert_eq!(forecast.deployments[1].deployment, "grow");
+        assert_eq!(forecast.deployments[1].monthly_savings, -73.0);
+        assert_eq!(forecast.monthly_savings, -73.0);
+        assert_eq!(forecast.annual_savings, -876.0);
+        assert_eq!(forecast.namespaces[0].monthly_savings, -73.0);
+
+        let empty = forecast_savings(&[], &pricing());
+        assert_eq!(empty.pending, 0);
+        assert_eq!(empty.monthly_savings, 0.0);
+        assert!(empty.namespaces.is_empty());
+    }
+
+    #[test]
+    fn test_what_if_buffers() {
+        let mut applied =
+            recommendation("prod", "web", Some(spec("1", "2Gi")), spec("500m", "1Gi"));
+        applied.status = "applied".to_string();
+        let latest = vec![
+            recommendation("prod", "api", Some(spec("2", "2Gi")), spec("1", "1Gi")),
+            applied,
+        ];
+
+        // Recommended: 80.30 + 43.80, running now: 160.60 + 43.80 as web is applied
+        let analysis = what_if_costs(&latest, 50.0, 100.0, &pricing());
+        assert_eq!(analysis.current_monthly_cost, 204.4);
+        assert_eq!(analysis.recommended_monthly_cost, 124.1);
+        // 1.5 cores and 2Gi plus 750m and 2Gi
+        assert_eq!(analysis.deployments[0].what_if_monthly_cost, 124.1);
+        assert_eq!(analysis.deployments[1].what_if_monthly_cost, 69.35);
+        assert_eq!(analysis.what_if_monthly_cost, 193.45);
+        assert_eq!(analysis.what_if_savings, 10.95);
+    }
+
+    #[test]
+    fn test_what_if_zero_and_negative_deltas() {
+        let latest = vec![recommendation(
+            "prod",
+            "api",
+            Some(spec("1", "1Gi")),
+            spec("1", "1Gi"),
+        )];
+
+        // No buffer: same cost as the recommendation, and nothing saved
+        let analysis = what_if_costs(&latest, 0.0, 0.0, &pricing());
+        assert_eq!(
+            analysis.what_if_monthly_cost,
+            analysis.recommended_monthly_cost
+        );
+        assert_eq!(analysis.what_if_monthly_cost, 80.3);
+        assert_eq!(analysis.what_if_savings, 0.0);
+
+        // Buffers above the current requests cost more than running as is
+        let analysis = what_if_costs(&latest, 100.0, 0.0, &pricing());
+        assert_eq!(analysis.what_if_monthly_cost, 153.3);
+        assert_eq!(analysis.what_if_savings, -73.0);
+    }
+
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(parse_percent("30%"), Ok(30.0));
+        assert_eq!(parse_percent(" 0 "), Ok(0.0));
+        assert!(parse_percent("-10%").is_err());
+        assert!(parse_percent("1001%").is_err());
+        assert!(parse_percent("lots").is_err());
     }
 }
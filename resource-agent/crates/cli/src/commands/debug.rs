@@ -1,13 +1,24 @@
 //! Debug and troubleshooting CLI commands
 
-use anyhow::Result;
+use agent_lib::anomaly::DetectorKind;
+use agent_lib::export::ExportFormat;
+use agent_lib::models::ContainerMetrics;
+use agent_lib::predictor::OnnxPredictor;
+use agent_lib::replay::{ReplayConfig, ReplayEventKind, Replayer};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tabled::Tabled;
 
-use crate::client::{AgentStatus, ApiClient, MetricsExport, PredictionHistory};
+use crate::client::{
+    AgentAnomalies, AgentHistory, AgentState, AgentStatus, ApiClient, MetricsExport,
+    PredictionHistory,
+};
 use crate::output::{
-    color_confidence, color_status, format_bytes, format_cpu, print_info, print_success,
-    print_warning, OutputFormat,
+    color_confidence, color_status, format_bytes, format_cpu, format_timestamp, print_error,
+    print_info, print_object, print_success, print_warning, OutputFormat, Watcher,
 };
 
 /// Row for predictions table
@@ -29,61 +40,169 @@ struct PredictionRow {
     model: String,
 }
 
+/// Row for the usage table of `crp debug predictions --agent-url`
+#[derive(Tabled)]
+struct UsageRow {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Pod")]
+    pod: String,
+    #[tabled(rename = "Samples")]
+    samples: usize,
+    #[tabled(rename = "CPU Avg")]
+    cpu_avg: String,
+    #[tabled(rename = "CPU Max")]
+    cpu_max: String,
+    #[tabled(rename = "Mem Max")]
+    memory_max: String,
+}
+
+/// Number of anomalies shown by `crp debug agent`
+const RECENT_ANOMALY_LIMIT: usize = 10;
+
+/// Row for agent anomalies table
+#[derive(Tabled)]
+struct AnomalyRow {
+    #[tabled(rename = "Detected")]
+    detected_at: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Pod")]
+    pod: String,
+    #[tabled(rename = "Type")]
+    anomaly_type: String,
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
 /// Show prediction history for a deployment
 pub async fn show_predictions(
     client: &ApiClient,
     deployment: &str,
-    format: OutputFormat,
+    format: &OutputFormat,
 ) -> Result<()> {
     let path = format!("api/v1/debug/predictions/{}", deployment);
     let result: PredictionHistory = client.get(&path).await?;
 
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&result)?;
-            println!("{}", json);
-        }
-        OutputFormat::Table => {
-            println!("{}", "Prediction History".bold());
-            println!("{}", "=".repeat(60));
-            println!("Deployment: {}", result.deployment.cyan());
-            if !result.namespace.is_empty() {
-                println!("Namespace:  {}", result.namespace.cyan());
-            }
-            println!();
+    print_object(&result, format, || {
+        println!("{}", "Prediction History".bold());
+        println!("{}", "=".repeat(60));
+        println!("Deployment: {}", result.deployment.cyan());
+        if !result.namespace.is_empty() {
+            println!("Namespace:  {}", result.namespace.cyan());
+        }
+        println!();
 
-            if result.predictions.is_empty() {
-                print_warning("No predictions found for this deployment");
-                return Ok(());
-            }
+        if result.predictions.is_empty() {
+            print_warning("No predictions found for this deployment");
+            return Ok(());
+        }
 
-            let rows: Vec<PredictionRow> = result
-                .predictions
-                .iter()
-                .map(|p| PredictionRow {
-                    timestamp: format_timestamp(&p.timestamp),
-                    cpu_request: format_cpu(p.cpu_request_millicores),
-                    cpu_limit: format_cpu(p.cpu_limit_millicores),
-                    memory_request: format_bytes(p.memory_request_bytes),
-                    memory_limit: format_bytes(p.memory_limit_bytes),
-                    confidence: color_confidence(p.confidence),
-                    model: p.model_version.clone(),
-                })
-                .collect();
+        let rows: Vec<PredictionRow> = result
+            .predictions
+            .iter()
+            .map(|p| PredictionRow {
+                timestamp: format_timestamp(&p.timestamp),
+                cpu_request: format_cpu(p.cpu_request_millicores),
+                cpu_limit: format_cpu(p.cpu_limit_millicores),
+                memory_request: format_bytes(p.memory_request_bytes),
+                memory_limit: format_bytes(p.memory_limit_bytes),
+                confidence: color_confidence(p.confidence),
+                model: p.model_version.clone(),
+            })
+            .collect();
 
-            let table = tabled::Table::new(rows)
-                .with(tabled::settings::Style::rounded())
-                .to_string();
-            println!("{}", table);
-            println!("\nTotal: {} predictions", result.predictions.len());
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+        println!("\nTotal: {} predictions", result.predictions.len());
+        Ok(())
+    })
+}
+
+/// Show the usage a deployment's predictions are made from, as kept by the
+/// agent on its node for the window `since`
+pub async fn show_prediction_usage(
+    agent: &ApiClient,
+    deployment: &str,
+    since: &str,
+    format: &OutputFormat,
+) -> Result<()> {
+    let from = chrono::Utc::now().timestamp() - parse_since(since)?;
+    let mut history: AgentHistory = agent.get(&format!("history?from={}", from)).await?;
+
+    let (namespace, name) = match deployment.split_once('/') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, deployment),
+    };
+    history.samples.retain(|m| {
+        m.owner.as_ref().is_some_and(|owner| owner.name == name)
+            && namespace.map_or(true, |ns| m.namespace == ns)
+    });
+    history.total = history.samples.len();
+
+    print_object(&history, format, || {
+        println!();
+        println!(
+            "{}",
+            format!("Usage Since {} (from the agent)", since).bold()
+        );
+        println!("{}", "-".repeat(60));
+        if history.samples.is_empty() {
+            print_warning("The agent holds no samples for this deployment");
+            return Ok(());
         }
-    }
 
-    Ok(())
+        let mut pods: Vec<(&str, &str, Vec<&ContainerMetrics>)> = Vec::new();
+        for m in &history.samples {
+            match pods
+                .iter_mut()
+                .find(|(ns, pod, _)| *ns == m.namespace && *pod == m.pod_name)
+            {
+                Some((_, _, samples)) => samples.push(m),
+                None => pods.push((&m.namespace, &m.pod_name, vec![m])),
+            }
+        }
+
+        let rows: Vec<UsageRow> = pods
+            .into_iter()
+            .map(|(namespace, pod, samples)| {
+                let cpu: Vec<f32> = samples.iter().map(|m| m.cpu_usage_cores).collect();
+                let cpu_avg = cpu.iter().sum::<f32>() / cpu.len() as f32;
+                let cpu_max = cpu.iter().copied().fold(0.0, f32::max);
+                let memory_max = samples
+                    .iter()
+                    .map(|m| m.memory_working_set_bytes)
+                    .max()
+                    .unwrap_or(0);
+                UsageRow {
+                    namespace: namespace.to_string(),
+                    pod: pod.to_string(),
+                    samples: samples.len(),
+                    cpu_avg: format_cpu((cpu_avg * 1000.0) as u32),
+                    cpu_max: format_cpu((cpu_max * 1000.0) as u32),
+                    memory_max: format_bytes(memory_max),
+                }
+            })
+            .collect();
+
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+        Ok(())
+    })
 }
 
 /// Show agent status on a node
-pub async fn show_agent_status(client: &ApiClient, node: &str, format: OutputFormat) -> Result<()> {
+pub async fn show_agent_status(
+    client: &ApiClient,
+    node: &str,
+    format: &OutputFormat,
+) -> Result<()> {
     // Try to get agent status from the API
     // Note: This endpoint may need to be added to the API
     let path = format!("api/v1/agents/{}", node);
@@ -93,42 +212,68 @@ pub async fn show_agent_status(client: &ApiClient, node: &str, format: OutputFor
     let result: Result<AgentStatus, _> = client.get(&path).await;
 
     match result {
-        Ok(status) => match format {
-            OutputFormat::Json => {
-                let json = serde_json::to_string_pretty(&status)?;
-                println!("{}", json);
-            }
-            OutputFormat::Table => {
-                println!("{}", "Agent Status".bold());
-                println!("{}", "=".repeat(50));
-                println!("Node:                   {}", status.node.cyan());
-                println!("Status:                 {}", color_status(&status.status));
-                println!(
-                    "Last Seen:              {}",
-                    format_timestamp(&status.last_seen)
-                );
+        Ok(status) => print_object(&status, format, || {
+            println!("{}", "Agent Status".bold());
+            println!("{}", "=".repeat(50));
+            println!("Node:                   {}", status.node.cyan());
+            println!("Status:                 {}", color_status(&status.status));
+            println!(
+                "Last Seen:              {}",
+                format_timestamp(&status.last_seen)
+            );
+            println!();
+            println!("{}", "Metrics".bold());
+            println!("{}", "-".repeat(50));
+            println!("Containers Monitored:   {}", status.containers_monitored);
+            println!("Model Version:          {}", status.model_version);
+            println!(
+                "Buffer Size:            {}",
+                format_bytes(status.buffer_size_bytes)
+            );
+            println!();
+            println!("{}", "Performance".bold());
+            println!("{}", "-".repeat(50));
+            println!(
+                "Collection Latency:     {:.2}ms",
+                status.collection_latency_ms
+            );
+            println!(
+                "Prediction Latency:     {:.2}ms",
+                status.prediction_latency_ms
+            );
+
+            if let Some(heartbeat) = &status.heartbeat {
                 println!();
-                println!("{}", "Metrics".bold());
+                println!("{}", "Heartbeat".bold());
                 println!("{}", "-".repeat(50));
-                println!("Containers Monitored:   {}", status.containers_monitored);
-                println!("Model Version:          {}", status.model_version);
                 println!(
-                    "Buffer Size:            {}",
-                    format_bytes(status.buffer_size_bytes)
+                    "Last Heartbeat:         {}",
+                    format_timestamp(&heartbeat.received_at)
                 );
-                println!();
-                println!("{}", "Performance".bold());
-                println!("{}", "-".repeat(50));
+                println!("Agent Version:          {}", heartbeat.agent_version);
                 println!(
-                    "Collection Latency:     {:.2}ms",
-                    status.collection_latency_ms
+                    "Last Collection:        {}",
+                    heartbeat
+                        .last_collection_at
+                        .as_deref()
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "never".to_string())
                 );
+                println!("Buffered Samples:       {}", heartbeat.buffer_items);
                 println!(
-                    "Prediction Latency:     {:.2}ms",
-                    status.prediction_latency_ms
+                    "Errors:                 collection={} prediction={} sync={}",
+                    heartbeat.collection_errors, heartbeat.prediction_errors, heartbeat.sync_errors
                 );
+
+                let mut components: Vec<_> = heartbeat.unhealthy_components.iter().collect();
+                components.sort();
+                for (component, message) in components {
+                    print_warning(&format!("{}: {}", component, message));
+                }
             }
-        },
+
+            Ok(())
+        })?,
         Err(_) => {
             // Endpoint might not exist, provide helpful message
             print_warning(&format!(
@@ -145,16 +290,255 @@ pub async fn show_agent_status(client: &ApiClient, node: &str, format: OutputFor
     Ok(())
 }
 
-/// Export metrics data
+/// Row for agent containers table
+#[derive(Tabled)]
+struct ContainerRow {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Pod")]
+    pod: String,
+    #[tabled(rename = "Deployment")]
+    deployment: String,
+    #[tabled(rename = "Container")]
+    container_id: String,
+}
+
+/// Poll agent status, and state when an agent URL is given, until interrupted
+pub async fn watch_agent(
+    client: &ApiClient,
+    node: &str,
+    agent: Option<&ApiClient>,
+    interval: Duration,
+    format: &OutputFormat,
+) -> Result<()> {
+    let mut watcher = Watcher::new(interval, format);
+    while watcher.next().await {
+        show_agent_status(client, node, format).await?;
+        if let Some(agent) = agent {
+            if let Err(e) = show_agent_state(agent, format).await {
+                print_error(&format!("{:#}", e));
+            }
+            if let Err(e) = show_agent_anomalies(agent, format).await {
+                print_error(&format!("{:#}", e));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the latest anomalies kept by an agent's /anomalies endpoint
+pub async fn show_agent_anomalies(agent: &ApiClient, format: &OutputFormat) -> Result<()> {
+    let path = format!("anomalies?limit={}", RECENT_ANOMALY_LIMIT);
+    let result: AgentAnomalies = agent.get(&path).await?;
+
+    print_object(&result, format, || {
+        if result.anomalies.is_empty() {
+            return Ok(());
+        }
+
+        println!();
+        println!("{}", "Recent Anomalies".bold());
+        println!("{}", "-".repeat(50));
+
+        let rows: Vec<AnomalyRow> = result
+            .anomalies
+            .iter()
+            .map(|a| AnomalyRow {
+                detected_at: format_unix_timestamp(a.detected_at),
+                namespace: a.namespace.clone(),
+                pod: a.pod_name.clone(),
+                anomaly_type: a.anomaly_type.clone(),
+                severity: color_status(&a.severity),
+                message: a.message.clone(),
+            })
+            .collect();
+
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+        Ok(())
+    })
+}
+
+/// Show the internal state reported by an agent's /state endpoint
+pub async fn show_agent_state(agent: &ApiClient, format: &OutputFormat) -> Result<()> {
+    let state: AgentState = agent.get("state").await?;
+
+    if *format != OutputFormat::Table {
+        return print_object(&state, format, || Ok(()));
+    }
+
+    println!();
+    println!("{}", "Agent Internal State".bold());
+    println!("{}", "=".repeat(50));
+    println!("Node:                   {}", state.node_name.cyan());
+    println!(
+        "Captured:               {}",
+        format_unix_timestamp(state.captured_at)
+    );
+
+    if let Some(connection) = &state.connection {
+        println!();
+        println!("{}", "Connection".bold());
+        println!("{}", "-".repeat(50));
+        println!(
+            "Connected:              {}",
+            if connection.connected { "yes" } else { "no" }
+        );
+        println!("Circuit:                {}", connection.circuit_state);
+        println!("Reconnect Attempts:     {}", connection.reconnect_attempts);
+        if let Some(error) = &connection.last_error {
+            print_warning(&format!("Last error: {}", error));
+        }
+    }
+
+    if let Some(streaming) = &state.streaming {
+        println!();
+        println!("{}", "Streaming".bold());
+        println!("{}", "-".repeat(50));
+        println!("Batches Sent:           {}", streaming.batches_sent);
+        println!("Failures:               {}", streaming.failures);
+        println!("Streams Opened:         {}", streaming.streams_opened);
+        println!(
+            "Last Sync:              {}",
+            streaming
+                .last_sync_secs_ago
+                .map(|secs| format!("{}s ago", secs))
+                .unwrap_or_else(|| "never".to_string())
+        );
+        if streaming.throttled {
+            print_warning("Sync traffic is currently throttled");
+        }
+        if let Some(error) = &streaming.last_error {
+            print_warning(&format!("Last error: {}", error));
+        }
+    }
+
+    if let Some(buffer) = &state.buffer {
+        println!();
+        println!("{}", "Offline Buffer".bold());
+        println!("{}", "-".repeat(50));
+        println!(
+            "Entries:                {} / {}",
+            buffer.entries, buffer.capacity
+        );
+        println!(
+            "Memory:                 {}",
+            format_bytes(buffer.memory_bytes)
+        );
+        if let Some(oldest) = buffer.oldest_timestamp {
+            println!(
+                "Oldest Entry:           {}",
+                format_unix_timestamp(oldest as i64)
+            );
+        }
+    }
+
+    if let Some(scheduler) = &state.scheduler {
+        println!();
+        println!("{}", "Prediction Scheduler".bold());
+        println!("{}", "-".repeat(50));
+        println!("Containers Tracked:     {}", scheduler.total_containers);
+        println!(
+            "With Predictions:       {}",
+            scheduler.containers_with_predictions
+        );
+        println!("Stale Predictions:      {}", scheduler.stale_predictions);
+        println!("Buffered Samples:       {}", scheduler.total_samples);
+    }
+
+    if let Some(model) = &state.model_update {
+        println!();
+        println!("{}", "Model".bold());
+        println!("{}", "-".repeat(50));
+        println!(
+            "Current Version:        {}",
+            model.current_version.as_deref().unwrap_or("none")
+        );
+        println!(
+            "Rollback Versions:      {}",
+            model.available_rollback_versions
+        );
+        if let Some(updated) = model.last_update_time {
+            println!("Last Update:            {}", format_unix_timestamp(updated));
+        }
+    }
+
+    if let Some(pipeline) = &state.anomaly_pipeline {
+        println!();
+        println!("{}", "Anomaly Detectors".bold());
+        println!("{}", "-".repeat(50));
+        for detector in &pipeline.detectors {
+            let status = if detector.enabled {
+                format!("threshold {}", detector.threshold)
+            } else {
+                "disabled".dimmed().to_string()
+            };
+            println!("{:<24}{}", format!("{}:", detector.kind.as_str()), status);
+            for (namespace, threshold) in &detector.namespace_thresholds {
+                println!("  {:<22}threshold {}", format!("{}:", namespace), threshold);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("Containers ({})", state.containers.len()).bold()
+    );
+    println!("{}", "-".repeat(50));
+    if state.containers.is_empty() {
+        print_info("No containers registered");
+    } else {
+        let rows: Vec<ContainerRow> = state
+            .containers
+            .iter()
+            .map(|c| ContainerRow {
+                namespace: c.namespace.clone(),
+                pod: c.pod_name.clone(),
+                deployment: c.deployment.clone().unwrap_or_default(),
+                container_id: c.container_id.chars().take(12).collect(),
+            })
+            .collect();
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+    }
+
+    Ok(())
+}
+
+/// Options of `crp debug export`
+pub struct ExportOptions {
+    pub since: String,
+    pub output: Option<String>,
+    pub namespace: Option<String>,
+    /// `json`, `csv` or `parquet`, from the output extension when unset
+    pub file_format: Option<String>,
+    /// Agent samples to export, `buffer` or `live`, the agent's default when
+    /// unset
+    pub source: Option<String>,
+}
+
+/// Export metrics data, from the API or straight from an agent
 pub async fn export_metrics(
     client: &ApiClient,
-    since: &str,
-    output: Option<String>,
-    namespace: Option<String>,
-    format: OutputFormat,
+    agent: Option<&ApiClient>,
+    options: &ExportOptions,
+    format: &OutputFormat,
 ) -> Result<()> {
+    let file_format = export_file_format(options)?;
+    if let Some(agent) = agent {
+        let file_format = file_format.unwrap_or(ExportFormat::Csv);
+        return export_agent_metrics(agent, options, file_format).await;
+    }
+
+    let since = &options.since;
     let mut path = format!("api/v1/metrics/export?since={}", since);
-    if let Some(ns) = &namespace {
+    if let Some(ns) = &options.namespace {
         path.push_str(&format!("&namespace={}", ns));
     }
 
@@ -163,36 +547,43 @@ pub async fn export_metrics(
 
     match result {
         Ok(export) => {
+            if let Some(file_format) = file_format {
+                let entries = export.metrics.len();
+                let data = agent_lib::export::export(&export_samples(export)?, file_format)?;
+                write_export(&data, options.output.as_deref(), file_format)?;
+                if options.output.is_some() {
+                    println!("Exported {} metric entries", entries);
+                }
+                return Ok(());
+            }
+
             let json = serde_json::to_string_pretty(&export)?;
 
-            if let Some(output_path) = output {
-                std::fs::write(&output_path, &json)?;
+            if let Some(output_path) = &options.output {
+                std::fs::write(output_path, &json)?;
                 print_success(&format!("Metrics exported to {}", output_path));
                 println!("Exported {} metric entries", export.metrics.len());
             } else {
-                match format {
-                    OutputFormat::Json => {
-                        println!("{}", json);
-                    }
-                    OutputFormat::Table => {
-                        println!("{}", "Metrics Export".bold());
-                        println!("{}", "=".repeat(50));
-                        println!("Period:     {}", export.since);
-                        if let Some(ns) = &export.namespace {
-                            println!("Namespace:  {}", ns);
-                        }
-                        println!("Entries:    {}", export.metrics.len());
-                        println!();
-                        print_info("Use --output <file> to save to a file");
-                        print_info("Use --format json to see full data");
+                print_object(&export, format, || {
+                    println!("{}", "Metrics Export".bold());
+                    println!("{}", "=".repeat(50));
+                    println!("Period:     {}", export.since);
+                    if let Some(ns) = &export.namespace {
+                        println!("Namespace:  {}", ns);
                     }
-                }
+                    println!("Entries:    {}", export.metrics.len());
+                    println!();
+                    print_info("Use --output <file> to save to a file");
+                    print_info("Use --format json to see full data");
+                    Ok(())
+                })?;
             }
         }
         Err(_) => {
             // Endpoint might not exist, provide helpful message
             print_warning("Could not export metrics");
             print_info("The metrics export endpoint may not be available.");
+            print_info("Use --agent-url to export from a node's agent instead.");
             print_info("You can export metrics directly from TimescaleDB:");
             println!();
             println!("  psql -h <db-host> -U <user> -d predictor -c \\");
@@ -203,11 +594,258 @@ pub async fn export_metrics(
     Ok(())
 }
 
-/// Format timestamp for display
-fn format_timestamp(ts: &str) -> String {
-    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(ts) {
-        dt.format("%Y-%m-%d %H:%M:%S").to_string()
-    } else {
-        ts.to_string()
+/// Export the samples of one agent, as CSV or Parquet
+async fn export_agent_metrics(
+    agent: &ApiClient,
+    options: &ExportOptions,
+    file_format: ExportFormat,
+) -> Result<()> {
+    let from = chrono::Utc::now().timestamp() - parse_since(&options.since)?;
+    let mut path = format!("export?format={}&from={}", file_format.extension(), from);
+    if let Some(source) = &options.source {
+        path.push_str(&format!("&source={}", source));
     }
+    if let Some(ns) = &options.namespace {
+        path.push_str(&format!("&namespace={}", ns));
+    }
+
+    let data = agent.get_bytes(&path).await?;
+    write_export(&data, options.output.as_deref(), file_format)
+}
+
+/// Format of the export file, `None` for JSON
+fn export_file_format(options: &ExportOptions) -> Result<Option<ExportFormat>> {
+    let name = match (&options.file_format, &options.output) {
+        (Some(name), _) => name.as_str(),
+        (None, Some(output)) => Path::new(output)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("json"),
+        (None, None) => "json",
+    };
+    if name.eq_ignore_ascii_case("json") {
+        return Ok(None);
+    }
+    name.parse().map(Some)
+}
+
+/// Write an export to `output`, or CSV to stdout
+fn write_export(data: &[u8], output: Option<&str>, file_format: ExportFormat) -> Result<()> {
+    match output {
+        Some(output_path) => {
+            std::fs::write(output_path, data)?;
+            print_success(&format!("Metrics exported to {}", output_path));
+        }
+        None if file_format == ExportFormat::Csv => {
+            std::io::stdout().write_all(data)?;
+        }
+        None => bail!("Use --output <file> to export {}", file_format.extension()),
+    }
+    Ok(())
+}
+
+/// Duration such as `90m`, `24h` or `7d`, in seconds
+fn parse_since(since: &str) -> Result<i64> {
+    let (value, unit) = since.split_at(since.len().saturating_sub(1));
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => bail!("Invalid period '{}', expected e.g. 30m, 24h or 7d", since),
+    };
+    let value: i64 = value
+        .parse()
+        .with_context(|| format!("Invalid period '{}', expected e.g. 30m, 24h or 7d", since))?;
+    Ok(value * multiplier)
+}
+
+/// Samples of an API export
+///
+/// Exports carry a single memory figure, used as both usage and working set.
+fn export_samples(export: MetricsExport) -> Result<Vec<ContainerMetrics>> {
+    export
+        .metrics
+        .into_iter()
+        .map(|entry| {
+            let timestamp = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                .with_context(|| format!("Invalid timestamp '{}'", entry.timestamp))?
+                .timestamp();
+            Ok(ContainerMetrics {
+                container_id: entry.container_id,
+                pod_name: entry.pod_name,
+                namespace: entry.namespace,
+                owner: None,
+                timestamp,
+                cpu_usage_cores: entry.cpu_usage_cores,
+                cpu_throttled_periods: 0,
+                memory_usage_bytes: entry.memory_usage_bytes,
+                memory_working_set_bytes: entry.memory_usage_bytes,
+                memory_cache_bytes: 0,
+                network_rx_bytes: 0,
+                network_tx_bytes: 0,
+            })
+        })
+        .collect()
+}
+
+/// Row for replay events table
+#[derive(Tabled)]
+struct ReplayRow {
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Pod")]
+    pod: String,
+    #[tabled(rename = "Event")]
+    event: String,
+    #[tabled(rename = "Details")]
+    details: String,
+}
+
+/// Overrides of the replay defaults
+pub struct ReplayOptions {
+    pub model: Option<PathBuf>,
+    pub prediction_interval: Option<Duration>,
+    pub leak_threshold: Option<f64>,
+    pub spike_threshold: Option<f64>,
+    pub anomalies_only: bool,
+}
+
+/// Replay exported metrics through prediction and anomaly detection
+pub fn replay(file: &Path, options: &ReplayOptions, format: &OutputFormat) -> Result<()> {
+    let metrics = read_replay_metrics(file)?;
+
+    let mut config = ReplayConfig::default();
+    if let Some(interval) = options.prediction_interval {
+        config.prediction_interval = interval;
+    }
+    if let Some(threshold) = options.leak_threshold {
+        config
+            .anomaly
+            .set_threshold(DetectorKind::MemoryLeak, threshold);
+    }
+    if let Some(threshold) = options.spike_threshold {
+        config
+            .anomaly
+            .set_threshold(DetectorKind::CpuSpike, threshold);
+    }
+    let replayer = match &options.model {
+        Some(path) => {
+            let bytes = std::fs::read(path)
+                .with_context(|| format!("Failed to read model {}", path.display()))?;
+            Replayer::with_predictor(config, Box::new(OnnxPredictor::new(&bytes)?))
+        }
+        None => Replayer::new(config),
+    };
+
+    let mut report = replayer.run(metrics);
+    let predictions = report.predictions();
+    let anomalies = report.anomalies();
+    if options.anomalies_only {
+        report
+            .events
+            .retain(|e| !matches!(e.kind, ReplayEventKind::Prediction { .. }));
+    }
+
+    print_object(&report, format, || {
+        println!(
+            "Replayed {} samples of {} containers: {} predictions, {} anomalies",
+            report.samples, report.containers, predictions, anomalies
+        );
+        if report.events.is_empty() {
+            return Ok(());
+        }
+        let rows: Vec<ReplayRow> = report
+            .events
+            .iter()
+            .map(|e| {
+                let (event, details) = match &e.kind {
+                    ReplayEventKind::Prediction {
+                        profile,
+                        fallback_reason,
+                    } => {
+                        let mut details = format!(
+                            "cpu {}/{}, memory {}/{}, confidence {}",
+                            format_cpu(profile.cpu_request_millicores),
+                            format_cpu(profile.cpu_limit_millicores),
+                            format_bytes(profile.memory_request_bytes),
+                            format_bytes(profile.memory_limit_bytes),
+                            color_confidence(profile.confidence)
+                        );
+                        if let Some(reason) = fallback_reason {
+                            details.push_str(&format!(" (fallback: {})", reason));
+                        }
+                        ("prediction".to_string(), details)
+                    }
+                    ReplayEventKind::MemoryLeak(leak) => (
+                        "memory leak".yellow().to_string(),
+                        format!(
+                            "{:.1} MB/h at {}, confidence {}",
+                            leak.leak_rate_mb_per_hour(),
+                            format_bytes(leak.current_memory_bytes),
+                            color_confidence(leak.confidence)
+                        ),
+                    ),
+                    ReplayEventKind::CpuSpike(spike) => (
+                        "cpu spike".yellow().to_string(),
+                        format!(
+                            "{:.2} cores, expected {:.2} (z={:.1})",
+                            spike.current_usage, spike.expected_usage, spike.z_score
+                        ),
+                    ),
+                    ReplayEventKind::CpuThrottling(throttle) => (
+                        "cpu throttling".yellow().to_string(),
+                        format!(
+                            "{:.1} throttled periods/s (threshold {:.1})",
+                            throttle.throttled_periods_per_sec, throttle.threshold
+                        ),
+                    ),
+                    ReplayEventKind::NetworkSpike(spike) => (
+                        "network spike".yellow().to_string(),
+                        format!(
+                            "{}/s, expected {}/s (z={:.1})",
+                            format_bytes(spike.current_usage as u64),
+                            format_bytes(spike.expected_usage as u64),
+                            spike.z_score
+                        ),
+                    ),
+                };
+                ReplayRow {
+                    time: format_unix_timestamp(e.timestamp),
+                    namespace: e.key.namespace.to_string(),
+                    pod: e.key.pod_name.to_string(),
+                    event,
+                    details,
+                }
+            })
+            .collect();
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::rounded())
+            .to_string();
+        println!("{}", table);
+        Ok(())
+    })
+}
+
+/// Read samples from a `crp debug export` file or a JSON array of agent
+/// samples
+fn read_replay_metrics(file: &Path) -> Result<Vec<ContainerMetrics>> {
+    let data = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    if let Ok(samples) = serde_json::from_str::<Vec<ContainerMetrics>>(&data) {
+        return Ok(samples);
+    }
+
+    let export: MetricsExport = serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a metrics export", file.display()))?;
+    export_samples(export)
+}
+
+/// Format a Unix timestamp (seconds) for display
+fn format_unix_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ts.to_string())
 }
@@ -0,0 +1,322 @@
+//! Diff of live Deployment resources against the latest recommendation
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::Container;
+use kube::Api;
+use serde::Serialize;
+
+use crate::client::{ApiClient, Recommendation};
+use crate::k8s::{self, KubeOptions};
+use crate::output::{parse_cpu_millicores, parse_memory_bytes, print_object, OutputFormat};
+
+/// Requests and limits of one container, as Kubernetes quantities
+#[derive(Debug, Clone, Default, Serialize)]
+struct ResourceValues {
+    cpu_request: Option<String>,
+    cpu_limit: Option<String>,
+    memory_request: Option<String>,
+    memory_limit: Option<String>,
+}
+
+/// Comparison for one container of the Deployment
+#[derive(Debug, Serialize)]
+struct ContainerDiff {
+    name: String,
+    current: ResourceValues,
+    /// Set for the container the recommendation targets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recommended: Option<ResourceValues>,
+    changed: bool,
+}
+
+/// Comparison of pod totals: summed requests and the largest limits
+#[derive(Debug, Serialize)]
+struct PodDiff {
+    current: ResourceValues,
+    recommended: ResourceValues,
+    changed: bool,
+}
+
+/// Full diff for a Deployment
+#[derive(Debug, Serialize)]
+struct DeploymentDiff {
+    namespace: String,
+    deployment: String,
+    recommendation_id: String,
+    confidence: f32,
+    status: String,
+    containers: Vec<ContainerDiff>,
+    /// Set when the recommendation carries a pod summary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pod: Option<PodDiff>,
+}
+
+/// Show live vs recommended resources for `namespace/deployment`
+pub async fn show_diff(
+    client: &ApiClient,
+    kube: &KubeOptions,
+    target: &str,
+    format: &OutputFormat,
+) -> Result<()> {
+    let (namespace, name) = target
+        .split_once('/')
+        .filter(|(ns, name)| !ns.is_empty() && !name.is_empty())
+        .with_context(|| format!("Expected <namespace>/<deployment>, got '{}'", target))?;
+
+    let kube_client = k8s::client(kube).await?;
+    let deployment = Api::<Deployment>::namespaced(kube_client, namespace)
+        .get(name)
+        .await
+        .with_context(|| format!("Failed to get deployment {}/{}", namespace, name))?;
+
+    let path = format!("api/v1/recommendations/{}/{}", namespace, name);
+    let recommendation: Recommendation = client.get(&path).await?;
+
+    let containers = deployment
+        .spec
+        .and_then(|spec| spec.template.spec)
+        .map(|spec| spec.containers)
+        .unwrap_or_default();
+    let diff = DeploymentDiff {
+        namespace: namespace.to_string(),
+        deployment: name.to_string(),
+        recommendation_id: recommendation.id.clone(),
+        confidence: recommendation.confidence,
+        status: recommendation.status.clone(),
+        containers: diff_containers(&containers, &recommendation),
+        pod: diff_pod(&containers, &recommendation),
+    };
+
+    print_object(&diff, format, || {
+        print_diff(&diff);
+        Ok(())
+    })
+}
+
+/// Compare every container against the recommendation
+fn diff_containers(
+    containers: &[Container],
+    recommendation: &Recommendation,
+) -> Vec<ContainerDiff> {
+    let recommended = k8s::recommended_containers(recommendation, containers);
+
+    containers
+        .iter()
+        .enumerate()
+        .map(|(index, container)| {
+            let current = current_values(container);
+            let recommended = recommended
+                .iter()
+                .find(|(i, _)| *i == index)
+                .map(|(_, quantities)| quantity_values(quantities.clone()));
+            let changed = recommended
+                .as_ref()
+                .is_some_and(|recommended| differs(&current, recommended));
+            ContainerDiff {
+                name: container.name.clone(),
+                current,
+                recommended,
+                changed,
+            }
+        })
+        .collect()
+}
+
+fn current_values(container: &Container) -> ResourceValues {
+    let resources = container.resources.as_ref();
+    let quantity = |limits: bool, resource: &str| {
+        resources
+            .and_then(|r| {
+                if limits {
+                    r.limits.as_ref()
+                } else {
+                    r.requests.as_ref()
+                }
+            })
+            .and_then(|values| values.get(resource))
+            .map(|q| q.0.clone())
+    };
+    ResourceValues {
+        cpu_request: quantity(false, "cpu"),
+        cpu_limit: quantity(true, "cpu"),
+        memory_request: quantity(false, "memory"),
+        memory_limit: quantity(true, "memory"),
+    }
+}
+
+fn quantity_values(quantities: k8s::Quantities) -> ResourceValues {
+    let (cpu_request, cpu_limit, memory_request, memory_limit) = quantities;
+    ResourceValues {
+        cpu_request: Some(cpu_request),
+        cpu_limit: Some(cpu_limit),
+        memory_request: Some(memory_request),
+        memory_limit: Some(memory_limit),
+    }
+}
+
+/// Compare the pod's totals against the recommendation's pod summary
+fn diff_pod(containers: &[Container], recommendation: &Recommendation) -> Option<PodDiff> {
+    let pod = recommendation.pod_summary.as_ref()?;
+    let current = pod_totals(containers);
+    let recommended = quantity_values((
+        k8s::cpu_quantity(pod.cpu_request_millicores),
+        k8s::cpu_quantity(pod.cpu_limit_millicores),
+        k8s::memory_quantity(pod.memory_request_bytes),
+        k8s::memory_quantity(pod.memory_limit_bytes),
+    ));
+    let changed = differs(&current, &recommended);
+    Some(PodDiff {
+        current,
+        recommended,
+        changed,
+    })
+}
+
+/// Sum of the containers' requests and the largest of their limits
+///
+/// A total is unset when no container sets the value.
+fn pod_totals(containers: &[Container]) -> ResourceValues {
+    let values: Vec<ResourceValues> = containers.iter().map(current_values).collect();
+    let cpu = |millicores: f64| k8s::cpu_quantity(millicores.round() as u32);
+    let memory = |bytes: f64| k8s::memory_quantity(bytes.round() as u64);
+
+    ResourceValues {
+        cpu_request: parsed(&values, |v| &v.cpu_request, parse_cpu_millicores)
+            .reduce(|a, b| a + b)
+            .map(cpu),
+        cpu_limit: parsed(&values, |v| &v.cpu_limit, parse_cpu_millicores)
+            .reduce(f64::max)
+            .map(cpu),
+        memory_request: parsed(&values, |v| &v.memory_request, parse_memory_bytes)
+            .reduce(|a, b| a + b)
+            .map(memory),
+        memory_limit: parsed(&values, |v| &v.memory_limit, parse_memory_bytes)
+            .reduce(f64::max)
+            .map(memory),
+    }
+}
+
+/// Parsed values of one field, skipping containers that don't set it
+fn parsed(
+    values: &[ResourceValues],
+    field: fn(&ResourceValues) -> &Option<String>,
+    parse: fn(&str) -> Option<f64>,
+) -> impl Iterator<Item = f64> + '_ {
+    values
+        .iter()
+        .filter_map(move |v| field(v).as_deref().and_then(parse))
+}
+
+/// A resource field with its current and recommended values and parser
+type Field<'a> = (
+    &'static str,
+    &'a Option<String>,
+    &'a Option<String>,
+    fn(&str) -> Option<f64>,
+);
+
+fn fields<'a>(current: &'a ResourceValues, recommended: &'a ResourceValues) -> [Field<'a>; 4] {
+    [
+        (
+            "requests.cpu",
+            &current.cpu_request,
+            &recommended.cpu_request,
+            parse_cpu_millicores,
+        ),
+        (
+            "requests.memory",
+            &current.memory_request,
+            &recommended.memory_request,
+            parse_memory_bytes,
+        ),
+        (
+            "limits.cpu",
+            &current.cpu_limit,
+            &recommended.cpu_limit,
+            parse_cpu_millicores,
+        ),
+        (
+            "limits.memory",
+            &current.memory_limit,
+            &recommended.memory_limit,
+            parse_memory_bytes,
+        ),
+    ]
+}
+
+fn differs(current: &ResourceValues, recommended: &ResourceValues) -> bool {
+    fields(current, recommended)
+        .into_iter()
+        .any(|(_, current, recommended, parse)| !same_quantity(current, recommended, parse))
+}
+
+/// Compare quantities by value so `1` and `1000m` are equal
+fn same_quantity(a: &Option<String>, b: &Option<String>, parse: fn(&str) -> Option<f64>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => match (parse(a), parse(b)) {
+            (Some(a), Some(b)) => (a - b).abs() < f64::EPSILON * a.abs().max(1.0),
+            _ => a == b,
+        },
+        (a, b) => a == b,
+    }
+}
+
+/// Print a kubectl-diff style comparison
+fn print_diff(diff: &DeploymentDiff) {
+    println!(
+        "{} {}/{}",
+        "Deployment".bold(),
+        diff.namespace,
+        diff.deployment
+    );
+    println!(
+        "Recommendation {} ({}, confidence {:.0}%)",
+        diff.recommendation_id,
+        diff.status,
+        diff.confidence * 100.0
+    );
+
+    for container in &diff.containers {
+        println!();
+        println!("  container {}", container.name.cyan());
+        match &container.recommended {
+            Some(recommended) => print_changes(&container.current, recommended),
+            None => print_changes(&container.current, &container.current),
+        }
+    }
+
+    if let Some(pod) = &diff.pod {
+        println!();
+        println!("  {}", "pod total".cyan());
+        print_changes(&pod.current, &pod.recommended);
+    }
+
+    println!();
+    if diff.containers.iter().any(|c| c.changed) {
+        println!("Apply with: crp apply {}", diff.recommendation_id);
+    } else if diff.containers.iter().all(|c| c.recommended.is_none()) {
+        println!(
+            "{}",
+            "No container matches the recommendation target".yellow()
+        );
+    } else {
+        println!("{}", "No changes".green());
+    }
+}
+
+/// Print each field, as a -/+ pair when the recommendation changes it
+fn print_changes(current: &ResourceValues, recommended: &ResourceValues) {
+    let unset = "<unset>".to_string();
+    for (field, current, new, parse) in fields(current, recommended) {
+        let current_value = current.as_ref().unwrap_or(&unset);
+        if same_quantity(current, new, parse) {
+            println!("    {:<16} {}", field, current_value);
+        } else {
+            let new_value = new.as_ref().unwrap_or(&unset);
+            println!("{}", format!("-   {:<16} {}", field, current_value).red());
+            println!("{}", format!("+   {:<16} {}", field, new_value).green());
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Apply recommendations by patching workloads through the Kubernetes API
+//!
+//! Bypasses the CRP API's apply flow: the recommendation is fetched from the
+//! API, then the target Deployment or StatefulSet is patched with the
+//! recommended requests and limits using the CLI's kubeconfig and context.
+//!
+//! Changing resources restarts every pod of the workload. Before patching,
+//! the PodDisruptionBudgets selecting its pods are checked against how many
+//! pods the rollout takes down at once; a rollout that would exceed a budget
+//! is refused unless `--stagger` caps the Deployment's `maxUnavailable`.
+
+use agent_lib::collector::disruption_budgets_for_pod;
+use agent_lib::models::DisruptionBudget;
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec, StatefulSet};
+use k8s_openapi::api::core::v1::{Container, PodTemplateSpec};
+use k8s_openapi::api::policy::v1::PodDisruptionBudget;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::Api;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::client::{ApiClient, Recommendation};
+use crate::error::{CliError, ErrorKind};
+use crate::k8s::{self, KubeOptions, APPLIED_AT_ANNOTATION, RECOMMENDATION_ANNOTATION};
+use crate::output::{print_object, print_success, print_warning, OutputFormat};
+
+/// Field manager recorded for patches made by the CLI
+const FIELD_MANAGER: &str = "crp";
+
+/// Where a dry-run is evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DryRunMode {
+    /// Only show what would be changed
+    Client,
+    /// Submit the patch to the API server without persisting it
+    Server,
+}
+
+/// Kind of workload a recommendation targets
+#[derive(Debug, Clone, Copy, Serialize)]
+enum WorkloadKind {
+    Deployment,
+    StatefulSet,
+}
+
+/// Result of a direct apply
+#[derive(Debug, Serialize)]
+struct DirectApplyResult {
+    id: String,
+    namespace: String,
+    kind: WorkloadKind,
+    name: String,
+    containers: Vec<String>,
+    /// Pods the rollout takes down at once
+    unavailable: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    disruption_budgets: Vec<DisruptionBudget>,
+    /// `maxUnavailable` was capped to stay within the budgets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    staggered: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<&'static str>,
+    patch: Value,
+}
+
+/// Pod template and rollout of the targeted workload
+struct Workload {
+    kind: WorkloadKind,
+    containers: Vec<Container>,
+    labels: HashMap<String, String>,
+    /// Pods the rollout takes down at once
+    unavailable: u32,
+}
+
+impl Workload {
+    fn new(kind: WorkloadKind, template: PodTemplateSpec, unavailable: u32) -> Self {
+        Self {
+            kind,
+            containers: template
+                .spec
+                .map(|spec| spec.containers)
+                .unwrap_or_default(),
+            labels: template
+                .metadata
+                .and_then(|metadata| metadata.labels)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            unavailable,
+        }
+    }
+}
+
+/// Patch the workload targeted by recommendation `id`
+pub async fn apply_direct(
+    client: &ApiClient,
+    kube: &KubeOptions,
+    id: &str,
+    dry_run: Option<DryRunMode>,
+    stagger: bool,
+    format: &OutputFormat,
+) -> Result<()> {
+    let list = client.list_recommendations(None).await?;
+    let rec = list
+        .recommendations
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| {
+            CliError::new(
+                ErrorKind::NotFound,
+                format!("Recommendation {} not found", id),
+            )
+        })?;
+
+    let kube_client = k8s::client(kube).await?;
+    let deployments: Api<Deployment> = Api::namespaced(kube_client.clone(), &rec.namespace);
+    let statefulsets: Api<StatefulSet> = Api::namespaced(kube_client.clone(), &rec.namespace);
+    let budgets: Api<PodDisruptionBudget> = Api::namespaced(kube_client, &rec.namespace);
+
+    let workload = if let Some(deployment) = deployments
+        .get_opt(&rec.deployment)
+        .await
+        .context("Failed to get deployment")?
+    {
+        let spec = deployment.spec.unwrap_or_default();
+        let unavailable = rollout_unavailable(&spec);
+        Workload::new(WorkloadKind::Deployment, spec.template, unavailable)
+    } else if let Some(statefulset) = statefulsets
+        .get_opt(&rec.deployment)
+        .await
+        .context("Failed to get statefulset")?
+    {
+        // Rolling updates restart one pod at a time, by ordinal
+        let spec = statefulset.spec.unwrap_or_default();
+        Workload::new(WorkloadKind::StatefulSet, spec.template, 1)
+    } else {
+        bail!(
+            "No Deployment or StatefulSet named {} in namespace {}",
+            rec.deployment,
+            rec.namespace
+        );
+    };
+    let Workload {
+        kind,
+        containers,
+        labels,
+        unavailable,
+    } = workload;
+
+    let pdbs = budgets
+        .list(&ListParams::default())
+        .await
+        .context("Failed to list PodDisruptionBudgets")?;
+    let pdbs = serde_json::to_value(&pdbs).context("Failed to read PodDisruptionBudgets")?;
+    let disruption_budgets = disruption_budgets_for_pod(&pdbs, &labels);
+    let allowed = disruption_budgets
+        .iter()
+        .map(|budget| budget.disruptions_allowed)
+        .min();
+
+    let staggered = match allowed {
+        Some(0) if unavailable > 0 => bail!(
+            "PodDisruptionBudget {} allows no disruptions of {} right now; \
+             retry once more pods are healthy",
+            tightest(&disruption_budgets),
+            rec.deployment
+        ),
+        Some(allowed) if unavailable > allowed => {
+            if !stagger {
+                bail!(
+                    "Rolling out {} restarts {} pods at a time, more than the {} allowed by \
+                     PodDisruptionBudget {}; rerun with --stagger to restart fewer at once",
+                    rec.deployment,
+                    unavailable,
+                    allowed,
+                    tightest(&disruption_budgets)
+                );
+            }
+            Some(allowed)
+        }
+        _ => None,
+    };
+
+    let recommended: Vec<(String, k8s::Quantities)> =
+        k8s::recommended_containers(&rec, &containers)
+            .into_iter()
+            .map(|(index, quantities)| (containers[index].name.clone(), quantities))
+            .collect();
+    if recommended.is_empty() {
+        bail!(
+            "Cannot tell which container of {} to patch: none is named {}",
+            rec.deployment,
+            rec.deployment
+        );
+    }
+    let mut patch = resource_patch(&rec, &recommended);
+    if let Some(max_unavailable) = staggered {
+        // Surge keeps the rollout progressing with fewer pods down
+        patch["spec"]["strategy"] = json!({
+            "type": "RollingUpdate",
+            "rollingUpdate": { "maxUnavailable": max_unavailable, "maxSurge": 1 }
+        });
+    }
+
+    let mut result = DirectApplyResult {
+        id: rec.id.clone(),
+        namespace: rec.namespace.clone(),
+        kind,
+        name: rec.deployment.clone(),
+        containers: recommended.into_iter().map(|(name, _)| name).collect(),
+        unavailable: staggered.unwrap_or(unavailable),
+        disruption_budgets,
+        staggered,
+        dry_run: None,
+        patch,
+    };
+
+    if dry_run == Some(DryRunMode::Client) {
+        result.dry_run = Some("client");
+        return print_result(&result, format);
+    }
+
+    let mut params = PatchParams {
+        field_manager: Some(FIELD_MANAGER.to_string()),
+        ..Default::default()
+    };
+    if dry_run == Some(DryRunMode::Server) {
+        params = params.dry_run();
+        result.dry_run = Some("server");
+    }
+    let patch = Patch::Strategic(&result.patch);
+    let patched = match kind {
+        WorkloadKind::Deployment => deployments
+            .patch(&rec.deployment, &params, &patch)
+            .await
+            .map(|_| ()),
+        WorkloadKind::StatefulSet => statefulsets
+            .patch(&rec.deployment, &params, &patch)
+            .await
+            .map(|_| ()),
+    };
+    patched.with_context(|| format!("Failed to patch {:?} {}", kind, rec.deployment))?;
+
+    print_result(&result, format)
+}
+
+/// Name of the budget allowing the fewest disruptions
+fn tightest(budgets: &[DisruptionBudget]) -> &str {
+    budgets
+        .iter()
+        .min_by_key(|budget| budget.disruptions_allowed)
+        .map_or("", |budget| budget.name.as_str())
+}
+
+/// Pods a Deployment's rollout takes down at once
+///
+/// Follows the Deployment controller: percentages of the replicas round
+/// down, and with both `maxUnavailable` and `maxSurge` at zero one pod is
+/// still replaced at a time. `Recreate` takes down every pod.
+fn rollout_unavailable(spec: &DeploymentSpec) -> u32 {
+    let replicas = spec.replicas.unwrap_or(1).max(0) as u32;
+    let strategy = spec.strategy.as_ref();
+    if strategy.and_then(|s| s.type_.as_deref()) == Some("Recreate") {
+        return replicas;
+    }
+    let rolling = strategy.and_then(|s| s.rolling_update.as_ref());
+    let scaled = |value: Option<&IntOrString>, round_up: bool| match value {
+        Some(IntOrString::Int(n)) => (*n).max(0) as u32,
+        Some(IntOrString::String(percent)) => {
+            let percent: f64 = percent.trim_end_matches('%').parse().unwrap_or(25.0);
+            let pods = replicas as f64 * percent / 100.0;
+            (if round_up { pods.ceil() } else { pods.floor() }) as u32
+        }
+        None => {
+            let pods = replicas as f64 * 0.25;
+            (if round_up { pods.ceil() } else { pods.floor() }) as u32
+        }
+    };
+    let unavailable = scaled(rolling.and_then(|r| r.max_unavailable.as_ref()), false);
+    let surge = scaled(rolling.and_then(|r| r.max_surge.as_ref()), true);
+    if unavailable == 0 && surge == 0 {
+        1
+    } else {
+        unavailable.min(replicas)
+    }
+}
+
+/// Strategic merge patch setting the containers' resources and annotations
+fn resource_patch(rec: &Recommendation, containers: &[(String, k8s::Quantities)]) -> Value {
+    let containers: Vec<Value> = containers
+        .iter()
+        .map(
+            |(name, (cpu_request, cpu_limit, memory_request, memory_limit))| {
+                // A zero limit is recommended as no limit; null removes it
+                let set = |quantity: &String| !matches!(quantity.as_str(), "" | "0" | "0m");
+                let cpu_limit = set(cpu_limit).then_some(cpu_limit);
+                let memory_limit = set(memory_limit).then_some(memory_limit);
+                json!({
+                    "name": name,
+                    "resources": {
+                        "requests": { "cpu": cpu_request, "memory": memory_request },
+                        "limits": { "cpu": cpu_limit, "memory": memory_limit },
+                    }
+                })
+            },
+        )
+        .collect();
+
+    json!({
+        "metadata": {
+            "annotations": {
+                RECOMMENDATION_ANNOTATION: rec.id,
+                APPLIED_AT_ANNOTATION: chrono::Utc::now().to_rfc3339(),
+            }
+        },
+        "spec": {
+            "template": {
+                "spec": {
+                    "containers": containers
+                }
+            }
+        }
+    })
+}
+
+fn print_result(result: &DirectApplyResult, format: &OutputFormat) -> Result<()> {
+    print_object(result, format, || {
+        let target = format!(
+            "{:?} {}/{} ({} {})",
+            result.kind,
+            result.namespace,
+            result.name,
+            if result.containers.len() == 1 {
+                "container"
+            } else {
+                "containers"
+            },
+            result.containers.join(", ")
+        );
+        for budget in &result.disruption_budgets {
+            println!(
+                "PodDisruptionBudget {}: {} disruption(s) allowed ({}/{} healthy)",
+                budget.name,
+                budget.disruptions_allowed,
+                budget.current_healthy,
+                budget.desired_healthy
+            );
+        }
+        if let Some(max_unavailable) = result.staggered {
+            print_warning(&format!(
+                "Restarts staggered: maxUnavailable capped at {} to stay within the budget",
+                max_unavailable
+            ));
+        }
+        match result.dry_run {
+            Some("client") => {
+                print_warning("Client dry-run - no changes sent");
+                println!("\nPatch that would be applied to {}:", target);
+                println!("---");
+                println!("{}", serde_json::to_string_pretty(&result.patch)?);
+            }
+            Some(_) => {
+                print_success(&format!("Server dry-run of patch to {} succeeded", target));
+            }
+            None => {
+                print_success(&format!(
+                    "Recommendation {} applied to {}",
+                    result.id, target
+                ));
+            }
+        }
+        Ok(())
+    })
+}
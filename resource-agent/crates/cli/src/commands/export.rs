@@ -0,0 +1,269 @@
+//! Export recommendations as GitOps-ready patch files
+//!
+//! Renders recommendations as Kustomize patches, Helm values or JSON
+//! patches so resource changes can be committed and rolled out by
+//! ArgoCD or Flux instead of being patched into the live cluster.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use super::recommendations::{fetch_recommendations, OwnerFilter};
+use crate::client::{ApiClient, Recommendation};
+use crate::k8s::{self, RECOMMENDATION_ANNOTATION};
+use crate::output::{print_success, print_warning};
+
+/// Patch format for exported manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ManifestFormat {
+    /// Strategic merge patches plus a kustomization.yaml
+    Kustomize,
+    /// A values file per namespace, keyed by workload name
+    HelmValues,
+    /// RFC 6902 JSON patches, for `kubectl patch --type=json`
+    Jsonpatch,
+}
+
+/// A rendered file and its path relative to the output directory
+struct ManifestFile {
+    path: String,
+    content: String,
+}
+
+/// Export recommendations as patch files
+///
+/// Rolled back recommendations are skipped. Files are written to
+/// `output_dir` when given, otherwise printed to stdout.
+pub async fn export_manifests(
+    client: &ApiClient,
+    namespace: Option<String>,
+    owner: Option<OwnerFilter>,
+    status: Option<String>,
+    format: ManifestFormat,
+    output_dir: Option<&Path>,
+) -> Result<()> {
+    let recommendations: Vec<Recommendation> =
+        fetch_recommendations(client, &namespace, &owner, &status)
+            .await?
+            .into_iter()
+            .filter(|r| !r.status.eq_ignore_ascii_case("rolled_back"))
+            .collect();
+    if recommendations.is_empty() {
+        print_warning("No recommendations to export");
+        return Ok(());
+    }
+
+    let files = match format {
+        ManifestFormat::Kustomize => kustomize(&recommendations),
+        ManifestFormat::HelmValues => helm_values(&recommendations),
+        ManifestFormat::Jsonpatch => json_patches(&recommendations)?,
+    };
+
+    match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            for file in &files {
+                let path = dir.join(&file.path);
+                std::fs::write(&path, &file.content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            print_success(&format!(
+                "Exported {} recommendations to {} files in {}",
+                recommendations.len(),
+                files.len(),
+                dir.display()
+            ));
+        }
+        None => {
+            for (i, file) in files.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("# {}", file.path);
+                print!("{}", file.content);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One strategic merge patch per workload plus a kustomization listing them
+fn kustomize(recommendations: &[Recommendation]) -> Vec<ManifestFile> {
+    let mut files: Vec<ManifestFile> = recommendations
+        .iter()
+        .map(|rec| {
+            let (cpu_request, cpu_limit, memory_request, memory_limit) =
+                k8s::recommended_quantities(rec);
+            let (api_version, kind) = target_kind(rec);
+            let mut content = header(rec);
+            let _ = write!(
+                content,
+                r#"apiVersion: {api_version}
+kind: {kind}
+metadata:
+  name: {name}
+  namespace: {namespace}
+  annotations:
+    {annotation}: "{id}"
+"#,
+                name = rec.deployment,
+                namespace = rec.namespace,
+                annotation = RECOMMENDATION_ANNOTATION,
+                id = rec.id,
+            );
+            let pod_spec = pod_spec_path(rec);
+            for (depth, key) in pod_spec.iter().enumerate() {
+                let _ = writeln!(content, "{:indent$}{}:", "", key, indent = depth * 2);
+            }
+            let containers = format!(
+                r#"containers:
+  - name: {name}
+    resources:
+      requests:
+        cpu: "{cpu_request}"
+        memory: "{memory_request}"
+      limits:
+        cpu: "{cpu_limit}"
+        memory: "{memory_limit}"
+"#,
+                name = rec.deployment,
+            );
+            for line in containers.lines() {
+                let _ = writeln!(
+                    content,
+                    "{:indent$}{}",
+                    "",
+                    line,
+                    indent = pod_spec.len() * 2
+                );
+            }
+            ManifestFile {
+                path: format!(
+                    "{}-{}-{}-resources.yaml",
+                    rec.namespace,
+                    rec.workload_kind(),
+                    rec.deployment
+                ),
+                content,
+            }
+        })
+        .collect();
+
+    let mut kustomization = String::from(
+        "apiVersion: kustomize.config.k8s.io/v1beta1\nkind: Kustomization\npatches:\n",
+    );
+    for file in &files {
+        let _ = writeln!(kustomization, "  - path: {}", file.path);
+    }
+    files.push(ManifestFile {
+        path: "kustomization.yaml".to_string(),
+        content: kustomization,
+    });
+    files
+}
+
+/// One values file per namespace with a `resources` block per workload
+fn helm_values(recommendations: &[Recommendation]) -> Vec<ManifestFile> {
+    let mut by_namespace: BTreeMap<&str, Vec<&Recommendation>> = BTreeMap::new();
+    for rec in recommendations {
+        by_namespace.entry(&rec.namespace).or_default().push(rec);
+    }
+
+    by_namespace
+        .into_iter()
+        .map(|(namespace, recs)| {
+            let mut content = String::new();
+            for rec in recs {
+                let (cpu_request, cpu_limit, memory_request, memory_limit) =
+                    k8s::recommended_quantities(rec);
+                content.push_str(&header(rec));
+                let _ = write!(
+                    content,
+                    r#"{name}:
+  resources:
+    requests:
+      cpu: "{cpu_request}"
+      memory: "{memory_request}"
+    limits:
+      cpu: "{cpu_limit}"
+      memory: "{memory_limit}"
+"#,
+                    name = rec.deployment,
+                );
+            }
+            ManifestFile {
+                path: format!("{}-values.yaml", namespace),
+                content,
+            }
+        })
+        .collect()
+}
+
+/// One JSON patch per workload, targeting its first container
+fn json_patches(recommendations: &[Recommendation]) -> Result<Vec<ManifestFile>> {
+    recommendations
+        .iter()
+        .map(|rec| {
+            let (cpu_request, cpu_limit, memory_request, memory_limit) =
+                k8s::recommended_quantities(rec);
+            let patch = json!([
+                {
+                    "op": "add",
+                    "path": format!("/{}/containers/0/resources", pod_spec_path(rec).join("/")),
+                    "value": {
+                        "requests": { "cpu": cpu_request, "memory": memory_request },
+                        "limits": { "cpu": cpu_limit, "memory": memory_limit },
+                    }
+                }
+            ]);
+            let mut content = serde_json::to_string_pretty(&patch)?;
+            content.push('\n');
+            Ok(ManifestFile {
+                path: format!(
+                    "{}-{}-{}.patch.json",
+                    rec.namespace,
+                    rec.workload_kind(),
+                    rec.deployment
+                ),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// YAML comment identifying the recommendation a patch came from
+fn header(rec: &Recommendation) -> String {
+    format!(
+        "# Recommendation {} for {}/{} (confidence {:.0}%, model {})\n",
+        rec.id,
+        rec.namespace,
+        rec.owner(),
+        rec.confidence * 100.0,
+        rec.model_version
+    )
+}
+
+/// API version and kind of the workload a recommendation targets
+fn target_kind(rec: &Recommendation) -> (&'static str, &'static str) {
+    match rec.workload_kind() {
+        "statefulset" => ("apps/v1", "StatefulSet"),
+        "daemonset" => ("apps/v1", "DaemonSet"),
+        "rollout" => ("argoproj.io/v1alpha1", "Rollout"),
+        "job" => ("batch/v1", "Job"),
+        "cronjob" => ("batch/v1", "CronJob"),
+        _ => ("apps/v1", "Deployment"),
+    }
+}
+
+/// Keys leading to the pod spec of the workload a recommendation targets
+fn pod_spec_path(rec: &Recommendation) -> &'static [&'static str] {
+    match rec.workload_kind() {
+        "cronjob" => &["spec", "jobTemplate", "spec", "template", "spec"],
+        _ => &["spec", "template", "spec"],
+    }
+}
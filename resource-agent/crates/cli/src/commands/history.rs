@@ -0,0 +1,186 @@
+//! Timeline of a workload's recommendations, approvals, applies and rollbacks
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tabled::Tabled;
+
+use crate::client::{ApiClient, ApprovalHistory, Recommendation, RollbackEvent};
+use crate::commands::recommendations::truncate_id;
+use crate::error::{CliError, ErrorKind};
+use crate::output::{
+    color_status, format_bytes, format_cpu, format_timestamp, print_list, print_warning,
+    OutputFormat,
+};
+
+/// Row for history table
+#[derive(Tabled)]
+struct HistoryRow {
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Event")]
+    event: String,
+    #[tabled(rename = "Recommendation")]
+    recommendation_id: String,
+    #[tabled(rename = "By")]
+    actor: String,
+    #[tabled(rename = "CPU Request")]
+    cpu_request: String,
+    #[tabled(rename = "Memory Request")]
+    memory_request: String,
+    #[tabled(rename = "Details")]
+    details: String,
+}
+
+/// One entry of the timeline
+#[derive(Debug, Serialize)]
+pub struct HistoryEvent {
+    pub time: String,
+    /// created, approved, rejected, auto_approved, applied or rolled_back
+    pub event: String,
+    pub recommendation_id: String,
+    /// Who triggered the event, when known
+    pub actor: Option<String>,
+    /// CPU request recommended, applied or restored by the event
+    pub cpu_request: Option<String>,
+    pub memory_request: Option<String>,
+    pub details: Option<String>,
+}
+
+/// Show the history of `<namespace>/<deployment>`, oldest first
+pub async fn show_history(client: &ApiClient, target: &str, format: &OutputFormat) -> Result<()> {
+    let (namespace, deployment) = target
+        .split_once('/')
+        .filter(|(ns, name)| !ns.is_empty() && !name.is_empty())
+        .with_context(|| format!("Expected <namespace>/<deployment>, got '{}'", target))?;
+
+    let recommendations: Vec<Recommendation> = client
+        .list_recommendations(Some(namespace))
+        .await?
+        .recommendations
+        .into_iter()
+        .filter(|r| r.deployment == deployment)
+        .collect();
+    if recommendations.is_empty() {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!("No recommendations found for {}/{}", namespace, deployment),
+        )
+        .into());
+    }
+
+    let mut events: Vec<HistoryEvent> = Vec::new();
+    for rec in &recommendations {
+        events.push(HistoryEvent {
+            time: rec.created_at.clone(),
+            event: "created".to_string(),
+            recommendation_id: rec.id.clone(),
+            actor: Some(format!("model {}", rec.model_version)),
+            cpu_request: Some(format_cpu(rec.cpu_request_millicores)),
+            memory_request: Some(format_bytes(rec.memory_request_bytes)),
+            details: Some(format!(
+                "{:.0}% confidence, {} window",
+                rec.confidence * 100.0,
+                rec.time_window
+            )),
+        });
+        if let Some(applied_at) = &rec.applied_at {
+            events.push(HistoryEvent {
+                time: applied_at.clone(),
+                event: "applied".to_string(),
+                recommendation_id: rec.id.clone(),
+                actor: None,
+                cpu_request: Some(format_cpu(rec.cpu_request_millicores)),
+                memory_request: Some(format_bytes(rec.memory_request_bytes)),
+                details: None,
+            });
+        }
+
+        let path = format!("api/v1/recommendation/{}/approval-history", rec.id);
+        match client.get::<Vec<ApprovalHistory>>(&path).await {
+            Ok(approvals) => events.extend(approvals.into_iter().map(|a| HistoryEvent {
+                time: a.created_at,
+                event: a.action,
+                recommendation_id: a.recommendation_id,
+                actor: Some(a.approver),
+                cpu_request: None,
+                memory_request: None,
+                details: a.reason,
+            })),
+            Err(e) => print_warning(&format!(
+                "Approval history of {} unavailable: {:#}",
+                rec.id, e
+            )),
+        }
+    }
+
+    let path = format!("api/v1/safety/rollbacks?namespace={}", namespace);
+    match client.get::<Vec<RollbackEvent>>(&path).await {
+        Ok(rollbacks) => {
+            for rollback in rollbacks {
+                let Some(rec) = recommendations
+                    .iter()
+                    .find(|r| r.id == rollback.original_recommendation_id)
+                else {
+                    continue;
+                };
+                // A rollback restores the resources in place before the apply
+                let restored = rec.current_resources.as_ref();
+                let actor = if rollback.auto_triggered {
+                    "auto"
+                } else {
+                    "manual"
+                };
+                events.push(HistoryEvent {
+                    time: rollback.created_at.clone(),
+                    event: "rolled_back".to_string(),
+                    recommendation_id: rec.id.clone(),
+                    actor: Some(actor.to_string()),
+                    cpu_request: restored.map(|r| r.cpu_request.clone()),
+                    memory_request: restored.map(|r| r.memory_request.clone()),
+                    details: Some(rollback_details(&rollback)),
+                });
+            }
+        }
+        Err(e) => print_warning(&format!("Rollback events unavailable: {:#}", e)),
+    }
+
+    // Timestamps come from different tables; order by instant, not by string
+    events.sort_by_key(|e| chrono::DateTime::parse_from_rfc3339(&e.time).ok());
+
+    print_list(&events, format, |events| {
+        events
+            .iter()
+            .map(|e| HistoryRow {
+                time: format_timestamp(&e.time),
+                event: color_status(&e.event),
+                recommendation_id: truncate_id(&e.recommendation_id),
+                actor: e.actor.clone().unwrap_or_else(|| "-".to_string()),
+                cpu_request: e.cpu_request.clone().unwrap_or_else(|| "-".to_string()),
+                memory_request: e.memory_request.clone().unwrap_or_else(|| "-".to_string()),
+                details: e.details.clone().unwrap_or_else(|| "-".to_string()),
+            })
+            .collect()
+    })?;
+    if let OutputFormat::Table = format {
+        println!(
+            "\n{} events for {}/{} across {} recommendations",
+            events.len(),
+            namespace,
+            deployment,
+            recommendations.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn rollback_details(rollback: &RollbackEvent) -> String {
+    match rollback.trigger_reason.as_str() {
+        "oom_increase" => format!("{} OOM kills after apply", rollback.oom_kills_detected),
+        "throttle_increase" => format!(
+            "CPU throttling up {:.0}%",
+            rollback.throttle_increase_percent
+        ),
+        reason => reason.to_string(),
+    }
+}
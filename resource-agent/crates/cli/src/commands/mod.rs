@@ -1,5 +1,15 @@
 //! CLI command implementations
 
+pub mod anomalies;
+pub mod bulk;
+pub mod bundle;
+pub mod completion;
+pub mod context;
 pub mod costs;
 pub mod debug;
+pub mod diff;
+pub mod direct;
+pub mod export;
+pub mod history;
+pub mod policy;
 pub mod recommendations;
@@ -0,0 +1,156 @@
+//! Guardrail policy management: `crp policy list|set|delete|test`
+
+use anyhow::Result;
+use tabled::Tabled;
+
+use crate::client::{ApiClient, Policy, PolicyCheckResult};
+use crate::output::{
+    format_bytes, format_cpu, format_timestamp, parse_cpu_millicores, parse_memory_bytes,
+    print_list, print_object, print_success, print_warning, OutputFormat,
+};
+
+/// Row for policies table
+#[derive(Tabled)]
+struct PolicyRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Namespaces")]
+    namespaces: String,
+    #[tabled(rename = "CPU")]
+    cpu: String,
+    #[tabled(rename = "Memory")]
+    memory: String,
+    #[tabled(rename = "Excluded")]
+    excluded: String,
+    #[tabled(rename = "Approval")]
+    require_approval: String,
+    #[tabled(rename = "Updated")]
+    updated_at: String,
+}
+
+/// Row for policy violations table
+#[derive(Tabled)]
+struct ViolationRow {
+    #[tabled(rename = "Policy")]
+    policy: String,
+    #[tabled(rename = "Rule")]
+    rule: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+/// List all policies
+pub async fn list_policies(client: &ApiClient, format: &OutputFormat) -> Result<()> {
+    let policies: Vec<Policy> = client.get("api/v1/policies").await?;
+
+    print_list(&policies, format, |policies| {
+        policies
+            .iter()
+            .map(|p| PolicyRow {
+                name: p.name.clone(),
+                namespaces: join_or(&p.namespaces, "*"),
+                cpu: format_range(
+                    p.min_cpu_millicores.map(format_cpu),
+                    p.max_cpu_millicores.map(format_cpu),
+                ),
+                memory: format_range(
+                    p.min_memory_bytes.map(format_bytes),
+                    p.max_memory_bytes.map(format_bytes),
+                ),
+                excluded: join_or(&p.excluded_namespaces, "-"),
+                require_approval: if p.require_approval { "required" } else { "-" }.to_string(),
+                updated_at: p
+                    .updated_at
+                    .as_deref()
+                    .map(format_timestamp)
+                    .unwrap_or_else(|| "-".to_string()),
+            })
+            .collect()
+    })
+}
+
+/// Create or replace the policy named `policy.name`
+pub async fn set_policy(client: &ApiClient, policy: Policy, format: &OutputFormat) -> Result<()> {
+    let path = format!("api/v1/policies/{}", policy.name);
+    let saved: Policy = client.put(&path, &policy).await?;
+
+    print_object(&saved, format, || {
+        print_success(&format!("Policy \"{}\" saved", saved.name));
+        Ok(())
+    })
+}
+
+/// Delete a policy
+pub async fn delete_policy(client: &ApiClient, name: &str) -> Result<()> {
+    client.delete(&format!("api/v1/policies/{}", name)).await?;
+    print_success(&format!("Policy \"{}\" deleted", name));
+    Ok(())
+}
+
+/// Show which policies a recommendation would violate
+pub async fn test_policies(client: &ApiClient, id: &str, format: &OutputFormat) -> Result<()> {
+    let path = format!("api/v1/recommendation/{}/policy-check", id);
+    let result: PolicyCheckResult = client.get(&path).await?;
+
+    print_object(&result, format, || {
+        let target = format!("{}/{}", result.namespace, result.deployment);
+        if result.policies_checked == 0 {
+            print_warning(&format!("No policies apply to {}", target));
+        } else if result.allowed {
+            print_success(&format!(
+                "Recommendation {} for {} passes {} policies",
+                id, target, result.policies_checked
+            ));
+        } else {
+            print_warning(&format!(
+                "Recommendation {} for {} violates {} rules of {} policies checked",
+                id,
+                target,
+                result.violations.len(),
+                result.policies_checked
+            ));
+            print_list(&result.violations, &OutputFormat::Table, |violations| {
+                violations
+                    .iter()
+                    .map(|v| ViolationRow {
+                        policy: v.policy.clone(),
+                        rule: v.rule.clone(),
+                        message: v.message.clone(),
+                    })
+                    .collect()
+            })?;
+        }
+        Ok(())
+    })
+}
+
+/// Parse a CPU quantity such as `100m` or `2` into millicores
+pub fn parse_cpu(value: &str) -> Result<u32, String> {
+    parse_cpu_millicores(value)
+        .filter(|m| m.is_finite() && (0.0..=u32::MAX as f64).contains(m))
+        .map(|m| m.round() as u32)
+        .ok_or_else(|| format!("invalid CPU quantity '{}', expected e.g. 100m or 2", value))
+}
+
+/// Parse a memory quantity such as `256Mi` or `1G` into bytes
+pub fn parse_memory(value: &str) -> Result<u64, String> {
+    parse_memory_bytes(value)
+        .filter(|b| b.is_finite() && *b >= 0.0)
+        .map(|b| b.round() as u64)
+        .ok_or_else(|| format!("invalid memory quantity '{}', expected e.g. 256Mi", value))
+}
+
+fn join_or(values: &[String], empty: &str) -> String {
+    if values.is_empty() {
+        empty.to_string()
+    } else {
+        values.join(",")
+    }
+}
+
+fn format_range(min: Option<String>, max: Option<String>) -> String {
+    match (min, max) {
+        (None, None) => "-".to_string(),
+        (min, max) => format!("{}..{}", min.unwrap_or_default(), max.unwrap_or_default()),
+    }
+}
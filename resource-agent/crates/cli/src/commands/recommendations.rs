@@ -1,14 +1,75 @@
 //! Recommendation-related CLI commands
 
 use anyhow::Result;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
 use tabled::Tabled;
 
-use crate::client::{ApiClient, ApplyRequest, ApproveRequest, ModelList, RecommendationList};
+use crate::client::{
+    ApiClient, ApplyRequest, ApproveRequest, ModelList, Recommendation, ResourceSpec,
+    RollbackRequest,
+};
+use crate::error::{CliError, ErrorKind};
 use crate::output::{
-    color_confidence, color_status, format_bytes, format_cpu, print_success, print_warning,
-    OutputFormat,
+    color_confidence, color_status, confirm, format_bytes, format_cpu, print_error, print_list,
+    print_object, print_success, print_warning, OutputFormat, Watcher,
 };
 
+/// Workload kinds recommendations can be owned by
+pub const OWNER_KINDS: [&str; 6] = [
+    "deployment",
+    "statefulset",
+    "daemonset",
+    "rollout",
+    "job",
+    "cronjob",
+];
+
+/// Filter on the owning workload, given as `[kind/]name`
+///
+/// The kind matches case-insensitively and the name as a substring, so
+/// `statefulset/db` selects StatefulSets whose name contains `db`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerFilter {
+    pub kind: Option<String>,
+    pub name: String,
+}
+
+impl OwnerFilter {
+    pub fn matches(&self, rec: &Recommendation) -> bool {
+        self.kind
+            .as_ref()
+            .map_or(true, |kind| rec.workload_kind().eq_ignore_ascii_case(kind))
+            && rec.deployment.contains(&self.name)
+    }
+}
+
+impl FromStr for OwnerFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((kind, name)) = s.split_once('/') else {
+            return Ok(Self {
+                kind: None,
+                name: s.to_string(),
+            });
+        };
+        let kind = kind.to_ascii_lowercase();
+        if !OWNER_KINDS.contains(&kind.as_str()) {
+            return Err(format!(
+                "unknown workload kind '{}', expected one of: {}",
+                kind,
+                OWNER_KINDS.join(", ")
+            ));
+        }
+        Ok(Self {
+            kind: Some(kind),
+            name: name.to_string(),
+        })
+    }
+}
+
 /// Row for recommendations table
 #[derive(Tabled)]
 struct RecommendationRow {
@@ -16,8 +77,8 @@ struct RecommendationRow {
     id: String,
     #[tabled(rename = "Namespace")]
     namespace: String,
-    #[tabled(rename = "Deployment")]
-    deployment: String,
+    #[tabled(rename = "Owner")]
+    owner: String,
     #[tabled(rename = "CPU Req")]
     cpu_request: String,
     #[tabled(rename = "CPU Lim")]
@@ -53,74 +114,167 @@ struct ModelRow {
 pub async fn get_recommendations(
     client: &ApiClient,
     namespace: Option<String>,
-    deployment: Option<String>,
+    owner: Option<OwnerFilter>,
     status: Option<String>,
-    format: OutputFormat,
+    format: &OutputFormat,
 ) -> Result<()> {
-    let path = match &namespace {
-        Some(ns) => format!("api/v1/recommendations/{}", ns),
-        None => "api/v1/recommendations".to_string(),
-    };
+    let filtered = fetch_recommendations(client, &namespace, &owner, &status).await?;
+    print_recommendations(&filtered, format)
+}
 
-    let result: RecommendationList = client.get(&path).await?;
+/// Poll recommendations and redraw them until interrupted
+///
+/// Status changes since the previous poll are listed under the table.
+pub async fn watch_recommendations(
+    client: &ApiClient,
+    namespace: Option<String>,
+    owner: Option<OwnerFilter>,
+    status: Option<String>,
+    interval: Duration,
+    format: &OutputFormat,
+) -> Result<()> {
+    let mut watcher = Watcher::new(interval, format);
+    let mut statuses: HashMap<String, String> = HashMap::new();
+    while watcher.next().await {
+        let filtered = match fetch_recommendations(client, &namespace, &owner, &status).await {
+            Ok(filtered) => filtered,
+            Err(e) => {
+                print_error(&format!("{:#}", e));
+                continue;
+            }
+        };
+        print_recommendations(&filtered, format)?;
 
-    // Filter by deployment and status if specified
-    let filtered: Vec<_> = result
+        let first_poll = statuses.is_empty();
+        let mut changes = Vec::new();
+        for rec in &filtered {
+            let name = format!(
+                "{}/{} ({})",
+                rec.namespace,
+                rec.owner(),
+                truncate_id(&rec.id)
+            );
+            match statuses.insert(rec.id.clone(), rec.status.clone()) {
+                Some(previous) if previous != rec.status => changes.push(format!(
+                    "{}: {} -> {}",
+                    name,
+                    color_status(&previous),
+                    color_status(&rec.status)
+                )),
+                None if !first_poll => {
+                    changes.push(format!("{}: new, {}", name, color_status(&rec.status)))
+                }
+                _ => {}
+            }
+        }
+        if let OutputFormat::Table = format {
+            if !changes.is_empty() {
+                println!("\nChanges since last poll:");
+                for change in changes {
+                    println!("  {}", change);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch recommendations and apply the owner and status filters
+pub async fn fetch_recommendations(
+    client: &ApiClient,
+    namespace: &Option<String>,
+    owner: &Option<OwnerFilter>,
+    status: &Option<String>,
+) -> Result<Vec<Recommendation>> {
+    let result = client.list_recommendations(namespace.as_deref()).await?;
+
+    // Filter by owner and status if specified
+    Ok(result
         .recommendations
         .into_iter()
-        .filter(|r| {
-            deployment
-                .as_ref()
-                .map(|d| r.deployment.contains(d))
-                .unwrap_or(true)
-        })
+        .filter(|r| owner.as_ref().map(|o| o.matches(r)).unwrap_or(true))
         .filter(|r| {
             status
                 .as_ref()
                 .map(|s| r.status.eq_ignore_ascii_case(s))
                 .unwrap_or(true)
         })
-        .collect();
+        .collect())
+}
 
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&filtered)?;
-            println!("{}", json);
-        }
-        OutputFormat::Table => {
-            if filtered.is_empty() {
-                print_warning("No recommendations found");
-                return Ok(());
-            }
+/// Get recommendations for workloads with a usage shape similar to the
+/// given recommendation's
+///
+/// Workloads are similar when the agents placed them in the same cluster
+/// of fingerprints: comparable periodicity, burstiness and footprint.
+pub async fn get_similar(client: &ApiClient, id: &str, format: &OutputFormat) -> Result<()> {
+    let recommendations = client.list_recommendations(None).await?.recommendations;
+    let rec = recommendations.iter().find(|r| r.id == id).ok_or_else(|| {
+        CliError::new(
+            ErrorKind::NotFound,
+            format!("Recommendation {} not found", id),
+        )
+    })?;
+    let Some(cluster) = rec.workload_cluster.clone() else {
+        print_warning(&format!(
+            "Recommendation {} has no workload fingerprint yet",
+            id
+        ));
+        return Ok(());
+    };
 
-            let rows: Vec<RecommendationRow> = filtered
-                .iter()
-                .map(|r| RecommendationRow {
-                    id: truncate_id(&r.id),
-                    namespace: r.namespace.clone(),
-                    deployment: r.deployment.clone(),
-                    cpu_request: format_cpu(r.cpu_request_millicores),
-                    cpu_limit: format_cpu(r.cpu_limit_millicores),
-                    memory_request: format_bytes(r.memory_request_bytes),
-                    memory_limit: format_bytes(r.memory_limit_bytes),
-                    confidence: color_confidence(r.confidence),
-                    status: color_status(&r.status),
-                })
-                .collect();
+    let similar: Vec<Recommendation> = recommendations
+        .into_iter()
+        .filter(|r| r.id != id && r.workload_cluster.as_deref() == Some(cluster.as_str()))
+        .collect();
+    if let OutputFormat::Table = format {
+        println!(
+            "Workloads similar to {} (cluster {})\n",
+            truncate_id(id),
+            cluster
+        );
+    }
+    print_recommendations(&similar, format)
+}
 
-            let table = tabled::Table::new(rows)
-                .with(tabled::settings::Style::rounded())
-                .to_string();
-            println!("{}", table);
-            println!("\nTotal: {} recommendations", filtered.len());
+fn print_recommendations(filtered: &[Recommendation], format: &OutputFormat) -> Result<()> {
+    if let OutputFormat::Table = format {
+        if filtered.is_empty() {
+            print_warning("No recommendations found");
+            return Ok(());
         }
     }
 
+    print_list(filtered, format, |filtered| {
+        filtered
+            .iter()
+            .map(|r| RecommendationRow {
+                id: truncate_id(&r.id),
+                namespace: r.namespace.clone(),
+                owner: r.owner(),
+                cpu_request: format_cpu(r.cpu_request_millicores),
+                cpu_limit: format_cpu(r.cpu_limit_millicores),
+                memory_request: format_bytes(r.memory_request_bytes),
+                memory_limit: format_bytes(r.memory_limit_bytes),
+                confidence: color_confidence(r.confidence),
+                status: color_status(&r.status),
+            })
+            .collect()
+    })?;
+    if let OutputFormat::Table = format {
+        println!("\nTotal: {} recommendations", filtered.len());
+    }
+
     Ok(())
 }
 
 /// Get model versions
-pub async fn get_models(client: &ApiClient, active_only: bool, format: OutputFormat) -> Result<()> {
+pub async fn get_models(
+    client: &ApiClient,
+    active_only: bool,
+    format: &OutputFormat,
+) -> Result<()> {
     let result: ModelList = client.get("api/v1/models").await?;
 
     let filtered: Vec<_> = if active_only {
@@ -129,41 +283,30 @@ pub async fn get_models(client: &ApiClient, active_only: bool, format: OutputFor
         result.models
     };
 
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&filtered)?;
-            println!("{}", json);
-        }
-        OutputFormat::Table => {
-            if filtered.is_empty() {
-                print_warning("No models found");
-                return Ok(());
-            }
-
-            let rows: Vec<ModelRow> = filtered
-                .iter()
-                .map(|m| ModelRow {
-                    version: m.version.clone(),
-                    created_at: format_timestamp(&m.created_at),
-                    accuracy: format!("{:.1}%", m.validation_accuracy * 100.0),
-                    size: format_bytes(m.size_bytes as u64),
-                    active: if m.is_active {
-                        "✓".to_string()
-                    } else {
-                        "".to_string()
-                    },
-                    rollbacks: m.rollback_count.to_string(),
-                })
-                .collect();
-
-            let table = tabled::Table::new(rows)
-                .with(tabled::settings::Style::rounded())
-                .to_string();
-            println!("{}", table);
+    if let OutputFormat::Table = format {
+        if filtered.is_empty() {
+            print_warning("No models found");
+            return Ok(());
         }
     }
 
-    Ok(())
+    print_list(&filtered, format, |filtered| {
+        filtered
+            .iter()
+            .map(|m| ModelRow {
+                version: m.version.clone(),
+                created_at: format_timestamp(&m.created_at),
+                accuracy: format!("{:.1}%", m.validation_accuracy * 100.0),
+                size: format_bytes(m.size_bytes as u64),
+                active: if m.is_active {
+                    "✓".to_string()
+                } else {
+                    "".to_string()
+                },
+                rollbacks: m.rollback_count.to_string(),
+            })
+            .collect()
+    })
 }
 
 /// Apply a recommendation
@@ -171,39 +314,32 @@ pub async fn apply_recommendation(
     client: &ApiClient,
     id: &str,
     dry_run: bool,
-    format: OutputFormat,
+    format: &OutputFormat,
 ) -> Result<()> {
     let path = format!("api/v1/recommendation/{}/apply", id);
     let request = ApplyRequest { dry_run };
 
     let response: crate::client::ApplyResponse = client.post(&path, &request).await?;
 
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&response)?;
-            println!("{}", json);
-        }
-        OutputFormat::Table => {
-            if dry_run {
-                print_warning("Dry-run mode - no changes applied");
-                println!("\nRecommendation: {}", id);
-                println!("Status: {}", response.status);
-                println!("Message: {}", response.message);
-
-                if let Some(patch) = &response.yaml_patch {
-                    println!("\nYAML Patch that would be applied:");
-                    println!("---");
-                    println!("{}", patch);
-                }
-            } else {
-                print_success(&format!("Recommendation {} applied successfully", id));
-                println!("Status: {}", response.status);
-                println!("Message: {}", response.message);
+    print_object(&response, format, || {
+        if dry_run {
+            print_warning("Dry-run mode - no changes applied");
+            println!("\nRecommendation: {}", id);
+            println!("Status: {}", response.status);
+            println!("Message: {}", response.message);
+
+            if let Some(patch) = &response.yaml_patch {
+                println!("\nYAML Patch that would be applied:");
+                println!("---");
+                println!("{}", patch);
             }
+        } else {
+            print_success(&format!("Recommendation {} applied successfully", id));
+            println!("Status: {}", response.status);
+            println!("Message: {}", response.message);
         }
-    }
-
-    Ok(())
+        Ok(())
+    })
 }
 
 /// Approve a recommendation
@@ -212,7 +348,7 @@ pub async fn approve_recommendation(
     id: &str,
     approver: &str,
     reason: Option<String>,
-    format: OutputFormat,
+    format: &OutputFormat,
 ) -> Result<()> {
     let path = format!("api/v1/recommendation/{}/approve", id);
     let request = ApproveRequest {
@@ -222,24 +358,95 @@ pub async fn approve_recommendation(
 
     let response: crate::client::ApproveResponse = client.post(&path, &request).await?;
 
-    match format {
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&response)?;
-            println!("{}", json);
-        }
-        OutputFormat::Table => {
-            print_success(&format!("Recommendation {} approved", id));
-            println!("Status: {}", response.status);
-            println!("Approver: {}", approver);
-            println!("Message: {}", response.message);
+    print_object(&response, format, || {
+        print_success(&format!("Recommendation {} approved", id));
+        println!("Status: {}", response.status);
+        println!("Approver: {}", approver);
+        println!("Message: {}", response.message);
+        Ok(())
+    })
+}
+
+/// Row for the before/after table of a rollback
+#[derive(Tabled)]
+struct RollbackRow {
+    #[tabled(rename = "Resource")]
+    resource: String,
+    #[tabled(rename = "Previous")]
+    previous: String,
+    #[tabled(rename = "Restored")]
+    restored: String,
+}
+
+/// Roll back an applied recommendation
+pub async fn rollback_recommendation(
+    client: &ApiClient,
+    id: &str,
+    to_revision: Option<u32>,
+    yes: bool,
+    format: &OutputFormat,
+) -> Result<()> {
+    if !yes {
+        let target = match to_revision {
+            Some(revision) => format!("revision {}", revision),
+            None => "the previous revision".to_string(),
+        };
+        if !confirm(&format!("Roll back recommendation {} to {}?", id, target))? {
+            print_warning("Rollback cancelled");
+            return Ok(());
         }
     }
 
-    Ok(())
+    let path = format!("api/v1/recommendation/{}/rollback", id);
+    let request = RollbackRequest { to_revision };
+
+    let response: crate::client::RollbackResponse = client.post(&path, &request).await?;
+
+    print_object(&response, format, || {
+        print_success(&format!("Recommendation {} rolled back", id));
+        println!("Status: {}", response.status);
+        if let Some(revision) = response.revision {
+            println!("Revision: {}", revision);
+        }
+        println!("Message: {}", response.message);
+
+        if let (Some(previous), Some(restored)) =
+            (&response.previous_resources, &response.restored_resources)
+        {
+            let rows = rollback_rows(previous, restored);
+            let table = tabled::Table::new(rows)
+                .with(tabled::settings::Style::rounded())
+                .to_string();
+            println!("\n{}", table);
+        }
+        Ok(())
+    })
+}
+
+fn rollback_rows(previous: &ResourceSpec, restored: &ResourceSpec) -> Vec<RollbackRow> {
+    let row = |resource: &str, previous: &str, restored: &str| RollbackRow {
+        resource: resource.to_string(),
+        previous: previous.to_string(),
+        restored: restored.to_string(),
+    };
+    vec![
+        row("CPU Request", &previous.cpu_request, &restored.cpu_request),
+        row("CPU Limit", &previous.cpu_limit, &restored.cpu_limit),
+        row(
+            "Memory Request",
+            &previous.memory_request,
+            &restored.memory_request,
+        ),
+        row(
+            "Memory Limit",
+            &previous.memory_limit,
+            &restored.memory_limit,
+        ),
+    ]
 }
 
 /// Truncate ID for display
-fn truncate_id(id: &str) -> String {
+pub fn truncate_id(id: &str) -> String {
     if id.len() > 8 {
         format!("{}...", &id[..8])
     } else {
@@ -1,23 +1,120 @@
 //! Configuration management for the CLI
+//!
+//! `~/.config/crp/config.toml` maps context names to a cluster's
+//! Recommendation API and credentials, so one CLI can serve many clusters:
+//!
+//! ```toml
+//! current-context = "prod"
+//!
+//! [contexts.prod]
+//! api-url = "https://crp.prod.example.com"
+//! token = "..."
+//! kube-context = "prod-eks"
+//!
+//! [contexts.staging]
+//! api-url = "https://crp.staging.example.com"
+//! oidc-issuer = "https://sso.example.com/realms/platform"
+//! oidc-client-id = "crp-cli"
+//! ca-cert = "/etc/crp/staging-ca.pem"
+//! ```
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use crate::auth::{AuthOptions, OidcConfig, DEFAULT_OIDC_SCOPES};
+use crate::error::{CliError, ErrorKind};
+
+/// API URL used when neither a flag, CRP_API_URL nor a context sets one
+pub const DEFAULT_API_URL: &str = "http://localhost:8080";
+
 /// CLI configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
 pub struct Config {
-    /// API endpoint URL
-    pub api_url: Option<String>,
+    /// Context used when --context is not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_context: Option<String>,
     /// Default namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default_namespace: Option<String>,
     /// Default output format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default_format: Option<String>,
+    /// Named cluster contexts
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contexts: BTreeMap<String, ClusterContext>,
+}
+
+/// Connection settings for one cluster
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClusterContext {
+    /// Recommendation API URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// Bearer token sent to the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Kubeconfig context for commands that talk to the cluster directly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_context: Option<String>,
+    /// Kubeconfig file for commands that talk to the cluster directly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kubeconfig: Option<String>,
+    /// OIDC issuer URL for `crp login`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oidc_issuer: Option<String>,
+    /// OIDC client ID for `crp login`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oidc_client_id: Option<String>,
+    /// Space-separated OIDC scopes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oidc_scopes: Option<String>,
+    /// PEM client certificate for mTLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key for the client certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// PEM CA bundle for the API's server certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl ClusterContext {
+    /// Authentication settings of this context
+    pub fn auth(&self) -> AuthOptions {
+        AuthOptions {
+            token: self.token.clone(),
+            oidc: oidc_config(
+                self.oidc_issuer.clone(),
+                self.oidc_client_id.clone(),
+                self.oidc_scopes.clone(),
+            ),
+            client_cert: self.client_cert.clone(),
+            client_key: self.client_key.clone(),
+            ca_cert: self.ca_cert.clone(),
+        }
+    }
+}
+
+/// OIDC settings when both issuer and client ID are known
+pub fn oidc_config(
+    issuer: Option<String>,
+    client_id: Option<String>,
+    scopes: Option<String>,
+) -> Option<OidcConfig> {
+    Some(OidcConfig {
+        issuer: issuer?,
+        client_id: client_id?,
+        scopes: scopes.unwrap_or_else(|| DEFAULT_OIDC_SCOPES.to_string()),
+    })
 }
 
 impl Config {
     /// Load configuration from file
-    #[allow(dead_code)]
     pub fn load() -> Result<Self> {
         let config_path = Self::config_path()?;
 
@@ -28,11 +125,13 @@ impl Config {
         let content =
             std::fs::read_to_string(&config_path).context("Failed to read config file")?;
 
-        serde_json::from_str(&content).context("Failed to parse config file")
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", config_path.display()))
     }
 
     /// Save configuration to file
-    #[allow(dead_code)]
+    ///
+    /// The file may hold API tokens, so it is only readable by the owner.
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
 
@@ -40,16 +139,42 @@ impl Config {
             std::fs::create_dir_all(parent).context("Failed to create config directory")?;
         }
 
-        let content = serde_json::to_string_pretty(self).context("Failed to serialize config")?;
+        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
         std::fs::write(&config_path, content).context("Failed to write config file")?;
 
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&config_path, std::fs::Permissions::from_mode(0o600))
+                .context("Failed to restrict config file permissions")?;
+        }
+
         Ok(())
     }
 
+    /// The context named `name`, or the current context when `name` is None
+    pub fn context(&self, name: Option<&str>) -> Result<Option<&ClusterContext>> {
+        let Some(name) = name.or(self.current_context.as_deref()) else {
+            return Ok(None);
+        };
+        match self.contexts.get(name) {
+            Some(context) => Ok(Some(context)),
+            None => Err(CliError::new(
+                ErrorKind::NotFound,
+                format!(
+                    "Context '{}' not found in {}",
+                    name,
+                    Self::config_path()?.display()
+                ),
+            )
+            .into()),
+        }
+    }
+
     /// Get the configuration file path
-    fn config_path() -> Result<PathBuf> {
+    pub fn config_path() -> Result<PathBuf> {
         let home = dirs_next::home_dir().context("Could not determine home directory")?;
-        Ok(home.join(".config").join("crp").join("config.json"))
+        Ok(home.join(".config").join("crp").join("config.toml"))
     }
 }
 
@@ -0,0 +1,127 @@
+//! Exit codes and the error envelope printed when a command fails
+//!
+//! | Code | Kind              | Meaning                                  |
+//! |------|-------------------|------------------------------------------|
+//! | 0    |                   | Success                                  |
+//! | 1    | `failure`         | Any other error, including usage errors  |
+//! | 2    | `partial_failure` | Some items of a bulk operation failed    |
+//! | 3    | `not_found`       | Recommendation, context or resource      |
+//! | 4    | `auth`            | Missing, invalid or rejected credentials |
+//! | 5    | `connection`      | API unreachable or timed out             |
+
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::process::ExitCode;
+
+use crate::client::ApiError;
+use crate::output::{print_error, OutputFormat};
+
+/// Failure category, determining the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Failure,
+    PartialFailure,
+    NotFound,
+    Auth,
+    Connection,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Failure => 1,
+            ErrorKind::PartialFailure => 2,
+            ErrorKind::NotFound => 3,
+            ErrorKind::Auth => 4,
+            ErrorKind::Connection => 5,
+        }
+    }
+
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => ErrorKind::NotFound,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ErrorKind::Auth,
+            _ => ErrorKind::Failure,
+        }
+    }
+}
+
+/// Error raised by the CLI itself with an explicit kind
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// Kind of the first classifiable error in the chain
+pub fn classify(error: &anyhow::Error) -> ErrorKind {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<CliError>() {
+            return e.kind;
+        }
+        if let Some(ApiError::Status { status, .. }) = cause.downcast_ref::<ApiError>() {
+            return ErrorKind::from_status(*status);
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if e.is_connect() || e.is_timeout() {
+                return ErrorKind::Connection;
+            }
+            if let Some(status) = e.status() {
+                return ErrorKind::from_status(status);
+            }
+        }
+    }
+    ErrorKind::Failure
+}
+
+/// Machine-readable error, printed to stderr with `--format json|yaml`
+#[derive(Debug, Serialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    kind: ErrorKind,
+    exit_code: u8,
+    message: String,
+    /// Underlying causes, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    causes: Vec<String>,
+}
+
+/// Print `error` in the requested format and return the matching exit code
+pub fn report(error: &anyhow::Error, format: &OutputFormat) -> ExitCode {
+    let kind = classify(error);
+    let envelope = ErrorEnvelope {
+        error: ErrorBody {
+            kind,
+            exit_code: kind.exit_code(),
+            message: error.to_string(),
+            causes: error.chain().skip(1).map(|c| c.to_string()).collect(),
+        },
+    };
+
+    let rendered = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(&envelope).ok(),
+        OutputFormat::Yaml => serde_yaml::to_string(&envelope).ok(),
+        _ => None,
+    };
+    match rendered {
+        Some(rendered) => eprintln!("{}", rendered.trim_end()),
+        None => print_error(&format!("{:#}", error)),
+    }
+
+    ExitCode::from(kind.exit_code())
+}
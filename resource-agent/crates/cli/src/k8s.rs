@@ -0,0 +1,137 @@
+//! Kubernetes helpers shared by commands that talk to the cluster directly
+
+use anyhow::{Context, Result};
+use k8s_openapi::api::core::v1::Container;
+use kube::config::{KubeConfigOptions, Kubeconfig};
+
+use crate::client::Recommendation;
+
+/// Annotation recording the recommendation last applied to a workload
+pub const RECOMMENDATION_ANNOTATION: &str = "predictor.io/recommendation-id";
+
+/// Annotation recording when that recommendation was applied
+pub const APPLIED_AT_ANNOTATION: &str = "predictor.io/applied-at";
+
+/// Kubeconfig file and context used to reach the cluster
+#[derive(Debug, Clone, Default)]
+pub struct KubeOptions {
+    /// Kubeconfig file, or the default one when unset
+    pub kubeconfig: Option<String>,
+    /// Kubeconfig context, or the current one when unset
+    pub context: Option<String>,
+}
+
+/// Build a client from the given kubeconfig and context, or the defaults
+pub async fn client(options: &KubeOptions) -> Result<kube::Client> {
+    let config_options = KubeConfigOptions {
+        context: options.context.clone(),
+        ..Default::default()
+    };
+    let config = match (&options.kubeconfig, &options.context) {
+        (Some(path), _) => {
+            let kubeconfig = Kubeconfig::read_from(path)
+                .with_context(|| format!("Failed to read kubeconfig {}", path))?;
+            kube::Config::from_custom_kubeconfig(kubeconfig, &config_options)
+                .await
+                .context("Invalid kubeconfig")?
+        }
+        (None, Some(context)) => kube::Config::from_kubeconfig(&config_options)
+            .await
+            .with_context(|| format!("Failed to load kubeconfig context {}", context))?,
+        (None, None) => kube::Config::infer()
+            .await
+            .context("Failed to load Kubernetes configuration")?,
+    };
+    kube::Client::try_from(config).context("Failed to create Kubernetes client")
+}
+
+/// Index of the container a recommendation for `workload` applies to
+///
+/// The container named after the workload, or the only container when
+/// names don't match.
+pub fn target_container(containers: &[Container], workload: &str) -> Option<usize> {
+    containers
+        .iter()
+        .position(|c| c.name == workload)
+        .or_else(|| (containers.len() == 1).then_some(0))
+}
+
+/// (cpu request, cpu limit, memory request, memory limit) quantities
+pub type Quantities = (String, String, String, String);
+
+/// Recommended quantities for each container of `containers` they apply to,
+/// by container index
+///
+/// With per-container recommendations in the pod summary, every container
+/// named there gets its own; otherwise only the target container gets the
+/// recommendation.
+pub fn recommended_containers(
+    rec: &Recommendation,
+    containers: &[Container],
+) -> Vec<(usize, Quantities)> {
+    let per_container = rec
+        .pod_summary
+        .as_ref()
+        .map(|pod| pod.containers.as_slice())
+        .unwrap_or_default();
+    if per_container.is_empty() {
+        return target_container(containers, &rec.deployment)
+            .map(|index| (index, recommended_quantities(rec)))
+            .into_iter()
+            .collect();
+    }
+
+    containers
+        .iter()
+        .enumerate()
+        .filter_map(|(index, container)| {
+            let recommended = per_container.iter().find(|c| c.name == container.name)?;
+            Some((
+                index,
+                (
+                    cpu_quantity(recommended.cpu_request_millicores),
+                    cpu_quantity(recommended.cpu_limit_millicores),
+                    memory_quantity(recommended.memory_request_bytes),
+                    memory_quantity(recommended.memory_limit_bytes),
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// Recommended (cpu request, cpu limit, memory request, memory limit) quantities
+pub fn recommended_quantities(rec: &Recommendation) -> Quantities {
+    match &rec.recommended_resources {
+        Some(spec) => (
+            spec.cpu_request.clone(),
+            spec.cpu_limit.clone(),
+            spec.memory_request.clone(),
+            spec.memory_limit.clone(),
+        ),
+        None => (
+            cpu_quantity(rec.cpu_request_millicores),
+            cpu_quantity(rec.cpu_limit_millicores),
+            memory_quantity(rec.memory_request_bytes),
+            memory_quantity(rec.memory_limit_bytes),
+        ),
+    }
+}
+
+/// Kubernetes quantity for millicores, e.g. `250m` or `2`
+pub fn cpu_quantity(millicores: u32) -> String {
+    if millicores > 0 && millicores % 1000 == 0 {
+        (millicores / 1000).to_string()
+    } else {
+        format!("{}m", millicores)
+    }
+}
+
+/// Kubernetes quantity for bytes in the largest exact binary unit
+pub fn memory_quantity(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [("Gi", 1 << 30), ("Mi", 1 << 20), ("Ki", 1 << 10)];
+    UNITS
+        .iter()
+        .find(|(_, size)| bytes > 0 && bytes % size == 0)
+        .map(|(unit, size)| format!("{}{}", bytes / size, unit))
+        .unwrap_or_else(|| bytes.to_string())
+}
@@ -3,36 +3,61 @@
 //! A command-line tool for querying recommendations, viewing costs,
 //! and debugging the container resource predictor system.
 
+mod auth;
 mod client;
 mod commands;
 mod config;
+mod error;
+mod k8s;
 mod output;
+mod pricing;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use commands::{costs, debug, recommendations};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use commands::{
+    anomalies, bulk, bundle, completion, context, costs, debug, diff, direct, export, history,
+    policy, recommendations,
+};
+use recommendations::OwnerFilter;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Container Resource Predictor CLI
 #[derive(Parser)]
 #[command(name = "crp")]
 #[command(author, version, about = "CLI for Container Resource Predictor", long_about = None)]
 pub struct Cli {
-    /// API endpoint URL (can also be set via CRP_API_URL env var)
-    #[arg(long, env = "CRP_API_URL", default_value = "http://localhost:8080")]
-    pub api_url: String,
+    /// API endpoint URL, overriding the context's (default: http://localhost:8080)
+    #[arg(long, env = "CRP_API_URL")]
+    pub api_url: Option<String>,
+
+    /// Context from ~/.config/crp/config.toml to use instead of the current one
+    #[arg(long, visible_alias = "cluster")]
+    pub context: Option<String>,
 
     /// Path to kubeconfig file (uses default if not specified)
     #[arg(long, env = "KUBECONFIG")]
     pub kubeconfig: Option<String>,
 
-    /// Output format
-    #[arg(long, short, default_value = "table")]
+    /// Output format: table, wide, json, yaml, csv or custom-columns=HEADER:.path,...
+    #[arg(long, short, short_alias = 'o', default_value = "table")]
     pub format: output::OutputFormat,
 
+    /// API request timeout in seconds
+    #[arg(long, env = "CRP_TIMEOUT", default_value_t = client::DEFAULT_TIMEOUT_SECS)]
+    pub timeout: u64,
+
+    /// Retries for failed API reads (connection errors, timeouts, 429/502/503/504)
+    #[arg(long, env = "CRP_RETRIES", default_value_t = client::DEFAULT_RETRIES)]
+    pub retries: u32,
+
     /// Enable verbose output
     #[arg(long, short)]
     pub verbose: bool,
 
+    #[command(flatten)]
+    pub auth: AuthArgs,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -43,20 +68,34 @@ pub enum Commands {
     #[command(subcommand)]
     Get(GetCommands),
 
-    /// Apply a recommendation
+    /// Apply a recommendation, or all matching ones with --all
     Apply {
         /// Recommendation ID to apply
-        id: String,
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
 
-        /// Perform a dry-run without applying changes
-        #[arg(long)]
-        dry_run: bool,
+        /// Perform a dry-run without applying changes (server only with --direct)
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "client")]
+        dry_run: Option<direct::DryRunMode>,
+
+        /// Patch the workload through the Kubernetes API instead of the CRP API
+        #[arg(long, conflicts_with = "all")]
+        direct: bool,
+
+        /// With --direct, cap the rollout's maxUnavailable to what the
+        /// workload's PodDisruptionBudgets allow instead of refusing
+        #[arg(long, requires = "direct")]
+        stagger: bool,
+
+        #[command(flatten)]
+        bulk: BulkArgs,
     },
 
-    /// Approve a recommendation for application
+    /// Approve a recommendation, or all matching ones with --all
     Approve {
         /// Recommendation ID to approve
-        id: String,
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
 
         /// Approver name
         #[arg(long, default_value = "cli-user")]
@@ -65,8 +104,35 @@ pub enum Commands {
         /// Reason for approval
         #[arg(long)]
         reason: Option<String>,
+
+        #[command(flatten)]
+        bulk: BulkArgs,
+    },
+
+    /// Roll back an applied recommendation
+    Rollback {
+        /// Recommendation ID to roll back
+        id: String,
+
+        /// Revision to restore (defaults to the one before the recommendation)
+        #[arg(long)]
+        to_revision: Option<u32>,
+
+        /// Skip the confirmation prompt
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// Compare a Deployment's live resources with its recommendation
+    Diff {
+        /// Deployment as <namespace>/<deployment>
+        target: String,
     },
 
+    /// Manage guardrail policies for recommendations
+    #[command(subcommand)]
+    Policy(PolicyCommands),
+
     /// View cost analysis and savings
     #[command(subcommand)]
     Costs(CostsCommands),
@@ -74,6 +140,172 @@ pub enum Commands {
     /// Debug and troubleshooting commands
     #[command(subcommand)]
     Debug(DebugCommands),
+
+    /// Export recommendations for GitOps workflows
+    #[command(subcommand)]
+    Export(ExportCommands),
+
+    /// Print a shell completion script
+    Completion {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Generate documentation
+    #[command(subcommand)]
+    Docs(DocsCommands),
+
+    /// Manage cluster contexts
+    #[command(subcommand)]
+    Config(ConfigCommands),
+
+    /// Log in with the configured OIDC provider (device flow)
+    Login,
+
+    /// Remove cached OIDC tokens
+    Logout,
+
+    /// List namespaces for shell completion
+    #[command(name = completion::COMPLETE_NAMESPACES, hide = true)]
+    CompleteNamespaces,
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommands {
+    /// Render recommendations as patch files to commit to Git
+    Manifests {
+        /// Filter by namespace
+        #[arg(long, short)]
+        namespace: Option<String>,
+
+        /// Filter by owning workload, as [kind/]name (e.g. statefulset/db)
+        #[arg(
+            long,
+            short = 'd',
+            visible_alias = "deployment",
+            value_name = "[KIND/]NAME"
+        )]
+        owner: Option<OwnerFilter>,
+
+        /// Filter by status (pending, approved, applied)
+        #[arg(long)]
+        status: Option<String>,
+
+        /// Patch format
+        #[arg(long = "format", value_enum, default_value = "kustomize")]
+        manifest_format: export::ManifestFormat,
+
+        /// Directory to write the files to (prints them if not specified)
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DocsCommands {
+    /// Generate man pages
+    Man {
+        /// Directory to write a page per command to (prints crp(1) if not specified)
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+    },
+}
+
+/// Credentials for the Recommendation API, overriding the context's
+#[derive(Args)]
+#[command(next_help_heading = "Authentication")]
+pub struct AuthArgs {
+    /// Bearer token for the API
+    #[arg(long, env = "CRP_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// OIDC issuer URL for device-flow login
+    #[arg(long, env = "CRP_OIDC_ISSUER")]
+    pub oidc_issuer: Option<String>,
+
+    /// OIDC client ID for device-flow login
+    #[arg(long, env = "CRP_OIDC_CLIENT_ID")]
+    pub oidc_client_id: Option<String>,
+
+    /// Space-separated OIDC scopes (default: "openid offline_access")
+    #[arg(long, env = "CRP_OIDC_SCOPES")]
+    pub oidc_scopes: Option<String>,
+
+    /// PEM client certificate for mTLS
+    #[arg(long, env = "CRP_CLIENT_CERT")]
+    pub client_cert: Option<PathBuf>,
+
+    /// PEM private key for the client certificate
+    #[arg(long, env = "CRP_CLIENT_KEY")]
+    pub client_key: Option<PathBuf>,
+
+    /// PEM CA bundle to trust for the API's certificate
+    #[arg(long, env = "CRP_CA_CERT")]
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl From<AuthArgs> for auth::AuthOptions {
+    fn from(args: AuthArgs) -> Self {
+        Self {
+            token: args.token,
+            oidc: config::oidc_config(args.oidc_issuer, args.oidc_client_id, args.oidc_scopes),
+            client_cert: args.client_cert,
+            client_key: args.client_key,
+            ca_cert: args.ca_cert,
+        }
+    }
+}
+
+/// Options for acting on many recommendations at once
+#[derive(Args)]
+pub struct BulkArgs {
+    /// Act on every recommendation matching the filters
+    #[arg(long)]
+    pub all: bool,
+
+    /// Only recommendations in this namespace
+    #[arg(long, short, requires = "all")]
+    pub namespace: Option<String>,
+
+    /// Only recommendations whose owner matches [kind/]name, the name as a substring
+    #[arg(
+        long,
+        short = 'd',
+        visible_alias = "deployment",
+        value_name = "[KIND/]NAME",
+        requires = "all"
+    )]
+    pub owner: Option<OwnerFilter>,
+
+    /// Minimum reduction of the CPU or memory request, in percent
+    #[arg(long, requires = "all")]
+    pub min_savings: Option<f64>,
+
+    /// Number of requests sent in parallel
+    #[arg(long, default_value_t = bulk::DEFAULT_CONCURRENCY, requires = "all")]
+    pub concurrency: usize,
+
+    /// Maximum requests per second
+    #[arg(long, default_value_t = bulk::DEFAULT_RATE_LIMIT, requires = "all")]
+    pub rate_limit: f64,
+
+    /// Skip the confirmation prompt
+    #[arg(long, short, requires = "all")]
+    pub yes: bool,
+}
+
+impl From<BulkArgs> for bulk::BulkOptions {
+    fn from(args: BulkArgs) -> Self {
+        Self {
+            namespace: args.namespace,
+            owner: args.owner,
+            min_savings: args.min_savings,
+            concurrency: args.concurrency,
+            rate_limit: args.rate_limit,
+            yes: args.yes,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -84,13 +316,38 @@ pub enum GetCommands {
         #[arg(long, short)]
         namespace: Option<String>,
 
-        /// Filter by deployment name
-        #[arg(long, short)]
-        deployment: Option<String>,
+        /// Filter by owning workload, as [kind/]name (e.g. statefulset/db)
+        #[arg(
+            long,
+            short = 'd',
+            visible_alias = "deployment",
+            value_name = "[KIND/]NAME"
+        )]
+        owner: Option<OwnerFilter>,
 
         /// Filter by status (pending, approved, applied, rolled_back)
         #[arg(long)]
         status: Option<String>,
+
+        /// Keep polling and redraw the table as statuses change
+        #[arg(long, short)]
+        watch: bool,
+
+        /// Seconds between polls in watch mode
+        #[arg(long, default_value_t = output::DEFAULT_WATCH_INTERVAL_SECS, requires = "watch")]
+        interval: u64,
+    },
+
+    /// Get recommendations for workloads with a similar usage shape
+    Similar {
+        /// Recommendation ID
+        id: String,
+    },
+
+    /// Show the timeline of recommendations, approvals, applies and rollbacks
+    History {
+        /// Deployment as <namespace>/<deployment>
+        target: String,
     },
 
     /// Get model versions
@@ -99,6 +356,81 @@ pub enum GetCommands {
         #[arg(long)]
         active_only: bool,
     },
+
+    /// Get recent anomalies reported by agents
+    Anomalies {
+        /// Filter by namespace
+        #[arg(long, short)]
+        namespace: Option<String>,
+
+        /// Filter by severity (warning, critical)
+        #[arg(long)]
+        severity: Option<String>,
+
+        /// Only anomalies detected within this window (e.g. 30m, 6h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Keep polling and redraw the table as anomalies are reported
+        #[arg(long, short)]
+        watch: bool,
+
+        /// Seconds between polls in watch mode
+        #[arg(long, default_value_t = output::DEFAULT_WATCH_INTERVAL_SECS, requires = "watch")]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PolicyCommands {
+    /// List policies
+    List,
+
+    /// Create or replace a policy
+    Set {
+        /// Policy name
+        name: String,
+
+        /// Namespace the policy applies to (repeatable; all if not specified)
+        #[arg(long = "namespace", short)]
+        namespaces: Vec<String>,
+
+        /// Minimum recommended CPU request (e.g. 100m)
+        #[arg(long, value_parser = policy::parse_cpu)]
+        min_cpu: Option<u32>,
+
+        /// Maximum recommended CPU request (e.g. 4)
+        #[arg(long, value_parser = policy::parse_cpu)]
+        max_cpu: Option<u32>,
+
+        /// Minimum recommended memory request (e.g. 128Mi)
+        #[arg(long, value_parser = policy::parse_memory)]
+        min_memory: Option<u64>,
+
+        /// Maximum recommended memory request (e.g. 8Gi)
+        #[arg(long, value_parser = policy::parse_memory)]
+        max_memory: Option<u64>,
+
+        /// Namespace whose recommendations must never be applied (repeatable)
+        #[arg(long = "exclude-namespace")]
+        excluded_namespaces: Vec<String>,
+
+        /// Require recommendations to be approved before they are applied
+        #[arg(long)]
+        require_approval: bool,
+    },
+
+    /// Delete a policy
+    Delete {
+        /// Policy name
+        name: String,
+    },
+
+    /// Show which policies a recommendation would violate
+    Test {
+        /// Recommendation ID to check
+        id: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -108,6 +440,10 @@ pub enum CostsCommands {
         /// Filter by namespace (shows cluster-wide if not specified)
         #[arg(long, short)]
         namespace: Option<String>,
+
+        /// Estimate costs with rates from a price sheet or cloud pricing file
+        #[arg(long)]
+        pricing_file: Option<PathBuf>,
     },
 
     /// Show savings report
@@ -115,6 +451,40 @@ pub enum CostsCommands {
         /// Time period (e.g., 30d, 90d, 1y)
         #[arg(long, default_value = "30d")]
         since: String,
+
+        /// Estimate savings with rates from a price sheet or cloud pricing file
+        #[arg(long)]
+        pricing_file: Option<PathBuf>,
+    },
+
+    /// Project savings if all pending recommendations were applied
+    Forecast {
+        /// Filter by namespace
+        #[arg(long, short)]
+        namespace: Option<String>,
+
+        /// Price sheet or cloud pricing file (defaults to AWS on-demand rates)
+        #[arg(long)]
+        pricing_file: Option<PathBuf>,
+    },
+
+    /// Model costs under a different buffer policy before changing it
+    WhatIf {
+        /// Extra CPU added to recommended requests (e.g. 30%)
+        #[arg(long, default_value = "0%", value_parser = costs::parse_percent)]
+        cpu_buffer: f64,
+
+        /// Extra memory added to recommended requests (e.g. 20%)
+        #[arg(long, default_value = "0%", value_parser = costs::parse_percent)]
+        memory_buffer: f64,
+
+        /// Filter by namespace
+        #[arg(long, short)]
+        namespace: Option<String>,
+
+        /// Price sheet or cloud pricing file (defaults to AWS on-demand rates)
+        #[arg(long)]
+        pricing_file: Option<PathBuf>,
     },
 }
 
@@ -124,12 +494,60 @@ pub enum DebugCommands {
     Predictions {
         /// Deployment name (format: namespace/deployment or just deployment)
         deployment: String,
+
+        /// Agent API URL (e.g. via kubectl port-forward) to include the usage it holds
+        #[arg(long)]
+        agent_url: Option<String>,
+
+        /// Usage window shown from the agent (e.g. 1h, 24h)
+        #[arg(long, default_value = "1h", requires = "agent_url")]
+        since: String,
     },
 
     /// Show agent status on a node
     Agent {
         /// Node name
         node: String,
+
+        /// Agent API URL (e.g. via kubectl port-forward) to include its internal state
+        /// and recent anomalies
+        #[arg(long)]
+        agent_url: Option<String>,
+
+        /// Keep polling and redraw as the agent changes
+        #[arg(long, short)]
+        watch: bool,
+
+        /// Seconds between polls in watch mode
+        #[arg(long, default_value_t = output::DEFAULT_WATCH_INTERVAL_SECS, requires = "watch")]
+        interval: u64,
+    },
+
+    /// Collect agent status, state, metrics, health and logs into a tar.gz
+    Bundle {
+        /// Node whose agent to collect from
+        #[arg(long)]
+        node: String,
+
+        /// Archive path (default: crp-bundle-<node>-<time>.tar.gz)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Agent API URL (e.g. via kubectl port-forward) instead of the API server pod proxy
+        #[arg(long)]
+        agent_url: Option<String>,
+
+        /// Agent API port used through the pod proxy
+        #[arg(long, default_value_t = bundle::DEFAULT_AGENT_PORT)]
+        agent_port: u16,
+
+        /// Number of recent agent log lines to include
+        #[arg(long, default_value_t = bundle::DEFAULT_LOG_LINES)]
+        log_lines: i64,
+
+        /// Replace namespace names with pseudonyms
+        #[arg(long)]
+        redact_namespaces: bool,
     },
 
     /// Export metrics data
@@ -145,65 +563,521 @@ pub enum DebugCommands {
         /// Namespace filter
         #[arg(long, short)]
         namespace: Option<String>,
+
+        /// File format: json, csv or parquet (default: from the output extension, else json)
+        #[arg(long)]
+        file_format: Option<String>,
+
+        /// Agent API URL (e.g. via kubectl port-forward) to export from instead of the API
+        #[arg(long)]
+        agent_url: Option<String>,
+
+        /// Samples to export from the agent: buffer (offline buffer) or live (held for
+        /// prediction); buffer when the agent syncs metrics, else live
+        #[arg(long, requires = "agent_url")]
+        source: Option<String>,
+    },
+
+    /// Replay exported metrics through prediction and anomaly detection
+    Replay {
+        /// File written by `crp debug export`, or a JSON array of agent samples
+        file: PathBuf,
+
+        /// ONNX model to predict with (default: fallback heuristics)
+        #[arg(long)]
+        model: Option<PathBuf>,
+
+        /// Seconds between predictions per container (default: 300)
+        #[arg(long)]
+        prediction_interval: Option<u64>,
+
+        /// Memory growth in bytes/sec reported as a leak (default: 1024)
+        #[arg(long)]
+        leak_threshold: Option<f64>,
+
+        /// Standard deviations above the mean reported as a CPU spike (default: 3)
+        #[arg(long)]
+        spike_threshold: Option<f64>,
+
+        /// Show anomalies only, not predictions
+        #[arg(long)]
+        anomalies_only: bool,
+    },
+}
+
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
+pub enum ConfigCommands {
+    /// List configured contexts
+    GetContexts,
+
+    /// Switch the current context
+    UseContext {
+        /// Context name
+        name: String,
+    },
+
+    /// Create a context or update its settings
+    SetContext {
+        /// Context name
+        name: String,
+
+        /// Recommendation API URL of the cluster
+        #[arg(long = "api-url")]
+        api_url: Option<String>,
+
+        /// Bearer token for the API
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Kubeconfig context for diff and direct apply
+        #[arg(long)]
+        kube_context: Option<String>,
+
+        /// OIDC issuer URL for `crp login`
+        #[arg(long = "issuer")]
+        oidc_issuer: Option<String>,
+
+        /// OIDC client ID for `crp login`
+        #[arg(long = "client-id")]
+        oidc_client_id: Option<String>,
+
+        /// Space-separated OIDC scopes
+        #[arg(long = "scopes")]
+        oidc_scopes: Option<String>,
+
+        /// PEM client certificate for mTLS
+        #[arg(long = "cert")]
+        client_cert: Option<PathBuf>,
+
+        /// PEM private key for the client certificate
+        #[arg(long = "key")]
+        client_key: Option<PathBuf>,
+
+        /// PEM CA bundle to trust for the API's certificate
+        #[arg(long = "ca")]
+        ca_cert: Option<PathBuf>,
     },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+async fn main() -> std::process::ExitCode {
+    // clap exits with 2 on usage errors, which is reserved for partial failures
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        let _ = e.print();
+        std::process::exit(if e.use_stderr() { 1 } else { 0 });
+    });
+
+    let format = cli.format.clone();
+    match run(cli).await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => error::report(&e, &format),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let mut config = config::Config::load()?;
+
+    // Context commands only touch the config file
+    if let Commands::Config(config_cmd) = cli.command {
+        return match config_cmd {
+            ConfigCommands::GetContexts => context::get_contexts(&config, &cli.format),
+            ConfigCommands::UseContext { name } => context::use_context(&mut config, &name),
+            ConfigCommands::SetContext {
+                name,
+                api_url,
+                token,
+                kube_context,
+                oidc_issuer,
+                oidc_client_id,
+                oidc_scopes,
+                client_cert,
+                client_key,
+                ca_cert,
+            } => context::set_context(
+                &mut config,
+                &name,
+                config::ClusterContext {
+                    api_url,
+                    token,
+                    kube_context,
+                    kubeconfig: None,
+                    oidc_issuer,
+                    oidc_client_id,
+                    oidc_scopes,
+                    client_cert,
+                    client_key,
+                    ca_cert,
+                },
+            ),
+        };
+    }
+
+    // Flags and env vars take precedence over the selected context
+    let cluster = config
+        .context(cli.context.as_deref())?
+        .cloned()
+        .unwrap_or_default();
+    let auth = auth::AuthOptions::from(cli.auth).or(cluster.auth());
+    let api_url = cli
+        .api_url
+        .clone()
+        .or(cluster.api_url)
+        .unwrap_or_else(|| config::DEFAULT_API_URL.to_string());
+    let kube = k8s::KubeOptions {
+        kubeconfig: cli.kubeconfig.clone().or(cluster.kubeconfig),
+        context: cluster.kube_context,
+    };
+
+    if let Commands::Login | Commands::Logout = cli.command {
+        let Some(oidc) = auth.oidc else {
+            return Err(error::CliError::new(
+                error::ErrorKind::Auth,
+                "No OIDC provider configured; set --oidc-issuer and --oidc-client-id \
+                 or: crp config set-context <NAME> --issuer <URL> --client-id <ID>",
+            )
+            .into());
+        };
+        let session = auth::OidcSession::new(oidc)?;
+        if let Commands::Login = cli.command {
+            session.login().await?;
+            output::print_success("Logged in");
+        } else if session.logout()? {
+            output::print_success("Logged out");
+        } else {
+            output::print_warning("Not logged in");
+        }
+        return Ok(());
+    }
 
     // Initialize client
-    let client = client::ApiClient::new(&cli.api_url)?;
+    let client = client::ApiClient::with_auth(&api_url, &auth)?
+        .with_timeout(Duration::from_secs(cli.timeout.max(1)))
+        .with_retries(cli.retries);
 
     // Execute command
     match cli.command {
         Commands::Get(get_cmd) => match get_cmd {
             GetCommands::Recommendations {
                 namespace,
-                deployment,
+                owner,
+                status,
+                watch: true,
+                interval,
+            } => {
+                recommendations::watch_recommendations(
+                    &client,
+                    namespace,
+                    owner,
+                    status,
+                    Duration::from_secs(interval.max(1)),
+                    &cli.format,
+                )
+                .await?;
+            }
+            GetCommands::Recommendations {
+                namespace,
+                owner,
                 status,
+                ..
             } => {
                 recommendations::get_recommendations(
-                    &client, namespace, deployment, status, cli.format,
+                    &client,
+                    namespace,
+                    owner,
+                    status,
+                    &cli.format,
                 )
                 .await?;
             }
+            GetCommands::Similar { id } => {
+                recommendations::get_similar(&client, &id, &cli.format).await?;
+            }
+            GetCommands::History { target } => {
+                history::show_history(&client, &target, &cli.format).await?;
+            }
             GetCommands::Models { active_only } => {
-                recommendations::get_models(&client, active_only, cli.format).await?;
+                recommendations::get_models(&client, active_only, &cli.format).await?;
+            }
+            GetCommands::Anomalies {
+                namespace,
+                severity,
+                since,
+                watch,
+                interval,
+            } => {
+                let filters = anomalies::AnomalyFilters {
+                    namespace,
+                    severity,
+                    since,
+                };
+                if watch {
+                    anomalies::watch_anomalies(
+                        &client,
+                        &filters,
+                        Duration::from_secs(interval.max(1)),
+                        &cli.format,
+                    )
+                    .await?;
+                } else {
+                    anomalies::get_anomalies(&client, &filters, &cli.format).await?;
+                }
+            }
+        },
+        Commands::Apply {
+            id,
+            dry_run,
+            direct,
+            stagger,
+            bulk,
+        } => match id {
+            Some(id) if direct => {
+                direct::apply_direct(&client, &kube, &id, dry_run, stagger, &cli.format).await?;
+            }
+            Some(id) => {
+                recommendations::apply_recommendation(&client, &id, dry_run.is_some(), &cli.format)
+                    .await?;
+            }
+            None => {
+                bulk::apply_all(&client, &bulk.into(), dry_run.is_some(), &cli.format).await?;
             }
         },
-        Commands::Apply { id, dry_run } => {
-            recommendations::apply_recommendation(&client, &id, dry_run, cli.format).await?;
-        }
         Commands::Approve {
             id,
             approver,
             reason,
+            bulk,
+        } => match id {
+            Some(id) => {
+                recommendations::approve_recommendation(
+                    &client,
+                    &id,
+                    &approver,
+                    reason,
+                    &cli.format,
+                )
+                .await?;
+            }
+            None => {
+                bulk::approve_all(&client, &bulk.into(), &approver, reason, &cli.format).await?;
+            }
+        },
+        Commands::Rollback {
+            id,
+            to_revision,
+            yes,
         } => {
-            recommendations::approve_recommendation(&client, &id, &approver, reason, cli.format)
+            recommendations::rollback_recommendation(&client, &id, to_revision, yes, &cli.format)
                 .await?;
         }
+        Commands::Diff { target } => {
+            diff::show_diff(&client, &kube, &target, &cli.format).await?;
+        }
+        Commands::Policy(policy_cmd) => match policy_cmd {
+            PolicyCommands::List => {
+                policy::list_policies(&client, &cli.format).await?;
+            }
+            PolicyCommands::Set {
+                name,
+                namespaces,
+                min_cpu,
+                max_cpu,
+                min_memory,
+                max_memory,
+                excluded_namespaces,
+                require_approval,
+            } => {
+                let policy = client::Policy {
+                    name,
+                    namespaces,
+                    min_cpu_millicores: min_cpu,
+                    max_cpu_millicores: max_cpu,
+                    min_memory_bytes: min_memory,
+                    max_memory_bytes: max_memory,
+                    excluded_namespaces,
+                    require_approval,
+                    ..Default::default()
+                };
+                policy::set_policy(&client, policy, &cli.format).await?;
+            }
+            PolicyCommands::Delete { name } => {
+                policy::delete_policy(&client, &name).await?;
+            }
+            PolicyCommands::Test { id } => {
+                policy::test_policies(&client, &id, &cli.format).await?;
+            }
+        },
         Commands::Costs(costs_cmd) => match costs_cmd {
-            CostsCommands::Show { namespace } => {
-                costs::show_costs(&client, namespace, cli.format).await?;
+            CostsCommands::Show {
+                namespace,
+                pricing_file,
+            } => {
+                let pricing = pricing_file.as_deref().map(pricing::load).transpose()?;
+                costs::show_costs(&client, namespace, pricing.as_deref(), &cli.format).await?;
             }
-            CostsCommands::Savings { since } => {
-                costs::show_savings(&client, &since, cli.format).await?;
+            CostsCommands::Savings {
+                since,
+                pricing_file,
+            } => {
+                let pricing = pricing_file.as_deref().map(pricing::load).transpose()?;
+                costs::show_savings(&client, &since, pricing.as_deref(), &cli.format).await?;
+            }
+            CostsCommands::Forecast {
+                namespace,
+                pricing_file,
+            } => {
+                let pricing = pricing::load_or_default(pricing_file.as_deref())?;
+                costs::show_forecast(&client, namespace, pricing.as_ref(), &cli.format).await?;
+            }
+            CostsCommands::WhatIf {
+                cpu_buffer,
+                memory_buffer,
+                namespace,
+                pricing_file,
+            } => {
+                let pricing = pricing::load_or_default(pricing_file.as_deref())?;
+                costs::show_what_if(
+                    &client,
+                    namespace,
+                    cpu_buffer,
+                    memory_buffer,
+                    pricing.as_ref(),
+                    &cli.format,
+                )
+                .await?;
             }
         },
+        Commands::Export(export_cmd) => match export_cmd {
+            ExportCommands::Manifests {
+                namespace,
+                owner,
+                status,
+                manifest_format,
+                output_dir,
+            } => {
+                export::export_manifests(
+                    &client,
+                    namespace,
+                    owner,
+                    status,
+                    manifest_format,
+                    output_dir.as_deref(),
+                )
+                .await?;
+            }
+        },
+        // Handled before creating the client
+        Commands::Config(_) | Commands::Login | Commands::Logout => {}
+        Commands::Completion { shell } => {
+            completion::print_completions(&mut Cli::command(), shell)?;
+        }
+        Commands::Docs(docs_cmd) => match docs_cmd {
+            DocsCommands::Man { output_dir } => {
+                completion::generate_man(Cli::command(), output_dir.as_deref())?;
+            }
+        },
+        Commands::CompleteNamespaces => {
+            completion::complete_namespaces(&client).await?;
+        }
         Commands::Debug(debug_cmd) => match debug_cmd {
-            DebugCommands::Predictions { deployment } => {
-                debug::show_predictions(&client, &deployment, cli.format).await?;
+            DebugCommands::Predictions {
+                deployment,
+                agent_url,
+                since,
+            } => {
+                debug::show_predictions(&client, &deployment, &cli.format).await?;
+                if let Some(url) = agent_url {
+                    let agent = client::ApiClient::new(&url)?
+                        .with_timeout(Duration::from_secs(cli.timeout.max(1)))
+                        .with_retries(cli.retries);
+                    debug::show_prediction_usage(&agent, &deployment, &since, &cli.format).await?;
+                }
+            }
+            DebugCommands::Agent {
+                node,
+                agent_url,
+                watch,
+                interval,
+            } => {
+                let agent = agent_url
+                    .map(|url| client::ApiClient::new(&url))
+                    .transpose()?
+                    .map(|agent| {
+                        agent
+                            .with_timeout(Duration::from_secs(cli.timeout.max(1)))
+                            .with_retries(cli.retries)
+                    });
+                if watch {
+                    debug::watch_agent(
+                        &client,
+                        &node,
+                        agent.as_ref(),
+                        Duration::from_secs(interval.max(1)),
+                        &cli.format,
+                    )
+                    .await?;
+                } else {
+                    debug::show_agent_status(&client, &node, &cli.format).await?;
+                    if let Some(agent) = &agent {
+                        debug::show_agent_state(agent, &cli.format).await?;
+                        debug::show_agent_anomalies(agent, &cli.format).await?;
+                    }
+                }
             }
-            DebugCommands::Agent { node } => {
-                debug::show_agent_status(&client, &node, cli.format).await?;
+            DebugCommands::Bundle {
+                node,
+                output,
+                agent_url,
+                agent_port,
+                log_lines,
+                redact_namespaces,
+            } => {
+                let options = bundle::BundleOptions {
+                    node,
+                    output,
+                    agent_url,
+                    agent_port,
+                    log_lines,
+                    redact_namespaces,
+                };
+                bundle::collect_bundle(&client, &kube, &options).await?;
             }
             DebugCommands::Export {
                 since,
                 output,
                 namespace,
+                file_format,
+                agent_url,
+                source,
+            } => {
+                let agent = agent_url
+                    .map(|url| client::ApiClient::new(&url))
+                    .transpose()?;
+                let options = debug::ExportOptions {
+                    since,
+                    output,
+                    namespace,
+                    file_format,
+                    source,
+                };
+                debug::export_metrics(&client, agent.as_ref(), &options, &cli.format).await?;
+            }
+            DebugCommands::Replay {
+                file,
+                model,
+                prediction_interval,
+                leak_threshold,
+                spike_threshold,
+                anomalies_only,
             } => {
-                debug::export_metrics(&client, &since, output, namespace, cli.format).await?;
+                let options = debug::ReplayOptions {
+                    model,
+                    prediction_interval: prediction_interval.map(Duration::from_secs),
+                    leak_threshold,
+                    spike_threshold,
+                    anomalies_only,
+                };
+                debug::replay(&file, &options, &cli.format)?;
             }
         },
     }
@@ -1,37 +1,247 @@
 //! Output formatting utilities
 
-use clap::ValueEnum;
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 use serde::Serialize;
-use tabled::{settings::Style, Table, Tabled};
+use serde_json::Value;
+use std::io::{IsTerminal, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tabled::{builder::Builder, settings::Style, Table, Tabled};
+use tokio::sync::Notify;
+use tokio::time::{Interval, MissedTickBehavior};
 
 /// Output format for CLI commands
-#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+///
+/// Parsed from `table`, `wide`, `json`, `yaml`, `csv` or
+/// `custom-columns=HEADER:.path,...`.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum OutputFormat {
     /// Table format (default)
     #[default]
     Table,
+    /// Table with every field as a column
+    Wide,
     /// JSON format
     Json,
+    /// YAML format
+    Yaml,
+    /// CSV with every field as a column
+    Csv,
+    /// Table of selected fields
+    CustomColumns(Vec<Column>),
 }
 
-/// Print a table from a list of items
-#[allow(dead_code)]
-pub fn print_table<T: Tabled + Serialize>(items: &[T], format: OutputFormat) {
+/// A custom column: header and dotted path into the serialized item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub header: String,
+    pub path: String,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(spec) = s.strip_prefix("custom-columns=") {
+            return parse_columns(spec).map(OutputFormat::CustomColumns);
+        }
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "wide" => Ok(OutputFormat::Wide),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!(
+                "unknown format '{}', expected table, wide, json, yaml, csv or custom-columns=...",
+                other
+            )),
+        }
+    }
+}
+
+/// Parse `HEADER:.path,HEADER:.path`
+fn parse_columns(spec: &str) -> Result<Vec<Column>, String> {
+    let columns: Vec<Column> = spec
+        .split(',')
+        .filter(|column| !column.is_empty())
+        .map(|column| match column.split_once(':') {
+            Some((header, path)) if !header.is_empty() && !path.is_empty() => Ok(Column {
+                header: header.to_string(),
+                path: path.trim_start_matches('.').to_string(),
+            }),
+            _ => Err(format!(
+                "invalid column '{}', expected HEADER:.path",
+                column
+            )),
+        })
+        .collect::<Result<_, _>>()?;
+    if columns.is_empty() {
+        return Err("custom-columns needs at least one HEADER:.path column".to_string());
+    }
+    Ok(columns)
+}
+
+/// Print a list of items in any output format
+///
+/// `rows` builds the default table; every other format is built from the
+/// serialized items.
+pub fn print_list<T, R>(
+    items: &[T],
+    format: &OutputFormat,
+    rows: impl FnOnce(&[T]) -> Vec<R>,
+) -> Result<()>
+where
+    T: Serialize,
+    R: Tabled,
+{
     match format {
         OutputFormat::Table => {
             if items.is_empty() {
                 println!("{}", "No items found".yellow());
-                return;
+                return Ok(());
             }
-            let table = Table::new(items).with(Style::rounded()).to_string();
+            let table = Table::new(rows(items)).with(Style::rounded()).to_string();
             println!("{}", table);
         }
-        OutputFormat::Json => {
-            if let Ok(json) = serde_json::to_string_pretty(&items) {
-                println!("{}", json);
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(items)?),
+        _ => {
+            let records = items
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<_>, _>>()?;
+            print_records(&records, format);
+        }
+    }
+    Ok(())
+}
+
+/// Print a single value in any output format
+///
+/// `table` prints the default human-readable view. In wide format the
+/// value's fields are listed one per row.
+pub fn print_object<T: Serialize>(
+    value: &T,
+    format: &OutputFormat,
+    table: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => table()?,
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        OutputFormat::Wide => {
+            let mut fields = Vec::new();
+            flatten("", &serde_json::to_value(value)?, &mut fields);
+            let mut builder = Builder::default();
+            builder.push_record(["FIELD", "VALUE"]);
+            for (field, value) in fields {
+                builder.push_record([field, cell(&value, "<none>")]);
+            }
+            println!("{}", builder.build().with(Style::rounded()));
+        }
+        _ => print_records(&[serde_json::to_value(value)?], format),
+    }
+    Ok(())
+}
+
+/// Print serialized records as a wide table, CSV or custom columns
+fn print_records(records: &[Value], format: &OutputFormat) {
+    println!("{}", render_records(records, format));
+}
+
+/// Render serialized records as a wide table, CSV or custom columns
+fn render_records(records: &[Value], format: &OutputFormat) -> String {
+    let columns: Vec<Column> = match format {
+        OutputFormat::CustomColumns(columns) => columns.clone(),
+        _ => {
+            // Every leaf field, in first-seen order
+            let mut paths: Vec<String> = Vec::new();
+            for record in records {
+                let mut fields = Vec::new();
+                flatten("", record, &mut fields);
+                for (path, _) in fields {
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                }
+            }
+            paths
+                .into_iter()
+                .map(|path| Column {
+                    header: path.to_uppercase(),
+                    path,
+                })
+                .collect()
+        }
+    };
+
+    let values = |record: &Value, missing: &str| -> Vec<String> {
+        columns
+            .iter()
+            .map(|column| {
+                let value = column
+                    .path
+                    .split('.')
+                    .try_fold(record, |value, key| value.get(key));
+                value
+                    .map(|v| cell(v, missing))
+                    .unwrap_or_else(|| missing.to_string())
+            })
+            .collect()
+    };
+
+    if let OutputFormat::Csv = format {
+        let header: Vec<String> = columns.iter().map(|c| csv_field(&c.path)).collect();
+        let mut lines = vec![header.join(",")];
+        for record in records {
+            let fields: Vec<String> = values(record, "").iter().map(|v| csv_field(v)).collect();
+            lines.push(fields.join(","));
+        }
+        return lines.join("\n");
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(columns.iter().map(|c| c.header.clone()));
+    for record in records {
+        builder.push_record(values(record, "<none>"));
+    }
+    builder.build().with(Style::rounded()).to_string()
+}
+
+/// Collect leaf fields of `value` as dotted paths
+fn flatten(prefix: &str, value: &Value, fields: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, value, fields);
             }
         }
+        _ => fields.push((prefix.to_string(), value.clone())),
+    }
+}
+
+/// Render a JSON value as a single cell
+fn cell(value: &Value, missing: &str) -> String {
+    match value {
+        Value::Null => missing.to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a CSV field when it contains separators, quotes or newlines
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
     }
 }
 
@@ -41,7 +251,6 @@ pub fn print_success(message: &str) {
 }
 
 /// Print an error message
-#[allow(dead_code)]
 pub fn print_error(message: &str) {
     eprintln!("{} {}", "✗".red().bold(), message);
 }
@@ -56,6 +265,113 @@ pub fn print_info(message: &str) {
     println!("{} {}", "ℹ".blue().bold(), message);
 }
 
+/// Ask a yes/no question on the terminal, defaulting to no
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!("Refusing to prompt without a terminal; pass --yes to confirm");
+    }
+
+    // Prompt on stderr so JSON output on stdout stays parseable
+    eprint!("{} {} [y/N] ", "?".cyan().bold(), prompt);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Default time between polls in `--watch` mode
+pub const DEFAULT_WATCH_INTERVAL_SECS: u64 = 5;
+
+/// Drives `--watch` polling: ticks every interval until Ctrl-C
+pub struct Watcher {
+    period: Duration,
+    ticker: Interval,
+    format: OutputFormat,
+    interrupted: Arc<Notify>,
+}
+
+impl Watcher {
+    pub fn new(period: Duration, format: &OutputFormat) -> Self {
+        let interrupted = Arc::new(Notify::new());
+        let notify = interrupted.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                notify.notify_one();
+            }
+        });
+
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            period,
+            ticker,
+            format: format.clone(),
+            interrupted,
+        }
+    }
+
+    /// Wait for the next poll and clear the screen, false once interrupted
+    ///
+    /// JSON, YAML and CSV output is not cleared, so each poll appends.
+    pub async fn next(&mut self) -> bool {
+        tokio::select! {
+            _ = self.ticker.tick() => {}
+            _ = self.interrupted.notified() => return false,
+        }
+
+        if matches!(
+            self.format,
+            OutputFormat::Table | OutputFormat::Wide | OutputFormat::CustomColumns(_)
+        ) {
+            print!("\x1b[2J\x1b[H");
+            println!(
+                "{}",
+                format!(
+                    "Every {}s, last updated {} (Ctrl-C to stop)",
+                    self.period.as_secs(),
+                    chrono::Local::now().format("%H:%M:%S")
+                )
+                .dimmed()
+            );
+        }
+        true
+    }
+}
+
+/// Format timestamp for display
+pub fn format_timestamp(ts: &str) -> String {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(ts) {
+        dt.format("%Y-%m-%d %H:%M:%S").to_string()
+    } else {
+        ts.to_string()
+    }
+}
+
+/// Parse a period such as `90s`, `30m`, `6h`, `7d`, `2w` or `1y`
+pub fn parse_period(period: &str) -> Result<chrono::Duration> {
+    let period = period.trim();
+    let split = period
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(period.len());
+    let (amount, unit) = period.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid period '{}', expected e.g. 30m, 6h or 7d", period))?;
+
+    Ok(match unit {
+        "s" => chrono::Duration::seconds(amount),
+        "m" => chrono::Duration::minutes(amount),
+        "h" | "" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        "w" => chrono::Duration::weeks(amount),
+        "y" => chrono::Duration::days(amount * 365),
+        _ => bail!(
+            "Invalid period unit '{}', expected s, m, h, d, w or y",
+            unit
+        ),
+    })
+}
+
 /// Format bytes as human-readable string
 pub fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -82,6 +398,37 @@ pub fn format_cpu(millicores: u32) -> String {
     }
 }
 
+/// Parse a Kubernetes CPU quantity such as `500m` or `1.5`
+pub fn parse_cpu_millicores(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    match quantity.strip_suffix('m') {
+        Some(millis) => millis.parse().ok(),
+        None => quantity.parse::<f64>().ok().map(|cores| cores * 1000.0),
+    }
+}
+
+/// Parse a Kubernetes memory quantity such as `128Mi` or `1G`
+pub fn parse_memory_bytes(quantity: &str) -> Option<f64> {
+    const SUFFIXES: [(&str, f64); 8] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+
+    let quantity = quantity.trim();
+    for (suffix, multiplier) in SUFFIXES {
+        if let Some(value) = quantity.strip_suffix(suffix) {
+            return value.parse::<f64>().ok().map(|v| v * multiplier);
+        }
+    }
+    quantity.parse().ok()
+}
+
 /// Format confidence as percentage
 pub fn format_confidence(confidence: f32) -> String {
     format!("{:.0}%", confidence * 100.0)
@@ -106,7 +453,7 @@ pub fn color_status(status: &str) -> String {
         "rolled_back" => status.red().to_string(),
         "healthy" | "running" => status.green().to_string(),
         "degraded" | "warning" => status.yellow().to_string(),
-        "unhealthy" | "error" | "failed" => status.red().to_string(),
+        "unhealthy" | "error" | "failed" | "critical" => status.red().to_string(),
         _ => status.to_string(),
     }
 }
@@ -122,3 +469,87 @@ pub fn color_confidence(confidence: f32) -> String {
         formatted.red().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(header: &str, path: &str) -> Column {
+        Column {
+            header: header.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_custom_columns() {
+        let format: OutputFormat = "custom-columns=NAME:.metadata.name,CPU:.cpu"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            format,
+            OutputFormat::CustomColumns(vec![
+                column("NAME", "metadata.name"),
+                column("CPU", "cpu"),
+            ])
+        );
+
+        // Trailing commas are ignored
+        assert_eq!(
+            parse_columns("NAME:.name,").unwrap(),
+            vec![column("NAME", "name")]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_columns() {
+        for spec in ["NAME", ":.name", "NAME:", "NAME:.name,CPU", ""] {
+            assert!(parse_columns(spec).is_err(), "accepted '{}'", spec);
+        }
+        let err = parse_columns("NAME:.name,CPU").unwrap_err();
+        assert!(err.contains("invalid column 'CPU'"), "{}", err);
+
+        assert!("custom-columns=".parse::<OutputFormat>().is_err());
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("cr\rlf"), "\"cr\rlf\"");
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let records = vec![
+            json!({"name": "web, frontend", "usage": {"cpu": 0.5}}),
+            json!({"name": "db \"primary\"", "usage": {"cpu": 1.5, "memory": 512}}),
+        ];
+        let csv = render_records(&records, &OutputFormat::Csv);
+        assert_eq!(
+            csv,
+            "name,usage.cpu,usage.memory\n\
+             \"web, frontend\",0.5,\n\
+             \"db \"\"primary\"\"\",1.5,512"
+        );
+    }
+
+    #[test]
+    fn test_render_missing_paths() {
+        let records = vec![json!({"name": "web", "usage": null})];
+        let format = OutputFormat::CustomColumns(vec![
+            column("NAME", "name"),
+            column("CPU", "usage.cpu"),
+            column("OWNER", "owner.name"),
+        ]);
+        let table = render_records(&records, &format);
+        let row = table.lines().nth(3).unwrap();
+        assert!(row.contains("web"), "{}", table);
+        assert_eq!(row.matches("<none>").count(), 2, "{}", table);
+    }
+}
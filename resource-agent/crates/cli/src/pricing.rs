@@ -0,0 +1,465 @@
+//! Pricing providers used to estimate costs with the organization's own rates
+//!
+//! A pricing file is either a static price sheet in the API's pricing config
+//! format, or a Helm-style `costEstimation` block as in the chart values and
+//! `docs/examples/pricing-*.yaml` (AWS/GCP/Azure on-demand or custom rates).
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::client::ResourceSpec;
+use crate::output::{parse_cpu_millicores, parse_memory_bytes};
+
+/// Average hours in a month, matching the API's cost calculator
+pub const HOURS_PER_MONTH: f64 = 730.0;
+
+/// Currency assumed when a pricing file doesn't set one
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+const BYTES_PER_GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Hourly price of one CPU core and one GiB of memory
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Rates {
+    #[serde(alias = "cpu_price_per_core_hour", deserialize_with = "price")]
+    pub cpu_per_core_hour: f64,
+    #[serde(alias = "memory_price_per_gb_hour", deserialize_with = "price")]
+    pub memory_per_gib_hour: f64,
+}
+
+/// Source of CPU and memory rates
+pub trait PricingProvider {
+    /// Short description shown alongside estimates, e.g. `aws on-demand`
+    fn name(&self) -> String;
+
+    /// ISO currency code of the rates
+    fn currency(&self) -> &str;
+
+    /// Rates applying to workloads in `namespace`
+    fn rates(&self, namespace: &str) -> Rates;
+
+    /// Monthly cost of the requests in `spec`, or `None` if they don't parse
+    fn monthly_cost(&self, namespace: &str, spec: &ResourceSpec) -> Option<f64> {
+        let rates = self.rates(namespace);
+        let cores = parse_cpu_millicores(&spec.cpu_request)? / 1000.0;
+        let gib = parse_memory_bytes(&spec.memory_request)? / BYTES_PER_GIB;
+        Some((cores * rates.cpu_per_core_hour + gib * rates.memory_per_gib_hour) * HOURS_PER_MONTH)
+    }
+}
+
+/// Cloud providers with built-in on-demand rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cloud {
+    Aws,
+    Gcp,
+    Azure,
+}
+
+impl Cloud {
+    /// General purpose on-demand rates, as used by the API by default
+    fn on_demand(self) -> Rates {
+        let (cpu_per_core_hour, memory_per_gib_hour) = match self {
+            Cloud::Aws => (0.0425, 0.00533),
+            Cloud::Gcp => (0.0335, 0.00449),
+            Cloud::Azure => (0.0420, 0.00525),
+        };
+        Rates {
+            cpu_per_core_hour,
+            memory_per_gib_hour,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Cloud::Aws => "aws",
+            Cloud::Gcp => "gcp",
+            Cloud::Azure => "azure",
+        }
+    }
+}
+
+/// Static price sheet with optional per-namespace overrides
+///
+/// Same format as the API's pricing config file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PriceSheet {
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(flatten)]
+    pub rates: Rates,
+    #[serde(default)]
+    pub custom_rates: HashMap<String, Rates>,
+}
+
+impl PricingProvider for PriceSheet {
+    fn name(&self) -> String {
+        match &self.region {
+            Some(region) => format!("price sheet ({})", region),
+            None => "price sheet".to_string(),
+        }
+    }
+
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    fn rates(&self, namespace: &str) -> Rates {
+        let custom = self.custom_rates.get(namespace);
+        Rates {
+            cpu_per_core_hour: custom
+                .map(|r| r.cpu_per_core_hour)
+                .filter(|rate| *rate > 0.0)
+                .unwrap_or(self.rates.cpu_per_core_hour),
+            memory_per_gib_hour: custom
+                .map(|r| r.memory_per_gib_hour)
+                .filter(|rate| *rate > 0.0)
+                .unwrap_or(self.rates.memory_per_gib_hour),
+        }
+    }
+}
+
+/// Cloud on-demand pricing, overridable from the pricing file
+#[derive(Debug, Clone)]
+pub struct CloudPricing {
+    pub cloud: Cloud,
+    pub currency: String,
+    pub rates: Rates,
+}
+
+impl CloudPricing {
+    /// Built-in general purpose on-demand rates for `cloud`
+    pub fn on_demand(cloud: Cloud) -> Self {
+        Self {
+            cloud,
+            currency: DEFAULT_CURRENCY.to_string(),
+            rates: cloud.on_demand(),
+        }
+    }
+}
+
+impl PricingProvider for CloudPricing {
+    fn name(&self) -> String {
+        format!("{} on-demand", self.cloud.as_str())
+    }
+
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    fn rates(&self, _namespace: &str) -> Rates {
+        self.rates
+    }
+}
+
+/// Flat custom per-core and per-GiB rates, e.g. internal chargeback rates
+#[derive(Debug, Clone)]
+pub struct CustomRates {
+    pub currency: String,
+    pub rates: Rates,
+}
+
+impl PricingProvider for CustomRates {
+    fn name(&self) -> String {
+        "custom rates".to_string()
+    }
+
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    fn rates(&self, _namespace: &str) -> Rates {
+        self.rates
+    }
+}
+
+/// `costEstimation` block of the Helm chart values
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CostEstimation {
+    provider: String,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    aws_pricing: Option<HelmRates>,
+    #[serde(default)]
+    gcp_pricing: Option<HelmRates>,
+    #[serde(default)]
+    azure_pricing: Option<HelmRates>,
+    #[serde(default)]
+    custom_pricing: Option<HelmRates>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HelmRates {
+    #[serde(deserialize_with = "price")]
+    cpu_per_core_hour: f64,
+    #[serde(rename = "memoryPerGBHour", deserialize_with = "price")]
+    memory_per_gb_hour: f64,
+}
+
+impl From<HelmRates> for Rates {
+    fn from(rates: HelmRates) -> Self {
+        Rates {
+            cpu_per_core_hour: rates.cpu_per_core_hour,
+            memory_per_gib_hour: rates.memory_per_gb_hour,
+        }
+    }
+}
+
+/// Load a pricing provider from a YAML or JSON pricing file
+///
+/// Only the first document of a multi-document YAML file is read.
+pub fn load(path: &Path) -> Result<Box<dyn PricingProvider>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pricing file {}", path.display()))?;
+    parse(&content).with_context(|| format!("Invalid pricing file {}", path.display()))
+}
+
+/// Load `path` if given, otherwise fall back to AWS on-demand rates
+pub fn load_or_default(path: Option<&Path>) -> Result<Box<dyn PricingProvider>> {
+    match path {
+        Some(path) => load(path),
+        None => Ok(Box::new(CloudPricing::on_demand(Cloud::Aws))),
+    }
+}
+
+fn parse(content: &str) -> Result<Box<dyn PricingProvider>> {
+    let document = serde_yaml::Deserializer::from_str(content)
+        .next()
+        .context("Pricing file is empty")?;
+    let mut value = serde_yaml::Value::deserialize(document)?;
+
+    let Some(estimation) = value.get_mut("costEstimation").map(std::mem::take) else {
+        let sheet: PriceSheet = serde_yaml::from_value(value)?;
+        return Ok(Box::new(sheet));
+    };
+
+    let estimation: CostEstimation = serde_yaml::from_value(estimation)?;
+    let currency = estimation
+        .currency
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string());
+    let cloud = match estimation.provider.to_lowercase().as_str() {
+        "aws" => Cloud::Aws,
+        "gcp" => Cloud::Gcp,
+        "azure" => Cloud::Azure,
+        "custom" | "on_premise" => {
+            let rates = estimation
+                .custom_pricing
+                .context("provider 'custom' requires customPricing rates")?;
+            return Ok(Box::new(CustomRates {
+                currency,
+                rates: rates.into(),
+            }));
+        }
+        other => bail!(
+            "unknown provider '{}', expected aws, gcp, azure or custom",
+            other
+        ),
+    };
+
+    let overrides = match cloud {
+        Cloud::Aws => estimation.aws_pricing,
+        Cloud::Gcp => estimation.gcp_pricing,
+        Cloud::Azure => estimation.azure_pricing,
+    };
+    Ok(Box::new(CloudPricing {
+        cloud,
+        currency,
+        rates: overrides.map_or_else(|| cloud.on_demand(), Rates::from),
+    }))
+}
+
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+/// Accept prices as numbers or quoted strings, as in the Helm values
+fn price<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Price {
+        Number(f64),
+        Text(String),
+    }
+
+    match Price::deserialize(deserializer)? {
+        Price::Number(price) => Ok(price),
+        Price::Text(text) => text.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn spec(cpu: &str, memory: &str) -> ResourceSpec {
+        ResourceSpec {
+            cpu_request: cpu.to_string(),
+            cpu_limit: cpu.to_string(),
+            memory_request: memory.to_string(),
+            memory_limit: memory.to_string(),
+        }
+    }
+
+    fn rates(cpu_per_core_hour: f64, memory_per_gib_hour: f64) -> Rates {
+        Rates {
+            cpu_per_core_hour,
+            memory_per_gib_hour,
+        }
+    }
+
+    fn pricing_file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_price_sheet() {
+        let pricing = parse(
+            r#"
+region: eu-west-1
+currency: EUR
+cpu_price_per_core_hour: 0.05
+memory_price_per_gb_hour: "0.01"
+custom_rates:
+  batch:
+    cpu_price_per_core_hour: 0.02
+    memory_price_per_gb_hour: 0
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(pricing.name(), "price sheet (eu-west-1)");
+        assert_eq!(pricing.currency(), "EUR");
+        assert_eq!(pricing.rates("web"), rates(0.05, 0.01));
+        // Unset custom rates fall back to the sheet's
+        assert_eq!(pricing.rates("batch"), rates(0.02, 0.01));
+
+        let cost = pricing.monthly_cost("web", &spec("2", "1Gi")).unwrap();
+        assert!((cost - (2.0 * 0.05 + 0.01) * HOURS_PER_MONTH).abs() < 1e-9);
+        assert_eq!(pricing.monthly_cost("web", &spec("lots", "1Gi")), None);
+    }
+
+    #[test]
+    fn test_price_sheet_json_defaults() {
+        let pricing =
+            parse(r#"{"cpu_per_core_hour": 0.04, "memory_per_gib_hour": 0.005}"#).unwrap();
+        assert_eq!(pricing.name(), "price sheet");
+        assert_eq!(pricing.currency(), DEFAULT_CURRENCY);
+        assert_eq!(pricing.rates("any"), rates(0.04, 0.005));
+    }
+
+    #[test]
+    fn test_cost_estimation() {
+        let pricing = parse("costEstimation:\n  provider: GCP\n").unwrap();
+        assert_eq!(pricing.name(), "gcp on-demand");
+        assert_eq!(pricing.rates("any"), Cloud::Gcp.on_demand());
+
+        let pricing = parse(
+            r#"
+costEstimation:
+  provider: azure
+  currency: GBP
+  azurePricing:
+    cpuPerCoreHour: "0.03"
+    memoryPerGBHour: "0.004"
+"#,
+        )
+        .unwrap();
+        assert_eq!(pricing.name(), "azure on-demand");
+        assert_eq!(pricing.currency(), "GBP");
+        assert_eq!(pricing.rates("any"), rates(0.03, 0.004));
+
+        let pricing = parse(
+            r#"
+costEstimation:
+  provider: on_premise
+  customPricing:
+    cpuPerCoreHour: 0.009
+    memoryPerGBHour: 0.0015
+"#,
+        )
+        .unwrap();
+        assert_eq!(pricing.name(), "custom rates");
+        assert_eq!(pricing.rates("any"), rates(0.009, 0.0015));
+    }
+
+    #[test]
+    fn test_example_pricing_files() {
+        let examples = concat!(env!("CARGO_MANIFEST_DIR"), "/../../../docs/examples");
+        for (file, name) in [
+            ("pricing-aws.yaml", "aws on-demand"),
+            ("pricing-gcp.yaml", "gcp on-demand"),
+            ("pricing-azure.yaml", "azure on-demand"),
+            ("pricing-onprem.yaml", "custom rates"),
+        ] {
+            let pricing = load(&Path::new(examples).join(file)).unwrap();
+            assert_eq!(pricing.name(), name, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_unknown_provider() {
+        let err = parse("costEstimation:\n  provider: oracle\n")
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("unknown provider 'oracle'"),
+            "{}",
+            err
+        );
+
+        let err = parse("costEstimation:\n  provider: custom\n")
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("requires customPricing"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        for content in [
+            "",
+            "cpu_per_core_hour: 0.04\n",
+            "cpu_per_core_hour: cheap\nmemory_per_gib_hour: 0.005\n",
+            "costEstimation:\n  currency: USD\n",
+            "[not, a, sheet]",
+            "{ unclosed",
+        ] {
+            let file = pricing_file(content);
+            let err = load(file.path()).err().unwrap();
+            assert!(
+                err.to_string().starts_with("Invalid pricing file"),
+                "{:?}: {}",
+                content,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pricing.yaml");
+        let err = load_or_default(Some(&path)).err().unwrap();
+        assert!(
+            err.to_string().starts_with("Failed to read pricing file"),
+            "{}",
+            err
+        );
+
+        let pricing = load_or_default(None).unwrap();
+        assert_eq!(pricing.name(), "aws on-demand");
+        assert_eq!(pricing.currency(), DEFAULT_CURRENCY);
+    }
+}
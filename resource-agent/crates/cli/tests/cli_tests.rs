@@ -20,6 +20,7 @@ fn test_cli_help() {
     assert!(stdout.contains("get"), "Should show get command");
     assert!(stdout.contains("apply"), "Should show apply command");
     assert!(stdout.contains("approve"), "Should show approve command");
+    assert!(stdout.contains("rollback"), "Should show rollback command");
     assert!(stdout.contains("costs"), "Should show costs command");
     assert!(stdout.contains("debug"), "Should show debug command");
 }
@@ -64,10 +65,12 @@ fn test_get_recommendations_help() {
         stdout.contains("--namespace"),
         "Should show namespace option"
     );
+    assert!(stdout.contains("--owner"), "Should show owner option");
     assert!(
         stdout.contains("--deployment"),
-        "Should show deployment option"
+        "Should show deployment alias"
     );
+    assert!(stdout.contains("--watch"), "Should show watch option");
 }
 
 /// Test get models subcommand help
@@ -87,6 +90,56 @@ fn test_get_models_help() {
     );
 }
 
+/// Test get anomalies command help
+#[test]
+fn test_get_anomalies_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "get", "anomalies", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Get anomalies help should succeed");
+    assert!(stdout.contains("--severity"), "Should show severity option");
+    assert!(stdout.contains("--since"), "Should show since option");
+    assert!(stdout.contains("--watch"), "Should show watch option");
+}
+
+/// Test get history subcommand help
+#[test]
+fn test_get_history_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "get", "history", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Get history help should succeed");
+    assert!(
+        stdout.contains("<TARGET>"),
+        "Should take a deployment target"
+    );
+}
+
+/// Test get history rejects targets without a namespace
+#[test]
+fn test_get_history_invalid_target() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "get", "history", "api-server"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "Should fail without a namespace");
+    assert!(
+        stderr.contains("Expected <namespace>/<deployment>"),
+        "Should explain the target format"
+    );
+}
+
 /// Test apply command help
 #[test]
 fn test_apply_help() {
@@ -99,6 +152,13 @@ fn test_apply_help() {
 
     assert!(output.status.success(), "Apply help should succeed");
     assert!(stdout.contains("--dry-run"), "Should show dry-run option");
+    assert!(stdout.contains("--all"), "Should show all option");
+    assert!(stdout.contains("--direct"), "Should show direct option");
+    assert!(stdout.contains("--stagger"), "Should show stagger option");
+    assert!(
+        stdout.contains("--concurrency"),
+        "Should show concurrency option"
+    );
 }
 
 /// Test approve command help
@@ -114,6 +174,169 @@ fn test_approve_help() {
     assert!(output.status.success(), "Approve help should succeed");
     assert!(stdout.contains("--approver"), "Should show approver option");
     assert!(stdout.contains("--reason"), "Should show reason option");
+    assert!(
+        stdout.contains("--min-savings"),
+        "Should show min-savings option"
+    );
+}
+
+/// Test rollback command help
+#[test]
+fn test_rollback_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "rollback", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Rollback help should succeed");
+    assert!(
+        stdout.contains("--to-revision"),
+        "Should show to-revision option"
+    );
+    assert!(stdout.contains("--yes"), "Should show yes option");
+}
+
+/// Test diff command help
+#[test]
+fn test_diff_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "diff", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Diff help should succeed");
+    assert!(
+        stdout.contains("<namespace>/<deployment>"),
+        "Should describe target format"
+    );
+}
+
+/// Test policy set subcommand help
+#[test]
+fn test_policy_set_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "policy", "set", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Policy set help should succeed");
+    assert!(stdout.contains("--min-cpu"), "Should show min-cpu option");
+    assert!(
+        stdout.contains("--max-memory"),
+        "Should show max-memory option"
+    );
+    assert!(
+        stdout.contains("--exclude-namespace"),
+        "Should show exclude-namespace option"
+    );
+    assert!(
+        stdout.contains("--require-approval"),
+        "Should show require-approval option"
+    );
+}
+
+/// Test policy set rejects invalid quantities
+#[test]
+fn test_policy_set_invalid_quantity() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "-p",
+            "crp-cli",
+            "--",
+            "policy",
+            "set",
+            "bounds",
+            "--min-memory",
+            "lots",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        !output.status.success(),
+        "Should fail with invalid quantity"
+    );
+    assert!(
+        stderr.contains("invalid memory quantity"),
+        "Should explain the quantity format"
+    );
+}
+
+/// Test bash completion generation
+#[test]
+fn test_completion_bash() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "completion", "bash"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Completion should succeed");
+    assert!(
+        stdout.contains("_crp()"),
+        "Should define completion function"
+    );
+    assert!(
+        stdout.contains("crp complete-namespaces"),
+        "Should complete namespaces dynamically"
+    );
+}
+
+/// Test man page generation
+#[test]
+fn test_docs_man() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "docs", "man"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "Man page generation should succeed"
+    );
+    assert!(stdout.contains(".TH crp"), "Should render a man page");
+}
+
+/// Test export manifests subcommand help
+#[test]
+fn test_export_manifests_help() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "-p",
+            "crp-cli",
+            "--",
+            "export",
+            "manifests",
+            "--help",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "Export manifests help should succeed"
+    );
+    assert!(stdout.contains("kustomize"), "Should show kustomize format");
+    assert!(
+        stdout.contains("helm-values"),
+        "Should show helm-values format"
+    );
+    assert!(stdout.contains("jsonpatch"), "Should show jsonpatch format");
 }
 
 /// Test costs show subcommand help
@@ -131,6 +354,10 @@ fn test_costs_show_help() {
         stdout.contains("--namespace"),
         "Should show namespace option"
     );
+    assert!(
+        stdout.contains("--pricing-file"),
+        "Should show pricing-file option"
+    );
 }
 
 /// Test costs savings subcommand help
@@ -145,6 +372,81 @@ fn test_costs_savings_help() {
 
     assert!(output.status.success(), "Costs savings help should succeed");
     assert!(stdout.contains("--since"), "Should show since option");
+    assert!(
+        stdout.contains("--pricing-file"),
+        "Should show pricing-file option"
+    );
+}
+
+/// Test costs forecast subcommand help
+#[test]
+fn test_costs_forecast_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "costs", "forecast", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "Costs forecast help should succeed"
+    );
+    assert!(
+        stdout.contains("--namespace"),
+        "Should show namespace option"
+    );
+    assert!(
+        stdout.contains("--pricing-file"),
+        "Should show pricing-file option"
+    );
+}
+
+/// Test costs what-if subcommand help
+#[test]
+fn test_costs_what_if_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "costs", "what-if", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Costs what-if help should succeed");
+    assert!(
+        stdout.contains("--cpu-buffer"),
+        "Should show cpu-buffer option"
+    );
+    assert!(
+        stdout.contains("--memory-buffer"),
+        "Should show memory-buffer option"
+    );
+}
+
+/// Test costs what-if rejects a malformed buffer
+#[test]
+fn test_costs_what_if_invalid_buffer() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "-p",
+            "crp-cli",
+            "--",
+            "costs",
+            "what-if",
+            "--cpu-buffer",
+            "lots",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "Malformed buffer should fail");
+    assert!(
+        stderr.contains("invalid percentage"),
+        "Should explain the expected format"
+    );
 }
 
 /// Test debug predictions subcommand help
@@ -187,6 +489,11 @@ fn test_debug_agent_help() {
 
     assert!(output.status.success(), "Debug agent help should succeed");
     assert!(stdout.contains("node"), "Should show node argument");
+    assert!(
+        stdout.contains("--agent-url"),
+        "Should show agent-url option"
+    );
+    assert!(stdout.contains("--watch"), "Should show watch option");
 }
 
 /// Test debug export subcommand help
@@ -208,6 +515,29 @@ fn test_debug_export_help() {
     );
 }
 
+/// Test debug bundle subcommand help
+#[test]
+fn test_debug_bundle_help() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "debug", "bundle", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Debug bundle help should succeed");
+    assert!(stdout.contains("--node"), "Should show node option");
+    assert!(stdout.contains("--output"), "Should show output option");
+    assert!(
+        stdout.contains("--log-lines"),
+        "Should show log lines option"
+    );
+    assert!(
+        stdout.contains("--redact-namespaces"),
+        "Should show redaction option"
+    );
+}
+
 /// Test format option
 #[test]
 fn test_format_option() {
@@ -221,6 +551,31 @@ fn test_format_option() {
     assert!(stdout.contains("--format"), "Should show format option");
     assert!(stdout.contains("table"), "Should show table format");
     assert!(stdout.contains("json"), "Should show json format");
+    assert!(stdout.contains("yaml"), "Should show yaml format");
+    assert!(stdout.contains("csv"), "Should show csv format");
+    assert!(
+        stdout.contains("custom-columns"),
+        "Should show custom-columns format"
+    );
+}
+
+/// Test invalid format error handling
+#[test]
+fn test_invalid_format() {
+    let output = Command::new("cargo")
+        .args([
+            "run", "-p", "crp-cli", "--", "--format", "xml", "get", "models",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "Invalid format should fail");
+    assert!(
+        stderr.contains("unknown format"),
+        "Should explain the error"
+    );
 }
 
 /// Test api-url option
@@ -237,6 +592,197 @@ fn test_api_url_option() {
     assert!(stdout.contains("CRP_API_URL"), "Should show env var");
 }
 
+/// Test request timeout and retry options
+#[test]
+fn test_timeout_and_retry_options() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("--timeout"), "Should show timeout option");
+    assert!(stdout.contains("--retries"), "Should show retries option");
+}
+
+/// Test an unreachable API fails after the configured attempts
+#[test]
+fn test_unreachable_api_gives_up() {
+    let home = tempfile::tempdir().expect("Failed to create temp dir");
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "-p",
+            "crp-cli",
+            "--",
+            "--api-url",
+            "http://127.0.0.1:9",
+            "--retries",
+            "1",
+            "--timeout",
+            "2",
+            "get",
+            "recommendations",
+        ])
+        .env("HOME", home.path())
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(
+        output.status.code(),
+        Some(5),
+        "Should exit with connection error"
+    );
+    assert!(
+        stderr.contains("after 2 attempt(s)"),
+        "Should report the retried attempts"
+    );
+}
+
+/// Test failures are reported as a JSON envelope with --format json
+#[test]
+fn test_json_error_envelope() {
+    let home = tempfile::tempdir().expect("Failed to create temp dir");
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "-p",
+            "crp-cli",
+            "--",
+            "--format",
+            "json",
+            "config",
+            "use-context",
+            "missing",
+        ])
+        .env("HOME", home.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3), "Should exit with not found");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let start = stderr.find('{').expect("Should print a JSON envelope");
+    let envelope: serde_json::Value =
+        serde_json::from_str(&stderr[start..]).expect("Envelope should be valid JSON");
+    assert_eq!(envelope["error"]["kind"], "not_found");
+    assert_eq!(envelope["error"]["exit_code"], 3);
+    assert!(envelope["error"]["message"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("missing"));
+}
+
+/// Test usage errors don't use the partial failure exit code
+#[test]
+fn test_usage_error_exit_code() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "--no-such-flag"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(1), "Usage errors should exit 1");
+}
+
+/// Test context selection option
+#[test]
+fn test_context_option() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("--context"), "Should show context option");
+    assert!(stdout.contains("cluster"), "Should show cluster alias");
+    assert!(stdout.contains("config"), "Should show config command");
+}
+
+/// Test authentication options and login commands appear in help
+#[test]
+fn test_auth_options() {
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains("Authentication"),
+        "Should group auth options"
+    );
+    assert!(stdout.contains("--token"), "Should show token option");
+    assert!(stdout.contains("--oidc-issuer"), "Should show OIDC option");
+    assert!(stdout.contains("--client-cert"), "Should show mTLS option");
+    assert!(stdout.contains("login"), "Should show login command");
+    assert!(stdout.contains("logout"), "Should show logout command");
+}
+
+/// Test login fails clearly without an OIDC provider
+#[test]
+fn test_login_without_oidc() {
+    let home = tempfile::tempdir().expect("Failed to create temp dir");
+    let output = Command::new("cargo")
+        .args(["run", "-p", "crp-cli", "--", "login"])
+        .env("HOME", home.path())
+        .env_remove("CRP_OIDC_ISSUER")
+        .env_remove("CRP_OIDC_CLIENT_ID")
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "Login should fail");
+    assert!(
+        stderr.contains("No OIDC provider configured"),
+        "Should explain missing OIDC settings"
+    );
+}
+
+/// Test creating, listing and switching contexts in an isolated home
+#[test]
+fn test_config_contexts() {
+    let home = tempfile::tempdir().expect("Failed to create temp dir");
+    let crp = |args: &[&str]| {
+        Command::new("cargo")
+            .args(["run", "-p", "crp-cli", "--"])
+            .args(args)
+            .env("HOME", home.path())
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    for (name, url) in [
+        ("prod", "https://crp.prod.example.com"),
+        ("staging", "https://crp.staging.example.com"),
+    ] {
+        let output = crp(&["config", "set-context", name, "--api-url", url]);
+        assert!(output.status.success(), "set-context should succeed");
+    }
+
+    let output = crp(&["config", "use-context", "staging"]);
+    assert!(output.status.success(), "use-context should succeed");
+
+    let output = crp(&["--format", "json", "config", "get-contexts"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "get-contexts should succeed");
+    assert!(
+        stdout.contains("crp.prod.example.com"),
+        "Should list prod context"
+    );
+    assert!(
+        stdout.contains("\"name\": \"staging\",\n    \"current\": true"),
+        "Should mark staging as current"
+    );
+
+    let output = crp(&["config", "use-context", "missing"]);
+    assert!(!output.status.success(), "Unknown context should fail");
+}
+
 /// Test invalid command error handling
 #[test]
 fn test_invalid_command() {